@@ -171,3 +171,13 @@ impl<T> ShallowEq for Elem<T> {
         !Self::ptr_eq(self, other)
     }
 }
+
+impl<T> ShallowEq for Option<Elem<T>> {
+    fn shallow_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(x), Some(y)) => Elem::ptr_eq(x, y),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
@@ -5,11 +5,15 @@ use std::{
     path::PathBuf,
 };
 
+use crate::connector::irc::IrcConfig;
+
 /// A lightweight instant messaging client.
 #[derive(Default)]
 pub struct Args {
     /// the path to a custom profile directory
     pub profile: Option<PathBuf>,
+    /// IRC servers to connect to
+    pub irc: Vec<IrcConfig>,
 }
 
 impl Args {
@@ -47,6 +51,7 @@ static HANDLER_TABLE: &[(&str, &(dyn ArgHandler<Args> + Send + Sync))] = &[
     ("-h", &(handle_help as fn(&mut Args))),
     ("--help", &(handle_help as fn(&mut Args))),
     ("--profile", &(handle_profile as fn(&mut Args, OsString))),
+    ("--irc", &(handle_irc as fn(&mut Args, OsString))),
 ];
 
 fn display_help_and_exit() -> ! {
@@ -62,7 +67,9 @@ FLAGS:
     -h, --help       display help information
 
 OPTIONS:
-    --profile <PROFILE>    the path to a custom profile directory"
+    --profile <PROFILE>    the path to a custom profile directory
+    --irc <SPEC>           connect to an IRC server. <SPEC> is in the form
+                           of NICK@HOST[:PORT][/CHANNEL[,CHANNEL...]]"
     );
     std::process::exit(0);
 }
@@ -95,3 +102,52 @@ fn handle_help<T>(_: &mut T) {
 fn handle_profile(args: &mut Args, value: OsString) {
     args.profile = Some(value.into());
 }
+
+fn handle_irc(args: &mut Args, value: OsString) {
+    let config = value.to_str().and_then(parse_irc_spec);
+    if let Some(config) = config {
+        args.irc.push(config);
+    } else {
+        eprintln!(
+            "error: Invalid value for '--irc': {:?} (expected \
+             NICK@HOST[:PORT][/CHANNEL[,CHANNEL...]])",
+            value
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Parse `NICK@HOST[:PORT][/CHANNEL[,CHANNEL...]]`.
+fn parse_irc_spec(spec: &str) -> Option<IrcConfig> {
+    let mut parts = spec.splitn(2, '/');
+    let server = parts.next().unwrap();
+    let channels = parts.next().unwrap_or("");
+
+    let mut parts = server.splitn(2, '@');
+    let nick = parts.next().filter(|s| !s.is_empty())?;
+    let host_port = parts.next()?;
+
+    let mut parts = host_port.splitn(2, ':');
+    let host = parts.next().filter(|s| !s.is_empty())?;
+
+    let mut config = IrcConfig::new(host, nick);
+    if let Some(port) = parts.next() {
+        config.port = port.parse().ok()?;
+    }
+
+    config.channels = channels
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            // Allow omitting the channel prefix (`#` is awkward to type in
+            // some shells)
+            if s.starts_with(|c: char| c.is_alphanumeric()) {
+                format!("#{}", s)
+            } else {
+                s.to_owned()
+            }
+        })
+        .collect();
+
+    Some(config)
+}
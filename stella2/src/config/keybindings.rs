@@ -0,0 +1,136 @@
+//! User-defined key bindings
+//!
+//! The key bindings are loaded from `keybindings.json` in the profile's data
+//! directory. The file contains a JSON object mapping action names to arrays
+//! of key combinations:
+//!
+//! ```json
+//! {
+//!     "redo": ["Ctrl+Shift+Z"],
+//!     "toggle_sidebar": ["Ctrl+B"],
+//!     "quit": []
+//! }
+//! ```
+//!
+//! An empty array removes the default key bindings of the action. Actions not
+//! mentioned by the file retain their default key bindings.
+use miniserde::json;
+use std::{collections::BTreeMap, path::PathBuf};
+use tcw3::{
+    pal::accel::{KeyBindings, KeyPattern},
+    uicore::ActionId,
+};
+
+use super::profile::Profile;
+
+/// The file path to load the user-defined key bindings from.
+fn keybindings_path(profile: &Profile) -> PathBuf {
+    profile.data_dir().join("keybindings.json")
+}
+
+/// Load the user-defined key bindings from a given profile. `actions` lists
+/// the names of the actions which can be bound to key combinations.
+///
+/// Errors are logged and do not prevent the valid portion of the file from
+/// being used.
+pub fn load_user_key_bindings(profile: &Profile, actions: &[(&str, ActionId)]) -> KeyBindings {
+    let path = keybindings_path(profile);
+
+    if !path.is_file() {
+        log::debug!("The key bindings file was not found at {:?}.", path);
+        return KeyBindings::new();
+    }
+
+    log::info!("Loading key bindings from {:?}.", path);
+
+    match std::fs::read_to_string(&path) {
+        Ok(json) => parse_key_bindings(&json, actions),
+        Err(e) => {
+            log::error!("Could not read the key bindings: {}", e);
+            KeyBindings::new()
+        }
+    }
+}
+
+fn parse_key_bindings(json: &str, actions: &[(&str, ActionId)]) -> KeyBindings {
+    let mut bindings = KeyBindings::new();
+
+    let map: BTreeMap<String, Vec<String>> = match json::from_str(json) {
+        Ok(map) => map,
+        Err(_) => {
+            log::error!("Could not parse the key bindings: deserialization failed");
+            return bindings;
+        }
+    };
+
+    for (name, patterns) in map.iter() {
+        let action = if let Some(&(_, action)) = actions.iter().find(|(n, _)| n == name) {
+            action
+        } else {
+            log::warn!("Ignoring key bindings for an unknown action {:?}", name);
+            continue;
+        };
+
+        bindings.unbind(action);
+
+        for pattern in patterns.iter() {
+            match pattern.parse::<KeyPattern>() {
+                Ok(pattern) => bindings.bind(action, pattern),
+                Err(e) => {
+                    log::warn!(
+                        "Ignoring an invalid key combination {:?} for {:?}: {}",
+                        pattern,
+                        name,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    bindings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTIONS: &[(&str, ActionId)] = &[("undo", 1), ("redo", 2), ("quit", 3)];
+
+    fn pat(s: &str) -> KeyPattern {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let bindings = parse_key_bindings(
+            r#"{
+                "redo": ["Ctrl+Shift+Z", "Ctrl+Hoge"],
+                "quit": [],
+                "reticulate_splines": ["Ctrl+R"]
+            }"#,
+            ACTIONS,
+        );
+
+        assert_eq!(
+            bindings.patterns_of(2).collect::<Vec<_>>(),
+            [pat("Ctrl+Shift+Z")]
+        );
+        assert_eq!(bindings.patterns_of(3).count(), 0);
+        assert_eq!(bindings.bindings().len(), 1);
+
+        // `quit` is unbound when overlaid on the defaults, but `undo` isn't
+        let mut defaults = KeyBindings::new();
+        defaults.bind(1, pat("Ctrl+Z"));
+        defaults.bind(3, pat("Ctrl+Q"));
+        let combined = defaults.overlay(&bindings);
+        assert_eq!(combined.patterns_of(1).collect::<Vec<_>>(), [pat("Ctrl+Z")]);
+        assert_eq!(combined.patterns_of(3).count(), 0);
+    }
+
+    #[test]
+    fn parse_malformed() {
+        let bindings = parse_key_bindings("[1, 2, 3]", ACTIONS);
+        assert_eq!(bindings, KeyBindings::new());
+    }
+}
@@ -0,0 +1,307 @@
+//! User preferences
+//!
+//! The preferences are stored in `settings.json` in the profile's data
+//! directory:
+//!
+//! ```json
+//! {
+//!     "version": 1,
+//!     "check_updates": false,
+//!     "time_format": "24h",
+//!     "font_size": 12
+//! }
+//! ```
+//!
+//! `version` identifies the schema version. Files written by an older version
+//! of the application are migrated to the current schema when loaded. Invalid
+//! or unknown values are replaced with their default values. Files written by
+//! a newer version are never overwritten because they may contain values that
+//! this version doesn't know about.
+use miniserde::json::{self, Number, Object, Value};
+use std::{
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
+
+use super::{profile::Profile, viewpersistence::write_atomically};
+use crate::model::{Settings, TimeFormat};
+
+/// The current schema version.
+const CURRENT_VERSION: u64 = 1;
+
+/// The functions to migrate a settings object to newer schema versions.
+/// The `i`-th element migrates an object from version `i` to `i + 1`.
+const MIGRATIONS: &[fn(&mut Object)] = &[migrate_v0_to_v1];
+
+/// Version 0 refers to a file without `version`. It has the same fields as
+/// version 1.
+fn migrate_v0_to_v1(_: &mut Object) {}
+
+/// The file path to store the user preferences.
+fn settings_path(profile: &Profile) -> PathBuf {
+    profile.data_dir().join("settings.json")
+}
+
+/// The temporary file path used during saving the user preferences.
+fn settings_tmp_path(profile: &Profile) -> PathBuf {
+    profile.data_dir().join(".settings.json.tmp")
+}
+
+/// Load the user preferences from a given profile.
+///
+/// Errors are logged and do not prevent the valid portion of the file from
+/// being used.
+pub fn load_settings(profile: &Profile) -> Settings {
+    let path = settings_path(profile);
+
+    if !path.is_file() {
+        log::info!("The settings file was not found at {:?}.", path);
+        return Settings::default();
+    }
+
+    log::info!("Loading settings from {:?}.", path);
+
+    match std::fs::read_to_string(&path) {
+        Ok(json) => parse_settings(&json),
+        Err(e) => {
+            log::error!("Could not read the settings: {}", e);
+            Settings::default()
+        }
+    }
+}
+
+/// Write the user preferences to a given profile.
+///
+/// This blocks the current thread. The file is small and only written when
+/// the user changes the preferences, so this is fine to call from the main
+/// thread.
+///
+/// Fails without writing anything if the existing file has a newer schema
+/// version.
+pub fn save_settings(profile: &Profile, settings: &Settings) -> Result<(), Error> {
+    let path = settings_path(profile);
+    let tmp_path = settings_tmp_path(profile);
+
+    match std::fs::read_to_string(&path) {
+        Ok(json) => check_overwritable(&json)?,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    log::info!(
+        "Writing the settings to {:?} using a temporary file at {:?}",
+        path,
+        tmp_path
+    );
+
+    write_atomically(&path, &tmp_path, &settings_to_json(settings))
+}
+
+fn parse_settings(json: &str) -> Settings {
+    let mut obj = match json::from_str(json) {
+        Ok(Value::Object(obj)) => obj,
+        Ok(_) | Err(_) => {
+            log::error!("Could not parse the settings: deserialization failed");
+            return Settings::default();
+        }
+    };
+
+    let version = if let Some(version) = schema_version(&obj) {
+        version
+    } else {
+        log::error!("Could not parse the settings: invalid schema version");
+        return Settings::default();
+    };
+
+    if version > CURRENT_VERSION {
+        log::warn!(
+            "The settings file has a newer schema version ({}) than supported ({}). \
+             Unrecognized values will be ignored.",
+            version,
+            CURRENT_VERSION
+        );
+    } else {
+        for migrate in MIGRATIONS[version as usize..].iter() {
+            migrate(&mut obj);
+        }
+    }
+
+    settings_from_object(&obj)
+}
+
+/// Get the schema version of a settings object. Returns `None` if it's
+/// invalid.
+fn schema_version(obj: &Object) -> Option<u64> {
+    match obj.get("version") {
+        None => Some(0),
+        Some(Value::Number(Number::U64(x))) => Some(*x),
+        Some(_) => None,
+    }
+}
+
+/// Check if the existing settings file `json` may be replaced with a file of
+/// the current schema version. Malformed files are replaced because they are
+/// ignored by `parse_settings` anyway.
+fn check_overwritable(json: &str) -> Result<(), Error> {
+    let version = match json::from_str(json) {
+        Ok(Value::Object(obj)) => schema_version(&obj),
+        Ok(_) | Err(_) => None,
+    };
+
+    match version {
+        Some(version) if version > CURRENT_VERSION => Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "the settings file was written by a newer version of the application \
+                 (schema version {})",
+                version
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Construct `Settings` from a settings object of the current schema
+/// version.
+fn settings_from_object(obj: &Object) -> Settings {
+    let mut settings = Settings::default();
+
+    let warn_invalid = |name: &str| {
+        log::warn!("Ignoring an invalid value for {:?} in the settings", name);
+    };
+
+    match obj.get("check_updates") {
+        Some(Value::Bool(x)) => settings.check_updates = *x,
+        Some(_) => warn_invalid("check_updates"),
+        None => {}
+    }
+
+    match obj.get("time_format") {
+        Some(Value::String(x)) if x == "12h" => settings.time_format = TimeFormat::H12,
+        Some(Value::String(x)) if x == "24h" => settings.time_format = TimeFormat::H24,
+        Some(_) => warn_invalid("time_format"),
+        None => {}
+    }
+
+    match obj.get("font_size") {
+        Some(Value::Number(Number::U64(x)))
+            if *x <= u32::max_value() as u64 && Settings::is_valid_font_size(*x as u32) =>
+        {
+            settings.font_size = *x as u32;
+        }
+        Some(_) => warn_invalid("font_size"),
+        None => {}
+    }
+
+    settings
+}
+
+fn settings_to_json(settings: &Settings) -> String {
+    let mut obj = Object::new();
+
+    obj.insert(
+        "version".to_owned(),
+        Value::Number(Number::U64(CURRENT_VERSION)),
+    );
+    obj.insert(
+        "check_updates".to_owned(),
+        Value::Bool(settings.check_updates),
+    );
+    obj.insert(
+        "time_format".to_owned(),
+        Value::String(
+            match settings.time_format {
+                TimeFormat::H12 => "12h",
+                TimeFormat::H24 => "24h",
+            }
+            .to_owned(),
+        ),
+    );
+    obj.insert(
+        "font_size".to_owned(),
+        Value::Number(Number::U64(settings.font_size as u64)),
+    );
+
+    json::to_string(&Value::Object(obj))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_cover_all_versions() {
+        assert_eq!(MIGRATIONS.len() as u64, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let settings = Settings {
+            check_updates: true,
+            time_format: TimeFormat::H12,
+            font_size: 15,
+        };
+        assert_eq!(parse_settings(&settings_to_json(&settings)), settings);
+    }
+
+    #[test]
+    fn migrate_unversioned() {
+        let settings = parse_settings(r#"{ "check_updates": true, "font_size": 10 }"#);
+        assert_eq!(
+            settings,
+            Settings {
+                check_updates: true,
+                font_size: 10,
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn newer_version() {
+        let settings = parse_settings(
+            r#"{ "version": 9999, "time_format": "12h", "reticulate_splines": true }"#,
+        );
+        assert_eq!(
+            settings,
+            Settings {
+                time_format: TimeFormat::H12,
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let settings = parse_settings(
+            r#"{
+                "version": 1,
+                "check_updates": "yes",
+                "time_format": "36h",
+                "font_size": 42
+            }"#,
+        );
+        assert_eq!(settings, Settings::default());
+
+        let settings = parse_settings(r#"{ "version": 1, "font_size": -1 }"#);
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn overwrite() {
+        assert!(check_overwritable(r#"{ "version": 1, "font_size": 10 }"#).is_ok());
+        assert!(check_overwritable(r#"{ "font_size": 10 }"#).is_ok());
+        assert!(check_overwritable("{").is_ok());
+        assert!(check_overwritable(r#"{ "version": 2, "font_size": 10 }"#).is_err());
+    }
+
+    #[test]
+    fn parse_malformed() {
+        assert_eq!(parse_settings("[1, 2, 3]"), Settings::default());
+        assert_eq!(parse_settings("{"), Settings::default());
+        assert_eq!(
+            parse_settings(r#"{ "version": "1", "font_size": 10 }"#),
+            Settings::default()
+        );
+    }
+}
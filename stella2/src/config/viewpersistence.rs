@@ -57,7 +57,13 @@ fn state_tmp_path(profile: &Profile) -> PathBuf {
 
 /// Restore the application state from a given profile. `state` will be
 /// updated with the restored state.
-pub fn restore_state(profile: &Profile, app_state: Elem<model::AppState>) -> Elem<model::AppState> {
+///
+/// If the persisted state exists but couldn't be read, `state` is returned
+/// unmodified along with the error, which should be reported to the user.
+pub fn restore_state(
+    profile: &Profile,
+    app_state: Elem<model::AppState>,
+) -> (Elem<model::AppState>, Option<std::io::Error>) {
     let state_path = state_path(profile);
 
     if state_path.is_file() {
@@ -66,11 +72,11 @@ pub fn restore_state(profile: &Profile, app_state: Elem<model::AppState>) -> Ele
         // Load `PersistedState` from the file
         match load_persisted_state(&state_path) {
             Ok(st) => {
-                return st.merge_into_app(app_state);
+                return (st.merge_into_app(app_state), None);
             }
             Err(e) => {
-                // TODO: Report the error to the user
                 log::error!("Could not read the persisted state: {}", e);
+                return (app_state, Some(e));
             }
         }
     } else {
@@ -80,7 +86,7 @@ pub fn restore_state(profile: &Profile, app_state: Elem<model::AppState>) -> Ele
         );
     }
 
-    app_state
+    (app_state, None)
 }
 
 /// Load `PersistedState` from the specified path.
@@ -104,7 +110,11 @@ fn load_persisted_state(path: &Path) -> Result<PersistedState, std::io::Error> {
 }
 
 /// Write a file atomically.
-fn write_atomically(path: &Path, tmp_path: &Path, contents: &str) -> Result<(), std::io::Error> {
+pub(super) fn write_atomically(
+    path: &Path,
+    tmp_path: &Path,
+    contents: &str,
+) -> Result<(), std::io::Error> {
     // Use a temporary file `tmp_path` to atomically update `patH`.
     std::fs::write(tmp_path, contents)?;
 
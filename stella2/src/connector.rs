@@ -0,0 +1,139 @@
+//! Protocol connectors
+//!
+//! A connector maintains a connection to a chat service. Its network I/O runs
+//! on a background thread, and the events it produces are delivered through
+//! an [`EventSink`]. [`main_thread_sink`] constructs an `EventSink` that
+//! translates the events to [`AppAction`]s and dispatches them on the main
+//! thread.
+use harmony::Elem;
+use log::{info, warn};
+use std::{sync::Arc, time::Duration};
+use tcw3::pal::{self, prelude::*, MtSticky};
+
+use crate::model::{AccountId, AppAction, ChannelRef, ConnectionState, ConvAction, Message};
+
+pub mod irc;
+
+/// A connection to a chat service.
+///
+/// The connection is closed when the connector is dropped.
+pub trait Connector {
+    /// Join the specified channel. The channel is automatically re-joined
+    /// after reconnection.
+    fn join(&self, channel: &str);
+
+    /// Send a message to the specified channel.
+    fn send(&self, channel: &str, text: &str);
+}
+
+/// An event produced by a [`Connector`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The connection was established.
+    Connected,
+    /// The connection was lost. The connector will try to reconnect after
+    /// `retry_in`.
+    Disconnected { reason: String, retry_in: Duration },
+    /// The user joined a channel.
+    Joined { channel: String },
+    /// A message was received.
+    Message {
+        channel: String,
+        sender: String,
+        text: String,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+}
+
+/// Receives events from a [`Connector`]. Called on a background thread.
+pub type EventSink = Box<dyn Fn(Event) + Send>;
+
+/// The reconnection policy.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    /// The delay before the first reconnection attempt.
+    pub initial: Duration,
+    /// The upper bound of the delay. The delay is doubled after every failed
+    /// attempt until it reaches this value.
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Backoff {
+    /// Get the delay that follows `delay`.
+    pub fn next(&self, delay: Duration) -> Duration {
+        (delay * 2).min(self.max)
+    }
+}
+
+/// Construct an [`EventSink`] that converts events from a connector for the
+/// given server into `AppAction`s and passes them to `dispatch` on the main
+/// thread.
+pub fn main_thread_sink(
+    wm: pal::Wm,
+    account: AccountId,
+    server: String,
+    dispatch: impl Fn(AppAction) + 'static,
+) -> EventSink {
+    let dispatch: Arc<MtSticky<Box<dyn Fn(AppAction)>>> =
+        Arc::new(MtSticky::with_wm(wm, Box::new(dispatch)));
+
+    Box::new(move |event| {
+        let action = match event {
+            Event::Connected => {
+                info!("{}: Connected", server);
+                ConvAction::SetConnectionState {
+                    account,
+                    server: server.clone(),
+                    state: ConnectionState::Connected,
+                }
+            }
+            Event::Disconnected { reason, retry_in } => {
+                warn!(
+                    "{}: Disconnected ({}); reconnecting in {:?}",
+                    server, reason, retry_in
+                );
+                ConvAction::SetConnectionState {
+                    account,
+                    server: server.clone(),
+                    state: ConnectionState::Disconnected { reason, retry_in },
+                }
+            }
+            Event::Joined { channel } => ConvAction::AddChannel(ChannelRef {
+                account,
+                server: server.clone(),
+                channel,
+            }),
+            Event::Message {
+                channel,
+                sender,
+                text,
+                timestamp,
+            } => ConvAction::MessageReceived(
+                ChannelRef {
+                    account,
+                    server: server.clone(),
+                    channel,
+                },
+                Elem::new(Message {
+                    sender,
+                    timestamp,
+                    text,
+                }),
+            ),
+        };
+
+        let dispatch = Arc::clone(&dispatch);
+        pal::Wm::invoke_on_main_thread(move |wm| {
+            dispatch.get_with_wm(wm)(AppAction::Conv(action));
+        });
+    })
+}
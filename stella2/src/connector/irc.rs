@@ -0,0 +1,471 @@
+//! IRC client (RFC 1459/2812)
+use log::{debug, trace, warn};
+use std::{
+    borrow::Cow,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Backoff, Connector, Event, EventSink};
+
+/// The parameters of an IRC connection.
+#[derive(Debug, Clone)]
+pub struct IrcConfig {
+    pub host: String,
+    pub port: u16,
+    pub nick: String,
+    /// The server password (`PASS`), if any.
+    pub password: Option<String>,
+    /// The channels to join after connecting.
+    pub channels: Vec<String>,
+    pub backoff: Backoff,
+}
+
+impl IrcConfig {
+    pub const DEFAULT_PORT: u16 = 6667;
+
+    pub fn new(host: impl Into<String>, nick: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: Self::DEFAULT_PORT,
+            nick: nick.into(),
+            password: None,
+            channels: Vec::new(),
+            backoff: Backoff::default(),
+        }
+    }
+}
+
+/// An IRC implementation of [`Connector`].
+#[derive(Debug)]
+pub struct IrcConnector {
+    cmd_send: mpsc::Sender<Command>,
+}
+
+#[derive(Debug)]
+enum Command {
+    Join(String),
+    Send { channel: String, text: String },
+    Shutdown,
+}
+
+impl IrcConnector {
+    /// Start a worker thread that connects to the IRC server described by
+    /// `config`.
+    pub fn connect(config: IrcConfig, sink: EventSink) -> Self {
+        let (cmd_send, cmd_recv) = mpsc::channel();
+
+        thread::Builder::new()
+            .name(format!("irc {}", config.host))
+            .spawn(move || Worker::new(config, cmd_recv, sink).run())
+            .expect("failed to spawn a thread");
+
+        Self { cmd_send }
+    }
+}
+
+impl Drop for IrcConnector {
+    fn drop(&mut self) {
+        // Let the worker thread close the connection and exit. Don't wait for
+        // it to finish.
+        let _ = self.cmd_send.send(Command::Shutdown);
+    }
+}
+
+impl Connector for IrcConnector {
+    fn join(&self, channel: &str) {
+        let _ = self.cmd_send.send(Command::Join(channel.to_owned()));
+    }
+
+    fn send(&self, channel: &str, text: &str) {
+        let _ = self.cmd_send.send(Command::Send {
+            channel: channel.to_owned(),
+            text: text.to_owned(),
+        });
+    }
+}
+
+/// The interval at which the worker thread checks for commands while waiting
+/// for incoming data.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The maximum number of bytes in a `PRIVMSG` text. The maximum length of an
+/// IRC message is 512 bytes including the prefix the server prepends when
+/// relaying it, so leave some headroom.
+const MAX_TEXT_LEN: usize = 400;
+
+/// Indicates why a session has ended.
+enum SessionEnd {
+    Shutdown,
+    Disconnected(io::Error),
+}
+
+impl From<io::Error> for SessionEnd {
+    fn from(x: io::Error) -> Self {
+        SessionEnd::Disconnected(x)
+    }
+}
+
+struct Worker {
+    config: IrcConfig,
+    cmd_recv: mpsc::Receiver<Command>,
+    sink: EventSink,
+    /// The channels to (re-)join upon connection.
+    channels: Vec<String>,
+    /// The delay before the next reconnection attempt.
+    delay: Duration,
+}
+
+impl Worker {
+    fn new(config: IrcConfig, cmd_recv: mpsc::Receiver<Command>, sink: EventSink) -> Self {
+        Self {
+            channels: config.channels.clone(),
+            delay: config.backoff.initial,
+            config,
+            cmd_recv,
+            sink,
+        }
+    }
+
+    fn run(mut self) {
+        loop {
+            let error = match self.session() {
+                SessionEnd::Shutdown => return,
+                SessionEnd::Disconnected(e) => e,
+            };
+
+            (self.sink)(Event::Disconnected {
+                reason: error.to_string(),
+                retry_in: self.delay,
+            });
+
+            // Wait before reconnecting, but still respond to commands
+            let deadline = Instant::now() + self.delay;
+            loop {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match self.cmd_recv.recv_timeout(timeout) {
+                    Ok(Command::Shutdown) | Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                    Ok(Command::Join(channel)) => {
+                        self.add_channel(channel);
+                    }
+                    Ok(Command::Send { channel, .. }) => {
+                        warn!("Dropping a message to {:?} (not connected)", channel);
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                }
+            }
+
+            self.delay = self.config.backoff.next(self.delay);
+        }
+    }
+
+    fn add_channel(&mut self, channel: String) -> bool {
+        if self
+            .channels
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(&channel))
+        {
+            false
+        } else {
+            self.channels.push(channel);
+            true
+        }
+    }
+
+    /// Connect to the server and process messages until the connection is
+    /// lost or a shutdown is requested.
+    fn session(&mut self) -> SessionEnd {
+        let addrs = match (self.config.host.as_str(), self.config.port).to_socket_addrs() {
+            Ok(addrs) => addrs,
+            Err(e) => return e.into(),
+        };
+
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no address found");
+        for addr in addrs {
+            debug!("Connecting to {:?}", addr);
+            match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                Ok(stream) => return self.session_with_stream(stream),
+                Err(e) => last_error = e,
+            }
+        }
+
+        last_error.into()
+    }
+
+    fn session_with_stream(&mut self, stream: TcpStream) -> SessionEnd {
+        match self.session_with_stream_inner(stream) {
+            Ok(()) => SessionEnd::Shutdown,
+            Err(e) => e,
+        }
+    }
+
+    fn session_with_stream_inner(&mut self, mut stream: TcpStream) -> Result<(), SessionEnd> {
+        stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+        let mut nick = self.config.nick.clone();
+        let mut registered = false;
+
+        // Register the connection
+        if let Some(password) = &self.config.password {
+            write_message(&mut stream, "PASS", &[password])?;
+        }
+        write_message(&mut stream, "NICK", &[&nick])?;
+        write_message(&mut stream, "USER", &[&nick, "0", "*", &nick])?;
+
+        let mut read_buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        loop {
+            // Process commands
+            loop {
+                match self.cmd_recv.try_recv() {
+                    Ok(Command::Shutdown) | Err(mpsc::TryRecvError::Disconnected) => {
+                        let _ = write_message(&mut stream, "QUIT", &[]);
+                        return Ok(());
+                    }
+                    Ok(Command::Join(channel)) => {
+                        if self.add_channel(channel.clone()) && registered {
+                            write_message(&mut stream, "JOIN", &[&channel])?;
+                        }
+                    }
+                    Ok(Command::Send { channel, text }) => {
+                        if registered {
+                            for line in split_text(&text) {
+                                write_message(&mut stream, "PRIVMSG", &[&channel, line])?;
+                            }
+                        } else {
+                            warn!("Dropping a message to {:?} (not registered)", channel);
+                        }
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                }
+            }
+
+            // Receive data
+            match stream.read(&mut chunk) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "the connection was closed by the server",
+                    )
+                    .into());
+                }
+                Ok(num_bytes) => read_buf.extend_from_slice(&chunk[..num_bytes]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+
+            // Process received messages
+            while let Some(i) = read_buf.iter().position(|&b| b == b'\n') {
+                let line_bytes: Vec<u8> = read_buf.drain(..=i).collect();
+                let line = String::from_utf8_lossy(&line_bytes);
+                let line = line.trim_end_matches(|c| c == '\r' || c == '\n');
+                trace!("<< {:?}", line);
+
+                let msg = if let Some(msg) = Message::parse(line) {
+                    msg
+                } else {
+                    continue;
+                };
+
+                match msg.command {
+                    "PING" => {
+                        write_message(&mut stream, "PONG", &msg.params)?;
+                    }
+                    "ERROR" => {
+                        let reason = msg.params.get(0).copied().unwrap_or("unknown error");
+                        return Err(io::Error::new(io::ErrorKind::Other, reason).into());
+                    }
+                    // RPL_WELCOME
+                    "001" => {
+                        registered = true;
+                        if let Some(&n) = msg.params.get(0) {
+                            nick = n.to_owned();
+                        }
+                        self.delay = self.config.backoff.initial;
+                        (self.sink)(Event::Connected);
+
+                        for channel in self.channels.iter() {
+                            write_message(&mut stream, "JOIN", &[channel])?;
+                        }
+                    }
+                    // ERR_NICKNAMEINUSE
+                    "433" if !registered => {
+                        nick.push('_');
+                        write_message(&mut stream, "NICK", &[&nick])?;
+                    }
+                    "NICK" => {
+                        if let (Some(from), Some(&to)) = (msg.nick(), msg.params.get(0)) {
+                            if from == nick {
+                                nick = to.to_owned();
+                            }
+                        }
+                    }
+                    "JOIN" => {
+                        if let (Some(from), Some(&channel)) = (msg.nick(), msg.params.get(0)) {
+                            if from == nick {
+                                (self.sink)(Event::Joined {
+                                    channel: channel.to_owned(),
+                                });
+                            }
+                        }
+                    }
+                    "PRIVMSG" => {
+                        if let (Some(sender), &[target, text]) = (msg.nick(), &msg.params[..]) {
+                            let text = if let Some(text) = decode_ctcp_action(text) {
+                                Cow::Owned(format!("* {} {}", sender, text))
+                            } else if text.starts_with('\x01') {
+                                // Ignore other CTCP messages
+                                continue;
+                            } else {
+                                Cow::Borrowed(text)
+                            };
+
+                            // A message sent directly to us is filed under
+                            // the sender's name
+                            let channel = if is_channel_name(target) {
+                                target
+                            } else {
+                                sender
+                            };
+
+                            (self.sink)(Event::Message {
+                                channel: channel.to_owned(),
+                                sender: sender.to_owned(),
+                                text: text.into_owned(),
+                                timestamp: chrono::Utc::now(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// A parsed IRC message.
+#[derive(Debug, PartialEq)]
+struct Message<'a> {
+    prefix: Option<&'a str>,
+    command: &'a str,
+    params: Vec<&'a str>,
+}
+
+impl<'a> Message<'a> {
+    /// Parse a line (without a line terminator). Returns `None` if the line
+    /// is malformed.
+    fn parse(mut line: &'a str) -> Option<Self> {
+        // Skip IRCv3 message tags
+        if line.starts_with('@') {
+            line = line.splitn(2, ' ').nth(1)?;
+        }
+        line = line.trim_start_matches(' ');
+
+        let prefix = if line.starts_with(':') {
+            let mut parts = line[1..].splitn(2, ' ');
+            let prefix = parts.next()?;
+            line = parts.next()?.trim_start_matches(' ');
+            Some(prefix)
+        } else {
+            None
+        };
+
+        let mut parts = line.splitn(2, ' ');
+        let command = parts.next().filter(|s| !s.is_empty())?;
+        let mut rest = parts.next().unwrap_or("");
+
+        let mut params = Vec::new();
+        loop {
+            rest = rest.trim_start_matches(' ');
+            if rest.is_empty() {
+                break;
+            } else if rest.starts_with(':') {
+                params.push(&rest[1..]);
+                break;
+            }
+
+            let mut parts = rest.splitn(2, ' ');
+            params.push(parts.next().unwrap());
+            rest = parts.next().unwrap_or("");
+        }
+
+        Some(Self {
+            prefix,
+            command,
+            params,
+        })
+    }
+
+    /// Get the nickname part of the prefix.
+    fn nick(&self) -> Option<&'a str> {
+        self.prefix
+            .map(|p| p.split(|c| c == '!' || c == '@').next().unwrap())
+    }
+}
+
+/// Write an IRC message to `stream`. Line breaks in `params` are replaced
+/// with spaces so that they cannot be used to inject commands.
+fn write_message(stream: &mut impl Write, command: &str, params: &[&str]) -> io::Result<()> {
+    let mut line = command.to_owned();
+
+    for (i, param) in params.iter().enumerate() {
+        line.push(' ');
+        let is_last = i + 1 == params.len();
+        if is_last && (param.is_empty() || param.starts_with(':') || param.contains(' ')) {
+            line.push(':');
+        }
+        line.extend(param.chars().map(|c| match c {
+            '\r' | '\n' | '\0' => ' ',
+            c => c,
+        }));
+    }
+
+    trace!(">> {:?}", line);
+    line.push_str("\r\n");
+    stream.write_all(line.as_bytes())
+}
+
+/// Split `text` into lines that fit in `PRIVMSG`s.
+fn split_text(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .filter(|line| !line.is_empty())
+        .flat_map(|mut line| {
+            std::iter::from_fn(move || {
+                if line.is_empty() {
+                    return None;
+                }
+                let mut len = line.len().min(MAX_TEXT_LEN);
+                while !line.is_char_boundary(len) {
+                    len -= 1;
+                }
+                let (head, tail) = line.split_at(len);
+                line = tail;
+                Some(head)
+            })
+        })
+}
+
+fn is_channel_name(target: &str) -> bool {
+    target.starts_with(|c| c == '#' || c == '&' || c == '+' || c == '!')
+}
+
+/// Extract the text from a CTCP `ACTION` (`/me`) message.
+fn decode_ctcp_action(text: &str) -> Option<&str> {
+    const HEADER: &str = "\x01ACTION ";
+    if text.starts_with(HEADER) {
+        Some(text[HEADER.len()..].trim_end_matches('\x01'))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,242 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    time::Duration,
+};
+
+use super::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The server side of a connection to a fake IRC server.
+struct FakeClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl FakeClient {
+    fn accept(listener: &TcpListener) -> Self {
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        Self {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        }
+    }
+
+    fn read_line(&mut self) -> String {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        assert!(line.ends_with("\r\n"), "{:?}", line);
+        line.truncate(line.len() - 2);
+        line
+    }
+
+    fn send(&mut self, line: &str) {
+        self.writer.write_all(line.as_bytes()).unwrap();
+        self.writer.write_all(b"\r\n").unwrap();
+    }
+
+    /// Perform the registration process.
+    fn register(&mut self, nick: &str) {
+        assert_eq!(self.read_line(), format!("NICK {}", nick));
+        assert_eq!(self.read_line(), format!("USER {0} 0 * {0}", nick));
+        self.send(&format!(":irc.example.com 001 {} :Welcome", nick));
+    }
+}
+
+struct TestConnection {
+    listener: TcpListener,
+    connector: IrcConnector,
+    event_recv: mpsc::Receiver<Event>,
+}
+
+fn connect(modify_config: impl FnOnce(&mut IrcConfig)) -> TestConnection {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+    let mut config = IrcConfig::new("127.0.0.1", "stella");
+    config.port = listener.local_addr().unwrap().port();
+    config.channels = vec!["#test".to_owned()];
+    modify_config(&mut config);
+
+    let (event_send, event_recv) = mpsc::channel();
+    let connector = IrcConnector::connect(
+        config,
+        Box::new(move |event| {
+            let _ = event_send.send(event);
+        }),
+    );
+
+    TestConnection {
+        listener,
+        connector,
+        event_recv,
+    }
+}
+
+impl TestConnection {
+    fn recv_event(&self) -> Event {
+        self.event_recv
+            .recv_timeout(TIMEOUT)
+            .expect("timed out waiting for an event")
+    }
+}
+
+#[test]
+fn session() {
+    let conn = connect(|_| {});
+    let mut client = FakeClient::accept(&conn.listener);
+
+    // The nickname is already taken; the client should pick another one
+    assert_eq!(client.read_line(), "NICK stella");
+    assert_eq!(client.read_line(), "USER stella 0 * stella");
+    client.send(":irc.example.com 433 * stella :Nickname is already in use");
+    assert_eq!(client.read_line(), "NICK stella_");
+    client.send(":irc.example.com 001 stella_ :Welcome");
+    assert_eq!(conn.recv_event(), Event::Connected);
+
+    // Configured channels are joined automatically
+    assert_eq!(client.read_line(), "JOIN #test");
+    client.send(":stella_!stella@localhost JOIN #test");
+    assert_eq!(
+        conn.recv_event(),
+        Event::Joined {
+            channel: "#test".to_owned()
+        }
+    );
+
+    // Receive messages
+    client.send("@time=2020-01-01T00:00:00.000Z :alice!alice@localhost PRIVMSG #test :hello world");
+    match conn.recv_event() {
+        Event::Message {
+            channel,
+            sender,
+            text,
+            ..
+        } => {
+            assert_eq!(channel, "#test");
+            assert_eq!(sender, "alice");
+            assert_eq!(text, "hello world");
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    client.send(":alice!alice@localhost PRIVMSG stella_ :\x01ACTION waves\x01");
+    match conn.recv_event() {
+        Event::Message {
+            channel,
+            sender,
+            text,
+            ..
+        } => {
+            assert_eq!(channel, "alice");
+            assert_eq!(sender, "alice");
+            assert_eq!(text, "* alice waves");
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    client.send("PING :irc.example.com");
+    assert_eq!(client.read_line(), "PONG irc.example.com");
+
+    // Send messages
+    conn.connector.send("#test", "hi there\nhow are you?");
+    assert_eq!(client.read_line(), "PRIVMSG #test :hi there");
+    assert_eq!(client.read_line(), "PRIVMSG #test :how are you?");
+
+    conn.connector.join("#other");
+    assert_eq!(client.read_line(), "JOIN #other");
+
+    // Dropping the connector closes the connection
+    drop(conn.connector);
+    assert_eq!(client.read_line(), "QUIT");
+}
+
+#[test]
+fn reconnect() {
+    let conn = connect(|config| {
+        config.backoff = Backoff {
+            initial: Duration::from_millis(10),
+            max: Duration::from_millis(20),
+        };
+    });
+
+    let mut client = FakeClient::accept(&conn.listener);
+    client.register("stella");
+    assert_eq!(conn.recv_event(), Event::Connected);
+    assert_eq!(client.read_line(), "JOIN #test");
+
+    conn.connector.join("#other");
+    assert_eq!(client.read_line(), "JOIN #other");
+
+    // The server closes the connection
+    drop(client);
+    match conn.recv_event() {
+        Event::Disconnected { retry_in, .. } => {
+            assert_eq!(retry_in, Duration::from_millis(10));
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    // The client reconnects and re-joins the channels
+    let mut client = FakeClient::accept(&conn.listener);
+    client.register("stella");
+    assert_eq!(conn.recv_event(), Event::Connected);
+    assert_eq!(client.read_line(), "JOIN #test");
+    assert_eq!(client.read_line(), "JOIN #other");
+}
+
+#[test]
+fn parse_message() {
+    assert_eq!(
+        Message::parse(":nick!user@host PRIVMSG #chan :hello  world"),
+        Some(Message {
+            prefix: Some("nick!user@host"),
+            command: "PRIVMSG",
+            params: vec!["#chan", "hello  world"],
+        })
+    );
+    assert_eq!(
+        Message::parse("@tag=value PING irc.example.com"),
+        Some(Message {
+            prefix: None,
+            command: "PING",
+            params: vec!["irc.example.com"],
+        })
+    );
+    assert_eq!(
+        Message::parse("MODE #chan +o  nick"),
+        Some(Message {
+            prefix: None,
+            command: "MODE",
+            params: vec!["#chan", "+o", "nick"],
+        })
+    );
+    assert_eq!(Message::parse(""), None);
+    assert_eq!(Message::parse(":prefix"), None);
+
+    let msg = Message::parse(":nick!user@host QUIT").unwrap();
+    assert_eq!(msg.nick(), Some("nick"));
+}
+
+#[test]
+fn write_message_escapes_params() {
+    let mut out = Vec::new();
+    write_message(&mut out, "PRIVMSG", &["#chan", "a\r\nQUIT"]).unwrap();
+    write_message(&mut out, "PRIVMSG", &["#chan", ":)"]).unwrap();
+    write_message(&mut out, "PRIVMSG", &["#chan", ""]).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "PRIVMSG #chan :a  QUIT\r\nPRIVMSG #chan ::)\r\nPRIVMSG #chan :\r\n"
+    );
+}
+
+#[test]
+fn split_text_respects_char_boundaries() {
+    let text = "あ".repeat(MAX_TEXT_LEN);
+    let lines: Vec<_> = split_text(&text).collect();
+    assert!(lines.len() > 1);
+    assert!(lines.iter().all(|line| line.len() <= MAX_TEXT_LEN));
+    assert_eq!(lines.concat(), text);
+}
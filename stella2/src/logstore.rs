@@ -0,0 +1,918 @@
+//! Persistent message log store
+//!
+//! Messages are stored in the profile directory, in one directory per
+//! channel:
+//!
+//! ```text
+//! <data_dir>/logs/<account>/<server>/<channel>/
+//!     00000000.seg
+//!     00000001.seg
+//!     ...
+//!     index
+//! ```
+//!
+//! # Segments
+//!
+//! A segment file is a sequence of records. New records are only appended to
+//! the last segment. When the last segment reaches [`SEGMENT_SIZE`], it's
+//! flushed to the storage device and a new segment is started.
+//!
+//! Each record is encoded as follows (all integers are little-endian):
+//!
+//! | Size (bytes) | Description                                          |
+//! | ------------ | ---------------------------------------------------- |
+//! | 4            | The payload length                                   |
+//! | 4            | The CRC-32 checksum of the payload                   |
+//! | 8            | Payload: The timestamp (milliseconds since the epoch) |
+//! | 2            | Payload: The sender name length                      |
+//! | (variable)   | Payload: The sender name (UTF-8)                     |
+//! | (variable)   | Payload: The message text (UTF-8)                    |
+//!
+//! # Index
+//!
+//! `index` is a sparse index of the records. Each entry is a tuple of a
+//! timestamp (`i64`), a segment number (`u32`), and a byte offset (`u64`).
+//! An entry is created for the first record of every segment and then for
+//! every record following [`INDEX_INTERVAL`] unindexed records. The index is
+//! used to locate records by timestamp and to read records backward.
+//!
+//! # Crash safety
+//!
+//! A record or an index entry is written after the data it refers to. If the
+//! application crashes during a write, the last segment or the index may end
+//! with an incomplete record or entry. Such leftovers are detected (by
+//! their lengths and checksums) and truncated when the channel log is opened.
+//!
+//! If a write fails without crashing the application, the partially written
+//! data is truncated immediately so that the subsequent writes don't follow
+//! garbage (which would be discarded along with them by the recovery process).
+//! If even that fails, the channel log refuses further writes. The index is
+//! only an acceleration structure, so a failure to update it doesn't fail the
+//! append operation. Instead, the whole index is rewritten when the next entry
+//! is added.
+use log::{debug, error, warn};
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+};
+
+use crate::{config::profile::Profile, model};
+
+/// The size at which a new segment is started.
+pub const SEGMENT_SIZE: u64 = 1 << 20;
+
+/// The number of records between two consecutive index entries.
+pub const INDEX_INTERVAL: usize = 64;
+
+const RECORD_HEADER_LEN: usize = 8;
+const INDEX_ENTRY_LEN: usize = 20;
+const INDEX_FILE_NAME: &str = "index";
+const INDEX_TMP_FILE_NAME: &str = ".index.tmp";
+const SEGMENT_EXT: &str = "seg";
+
+/// Stores the message logs of all channels.
+#[derive(Debug)]
+pub struct LogStore {
+    root: PathBuf,
+    channels: HashMap<model::ChannelRef, ChannelLog>,
+}
+
+impl LogStore {
+    /// Construct a `LogStore` using the given profile.
+    pub fn new(profile: &Profile) -> Self {
+        Self::with_root(profile.data_dir().join("logs"))
+    }
+
+    fn with_root(root: PathBuf) -> Self {
+        Self {
+            root,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Get the `ChannelLog` for the given channel, opening it if it's not
+    /// open yet.
+    pub fn channel(&mut self, chref: &model::ChannelRef) -> io::Result<&mut ChannelLog> {
+        if !self.channels.contains_key(chref) {
+            let dir = self
+                .root
+                .join(chref.account.0.to_string())
+                .join(escape_file_name(&chref.server))
+                .join(escape_file_name(&chref.channel));
+            let log = ChannelLog::open(dir)?;
+            self.channels.insert(chref.clone(), log);
+        }
+
+        Ok(self.channels.get_mut(chref).unwrap())
+    }
+}
+
+/// Runs a [`LogStore`] on a worker thread so that the disk I/O doesn't block
+/// the main thread. The operations are performed in the order they were
+/// requested.
+///
+/// The worker thread exits when `LogStoreWorker` is dropped.
+#[derive(Debug)]
+pub struct LogStoreWorker {
+    cmd_send: mpsc::Sender<Command>,
+}
+
+type ReadOlderReply = Box<dyn FnOnce(io::Result<Vec<model::Message>>) + Send>;
+
+enum Command {
+    Append(model::ChannelRef, model::Message),
+    ReadOlder {
+        channel: model::ChannelRef,
+        max_count: usize,
+        reply: ReadOlderReply,
+    },
+    Flush(mpsc::Sender<()>),
+}
+
+impl LogStoreWorker {
+    /// Start a worker thread that operates on `store`.
+    pub fn new(store: LogStore) -> Self {
+        let (cmd_send, cmd_recv) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("log store".to_owned())
+            .spawn(move || Self::run(store, cmd_recv))
+            .expect("failed to spawn a thread");
+
+        Self { cmd_send }
+    }
+
+    fn run(mut store: LogStore, cmd_recv: mpsc::Receiver<Command>) {
+        for cmd in cmd_recv {
+            match cmd {
+                Command::Append(chref, msg) => {
+                    if let Err(e) = store.channel(&chref).and_then(|log| log.append(&msg)) {
+                        error!("Could not write a message to the log store: {}", e);
+                    }
+                }
+                Command::ReadOlder {
+                    channel,
+                    max_count,
+                    reply,
+                } => {
+                    reply(
+                        store
+                            .channel(&channel)
+                            .and_then(|log| log.read_older(max_count)),
+                    );
+                }
+                Command::Flush(done_send) => {
+                    let _ = done_send.send(());
+                }
+            }
+        }
+    }
+
+    /// Append a message to the log of the given channel. Errors are logged
+    /// and otherwise ignored.
+    pub fn append(&self, chref: &model::ChannelRef, msg: &model::Message) {
+        let _ = self
+            .cmd_send
+            .send(Command::Append(chref.clone(), msg.clone()));
+    }
+
+    /// Call [`ChannelLog::read_older`] on the log of the given channel.
+    /// `reply` is called on the worker thread with the result.
+    pub fn read_older(
+        &self,
+        chref: &model::ChannelRef,
+        max_count: usize,
+        reply: impl FnOnce(io::Result<Vec<model::Message>>) + Send + 'static,
+    ) {
+        let _ = self.cmd_send.send(Command::ReadOlder {
+            channel: chref.clone(),
+            max_count,
+            reply: Box::new(reply),
+        });
+    }
+
+    /// Block the current thread until all operations requested so far are
+    /// complete.
+    pub fn flush(&self) {
+        let (done_send, done_recv) = mpsc::channel();
+        if self.cmd_send.send(Command::Flush(done_send)).is_ok() {
+            let _ = done_recv.recv();
+        }
+    }
+}
+
+/// Encode a string into a string that can be safely used as a file name.
+fn escape_file_name(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => out.push(b as char),
+            // `.` is allowed except at the beginning (to prevent `.` and `..`)
+            b'.' if !out.is_empty() => out.push('.'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    if out.is_empty() {
+        out.push('%');
+    }
+    out
+}
+
+/// The position of a record in a channel log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogPos {
+    pub segment: u32,
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct IndexEntry {
+    timestamp: i64,
+    pos: LogPos,
+}
+
+impl IndexEntry {
+    fn encode(&self) -> [u8; INDEX_ENTRY_LEN] {
+        let mut bytes = [0u8; INDEX_ENTRY_LEN];
+        bytes[0..8].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.pos.segment.to_le_bytes());
+        bytes[12..20].copy_from_slice(&self.pos.offset.to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        Self {
+            timestamp: i64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            pos: LogPos {
+                segment: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+                offset: u64::from_le_bytes(bytes[12..20].try_into().unwrap()),
+            },
+        }
+    }
+}
+
+/// The message log of a single channel.
+#[derive(Debug)]
+pub struct ChannelLog {
+    dir: PathBuf,
+    /// The lengths of the segments. The segment numbers are consecutive and
+    /// start at `first_segment`.
+    segment_lens: Vec<u64>,
+    first_segment: u32,
+    index: Vec<IndexEntry>,
+    index_file: Option<Box<dyn AppendFile>>,
+    /// Set when the index file might not match `index` because of a failed
+    /// write. The index file is rewritten before adding the next entry.
+    index_dirty: bool,
+    /// The last segment, opened for appending.
+    segment_file: Option<Box<dyn AppendFile>>,
+    /// Set when a failed write couldn't be rolled back. All subsequent writes
+    /// are refused.
+    broken: bool,
+    /// The number of records written after the last index entry.
+    records_since_index: usize,
+    /// The position of the oldest record returned by `read_older`.
+    history_cursor: LogPos,
+}
+
+impl ChannelLog {
+    /// Open the message log in the specified directory, creating it if it
+    /// doesn't exist. Recovers from incomplete writes.
+    pub fn open(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        // Find segments
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXT) {
+                continue;
+            }
+            if let Some(seg) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u32>().ok())
+            {
+                segments.push((seg, fs::metadata(&path)?.len()));
+            }
+        }
+        segments.sort_unstable();
+
+        // The segment numbers must be consecutive. If they aren't (e.g., some
+        // segments were removed by the user), only use the ones following
+        // the last gap.
+        let first_valid = segments
+            .windows(2)
+            .rposition(|w| w[1].0 != w[0].0 + 1)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        if first_valid > 0 {
+            warn!(
+                "{:?}: Ignoring segments before #{} because of a gap",
+                dir, segments[first_valid].0
+            );
+            segments.drain(..first_valid);
+        }
+
+        let mut this = Self {
+            first_segment: segments.first().map(|s| s.0).unwrap_or(0),
+            segment_lens: segments.iter().map(|s| s.1).collect(),
+            index: Vec::new(),
+            index_file: None,
+            index_dirty: false,
+            segment_file: None,
+            broken: false,
+            records_since_index: 0,
+            history_cursor: LogPos {
+                segment: 0,
+                offset: 0,
+            },
+            dir,
+        };
+
+        this.recover()?;
+        this.history_cursor = this.end_pos();
+
+        Ok(this)
+    }
+
+    fn segment_path(&self, segment: u32) -> PathBuf {
+        self.dir.join(format!("{:08}.{}", segment, SEGMENT_EXT))
+    }
+
+    fn segment_len(&self, segment: u32) -> Option<u64> {
+        let i = segment.checked_sub(self.first_segment)? as usize;
+        self.segment_lens.get(i).copied()
+    }
+
+    fn last_segment(&self) -> Option<u32> {
+        if self.segment_lens.is_empty() {
+            None
+        } else {
+            Some(self.first_segment + self.segment_lens.len() as u32 - 1)
+        }
+    }
+
+    /// Load the index and repair the last segment and the index.
+    fn recover(&mut self) -> io::Result<()> {
+        let index_path = self.dir.join(INDEX_FILE_NAME);
+        let mut index_bytes = Vec::new();
+        match File::open(&index_path) {
+            Ok(mut file) => {
+                file.read_to_end(&mut index_bytes)?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let num_entries = index_bytes.len() / INDEX_ENTRY_LEN;
+        let mut dirty = index_bytes.len() % INDEX_ENTRY_LEN != 0;
+        if dirty {
+            warn!("{:?}: Discarding an incomplete index entry", self.dir);
+        }
+
+        // Discard entries pointing to nonexistent locations
+        let mut index: Vec<IndexEntry> = index_bytes
+            .chunks_exact(INDEX_ENTRY_LEN)
+            .map(IndexEntry::decode)
+            .collect();
+        index.retain(|e| match self.segment_len(e.pos.segment) {
+            Some(len) => e.pos.offset < len,
+            None => false,
+        });
+        dirty |= index.len() != num_entries;
+
+        // Find the end of the valid data in the last segment
+        if let Some(last_segment) = self.last_segment() {
+            loop {
+                let len = self.segment_len(last_segment).unwrap();
+                let indexed_start = index
+                    .iter()
+                    .rev()
+                    .find(|e| e.pos.segment == last_segment)
+                    .map(|e| e.pos.offset);
+
+                let mut num_records = 0;
+                let mut first_timestamp = None;
+                let valid_end = for_each_record(
+                    &self.segment_path(last_segment),
+                    indexed_start.unwrap_or(0),
+                    len,
+                    |_, _, msg| {
+                        if first_timestamp.is_none() {
+                            first_timestamp = Some(msg.timestamp.timestamp_millis());
+                        }
+                        num_records += 1;
+                        true
+                    },
+                )?;
+
+                if valid_end < len {
+                    warn!(
+                        "{:?}: Truncating segment #{} from {} to {} bytes",
+                        self.dir, last_segment, len, valid_end
+                    );
+                    let file = OpenOptions::new()
+                        .write(true)
+                        .open(self.segment_path(last_segment))?;
+                    file.set_len(valid_end)?;
+                    file.sync_all()?;
+                    *self.segment_lens.last_mut().unwrap() = valid_end;
+
+                    // If an index entry now points to the end, remove it and
+                    // scan again from the previous entry
+                    let old_len = index.len();
+                    index.retain(|e| e.pos.segment != last_segment || e.pos.offset < valid_end);
+                    if index.len() != old_len {
+                        dirty = true;
+                        continue;
+                    }
+                }
+
+                // The index entry for the start of the segment might be
+                // missing if the application crashed right after starting the
+                // segment
+                if let (None, Some(timestamp)) = (indexed_start, first_timestamp) {
+                    index.push(IndexEntry {
+                        timestamp,
+                        pos: LogPos {
+                            segment: last_segment,
+                            offset: 0,
+                        },
+                    });
+                    dirty = true;
+                }
+
+                // Excluding the indexed record itself
+                self.records_since_index = num_records.saturating_sub(1);
+                break;
+            }
+        }
+
+        self.index = index;
+
+        if dirty {
+            self.rewrite_index()?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace the index file with the contents of `self.index`.
+    fn rewrite_index(&mut self) -> io::Result<()> {
+        // Close the old file first; it can't be replaced while it's open on
+        // some platforms
+        self.index_file = None;
+
+        let tmp_path = self.dir.join(INDEX_TMP_FILE_NAME);
+        let bytes: Vec<u8> = self
+            .index
+            .iter()
+            .flat_map(|e| e.encode().to_vec())
+            .collect();
+        {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, self.dir.join(INDEX_FILE_NAME))
+    }
+
+    /// Get the position just after the last record.
+    pub fn end_pos(&self) -> LogPos {
+        match self.last_segment() {
+            Some(segment) => LogPos {
+                segment,
+                offset: self.segment_len(segment).unwrap(),
+            },
+            None => LogPos {
+                segment: 0,
+                offset: 0,
+            },
+        }
+    }
+
+    /// Append a message to the log.
+    ///
+    /// Timestamps are expected to be (roughly) non-decreasing. Otherwise,
+    /// [`ChannelLog::pos_at`] might return an imprecise result.
+    pub fn append(&mut self, msg: &model::Message) -> io::Result<()> {
+        if self.broken {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "the channel log is unusable because of a previous write error",
+            ));
+        }
+
+        let record = encode_record(msg);
+
+        let needs_new_segment = match self.last_segment() {
+            Some(segment) => self.segment_len(segment).unwrap() >= SEGMENT_SIZE,
+            None => true,
+        };
+
+        if needs_new_segment {
+            // Make sure the completed segment is durable before starting
+            // a new one. Only the last segment is checked for an incomplete
+            // write by `recover`.
+            if let Some(file) = self.segment_file.take() {
+                file.sync_all()?;
+            }
+
+            let segment = self.last_segment().map(|s| s + 1).unwrap_or(0);
+            debug!("{:?}: Starting segment #{}", self.dir, segment);
+            File::create(self.segment_path(segment))?;
+            if self.segment_lens.is_empty() {
+                self.first_segment = segment;
+            }
+            self.segment_lens.push(0);
+        }
+
+        let segment = self.last_segment().unwrap();
+        let offset = self.segment_len(segment).unwrap();
+
+        if self.segment_file.is_none() {
+            self.segment_file = Some(Box::new(
+                OpenOptions::new()
+                    .append(true)
+                    .open(self.segment_path(segment))?,
+            ));
+        }
+
+        self.write_or_roll_back(|this| &mut this.segment_file, offset, &record)?;
+        *self.segment_lens.last_mut().unwrap() += record.len() as u64;
+
+        if offset == 0 || self.records_since_index >= INDEX_INTERVAL {
+            let entry = IndexEntry {
+                timestamp: msg.timestamp.timestamp_millis(),
+                pos: LogPos { segment, offset },
+            };
+            self.index.push(entry);
+            self.records_since_index = 0;
+
+            // The record is already stored, so don't report the failure to
+            // the caller
+            if let Err(e) = self.write_index_entry(&entry) {
+                warn!(
+                    "{:?}: Could not update the index: {}. \
+                     It will be rewritten later.",
+                    self.dir, e
+                );
+                self.index_file = None;
+                self.index_dirty = true;
+            }
+        } else {
+            self.records_since_index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Write `entry`, which was just added to `self.index`, to the index file.
+    fn write_index_entry(&mut self, entry: &IndexEntry) -> io::Result<()> {
+        if self.index_dirty {
+            self.rewrite_index()?;
+            self.index_dirty = false;
+            return Ok(());
+        }
+
+        if self.index_file.is_none() {
+            self.index_file = Some(Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.dir.join(INDEX_FILE_NAME))?,
+            ));
+        }
+
+        // `recover` rewrites the index if it contains anything else, so
+        // the index file contains exactly `self.index` minus `entry`
+        self.index_file.as_mut().unwrap().write_all(&entry.encode())
+    }
+
+    /// Append `bytes` to the file in the field selected by `file`, whose
+    /// current length is `len`. On failure, the file is truncated to `len` to
+    /// remove partially written data. If that fails too, `self` is marked
+    /// as broken.
+    fn write_or_roll_back(
+        &mut self,
+        file: impl Fn(&mut Self) -> &mut Option<Box<dyn AppendFile>>,
+        len: u64,
+        bytes: &[u8],
+    ) -> io::Result<()> {
+        let f = file(self).as_mut().unwrap();
+        let e = match f.write_all(bytes) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        if let Err(e2) = f.set_len(len) {
+            warn!(
+                "{:?}: Could not remove partially written data: {}. \
+                 Refusing further writes.",
+                self.dir, e2
+            );
+            self.broken = true;
+        }
+
+        // Reopen the file next time
+        *file(self) = None;
+
+        Err(e)
+    }
+
+    /// Find the position of the first record having a timestamp not earlier
+    /// than `timestamp`.
+    pub fn pos_at(&self, timestamp: chrono::DateTime<chrono::Utc>) -> io::Result<LogPos> {
+        let timestamp = timestamp.timestamp_millis();
+
+        // Find the last index entry before `timestamp`
+        let i = match self.index.binary_search_by(|e| {
+            if e.timestamp < timestamp {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Greater
+            }
+        }) {
+            Ok(_) => unreachable!(),
+            Err(i) => i,
+        };
+
+        let start = match i.checked_sub(1) {
+            Some(i) => self.index[i].pos,
+            None => {
+                return Ok(self.index.first().map(|e| e.pos).unwrap_or(self.end_pos()));
+            }
+        };
+
+        let mut found = None;
+        self.scan(start, self.end_pos(), |pos, _, msg| {
+            if msg.timestamp.timestamp_millis() >= timestamp {
+                found = Some(pos);
+                false
+            } else {
+                true
+            }
+        })?;
+
+        Ok(found.unwrap_or(self.end_pos()))
+    }
+
+    /// Read at most `max_count` messages starting at `pos`. Returns the
+    /// messages and the position following the last returned message.
+    pub fn read_from(
+        &self,
+        pos: LogPos,
+        max_count: usize,
+    ) -> io::Result<(Vec<model::Message>, LogPos)> {
+        let mut messages = Vec::new();
+        let mut next_pos = pos;
+
+        if max_count > 0 {
+            self.scan(pos, self.end_pos(), |_, end_pos, msg| {
+                messages.push(msg);
+                next_pos = end_pos;
+                messages.len() < max_count
+            })?;
+        }
+
+        Ok((messages, next_pos))
+    }
+
+    /// Read at most `max_count` messages immediately preceding `pos`. Returns
+    /// the messages in the chronological order and the position of the first
+    /// returned message.
+    pub fn read_before(
+        &self,
+        pos: LogPos,
+        max_count: usize,
+    ) -> io::Result<(Vec<model::Message>, LogPos)> {
+        let mut messages: Vec<(LogPos, model::Message)> = Vec::new();
+        let mut chunk_end = pos;
+
+        while messages.len() < max_count {
+            // Find the last index entry before `chunk_end`
+            let chunk_start = match self.index.iter().rev().find(|e| e.pos < chunk_end) {
+                Some(e) => e.pos,
+                None => break,
+            };
+
+            let mut chunk = Vec::new();
+            self.scan(chunk_start, chunk_end, |pos, _, msg| {
+                chunk.push((pos, msg));
+                true
+            })?;
+
+            chunk.extend(messages.drain(..));
+            messages = chunk;
+            chunk_end = chunk_start;
+        }
+
+        let excess = messages.len().saturating_sub(max_count);
+        messages.drain(..excess);
+
+        let first_pos = messages.first().map(|m| m.0).unwrap_or(pos);
+        Ok((messages.into_iter().map(|m| m.1).collect(), first_pos))
+    }
+
+    /// Read at most `max_count` messages preceding the ones returned by the
+    /// previous calls to this method. The first call returns the last
+    /// messages written before the log was opened.
+    pub fn read_older(&mut self, max_count: usize) -> io::Result<Vec<model::Message>> {
+        let (messages, pos) = self.read_before(self.history_cursor, max_count)?;
+        self.history_cursor = pos;
+        Ok(messages)
+    }
+
+    /// Call `f` for each record in range `start..end`, stopping when `f`
+    /// returns `false`. `f` receives the start and end positions of each
+    /// record.
+    fn scan(
+        &self,
+        start: LogPos,
+        end: LogPos,
+        mut f: impl FnMut(LogPos, LogPos, model::Message) -> bool,
+    ) -> io::Result<()> {
+        let mut segment = start.segment;
+        let mut offset = start.offset;
+
+        while (LogPos { segment, offset }) < end {
+            let seg_len = if let Some(len) = self.segment_len(segment) {
+                len
+            } else {
+                break;
+            };
+            let scan_end = if segment == end.segment {
+                end.offset.min(seg_len)
+            } else {
+                seg_len
+            };
+
+            let mut stop = false;
+            for_each_record(
+                &self.segment_path(segment),
+                offset,
+                scan_end,
+                |offset, end_offset, msg| {
+                    stop = !f(
+                        LogPos { segment, offset },
+                        LogPos {
+                            segment,
+                            offset: end_offset,
+                        },
+                        msg,
+                    );
+                    !stop
+                },
+            )?;
+            if stop {
+                break;
+            }
+
+            segment += 1;
+            offset = 0;
+        }
+
+        Ok(())
+    }
+}
+
+/// A file opened for appending. This is a trait so that tests can inject
+/// write errors.
+trait AppendFile: Write + std::fmt::Debug {
+    fn set_len(&self, len: u64) -> io::Result<()>;
+    fn sync_all(&self) -> io::Result<()>;
+}
+
+impl AppendFile for File {
+    fn set_len(&self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+
+    fn sync_all(&self) -> io::Result<()> {
+        File::sync_all(self)
+    }
+}
+
+/// Read the records in `start..end` of the specified segment file. Calls
+/// `on_record` with the start and end offsets of each valid record until it
+/// returns `false`.
+///
+/// Returns the end offset of the last record that was read. If an invalid
+/// record is encountered, this is the offset of the invalid record.
+fn for_each_record(
+    path: &Path,
+    start: u64,
+    end: u64,
+    mut on_record: impl FnMut(u64, u64, model::Message) -> bool,
+) -> io::Result<u64> {
+    let mut file = BufReader::new(File::open(path)?);
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut offset = start;
+    let mut payload = Vec::new();
+
+    while offset < end {
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        let valid = end - offset >= RECORD_HEADER_LEN as u64
+            && read_exact_or_eof(&mut file, &mut header)?
+            && {
+                let len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as u64;
+                let crc = u32::from_le_bytes(header[4..8].try_into().unwrap());
+                end - offset - RECORD_HEADER_LEN as u64 >= len && {
+                    payload.resize(len as usize, 0);
+                    read_exact_or_eof(&mut file, &mut payload)? && crc32(&payload) == crc
+                }
+            };
+
+        let msg = if valid {
+            decode_payload(&payload)
+        } else {
+            None
+        };
+
+        if let Some(msg) = msg {
+            let end_offset = offset + (RECORD_HEADER_LEN + payload.len()) as u64;
+            let cont = on_record(offset, end_offset, msg);
+            offset = end_offset;
+            if !cont {
+                break;
+            }
+        } else {
+            warn!("{:?}: Found an invalid record at offset {}", path, offset);
+            break;
+        }
+    }
+
+    Ok(offset)
+}
+
+/// Like `Read::read_exact`, but returns `Ok(false)` on EOF.
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn encode_record(msg: &model::Message) -> Vec<u8> {
+    let mut sender = msg.sender.as_str();
+    if sender.len() > u16::max_value() as usize {
+        // Truncate at a character boundary
+        let mut len = u16::max_value() as usize;
+        while !sender.is_char_boundary(len) {
+            len -= 1;
+        }
+        sender = &sender[..len];
+    }
+
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + 10 + sender.len() + msg.text.len());
+    record.extend_from_slice(&[0u8; RECORD_HEADER_LEN]);
+    record.extend_from_slice(&msg.timestamp.timestamp_millis().to_le_bytes());
+    record.extend_from_slice(&(sender.len() as u16).to_le_bytes());
+    record.extend_from_slice(sender.as_bytes());
+    record.extend_from_slice(msg.text.as_bytes());
+
+    let payload_len = (record.len() - RECORD_HEADER_LEN) as u32;
+    let crc = crc32(&record[RECORD_HEADER_LEN..]);
+    record[0..4].copy_from_slice(&payload_len.to_le_bytes());
+    record[4..8].copy_from_slice(&crc.to_le_bytes());
+
+    record
+}
+
+fn decode_payload(payload: &[u8]) -> Option<model::Message> {
+    use chrono::TimeZone;
+
+    if payload.len() < 10 {
+        return None;
+    }
+    let timestamp = i64::from_le_bytes(payload[0..8].try_into().unwrap());
+    let sender_len = u16::from_le_bytes(payload[8..10].try_into().unwrap()) as usize;
+    let sender = payload.get(10..10 + sender_len)?;
+    let text = &payload[10 + sender_len..];
+
+    Some(model::Message {
+        sender: std::str::from_utf8(sender).ok()?.to_owned(),
+        timestamp: chrono::Utc.timestamp_millis_opt(timestamp).single()?,
+        text: std::str::from_utf8(text).ok()?.to_owned(),
+    })
+}
+
+/// Calculate the CRC-32 (ISO-HDLC) checksum of `data`.
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| {
+        (0..8).fold(crc ^ b as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (!(crc & 1)).wrapping_add(1))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests;
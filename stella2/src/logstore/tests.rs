@@ -0,0 +1,448 @@
+use chrono::TimeZone;
+use std::{fs, io::Write, path::PathBuf, sync::mpsc};
+
+use super::*;
+
+/// A temporary directory that is removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("stella2-logstore-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn message(i: usize) -> model::Message {
+    model::Message {
+        sender: if i % 2 == 0 { "alice" } else { "bob" }.to_owned(),
+        timestamp: chrono::Utc.timestamp_millis(1_500_000_000_000 + i as i64 * 1000),
+        text: format!("message {}", i),
+    }
+}
+
+fn texts(messages: &[model::Message]) -> Vec<String> {
+    messages.iter().map(|m| m.text.clone()).collect()
+}
+
+fn expected_texts(range: std::ops::Range<usize>) -> Vec<String> {
+    range.map(|i| message(i).text).collect()
+}
+
+fn first_pos(log: &ChannelLog) -> LogPos {
+    log.index[0].pos
+}
+
+fn segment_path(dir: &TempDir, segment: u32) -> PathBuf {
+    dir.0.join(format!("{:08}.{}", segment, SEGMENT_EXT))
+}
+
+#[test]
+fn append_and_read() {
+    let dir = TempDir::new("append_and_read");
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..200 {
+            log.append(&message(i)).unwrap();
+        }
+    }
+
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+
+    let (messages, next) = log.read_from(first_pos(&log), 1000).unwrap();
+    assert_eq!(texts(&messages), expected_texts(0..200));
+    assert_eq!(next, log.end_pos());
+
+    let m = &messages[42];
+    assert_eq!(m.sender, message(42).sender);
+    assert_eq!(m.timestamp, message(42).timestamp);
+
+    // Read backward across index entries
+    let (messages, pos) = log.read_before(log.end_pos(), 100).unwrap();
+    assert_eq!(texts(&messages), expected_texts(100..200));
+    let (messages, _) = log.read_before(pos, 30).unwrap();
+    assert_eq!(texts(&messages), expected_texts(70..100));
+
+    // Find a message by timestamp
+    let pos = log.pos_at(message(150).timestamp).unwrap();
+    let (messages, _) = log.read_from(pos, 3).unwrap();
+    assert_eq!(texts(&messages), expected_texts(150..153));
+
+    let pos = log.pos_at(chrono::Utc.timestamp_millis(0)).unwrap();
+    assert_eq!(pos, first_pos(&log));
+    let pos = log.pos_at(message(1000).timestamp).unwrap();
+    assert_eq!(pos, log.end_pos());
+}
+
+#[test]
+fn read_older() {
+    let dir = TempDir::new("read_older");
+
+    let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+    for i in 0..100 {
+        log.append(&message(i)).unwrap();
+    }
+    drop(log);
+
+    let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+
+    // Messages appended after opening are not returned
+    log.append(&message(100)).unwrap();
+
+    assert_eq!(texts(&log.read_older(40).unwrap()), expected_texts(60..100));
+    assert_eq!(texts(&log.read_older(40).unwrap()), expected_texts(20..60));
+    assert_eq!(texts(&log.read_older(40).unwrap()), expected_texts(0..20));
+    assert!(log.read_older(40).unwrap().is_empty());
+}
+
+#[test]
+fn worker() {
+    let dir = TempDir::new("worker");
+    let chref = model::ChannelRef {
+        account: model::AccountId(1),
+        server: "irc.example.com".to_owned(),
+        channel: "#a".to_owned(),
+    };
+
+    let worker = LogStoreWorker::new(LogStore::with_root(dir.0.clone()));
+    for i in 0..10 {
+        worker.append(&chref, &message(i));
+    }
+    worker.flush();
+    drop(worker);
+
+    let worker = LogStoreWorker::new(LogStore::with_root(dir.0.clone()));
+    let (reply_send, reply_recv) = mpsc::channel();
+    for _ in 0..3 {
+        let reply_send = reply_send.clone();
+        worker.read_older(&chref, 4, move |result| {
+            reply_send.send(texts(&result.unwrap())).unwrap();
+        });
+    }
+
+    // The requests are processed in order
+    assert_eq!(reply_recv.recv().unwrap(), expected_texts(6..10));
+    assert_eq!(reply_recv.recv().unwrap(), expected_texts(2..6));
+    assert_eq!(reply_recv.recv().unwrap(), expected_texts(0..2));
+}
+
+#[test]
+fn multiple_segments() {
+    let dir = TempDir::new("multiple_segments");
+    let big_text = "x".repeat(100_000);
+    let count = (SEGMENT_SIZE as usize / big_text.len()) * 2 + 5;
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..count {
+            log.append(&model::Message {
+                text: format!("{} {}", i, big_text),
+                ..message(i)
+            })
+            .unwrap();
+        }
+    }
+
+    assert!(segment_path(&dir, 2).is_file());
+
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+    let (messages, _) = log.read_from(first_pos(&log), count + 1).unwrap();
+    assert_eq!(messages.len(), count);
+
+    let (messages, _) = log.read_before(log.end_pos(), count + 1).unwrap();
+    assert_eq!(messages.len(), count);
+    for (i, m) in messages.iter().enumerate() {
+        assert!(m.text.starts_with(&format!("{} ", i)));
+    }
+}
+
+#[test]
+fn recover_truncated_record() {
+    let dir = TempDir::new("recover_truncated_record");
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..10 {
+            log.append(&message(i)).unwrap();
+        }
+    }
+
+    // Simulate a crash in the middle of writing the last record
+    let path = segment_path(&dir, 0);
+    let len = fs::metadata(&path).unwrap().len();
+    let file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+    file.set_len(len - 5).unwrap();
+    drop(file);
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        let (messages, _) = log.read_from(first_pos(&log), 100).unwrap();
+        assert_eq!(texts(&messages), expected_texts(0..9));
+
+        // The incomplete record was removed
+        assert!(fs::metadata(&path).unwrap().len() < len - 5);
+
+        // Appending works after recovery
+        log.append(&message(9)).unwrap();
+        log.append(&message(10)).unwrap();
+    }
+
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+    let (messages, _) = log.read_from(first_pos(&log), 100).unwrap();
+    assert_eq!(texts(&messages), expected_texts(0..11));
+}
+
+#[test]
+fn recover_corrupted_record() {
+    let dir = TempDir::new("recover_corrupted_record");
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..10 {
+            log.append(&message(i)).unwrap();
+        }
+    }
+
+    // Flip a bit in the last record (the garbage left by a crash does not
+    // necessarily look incomplete)
+    let path = segment_path(&dir, 0);
+    let mut bytes = fs::read(&path).unwrap();
+    *bytes.last_mut().unwrap() ^= 1;
+    fs::write(&path, &bytes).unwrap();
+
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+    let (messages, _) = log.read_from(first_pos(&log), 100).unwrap();
+    assert_eq!(texts(&messages), expected_texts(0..9));
+}
+
+#[test]
+fn recover_truncated_index() {
+    let dir = TempDir::new("recover_truncated_index");
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..INDEX_INTERVAL * 3 {
+            log.append(&message(i)).unwrap();
+        }
+    }
+
+    // Simulate a crash in the middle of writing an index entry
+    let index_path = dir.0.join(INDEX_FILE_NAME);
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&index_path)
+        .unwrap()
+        .write_all(&[1, 2, 3, 4, 5, 6, 7])
+        .unwrap();
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        assert_eq!(
+            fs::metadata(&index_path).unwrap().len() % INDEX_ENTRY_LEN as u64,
+            0
+        );
+
+        let (messages, _) = log.read_before(log.end_pos(), 1000).unwrap();
+        assert_eq!(texts(&messages), expected_texts(0..INDEX_INTERVAL * 3));
+
+        log.append(&message(INDEX_INTERVAL * 3)).unwrap();
+    }
+
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+    let (messages, _) = log.read_before(log.end_pos(), 1000).unwrap();
+    assert_eq!(texts(&messages), expected_texts(0..INDEX_INTERVAL * 3 + 1));
+}
+
+#[test]
+fn recover_missing_index() {
+    let dir = TempDir::new("recover_missing_index");
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..10 {
+            log.append(&message(i)).unwrap();
+        }
+    }
+
+    // Simulate a crash before the first index entry was written
+    fs::remove_file(dir.0.join(INDEX_FILE_NAME)).unwrap();
+
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+    let (messages, _) = log.read_before(log.end_pos(), 100).unwrap();
+    assert_eq!(texts(&messages), expected_texts(0..10));
+}
+
+/// Wraps a file and fails after writing a given number of bytes.
+#[derive(Debug)]
+struct FailingFile {
+    inner: File,
+    remaining: usize,
+    fail_set_len: bool,
+}
+
+impl Write for FailingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Err(io::Error::new(io::ErrorKind::Other, "injected error"));
+        }
+        let len = self.inner.write(&buf[..buf.len().min(self.remaining)])?;
+        self.remaining -= len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl AppendFile for FailingFile {
+    fn set_len(&self, len: u64) -> io::Result<()> {
+        if self.fail_set_len {
+            Err(io::Error::new(io::ErrorKind::Other, "injected error"))
+        } else {
+            self.inner.set_len(len)
+        }
+    }
+
+    fn sync_all(&self) -> io::Result<()> {
+        self.inner.sync_all()
+    }
+}
+
+/// Make the next write to the first segment fail after writing a few bytes.
+fn inject_write_error(log: &mut ChannelLog, dir: &TempDir, fail_set_len: bool) {
+    let file = fs::OpenOptions::new()
+        .append(true)
+        .open(segment_path(dir, 0))
+        .unwrap();
+    log.segment_file = Some(Box::new(FailingFile {
+        inner: file,
+        remaining: 5,
+        fail_set_len,
+    }));
+}
+
+#[test]
+fn roll_back_failed_write() {
+    let dir = TempDir::new("roll_back_failed_write");
+    let path = segment_path(&dir, 0);
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..10 {
+            log.append(&message(i)).unwrap();
+        }
+        let len = fs::metadata(&path).unwrap().len();
+
+        inject_write_error(&mut log, &dir, false);
+        log.append(&message(100)).unwrap_err();
+
+        // The partially written record was removed
+        assert_eq!(fs::metadata(&path).unwrap().len(), len);
+
+        // Appending works after the failure
+        log.append(&message(10)).unwrap();
+        log.append(&message(11)).unwrap();
+
+        let (messages, _) = log.read_from(first_pos(&log), 100).unwrap();
+        assert_eq!(texts(&messages), expected_texts(0..12));
+    }
+
+    // The records written after the failure are not lost by recovery
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+    let (messages, _) = log.read_from(first_pos(&log), 100).unwrap();
+    assert_eq!(texts(&messages), expected_texts(0..12));
+}
+
+#[test]
+fn refuse_writes_after_failed_roll_back() {
+    let dir = TempDir::new("refuse_writes_after_failed_roll_back");
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..10 {
+            log.append(&message(i)).unwrap();
+        }
+
+        inject_write_error(&mut log, &dir, true);
+        log.append(&message(100)).unwrap_err();
+
+        // The garbage couldn't be removed, so appending more records would
+        // make them unrecoverable
+        log.append(&message(10)).unwrap_err();
+    }
+
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+    let (messages, _) = log.read_from(first_pos(&log), 100).unwrap();
+    assert_eq!(texts(&messages), expected_texts(0..10));
+}
+
+#[test]
+fn tolerate_failed_index_write() {
+    let dir = TempDir::new("tolerate_failed_index_write");
+
+    {
+        let mut log = ChannelLog::open(dir.0.clone()).unwrap();
+        for i in 0..=INDEX_INTERVAL {
+            log.append(&message(i)).unwrap();
+        }
+        assert_eq!(log.index.len(), 1);
+
+        // Make the next index write fail after writing a few bytes. The
+        // garbage can't be removed, either.
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(dir.0.join(INDEX_FILE_NAME))
+            .unwrap();
+        log.index_file = Some(Box::new(FailingFile {
+            inner: file,
+            remaining: 5,
+            fail_set_len: true,
+        }));
+
+        // The record is stored and indexed in memory despite the failure
+        log.append(&message(INDEX_INTERVAL + 1)).unwrap();
+        assert_eq!(log.index.len(), 2);
+
+        let (messages, _) = log.read_from(first_pos(&log), 1000).unwrap();
+        assert_eq!(texts(&messages), expected_texts(0..INDEX_INTERVAL + 2));
+
+        // The next index entry causes the index file to be rewritten
+        for i in INDEX_INTERVAL + 2..INDEX_INTERVAL * 2 + 3 {
+            log.append(&message(i)).unwrap();
+        }
+        assert_eq!(log.index.len(), 3);
+        assert!(!log.index_dirty);
+    }
+
+    let index_len = fs::metadata(dir.0.join(INDEX_FILE_NAME)).unwrap().len();
+    assert_eq!(index_len, 3 * INDEX_ENTRY_LEN as u64);
+
+    let log = ChannelLog::open(dir.0.clone()).unwrap();
+    assert_eq!(log.index.len(), 3);
+    let (messages, _) = log.read_before(log.end_pos(), 1000).unwrap();
+    assert_eq!(texts(&messages), expected_texts(0..INDEX_INTERVAL * 2 + 3));
+}
+
+#[test]
+fn escape_file_names() {
+    assert_eq!(escape_file_name("#rust"), "%23rust");
+    assert_eq!(escape_file_name("irc.example.com"), "irc.example.com");
+    assert_eq!(escape_file_name(".."), "%2E.");
+    assert_eq!(escape_file_name("a/b"), "a%2Fb");
+    assert_eq!(escape_file_name(""), "%");
+}
+
+#[test]
+fn crc32_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+}
@@ -12,7 +12,9 @@ use log::debug;
 use tcw3::pal::{self, prelude::*};
 
 mod config;
+mod connector;
 mod crashhandler;
+mod logstore;
 mod model;
 mod stylesheet;
 mod view;
@@ -93,9 +95,13 @@ fn main() {
 
     // Register the application's custom stylesheet
     let style_manager = tcw3::ui::theming::Manager::global(wm);
-    stylesheet::register_stylesheet(style_manager);
+    stylesheet::register_stylesheet(style_manager, profile);
 
-    let _view = self::view::AppView::new(wm, profile);
+    let view = self::view::AppView::new(wm, profile);
+
+    for config in args.irc {
+        self::view::AppView::add_irc_account(&view, config);
+    }
 
     debug!("Entering the main loop");
     wm.enter_main_loop();
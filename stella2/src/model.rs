@@ -1,11 +1,16 @@
 use harmony::{set_field, Elem};
 use miniserde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub main_wnd: Elem<WndState>,
     /// Indicates whether the Preferences window is visible.
     pub pref_visible: bool,
+    pub conv: Elem<ConvState>,
+    pub composer: Elem<ComposerState>,
+    /// The user preferences. They are persisted by `config::settings`.
+    pub settings: Elem<Settings>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,10 +31,167 @@ impl AppState {
                 sidebar_visible: true,
             }),
             pref_visible: false,
+            conv: Elem::new(ConvState::new()),
+            composer: Elem::new(ComposerState::new()),
+            settings: Elem::new(Settings::default()),
         }
     }
 }
 
+/// The user preferences, which are persisted separately from the rest of
+/// the app state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Indicates whether updates are checked automatically.
+    pub check_updates: bool,
+    /// The format of message timestamps.
+    pub time_format: TimeFormat,
+    /// The font size of chat messages, measured in points. The valid range
+    /// is `Settings::FONT_SIZE_MIN..=Settings::FONT_SIZE_MAX`.
+    pub font_size: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// `1:23 PM`
+    H12,
+    /// `13:23`
+    H24,
+}
+
+impl Settings {
+    pub const FONT_SIZE_MIN: u32 = 10;
+    pub const FONT_SIZE_MAX: u32 = 17;
+
+    /// Return `true` if `x` is a valid value for `font_size`.
+    pub fn is_valid_font_size(x: u32) -> bool {
+        (Self::FONT_SIZE_MIN..=Self::FONT_SIZE_MAX).contains(&x)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            check_updates: false,
+            time_format: TimeFormat::H24,
+            font_size: 12,
+        }
+    }
+}
+
+/// The state of the message composing area.
+#[derive(Debug, Clone)]
+pub struct ComposerState {
+    /// The files to be attached to the message being composed.
+    pub attachments: Elem<Vec<PathBuf>>,
+}
+
+impl ComposerState {
+    pub fn new() -> Self {
+        Self {
+            attachments: Elem::new(Vec::new()),
+        }
+    }
+}
+
+/// The conversation data: accounts, servers, channels, and messages.
+#[derive(Debug, Clone)]
+pub struct ConvState {
+    pub accounts: Elem<Vec<Elem<Account>>>,
+    /// The channel displayed in the main window.
+    pub selected_channel: Option<ChannelRef>,
+}
+
+/// Identifies an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AccountId(pub u32);
+
+/// Identifies a channel.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChannelRef {
+    pub account: AccountId,
+    /// The name of the server, which is unique within the account.
+    pub server: String,
+    /// The name of the channel, which is unique within the server.
+    pub channel: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub id: AccountId,
+    /// The display name of the account.
+    pub name: String,
+    pub servers: Elem<Vec<Elem<Server>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Server {
+    pub name: String,
+    pub connection: ConnectionState,
+    pub channels: Elem<Vec<Elem<Channel>>>,
+}
+
+/// The state of the connection to a server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    /// The connection hasn't been established yet.
+    Connecting,
+    Connected,
+    /// The connection was lost. The connector will try to reconnect after
+    /// `retry_in`.
+    Disconnected {
+        reason: String,
+        retry_in: Duration,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub name: String,
+    /// The received messages in the chronological order. New messages are
+    /// appended to the end.
+    pub messages: Elem<Vec<Elem<Message>>>,
+    /// The number of messages received since the channel was read for the
+    /// last time.
+    pub unread_count: usize,
+    /// Indicates whether older messages might be available in the message
+    /// log store.
+    pub has_older_messages: bool,
+    /// Indicates whether older messages are being loaded.
+    pub loading_older_messages: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub sender: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub text: String,
+}
+
+impl ConvState {
+    pub fn new() -> Self {
+        Self {
+            accounts: Elem::new(Vec::new()),
+            selected_channel: None,
+        }
+    }
+
+    /// Find the channel specified by `chref`.
+    pub fn channel(&self, chref: &ChannelRef) -> Option<&Elem<Channel>> {
+        let account = self.accounts.iter().find(|a| a.id == chref.account)?;
+        let server = account.servers.iter().find(|s| s.name == chref.server)?;
+        server.channels.iter().find(|c| c.name == chref.channel)
+    }
+
+    /// Get the channel displayed in the main window.
+    pub fn selected_channel(&self) -> Option<Elem<Channel>> {
+        self.selected_channel
+            .as_ref()
+            .and_then(|chref| self.channel(chref))
+            .cloned()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum AppAction {
     Wnd(WndAction),
@@ -37,6 +199,9 @@ pub enum AppAction {
     HidePref,
     /// Toggles the visibility of the Preferences window.
     TogglePref,
+    Conv(ConvAction),
+    Composer(ComposerAction),
+    Settings(SettingsAction),
 }
 
 #[derive(Debug, Clone)]
@@ -46,6 +211,58 @@ pub enum WndAction {
     ToggleSidebar,
 }
 
+#[derive(Debug, Clone)]
+pub enum SettingsAction {
+    SetCheckUpdates(bool),
+    SetTimeFormat(TimeFormat),
+    /// Sets the font size. The value is clamped to the valid range.
+    SetFontSize(u32),
+}
+
+#[derive(Debug, Clone)]
+pub enum ComposerAction {
+    /// Attaches files to the message being composed. The files already
+    /// attached are ignored.
+    AttachFiles(Vec<PathBuf>),
+    /// Removes a file from the attachments. Does nothing if the file is not
+    /// attached.
+    DetachFile(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub enum ConvAction {
+    /// Adds an account. Does nothing if the account already exists.
+    AddAccount { id: AccountId, name: String },
+    /// Updates the connection state of a server. The server is created if it
+    /// doesn't exist. Does nothing if the account does not exist.
+    SetConnectionState {
+        account: AccountId,
+        server: String,
+        state: ConnectionState,
+    },
+    /// Adds a channel (and a server if needed). Does nothing if the channel
+    /// already exists or the account does not exist.
+    AddChannel(ChannelRef),
+    /// A message was received. The channel is created if it doesn't exist.
+    MessageReceived(ChannelRef, Elem<Message>),
+    /// Selects the channel to display. Marks the channel as read. Does
+    /// nothing if the channel doesn't exist.
+    SelectChannel(ChannelRef),
+    /// Resets the unread count of the channel.
+    MarkAsRead(ChannelRef),
+    /// Requests loading older messages from the message log store. Does
+    /// nothing if there are no more messages or they are already being
+    /// loaded.
+    RequestOlderMessages(ChannelRef),
+    /// Older messages were loaded from the message log store.
+    OlderMessagesLoaded {
+        channel: ChannelRef,
+        /// The loaded messages in the chronological order.
+        messages: Vec<Elem<Message>>,
+        has_more: bool,
+    },
+}
+
 impl AppState {
     pub fn reduce(this: Elem<Self>, action: &AppAction) -> Elem<Self> {
         match action {
@@ -61,6 +278,37 @@ impl AppState {
                 pref_visible: !this.pref_visible,
                 ..this
             },
+            AppAction::Conv(conv_action) => set_field! {
+                conv: ConvState::reduce(Elem::clone(&this.conv), conv_action),
+                ..this
+            },
+            AppAction::Composer(composer_action) => set_field! {
+                composer: ComposerState::reduce(Elem::clone(&this.composer), composer_action),
+                ..this
+            },
+            AppAction::Settings(settings_action) => set_field! {
+                settings: Settings::reduce(Elem::clone(&this.settings), settings_action),
+                ..this
+            },
+        }
+    }
+}
+
+impl Settings {
+    fn reduce(this: Elem<Self>, action: &SettingsAction) -> Elem<Self> {
+        match *action {
+            SettingsAction::SetCheckUpdates(x) => set_field! {
+                check_updates: x,
+                ..this
+            },
+            SettingsAction::SetTimeFormat(x) => set_field! {
+                time_format: x,
+                ..this
+            },
+            SettingsAction::SetFontSize(x) => set_field! {
+                font_size: x.max(Self::FONT_SIZE_MIN).min(Self::FONT_SIZE_MAX),
+                ..this
+            },
         }
     }
 }
@@ -83,3 +331,542 @@ impl WndState {
         }
     }
 }
+
+impl ConvState {
+    fn reduce(this: Elem<Self>, action: &ConvAction) -> Elem<Self> {
+        match action {
+            ConvAction::AddAccount { id, name } => {
+                if this.accounts.iter().any(|a| a.id == *id) {
+                    return this;
+                }
+
+                let account = Elem::new(Account {
+                    id: *id,
+                    name: name.clone(),
+                    servers: Elem::new(Vec::new()),
+                });
+                set_field! {
+                    accounts: vec_push(&this.accounts, account),
+                    ..this
+                }
+            }
+            ConvAction::SetConnectionState {
+                account,
+                server,
+                state,
+            } => set_field! {
+                accounts: Self::update_server(&this.accounts, *account, server, |sv| set_field! {
+                    connection: state.clone(),
+                    ..sv
+                }),
+                ..this
+            },
+            ConvAction::AddChannel(chref) => set_field! {
+                accounts: Self::update_channel(&this.accounts, chref, |ch| ch),
+                ..this
+            },
+            ConvAction::MessageReceived(chref, message) => {
+                let is_selected = this.selected_channel.as_ref() == Some(chref);
+                set_field! {
+                    accounts: Self::update_channel(&this.accounts, chref, |ch| {
+                        Channel::receive(ch, Elem::clone(message), is_selected)
+                    }),
+                    ..this
+                }
+            }
+            ConvAction::SelectChannel(chref) => {
+                if this.channel(chref).is_none() {
+                    return this;
+                }
+                let this = set_field! {
+                    selected_channel: Some(chref.clone()),
+                    ..this
+                };
+                Self::reduce(this, &ConvAction::MarkAsRead(chref.clone()))
+            }
+            ConvAction::MarkAsRead(chref) => {
+                if this.channel(chref).is_none() {
+                    return this;
+                }
+                set_field! {
+                    accounts: Self::update_channel(&this.accounts, chref, |ch| set_field! {
+                        unread_count: 0,
+                        ..ch
+                    }),
+                    ..this
+                }
+            }
+            ConvAction::RequestOlderMessages(chref) => {
+                match this.channel(chref) {
+                    Some(ch) if ch.has_older_messages && !ch.loading_older_messages => {}
+                    _ => return this,
+                }
+                set_field! {
+                    accounts: Self::update_channel(&this.accounts, chref, |ch| set_field! {
+                        loading_older_messages: true,
+                        ..ch
+                    }),
+                    ..this
+                }
+            }
+            ConvAction::OlderMessagesLoaded {
+                channel: chref,
+                messages,
+                has_more,
+            } => {
+                if this.channel(chref).is_none() {
+                    return this;
+                }
+                set_field! {
+                    accounts: Self::update_channel(&this.accounts, chref, |ch| {
+                        Channel::prepend(ch, messages, *has_more)
+                    }),
+                    ..this
+                }
+            }
+        }
+    }
+
+    /// Update the channel specified by `chref` using `f`, creating the channel
+    /// and the server if they don't exist. Does nothing if the account doesn't
+    /// exist.
+    fn update_channel(
+        accounts: &Elem<Vec<Elem<Account>>>,
+        chref: &ChannelRef,
+        f: impl FnOnce(Elem<Channel>) -> Elem<Channel>,
+    ) -> Elem<Vec<Elem<Account>>> {
+        Self::update_server(accounts, chref.account, &chref.server, |server| {
+            Server::update_channel(server, chref, f)
+        })
+    }
+
+    /// Update the server specified by `account` and `server_name` using `f`,
+    /// creating the server if it doesn't exist. Does nothing if the account
+    /// doesn't exist.
+    fn update_server(
+        accounts: &Elem<Vec<Elem<Account>>>,
+        account: AccountId,
+        server_name: &str,
+        f: impl FnOnce(Elem<Server>) -> Elem<Server>,
+    ) -> Elem<Vec<Elem<Account>>> {
+        let account_i = if let Some(i) = accounts.iter().position(|a| a.id == account) {
+            i
+        } else {
+            log::warn!("Unknown account: {:?}", account);
+            return Elem::clone(accounts);
+        };
+
+        vec_update(accounts, account_i, |account| {
+            let servers = &account.servers;
+            let servers = match servers.iter().position(|s| s.name == server_name) {
+                Some(i) => vec_update(servers, i, f),
+                None => {
+                    let server = Elem::new(Server {
+                        name: server_name.to_owned(),
+                        connection: ConnectionState::Connecting,
+                        channels: Elem::new(Vec::new()),
+                    });
+                    vec_push(servers, f(server))
+                }
+            };
+            set_field! {
+                servers: servers,
+                ..account
+            }
+        })
+    }
+}
+
+impl Server {
+    fn update_channel(
+        this: Elem<Self>,
+        chref: &ChannelRef,
+        f: impl FnOnce(Elem<Channel>) -> Elem<Channel>,
+    ) -> Elem<Self> {
+        let channels = &this.channels;
+        let channels = match channels.iter().position(|c| c.name == chref.channel) {
+            Some(i) => vec_update(channels, i, f),
+            None => {
+                let channel = Elem::new(Channel {
+                    name: chref.channel.clone(),
+                    messages: Elem::new(Vec::new()),
+                    unread_count: 0,
+                    has_older_messages: true,
+                    loading_older_messages: false,
+                });
+                vec_push(channels, f(channel))
+            }
+        };
+        set_field! {
+            channels: channels,
+            ..this
+        }
+    }
+}
+
+impl Channel {
+    fn receive(this: Elem<Self>, message: Elem<Message>, is_selected: bool) -> Elem<Self> {
+        // This clones the message list. It only consists of `Elem`s, so
+        // it should be fast enough for the amount of messages we keep in
+        // memory.
+        set_field! {
+            messages: vec_push(&this.messages, message),
+            unread_count: if is_selected { 0 } else { this.unread_count + 1 },
+            ..this
+        }
+    }
+
+    fn prepend(this: Elem<Self>, messages: &[Elem<Message>], has_more: bool) -> Elem<Self> {
+        let mut new_messages = Vec::with_capacity(messages.len() + this.messages.len());
+        new_messages.extend(messages.iter().cloned());
+        new_messages.extend(this.messages.iter().cloned());
+
+        set_field! {
+            messages: Elem::new(new_messages),
+            has_older_messages: has_more,
+            loading_older_messages: false,
+            ..this
+        }
+    }
+}
+
+impl ComposerState {
+    fn reduce(this: Elem<Self>, action: &ComposerAction) -> Elem<Self> {
+        match action {
+            ComposerAction::AttachFiles(files) => {
+                let mut attachments = (*this.attachments).clone();
+                for file in files.iter() {
+                    if !attachments.contains(file) {
+                        attachments.push(file.clone());
+                    }
+                }
+
+                if attachments.len() == this.attachments.len() {
+                    return this;
+                }
+
+                set_field! {
+                    attachments: Elem::new(attachments),
+                    ..this
+                }
+            }
+            ComposerAction::DetachFile(file) => {
+                if !this.attachments.contains(file) {
+                    return this;
+                }
+
+                let attachments = this
+                    .attachments
+                    .iter()
+                    .filter(|f| *f != file)
+                    .cloned()
+                    .collect();
+
+                set_field! {
+                    attachments: Elem::new(attachments),
+                    ..this
+                }
+            }
+        }
+    }
+}
+
+/// Create a copy of `vec` with `x` appended.
+fn vec_push<T>(vec: &Elem<Vec<Elem<T>>>, x: Elem<T>) -> Elem<Vec<Elem<T>>> {
+    let mut new_vec = Vec::with_capacity(vec.len() + 1);
+    new_vec.extend(vec.iter().cloned());
+    new_vec.push(x);
+    Elem::new(new_vec)
+}
+
+/// Create a copy of `vec` with the `i`-th element updated by `f`. Returns
+/// `vec` as-is if `f` returns the original element.
+fn vec_update<T>(
+    vec: &Elem<Vec<Elem<T>>>,
+    i: usize,
+    f: impl FnOnce(Elem<T>) -> Elem<T>,
+) -> Elem<Vec<Elem<T>>> {
+    let new_x = f(Elem::clone(&vec[i]));
+    if Elem::ptr_eq(&new_x, &vec[i]) {
+        return Elem::clone(vec);
+    }
+
+    let mut new_vec: Vec<_> = vec.iter().cloned().collect();
+    new_vec[i] = new_x;
+    Elem::new(new_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chref(channel: &str) -> ChannelRef {
+        ChannelRef {
+            account: AccountId(1),
+            server: "irc.example.com".to_owned(),
+            channel: channel.to_owned(),
+        }
+    }
+
+    fn message(text: &str) -> Elem<Message> {
+        Elem::new(Message {
+            sender: "alice".to_owned(),
+            timestamp: chrono::Utc::now(),
+            text: text.to_owned(),
+        })
+    }
+
+    fn texts(channel: &Channel) -> Vec<&str> {
+        channel.messages.iter().map(|m| &m.text[..]).collect()
+    }
+
+    fn conv_with_account() -> Elem<ConvState> {
+        ConvState::reduce(
+            Elem::new(ConvState::new()),
+            &ConvAction::AddAccount {
+                id: AccountId(1),
+                name: "alice".to_owned(),
+            },
+        )
+    }
+
+    #[test]
+    fn add_account() {
+        let conv = conv_with_account();
+        assert_eq!(conv.accounts.len(), 1);
+        assert_eq!(conv.accounts[0].name, "alice");
+
+        // The account already exists
+        let conv2 = ConvState::reduce(
+            Elem::clone(&conv),
+            &ConvAction::AddAccount {
+                id: AccountId(1),
+                name: "bob".to_owned(),
+            },
+        );
+        assert!(Elem::ptr_eq(&conv, &conv2));
+    }
+
+    #[test]
+    fn set_connection_state() {
+        let conv = conv_with_account();
+        let set_state = |conv, state| {
+            ConvState::reduce(
+                conv,
+                &ConvAction::SetConnectionState {
+                    account: AccountId(1),
+                    server: "irc.example.com".to_owned(),
+                    state,
+                },
+            )
+        };
+
+        // The server is created if it doesn't exist
+        let conv = set_state(conv, ConnectionState::Connected);
+        assert_eq!(conv.accounts[0].servers.len(), 1);
+        assert_eq!(
+            conv.accounts[0].servers[0].connection,
+            ConnectionState::Connected
+        );
+
+        // Channels are added to the existing server
+        let conv = ConvState::reduce(conv, &ConvAction::AddChannel(chref("#a")));
+        assert_eq!(conv.accounts[0].servers.len(), 1);
+
+        let disconnected = ConnectionState::Disconnected {
+            reason: "connection reset".to_owned(),
+            retry_in: Duration::from_secs(2),
+        };
+        let conv = set_state(conv, disconnected.clone());
+        assert_eq!(conv.accounts[0].servers[0].connection, disconnected);
+        assert!(conv.channel(&chref("#a")).is_some());
+
+        // The state didn't change
+        let conv2 = set_state(Elem::clone(&conv), disconnected);
+        assert!(Elem::ptr_eq(&conv.accounts, &conv2.accounts));
+    }
+
+    #[test]
+    fn add_channel() {
+        let conv = conv_with_account();
+        let conv = ConvState::reduce(conv, &ConvAction::AddChannel(chref("#a")));
+        let conv = ConvState::reduce(conv, &ConvAction::AddChannel(chref("#b")));
+
+        // The server is created only once
+        assert_eq!(conv.accounts[0].servers.len(), 1);
+        assert_eq!(conv.accounts[0].servers[0].channels.len(), 2);
+        assert!(conv.channel(&chref("#a")).is_some());
+        assert!(conv.channel(&chref("#b")).is_some());
+
+        // The channel already exists
+        let conv2 = ConvState::reduce(Elem::clone(&conv), &ConvAction::AddChannel(chref("#a")));
+        assert!(Elem::ptr_eq(&conv.accounts, &conv2.accounts));
+
+        // The account doesn't exist
+        let unknown = ChannelRef {
+            account: AccountId(2),
+            ..chref("#a")
+        };
+        let conv2 = ConvState::reduce(Elem::clone(&conv), &ConvAction::AddChannel(unknown));
+        assert!(Elem::ptr_eq(&conv.accounts, &conv2.accounts));
+    }
+
+    #[test]
+    fn message_received() {
+        let conv = conv_with_account();
+        let conv = ConvState::reduce(conv, &ConvAction::AddChannel(chref("#a")));
+        let conv = ConvState::reduce(conv, &ConvAction::SelectChannel(chref("#a")));
+
+        let conv = ConvState::reduce(
+            conv,
+            &ConvAction::MessageReceived(chref("#a"), message("one")),
+        );
+        // The channel is created on demand
+        let conv = ConvState::reduce(
+            conv,
+            &ConvAction::MessageReceived(chref("#b"), message("two")),
+        );
+        let conv = ConvState::reduce(
+            conv,
+            &ConvAction::MessageReceived(chref("#b"), message("three")),
+        );
+
+        let ch_a = conv.channel(&chref("#a")).unwrap();
+        let ch_b = conv.channel(&chref("#b")).unwrap();
+        assert_eq!(texts(ch_a), ["one"]);
+        assert_eq!(texts(ch_b), ["two", "three"]);
+
+        // Messages in the selected channel are not counted as unread
+        assert_eq!(ch_a.unread_count, 0);
+        assert_eq!(ch_b.unread_count, 2);
+
+        // Selecting the channel marks it as read
+        let conv = ConvState::reduce(conv, &ConvAction::SelectChannel(chref("#b")));
+        assert_eq!(conv.channel(&chref("#b")).unwrap().unread_count, 0);
+        assert_eq!(conv.selected_channel().unwrap().name, "#b");
+    }
+
+    #[test]
+    fn select_unknown_channel() {
+        let conv = conv_with_account();
+        let conv = ConvState::reduce(conv, &ConvAction::AddChannel(chref("#a")));
+        let conv = ConvState::reduce(conv, &ConvAction::SelectChannel(chref("#a")));
+
+        // The selection is unchanged
+        let conv2 = ConvState::reduce(Elem::clone(&conv), &ConvAction::SelectChannel(chref("#b")));
+        assert!(Elem::ptr_eq(&conv, &conv2));
+        assert_eq!(conv2.selected_channel, Some(chref("#a")));
+        assert!(conv2.channel(&chref("#b")).is_none());
+    }
+
+    #[test]
+    fn mark_unknown_channel_as_read() {
+        let conv = conv_with_account();
+        let conv2 = ConvState::reduce(Elem::clone(&conv), &ConvAction::MarkAsRead(chref("#a")));
+        assert!(Elem::ptr_eq(&conv.accounts, &conv2.accounts));
+        assert!(conv2.channel(&chref("#a")).is_none());
+    }
+
+    #[test]
+    fn older_messages() {
+        let conv = conv_with_account();
+        let conv = ConvState::reduce(
+            conv,
+            &ConvAction::MessageReceived(chref("#a"), message("three")),
+        );
+
+        let conv = ConvState::reduce(conv, &ConvAction::RequestOlderMessages(chref("#a")));
+        assert!(conv.channel(&chref("#a")).unwrap().loading_older_messages);
+
+        // The messages are already being loaded
+        let conv2 = ConvState::reduce(
+            Elem::clone(&conv),
+            &ConvAction::RequestOlderMessages(chref("#a")),
+        );
+        assert!(Elem::ptr_eq(&conv.accounts, &conv2.accounts));
+
+        let conv = ConvState::reduce(
+            conv,
+            &ConvAction::OlderMessagesLoaded {
+                channel: chref("#a"),
+                messages: vec![message("one"), message("two")],
+                has_more: false,
+            },
+        );
+        let ch = conv.channel(&chref("#a")).unwrap();
+        assert_eq!(texts(ch), ["one", "two", "three"]);
+        assert!(!ch.loading_older_messages);
+        assert!(!ch.has_older_messages);
+
+        // There are no more messages
+        let conv2 = ConvState::reduce(
+            Elem::clone(&conv),
+            &ConvAction::RequestOlderMessages(chref("#a")),
+        );
+        assert!(Elem::ptr_eq(&conv.accounts, &conv2.accounts));
+    }
+
+    #[test]
+    fn attach_files() {
+        let composer = ComposerState::reduce(
+            Elem::new(ComposerState::new()),
+            &ComposerAction::AttachFiles(vec!["a.png".into(), "b.png".into(), "a.png".into()]),
+        );
+        assert_eq!(
+            *composer.attachments,
+            [PathBuf::from("a.png"), "b.png".into()]
+        );
+
+        // Files already attached are ignored
+        let composer = ComposerState::reduce(
+            composer,
+            &ComposerAction::AttachFiles(vec!["b.png".into(), "c.png".into()]),
+        );
+        assert_eq!(
+            *composer.attachments,
+            [PathBuf::from("a.png"), "b.png".into(), "c.png".into()]
+        );
+
+        // Nothing has changed
+        let composer2 = ComposerState::reduce(
+            Elem::clone(&composer),
+            &ComposerAction::AttachFiles(vec!["a.png".into()]),
+        );
+        assert!(Elem::ptr_eq(&composer, &composer2));
+    }
+
+    #[test]
+    fn detach_file() {
+        let composer = ComposerState::reduce(
+            Elem::new(ComposerState::new()),
+            &ComposerAction::AttachFiles(vec!["a.png".into(), "b.png".into()]),
+        );
+
+        let composer = ComposerState::reduce(composer, &ComposerAction::DetachFile("a.png".into()));
+        assert_eq!(*composer.attachments, [PathBuf::from("b.png")]);
+
+        // The file is not attached
+        let composer2 = ComposerState::reduce(
+            Elem::clone(&composer),
+            &ComposerAction::DetachFile("a.png".into()),
+        );
+        assert!(Elem::ptr_eq(&composer, &composer2));
+    }
+
+    #[test]
+    fn clamp_font_size() {
+        let font_size = |x| {
+            let settings = Settings::reduce(
+                Elem::new(Settings::default()),
+                &SettingsAction::SetFontSize(x),
+            );
+            settings.font_size
+        };
+
+        assert_eq!(font_size(14), 14);
+        assert_eq!(font_size(Settings::FONT_SIZE_MIN), Settings::FONT_SIZE_MIN);
+        assert_eq!(font_size(Settings::FONT_SIZE_MAX), Settings::FONT_SIZE_MAX);
+        assert_eq!(font_size(0), Settings::FONT_SIZE_MIN);
+        assert_eq!(font_size(u32::max_value()), Settings::FONT_SIZE_MAX);
+    }
+}
@@ -5,15 +5,20 @@ use stella2_assets as assets;
 #[allow(unused_imports)]
 use tcw3::{
     images::{himg_figures, HImg},
-    pal::{LayerFlags, SysFontType, RGBAF32},
+    pal::{Appearance, LayerFlags, SysFontType, RGBAF32},
     stvg::StvgImg,
     stylesheet,
     ui::{
-        theming::{roles, LayerXform, Layouter, Manager, Metrics, Stylesheet},
+        theming::{
+            roles, LayerXform, Layouter, Manager, Metrics, Stylesheet, TextStylesheet,
+            TextStylesheetSymbols,
+        },
         AlignFlags,
     },
 };
 
+use crate::config::profile::Profile;
+
 /// Define styling ID values.
 pub mod elem_id {
     use tcw3::ui::theming::ClassSet;
@@ -40,6 +45,7 @@ pub mod elem_id {
                 , SIDEBAR_GROUP_HEADER
                 , SIDEBAR_GROUP_BULLET
                 , SIDEBAR_ITEM
+                , SIDEBAR_UNREAD_BADGE
 
                 , TABBAR
                 , TABBAR_TAB
@@ -92,6 +98,7 @@ pub mod my_roles {
     iota::iota! {
         pub const BULLET: Role = iota + 1;
                 , CLOSE
+                , BADGE
     }
 }
 
@@ -118,6 +125,16 @@ fn himg_tab_icon_act(data: &(&'static [u8], [f32; 2])) -> HImg {
     himg_from_stvg_col(*data, [0.2, 0.5, 0.9, 1.0].into())
 }
 
+/// Construct a `HImg` for a tab icon in the dark appearance.
+fn himg_tab_icon_dark(data: &(&'static [u8], [f32; 2])) -> HImg {
+    himg_from_stvg_col(*data, [1.0, 1.0, 1.0, 1.0].into())
+}
+
+/// Construct a `HImg` for a toolbar icon in the dark appearance.
+fn himg_toolbar_icon_dark(data: &(&'static [u8], [f32; 2])) -> HImg {
+    himg_from_stvg_col(*data, [0.85, 0.85, 0.85, 1.0].into())
+}
+
 fn new_custom_stylesheet() -> impl Stylesheet {
     const TOOLBAR_IMG_SIZE: Vector2<f32> = Vector2::new(24.0, 16.0);
     const TOOLBAR_IMG_METRICS: Metrics = Metrics {
@@ -538,6 +555,11 @@ fn new_custom_stylesheet() -> impl Stylesheet {
                 margin: [NAN, NAN, NAN, 25.0],
                 ..Metrics::default()
             },
+            // unread count
+            subview_metrics[my_roles::BADGE]: Metrics {
+                margin: [NAN, 8.0, NAN, NAN],
+                ..Metrics::default()
+            },
         },
         ([#SIDEBAR_ITEM.ACTIVE]) (priority = 10000) {
             num_layers: 1,
@@ -552,6 +574,9 @@ fn new_custom_stylesheet() -> impl Stylesheet {
         ([.LABEL] < [#SIDEBAR_ITEM.ACTIVE]) (priority = 10000) {
             fg_color: RGBAF32::new(1.0, 1.0, 1.0, 1.0),
         },
+        ([#SIDEBAR_UNREAD_BADGE]) (priority = 10000) {
+            font: SysFontType::Emph,
+        },
 
         // -------------------------------------------------------------------
         // "Preferences" window
@@ -787,6 +812,205 @@ fn new_custom_stylesheet() -> impl Stylesheet {
     }
 }
 
+/// Overrides the colors of `new_custom_stylesheet` and
+/// `new_custom_platform_stylesheet` for the dark appearance, the same way
+/// tcw3's default dark stylesheet overrides its default stylesheet.
+fn new_custom_dark_stylesheet() -> impl Stylesheet {
+    const FIELD_BG_COLOR: RGBAF32 = RGBAF32::new(0.11, 0.11, 0.11, 1.0);
+
+    stylesheet! {
+        ([#SPLITTER]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.3, 0.3, 0.3, 1.0),
+        },
+
+        // Toolbar and titlebar background
+        ([#TOOLBAR]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.22, 0.22, 0.22, 1.0),
+            layer_bg_color[1]: RGBAF32::new(0.0, 0.0, 0.0, 0.5),
+        },
+        ([#TOOLBAR] .. [#WND.ACTIVE]) (priority = 10500) {
+            layer_bg_color[0]: RGBAF32::new(0.18, 0.18, 0.18, 1.0),
+        },
+
+        // Pane background
+        ([#SIDEBAR]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 1.0),
+        },
+        ([#TABBAR]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 1.0),
+            layer_bg_color[1]: RGBAF32::new(0.0, 0.0, 0.0, 0.4),
+        },
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        ([#SIDEBAR] .. [#WND.ACTIVE]) (priority = 10500) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 0.8),
+        },
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        ([#TABBAR] .. [#WND.ACTIVE]) (priority = 10500) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 0.8),
+        },
+
+        ([#LOG_VIEW]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.11, 0.11, 0.11, 1.0),
+        },
+        ([#EDITOR]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 1.0),
+        },
+
+        // Toolbar buttons
+        ([#SHOW_MENU.BUTTON]) (priority = 10000) {
+            #[dyn] layer_img[2]: Some(himg_toolbar_icon_dark(&assets::toolbar::MENU)),
+        },
+        ([#SIDEBAR_SHOW.BUTTON]) (priority = 10000) {
+            #[dyn] layer_img[2]: Some(himg_toolbar_icon_dark(&assets::toolbar::SIDEBAR_SHOW)),
+        },
+        ([#SIDEBAR_HIDE.BUTTON]) (priority = 10000) {
+            #[dyn] layer_img[2]: Some(himg_toolbar_icon_dark(&assets::toolbar::SIDEBAR_HIDE)),
+        },
+
+        // Toolbar presentation elements
+        ([#MEMBER_COUNT_ICON]) (priority = 10000) {
+            #[dyn] layer_img[0]: Some(himg_toolbar_icon_dark(&assets::toolbar::USER_OUTLINE)),
+        },
+        ([#TOOLBAR_SEPARATOR]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.4, 0.4, 0.4, 1.0),
+        },
+
+        // Tabbar
+        ([#TABBAR_TAB]) (priority = 10000) {
+            layer_bg_color[1]: RGBAF32::new(0.22, 0.22, 0.22, 1.0),
+        },
+        ([#TABBAR_TAB] .. [#WND.ACTIVE]) (priority = 10500) {
+            layer_bg_color[1]: RGBAF32::new(0.18, 0.18, 0.18, 1.0),
+        },
+        ([#TABBAR_TAB.HOVER:not(.ACTIVE)]) (priority = 10500) {
+            layer_bg_color[1]: RGBAF32::new(1.0, 1.0, 1.0, 0.1),
+        },
+        ([#TABBAR_TAB_CLOSE]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(1.0, 1.0, 1.0, 1.0),
+            #[dyn] layer_img[1]: Some(
+                himg_from_stvg_col(assets::CLOSE, [0.75, 0.75, 0.75, 1.0].into()),
+            ),
+        },
+        #[cfg(not(target_os = "macos"))]
+        ([#TABBAR_CLOSE]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(
+                himg_from_stvg_col(assets::CLOSE, [0.75, 0.75, 0.75, 1.0].into()),
+            ),
+        },
+
+        // Search field
+        ([#SEARCH_FIELD]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(himg_figures![
+                rect([1.0, 1.0, 1.0, 0.05]).radius(3.0),
+                rect([1.0, 1.0, 1.0, 0.15]).radius(3.0 - 0.25).margin([0.25; 4]).line_width(0.5),
+            ]),
+            #[dyn] layer_img[2]: Some(himg_from_stvg_col(assets::SEARCH, [0.6, 0.6, 0.6, 1.0].into())),
+        },
+        ([#SEARCH_FIELD.FOCUS]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(himg_figures![rect(FIELD_BG_COLOR).radius(3.0)]),
+        },
+
+        // Composing area
+        ([#EDITOR_FIELD]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(himg_figures![rect(FIELD_BG_COLOR).radius(3.0)]),
+        },
+        ([.LABEL] < [#EDITOR_FIELD]) (priority = 10000) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.4),
+        },
+
+        // Sidebar
+        ([.LABEL] < [#SIDEBAR_GROUP_HEADER]) (priority = 10000) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.4),
+        },
+        ([#SIDEBAR_GROUP_BULLET]) (priority = 10000) {
+            #[dyn] layer_img[0]: Some(himg_toolbar_icon_dark(&assets::LIST_GROUP_OPEN)),
+        },
+        ([#SIDEBAR_ITEM.ACTIVE]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(1.0, 1.0, 1.0, 0.15),
+        },
+        ([#SIDEBAR_ITEM.ACTIVE] .. [#WND.ACTIVE]) (priority = 10500) {
+            layer_bg_color[0]: RGBAF32::new(1.0, 1.0, 1.0, 0.25),
+        },
+
+        // -------------------------------------------------------------------
+        // "Preferences" window
+        ([#PREF_HEADER]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 1.0),
+        },
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        ([#PREF_HEADER] .. [#WND.ACTIVE]) (priority = 10500) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 0.8),
+        },
+        ([#PREF_MAIN]) (priority = 10000) {
+            layer_bg_color[0]: RGBAF32::new(0.2, 0.2, 0.2, 1.0),
+        },
+
+        // All tabs
+        ([] < [#PREF_TAB_BAR] .. [#WND:not(.ACTIVE)]) (priority = 10400) {
+            layer_bg_color[0]: RGBAF32::new(1.0, 1.0, 1.0, 0.5),
+        },
+        ([.LABEL] .. [] < [#PREF_TAB_BAR]) (priority = 10000) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.6),
+        },
+        ([.LABEL] .. [.HOVER] < [#PREF_TAB_BAR]) (priority = 10100) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.8),
+        },
+        ([.LABEL] .. [.ACTIVE] < [#PREF_TAB_BAR]) (priority = 10200) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 1.0),
+        },
+        ([.LABEL] .. [] < [#PREF_TAB_BAR] .. [#WND:not(.ACTIVE)]) (priority = 10400) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.4),
+        },
+        ([.LABEL] .. [.CHECKED] < [#PREF_TAB_BAR] .. [#WND:not(.ACTIVE)]) (priority = 10400) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.6),
+        },
+
+        // Individual tabs
+        ([#PREF_TAB_GENERAL]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(himg_tab_icon_dark(&assets::pref::TAB_GENERAL)),
+        },
+        ([#PREF_TAB_ACCOUNTS]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(himg_tab_icon_dark(&assets::pref::TAB_ACCOUNTS)),
+        },
+        ([#PREF_TAB_CONNECTION]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(himg_tab_icon_dark(&assets::pref::TAB_CONNECTION)),
+        },
+        ([#PREF_TAB_ADVANCED]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(himg_tab_icon_dark(&assets::pref::TAB_ADVANCED)),
+        },
+        ([#PREF_TAB_ABOUT]) (priority = 10000) {
+            #[dyn] layer_img[1]: Some(himg_tab_icon_dark(&assets::pref::TAB_ABOUT)),
+        },
+
+        // Platform-specific rules
+        #[cfg(target_os = "windows")]
+        ([#WND]) (priority = 20000) {
+            layer_bg_color[0]: RGBAF32::new(0.3, 0.3, 0.3, 1.0),
+            layer_bg_color[1]: RGBAF32::new(0.3, 0.3, 0.3, 1.0),
+            layer_bg_color[2]: RGBAF32::new(0.3, 0.3, 0.3, 1.0),
+            layer_bg_color[3]: RGBAF32::new(0.3, 0.3, 0.3, 1.0),
+        },
+        #[cfg(target_os = "windows")]
+        ([#WND.ACTIVE]) (priority = 20500) {
+            layer_bg_color[0]: RGBAF32::new(0.45, 0.45, 0.45, 1.0),
+            layer_bg_color[1]: RGBAF32::new(0.45, 0.45, 0.45, 1.0),
+            layer_bg_color[2]: RGBAF32::new(0.45, 0.45, 0.45, 1.0),
+            layer_bg_color[3]: RGBAF32::new(0.45, 0.45, 0.45, 1.0),
+        },
+        #[cfg(target_os = "windows")]
+        ([#SEARCH_FIELD]) (priority = 20000) {
+            #[dyn] layer_img[1]: Some(himg_figures![
+                rect([1.0, 1.0, 1.0, 0.05]),
+                rect([1.0, 1.0, 1.0, 0.15]).margin([0.25; 4]).line_width(0.5),
+            ]),
+        },
+        #[cfg(target_os = "windows")]
+        ([#SEARCH_FIELD.FOCUS]) (priority = 20000) {
+            layer_bg_color[1]: FIELD_BG_COLOR,
+        },
+    }
+}
+
 #[cfg(target_os = "windows")]
 fn new_custom_platform_stylesheet() -> impl Stylesheet {
     const BORDER_COLOR: RGBAF32 = RGBAF32::new(0.7, 0.7, 0.7, 1.0);
@@ -854,10 +1078,75 @@ fn new_custom_platform_stylesheet() -> impl Stylesheet {
 
 pub const ENABLE_BACKDROP_BLUR: bool = cfg!(any(target_os = "windows", target_os = "macos"));
 
-pub fn register_stylesheet(manager: &'static Manager) {
+/// The file name of the user stylesheet, which is loaded from the profile's
+/// data directory. See [`TextStylesheet`] for the syntax.
+const USER_STYLESHEET_FILE_NAME: &str = "stylesheet.txt";
+
+pub fn register_stylesheet(manager: &'static Manager, profile: &Profile) {
+    let user_stylesheet = load_user_stylesheet(profile);
+
     manager.subscribe_new_sheet_set(Box::new(move |_, _, ctx| {
         ctx.insert_stylesheet(new_custom_stylesheet());
         ctx.insert_stylesheet(new_custom_platform_stylesheet());
+        if ctx.appearance() == Appearance::Dark {
+            ctx.insert_stylesheet(new_custom_dark_stylesheet());
+        }
+        if let Some(user_stylesheet) = &user_stylesheet {
+            ctx.insert_stylesheet(user_stylesheet.clone());
+        }
     }));
     manager.update_sheet_set();
 }
+
+/// Load the user stylesheet. Returns `None` if it does not exist or could not
+/// be loaded.
+fn load_user_stylesheet(profile: &Profile) -> Option<TextStylesheet> {
+    let path = profile.data_dir().join(USER_STYLESHEET_FILE_NAME);
+
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            log::warn!("Could not read the user stylesheet {:?}: {}", path, e);
+            return None;
+        }
+    };
+
+    match TextStylesheet::parse(&source, &user_stylesheet_symbols()) {
+        Ok(stylesheet) => {
+            log::info!("Loaded the user stylesheet {:?}", path);
+            Some(stylesheet)
+        }
+        Err(e) => {
+            log::error!("Could not parse the user stylesheet {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Make the application-defined IDs and roles available to the user
+/// stylesheet.
+fn user_stylesheet_symbols() -> TextStylesheetSymbols {
+    macro_rules! define {
+        ($symbols:expr, $method:ident, $module:ident::{$($name:ident),* $(,)*}) => {
+            $symbols$(.$method(stringify!($name), $module::$name))*
+        };
+    }
+
+    let symbols = define!(
+        TextStylesheetSymbols::new(),
+        with_id,
+        elem_id::{
+            SHOW_MENU, SIDEBAR_SHOW, SIDEBAR_HIDE, SEARCH_FIELD_WRAP, SEARCH_FIELD,
+            TOOLBAR_SEPARATOR, MEMBER_COUNT_ICON, TOOLBAR, SIDEBAR, CENTRAL, LOG_VIEW, EDITOR,
+            EDITOR_SPLIT, EDITOR_FIELD, SIDEBAR_GROUP_HEADER, SIDEBAR_GROUP_BULLET,
+            SIDEBAR_ITEM, SIDEBAR_UNREAD_BADGE, TABBAR, TABBAR_TAB, TABBAR_TAB_CLOSE,
+            TABBAR_CLOSE, PREF, PREF_HEADER, PREF_TITLE_WRAP, PREF_MAIN, PREF_TAB_BAR,
+            PREF_TAB_GENERAL, PREF_TAB_ACCOUNTS, PREF_TAB_CONNECTION, PREF_TAB_ADVANCED,
+            PREF_TAB_ABOUT, PREF_GROUP_CAPTION, PREF_GROUP, PREF_CONTENT_GENERAL,
+            PREF_GENERAL_FONT_SIZE, WND, STACK_HORZ_LEFT_TOP, STACK_HORZ_LEFT_VCENTER,
+        }
+    );
+
+    define!(symbols, with_role, my_roles::{BULLET, CLOSE, BADGE})
+}
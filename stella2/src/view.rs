@@ -1,4 +1,4 @@
-use harmony::Elem;
+use harmony::{set_field, Elem};
 use log::trace;
 use std::{
     cell::{Cell, RefCell},
@@ -6,27 +6,36 @@ use std::{
 };
 use tcw3::{
     pal,
-    pal::prelude::*,
+    pal::{prelude::*, MtSticky},
     ui::layouts::FillLayout,
     ui::theming,
+    ui::views::MessageBox,
     uicore::{ActionId, ActionStatus, HWnd, HWndRef, WndListener, WndStyleFlags},
 };
 
 use crate::{
-    config::{profile::Profile, viewpersistence},
+    config::{profile::Profile, settings, viewpersistence},
+    connector::{self, irc, Connector},
+    logstore::{LogStore, LogStoreWorker},
     model, stylesheet,
 };
 
 mod channellist;
+mod dndutils;
 mod dpiscalewatcher;
 mod global;
+mod listdiff;
 mod logview;
+mod notifier;
 mod prefwnd;
 mod radiolist;
 mod splitutils;
 mod tabbar;
 mod toolbar;
 
+#[cfg(test)]
+mod testutils;
+
 pub struct AppView {
     wm: pal::Wm,
     profile: &'static Profile,
@@ -34,49 +43,145 @@ pub struct AppView {
     pending_actions: RefCell<Vec<model::AppAction>>,
     persist_sched: viewpersistence::PersistenceScheduler,
     main_wnd: Rc<WndView>,
+    notifier: notifier::Notifier,
+    error_box: RefCell<Option<MessageBox>>,
     pref_wnd: Cell<Option<Rc<prefwnd::PrefWndView>>>,
+    connectors: RefCell<Vec<Box<dyn Connector>>>,
+    next_account_id: Cell<u32>,
+    log_store: LogStoreWorker,
 }
 
+/// The maximum number of messages loaded from the message log store at once.
+const HISTORY_PAGE_SIZE: usize = 100;
+
 impl AppView {
     pub fn new(wm: pal::Wm, profile: &'static Profile) -> Rc<Self> {
         let mut state = Elem::new(model::AppState::new());
 
         // Restore the app state from the user profile
-        state = viewpersistence::restore_state(profile, state);
+        let (new_state, restore_error) = viewpersistence::restore_state(profile, state);
+        state = new_state;
+
+        // Load the user preferences
+        state = set_field! {
+            settings: Elem::new(settings::load_settings(profile)),
+            ..state
+        };
 
         let persist_sched = viewpersistence::PersistenceScheduler::new(&state);
 
+        global::init_key_bindings(wm, profile);
         global::set_main_menu(wm);
 
-        let main_wnd = WndView::new(wm, Elem::clone(&state.main_wnd));
+        let main_wnd = WndView::new(
+            wm,
+            Elem::clone(&state.main_wnd),
+            Elem::clone(&state.conv),
+            Elem::clone(&state.composer),
+            Elem::clone(&state.settings),
+        );
 
         let this = Rc::new(Self {
             wm,
             profile,
             main_wnd,
+            notifier: notifier::Notifier::new(wm),
+            error_box: RefCell::new(None),
             state: RefCell::new(state),
             pending_actions: RefCell::new(Vec::new()),
             persist_sched,
             pref_wnd: Cell::new(None),
+            connectors: RefCell::new(Vec::new()),
+            next_account_id: Cell::new(0),
+            log_store: LogStoreWorker::new(LogStore::new(profile)),
         });
 
         let this_weak = Rc::downgrade(&this);
         this.main_wnd
             .set_dispatch(move |app_action| Self::dispatch_weak(&this_weak, app_action));
 
+        let this_weak = Rc::downgrade(&this);
+        this.notifier.set_on_activate(move |chref| {
+            Self::dispatch_weak(
+                &this_weak,
+                model::AppAction::Conv(model::ConvAction::SelectChannel(chref)),
+            )
+        });
+
         let this_weak = Rc::downgrade(&this);
         this.main_wnd.set_quit(move || {
-            // Persist the state to disk before quitting
+            // Persist the state and the message logs to disk before quitting
             if let Some(this) = this_weak.upgrade() {
                 this.persist_sched.flush(wm, &this.state.borrow(), profile);
+                this.log_store.flush();
             }
 
             wm.terminate();
         });
 
+        if let Some(e) = restore_error {
+            Self::show_error(&this, format!("Could not restore the window layout: {}", e));
+        }
+
         this
     }
 
+    /// Display an error message in a message box owned by the main window.
+    fn show_error(this: &Rc<Self>, message: String) {
+        let mbox = MessageBox::new(this.wm, theming::Manager::global(this.wm));
+        mbox.set_caption("Stella 2");
+        mbox.set_message(message);
+        mbox.set_buttons(vec!["OK"]);
+        mbox.set_default_button(Some(0));
+        mbox.set_cancel_button(Some(0));
+
+        let this_weak = Rc::downgrade(this);
+        mbox.subscribe_dismissed(Box::new(move |_, _| {
+            if let Some(this) = this_weak.upgrade() {
+                this.error_box.replace(None);
+            }
+        }));
+
+        mbox.show(Some(this.main_wnd.hwnd.clone()));
+
+        // Replacing the old message box (if any) closes it
+        this.error_box.replace(Some(mbox));
+    }
+
+    /// Add an account and connect to the specified IRC server.
+    pub fn add_irc_account(this: &Rc<Self>, config: irc::IrcConfig) {
+        let account = model::AccountId(this.next_account_id.get());
+        this.next_account_id.set(account.0 + 1);
+
+        Self::dispatch(
+            this,
+            model::AppAction::Conv(model::ConvAction::AddAccount {
+                id: account,
+                name: config.nick.clone(),
+            }),
+        );
+
+        // Show the server while connecting
+        Self::dispatch(
+            this,
+            model::AppAction::Conv(model::ConvAction::SetConnectionState {
+                account,
+                server: config.host.clone(),
+                state: model::ConnectionState::Connecting,
+            }),
+        );
+
+        let this_weak = Rc::downgrade(this);
+        let sink =
+            connector::main_thread_sink(this.wm, account, config.host.clone(), move |app_action| {
+                Self::dispatch_weak(&this_weak, app_action)
+            });
+
+        this.connectors
+            .borrow_mut()
+            .push(Box::new(irc::IrcConnector::connect(config, sink)));
+    }
+
     fn dispatch_weak(this_weak: &Weak<Self>, action: model::AppAction) {
         if let Some(this) = this_weak.upgrade() {
             Self::dispatch(&this, action);
@@ -97,30 +202,119 @@ impl AppView {
         }
     }
 
+    /// Request the message log store operations associated with `action`.
+    /// `conv` is the state before `action` is applied. The operations are
+    /// performed on a worker thread, and their results are dispatched as
+    /// actions when they are ready.
+    fn update_log_store(this: &Rc<Self>, conv: &model::ConvState, action: &model::ConvAction) {
+        match action {
+            model::ConvAction::MessageReceived(chref, message) => {
+                this.log_store.append(chref, message);
+            }
+            model::ConvAction::RequestOlderMessages(chref) => {
+                match conv.channel(chref) {
+                    Some(ch) if ch.has_older_messages && !ch.loading_older_messages => {}
+                    _ => return,
+                }
+
+                let this_weak = MtSticky::with_wm(this.wm, Rc::downgrade(this));
+                let channel = chref.clone();
+
+                this.log_store
+                    .read_older(chref, HISTORY_PAGE_SIZE, move |result| {
+                        let (messages, has_more) = match result {
+                            Ok(messages) => {
+                                let has_more = messages.len() == HISTORY_PAGE_SIZE;
+                                (messages, has_more)
+                            }
+                            Err(e) => {
+                                log::error!("Could not read messages from the log store: {}", e);
+                                (Vec::new(), false)
+                            }
+                        };
+
+                        pal::Wm::invoke_on_main_thread(move |wm| {
+                            let action = model::ConvAction::OlderMessagesLoaded {
+                                channel,
+                                messages: messages.into_iter().map(Elem::new).collect(),
+                                has_more,
+                            };
+                            Self::dispatch_weak(
+                                this_weak.get_with_wm(wm),
+                                model::AppAction::Conv(action),
+                            );
+                        });
+                    });
+            }
+            _ => {}
+        }
+    }
+
+    /// Display or close desktop notifications based on `action`. `conv` is
+    /// the state before `action` is applied.
+    fn update_notifications(&self, conv: &model::ConvState, action: &model::ConvAction) {
+        match action {
+            model::ConvAction::MessageReceived(chref, message) => {
+                let is_seen = self.main_wnd.hwnd.is_focused()
+                    && conv.selected_channel.as_ref() == Some(chref);
+                if !is_seen {
+                    self.notifier.message_received(chref, message);
+                }
+            }
+            model::ConvAction::SelectChannel(chref) | model::ConvAction::MarkAsRead(chref) => {
+                self.notifier.dismiss(chref);
+            }
+            _ => {}
+        }
+    }
+
     fn poll(self: Rc<Self>) {
         // Update the state
+        let mut save_error = None;
         {
             let mut state = self.state.borrow_mut();
             let mut pending_actions = self.pending_actions.borrow_mut();
 
             let mut new_state = Elem::clone(&*state);
             for action in pending_actions.drain(..) {
+                if let model::AppAction::Conv(conv_action) = &action {
+                    Self::update_log_store(&self, &new_state.conv, conv_action);
+                    self.update_notifications(&new_state.conv, conv_action);
+                }
                 new_state = model::AppState::reduce(new_state, &action);
             }
+            let settings_changed = !Elem::ptr_eq(&state.settings, &new_state.settings);
             *state = new_state;
 
             // Persist the app state
             self.persist_sched
                 .handle_update(self.wm, &state, self.profile);
+
+            // Persist the user preferences
+            if settings_changed {
+                if let Err(e) = settings::save_settings(self.profile, &state.settings) {
+                    log::error!("Could not write the settings: {}", e);
+                    save_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(e) = save_error {
+            Self::show_error(&self, format!("Could not save the preferences: {}", e));
         }
 
         let state = self.state.borrow();
 
-        self.main_wnd.poll(&state.main_wnd);
+        self.main_wnd.poll(
+            &state.main_wnd,
+            &state.conv,
+            &state.composer,
+            &state.settings,
+        );
 
         match (cell_is_some(&self.pref_wnd), state.pref_visible) {
             (false, true) => {
-                let pref_wnd = prefwnd::PrefWndView::new(self.wm);
+                let pref_wnd = prefwnd::PrefWndView::new(self.wm, Elem::clone(&state.settings));
 
                 let this_weak = Rc::downgrade(&self);
                 pref_wnd
@@ -131,7 +325,12 @@ impl AppView {
             (true, false) => {
                 self.pref_wnd.set(None);
             }
-            _ => {}
+            (true, true) => {
+                let pref_wnd = self.pref_wnd.take().unwrap();
+                pref_wnd.poll(&state.settings);
+                self.pref_wnd.set(Some(pref_wnd));
+            }
+            (false, false) => {}
         }
     }
 }
@@ -145,13 +344,22 @@ struct WndView {
 }
 
 impl WndView {
-    pub fn new(wm: pal::Wm, wnd_state: Elem<model::WndState>) -> Rc<Self> {
+    pub fn new(
+        wm: pal::Wm,
+        wnd_state: Elem<model::WndState>,
+        conv: Elem<model::ConvState>,
+        composer: Elem<model::ComposerState>,
+        settings: Elem<model::Settings>,
+    ) -> Rc<Self> {
         let hwnd = HWnd::new(wm);
         let style_manager = theming::Manager::global(wm);
 
         let main_view = MainViewBuilder::new()
             .with_wm(wm)
             .with_wnd_state(Elem::clone(&wnd_state))
+            .with_conv(conv)
+            .with_composer(composer)
+            .with_settings(settings)
             .with_style_manager(style_manager)
             .build();
 
@@ -159,6 +367,7 @@ impl WndView {
             .set_layout(FillLayout::new(main_view.view().clone()));
 
         hwnd.set_caption("Stella 2");
+        hwnd.set_menu_bar(global::main_menu_bar(wm));
         Self::update_wnd_style_flags(hwnd.as_ref(), false);
         hwnd.set_visibility(true);
 
@@ -227,10 +436,19 @@ impl WndView {
         self.main_view.set_wnd_focused(is_focused);
     }
 
-    fn poll(&self, new_wnd_state: &Elem<model::WndState>) {
+    fn poll(
+        &self,
+        new_wnd_state: &Elem<model::WndState>,
+        new_conv: &Elem<model::ConvState>,
+        new_composer: &Elem<model::ComposerState>,
+        new_settings: &Elem<model::Settings>,
+    ) {
         *self.wnd_state.borrow_mut() = new_wnd_state.clone();
 
         self.main_view.set_wnd_state(new_wnd_state.clone());
+        self.main_view.set_conv(new_conv.clone());
+        self.main_view.set_composer(new_composer.clone());
+        self.main_view.set_settings(new_settings.clone());
     }
 }
 
@@ -247,11 +465,11 @@ impl WndListener for WndViewWndListener {
 
     fn interpret_event(
         &self,
-        _: pal::Wm,
+        wm: pal::Wm,
         _: HWndRef<'_>,
         ctx: &mut tcw3::uicore::InterpretEventCtx<'_>,
     ) {
-        global::interpret_event(ctx);
+        global::interpret_event(wm, ctx);
     }
 
     fn validate_action(&self, _: pal::Wm, _: HWndRef<'_>, action: ActionId) -> ActionStatus {
@@ -306,3 +524,58 @@ fn cell_is_some<T>(cell: &Cell<Option<T>>) -> bool {
     cell.set(inner);
     x
 }
+
+#[cfg(test)]
+mod tests {
+    use tcw3::testing::{pal_testing::wmapi::PopupMenuItem, prelude::*, use_testing_wm};
+
+    use super::*;
+
+    #[use_testing_wm]
+    #[test]
+    fn main_menu_bar(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        testutils::init_style_manager(wm);
+
+        let state = model::AppState::new();
+        let sidebar_visible = state.main_wnd.sidebar_visible;
+        let wnd_view = WndView::new(
+            wm,
+            Elem::clone(&state.main_wnd),
+            Elem::clone(&state.conv),
+            Elem::clone(&state.composer),
+            Elem::clone(&state.settings),
+        );
+
+        let toggled = Rc::new(Cell::new(false));
+        {
+            let toggled = Rc::clone(&toggled);
+            wnd_view.set_dispatch(move |action| {
+                if let model::AppAction::Wnd(model::WndAction::ToggleSidebar) = action {
+                    toggled.set(true);
+                }
+            });
+        }
+        twm.step_unsend();
+
+        let pal_hwnd = testutils::expect_single_wnd(twm);
+        let menu_bar = twm.wnd_menu_bar(&pal_hwnd);
+
+        let labels: Vec<_> = menu_bar.items.iter().map(|item| item.label()).collect();
+        assert_eq!(labels, [Some("File"), Some("Edit"), Some("View")]);
+
+        let quit_path = menu_bar.path_of(&["File", "Quit"]).unwrap();
+        assert!(menu_bar.item(&quit_path).unwrap().is_enabled());
+
+        let path = menu_bar.path_of(&["View", "Show Sidebar"]).unwrap();
+        let status = match menu_bar.item(&path) {
+            Some(PopupMenuItem::Action { status, .. }) => *status,
+            item => panic!("unexpected item: {:?}", item),
+        };
+        assert!(status.contains(ActionStatus::VALID | ActionStatus::ENABLED));
+        assert_eq!(status.contains(ActionStatus::CHECKED), sidebar_visible);
+
+        twm.pick_menu_bar_item(&pal_hwnd, &path);
+        assert!(toggled.get());
+    }
+}
@@ -1,14 +1,21 @@
+use cgmath::Point2;
 use std::{ops::Range, rc::Rc};
 use tcw3::{
+    pal,
     ui::{
+        layouts::FillLayout,
         prelude::*,
         theming,
         views::{table, table::LineTy, Button, Label},
     },
-    uicore::{HView, HViewRef},
+    uicore::{HView, HViewRef, MouseDragListener, ViewFlags, ViewListener},
 };
 
-use crate::stylesheet::{elem_id, my_roles};
+use super::listdiff;
+use crate::{
+    model,
+    stylesheet::{elem_id, my_roles},
+};
 
 stella2_meta::designer_impl! {
     crate::view::channellist::ChannelListView
@@ -22,11 +29,60 @@ impl ChannelListView {
             edit.set_model(TableModelQuery {
                 style_manager: self.style_manager(),
                 elem: Rc::clone(self.elem()),
+                this_weak: Rc::new(self.downgrade()),
+                rows: Vec::new(),
             });
-            edit.insert(LineTy::Row, 0..29);
             edit.insert(LineTy::Col, 0..1);
             edit.set_scroll_pos([0.0, 0.0]);
         }
+
+        self.update_rows();
+    }
+
+    /// Update the table model to reflect the current value of `conv`.
+    fn update_rows(&self) {
+        let new_rows = Row::rows_from_conv(&self.conv());
+
+        let mut edit = if let Ok(edit) = self.table().table().edit() {
+            edit
+        } else {
+            // `init` hasn't been called yet
+            return;
+        };
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+
+        // Rows are matched by their keys so that a change in a row's contents
+        // (e.g., the unread count) does not cause the row to be re-inserted.
+        let (old_range, new_range) =
+            listdiff::changed_range(&model.rows, &new_rows, |x, y| x.key_eq(y));
+
+        let changed_rows: Vec<u64> = (0..old_range.start)
+            .chain(old_range.end..model.rows.len())
+            .filter_map(|old_i| {
+                let new_i = if old_i < old_range.start {
+                    old_i
+                } else {
+                    old_i - old_range.end + new_range.end
+                };
+                if model.rows[old_i] != new_rows[new_i] {
+                    Some(new_i as u64)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        edit.remove(LineTy::Row, old_range.start as u64..old_range.end as u64);
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+        model.rows = new_rows;
+
+        edit.insert(LineTy::Row, new_range.start as u64..new_range.end as u64);
+
+        for i in changed_rows {
+            edit.renew_subviews(LineTy::Row, i..i + 1);
+        }
     }
 }
 
@@ -40,60 +96,157 @@ impl theming::Widget for ChannelListView {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+enum Row {
+    /// A group header representing a server.
+    Server {
+        account: model::AccountId,
+        server: String,
+        title: String,
+    },
+    Channel {
+        chref: model::ChannelRef,
+        unread_count: usize,
+        selected: bool,
+    },
+}
+
+impl Row {
+    fn rows_from_conv(conv: &model::ConvState) -> Vec<Self> {
+        let mut rows = Vec::new();
+
+        for account in conv.accounts.iter() {
+            for server in account.servers.iter() {
+                let status = match server.connection {
+                    model::ConnectionState::Connecting => " (connecting)",
+                    model::ConnectionState::Connected => "",
+                    model::ConnectionState::Disconnected { .. } => " (reconnecting)",
+                };
+                rows.push(Row::Server {
+                    account: account.id,
+                    server: server.name.clone(),
+                    title: format!("{} — {}{}", server.name, account.name, status),
+                });
+
+                for channel in server.channels.iter() {
+                    let chref = model::ChannelRef {
+                        account: account.id,
+                        server: server.name.clone(),
+                        channel: channel.name.clone(),
+                    };
+                    rows.push(Row::Channel {
+                        selected: conv.selected_channel.as_ref() == Some(&chref),
+                        chref,
+                        unread_count: channel.unread_count,
+                    });
+                }
+            }
+        }
+
+        rows
+    }
+
+    /// Compare the identities of two rows, ignoring their contents.
+    fn key_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Row::Server {
+                    account: a1,
+                    server: s1,
+                    ..
+                },
+                Row::Server {
+                    account: a2,
+                    server: s2,
+                    ..
+                },
+            ) => a1 == a2 && s1 == s2,
+            (Row::Channel { chref: c1, .. }, Row::Channel { chref: c2, .. }) => c1 == c2,
+            _ => false,
+        }
+    }
+}
+
 struct TableModelQuery {
     style_manager: &'static theming::Manager,
     elem: Rc<theming::Elem>,
+    this_weak: Rc<WeakChannelListView>,
+    rows: Vec<Row>,
 }
 
 impl table::TableModelQuery for TableModelQuery {
     fn new_view(&mut self, cell: table::CellIdx) -> (HView, Box<dyn table::CellCtrler>) {
+        let row = &self.rows[cell[1] as usize];
+
         let label = Label::new(self.style_manager);
-        label.set_text(match (cell[1] % 4, (cell[1] / 4) % 4) {
-            (0, 0) => "randomserver — Slack",
-            (0, 1) => "workplace — Slack",
-            (0, 2) => "thawedpeach — GNU Social",
-            (0, 3) => "FreeNode",
-            (1, _) => "#general",
-            (2, _) => "#prolang",
-            (3, _) => "#random",
-            _ => unreachable!(),
-        });
 
         let wrap = theming::StyledBox::new(self.style_manager, Default::default());
         wrap.set_child(theming::roles::GENERIC, Some(&label));
-        wrap.set_class_set(
-            if cell[1] % 4 == 0 {
-                elem_id::SIDEBAR_GROUP_HEADER
-            } else {
-                elem_id::SIDEBAR_ITEM
-            } | if cell[1] == 1 || (cell[1] % 4 == 0 && cell[1] < 28) {
-                theming::ClassSet::ACTIVE
-            } else {
-                theming::ClassSet::empty()
-            },
-        );
 
         self.elem.insert_child(wrap.style_elem());
 
-        let button = if cell[1] % 4 == 0 {
-            let button = Button::new(self.style_manager);
-            // Clear `.BUTTON` and replace with `#SIDEBAR_GROUP_BULLET`
-            button.set_class_set(elem_id::SIDEBAR_GROUP_BULLET);
+        match row {
+            Row::Server { title, .. } => {
+                label.set_text(title.clone());
 
-            wrap.set_child(my_roles::BULLET, Some(&button));
+                // TODO: Support collapsing groups
+                wrap.set_class_set(elem_id::SIDEBAR_GROUP_HEADER | theming::ClassSet::ACTIVE);
 
-            Some(button)
-        } else {
-            None
-        };
+                let button = Button::new(self.style_manager);
+                // Clear `.BUTTON` and replace with `#SIDEBAR_GROUP_BULLET`
+                button.set_class_set(elem_id::SIDEBAR_GROUP_BULLET);
+
+                wrap.set_child(my_roles::BULLET, Some(&button));
 
-        (wrap.view(), Box::new(((wrap, button),)))
+                (wrap.view(), Box::new(((wrap, button, label),)))
+            }
+            Row::Channel {
+                chref,
+                unread_count,
+                selected,
+            } => {
+                label.set_text(chref.channel.clone());
+
+                wrap.set_class_set(
+                    elem_id::SIDEBAR_ITEM
+                        | if *selected {
+                            theming::ClassSet::ACTIVE
+                        } else {
+                            theming::ClassSet::empty()
+                        },
+                );
+
+                let badge = if *unread_count > 0 {
+                    let badge = Label::new(self.style_manager);
+                    badge.set_class_set(theming::ClassSet::LABEL | elem_id::SIDEBAR_UNREAD_BADGE);
+                    badge.set_text(unread_count.to_string());
+                    wrap.set_child(my_roles::BADGE, Some(&badge));
+                    Some(badge)
+                } else {
+                    None
+                };
+
+                // Select the channel when clicked
+                let view = HView::new(ViewFlags::ACCEPT_MOUSE_DRAG);
+                view.set_layout(FillLayout::new(wrap.view()));
+                view.set_listener(ChannelRowListener {
+                    this_weak: Rc::clone(&self.this_weak),
+                    chref: chref.clone(),
+                });
+
+                (view, Box::new(((wrap, label, badge),)))
+            }
+        }
     }
 
     fn range_size(&mut self, line_ty: LineTy, range: Range<u64>, _approx: bool) -> f64 {
         match line_ty {
-            LineTy::Row => (range.start..range.end)
-                .map(|i| if i % 4 == 0 { 25.0 } else { 20.0 })
+            LineTy::Row => self.rows[range.start as usize..range.end as usize]
+                .iter()
+                .map(|row| match row {
+                    Row::Server { .. } => 25.0,
+                    Row::Channel { .. } => 20.0,
+                })
                 .sum(),
 
             // `TableFlags::GROW_LAST_COL` expands the column to cover the region.
@@ -102,3 +255,43 @@ impl table::TableModelQuery for TableModelQuery {
         }
     }
 }
+
+/// The view listener for a channel row. Selects the channel when clicked.
+struct ChannelRowListener {
+    this_weak: Rc<WeakChannelListView>,
+    chref: model::ChannelRef,
+}
+
+impl ViewListener for ChannelRowListener {
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        Box::new(ChannelRowDragListener {
+            this_weak: Rc::clone(&self.this_weak),
+            chref: self.chref.clone(),
+        })
+    }
+}
+
+struct ChannelRowDragListener {
+    this_weak: Rc<WeakChannelListView>,
+    chref: model::ChannelRef,
+}
+
+impl MouseDragListener for ChannelRowDragListener {
+    fn mouse_down(&self, _: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, button: u8) {
+        if button != 0 {
+            return;
+        }
+
+        if let Some(this) = self.this_weak.upgrade() {
+            this.raise_dispatch(model::AppAction::Conv(model::ConvAction::SelectChannel(
+                self.chref.clone(),
+            )));
+        }
+    }
+}
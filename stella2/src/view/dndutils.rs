@@ -0,0 +1,92 @@
+use cgmath::Point2;
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use subscriber_list::SubscriberList;
+use tcw3::{
+    pal,
+    ui::{
+        layouts::FillLayout,
+        theming::{HElem, Widget},
+    },
+    uicore::{DragData, DragDataTypes, HView, HViewRef, Sub, ViewFlags, ViewListener},
+};
+
+pub type DropFilesCb = Box<dyn Fn(Vec<PathBuf>)>;
+
+/// Wraps a widget to accept files dropped onto it.
+pub struct FileDropTarget {
+    view: HView,
+    style_elem: Option<HElem>,
+    shared: Rc<FileDropTargetShared>,
+}
+
+struct FileDropTargetShared {
+    handlers: RefCell<SubscriberList<DropFilesCb>>,
+}
+
+impl FileDropTarget {
+    pub fn new(child: &dyn Widget) -> Self {
+        let shared = Rc::new(FileDropTargetShared {
+            handlers: RefCell::new(SubscriberList::new()),
+        });
+
+        let view = HView::new(ViewFlags::ACCEPT_DROP);
+        view.set_layout(FillLayout::new(child.view_ref().cloned()));
+        view.set_listener(FileDropTargetListener {
+            shared: Rc::clone(&shared),
+        });
+
+        Self {
+            view,
+            style_elem: child.style_elem(),
+            shared,
+        }
+    }
+
+    pub fn view(&self) -> HView {
+        self.view.clone()
+    }
+
+    pub fn subscribe_drop_files(&self, cb: DropFilesCb) -> Sub {
+        self.shared.handlers.borrow_mut().insert(cb).untype()
+    }
+}
+
+/// The styling element of the wrapped widget is used as-is.
+impl Widget for FileDropTarget {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view.as_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        self.style_elem
+    }
+}
+
+struct FileDropTargetListener {
+    shared: Rc<FileDropTargetShared>,
+}
+
+impl ViewListener for FileDropTargetListener {
+    fn drag_over(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        types: DragDataTypes,
+    ) -> DragDataTypes {
+        types & DragDataTypes::FILES
+    }
+
+    fn drag_drop(&self, _: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, data: &DragData) -> bool {
+        if data.files.is_empty() {
+            return false;
+        }
+
+        // Raise `drop_files`
+        for cb in self.shared.handlers.borrow().iter() {
+            cb(data.files.clone());
+        }
+
+        true
+    }
+}
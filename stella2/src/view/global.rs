@@ -1,9 +1,12 @@
 //! Application-global commands
+use std::cell::RefCell;
 use tcw3::{
-    pal,
+    pal::{self, accel::KeyBindings, MtLock},
     uicore::{ActionId, InterpretEventCtx},
 };
 
+use crate::config::{keybindings::load_user_key_bindings, profile::Profile};
+
 /// Re-export system actions
 pub use tcw3::uicore::actions as sys;
 
@@ -13,209 +16,366 @@ iota::iota! {
             , SHOW_PREF
 }
 
-pub fn interpret_event(ctx: &mut InterpretEventCtx<'_>) {
+/// The names of the actions which can be bound to key combinations by
+/// `keybindings.json`.
+pub const ACTION_NAMES: &[(&str, ActionId)] = &[
+    ("select_all", sys::SELECT_ALL),
+    ("undo", sys::UNDO),
+    ("redo", sys::REDO),
+    ("copy", sys::COPY),
+    ("cut", sys::CUT),
+    ("paste", sys::PASTE),
+    ("paste_as_plain_text", sys::PASTE_AS_PLAIN_TEXT),
+    ("quit", QUIT),
+    ("toggle_sidebar", TOGGLE_SIDEBAR),
+    ("show_pref", SHOW_PREF),
+];
+
+/// The effective key bindings and the accelerator table constructed from
+/// them. Initialized by `init_key_bindings`.
+static KEY_ACCEL: MtLock<RefCell<Option<(KeyBindings, pal::AccelTable)>>> =
+    MtLock::new(RefCell::new(None));
+
+/// Get the default key bindings of the current platform.
+fn default_key_bindings() -> KeyBindings {
+    let mut bindings = KeyBindings::new();
+
+    // On macOS, these actions are triggered by the main menu
+    if cfg!(not(target_os = "macos")) {
+        let redo = if cfg!(target_os = "windows") {
+            "Ctrl+Y"
+        } else {
+            "Ctrl+Shift+Z"
+        };
+
+        for &(action, pattern) in [
+            (sys::SELECT_ALL, "Ctrl+A"),
+            (sys::UNDO, "Ctrl+Z"),
+            (sys::REDO, redo),
+            (sys::COPY, "Ctrl+C"),
+            (sys::CUT, "Ctrl+X"),
+            (sys::PASTE, "Ctrl+V"),
+            (sys::PASTE_AS_PLAIN_TEXT, "Ctrl+Shift+V"),
+            (QUIT, "Ctrl+Q"),
+        ]
+        .iter()
+        {
+            bindings.bind(action, pattern.parse().unwrap());
+        }
+    }
+
+    bindings
+}
+
+/// Load the user-defined key bindings from `profile` and put them on top of
+/// the default ones.
+pub fn init_key_bindings(wm: pal::Wm, profile: &Profile) {
+    let bindings = default_key_bindings().overlay(&load_user_key_bindings(profile, ACTION_NAMES));
+
+    let action_name = |action| {
+        (ACTION_NAMES.iter())
+            .find(|(_, a)| *a == action)
+            .map(|(name, _)| *name)
+            .unwrap_or("?")
+    };
+    for conflict in bindings.conflicts() {
+        let names: Vec<_> = conflict.actions.iter().map(|&a| action_name(a)).collect();
+        log::warn!(
+            "The key combination {} is bound to more than one action: {:?}",
+            conflict.pattern,
+            names
+        );
+    }
+
+    let accel_table = bindings.to_accel_table();
+    KEY_ACCEL
+        .get_with_wm(wm)
+        .replace(Some((bindings, accel_table)));
+}
+
+fn with_key_accel<R>(wm: pal::Wm, f: impl FnOnce(&KeyBindings, &pal::AccelTable) -> R) -> R {
+    let mut key_accel = KEY_ACCEL.get_with_wm(wm).borrow_mut();
+    let (bindings, accel_table) = key_accel.get_or_insert_with(|| {
+        let bindings = default_key_bindings();
+        let accel_table = bindings.to_accel_table();
+        (bindings, accel_table)
+    });
+    f(bindings, accel_table)
+}
+
+pub fn interpret_event(wm: pal::Wm, ctx: &mut InterpretEventCtx<'_>) {
+    with_key_accel(wm, |_, accel_table| ctx.use_accel(accel_table));
+
     ctx.use_accel(&tcw3::pal::accel_table![
-        (
-            sys::SELECT_ALL,
-            windows("Ctrl+A"),
-            gtk("Ctrl+A"),
-            macos_sel("selectAll:")
-        ),
-        (
-            sys::UNDO,
-            windows("Ctrl+Z"),
-            gtk("Ctrl+Z"),
-            macos_sel("undo:")
-        ),
-        (
-            sys::REDO,
-            windows("Ctrl+Y"),
-            gtk("Ctrl+Shift+Z"),
-            macos_sel("redo:")
-        ),
-        (
-            sys::COPY,
-            windows("Ctrl+C"),
-            gtk("Ctrl+C"),
-            macos_sel("copy:")
-        ),
-        (
-            sys::CUT,
-            windows("Ctrl+X"),
-            gtk("Ctrl+X"),
-            macos_sel("cut:")
-        ),
-        (
-            sys::PASTE,
-            windows("Ctrl+V"),
-            gtk("Ctrl+V"),
-            macos_sel("paste:")
-        ),
+        (sys::SELECT_ALL, macos_sel("selectAll:")),
+        (sys::UNDO, macos_sel("undo:")),
+        (sys::REDO, macos_sel("redo:")),
+        (sys::COPY, macos_sel("copy:")),
+        (sys::CUT, macos_sel("cut:")),
+        (sys::PASTE, macos_sel("paste:")),
         (sys::PASTE_AS_PLAIN_TEXT, macos_sel("pasteAsPlainText:")),
-        (
-            QUIT,
-            windows("Ctrl+Q"),
-            gtk("Ctrl+Q"),
-            macos_sel("terminate:")
-        ),
+        (QUIT, macos_sel("terminate:")),
         (TOGGLE_SIDEBAR, macos_sel("toggleSidebar:")),
         (SHOW_PREF, macos_sel("orderFrontPreferencesPanel:")),
     ]);
 }
 
-/// Create a main menu on macOS.
-#[cfg(target_os = "macos")]
-pub fn set_main_menu(_: pal::Wm) {
-    // Most of these are predefined by the Interface Builder template that
-    // comes with Xcode.
-    static MENU: &[Item] = &[
-        Item::Submenu(
-            "",
-            &[
-                Item::leaf("About Stella 2", "orderFrontStandardAboutPanel:"),
-                Item::Sep,
-                Item::leaf("Preferences…", "orderFrontPreferencesPanel:").with_cmd(","),
-                Item::Sep,
-                Item::Submenu("Services", &[]),
-                Item::Sep,
-                Item::leaf("Hide Stella 2", "hide:").with_cmd("h"),
-                Item::leaf("Hide Others", "hideOtherApplications:").with_cmd_opt("h"),
-                Item::leaf("Show All", "unhideAllApplications:"),
-                Item::Sep,
-                Item::leaf("Quit Stella 2", "terminate:").with_cmd("q"),
-            ],
-        ),
-        Item::Submenu(
-            "Edit",
-            &[
-                Item::leaf("Undo", "undo:").with_cmd("z"),
-                Item::leaf("Redo", "redo:").with_cmd("Z"),
-                Item::Sep,
-                Item::leaf("Cut", "cut:").with_cmd("x"),
-                Item::leaf("Copy", "copy:").with_cmd("c"),
-                Item::leaf("Paste", "paste:").with_cmd("v"),
-                Item::leaf("Paste and Match Style", "pasteAsPlainText:").with_cmd_opt("V"),
-                Item::leaf("Delete", "delete:"),
-                Item::leaf("Select All", "selectAll:").with_cmd("a"),
-                Item::Sep,
-                Item::Submenu(
-                    "Transformations",
-                    &[
-                        Item::leaf("Make Upper Case", "uppercaseWord:"),
-                        Item::leaf("Make Lower Case", "lowercaseWord:"),
-                        Item::leaf("Capitalize", "capitalizeWord:"),
-                    ],
-                ),
-                Item::Submenu(
-                    "Speech",
-                    &[
-                        Item::leaf("Start Speaking", "startSpeaking:"),
-                        Item::leaf("Stop Speaking", "stopSpeaking:"),
-                    ],
-                ),
-            ],
-        ),
-        Item::Submenu(
-            "View",
-            &[
-                Item::leaf("Show Sidebar", "toggleSidebar:").with_cmd_ctrl("s"),
-                Item::leaf("Enter Full Screen", "toggleFullScreen:").with_cmd_ctrl("f"),
-            ],
-        ),
-        Item::Submenu(
-            "Window",
-            &[
-                Item::leaf("Minimize", "performMiniaturize:").with_cmd("m"),
-                Item::leaf("Zoom", "performZoom:"),
-                Item::Sep,
-                Item::leaf("Bring All to Front", "arrangeInFront:"),
-            ],
-        ),
-        Item::Submenu(
-            "Help",
-            &[Item::leaf(
-                "Commence Spline Reticulation",
-                "reticulateSpline̦:",
-            )],
-        ),
-    ];
+/// Construct a menu item for `action`, displaying the key combination bound
+/// to the action (if any).
+fn action_item(bindings: &KeyBindings, label: &str, action: ActionId) -> pal::MenuItem {
+    let item = pal::MenuItem::action(label, action);
+    if let Some(pattern) = bindings.patterns_of(action).next() {
+        item.with_shortcut(pattern.to_string())
+    } else {
+        item
+    }
+}
 
-    use cocoa::{
-        appkit::{NSApplication, NSEventModifierFlags, NSMenu, NSMenuItem},
-        base::{id, nil},
-        foundation::{NSAutoreleasePool, NSString},
-    };
-    use objc::{msg_send, runtime::Sel, sel, sel_impl};
+/// Construct the menu displayed by the toolbar's menu button.
+pub fn main_popup_menu(wm: pal::Wm) -> Vec<pal::MenuItem> {
+    with_key_accel(wm, |bindings, _| {
+        vec![
+            action_item(bindings, "Preferences…", SHOW_PREF),
+            action_item(bindings, "Show Sidebar", TOGGLE_SIDEBAR),
+            pal::MenuItem::Sep,
+            action_item(bindings, "Quit", QUIT),
+        ]
+    })
+}
+
+/// Construct the menu bar of a main window from `MAIN_MENU`. The menu bar is
+/// not displayed on macOS, where `set_main_menu` creates the application's
+/// main menu instead.
+pub fn main_menu_bar(wm: pal::Wm) -> Vec<pal::MenuItem> {
+    fn convert(bindings: &KeyBindings, defs: &[MenuDef]) -> Vec<pal::MenuItem> {
+        (defs.iter())
+            .filter(|def| def.platforms != Platforms::MacOs)
+            .filter_map(|def| match def.kind {
+                MenuDefKind::Action(action, _) => Some(action_item(bindings, def.label, action)),
+                MenuDefKind::Selector(_) => None,
+                MenuDefKind::Submenu(children) => Some(pal::MenuItem::submenu(
+                    def.label,
+                    convert(bindings, children),
+                )),
+                MenuDefKind::Sep => Some(pal::MenuItem::Sep),
+            })
+            .collect()
+    }
+
+    with_key_accel(wm, |bindings, _| convert(bindings, MAIN_MENU))
+}
+
+/// Specifies the platforms on which a `MenuDef` is displayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Platforms {
+    All,
+    /// Only in the macOS main menu.
+    MacOs,
+    /// Only in the menu bar (i.e., on platforms other than macOS).
+    NotMacOs,
+}
+
+/// The modifier keys of a key equivalent in the macOS main menu.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+enum MacKeyMods {
+    Cmd,
+    CmdOpt,
+    CmdCtrl,
+}
 
-    enum Item {
-        Leaf(Leaf),
-        Submenu(&'static str, &'static [Item]),
-        Sep,
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+enum MenuDefKind {
+    /// Performs an action. On macOS, the action is triggered through the
+    /// given selector, which `interpret_event` maps to the action.
+    Action(ActionId, &'static str),
+    /// Sends a selector that doesn't correspond to any action. This is only
+    /// supported on macOS.
+    Selector(&'static str),
+    Submenu(&'static [MenuDef]),
+    Sep,
+}
+
+/// An item in `MAIN_MENU`.
+struct MenuDef {
+    label: &'static str,
+    /// Overrides `label` in the macOS main menu.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    macos_label: Option<&'static str>,
+    kind: MenuDefKind,
+    platforms: Platforms,
+    /// The key equivalent displayed in the macOS main menu. On other
+    /// platforms, key combinations are derived from `KeyBindings`.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    key_eq: (&'static str, MacKeyMods),
+}
+
+impl MenuDef {
+    const SEP: Self = Self::new("", MenuDefKind::Sep);
+
+    const fn new(label: &'static str, kind: MenuDefKind) -> Self {
+        Self {
+            label,
+            macos_label: None,
+            kind,
+            platforms: Platforms::All,
+            key_eq: ("", MacKeyMods::Cmd),
+        }
     }
 
-    struct Leaf {
-        title: &'static str,
-        action: &'static str,
-        key_eq: &'static str,
-        mod_flags: NSEventModifierFlags,
+    const fn action(label: &'static str, action: ActionId, selector: &'static str) -> Self {
+        Self::new(label, MenuDefKind::Action(action, selector))
     }
 
-    impl Item {
-        const fn leaf(title: &'static str, action: &'static str) -> Self {
-            Self::Leaf(Leaf {
-                title,
-                action,
-                key_eq: "",
-                mod_flags: NSEventModifierFlags::empty(),
-            })
+    const fn selector(label: &'static str, selector: &'static str) -> Self {
+        Self::new(label, MenuDefKind::Selector(selector)).macos_only()
+    }
+
+    const fn submenu(label: &'static str, children: &'static [MenuDef]) -> Self {
+        Self::new(label, MenuDefKind::Submenu(children))
+    }
+
+    const fn with_macos_label(self, macos_label: &'static str) -> Self {
+        Self {
+            macos_label: Some(macos_label),
+            ..self
         }
+    }
 
-        const fn get_leaf(self) -> Leaf {
-            if let Self::Leaf(leaf) = self {
-                leaf
-            } else {
-                // Panicking in `const fn` isn't supported yet
-                Leaf {
-                    title: "",
-                    action: "",
-                    key_eq: "",
-                    mod_flags: NSEventModifierFlags::empty(),
-                }
-            }
+    const fn macos_only(self) -> Self {
+        Self {
+            platforms: Platforms::MacOs,
+            ..self
         }
+    }
 
-        const fn with_key_eq(self, key_eq: &'static str, mod_flags: NSEventModifierFlags) -> Self {
-            Self::Leaf(Leaf {
-                key_eq,
-                mod_flags,
-                ..self.get_leaf()
-            })
+    const fn not_macos(self) -> Self {
+        Self {
+            platforms: Platforms::NotMacOs,
+            ..self
         }
+    }
 
-        const fn with_cmd(self, key_eq: &'static str) -> Self {
-            self.with_key_eq(key_eq, NSEventModifierFlags::NSCommandKeyMask)
+    const fn with_cmd(self, key_eq: &'static str) -> Self {
+        Self {
+            key_eq: (key_eq, MacKeyMods::Cmd),
+            ..self
         }
+    }
 
-        const fn with_cmd_opt(self, key_eq: &'static str) -> Self {
-            self.with_key_eq(
-                key_eq,
-                NSEventModifierFlags::from_bits_truncate(
-                    NSEventModifierFlags::NSCommandKeyMask.bits()
-                        | NSEventModifierFlags::NSAlternateKeyMask.bits(),
-                ),
-            )
+    const fn with_cmd_opt(self, key_eq: &'static str) -> Self {
+        Self {
+            key_eq: (key_eq, MacKeyMods::CmdOpt),
+            ..self
         }
+    }
 
-        const fn with_cmd_ctrl(self, key_eq: &'static str) -> Self {
-            self.with_key_eq(
-                key_eq,
-                NSEventModifierFlags::from_bits_truncate(
-                    NSEventModifierFlags::NSCommandKeyMask.bits()
-                        | NSEventModifierFlags::NSControlKeyMask.bits(),
-                ),
-            )
+    const fn with_cmd_ctrl(self, key_eq: &'static str) -> Self {
+        Self {
+            key_eq: (key_eq, MacKeyMods::CmdCtrl),
+            ..self
         }
     }
+}
+
+/// The definition of the main menu, from which both the menu bar of main
+/// windows (`main_menu_bar`) and the macOS main menu (`set_main_menu`) are
+/// constructed. Most of the macOS-only items are predefined by the Interface
+/// Builder template that comes with Xcode.
+static MAIN_MENU: &[MenuDef] = &[
+    MenuDef::submenu(
+        "File",
+        &[
+            MenuDef::selector("About Stella 2", "orderFrontStandardAboutPanel:"),
+            MenuDef::SEP.macos_only(),
+            MenuDef::action("Preferences…", SHOW_PREF, "orderFrontPreferencesPanel:").with_cmd(","),
+            MenuDef::SEP,
+            MenuDef::submenu("Services", &[]).macos_only(),
+            MenuDef::SEP.macos_only(),
+            MenuDef::selector("Hide Stella 2", "hide:").with_cmd("h"),
+            MenuDef::selector("Hide Others", "hideOtherApplications:").with_cmd_opt("h"),
+            MenuDef::selector("Show All", "unhideAllApplications:"),
+            MenuDef::SEP.macos_only(),
+            MenuDef::action("Quit", QUIT, "terminate:")
+                .with_macos_label("Quit Stella 2")
+                .with_cmd("q"),
+        ],
+    )
+    .with_macos_label(""),
+    MenuDef::submenu(
+        "Edit",
+        &[
+            MenuDef::action("Undo", sys::UNDO, "undo:").with_cmd("z"),
+            MenuDef::action("Redo", sys::REDO, "redo:").with_cmd("Z"),
+            MenuDef::SEP,
+            MenuDef::action("Cut", sys::CUT, "cut:").with_cmd("x"),
+            MenuDef::action("Copy", sys::COPY, "copy:").with_cmd("c"),
+            MenuDef::action("Paste", sys::PASTE, "paste:").with_cmd("v"),
+            MenuDef::action(
+                "Paste and Match Style",
+                sys::PASTE_AS_PLAIN_TEXT,
+                "pasteAsPlainText:",
+            )
+            .with_cmd_opt("V"),
+            MenuDef::selector("Delete", "delete:"),
+            MenuDef::SEP.not_macos(),
+            MenuDef::action("Select All", sys::SELECT_ALL, "selectAll:").with_cmd("a"),
+            MenuDef::SEP.macos_only(),
+            MenuDef::submenu(
+                "Transformations",
+                &[
+                    MenuDef::selector("Make Upper Case", "uppercaseWord:"),
+                    MenuDef::selector("Make Lower Case", "lowercaseWord:"),
+                    MenuDef::selector("Capitalize", "capitalizeWord:"),
+                ],
+            )
+            .macos_only(),
+            MenuDef::submenu(
+                "Speech",
+                &[
+                    MenuDef::selector("Start Speaking", "startSpeaking:"),
+                    MenuDef::selector("Stop Speaking", "stopSpeaking:"),
+                ],
+            )
+            .macos_only(),
+        ],
+    ),
+    MenuDef::submenu(
+        "View",
+        &[
+            MenuDef::action("Show Sidebar", TOGGLE_SIDEBAR, "toggleSidebar:").with_cmd_ctrl("s"),
+            MenuDef::selector("Enter Full Screen", "toggleFullScreen:").with_cmd_ctrl("f"),
+        ],
+    ),
+    MenuDef::submenu(
+        "Window",
+        &[
+            MenuDef::selector("Minimize", "performMiniaturize:").with_cmd("m"),
+            MenuDef::selector("Zoom", "performZoom:"),
+            MenuDef::SEP.macos_only(),
+            MenuDef::selector("Bring All to Front", "arrangeInFront:"),
+        ],
+    )
+    .macos_only(),
+    MenuDef::submenu(
+        "Help",
+        &[MenuDef::selector(
+            "Commence Spline Reticulation",
+            "reticulateSpline̦:",
+        )],
+    )
+    .macos_only(),
+];
+
+/// Create a main menu on macOS from `MAIN_MENU`.
+#[cfg(target_os = "macos")]
+pub fn set_main_menu(_: pal::Wm) {
+    use cocoa::{
+        appkit::{NSApplication, NSEventModifierFlags, NSMenu, NSMenuItem},
+        base::{id, nil},
+        foundation::{NSAutoreleasePool, NSString},
+    };
+    use objc::{msg_send, runtime::Sel, sel, sel_impl};
 
     pub struct AutoreleasePool(id);
 
@@ -233,7 +393,7 @@ pub fn set_main_menu(_: pal::Wm) {
 
     let _arp = AutoreleasePool::new();
 
-    unsafe fn new_menu(title: &str, items: &[Item], app: id) -> id {
+    unsafe fn new_menu(title: &str, items: &[MenuDef], app: id) -> id {
         let menu = NSMenu::alloc(nil)
             .autorelease()
             .initWithTitle_(NSString::alloc(nil).autorelease().init_str(title));
@@ -245,29 +405,43 @@ pub fn set_main_menu(_: pal::Wm) {
             app.setWindowsMenu_(menu);
         }
 
-        for item in items.iter() {
-            match item {
-                Item::Leaf(Leaf {
-                    title,
-                    action,
-                    key_eq,
-                    mod_flags,
-                }) => {
+        for def in items.iter() {
+            if def.platforms == Platforms::NotMacOs {
+                continue;
+            }
+
+            let title = def.macos_label.unwrap_or(def.label);
+
+            match def.kind {
+                MenuDefKind::Action(_, selector) | MenuDefKind::Selector(selector) => {
+                    let (key_eq, mods) = def.key_eq;
+                    let mod_flags = match mods {
+                        MacKeyMods::Cmd => NSEventModifierFlags::NSCommandKeyMask,
+                        MacKeyMods::CmdOpt => {
+                            NSEventModifierFlags::NSCommandKeyMask
+                                | NSEventModifierFlags::NSAlternateKeyMask
+                        }
+                        MacKeyMods::CmdCtrl => {
+                            NSEventModifierFlags::NSCommandKeyMask
+                                | NSEventModifierFlags::NSControlKeyMask
+                        }
+                    };
+
                     let cocoa_item = menu.addItemWithTitle_action_keyEquivalent(
                         NSString::alloc(nil).autorelease().init_str(title),
-                        Sel::register(action),
+                        Sel::register(selector),
                         NSString::alloc(nil).autorelease().init_str(key_eq),
                     );
-                    cocoa_item.setKeyEquivalentModifierMask_(*mod_flags);
+                    cocoa_item.setKeyEquivalentModifierMask_(mod_flags);
                 }
-                Item::Submenu(title, children) => {
+                MenuDefKind::Submenu(children) => {
                     let submenu = NSMenuItem::alloc(nil).autorelease();
                     let () = msg_send![submenu, setTitle:
                         NSString::alloc(nil).autorelease().init_str(title)];
                     submenu.setSubmenu_(new_menu(title, children, app));
                     menu.addItem_(submenu);
                 }
-                Item::Sep => {
+                MenuDefKind::Sep => {
                     menu.addItem_(NSMenuItem::separatorItem(nil));
                 }
             }
@@ -277,7 +451,7 @@ pub fn set_main_menu(_: pal::Wm) {
 
     unsafe {
         let app = cocoa::appkit::NSApp();
-        app.setMainMenu_(new_menu("", MENU, app));
+        app.setMainMenu_(new_menu("", MAIN_MENU, app));
     }
 }
 
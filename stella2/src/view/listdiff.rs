@@ -0,0 +1,75 @@
+//! Computes the changed portion of a list, for updating table views
+//! incrementally.
+use std::ops::Range;
+
+/// Find the smallest ranges `(old_range, new_range)` such that replacing
+/// `old[old_range]` with `new[new_range]` transforms `old` into `new`.
+///
+/// The elements outside the ranges are compared by `eq`. The result is not
+/// necessarily minimal if elements were changed in more than one location.
+pub fn changed_range<T, U>(
+    old: &[T],
+    new: &[U],
+    mut eq: impl FnMut(&T, &U) -> bool,
+) -> (Range<usize>, Range<usize>) {
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(x, y)| eq(x, y))
+        .count();
+
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(max_suffix)
+        .take_while(|(x, y)| eq(x, y))
+        .count();
+
+    (prefix..old.len() - suffix, prefix..new.len() - suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> (Range<usize>, Range<usize>) {
+        changed_range(old.as_bytes(), new.as_bytes(), |x, y| x == y)
+    }
+
+    #[test]
+    fn no_change() {
+        assert_eq!(diff("", ""), (0..0, 0..0));
+        assert_eq!(diff("abc", "abc"), (3..3, 3..3));
+    }
+
+    #[test]
+    fn from_or_to_empty() {
+        assert_eq!(diff("", "abc"), (0..0, 0..3));
+        assert_eq!(diff("abc", ""), (0..3, 0..0));
+    }
+
+    #[test]
+    fn insertion() {
+        assert_eq!(diff("ac", "abc"), (1..1, 1..2));
+        assert_eq!(diff("abc", "xyabc"), (0..0, 0..2));
+        assert_eq!(diff("abc", "abcxy"), (3..3, 3..5));
+    }
+
+    #[test]
+    fn removal() {
+        assert_eq!(diff("abc", "ac"), (1..2, 1..1));
+        assert_eq!(diff("xyabc", "abc"), (0..2, 0..0));
+        assert_eq!(diff("abcxy", "abc"), (3..5, 3..3));
+    }
+
+    #[test]
+    fn replacement() {
+        assert_eq!(diff("abcd", "axyd"), (1..3, 1..3));
+        // The prefix and suffix must not overlap
+        assert_eq!(diff("aa", "aaa"), (2..2, 2..3));
+        // Not minimal, but covers both changes
+        assert_eq!(diff("abcde", "xbcdy"), (0..5, 0..5));
+    }
+}
@@ -1,5 +1,6 @@
 use cggeom::{box2, prelude::*, Box2};
 use cgmath::Matrix3;
+use harmony::Elem;
 use std::{cell::Cell, ops::Range};
 use tcw3::{
     pal,
@@ -11,61 +12,93 @@ use tcw3::{
     uicore::{HView, HViewRef, HWndRef, UpdateCtx, ViewListener},
 };
 
+use super::listdiff;
+use crate::model;
+
 stella2_meta::designer_impl! {
     crate::view::logview::LogView
 }
 
 const GUTTER_WIDTH: f32 = 100.0;
 
+/// Older messages are loaded when the scroll position is less than this
+/// value.
+const LOAD_OLDER_MESSAGES_THRESHOLD: f64 = 200.0;
+
 impl LogView {
     fn init(&self) {
         // Set up the table model
-        // TODO: hook up with a network layer
-        let lipsum = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. \
-                      Pellentesque ultricies diam sit amet ante auctor, et \
-                      pretium orci molestie. Aenean facilisis justo ac tincidunt \
-                      lobortis. Nulla molestie sem vel vehicula scelerisque. \
-                      Quisque in viverra lacus, a suscipit lectus. Integer \
-                      dignissim lacus neque, a condimentum tellus tempus ac. \
-                      Praesent interdum, velit id mattis fringilla, tortor velit \
-                      bibendum lorem, eget blandit augue nibh vel nunc. Duis ex \
-                      ligula, porttitor ultricies velit vel, porta lacinia lectus. \
-                      In pharetra auctor lorem, a efficitur tellus. Maecenas \
-                      feugiat dapibus dolor quis dignissim. Quisque sed tortor \
-                      sagittis, pretium mauris sit amet, ullamcorper turpis. \
-                      Suspendisse potenti."
-            .split_whitespace();
-        let mk_lipsum = |num_words| lipsum.clone().take(num_words).collect::<Vec<_>>().join(" ");
-        let rows = vec![
-            Row::Date(chrono::NaiveDate::from_ymd(2018, 3, 1)),
-            Row::LogItem("bob", "13:00", mk_lipsum(20)),
-            Row::LogItem("alice", "13:32", mk_lipsum(25)),
-            Row::LogItem("bob", "14:04", mk_lipsum(35)),
-            Row::LogItem("alice", "14:36", mk_lipsum(5)),
-            Row::LogItem("bob", "15:08", mk_lipsum(12)),
-            Row::Date(chrono::NaiveDate::from_ymd(2018, 3, 2)),
-            Row::LogItem("bob", "10:40", mk_lipsum(12)),
-            Row::LogItem("alice", "11:12", mk_lipsum(15)),
-            Row::LogItem("bob", "11:44", mk_lipsum(17)),
-            Row::LogItem("alice", "14:16", mk_lipsum(40)),
-            Row::LogItem("bob", "14:48", mk_lipsum(20)),
-        ];
-
         {
+            let settings = self.settings();
             let mut edit = self.table().table().edit().unwrap();
-            let num_rows = rows.len() as u64;
             edit.set_model(TableModelQuery {
                 width: 100.0,
                 dpi_scale: 1.0,
-                row_visuals: rows
-                    .iter()
-                    .map(|row| RowVisual::from_row(row, 100.0, 1.0))
-                    .collect(),
-                rows,
+                font_size: settings.font_size,
+                time_format: settings.time_format,
+                row_visuals: Vec::new(),
+                rows: Vec::new(),
             });
-            edit.insert(LineTy::Row, 0..num_rows);
             edit.insert(LineTy::Col, 0..1);
         }
+
+        self.update_rows();
+    }
+
+    /// Update the table model to reflect the current value of `channel`.
+    fn update_rows(&self) {
+        let new_rows = self
+            .channel()
+            .map(|channel| Row::rows_from_messages(&channel.messages))
+            .unwrap_or_default();
+
+        let mut edit = if let Ok(edit) = self.table().table().edit() {
+            edit
+        } else {
+            // `init` hasn't been called yet
+            return;
+        };
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+
+        let (old_range, new_range) =
+            listdiff::changed_range(&model.rows, &new_rows, Row::shallow_eq);
+
+        if old_range.is_empty() && new_range.is_empty() {
+            return;
+        }
+
+        let new_visuals: Vec<_> = new_rows[new_range.clone()]
+            .iter()
+            .map(|row| RowVisual::from_row(row, model))
+            .collect();
+
+        edit.remove(LineTy::Row, old_range.start as u64..old_range.end as u64);
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+        model.rows = new_rows;
+        model.row_visuals.splice(old_range, new_visuals);
+
+        edit.insert(LineTy::Row, new_range.start as u64..new_range.end as u64);
+    }
+
+    /// Raise `load_older_messages` if the view is scrolled near the top and
+    /// older messages are available.
+    fn check_scroll_pos(&self) {
+        match self.channel() {
+            Some(ch) if ch.has_older_messages && !ch.loading_older_messages => {}
+            _ => return,
+        }
+
+        let scroll_pos = if let Ok(edit) = self.table().table().edit() {
+            edit.scroll_pos()
+        } else {
+            return;
+        };
+
+        if scroll_pos[1] < LOAD_OLDER_MESSAGES_THRESHOLD {
+            self.raise_load_older_messages();
+        }
     }
 
     fn update_row_visuals(&self) {
@@ -83,10 +116,41 @@ impl LogView {
         model.width = width;
         model.dpi_scale = dpi_scale;
 
+        Self::renew_row_visuals(&mut edit);
+    }
+
+    /// Update the table model to reflect the current value of `settings`.
+    fn update_settings(&self) {
+        let settings = self.settings();
+
+        let mut edit = if let Ok(edit) = self.table().table().edit() {
+            edit
+        } else {
+            // `init` hasn't been called yet
+            return;
+        };
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+
+        if model.font_size == settings.font_size && model.time_format == settings.time_format {
+            return;
+        }
+
+        model.font_size = settings.font_size;
+        model.time_format = settings.time_format;
+
+        Self::renew_row_visuals(&mut edit);
+    }
+
+    /// Recreate all row visuals using the current parameters in the table
+    /// model.
+    fn renew_row_visuals(edit: &mut table::TableEdit<'_>) {
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+
         model.row_visuals = model
             .rows
             .iter()
-            .map(|row| RowVisual::from_row(row, width, dpi_scale))
+            .map(|row| RowVisual::from_row(row, model))
             .collect();
 
         let num_rows = model.rows.len() as u64;
@@ -99,6 +163,9 @@ struct TableModelQuery {
     row_visuals: Vec<RowVisual>,
     width: f32,
     dpi_scale: f32,
+    /// The font size in points. See `model::Settings::font_size`.
+    font_size: u32,
+    time_format: model::TimeFormat,
     rows: Vec<Row>,
 }
 
@@ -184,10 +251,72 @@ impl ViewListener for RowViewListener {
     }
 }
 
-// Mock-up log model
 enum Row {
     Date(chrono::NaiveDate),
-    LogItem(&'static str, &'static str, String),
+    Message(Elem<model::Message>),
+}
+
+impl Row {
+    /// Construct rows from a list of messages, inserting a date separator
+    /// every time the local date changes.
+    fn rows_from_messages(messages: &[Elem<model::Message>]) -> Vec<Self> {
+        let mut rows = Vec::with_capacity(messages.len() + 1);
+        let mut last_date = None;
+
+        for msg in messages.iter() {
+            let date = msg
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .date()
+                .naive_local();
+            if last_date != Some(date) {
+                rows.push(Row::Date(date));
+                last_date = Some(date);
+            }
+            rows.push(Row::Message(Elem::clone(msg)));
+        }
+
+        rows
+    }
+
+    fn shallow_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Row::Date(x), Row::Date(y)) => x == y,
+            (Row::Message(x), Row::Message(y)) => Elem::ptr_eq(x, y),
+            _ => false,
+        }
+    }
+}
+
+/// Choose an avatar color for the given sender name.
+fn avatar_color(sender: &str) -> pal::RGBAF32 {
+    const PALETTE: &[[f32; 3]] = &[
+        [0.8, 0.4, 0.3],
+        [0.1, 0.6, 0.6],
+        [0.4, 0.5, 0.8],
+        [0.6, 0.7, 0.2],
+        [0.7, 0.4, 0.7],
+        [0.9, 0.6, 0.2],
+    ];
+
+    // FNV-1a; `DefaultHasher` is not guaranteed to be stable
+    let hash = sender.bytes().fold(0x811c_9dc5u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(0x0100_0193)
+    });
+    let [r, g, b] = PALETTE[hash as usize % PALETTE.len()];
+
+    pal::RGBAF32::new(r, g, b, 1.0)
+}
+
+/// Format the timestamp of a message in the local time zone.
+fn format_time(timestamp: &chrono::DateTime<chrono::Utc>, format: model::TimeFormat) -> String {
+    timestamp
+        .with_timezone(&chrono::Local)
+        .format(match format {
+            model::TimeFormat::H12 => "%-I:%M %p",
+            model::TimeFormat::H24 => "%H:%M",
+        })
+        .to_string()
 }
 
 #[derive(Clone)]
@@ -198,17 +327,20 @@ struct RowVisual {
 }
 
 impl RowVisual {
+    /// Render a row using the parameters in `model`.
     #[allow(clippy::possible_missing_comma)]
-    fn from_row(row: &Row, row_width: f32, dpi_scale: f32) -> Self {
+    fn from_row(row: &Row, model: &TableModelQuery) -> Self {
+        let (row_width, dpi_scale) = (model.width, model.dpi_scale);
         let v_margin = 3.0;
         let h_margin = 10.0;
         let scrollbar_margin = 12.0;
 
         let text = match row {
             Row::Date(d) => d.to_string(),
-            Row::LogItem(name, _, body) => format!("{} @{} {}", name, name, body),
+            Row::Message(msg) => format!("{} {}", msg.sender, msg.text),
         };
         let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+            size: Some(model.font_size as f32),
             ..Default::default()
         });
         let text_layout = pal::TextLayout::from_text(
@@ -261,7 +393,7 @@ impl RowVisual {
                         pal::RGBAF32::new(0.0, 0.0, 0.0, 1.0),
                     );
                 }
-                Row::LogItem(author, time, _) => {
+                Row::Message(msg) => {
                     let y = v_margin - text_layout.layout_bounds().min.y;
                     builder.draw_text(
                         &text_layout,
@@ -271,11 +403,7 @@ impl RowVisual {
 
                     // Avatar
                     let avatar_size = 16.0;
-                    if *author == "bob" {
-                        builder.set_fill_rgb([0.8, 0.4, 0.3, 1.0].into());
-                    } else {
-                        builder.set_fill_rgb([0.1, 0.6, 0.6, 1.0].into());
-                    }
+                    builder.set_fill_rgb(avatar_color(&msg.sender));
                     builder.begin_path();
                     builder.rounded_rect(
                         box2! {
@@ -287,7 +415,8 @@ impl RowVisual {
                     builder.fill();
 
                     // Time
-                    let time_text_layout = pal::TextLayout::from_text(time, &char_style, None);
+                    let time = format_time(&msg.timestamp, model.time_format);
+                    let time_text_layout = pal::TextLayout::from_text(&time, &char_style, None);
                     builder.draw_text(
                         &time_text_layout,
                         [
@@ -313,3 +442,112 @@ impl RowVisual {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use tcw3::testing::{prelude::*, use_testing_wm};
+    #[cfg(feature = "testing")]
+    use tcw3::{testing::snapshot::assert_wnd_snapshot, ui::layouts::FillLayout, uicore::HWnd};
+
+    use super::*;
+    use crate::view::testutils;
+
+    fn message(sender: &str, (hour, min): (u32, u32), text: &str) -> Elem<model::Message> {
+        // Specify the local time so that the rendered time does not depend on
+        // the time zone
+        let timestamp = chrono::Local.ymd(2020, 4, 1).and_hms(hour, min, 0);
+
+        Elem::new(model::Message {
+            sender: sender.to_owned(),
+            timestamp: timestamp.with_timezone(&chrono::Utc),
+            text: text.to_owned(),
+        })
+    }
+
+    #[test]
+    fn time_format() {
+        let msg = message("alice", (13, 5), "hello");
+        assert_eq!(
+            format_time(&msg.timestamp, model::TimeFormat::H12),
+            "1:05 PM"
+        );
+        assert_eq!(format_time(&msg.timestamp, model::TimeFormat::H24), "13:05");
+
+        let msg = message("alice", (0, 30), "hello");
+        assert_eq!(
+            format_time(&msg.timestamp, model::TimeFormat::H12),
+            "12:30 AM"
+        );
+        assert_eq!(format_time(&msg.timestamp, model::TimeFormat::H24), "00:30");
+    }
+
+    #[use_testing_wm]
+    #[test]
+    fn font_size(_twm: &dyn TestingWm) {
+        let row = Row::Message(message("alice", (9, 41), "hello"));
+        let row_height = |font_size| {
+            let query = TableModelQuery {
+                row_visuals: Vec::new(),
+                width: 400.0,
+                dpi_scale: 1.0,
+                font_size,
+                time_format: model::TimeFormat::H24,
+                rows: Vec::new(),
+            };
+            RowVisual::from_row(&row, &query).height
+        };
+
+        let small = row_height(model::Settings::FONT_SIZE_MIN);
+        let large = row_height(model::Settings::FONT_SIZE_MAX);
+        assert!(large > small, "{} > {}", large, small);
+    }
+
+    #[cfg(feature = "testing")]
+    #[use_testing_wm]
+    #[test]
+    fn snapshot(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let style_manager = testutils::init_style_manager(wm);
+
+        let channel = Elem::new(model::Channel {
+            name: "#stella".to_owned(),
+            messages: Elem::new(vec![
+                message("alice", (9, 41), "hello"),
+                message("bob", (9, 42), "hi alice"),
+                message(
+                    "alice",
+                    (12, 3),
+                    "this message is long enough to be wrapped into multiple \
+                     lines by the text layout engine",
+                ),
+            ]),
+            unread_count: 0,
+            has_older_messages: false,
+            loading_older_messages: false,
+        });
+
+        let log_view = LogViewBuilder::new()
+            .with_wm(wm)
+            .with_style_manager(style_manager)
+            .with_channel(Some(channel))
+            .build();
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(log_view.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = testutils::expect_single_wnd(twm);
+        twm.set_wnd_size(&pal_hwnd, [400, 200]);
+        twm.step_unsend();
+
+        assert_wnd_snapshot(
+            twm,
+            &pal_hwnd,
+            testutils::snapshot_path("logview"),
+            &testutils::SNAPSHOT_OPTIONS,
+        );
+    }
+}
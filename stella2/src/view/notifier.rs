@@ -0,0 +1,182 @@
+//! Desktop notifications for received messages
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+use tcw3::{
+    pal,
+    pal::prelude::*,
+    uicore::{Notification, NotificationId},
+};
+
+use crate::model;
+
+/// The maximum number of characters of a message displayed in a notification.
+const MAX_BODY_LEN: usize = 200;
+
+/// Displays a desktop notification for each channel having new messages the
+/// user hasn't seen.
+pub(super) struct Notifier {
+    wm: pal::Wm,
+    shared: Rc<Shared>,
+}
+
+struct Shared {
+    /// The displayed notification of each channel. The second value is
+    /// a serial number used to tell the latest `Listener` of the channel from
+    /// the ones of replaced notifications, which share the same
+    /// `NotificationId`.
+    notes: RefCell<HashMap<model::ChannelRef, (NotificationId, u64)>>,
+    next_serial: Cell<u64>,
+    on_activate: RefCell<Box<dyn Fn(model::ChannelRef)>>,
+}
+
+impl Notifier {
+    pub(super) fn new(wm: pal::Wm) -> Self {
+        Self {
+            wm,
+            shared: Rc::new(Shared {
+                notes: RefCell::new(HashMap::new()),
+                next_serial: Cell::new(0),
+                on_activate: RefCell::new(Box::new(|_| {})),
+            }),
+        }
+    }
+
+    /// Set the function called when the user clicks a notification.
+    pub(super) fn set_on_activate(&self, cb: impl Fn(model::ChannelRef) + 'static) {
+        *self.shared.on_activate.borrow_mut() = Box::new(cb);
+    }
+
+    /// Notify the user of a message received in `chref`. The notification
+    /// previously displayed for the channel is replaced.
+    pub(super) fn message_received(&self, chref: &model::ChannelRef, message: &model::Message) {
+        let body = format!("{}: {}", message.sender, message.text);
+        let body = if body.chars().count() > MAX_BODY_LEN {
+            let mut body: String = body.chars().take(MAX_BODY_LEN - 1).collect();
+            body.push('…');
+            body
+        } else {
+            body
+        };
+
+        let serial = self.shared.next_serial.get();
+        self.shared.next_serial.set(serial + 1);
+
+        let replaces = self.shared.notes.borrow().get(chref).map(|&(id, _)| id);
+
+        let id = Notification::new(chref.channel.clone(), body).show(
+            self.wm,
+            replaces,
+            Box::new(Listener {
+                shared: Rc::downgrade(&self.shared),
+                chref: chref.clone(),
+                serial,
+            }),
+        );
+
+        self.shared
+            .notes
+            .borrow_mut()
+            .insert(chref.clone(), (id, serial));
+    }
+
+    /// Close the notification of `chref` (if any) because the user has seen
+    /// the channel.
+    pub(super) fn dismiss(&self, chref: &model::ChannelRef) {
+        let note = self.shared.notes.borrow_mut().remove(chref);
+        if let Some((id, _)) = note {
+            self.wm.close_notification(id);
+        }
+    }
+}
+
+struct Listener {
+    shared: Weak<Shared>,
+    chref: model::ChannelRef,
+    serial: u64,
+}
+
+impl pal::iface::NotificationListener<pal::Wm> for Listener {
+    fn activated(&self, _: pal::Wm, _: NotificationId, _: Option<&str>) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.on_activate.borrow()(self.chref.clone());
+        }
+    }
+
+    fn closed(&self, _: pal::Wm, _: NotificationId) {
+        if let Some(shared) = self.shared.upgrade() {
+            let mut notes = shared.notes.borrow_mut();
+            if notes.get(&self.chref).map(|&(_, serial)| serial) == Some(self.serial) {
+                notes.remove(&self.chref);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tcw3::testing::{prelude::*, use_testing_wm};
+
+    use super::*;
+
+    fn chref(channel: &str) -> model::ChannelRef {
+        model::ChannelRef {
+            account: model::AccountId(0),
+            server: "irc.example.com".to_owned(),
+            channel: channel.to_owned(),
+        }
+    }
+
+    fn message(text: &str) -> model::Message {
+        model::Message {
+            sender: "alice".to_owned(),
+            timestamp: chrono::Utc::now(),
+            text: text.to_owned(),
+        }
+    }
+
+    #[use_testing_wm]
+    #[test]
+    fn replace_and_activate(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let notifier = Notifier::new(wm);
+
+        let activated = Rc::new(RefCell::new(Vec::new()));
+        {
+            let activated = Rc::clone(&activated);
+            notifier.set_on_activate(move |chref| activated.borrow_mut().push(chref));
+        }
+
+        notifier.message_received(&chref("#a"), &message("hello"));
+        notifier.message_received(&chref("#b"), &message("hi"));
+        notifier.message_received(&chref("#a"), &message("anyone?"));
+        twm.step_unsend();
+
+        // The second message in `#a` replaced the first one
+        let notes = twm.notifications();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].notification.title, "#a");
+        assert_eq!(notes[0].notification.body, "alice: anyone?");
+        assert_eq!(notes[1].notification.title, "#b");
+
+        twm.activate_notification(notes[1].id, None);
+        assert_eq!(*activated.borrow(), [chref("#b")]);
+
+        // The replaced notification's listener must not forget the latest
+        // notification
+        notifier.dismiss(&chref("#a"));
+        twm.step_unsend();
+        let notes = twm.notifications();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].notification.title, "#b");
+
+        // A notification dismissed by the user is forgotten
+        twm.dismiss_notification(notes[0].id);
+        notifier.message_received(&chref("#b"), &message("bye"));
+        let notes = twm.notifications();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].notification.body, "alice: bye");
+    }
+}
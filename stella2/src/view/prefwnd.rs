@@ -1,3 +1,4 @@
+use harmony::Elem;
 use std::{
     cell::RefCell,
     rc::{Rc, Weak},
@@ -20,13 +21,14 @@ pub(super) struct PrefWndView {
 }
 
 impl PrefWndView {
-    pub(super) fn new(wm: pal::Wm) -> Rc<Self> {
+    pub(super) fn new(wm: pal::Wm, settings: Elem<model::Settings>) -> Rc<Self> {
         let hwnd = HWnd::new(wm);
         let style_manager = theming::Manager::global(wm);
 
         let pref_view = PrefViewBuilder::new()
             .with_wm(wm)
             .with_style_manager(style_manager)
+            .with_settings(settings)
             .build();
 
         hwnd.content_view()
@@ -77,6 +79,10 @@ impl PrefWndView {
         *self.dispatch.borrow_mut() = Box::new(cb);
     }
 
+    pub(super) fn poll(&self, new_settings: &Elem<model::Settings>) {
+        self.pref_view.set_settings(Elem::clone(new_settings));
+    }
+
     fn update_wnd_style_flags(hwnd: HWndRef, is_focused: bool) {
         hwnd.set_style_flags(
             if stylesheet::ENABLE_BACKDROP_BLUR && is_focused {
@@ -109,11 +115,11 @@ impl WndListener for PrefWndViewWndListener {
 
     fn interpret_event(
         &self,
-        _: pal::Wm,
+        wm: pal::Wm,
         _: HWndRef<'_>,
         ctx: &mut tcw3::uicore::InterpretEventCtx<'_>,
     ) {
-        global::interpret_event(ctx);
+        global::interpret_event(wm, ctx);
     }
 
     fn perform_action(&self, _: pal::Wm, _: HWndRef<'_>, _: ActionId) {
@@ -124,3 +130,60 @@ impl WndListener for PrefWndViewWndListener {
 stella2_meta::designer_impl! {
     crate::view::prefwnd::PrefView
 }
+
+/// The number of steps of the font size slider. Each step corresponds to
+/// one point.
+const FONT_SIZE_STEPS: u32 = model::Settings::FONT_SIZE_MAX - model::Settings::FONT_SIZE_MIN;
+
+fn font_size_to_slider_value(x: u32) -> f64 {
+    (x - model::Settings::FONT_SIZE_MIN) as f64 / FONT_SIZE_STEPS as f64
+}
+
+fn font_size_from_slider_value(x: f64) -> u32 {
+    model::Settings::FONT_SIZE_MIN + (x * FONT_SIZE_STEPS as f64).round() as u32
+}
+
+/// Get the tag value of the item representing `x` in the time format list.
+fn time_format_to_index(x: model::TimeFormat) -> u32 {
+    match x {
+        model::TimeFormat::H12 => 0,
+        model::TimeFormat::H24 => 1,
+    }
+}
+
+fn time_format_from_index(x: u32) -> model::TimeFormat {
+    match x {
+        0 => model::TimeFormat::H12,
+        _ => model::TimeFormat::H24,
+    }
+}
+
+// The snapshot test requires the testing backend
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use tcw3::testing::{prelude::*, snapshot::assert_wnd_snapshot, use_testing_wm};
+
+    use super::*;
+    use crate::view::testutils;
+
+    #[use_testing_wm]
+    #[test]
+    fn snapshot(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        testutils::init_style_manager(wm);
+
+        let _pref_wnd = PrefWndView::new(wm, Elem::new(model::Settings::default()));
+        twm.step_unsend();
+
+        let pal_hwnd = testutils::expect_single_wnd(twm);
+        twm.set_wnd_size(&pal_hwnd, [500, 400]);
+        twm.step_unsend();
+
+        assert_wnd_snapshot(
+            twm,
+            &pal_hwnd,
+            testutils::snapshot_path("prefview"),
+            &testutils::SNAPSHOT_OPTIONS,
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Utilities for testing views.
+#[cfg(feature = "testing")]
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+#[cfg(feature = "testing")]
+use tcw3::testing::snapshot::SnapshotOptions;
+use tcw3::{pal, testing::prelude::*, ui::theming};
+
+use crate::{config::profile::Profile, stylesheet};
+
+/// The tolerance used for comparing snapshots. The text rendering depends on
+/// the fonts installed on the system, so this is quite lenient.
+#[cfg(feature = "testing")]
+pub(super) const SNAPSHOT_OPTIONS: SnapshotOptions = SnapshotOptions::new()
+    .with_channel_tolerance(8)
+    .with_max_differing_pixels(200);
+
+/// Register the application's stylesheet (only once) and get the style
+/// manager.
+pub(super) fn init_style_manager(wm: pal::Wm) -> &'static theming::Manager {
+    static INIT: Once = Once::new();
+
+    let style_manager = theming::Manager::global(wm);
+    INIT.call_once(|| {
+        // Use a nonexistent profile so that the user stylesheet is not loaded
+        let profile =
+            Profile::from_custom_dir(&std::env::temp_dir().join("stella2-test-nonexistent"));
+        stylesheet::register_stylesheet(style_manager, &profile);
+    });
+
+    style_manager
+}
+
+/// Get the only window that is currently open.
+pub(super) fn expect_single_wnd(twm: &dyn TestingWm) -> pal::HWnd {
+    let hwnds = twm.hwnds();
+    assert_eq!(hwnds.len(), 1, "expected a single window");
+    hwnds.into_iter().next().unwrap()
+}
+
+/// Get the path of the reference image named `name`.
+#[cfg(feature = "testing")]
+pub(super) fn snapshot_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/view/snapshots")
+        .join(format!("{}.png", name))
+}
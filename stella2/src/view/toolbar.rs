@@ -1,6 +1,9 @@
-use tcw3::{ui::theming, uicore::HViewRef};
+use tcw3::{
+    ui::theming,
+    uicore::{HView, HViewRef},
+};
 
-use crate::model;
+use crate::{model, view::global};
 
 stella2_meta::designer_impl! {
     crate::view::toolbar::ToolbarView
@@ -14,10 +17,8 @@ impl ToolbarView {
     }
 
     /// Handle `menu_button.activate` event.
-    fn toggle_pref(&self) {
-        // Show or hide the Preferences window
-        // TODO: Show a dropdown menu
-        self.raise_dispatch(model::AppAction::TogglePref);
+    fn show_menu(&self, anchor: HView) {
+        anchor.popup_menu(&global::main_popup_menu(self.wm()));
     }
 }
 
@@ -3,7 +3,10 @@ use arrayvec::ArrayVec;
 use cgmath::Point2;
 use rgb::FromSlice;
 
-use crate::{op, Cmd, BYTES_PER_POINT, CONTOUR_HDR_SIZE};
+use crate::{
+    ext_op, op, Cmd, FillRule, Gradient, GradientKind, GradientStop, LineCap, LineJoin,
+    BYTES_PER_POINT, CONTOUR_HDR_SIZE, EXT_HDR_SIZE,
+};
 
 /// An iterator over drawing commands in StellaVG data.
 ///
@@ -48,11 +51,7 @@ impl<'a> Iterator for CmdDecoder<'a> {
             let mut cps = ArrayVec::<[Point2<i16>; 3]>::new();
 
             loop {
-                let cp_data = &self.curve_points[0..BYTES_PER_POINT];
-                cps.push(Point2::new(
-                    <i16>::from_le_bytes([cp_data[0], cp_data[1]]),
-                    <i16>::from_le_bytes([cp_data[2], cp_data[3]]),
-                ));
+                cps.push(read_point(self.curve_points));
 
                 let i = self.curve_index;
                 self.curve_index += 1;
@@ -71,6 +70,16 @@ impl<'a> Iterator for CmdDecoder<'a> {
                 _ => unreachable!(),
             }
         } else if !self.data.is_empty() {
+            if self.op == 0 && self.data[0] == op::EXT {
+                let code = self.data[1];
+                let param_len = self.data[2] as usize;
+                let param = &self.data[EXT_HDR_SIZE..][..param_len];
+                self.data = &self.data[EXT_HDR_SIZE + param_len..];
+
+                // Skip unknown extended ops
+                return decode_ext(code, param).or_else(|| self.next());
+            }
+
             if self.op == 0 {
                 self.op = self.data[0];
                 self.param_len = 1;
@@ -99,10 +108,7 @@ impl<'a> Iterator for CmdDecoder<'a> {
                     let param = &self.data[self.param_len..][..6];
                     self.param_len += CONTOUR_HDR_SIZE;
 
-                    let start = Point2::new(
-                        <i16>::from_le_bytes([param[0], param[1]]),
-                        <i16>::from_le_bytes([param[2], param[3]]),
-                    );
+                    let start = read_point(param);
                     let num_points = <u16>::from_le_bytes([param[4], param[5]]) as usize;
 
                     let flags_len = (num_points + 7) / 8;
@@ -131,3 +137,49 @@ impl<'a> Iterator for CmdDecoder<'a> {
         }
     }
 }
+
+fn read_point(data: &[u8]) -> Point2<i16> {
+    Point2::new(
+        <i16>::from_le_bytes([data[0], data[1]]),
+        <i16>::from_le_bytes([data[2], data[3]]),
+    )
+}
+
+fn read_u16(data: &[u8]) -> u16 {
+    <u16>::from_le_bytes([data[0], data[1]])
+}
+
+fn read_gradient(param: &[u8]) -> Gradient {
+    Gradient {
+        kind: GradientKind::from_u8(param[0]),
+        frame: [
+            read_point(&param[1..]),
+            read_point(&param[1 + BYTES_PER_POINT..]),
+            read_point(&param[1 + BYTES_PER_POINT * 2..]),
+        ],
+    }
+}
+
+/// Decode an extended op. Returns `None` if `code` is unknown.
+fn decode_ext(code: u8, param: &[u8]) -> Option<Cmd> {
+    Some(match code {
+        ext_op::SET_FILL_RULE => Cmd::SetFillRule(FillRule::from_u8(param[0])),
+        ext_op::SET_STROKE_RGB => Cmd::SetStrokeRgb(param[..4].as_rgba()[0]),
+        ext_op::STROKE => Cmd::Stroke,
+        ext_op::SET_LINE_WIDTH => Cmd::SetLineWidth(read_u16(param)),
+        ext_op::SET_LINE_CAP => Cmd::SetLineCap(LineCap::from_u8(param[0])),
+        ext_op::SET_LINE_JOIN => Cmd::SetLineJoin(LineJoin::from_u8(param[0])),
+        ext_op::SET_MITER_LIMIT => Cmd::SetMiterLimit(read_u16(param)),
+        ext_op::ADD_LINE_DASH => Cmd::AddLineDash(read_u16(param)),
+        ext_op::SET_LINE_DASH => Cmd::SetLineDash(read_u16(param) as i16),
+        ext_op::ADD_GRADIENT_STOP => Cmd::AddGradientStop(GradientStop {
+            offset: read_u16(param),
+            color: param[2..6].as_rgba()[0],
+        }),
+        ext_op::SET_FILL_GRADIENT => Cmd::SetFillGradient(read_gradient(param)),
+        ext_op::SET_STROKE_GRADIENT => Cmd::SetStrokeGradient(read_gradient(param)),
+        ext_op::BEGIN_GROUP => Cmd::BeginGroup(param[0]),
+        ext_op::END_GROUP => Cmd::EndGroup,
+        _ => return None,
+    })
+}
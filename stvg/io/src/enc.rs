@@ -3,7 +3,9 @@ use arrayvec::ArrayVec;
 use cgmath::Point2;
 use rgb::RGBA8;
 
-use crate::{op, Cmd, BYTES_PER_POINT};
+use crate::{
+    ext_op, op, Cmd, FillRule, Gradient, GradientStop, LineCap, LineJoin, BYTES_PER_POINT,
+};
 
 /// Encodes StellaVG data.
 ///
@@ -138,6 +140,79 @@ impl CmdEncoder {
         self.fill_rgba = color.into();
     }
 
+    /// Encode an extended operation.
+    fn ext(&mut self, code: u8, param: &[u8]) {
+        // Extended operations cannot be combined with other op flags
+        self.finalize_op();
+
+        self.data.push(op::EXT);
+        self.data.push(code);
+        self.data.push(param.len() as u8);
+        self.data.extend_from_slice(param);
+    }
+
+    pub fn set_fill_rule(&mut self, rule: FillRule) {
+        self.ext(ext_op::SET_FILL_RULE, &[rule as u8]);
+    }
+
+    pub fn set_fill_gradient(&mut self, gradient: Gradient) {
+        self.ext(ext_op::SET_FILL_GRADIENT, &gradient_to_array(gradient));
+    }
+
+    pub fn set_stroke_rgb(&mut self, color: RGBA8) {
+        let color: [u8; 4] = color.into();
+        self.ext(ext_op::SET_STROKE_RGB, &color);
+    }
+
+    pub fn set_stroke_gradient(&mut self, gradient: Gradient) {
+        self.ext(ext_op::SET_STROKE_GRADIENT, &gradient_to_array(gradient));
+    }
+
+    pub fn add_gradient_stop(&mut self, stop: GradientStop) {
+        let offset = stop.offset.to_le_bytes();
+        let color: [u8; 4] = stop.color.into();
+        self.ext(
+            ext_op::ADD_GRADIENT_STOP,
+            &[offset[0], offset[1], color[0], color[1], color[2], color[3]],
+        );
+    }
+
+    pub fn stroke(&mut self) {
+        self.ext(ext_op::STROKE, &[]);
+    }
+
+    pub fn set_line_width(&mut self, width: u16) {
+        self.ext(ext_op::SET_LINE_WIDTH, &width.to_le_bytes());
+    }
+
+    pub fn set_line_cap(&mut self, cap: LineCap) {
+        self.ext(ext_op::SET_LINE_CAP, &[cap as u8]);
+    }
+
+    pub fn set_line_join(&mut self, join: LineJoin) {
+        self.ext(ext_op::SET_LINE_JOIN, &[join as u8]);
+    }
+
+    pub fn set_miter_limit(&mut self, miter_limit: u16) {
+        self.ext(ext_op::SET_MITER_LIMIT, &miter_limit.to_le_bytes());
+    }
+
+    pub fn add_line_dash(&mut self, length: u16) {
+        self.ext(ext_op::ADD_LINE_DASH, &length.to_le_bytes());
+    }
+
+    pub fn set_line_dash(&mut self, phase: i16) {
+        self.ext(ext_op::SET_LINE_DASH, &phase.to_le_bytes());
+    }
+
+    pub fn begin_group(&mut self, opacity: u8) {
+        self.ext(ext_op::BEGIN_GROUP, &[opacity]);
+    }
+
+    pub fn end_group(&mut self) {
+        self.ext(ext_op::END_GROUP, &[]);
+    }
+
     pub fn cmd(&mut self, cmd: Cmd) {
         match cmd {
             Cmd::BeginPath => self.begin_path(),
@@ -147,6 +222,20 @@ impl CmdEncoder {
             Cmd::QuadBezierTo(cps) => self.quad_bezier_to(cps),
             Cmd::CubicBezierTo(cps) => self.cubic_bezier_to(cps),
             Cmd::SetFillRgb(color) => self.set_fill_rgb(color),
+            Cmd::SetFillRule(rule) => self.set_fill_rule(rule),
+            Cmd::SetFillGradient(gradient) => self.set_fill_gradient(gradient),
+            Cmd::SetStrokeRgb(color) => self.set_stroke_rgb(color),
+            Cmd::SetStrokeGradient(gradient) => self.set_stroke_gradient(gradient),
+            Cmd::AddGradientStop(stop) => self.add_gradient_stop(stop),
+            Cmd::Stroke => self.stroke(),
+            Cmd::SetLineWidth(width) => self.set_line_width(width),
+            Cmd::SetLineCap(cap) => self.set_line_cap(cap),
+            Cmd::SetLineJoin(join) => self.set_line_join(join),
+            Cmd::SetMiterLimit(miter_limit) => self.set_miter_limit(miter_limit),
+            Cmd::AddLineDash(length) => self.add_line_dash(length),
+            Cmd::SetLineDash(phase) => self.set_line_dash(phase),
+            Cmd::BeginGroup(opacity) => self.begin_group(opacity),
+            Cmd::EndGroup => self.end_group(),
         }
     }
 }
@@ -172,3 +261,15 @@ fn point_to_array(p: Point2<i16>) -> [u8; BYTES_PER_POINT] {
     let y = p.y.to_le_bytes();
     [x[0], x[1], y[0], y[1]]
 }
+
+fn gradient_to_array(gradient: Gradient) -> [u8; 1 + BYTES_PER_POINT * 3] {
+    let mut out = [0; 1 + BYTES_PER_POINT * 3];
+    out[0] = gradient.kind as u8;
+    for (chunk, &p) in out[1..]
+        .chunks_exact_mut(BYTES_PER_POINT)
+        .zip(gradient.frame.iter())
+    {
+        chunk.copy_from_slice(&point_to_array(p));
+    }
+    out
+}
@@ -32,10 +32,87 @@ mod op {
     /// This flag must be the last one.
     pub const CONTOUR: u8 = 1 << CONTOUR_SHIFT;
     pub const CONTOUR_SHIFT: u32 = 3;
+
+    /// Perform an extended operation. Followed by an extended op code (one of
+    /// the constants defined in [`ext_op`]), the length of the parameters in
+    /// bytes (`u8`), and the parameters.
+    ///
+    /// This flag cannot be combined with other flags.
+    ///
+    /// [`ext_op`]: super::ext_op
+    pub const EXT: u8 = 1 << 4;
+}
+
+/// The extended op codes used with [`op::EXT`]. The decoder skips unknown
+/// extended op codes.
+///
+/// The extended operations were added after the initial version of the
+/// format. Data not using them is encoded in the same way as before.
+mod ext_op {
+    /// Set the current fill rule. Followed by a `u8` value ([`FillRule`]).
+    ///
+    /// [`FillRule`]: super::FillRule
+    pub const SET_FILL_RULE: u8 = 0;
+
+    /// Set the current stroke color. Followed by a 4-byte color value.
+    pub const SET_STROKE_RGB: u8 = 1;
+
+    /// Stroke the current path.
+    pub const STROKE: u8 = 2;
+
+    /// Set the current line width. Followed by a fixed-point `u16` value.
+    pub const SET_LINE_WIDTH: u8 = 3;
+
+    /// Set the current line cap style. Followed by a `u8` value
+    /// ([`LineCap`]).
+    ///
+    /// [`LineCap`]: super::LineCap
+    pub const SET_LINE_CAP: u8 = 4;
+
+    /// Set the current line join style. Followed by a `u8` value
+    /// ([`LineJoin`]).
+    ///
+    /// [`LineJoin`]: super::LineJoin
+    pub const SET_LINE_JOIN: u8 = 5;
+
+    /// Set the current miter limit. Followed by a fixed-point `u16` value.
+    pub const SET_MITER_LIMIT: u8 = 6;
+
+    /// Append a dash length to the pending dash pattern. Followed by a
+    /// fixed-point `u16` value.
+    pub const ADD_LINE_DASH: u8 = 7;
+
+    /// Replace the current dash pattern with the pending one, and clear the
+    /// pending dash pattern. Followed by a fixed-point `i16` value (phase).
+    pub const SET_LINE_DASH: u8 = 8;
+
+    /// Append a color stop to the pending gradient. Followed by a `u16` value
+    /// (offset) and a 4-byte color value.
+    pub const ADD_GRADIENT_STOP: u8 = 9;
+
+    /// Set the current fill brush to a gradient created from the pending
+    /// color stops, and clear the pending color stops. Followed by a `u8`
+    /// value ([`GradientKind`]) and three points.
+    ///
+    /// [`GradientKind`]: super::GradientKind
+    pub const SET_FILL_GRADIENT: u8 = 10;
+
+    /// Set the current stroke brush to a gradient. The parameters are
+    /// identical to those of [`SET_FILL_GRADIENT`].
+    pub const SET_STROKE_GRADIENT: u8 = 11;
+
+    /// Start an opacity group. Followed by a `u8` value (opacity).
+    pub const BEGIN_GROUP: u8 = 12;
+
+    /// End the current opacity group.
+    pub const END_GROUP: u8 = 13;
 }
 
 const BYTES_PER_POINT: usize = 4;
 const CONTOUR_HDR_SIZE: usize = BYTES_PER_POINT + size_of::<u16>();
+/// The size of the header of an extended operation: `op::EXT`, an extended op
+/// code, and the parameter length.
+const EXT_HDR_SIZE: usize = 3;
 
 /// The number of fractional bits included in fixed-point numbers used by
 /// StellaVG.
@@ -54,4 +131,132 @@ pub enum Cmd {
     QuadBezierTo([Point2<i16>; 2]),
     CubicBezierTo([Point2<i16>; 3]),
     SetFillRgb(RGBA8),
+    /// Set the fill rule used by [`Cmd::Fill`]. Defaults to
+    /// [`FillRule::NonZero`].
+    SetFillRule(FillRule),
+    /// Set the current fill brush to a gradient having the color stops
+    /// specified by the preceding [`Cmd::AddGradientStop`]s.
+    SetFillGradient(Gradient),
+    SetStrokeRgb(RGBA8),
+    /// Set the current stroke brush to a gradient having the color stops
+    /// specified by the preceding [`Cmd::AddGradientStop`]s.
+    SetStrokeGradient(Gradient),
+    /// Append a color stop to the gradient to be created by the next
+    /// [`Cmd::SetFillGradient`] or [`Cmd::SetStrokeGradient`].
+    AddGradientStop(GradientStop),
+    Stroke,
+    /// Set the line width. The value is a fixed-point number (see
+    /// [`FRAC_BITS`]).
+    SetLineWidth(u16),
+    SetLineCap(LineCap),
+    SetLineJoin(LineJoin),
+    /// Set the miter limit. The value is a fixed-point number (see
+    /// [`FRAC_BITS`]).
+    SetMiterLimit(u16),
+    /// Append a dash length (a fixed-point number) to the dash pattern to be
+    /// set by the next [`Cmd::SetLineDash`].
+    AddLineDash(u16),
+    /// Set the dash pattern to the one specified by the preceding
+    /// [`Cmd::AddLineDash`]s. The value is the dash phase (a fixed-point
+    /// number). An empty dash pattern means a solid line.
+    SetLineDash(i16),
+    /// Start an opacity group. The contents of the group are composited with
+    /// the specified opacity (`255` = opaque) by the matching
+    /// [`Cmd::EndGroup`].
+    BeginGroup(u8),
+    EndGroup,
+}
+
+/// Specifies how to determine the inside of a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FillRule {
+    NonZero = 0,
+    EvenOdd = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineCap {
+    Butt = 0,
+    Round = 1,
+    Square = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineJoin {
+    Miter = 0,
+    Round = 1,
+    Bevel = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GradientKind {
+    /// The gradient position is the X coordinate in the gradient space.
+    Linear = 0,
+    /// The gradient position is the distance from the origin in the gradient
+    /// space.
+    Radial = 1,
+}
+
+/// A gradient brush.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    /// The points in the path coordinate space defining the gradient space.
+    /// The elements represent the origin, `(1, 0)`, and `(0, 1)` in the
+    /// gradient space, respectively.
+    ///
+    /// Gradient positions outside the range `[0, 1]` are clamped to the
+    /// range.
+    pub frame: [Point2<i16>; 3],
+}
+
+/// A color stop of a gradient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GradientStop {
+    /// The gradient position of the color stop. `0` and `u16::max_value()`
+    /// represent `0.0` and `1.0`, respectively.
+    pub offset: u16,
+    pub color: RGBA8,
+}
+
+impl FillRule {
+    fn from_u8(x: u8) -> Self {
+        match x {
+            0 => FillRule::NonZero,
+            1 => FillRule::EvenOdd,
+            _ => panic!("invalid fill rule"),
+        }
+    }
+}
+
+impl LineCap {
+    fn from_u8(x: u8) -> Self {
+        match x {
+            0 => LineCap::Butt,
+            1 => LineCap::Round,
+            2 => LineCap::Square,
+            _ => panic!("invalid line cap"),
+        }
+    }
+}
+
+impl LineJoin {
+    fn from_u8(x: u8) -> Self {
+        match x {
+            0 => LineJoin::Miter,
+            1 => LineJoin::Round,
+            2 => LineJoin::Bevel,
+            _ => panic!("invalid line join"),
+        }
+    }
+}
+
+impl GradientKind {
+    fn from_u8(x: u8) -> Self {
+        match x {
+            0 => GradientKind::Linear,
+            1 => GradientKind::Radial,
+            _ => panic!("invalid gradient kind"),
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use cgmath::Point2;
 use rgb::RGBA8;
 
-use stvg_io::Cmd;
+use stvg_io::{Cmd, FillRule, Gradient, GradientKind, GradientStop, LineCap, LineJoin};
 
 fn decode(b: &[u8]) -> Vec<Cmd> {
     stvg_io::CmdDecoder::from_bytes(b).collect()
@@ -44,3 +44,96 @@ fn roundtrip() {
 
     assert_eq!(decoded_cmds, cmds);
 }
+
+#[test]
+fn roundtrip_ext() {
+    let stops = [
+        GradientStop {
+            offset: 0,
+            color: RGBA8::new(1, 2, 3, 4),
+        },
+        GradientStop {
+            offset: 0xffff,
+            color: RGBA8::new(5, 6, 7, 8),
+        },
+    ];
+    let gradient = Gradient {
+        kind: GradientKind::Radial,
+        frame: [
+            Point2::new(100, 200),
+            Point2::new(300, 200),
+            Point2::new(100, -400),
+        ],
+    };
+
+    let cmds = vec![
+        Cmd::SetFillRule(FillRule::EvenOdd),
+        Cmd::BeginPath,
+        Cmd::MoveTo(Point2::new(1000, 2000)),
+        Cmd::LineTo(Point2::new(1000, 2100)),
+        Cmd::BeginGroup(128),
+        Cmd::AddGradientStop(stops[0]),
+        Cmd::AddGradientStop(stops[1]),
+        Cmd::SetFillGradient(gradient),
+        Cmd::Fill,
+        Cmd::SetStrokeRgb(RGBA8::new(42, 43, 44, 45)),
+        Cmd::SetLineWidth(32),
+        Cmd::SetLineCap(LineCap::Round),
+        Cmd::SetLineJoin(LineJoin::Miter),
+        Cmd::SetMiterLimit(64),
+        Cmd::AddLineDash(16),
+        Cmd::AddLineDash(48),
+        Cmd::SetLineDash(-8),
+        Cmd::Stroke,
+        Cmd::AddGradientStop(stops[1]),
+        Cmd::SetStrokeGradient(Gradient {
+            kind: GradientKind::Linear,
+            ..gradient
+        }),
+        Cmd::BeginPath,
+        Cmd::MoveTo(Point2::new(1000, 2000)),
+        Cmd::LineTo(Point2::new(1000, 2100)),
+        Cmd::Stroke,
+        Cmd::EndGroup,
+        Cmd::SetFillRgb(RGBA8::new(42, 43, 44, 45)),
+        Cmd::Fill,
+    ];
+
+    let bytes = encode(cmds.iter().cloned());
+
+    let decoded_cmds = decode(&bytes);
+
+    assert_eq!(decoded_cmds, cmds);
+}
+
+#[test]
+fn decode_basic_ops() {
+    // Data not using extended ops is encoded in the same way as before they
+    // were introduced
+    let bytes = [
+        0b1111, // `SET_FILL_RGB | FILL | BEGIN_PATH | CONTOUR`
+        1, 2, 3, 4, // color
+        10, 0, 20, 0, 1, 0, 1, 30, 0, 40, 0, // path data
+    ];
+    let cmds = vec![
+        Cmd::SetFillRgb(RGBA8::new(1, 2, 3, 4)),
+        Cmd::Fill,
+        Cmd::BeginPath,
+        Cmd::MoveTo(Point2::new(10, 20)),
+        Cmd::LineTo(Point2::new(30, 40)),
+    ];
+
+    assert_eq!(decode(&bytes), cmds);
+    assert_eq!(encode(cmds.iter().cloned()), &bytes[..]);
+}
+
+#[test]
+fn skip_unknown_ext_ops() {
+    let bytes = [
+        0b10000, 0xff, 2, 42, 43, // unknown extended op
+        0b10000, 0xfe, 0,      // unknown extended op
+        0b0010, // `FILL`
+    ];
+
+    assert_eq!(decode(&bytes), vec![Cmd::Fill]);
+}
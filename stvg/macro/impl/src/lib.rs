@@ -6,7 +6,7 @@ use pathfinder_geometry as pf_geo;
 use quote::quote;
 use rgb::RGBA8;
 use std::path::Path;
-use stvg_io::CmdEncoder;
+use stvg_io::{CmdEncoder, FillRule, Gradient, GradientKind, GradientStop};
 use syn::{parse_macro_input, spanned::Spanned, Lit, LitByteStr};
 
 #[proc_macro]
@@ -46,7 +46,11 @@ pub fn include_stvg(params: proc_macro::TokenStream) -> proc_macro::TokenStream
     let size;
 
     let mut converter = Converter {
+        tree: &svg_tree,
         encoder: CmdEncoder::new(),
+        fill_rule: FillRule::NonZero,
+        stroke_style: None,
+        errors: Vec::new(),
     };
 
     use usvg::NodeKind;
@@ -75,12 +79,23 @@ pub fn include_stvg(params: proc_macro::TokenStream) -> proc_macro::TokenStream
             );
 
             for child in svg_root.children() {
-                converter.process_node(&child, &xform, 1.0);
+                converter.process_node(&child, &xform);
             }
         }
         _ => unreachable!(),
     }
 
+    if !converter.errors.is_empty() {
+        let message = format!(
+            "could not convert {:?}: {}",
+            path,
+            converter.errors.join("; ")
+        );
+        return syn::Error::new_spanned(path_lit, message)
+            .to_compile_error()
+            .into();
+    }
+
     let stvg_bytes = converter.encoder.take_bytes();
 
     let syn_bytes = LitByteStr::new(&stvg_bytes, path_lit.span());
@@ -93,162 +108,398 @@ pub fn include_stvg(params: proc_macro::TokenStream) -> proc_macro::TokenStream
     .into()
 }
 
-struct Converter {
+struct Converter<'a> {
+    tree: &'a usvg::Tree,
     encoder: CmdEncoder,
+    /// The last fill rule passed to the encoder.
+    fill_rule: FillRule,
+    /// The last stroke style passed to the encoder.
+    stroke_style: Option<StrokeStyle>,
+    /// The reasons the conversion failed.
+    errors: Vec<String>,
+}
+
+/// The stroke parameters not including the paint.
+#[derive(Debug, Clone, PartialEq)]
+struct StrokeStyle {
+    width: u16,
+    cap: stvg_io::LineCap,
+    join: stvg_io::LineJoin,
+    miter_limit: u16,
+    dash: Vec<u16>,
+    dash_phase: i16,
 }
 
-impl Converter {
-    fn process_node(&mut self, node: &usvg::Node, xform: &usvg::Transform, opacity: f32) {
-        use usvg::{NodeExt, NodeKind, PathSegment};
+impl Converter<'_> {
+    fn process_node(&mut self, node: &usvg::Node, xform: &usvg::Transform) {
+        use usvg::{NodeExt, NodeKind};
 
         let mut node_xform = *xform;
         node_xform.append(&node.transform());
 
         match &*node.borrow() {
             NodeKind::Group(group) => {
-                let g_opacity = group.opacity.value() as f32;
+                let g_opacity = group.opacity.value();
+
+                // Use an opacity group so that overlapping shapes in the group
+                // don't show through each other
+                let use_group = g_opacity < 1.0;
+                if use_group {
+                    self.encoder.begin_group((g_opacity * 255.0).round() as u8);
+                }
 
                 for child in node.children() {
-                    self.process_node(&child, &node_xform, opacity * g_opacity);
+                    self.process_node(&child, &node_xform);
+                }
+
+                if use_group {
+                    self.encoder.end_group();
                 }
             }
             NodeKind::Path(path) if path.visibility == usvg::Visibility::Visible => {
+                let bbox = path.data.bbox();
+
                 if let Some(fill) = &path.fill {
-                    set_paint_as_fill(
-                        &mut self.encoder,
-                        &fill.paint,
-                        opacity * fill.opacity.value() as f32,
-                    );
-
-                    let segments = path
-                        .data
-                        .subpaths()
-                        .map(|subpath| subpath.0)
-                        .flatten()
-                        .cloned();
+                    if let Some(gradient) =
+                        self.paint_gradient(&fill.paint, fill.opacity.value(), bbox, &node_xform)
+                    {
+                        self.encoder.set_fill_gradient(gradient);
+                    } else if let usvg::Paint::Color(color) = fill.paint {
+                        self.encoder
+                            .set_fill_rgb(rgba8_from_usvg_color(color, fill.opacity.value()));
+                    }
+
+                    self.set_fill_rule(match fill.rule {
+                        usvg::FillRule::NonZero => FillRule::NonZero,
+                        usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+                    });
 
                     self.encoder.begin_path();
-                    for seg in segments {
-                        match seg {
-                            PathSegment::MoveTo { mut x, mut y } => {
-                                node_xform.apply_to(&mut x, &mut y);
-
-                                self.encoder.move_to(point_from(x, y));
-                            }
-                            PathSegment::LineTo { mut x, mut y } => {
-                                node_xform.apply_to(&mut x, &mut y);
-
-                                self.encoder.line_to(point_from(x, y));
-                            }
-                            PathSegment::CurveTo {
-                                mut x1,
-                                mut y1,
-                                mut x2,
-                                mut y2,
-                                mut x,
-                                mut y,
-                            } => {
-                                node_xform.apply_to(&mut x1, &mut y1);
-                                node_xform.apply_to(&mut x2, &mut y2);
-                                node_xform.apply_to(&mut x, &mut y);
-
-                                self.encoder.cubic_bezier_to([
-                                    point_from(x1, y1),
-                                    point_from(x2, y2),
-                                    point_from(x, y),
-                                ]);
-                            }
-                            PathSegment::ClosePath => {}
-                        }
-                    }
+                    self.encode_path(&path.data, &node_xform);
                     self.encoder.fill();
                 } // let Some(fill)
 
                 if let Some(stroke) = &path.stroke {
-                    use self::pf_geo::{
-                        outline::Outline, segment::SegmentKind, stroke::OutlineStrokeToFill,
-                    };
-                    set_paint_as_fill(
-                        &mut self.encoder,
-                        &stroke.paint,
-                        opacity * stroke.opacity.value() as f32,
-                    );
-
-                    // StellaVG doesn't support strokes, so convert them to fills
-                    let stroke_style = pf_geo::stroke::StrokeStyle {
-                        line_width: stroke.width.value() as f32,
-                        line_cap: pf_line_cap_from_usvg(stroke.linecap),
-                        line_join: pf_line_join_from_usvg(
-                            stroke.linejoin,
-                            stroke.miterlimit.value() as f32,
-                        ),
-                    };
-
-                    // Convert the path to a Pathfinder `Outline`
-                    let path = UsvgPathToSegments::new(
-                        path.data
-                            .subpaths()
-                            .map(|subpath| subpath.0)
-                            .flatten()
-                            .cloned(),
-                    );
-                    let outline = Outline::from_segments(path);
-
-                    // Stroke the `Outline`
-                    let mut stroke_to_fill = OutlineStrokeToFill::new(&outline, stroke_style);
-                    stroke_to_fill.offset();
-                    let mut outline = stroke_to_fill.into_outline();
-                    outline.transform(&pf_transform_2d_from_usvg(&node_xform));
-
-                    // Encode commands
-                    self.encoder.begin_path();
-                    for contour in outline.contours().iter() {
-                        self.encoder.move_to(point_from_pf(contour.position_of(0)));
-
-                        // Skip the last segment - Fills automatically closes
-                        // the path, so the last (closing) segment is redundant
-                        let count = contour.iter().count() - 1;
-
-                        for seg in contour.iter().take(count) {
-                            match seg.kind {
-                                SegmentKind::None => unreachable!(),
-                                SegmentKind::Line => {
-                                    self.encoder.line_to(point_from_pf(seg.baseline.to()));
-                                }
-                                SegmentKind::Quadratic => {
-                                    self.encoder.quad_bezier_to([
-                                        point_from_pf(seg.ctrl.from()),
-                                        point_from_pf(seg.baseline.to()),
-                                    ]);
-                                }
-                                SegmentKind::Cubic => {
-                                    self.encoder.cubic_bezier_to([
-                                        point_from_pf(seg.ctrl.from()),
-                                        point_from_pf(seg.ctrl.to()),
-                                        point_from_pf(seg.baseline.to()),
-                                    ]);
-                                }
-                            }
-                        }
+                    if let Some(scale) = similarity_scale(&node_xform) {
+                        self.encode_stroke(path, stroke, bbox, &node_xform, scale);
+                    } else {
+                        self.encode_stroke_as_fill(path, stroke, bbox, &node_xform);
                     }
-                    self.encoder.fill();
                 } // let Some(stroke)
             }
             _ => {}
         }
     }
-}
 
-fn set_paint_as_fill(encoder: &mut CmdEncoder, paint: &usvg::Paint, opacity: f32) {
-    match paint {
-        usvg::Paint::Color(color) => {
-            encoder.set_fill_rgb(rgba8_from_usvg_color(*color, opacity));
+    fn set_fill_rule(&mut self, fill_rule: FillRule) {
+        if self.fill_rule != fill_rule {
+            self.fill_rule = fill_rule;
+            self.encoder.set_fill_rule(fill_rule);
+        }
+    }
+
+    /// Encode the segments of `data` as the current path.
+    fn encode_path(&mut self, data: &usvg::PathData, xform: &usvg::Transform) {
+        use usvg::PathSegment;
+
+        let segments = data.subpaths().map(|subpath| subpath.0).flatten().cloned();
+
+        for seg in segments {
+            match seg {
+                PathSegment::MoveTo { mut x, mut y } => {
+                    xform.apply_to(&mut x, &mut y);
+
+                    self.encoder.move_to(point_from(x, y));
+                }
+                PathSegment::LineTo { mut x, mut y } => {
+                    xform.apply_to(&mut x, &mut y);
+
+                    self.encoder.line_to(point_from(x, y));
+                }
+                PathSegment::CurveTo {
+                    mut x1,
+                    mut y1,
+                    mut x2,
+                    mut y2,
+                    mut x,
+                    mut y,
+                } => {
+                    xform.apply_to(&mut x1, &mut y1);
+                    xform.apply_to(&mut x2, &mut y2);
+                    xform.apply_to(&mut x, &mut y);
+
+                    self.encoder.cubic_bezier_to([
+                        point_from(x1, y1),
+                        point_from(x2, y2),
+                        point_from(x, y),
+                    ]);
+                }
+                PathSegment::ClosePath => {}
+            }
         }
-        usvg::Paint::Link(link) => panic!("unsupported paint style: {:?}", link),
     }
+
+    /// Encode a stroke using StellaVG's stroke operation. `xform` must be a
+    /// similarity transformation that scales by `scale`.
+    fn encode_stroke(
+        &mut self,
+        path: &usvg::Path,
+        stroke: &usvg::Stroke,
+        bbox: Option<usvg::Rect>,
+        xform: &usvg::Transform,
+        scale: f64,
+    ) {
+        let opacity = stroke.opacity.value();
+        if let Some(gradient) = self.paint_gradient(&stroke.paint, opacity, bbox, xform) {
+            self.encoder.set_stroke_gradient(gradient);
+        } else if let usvg::Paint::Color(color) = stroke.paint {
+            self.encoder
+                .set_stroke_rgb(rgba8_from_usvg_color(color, opacity));
+        }
+
+        let style = StrokeStyle {
+            width: fixed_u16_from(stroke.width.value() * scale),
+            cap: match stroke.linecap {
+                usvg::LineCap::Butt => stvg_io::LineCap::Butt,
+                usvg::LineCap::Round => stvg_io::LineCap::Round,
+                usvg::LineCap::Square => stvg_io::LineCap::Square,
+            },
+            join: match stroke.linejoin {
+                usvg::LineJoin::Miter => stvg_io::LineJoin::Miter,
+                usvg::LineJoin::Round => stvg_io::LineJoin::Round,
+                usvg::LineJoin::Bevel => stvg_io::LineJoin::Bevel,
+            },
+            // The miter limit is a ratio, so it's not affected by `scale`
+            miter_limit: fixed_u16_from(
+                stroke.miterlimit.value() * (1 << stvg_io::FRAC_BITS) as f64,
+            ),
+            dash: (stroke.dasharray.iter().flatten())
+                .map(|&x| fixed_u16_from(x * scale))
+                .collect(),
+            dash_phase: if stroke.dasharray.is_some() {
+                point_from(stroke.dashoffset as f64 * scale, 0.0).x
+            } else {
+                0
+            },
+        };
+
+        if self.stroke_style.as_ref() != Some(&style) {
+            self.encoder.set_line_width(style.width);
+            self.encoder.set_line_cap(style.cap);
+            self.encoder.set_line_join(style.join);
+            self.encoder.set_miter_limit(style.miter_limit);
+            for &length in style.dash.iter() {
+                self.encoder.add_line_dash(length);
+            }
+            self.encoder.set_line_dash(style.dash_phase);
+
+            self.stroke_style = Some(style);
+        }
+
+        self.encoder.begin_path();
+        self.encode_path(&path.data, xform);
+        self.encoder.stroke();
+    }
+
+    /// Convert a stroke to a fill and encode it. This is used when `xform`
+    /// can't be expressed by StellaVG's stroke operation.
+    fn encode_stroke_as_fill(
+        &mut self,
+        path: &usvg::Path,
+        stroke: &usvg::Stroke,
+        bbox: Option<usvg::Rect>,
+        xform: &usvg::Transform,
+    ) {
+        use self::pf_geo::{outline::Outline, segment::SegmentKind, stroke::OutlineStrokeToFill};
+
+        // Pathfinder's stroke-to-fill conversion doesn't support dashes.
+        // Report an error instead of silently drawing a solid line.
+        if stroke.dasharray.is_some() {
+            self.errors.push(format!(
+                "path {:?} has a dashed stroke with a transform that is not a similarity \
+                 transform, which is not supported",
+                path.id
+            ));
+            return;
+        }
+
+        let opacity = stroke.opacity.value();
+        if let Some(gradient) = self.paint_gradient(&stroke.paint, opacity, bbox, xform) {
+            self.encoder.set_fill_gradient(gradient);
+        } else if let usvg::Paint::Color(color) = stroke.paint {
+            self.encoder
+                .set_fill_rgb(rgba8_from_usvg_color(color, opacity));
+        }
+        self.set_fill_rule(FillRule::NonZero);
+
+        let stroke_style = pf_geo::stroke::StrokeStyle {
+            line_width: stroke.width.value() as f32,
+            line_cap: pf_line_cap_from_usvg(stroke.linecap),
+            line_join: pf_line_join_from_usvg(stroke.linejoin, stroke.miterlimit.value() as f32),
+        };
+
+        // Convert the path to a Pathfinder `Outline`
+        let path = UsvgPathToSegments::new(
+            path.data
+                .subpaths()
+                .map(|subpath| subpath.0)
+                .flatten()
+                .cloned(),
+        );
+        let outline = Outline::from_segments(path);
+
+        // Stroke the `Outline`
+        let mut stroke_to_fill = OutlineStrokeToFill::new(&outline, stroke_style);
+        stroke_to_fill.offset();
+        let mut outline = stroke_to_fill.into_outline();
+        outline.transform(&pf_transform_2d_from_usvg(xform));
+
+        // Encode commands
+        self.encoder.begin_path();
+        for contour in outline.contours().iter() {
+            self.encoder.move_to(point_from_pf(contour.position_of(0)));
+
+            // Skip the last segment - Fills automatically closes
+            // the path, so the last (closing) segment is redundant
+            let count = contour.iter().count() - 1;
+
+            for seg in contour.iter().take(count) {
+                match seg.kind {
+                    SegmentKind::None => unreachable!(),
+                    SegmentKind::Line => {
+                        self.encoder.line_to(point_from_pf(seg.baseline.to()));
+                    }
+                    SegmentKind::Quadratic => {
+                        self.encoder.quad_bezier_to([
+                            point_from_pf(seg.ctrl.from()),
+                            point_from_pf(seg.baseline.to()),
+                        ]);
+                    }
+                    SegmentKind::Cubic => {
+                        self.encoder.cubic_bezier_to([
+                            point_from_pf(seg.ctrl.from()),
+                            point_from_pf(seg.ctrl.to()),
+                            point_from_pf(seg.baseline.to()),
+                        ]);
+                    }
+                }
+            }
+        }
+        self.encoder.fill();
+    }
+
+    /// If `paint` refers to a gradient, encode its color stops and return
+    /// the `Gradient` to be passed to the encoder. Returns `None` if `paint`
+    /// is a solid color.
+    ///
+    /// The focal point of radial gradients and spread methods other than
+    /// `pad` are not supported and are ignored.
+    fn paint_gradient(
+        &mut self,
+        paint: &usvg::Paint,
+        opacity: f64,
+        bbox: Option<usvg::Rect>,
+        xform: &usvg::Transform,
+    ) -> Option<Gradient> {
+        use usvg::NodeKind;
+
+        let id = match paint {
+            usvg::Paint::Color(_) => return None,
+            usvg::Paint::Link(id) => id,
+        };
+
+        let node = self
+            .tree
+            .defs_by_id(id)
+            .unwrap_or_else(|| panic!("undefined paint server: {:?}", id));
+
+        // Calculate the frame of the gradient space in the gradient's user
+        // space
+        let node = node.borrow();
+        let (kind, base, mut frame) = match &*node {
+            NodeKind::LinearGradient(g) => {
+                // Place the Y axis perpendicularly to the gradient vector
+                let (dx, dy) = (g.x2 - g.x1, g.y2 - g.y1);
+                (
+                    GradientKind::Linear,
+                    &g.base,
+                    [(g.x1, g.y1), (g.x2, g.y2), (g.x1 - dy, g.y1 + dx)],
+                )
+            }
+            NodeKind::RadialGradient(g) => {
+                let r = g.r.value();
+                (
+                    GradientKind::Radial,
+                    &g.base,
+                    [(g.cx, g.cy), (g.cx + r, g.cy), (g.cx, g.cy + r)],
+                )
+            }
+            _ => panic!("unsupported paint server: {:?}", id),
+        };
+
+        let mut g_xform = *xform;
+        if let usvg::Units::ObjectBoundingBox = base.units {
+            let bbox = bbox.expect("object bounding box is empty");
+            g_xform.append(&usvg::Transform::new(
+                bbox.width(),
+                0.0,
+                0.0,
+                bbox.height(),
+                bbox.x(),
+                bbox.y(),
+            ));
+        }
+        g_xform.append(&base.transform);
+
+        for (x, y) in frame.iter_mut() {
+            g_xform.apply_to(x, y);
+        }
+
+        for stop in base.stops.iter() {
+            let offset = stop.offset.value().max(0.0).min(1.0);
+            self.encoder.add_gradient_stop(GradientStop {
+                offset: (offset * 65535.0).round() as u16,
+                color: rgba8_from_usvg_color(stop.color, opacity * stop.opacity.value()),
+            });
+        }
+
+        Some(Gradient {
+            kind,
+            frame: [
+                point_from(frame[0].0, frame[0].1),
+                point_from(frame[1].0, frame[1].1),
+                point_from(frame[2].0, frame[2].1),
+            ],
+        })
+    }
+}
+
+/// If `xform` is a similarity transformation (i.e., a transformation
+/// preserving the shapes), return its scaling factor.
+fn similarity_scale(xform: &usvg::Transform) -> Option<f64> {
+    let (a, b, c, d) = (xform.a, xform.b, xform.c, xform.d);
+    let sq_x = a * a + b * b;
+    let sq_y = c * c + d * d;
+    let tolerance = sq_x.max(sq_y) * 1.0e-4;
+
+    if (sq_x - sq_y).abs() <= tolerance && (a * c + b * d).abs() <= tolerance {
+        Some(sq_x.sqrt())
+    } else {
+        None
+    }
+}
+
+/// Convert a non-negative value to a fixed-point `u16`. The value should be
+/// already shifted by `FRAC_BITS`.
+fn fixed_u16_from(x: f64) -> u16 {
+    if !(0.0..=<u16>::max_value() as f64).contains(&x) {
+        panic!("value overflowed u16");
+    }
+
+    x.round() as u16
 }
 
-fn rgba8_from_usvg_color(color: usvg::Color, opacity: f32) -> RGBA8 {
+fn rgba8_from_usvg_color(color: usvg::Color, opacity: f64) -> RGBA8 {
     RGBA8::new(color.red, color.green, color.blue, (opacity * 255.0) as u8)
 }
 
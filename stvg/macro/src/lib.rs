@@ -15,4 +15,9 @@
 ///
 /// Be aware that the range of coordinates are limited by the internal
 /// representation used by StellaVG. See [`stvg_io::FRAC_BITS`].
+///
+/// Linear and radial gradients are supported, but their focal points and
+/// spread methods other than `pad` are ignored. Strokes with a non-uniform
+/// scaling or skew transformation are converted to fills, in which case dash
+/// patterns are ignored.
 pub use stvg_macro_impl::include_stvg;
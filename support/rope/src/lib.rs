@@ -92,6 +92,22 @@ impl<T, O> NodeRef<T, O> {
             _ => false,
         }
     }
+
+    /// The internal implementation of `Rope::for_each_mut`.
+    fn for_each_mut(&mut self, f: &mut impl FnMut(&mut T)) {
+        match self {
+            NodeRef::Internal(inode) => {
+                for child in inode.children.iter_mut() {
+                    child.for_each_mut(f);
+                }
+            }
+            NodeRef::Leaf(elements) => {
+                for elem in elements.iter_mut() {
+                    f(elem);
+                }
+            }
+        }
+    }
 }
 
 /// A non-leaf node.
@@ -290,6 +306,15 @@ where
             .map(move |(cursor, offset)| (self.get_mut_at(cursor), offset))
     }
 
+    /// Call a given function for the mutable reference to every element, from
+    /// front to back.
+    ///
+    /// This takes O(n) time in total. Like `get_mut`, this does not allow
+    /// changing the elements' length (`<T as ToOffset<O>>::to_offset`).
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut T)) {
+        self.root.for_each_mut(&mut f);
+    }
+
     /// Update an element using a given function, possibly changing its length
     /// (`<T as ToOffset<O>>::to_offset`).
     ///
@@ -485,6 +510,31 @@ mod tests {
         assert_eq!(elems, (0..200).rev().collect::<Vec<u32>>());
     }
 
+    #[test]
+    fn for_each_mut() {
+        let mut rope: Rope<String> = Rope::new();
+        for i in 0..200 {
+            rope.push_back(i.to_string());
+        }
+
+        // Reverse every element, which preserves their lengths
+        let mut count = 0;
+        rope.for_each_mut(|x| {
+            *x = x.chars().rev().collect();
+            count += 1;
+        });
+        assert_eq!(count, 200);
+
+        dbg!(&rope.root);
+        rope.validate();
+
+        let elems: Vec<String> = rope.iter().cloned().collect();
+        let expected: Vec<String> = (0..200)
+            .map(|i| i.to_string().chars().rev().collect())
+            .collect();
+        assert_eq!(elems, expected);
+    }
+
     #[test]
     fn range() {
         const COUNT: usize = ORDER * 4 + 7;
@@ -1,7 +1,12 @@
 use array_intrusive_list::{Link, ListHead};
 use leakypool::{LeakyPool, UncheckedToken};
 use quick_error::quick_error;
-use std::{cell::RefCell, fmt, sync::Arc};
+use std::{
+    cell::RefCell,
+    fmt,
+    sync::{Arc, Weak},
+};
+use subscriber_list::{SubscriberList, UntypedSubscription};
 use tcw3_pal::{self as pal, prelude::*, Bitmap, MtLock, MtSticky, Wm};
 
 /// A bitmap created by rasterizing [`Img`]. The second value represents the
@@ -14,6 +19,10 @@ pub trait Img: Send + Sync + 'static {
     /// Construct a `Bitmap` for the specified DPI scale.
     ///
     /// Returns a constructed `Bitmap` and the actual DPI scale of the `Bitmap`.
+    ///
+    /// If the contents of the image change later (e.g., because it was
+    /// loaded asynchronously), the implementation should call
+    /// [`HImg::invalidate`] to discard the cached bitmaps.
     fn new_bmp(&self, dpi_scale: f32) -> Bmp;
 }
 
@@ -31,6 +40,12 @@ pub struct HImg {
     inner: Arc<ImgInner<dyn Img>>,
 }
 
+/// A weak reference to an image represented by [`HImg`].
+#[derive(Debug, Clone)]
+pub struct WeakHImg {
+    inner: Weak<ImgInner<dyn Img>>,
+}
+
 struct ImgInner<T: ?Sized> {
     cache_ref: MtSticky<RefCell<ImgCacheRef>>,
     img: T,
@@ -112,6 +127,48 @@ impl HImg {
     pub fn new_bmp_uncached(&self, dpi_scale: f32) -> Bmp {
         self.inner.img.new_bmp(dpi_scale)
     }
+
+    /// Discard the cached bitmaps of the image and notify the subscribers of
+    /// [`subscribe_imgs_invalidated`] so that they can create new bitmaps
+    /// reflecting the updated contents.
+    pub fn invalidate(&self, wm: Wm) {
+        let cache_ref = self.inner.cache_ref.get_with_wm(wm).borrow();
+
+        if let Some(img_ptr) = cache_ref.img_ptr {
+            CACHE.get_with_wm(wm).borrow_mut().img_clear(img_ptr);
+        }
+
+        drop(cache_ref);
+
+        if let Some(handlers) = &*INVALIDATE_HANDLERS.get_with_wm(wm).borrow() {
+            for handler in handlers.iter() {
+                handler(wm, self);
+            }
+        }
+    }
+
+    /// Construct a `WeakHImg` pointing to the same image as `self`.
+    pub fn downgrade(&self) -> WeakHImg {
+        WeakHImg {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+}
+
+impl PartialEq for HImg {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for HImg {}
+
+impl WeakHImg {
+    /// Attempt to get a `HImg` pointing to the image. Returns `None` if the
+    /// image has already been dropped.
+    pub fn upgrade(&self) -> Option<HImg> {
+        self.inner.upgrade().map(|inner| HImg { inner })
+    }
 }
 
 impl Drop for ImgCacheRef {
@@ -157,8 +214,29 @@ pub fn dpi_scale_release(wm: pal::Wm, dpi_scale: f32) {
         .dpi_scale_release(DpiScale::new(dpi_scale).unwrap());
 }
 
+/// The callback function type for [`subscribe_imgs_invalidated`].
+pub type ImgInvalidateCb = Box<dyn Fn(Wm, &HImg)>;
+
+/// Register a function to be called whenever [`HImg::invalidate`] is called
+/// on any image.
+///
+/// Returns a subscription token that can be used to unregister the function.
+pub fn subscribe_imgs_invalidated(wm: Wm, cb: ImgInvalidateCb) -> UntypedSubscription {
+    INVALIDATE_HANDLERS
+        .get_with_wm(wm)
+        .borrow_mut()
+        .get_or_insert_with(SubscriberList::new)
+        .insert(cb)
+        .untype()
+}
+
 static CACHE: MtLock<RefCell<Cache>> = MtLock::new(RefCell::new(unsafe { Cache::new() }));
 
+static INVALIDATE_HANDLERS: MtSticky<RefCell<Option<SubscriberList<ImgInvalidateCb>>>> = {
+    // `None` contains no unsendable value, so this is safe
+    unsafe { MtSticky::new_unchecked(RefCell::new(None)) }
+};
+
 //
 //  Cache -------+-----------------,
 //               |                 |
@@ -326,7 +404,12 @@ impl Cache {
     }
 
     fn img_remove(&mut self, img: ImgPtr) {
-        // Destroy all associated bitmmaps
+        self.img_clear(img);
+        self.imgs.deallocate(img);
+    }
+
+    /// Destroy all bitmaps associated with the specified image.
+    fn img_clear(&mut self, img: ImgPtr) {
         if let Some(mut bmp_ptr) = self.imgs[img].bmps.first {
             // Iterate through elements in a circular linked list.
             let first_bmp_ptr = bmp_ptr;
@@ -362,9 +445,9 @@ impl Cache {
                     bmp_ptr = next;
                 }
             }
-        }
 
-        self.imgs.deallocate(img);
+            self.imgs[img].bmps = Default::default();
+        }
     }
 
     fn img_find_bmp(&self, img: ImgPtr, dpi_scale: DpiScale) -> Option<&Bmp> {
@@ -511,5 +594,15 @@ mod tests {
 
         assert!(cache.img_find_bmp(img_ptr, scale1).is_some());
         assert!(cache.img_find_bmp(img_ptr, scale2).is_none());
+
+        cache.img_clear(img_ptr);
+        assert!(cache.img_find_bmp(img_ptr, scale1).is_none());
+
+        // The image can be reused after clearing
+        cache.img_add_bmp(img_ptr, scale1ptr, bmp.new_bmp(1.0));
+        assert!(cache.img_find_bmp(img_ptr, scale1).is_some());
+
+        cache.img_remove(img_ptr);
+        assert!(cache.dpi_scales[scale1ptr.0].bmps.first.is_none());
     }
 }
@@ -7,7 +7,8 @@ mod bitmap;
 mod canvas;
 mod figures;
 mod img;
-pub use self::{bitmap::*, canvas::*, figures::*, img::*};
+mod raster;
+pub use self::{bitmap::*, canvas::*, figures::*, img::*, raster::*};
 
 // Re-exports for macros defined in this crate
 #[doc(hidden)]
@@ -0,0 +1,422 @@
+//! Provides `RasterImg`, an `Img` implementation that displays a raster image
+//! (PNG, JPEG, GIF, or WebP) decoded by a background thread.
+use nativedispatch::Queue;
+use quick_error::quick_error;
+use std::{
+    borrow::Cow,
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex, Weak},
+};
+use tcw3_pal::{self as pal, prelude::*, Bitmap, Wm};
+
+use super::{Bmp, HImg, Img};
+
+/// Builds a [`HImg`] displaying raster images.
+///
+/// The images are decoded asynchronously by a [`Queue`]. Until the decoding
+/// is complete, the placeholder image specified by
+/// [`with_placeholder`](Self::with_placeholder) (or a transparent image if
+/// none is specified) is displayed instead. If the decoding fails, the
+/// placeholder image remains displayed.
+///
+/// Multiple images can be supplied for different DPI scale values. Each image
+/// is also downsampled to create smaller versions (mipmaps). When a bitmap is
+/// requested for a particular DPI scale, the one with the lowest resolution
+/// that satisfies the DPI scale is chosen.
+///
+/// Only the first frame of an animated image is displayed.
+#[derive(Debug, Clone)]
+pub struct RasterImgBuilder {
+    sources: Vec<(Source, f32)>,
+    placeholder: Option<HImg>,
+    queue: Option<Queue>,
+}
+
+/// The location of an encoded image.
+#[derive(Debug, Clone)]
+enum Source {
+    Bytes(Arc<[u8]>),
+    File(PathBuf),
+}
+
+impl Default for RasterImgBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RasterImgBuilder {
+    /// Construct a `RasterImgBuilder` with no images.
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            placeholder: None,
+            queue: None,
+        }
+    }
+
+    /// Add an encoded image stored in memory. `dpi_scale` specifies the
+    /// number of image pixels per logical pixel.
+    pub fn with_bytes(self, data: impl Into<Arc<[u8]>>, dpi_scale: f32) -> Self {
+        self.with_source(Source::Bytes(data.into()), dpi_scale)
+    }
+
+    /// Add an encoded image stored in a file. `dpi_scale` specifies the
+    /// number of image pixels per logical pixel.
+    pub fn with_file(self, path: impl Into<PathBuf>, dpi_scale: f32) -> Self {
+        self.with_source(Source::File(path.into()), dpi_scale)
+    }
+
+    fn with_source(mut self, source: Source, dpi_scale: f32) -> Self {
+        assert!(
+            dpi_scale.is_finite() && dpi_scale > 0.0,
+            "invalid DPI scale: {}",
+            dpi_scale
+        );
+        self.sources.push((source, dpi_scale));
+        self
+    }
+
+    /// Specify the image displayed until the decoding is complete.
+    pub fn with_placeholder(self, placeholder: HImg) -> Self {
+        Self {
+            placeholder: Some(placeholder),
+            ..self
+        }
+    }
+
+    /// Specify the queue used to decode the images. Defaults to
+    /// `Queue::global_bg()`.
+    pub fn with_queue(self, queue: Queue) -> Self {
+        Self {
+            queue: Some(queue),
+            ..self
+        }
+    }
+
+    /// Construct a `HImg` and start decoding the images.
+    pub fn build(self) -> HImg {
+        let state = Arc::new(Mutex::new(State::Loading));
+
+        let himg = HImg::new(RasterImg {
+            state: Arc::clone(&state),
+            placeholder: self.placeholder,
+        });
+
+        if self.sources.is_empty() {
+            *state.lock().unwrap() = State::Failed;
+            return himg;
+        }
+
+        let state = Arc::downgrade(&state);
+        let himg_weak = himg.downgrade();
+        let sources = self.sources;
+
+        let queue = self.queue.unwrap_or_else(Queue::global_bg);
+        queue.invoke(move || {
+            load(&state, &sources);
+
+            // Redraw the image with the new contents
+            Wm::invoke_on_main_thread(move |wm| {
+                if let Some(himg) = himg_weak.upgrade() {
+                    himg.invalidate(wm);
+                }
+            });
+        });
+
+        himg
+    }
+}
+
+/// Construct a [`HImg`] displaying an encoded image stored in memory. See
+/// [`RasterImgBuilder`] for more.
+pub fn himg_from_raster_bytes(data: impl Into<Arc<[u8]>>) -> HImg {
+    RasterImgBuilder::new().with_bytes(data, 1.0).build()
+}
+
+/// Construct a [`HImg`] displaying an encoded image stored in a file. See
+/// [`RasterImgBuilder`] for more.
+pub fn himg_from_raster_file(path: impl Into<PathBuf>) -> HImg {
+    RasterImgBuilder::new().with_file(path, 1.0).build()
+}
+
+/// `Img` created by [`RasterImgBuilder`].
+struct RasterImg {
+    state: Arc<Mutex<State>>,
+    placeholder: Option<HImg>,
+}
+
+enum State {
+    Loading,
+    Ready(Vec<Mip>),
+    Failed,
+}
+
+/// A decoded image for a particular DPI scale.
+struct Mip {
+    dpi_scale: f32,
+    size: [u32; 2],
+    contents: MipContents,
+}
+
+enum MipContents {
+    /// R8G8B8A8 pixels with premultiplied alpha. A `Bitmap` can only be
+    /// created by the main thread, so the decoder produces this.
+    Pixels(Vec<u8>),
+    Bitmap(Bitmap),
+}
+
+impl Mip {
+    /// Get a `Bmp`, creating a `Bitmap` on the first call.
+    fn bmp(&mut self) -> Bmp {
+        if let MipContents::Pixels(pixels) = &self.contents {
+            let bitmap = pal::BitmapBuilder::with_rgba8_premul(self.size, pixels).into_bitmap();
+            self.contents = MipContents::Bitmap(bitmap);
+        }
+
+        match &self.contents {
+            MipContents::Bitmap(bitmap) => (bitmap.clone(), self.dpi_scale),
+            MipContents::Pixels(_) => unreachable!(),
+        }
+    }
+}
+
+impl Img for RasterImg {
+    fn new_bmp(&self, dpi_scale: f32) -> Bmp {
+        if let State::Ready(mips) = &mut *self.state.lock().unwrap() {
+            let i = select_mip(mips.iter().map(|mip| mip.dpi_scale), dpi_scale);
+            return mips[i].bmp();
+        }
+
+        if let Some(placeholder) = &self.placeholder {
+            placeholder.new_bmp_uncached(dpi_scale)
+        } else {
+            (pal::BitmapBuilder::new([1, 1]).into_bitmap(), 1.0)
+        }
+    }
+}
+
+/// Decode the images and store the result to `state`. Does nothing if the
+/// image was dropped in the meantime.
+fn load(state: &Weak<Mutex<State>>, sources: &[(Source, f32)]) {
+    if state.upgrade().is_none() {
+        return;
+    }
+
+    let new_state = match load_mips(sources) {
+        Ok(mips) => State::Ready(mips),
+        Err(e) => {
+            log::warn!("Could not load a raster image: {}", e);
+            State::Failed
+        }
+    };
+
+    if let Some(state) = state.upgrade() {
+        *state.lock().unwrap() = new_state;
+    }
+}
+
+quick_error! {
+    #[derive(Debug)]
+    enum LoadError {
+        Io(err: io::Error) {
+            from()
+            display("{}", err)
+        }
+        Decode(err: image::ImageError) {
+            from()
+            display("{}", err)
+        }
+    }
+}
+
+/// The minimum DPI scale of generated mipmaps.
+const MIN_MIP_DPI_SCALE: f32 = 1.0;
+
+/// Decode the images and generate mipmaps. The returned `Mip`s are sorted by
+/// DPI scale.
+fn load_mips(sources: &[(Source, f32)]) -> Result<Vec<Mip>, LoadError> {
+    let mut mips = Vec::new();
+
+    for (source, dpi_scale) in sources.iter() {
+        let data = match source {
+            Source::Bytes(data) => Cow::Borrowed(&data[..]),
+            Source::File(path) => Cow::Owned(fs::read(path)?),
+        };
+
+        let (size, pixels) = decode(&data)?;
+        drop(data);
+
+        // Generate mipmaps by repeatedly halving the image
+        let base_size = size;
+        let mut mip = Mip {
+            dpi_scale: *dpi_scale,
+            size,
+            contents: MipContents::Pixels(pixels),
+        };
+
+        while mip.dpi_scale * 0.5 >= MIN_MIP_DPI_SCALE && (mip.size[0] > 1 || mip.size[1] > 1) {
+            let pixels = match &mip.contents {
+                MipContents::Pixels(pixels) => pixels,
+                MipContents::Bitmap(_) => unreachable!(),
+            };
+            let (size, pixels) = downsample(mip.size, pixels);
+
+            // Calculate the actual DPI scale using the rounded size
+            let dpi_scale = *dpi_scale
+                * (size[0] as f32 / base_size[0] as f32 + size[1] as f32 / base_size[1] as f32)
+                * 0.5;
+
+            mips.push(mip);
+            mip = Mip {
+                dpi_scale,
+                size,
+                contents: MipContents::Pixels(pixels),
+            };
+        }
+
+        mips.push(mip);
+    }
+
+    // DPI scales are validated by `RasterImgBuilder::with_source`, so
+    // `partial_cmp` never fails
+    mips.sort_by(|a, b| a.dpi_scale.partial_cmp(&b.dpi_scale).unwrap());
+
+    Ok(mips)
+}
+
+/// Decode an image and convert it to R8G8B8A8 with premultiplied alpha.
+fn decode(data: &[u8]) -> Result<([u32; 2], Vec<u8>), image::ImageError> {
+    let image = image::load_from_memory(data)?.into_rgba8();
+    let size = [image.width(), image.height()];
+
+    let mut pixels = image.into_raw();
+    premultiply(&mut pixels);
+
+    Ok((size, pixels))
+}
+
+fn premultiply(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        for x in pixel[..3].iter_mut() {
+            *x = ((*x as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+/// Halve the size of an image using a box filter. Odd dimensions are rounded
+/// up by replicating the last row or column.
+fn downsample(size: [u32; 2], pixels: &[u8]) -> ([u32; 2], Vec<u8>) {
+    let [width, height] = [size[0] as usize, size[1] as usize];
+    let new_size = [(size[0] + 1) / 2, (size[1] + 1) / 2];
+
+    let mut out = Vec::with_capacity(new_size[0] as usize * new_size[1] as usize * 4);
+
+    for y in 0..new_size[1] as usize {
+        let rows = [y * 2, (y * 2 + 1).min(height - 1)];
+        for x in 0..new_size[0] as usize {
+            let cols = [x * 2, (x * 2 + 1).min(width - 1)];
+            for ch in 0..4 {
+                let mut sum = 0;
+                for &row in rows.iter() {
+                    for &col in cols.iter() {
+                        sum += pixels[(row * width + col) * 4 + ch] as u32;
+                    }
+                }
+                out.push(((sum + 2) / 4) as u8);
+            }
+        }
+    }
+
+    (new_size, out)
+}
+
+/// Choose the mipmap with the lowest DPI scale not lower than `dpi_scale`.
+/// If there is no such mipmap, choose the one with the highest DPI scale.
+///
+/// `mip_dpi_scales` must be non-empty and sorted in an ascending order.
+fn select_mip(mip_dpi_scales: impl ExactSizeIterator<Item = f32>, dpi_scale: f32) -> usize {
+    let len = mip_dpi_scales.len();
+    assert!(len > 0);
+    mip_dpi_scales
+        .enumerate()
+        .find(|&(_, mip_dpi_scale)| mip_dpi_scale >= dpi_scale)
+        .map(|(i, _)| i)
+        .unwrap_or(len - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2×1 PNG image containing opaque red and half-transparent blue pixels.
+    const PNG_2X1: &[u8] = &[
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 2, 0, 0, 0, 1, 8, 6,
+        0, 0, 0, 244, 34, 127, 138, 0, 0, 0, 14, 73, 68, 65, 84, 120, 156, 99, 248, 207, 192, 0,
+        66, 13, 0, 15, 122, 3, 126, 119, 233, 127, 151, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96,
+        130,
+    ];
+
+    fn mip_pixels(mip: &Mip) -> &[u8] {
+        match &mip.contents {
+            MipContents::Pixels(pixels) => pixels,
+            MipContents::Bitmap(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn decode_png() {
+        let (size, pixels) = decode(PNG_2X1).unwrap();
+        assert_eq!(size, [2, 1]);
+        assert_eq!(pixels, [255, 0, 0, 255, 0, 0, 128, 128]);
+    }
+
+    #[test]
+    fn decode_garbage() {
+        assert!(decode(b"not an image").is_err());
+    }
+
+    #[test]
+    fn load_mips_with_downsampling() {
+        let mips = load_mips(&[(Source::Bytes(PNG_2X1.into()), 2.0)]).unwrap();
+
+        assert_eq!(mips.len(), 2);
+        assert_eq!(mips[0].size, [1, 1]);
+        assert_eq!(mips[0].dpi_scale, 1.5);
+        assert_eq!(mip_pixels(&mips[0]), [128, 0, 64, 192]);
+        assert_eq!(mips[1].size, [2, 1]);
+        assert_eq!(mips[1].dpi_scale, 2.0);
+    }
+
+    #[test]
+    fn load_mips_missing_file() {
+        let path = std::env::temp_dir().join("tcw3-raster-nonexistent.png");
+        assert!(load_mips(&[(Source::File(path), 1.0)]).is_err());
+    }
+
+    #[test]
+    fn downsample_odd() {
+        #[rustfmt::skip]
+        let pixels = [
+            0, 0, 0, 0,  4, 4, 4, 4,  8, 8, 8, 8,
+            8, 8, 8, 8,  12, 12, 12, 12,  16, 16, 16, 16,
+        ];
+        let (size, out) = downsample([3, 2], &pixels);
+        assert_eq!(size, [2, 1]);
+        assert_eq!(out, [6, 6, 6, 6, 12, 12, 12, 12]);
+    }
+
+    #[test]
+    fn select_mip_for_dpi_scale() {
+        let scales = [1.0, 2.0, 4.0];
+        let select = |dpi_scale| select_mip(scales.iter().cloned(), dpi_scale);
+        assert_eq!(select(0.5), 0);
+        assert_eq!(select(1.0), 0);
+        assert_eq!(select(1.5), 1);
+        assert_eq!(select(2.0), 1);
+        assert_eq!(select(3.0), 2);
+        assert_eq!(select(8.0), 2);
+    }
+}
@@ -0,0 +1,116 @@
+//! Measures the performance of the software compositor through the testing
+//! backend.
+use cggeom::box2;
+use criterion::Criterion;
+use tcw3_pal::{self as pal, iface::Wm as _, testing, testing::wmapi};
+
+const WND_SIZE: [u32; 2] = [1920, 1080];
+
+/// Construct a window resembling a text editor. Returns the window, its
+/// background layer, and the caret layer.
+fn new_editor_wnd(wm: pal::Wm) -> (pal::HWnd, pal::HLayer, pal::HLayer) {
+    let [wnd_w, wnd_h] = [WND_SIZE[0] as f32, WND_SIZE[1] as f32];
+
+    let bg = wm.new_layer(pal::LayerAttrs {
+        bg_color: Some([1.0, 1.0, 1.0, 1.0].into()),
+        bounds: Some(box2! { top_left: [0.0, 0.0], size: [wnd_w, wnd_h] }),
+        ..Default::default()
+    });
+
+    // Lines of "text"
+    let mut sublayers = vec![bg.clone()];
+    for i in 0..(WND_SIZE[1] / 20) {
+        let len = 200.0 + (i * 397 % 1300) as f32;
+        sublayers.push(wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([0.1, 0.1, 0.1, 0.8].into()),
+            bounds: Some(box2! { top_left: [40.0, i as f32 * 20.0 + 4.0], size: [len, 12.0] }),
+            ..Default::default()
+        }));
+    }
+
+    let caret = wm.new_layer(pal::LayerAttrs {
+        bg_color: Some([0.0, 0.0, 0.0, 1.0].into()),
+        bounds: Some(box2! { top_left: [640.5, 402.0], size: [1.0, 16.0] }),
+        ..Default::default()
+    });
+    sublayers.push(caret.clone());
+
+    let root = wm.new_layer(pal::LayerAttrs {
+        sublayers: Some(sublayers),
+        ..Default::default()
+    });
+
+    let hwnd = wm.new_wnd(pal::WndAttrs {
+        visible: Some(true),
+        size: Some(WND_SIZE),
+        layer: Some(Some(root)),
+        ..Default::default()
+    });
+
+    (hwnd, bg, caret)
+}
+
+fn caret_blink(c: &mut Criterion, twm: &dyn testing::TestingWm) {
+    let wm = twm.wm();
+    let (hwnd, bg, caret) = new_editor_wnd(wm);
+    let mut ss = wmapi::WndSnapshot::new();
+
+    wm.update_wnd(&hwnd);
+    twm.read_wnd_snapshot(&hwnd, &mut ss);
+
+    // Note that `read_wnd_snapshot` copies the entire window contents in
+    // addition to rendering the dirty region.
+    let mut group = c.benchmark_group("caret_blink");
+
+    // Only the caret is re-rendered
+    let mut visible = true;
+    group.bench_function("damaged_region", |b| {
+        b.iter(|| {
+            visible = !visible;
+            wm.set_layer_attr(
+                &caret,
+                pal::LayerAttrs {
+                    opacity: Some(visible as u8 as f32),
+                    ..Default::default()
+                },
+            );
+            wm.update_wnd(&hwnd);
+            twm.read_wnd_snapshot(&hwnd, &mut ss);
+        });
+    });
+
+    // Changing the background forces the entire window to be re-rendered
+    group.bench_function("entire_window", |b| {
+        b.iter(|| {
+            visible = !visible;
+            wm.set_layer_attr(
+                &caret,
+                pal::LayerAttrs {
+                    opacity: Some(visible as u8 as f32),
+                    ..Default::default()
+                },
+            );
+            wm.set_layer_attr(
+                &bg,
+                pal::LayerAttrs {
+                    bg_color: Some([1.0, 1.0, visible as u8 as f32, 1.0].into()),
+                    ..Default::default()
+                },
+            );
+            wm.update_wnd(&hwnd);
+            twm.read_wnd_snapshot(&hwnd, &mut ss);
+        });
+    });
+
+    group.finish();
+
+    wm.remove_wnd(&hwnd);
+}
+
+fn main() {
+    testing::run_test(|twm| {
+        let mut c = Criterion::default().configure_from_args();
+        caret_blink(&mut c, twm);
+        c.final_summary();
+    });
+}
@@ -29,11 +29,18 @@ fn main() {
             .probe("gtk+-3.0")
             .unwrap();
 
+        // `accessible.c` uses ATK directly. `gtk-sys` doesn't link it, so let
+        // `pkg-config` emit the linker flags.
+        pkg_config::Config::new().probe("atk").unwrap();
+
         let mut build = cc::Build::new();
         for path in gtk_lib.include_paths.iter() {
             build.include(path);
         }
 
-        build.file("src/gtk/wndwidget.c").compile("tcwsupport_gtk");
+        build
+            .file("src/gtk/wndwidget.c")
+            .file("src/gtk/accessible.c")
+            .compile("tcwsupport_gtk");
     }
 }
@@ -1,6 +1,10 @@
 //! Defines a platform-neutral key notation.
+//!
+//! This crate is used by `tcw3_pal_macro` (for `accel_table!`) and
+//! `tcw3_pal::accel` (for key bindings specified at runtime), so both accept
+//! exactly the same syntax.
 
-#[derive(Debug, Clone, Copy, enum_utils::FromStr)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, enum_utils::FromStr)]
 pub enum Key {
     /// A character without any key modifiers applied. Does not include
     /// the inputs by a numeric keypad.
@@ -71,7 +75,8 @@ bitflags::bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A key combination, e.g., `Ctrl+Shift+Z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct KeyPattern {
     pub key: Key,
     pub mod_flags: ModFlags,
@@ -121,3 +126,26 @@ impl std::str::FromStr for KeyPattern {
         Ok(Self { key, mod_flags })
     }
 }
+
+impl std::fmt::Display for KeyPattern {
+    /// Format the pattern in the syntax accepted by `from_str`. Modifiers are
+    /// written in a canonical order.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.mod_flags.contains(ModFlags::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.mod_flags.contains(ModFlags::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.mod_flags.contains(ModFlags::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        if self.mod_flags.contains(ModFlags::SUPER) {
+            write!(f, "Super+")?;
+        }
+        match self.key {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use proc_macro_error::abort;
+use tcw3_pal_keycode::{Key, KeyPattern, ModFlags};
 
 use super::{MacroInput, Trigger};
-use crate::keycode::{Key, KeyPattern, ModFlags};
 
 pub(super) fn gen_accel_table(input: &MacroInput) -> proc_macro2::TokenStream {
     let crate_path = &input.crate_path;
@@ -24,7 +24,7 @@ pub(super) fn gen_accel_table(input: &MacroInput) -> proc_macro2::TokenStream {
 
     quote::quote! {
         #crate_path::gtk::AccelTable {
-            key: &[#(#key_bindings),*],
+            key: ::std::borrow::Cow::Borrowed(&[#(#key_bindings),*]),
         }
     }
 }
@@ -1,8 +1,8 @@
 use proc_macro_error::abort;
 use std::convert::TryFrom;
+use tcw3_pal_keycode::{Key, KeyPattern, ModFlags};
 
 use super::{MacroInput, Trigger};
-use crate::keycode::{Key, KeyPattern, ModFlags};
 
 // `NSEventModifierFlags`
 const NS_SHIFT_KEY_MASK: u32 = 1 << 17;
@@ -52,7 +52,7 @@ pub(super) fn gen_accel_table(input: &MacroInput) -> proc_macro2::TokenStream {
 
     quote::quote! {
         #crate_path::macos::AccelTable {
-            key: &[#(#key_bindings),*],
+            key: ::std::borrow::Cow::Borrowed(&[#(#key_bindings),*]),
             sel: &[#(#sel_bindings),*],
         }
     }
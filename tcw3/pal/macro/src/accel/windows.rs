@@ -1,8 +1,8 @@
 use proc_macro_error::abort;
 use std::convert::TryInto;
+use tcw3_pal_keycode::{Key, KeyPattern, ModFlags};
 
 use super::{MacroInput, Trigger};
-use crate::keycode::{Key, KeyPattern, ModFlags};
 
 pub(super) fn gen_accel_table(input: &MacroInput) -> proc_macro2::TokenStream {
     let crate_path = &input.crate_path;
@@ -24,7 +24,7 @@ pub(super) fn gen_accel_table(input: &MacroInput) -> proc_macro2::TokenStream {
 
     quote::quote! {
         #crate_path::windows::AccelTable {
-            key: &[#(#key_bindings),*],
+            key: ::std::borrow::Cow::Borrowed(&[#(#key_bindings),*]),
         }
     }
 }
@@ -3,7 +3,6 @@
 extern crate proc_macro;
 
 mod accel;
-mod keycode;
 
 #[proc_macro]
 #[proc_macro_error::proc_macro_error]
@@ -0,0 +1,232 @@
+//! Key bindings specified at runtime.
+//!
+//! [`accel_table!`](crate::accel_table) creates an accelerator table at
+//! compile time. This module provides the means to construct one from key
+//! combinations only known at runtime (e.g., the ones loaded from a
+//! configuration file). The syntax of key combinations is identical to the one
+//! accepted by `accel_table!`.
+//!
+//! # Example
+//!
+//! ```
+//! use tcw3_pal::{accel::KeyBindings, actions, AccelTable};
+//!
+//! let mut defaults = KeyBindings::new();
+//! defaults.bind(actions::UNDO, "Ctrl+Z".parse().unwrap());
+//! defaults.bind(actions::REDO, "Ctrl+Y".parse().unwrap());
+//!
+//! let mut user = KeyBindings::new();
+//! user.bind(actions::REDO, "Ctrl+Shift+Z".parse().unwrap());
+//!
+//! let bindings = defaults.overlay(&user);
+//! assert!(bindings.conflicts().is_empty());
+//!
+//! let accel_table: AccelTable = bindings.to_accel_table();
+//! ```
+use std::collections::HashMap;
+
+use crate::iface::{AccelTableNew, ActionId};
+
+pub use tcw3_pal_keycode::{Key, KeyPattern, ModFlags};
+
+/// Associates a key combination with an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub action: ActionId,
+    pub pattern: KeyPattern,
+}
+
+/// Two or more actions bound to the same key combination, reported by
+/// [`KeyBindings::conflicts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyConflict {
+    pub pattern: KeyPattern,
+    pub actions: Vec<ActionId>,
+}
+
+/// A set of key bindings, which can be layered on top of another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyBindings {
+    bindings: Vec<KeyBinding>,
+    /// The actions mentioned by this layer. When this layer is put on top of
+    /// another by [`KeyBindings::overlay`], these actions' bindings in the
+    /// lower layer are discarded.
+    actions: Vec<ActionId>,
+}
+
+impl KeyBindings {
+    /// Construct an empty `KeyBindings`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key binding.
+    pub fn bind(&mut self, action: ActionId, pattern: KeyPattern) {
+        self.mention(action);
+
+        let binding = KeyBinding { action, pattern };
+        if !self.bindings.contains(&binding) {
+            self.bindings.push(binding);
+        }
+    }
+
+    /// Remove all key bindings of `action`. When this layer is put on top of
+    /// another, `action` will have no key bindings in the result.
+    pub fn unbind(&mut self, action: ActionId) {
+        self.mention(action);
+        self.bindings.retain(|b| b.action != action);
+    }
+
+    fn mention(&mut self, action: ActionId) {
+        if !self.actions.contains(&action) {
+            self.actions.push(action);
+        }
+    }
+
+    /// Get the key bindings.
+    pub fn bindings(&self) -> &[KeyBinding] {
+        &self.bindings
+    }
+
+    /// Get the key combinations bound to `action`.
+    pub fn patterns_of(&self, action: ActionId) -> impl Iterator<Item = KeyPattern> + '_ {
+        (self.bindings.iter())
+            .filter(move |b| b.action == action)
+            .map(|b| b.pattern)
+    }
+
+    /// Put `upper` on top of `self` and return the combined `KeyBindings`.
+    ///
+    /// An action mentioned by `upper` (by [`bind`](Self::bind) or
+    /// [`unbind`](Self::unbind)) only gets the key bindings specified by
+    /// `upper`. Furthermore, the key combinations bound by `upper` are
+    /// removed from the other actions in `self`.
+    pub fn overlay(&self, upper: &KeyBindings) -> KeyBindings {
+        let mut bindings: Vec<KeyBinding> = (self.bindings.iter())
+            .filter(|b| {
+                !upper.actions.contains(&b.action)
+                    && !upper.bindings.iter().any(|ub| ub.pattern == b.pattern)
+            })
+            .cloned()
+            .collect();
+        bindings.extend(upper.bindings.iter().cloned());
+
+        let mut actions = self.actions.clone();
+        for &action in upper.actions.iter() {
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+
+        Self { bindings, actions }
+    }
+
+    /// Find the key combinations bound to more than one action.
+    pub fn conflicts(&self) -> Vec<KeyConflict> {
+        let mut actions_by_pattern: HashMap<KeyPattern, Vec<ActionId>> = HashMap::new();
+        let mut patterns = Vec::new();
+
+        for binding in self.bindings.iter() {
+            let actions = actions_by_pattern
+                .entry(binding.pattern)
+                .or_insert_with(|| {
+                    patterns.push(binding.pattern);
+                    Vec::new()
+                });
+            if !actions.contains(&binding.action) {
+                actions.push(binding.action);
+            }
+        }
+
+        // Report in the order of appearance
+        patterns
+            .into_iter()
+            .filter_map(|pattern| {
+                let actions = actions_by_pattern.remove(&pattern).unwrap();
+                if actions.len() > 1 {
+                    Some(KeyConflict { pattern, actions })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Construct an accelerator table from the key bindings.
+    pub fn to_accel_table<T: AccelTableNew>(&self) -> T {
+        T::from_key_bindings(&self.bindings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pat(s: &str) -> KeyPattern {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let p = pat("Shift+Ctrl+z");
+        assert_eq!(p.key, Key::Char('z'));
+        assert_eq!(p.mod_flags, ModFlags::SHIFT | ModFlags::CONTROL);
+        assert_eq!(p, pat("Ctrl+Shift+Z"));
+        assert_eq!(p.to_string(), "Ctrl+Shift+Z");
+        assert_eq!(pat("Alt+PageUp").to_string(), "Alt+PageUp");
+        assert_eq!(pat(&pat("Super+F5").to_string()), pat("Super+F5"));
+
+        assert!("Ctrl+".parse::<KeyPattern>().is_err());
+        assert!("Ctrl+Hoge".parse::<KeyPattern>().is_err());
+    }
+
+    #[test]
+    fn overlay() {
+        let mut lower = KeyBindings::new();
+        lower.bind(1, pat("Ctrl+Q"));
+        lower.bind(2, pat("Ctrl+Y"));
+        lower.bind(3, pat("Ctrl+B"));
+        lower.bind(4, pat("Ctrl+P"));
+
+        let mut upper = KeyBindings::new();
+        upper.bind(2, pat("Ctrl+Shift+Z"));
+        upper.bind(5, pat("Ctrl+B"));
+        upper.unbind(4);
+
+        let combined = lower.overlay(&upper);
+        assert_eq!(combined.patterns_of(1).collect::<Vec<_>>(), [pat("Ctrl+Q")]);
+        assert_eq!(
+            combined.patterns_of(2).collect::<Vec<_>>(),
+            [pat("Ctrl+Shift+Z")]
+        );
+        // `Ctrl+B` was taken by action 5
+        assert_eq!(combined.patterns_of(3).count(), 0);
+        assert_eq!(combined.patterns_of(4).count(), 0);
+        assert_eq!(combined.patterns_of(5).collect::<Vec<_>>(), [pat("Ctrl+B")]);
+        assert!(combined.conflicts().is_empty());
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut bindings = KeyBindings::new();
+        bindings.bind(1, pat("Ctrl+Q"));
+        bindings.bind(2, pat("Ctrl+Q"));
+        bindings.bind(2, pat("Ctrl+W"));
+        bindings.bind(3, pat("Ctrl+W"));
+        bindings.bind(3, pat("Ctrl+E"));
+
+        assert_eq!(
+            bindings.conflicts(),
+            vec![
+                KeyConflict {
+                    pattern: pat("Ctrl+Q"),
+                    actions: vec![1, 2],
+                },
+                KeyConflict {
+                    pattern: pat("Ctrl+W"),
+                    actions: vec![2, 3],
+                },
+            ]
+        );
+    }
+}
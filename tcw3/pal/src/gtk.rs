@@ -18,9 +18,17 @@ pub use self::{
     text::{CharStyle, TextLayout},
 };
 
+mod acceltable;
+mod access;
+mod appearance;
+mod clipboard;
 mod comp;
+mod dnd;
+mod menu;
+mod notify;
 mod textinput;
 mod timer;
+mod tooltip;
 mod window;
 pub use self::{
     comp::HLayer,
@@ -230,6 +238,78 @@ impl iface::Wm for Wm {
     fn remove_text_input_ctx(self, htictx: &Self::HTextInputCtx) {
         htictx.remove(self);
     }
+
+    fn clipboard_formats(self) -> iface::ClipboardFormatFlags {
+        clipboard::formats(self)
+    }
+
+    fn get_clipboard_text(self) -> Option<String> {
+        clipboard::text(self)
+    }
+
+    fn get_clipboard_text_async(self, f: impl FnOnce(Self, Option<String>) + 'static) {
+        clipboard::text_async(self, f)
+    }
+
+    fn set_clipboard_text(self, text: &str) {
+        clipboard::set_text(self, text)
+    }
+
+    fn appearance(self) -> iface::Appearance {
+        appearance::appearance(self)
+    }
+
+    fn set_appearance_listener(self, listener: Box<dyn Fn(Self)>) {
+        appearance::set_listener(self, listener)
+    }
+
+    fn popup_menu(
+        self,
+        window: &Self::HWnd,
+        items: &[iface::MenuItem],
+        anchor: cggeom::Box2<f32>,
+        listener: Box<dyn iface::MenuListener<Self>>,
+    ) {
+        menu::popup_menu(self, window, items, anchor, listener)
+    }
+
+    fn show_tooltip(self, window: &Self::HWnd, text: &str, anchor: cggeom::Box2<f32>) {
+        tooltip::show_tooltip(self, window, text, anchor)
+    }
+
+    fn hide_tooltip(self, window: &Self::HWnd) {
+        tooltip::hide_tooltip(self, window)
+    }
+
+    fn start_drag(
+        self,
+        window: &Self::HWnd,
+        data: iface::DragData,
+        on_finish: Box<dyn FnOnce(Self, bool)>,
+    ) {
+        dnd::start_drag(self, window, data, on_finish)
+    }
+
+    fn update_access_tree(self, window: &Self::HWnd, root: iface::AccessNode) {
+        window.update_access_tree(self, &root);
+    }
+
+    fn is_access_active(self, window: &Self::HWnd) -> bool {
+        window.is_access_active(self)
+    }
+
+    fn show_notification(
+        self,
+        notification: iface::Notification<Self::Bitmap>,
+        replaces: Option<iface::NotificationId>,
+        listener: Box<dyn iface::NotificationListener<Self>>,
+    ) -> iface::NotificationId {
+        notify::show_notification(self, notification, replaces, listener)
+    }
+
+    fn close_notification(self, id: iface::NotificationId) {
+        notify::close_notification(self, id);
+    }
 }
 
 struct AssertSend<T>(T);
@@ -0,0 +1,115 @@
+//! Accelerator tables constructed at runtime
+use std::borrow::Cow;
+
+use super::{gdk_keys, AccelTable, ActionKeyBinding};
+use crate::{
+    accel::{Key, KeyBinding, ModFlags},
+    iface,
+};
+
+impl iface::AccelTableNew for AccelTable {
+    fn from_key_bindings(bindings: &[KeyBinding]) -> Self {
+        let mut key = Vec::with_capacity(bindings.len());
+
+        for binding in bindings.iter() {
+            let pat = &binding.pattern;
+
+            // See `tcw3_pal_macro::accel::gtk`
+            let keyvals = keyvals_for_key(pat.key);
+
+            let mut mod_flags = 0;
+            if pat.mod_flags.contains(ModFlags::SHIFT) {
+                mod_flags |= AccelTable::MOD_SHIFT;
+            }
+            if pat.mod_flags.contains(ModFlags::CONTROL) {
+                mod_flags |= AccelTable::MOD_CONTROL;
+            }
+            if pat.mod_flags.contains(ModFlags::ALT) {
+                mod_flags |= AccelTable::MOD_META;
+            }
+            if pat.mod_flags.contains(ModFlags::SUPER) {
+                mod_flags |= AccelTable::MOD_SUPER;
+            }
+
+            key.extend(keyvals.iter().map(|&keyval| ActionKeyBinding {
+                action: binding.action,
+                mod_flags,
+                keyval,
+            }));
+        }
+
+        Self {
+            key: Cow::Owned(key),
+        }
+    }
+}
+
+/// Get the keyvals matching `key`. Letters are upper/lower cased depending on
+/// the state of the CapsLock and Shift keys, so we return both cases.
+fn keyvals_for_key(key: Key) -> Vec<u32> {
+    vec![match key {
+        Key::Char(c) => {
+            let keyval = unsafe { gdk_sys::gdk_unicode_to_keyval(c as u32) };
+            let upper = unsafe { gdk_sys::gdk_keyval_to_upper(keyval) };
+            return if upper != keyval {
+                vec![keyval, upper]
+            } else {
+                vec![keyval]
+            };
+        }
+        Key::Escape => gdk_keys::Escape,
+        Key::Backspace => gdk_keys::BackSpace,
+        Key::Return => gdk_keys::Return,
+        Key::Tab => gdk_keys::Tab,
+        Key::Delete => gdk_keys::Delete,
+        Key::Left => gdk_keys::Left,
+        Key::Up => gdk_keys::Up,
+        Key::Right => gdk_keys::Right,
+        Key::Down => gdk_keys::Down,
+        Key::PageUp => gdk_keys::Page_Up,
+        Key::PageDown => gdk_keys::Page_Down,
+        Key::End => gdk_keys::End,
+        Key::Home => gdk_keys::Home,
+        Key::Insert => gdk_keys::Insert,
+        Key::Numpad0 => gdk_keys::KP_0,
+        Key::Numpad1 => gdk_keys::KP_1,
+        Key::Numpad2 => gdk_keys::KP_2,
+        Key::Numpad3 => gdk_keys::KP_3,
+        Key::Numpad4 => gdk_keys::KP_4,
+        Key::Numpad5 => gdk_keys::KP_5,
+        Key::Numpad6 => gdk_keys::KP_6,
+        Key::Numpad7 => gdk_keys::KP_7,
+        Key::Numpad8 => gdk_keys::KP_8,
+        Key::Numpad9 => gdk_keys::KP_9,
+        Key::NumpadMultiply => gdk_keys::KP_Multiply,
+        Key::NumpadAdd => gdk_keys::KP_Add,
+        Key::NumpadSeparator => gdk_keys::KP_Separator,
+        Key::NumpadSubtract => gdk_keys::KP_Subtract,
+        Key::NumpadDecimal => gdk_keys::KP_Decimal,
+        Key::NumpadDivide => gdk_keys::KP_Divide,
+        Key::F1 => gdk_keys::F1,
+        Key::F2 => gdk_keys::F2,
+        Key::F3 => gdk_keys::F3,
+        Key::F4 => gdk_keys::F4,
+        Key::F5 => gdk_keys::F5,
+        Key::F6 => gdk_keys::F6,
+        Key::F7 => gdk_keys::F7,
+        Key::F8 => gdk_keys::F8,
+        Key::F9 => gdk_keys::F9,
+        Key::F10 => gdk_keys::F10,
+        Key::F11 => gdk_keys::F11,
+        Key::F12 => gdk_keys::F12,
+        Key::F13 => gdk_keys::F13,
+        Key::F14 => gdk_keys::F14,
+        Key::F15 => gdk_keys::F15,
+        Key::F16 => gdk_keys::F16,
+        Key::F17 => gdk_keys::F17,
+        Key::F18 => gdk_keys::F18,
+        Key::F19 => gdk_keys::F19,
+        Key::F20 => gdk_keys::F20,
+        Key::F21 => gdk_keys::F21,
+        Key::F22 => gdk_keys::F22,
+        Key::F23 => gdk_keys::F23,
+        Key::F24 => gdk_keys::F24,
+    }]
+}
@@ -0,0 +1,200 @@
+//! Exposes accessibility trees to AT-SPI through ATK. The ATK objects are
+//! implemented in `accessible.c`.
+use glib::translate::{FromGlibPtrFull, ToGlibPtr};
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    os::raw::{c_char, c_int, c_void},
+    ptr::null,
+};
+
+use crate::iface;
+
+/// An `AtkObject` (which is a `TcwAccessible` or `TcwWndAccessible`).
+/// We don't depend on the `atk` crate, so its concrete type is not known to
+/// the type system.
+type AtkObjectPtr = *mut c_void;
+
+extern "C" {
+    fn tcw_accessible_new(widget: *mut gtk_sys::GtkWidget, id: u64) -> AtkObjectPtr;
+    fn tcw_accessible_update(
+        obj: AtkObjectPtr,
+        role: c_int,
+        name: *const c_char,
+        text_value: *const c_char,
+        has_range: c_int,
+        value: f64,
+        min_value: f64,
+        max_value: f64,
+        states: u32,
+        actions: *const c_int,
+        num_actions: usize,
+        bounds: *const f32,
+    );
+    fn tcw_accessible_set_children(
+        obj: AtkObjectPtr,
+        children: *const AtkObjectPtr,
+        num_children: usize,
+    );
+    fn tcw_accessible_invalidate(obj: AtkObjectPtr);
+}
+
+/// The ATK objects of the nodes in a window's accessibility tree.
+#[derive(Default)]
+pub(super) struct AccessObjects {
+    nodes: HashMap<iface::AccessNodeId, glib::Object>,
+    /// `true` if the widget's accessible object has been created, i.e., an
+    /// assistive technology is inspecting the window.
+    active: bool,
+}
+
+impl AccessObjects {
+    pub(super) fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub(super) fn activate(&mut self) {
+        self.active = true;
+    }
+
+    /// Reflect `root` to the ATK objects hosted by `widget`. The root node
+    /// is represented by the widget's own accessible object.
+    pub(super) fn update(&mut self, widget: &gtk::Widget, root: &iface::AccessNode) {
+        let widget_ptr: *mut gtk_sys::GtkWidget = widget.to_glib_none().0;
+
+        let mut new_nodes = HashMap::with_capacity(self.nodes.len());
+        let children: Vec<AtkObjectPtr> = (root.children.iter())
+            .map(|node| self.update_node(widget_ptr, node, &mut new_nodes))
+            .collect();
+
+        unsafe {
+            let root_obj = gtk_sys::gtk_widget_get_accessible(widget_ptr) as AtkObjectPtr;
+            tcw_accessible_set_children(root_obj, children.as_ptr(), children.len());
+        }
+
+        // Invalidate the nodes that are no longer in the tree
+        let old_nodes = std::mem::replace(&mut self.nodes, new_nodes);
+        for (id, obj) in old_nodes.iter() {
+            if !self.nodes.contains_key(id) {
+                unsafe { tcw_accessible_invalidate(obj_ptr(obj)) };
+            }
+        }
+    }
+
+    fn update_node(
+        &mut self,
+        widget_ptr: *mut gtk_sys::GtkWidget,
+        node: &iface::AccessNode,
+        new_nodes: &mut HashMap<iface::AccessNodeId, glib::Object>,
+    ) -> AtkObjectPtr {
+        let obj = self.nodes.remove(&node.id).unwrap_or_else(|| unsafe {
+            glib::Object::from_glib_full(
+                tcw_accessible_new(widget_ptr, node.id.0) as *mut gobject_sys::GObject
+            )
+        });
+        let ptr = obj_ptr(&obj);
+
+        let info = &node.info;
+        let name = info.name.as_ref().map(|s| c_string_lossy(s));
+        let text_value;
+        let (text_value_ptr, range) = match &info.value {
+            Some(iface::AccessValue::Text(text)) => {
+                text_value = c_string_lossy(text);
+                (text_value.as_ptr(), None)
+            }
+            Some(iface::AccessValue::Range { value, min, max }) => {
+                (null(), Some((*value, *min, *max)))
+            }
+            None => (null(), None),
+        };
+        let (value, min_value, max_value) = range.unwrap_or((0.0, 0.0, 0.0));
+        let actions: Vec<c_int> = info.actions.iter().map(|a| action_to_int(*a)).collect();
+        let bounds = [
+            node.bounds.min.x,
+            node.bounds.min.y,
+            node.bounds.max.x,
+            node.bounds.max.y,
+        ];
+
+        unsafe {
+            tcw_accessible_update(
+                ptr,
+                role_to_int(info.role),
+                name.as_ref().map_or(null(), |s| s.as_ptr()),
+                text_value_ptr,
+                range.is_some() as c_int,
+                value,
+                min_value,
+                max_value,
+                info.states.bits() as u32,
+                actions.as_ptr(),
+                actions.len(),
+                bounds.as_ptr(),
+            );
+        }
+
+        let children: Vec<AtkObjectPtr> = (node.children.iter())
+            .map(|child| self.update_node(widget_ptr, child, new_nodes))
+            .collect();
+        unsafe {
+            tcw_accessible_set_children(ptr, children.as_ptr(), children.len());
+        }
+
+        new_nodes.insert(node.id, obj);
+        ptr
+    }
+
+    /// Invalidate all nodes. Called when the window is removed.
+    pub(super) fn invalidate(&mut self) {
+        for obj in self.nodes.values() {
+            unsafe { tcw_accessible_invalidate(obj_ptr(obj)) };
+        }
+        self.nodes.clear();
+    }
+}
+
+fn obj_ptr(obj: &glib::Object) -> AtkObjectPtr {
+    let ptr: *mut gobject_sys::GObject = obj.to_glib_none().0;
+    ptr as AtkObjectPtr
+}
+
+/// Convert a string to `CString`, removing interior NUL characters.
+pub(super) fn c_string_lossy(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap()
+}
+
+// These definitions must be synchronized with `accessible.c`
+fn role_to_int(role: iface::AccessRole) -> c_int {
+    use iface::AccessRole;
+    match role {
+        AccessRole::Window => 0,
+        AccessRole::Group => 1,
+        AccessRole::Button => 2,
+        AccessRole::CheckBox => 3,
+        AccessRole::RadioButton => 4,
+        AccessRole::TextField => 5,
+        AccessRole::Slider => 6,
+        AccessRole::Label => 7,
+        AccessRole::Split => 8,
+        AccessRole::Table => 9,
+        AccessRole::Row => 10,
+        AccessRole::Cell => 11,
+    }
+}
+
+fn action_to_int(action: iface::AccessAction) -> c_int {
+    match action {
+        iface::AccessAction::Press => 0,
+        iface::AccessAction::Increment => 1,
+        iface::AccessAction::Decrement => 2,
+    }
+}
+
+pub(super) fn action_from_int(action: c_int) -> Option<iface::AccessAction> {
+    match action {
+        0 => Some(iface::AccessAction::Press),
+        1 => Some(iface::AccessAction::Increment),
+        2 => Some(iface::AccessAction::Decrement),
+        _ => None,
+    }
+}
@@ -0,0 +1,607 @@
+#include <gtk/gtk-a11y.h>
+#include <gtk/gtk.h>
+#include <stdint.h>
+#include <string.h>
+
+#include "accessible.h"
+#include "window.h"
+
+// These definitions must be synchronized with `access.rs`
+enum {
+    TCW_ACCESS_ROLE_WINDOW,
+    TCW_ACCESS_ROLE_GROUP,
+    TCW_ACCESS_ROLE_BUTTON,
+    TCW_ACCESS_ROLE_CHECK_BOX,
+    TCW_ACCESS_ROLE_RADIO_BUTTON,
+    TCW_ACCESS_ROLE_TEXT_FIELD,
+    TCW_ACCESS_ROLE_SLIDER,
+    TCW_ACCESS_ROLE_LABEL,
+    TCW_ACCESS_ROLE_SPLIT,
+    TCW_ACCESS_ROLE_TABLE,
+    TCW_ACCESS_ROLE_ROW,
+    TCW_ACCESS_ROLE_CELL,
+    TCW_ACCESS_ROLE_COUNT,
+};
+
+enum {
+    TCW_ACCESS_STATE_FOCUSABLE = 1,
+    TCW_ACCESS_STATE_FOCUSED = 1 << 1,
+    TCW_ACCESS_STATE_CHECKED = 1 << 2,
+    TCW_ACCESS_STATE_DISABLED = 1 << 3,
+    TCW_ACCESS_STATE_VERTICAL = 1 << 4,
+};
+
+enum {
+    TCW_ACCESS_ACTION_PRESS,
+    TCW_ACCESS_ACTION_INCREMENT,
+    TCW_ACCESS_ACTION_DECREMENT,
+    TCW_ACCESS_ACTION_COUNT,
+};
+
+#define TCW_ACCESS_MAX_ACTIONS 8
+
+static const AtkRole atk_roles[TCW_ACCESS_ROLE_COUNT] = {
+    [TCW_ACCESS_ROLE_WINDOW] = ATK_ROLE_FRAME,
+    [TCW_ACCESS_ROLE_GROUP] = ATK_ROLE_PANEL,
+    [TCW_ACCESS_ROLE_BUTTON] = ATK_ROLE_PUSH_BUTTON,
+    [TCW_ACCESS_ROLE_CHECK_BOX] = ATK_ROLE_CHECK_BOX,
+    [TCW_ACCESS_ROLE_RADIO_BUTTON] = ATK_ROLE_RADIO_BUTTON,
+    [TCW_ACCESS_ROLE_TEXT_FIELD] = ATK_ROLE_ENTRY,
+    [TCW_ACCESS_ROLE_SLIDER] = ATK_ROLE_SLIDER,
+    [TCW_ACCESS_ROLE_LABEL] = ATK_ROLE_LABEL,
+    [TCW_ACCESS_ROLE_SPLIT] = ATK_ROLE_SPLIT_PANE,
+    [TCW_ACCESS_ROLE_TABLE] = ATK_ROLE_TABLE,
+    [TCW_ACCESS_ROLE_ROW] = ATK_ROLE_TABLE_ROW,
+    [TCW_ACCESS_ROLE_CELL] = ATK_ROLE_TABLE_CELL,
+};
+
+static const char *const action_names[TCW_ACCESS_ACTION_COUNT] = {
+    [TCW_ACCESS_ACTION_PRESS] = "click",
+    [TCW_ACCESS_ACTION_INCREMENT] = "increment",
+    [TCW_ACCESS_ACTION_DECREMENT] = "decrement",
+};
+
+// ============================================================================
+// `TcwAccessible` represents a node in an accessibility tree.
+
+#define TCW_TYPE_ACCESSIBLE (tcw_accessible_get_type())
+#define TCW_ACCESSIBLE(obj)                                                    \
+    (G_TYPE_CHECK_INSTANCE_CAST((obj), TCW_TYPE_ACCESSIBLE, TcwAccessible))
+#define TCW_IS_ACCESSIBLE(obj)                                                 \
+    (G_TYPE_CHECK_INSTANCE_TYPE((obj), TCW_TYPE_ACCESSIBLE))
+
+typedef struct _TcwAccessible TcwAccessible;
+typedef struct _TcwAccessibleClass TcwAccessibleClass;
+
+struct _TcwAccessible {
+    AtkObject parent_instance;
+
+    /// The `TcwWndWidget` owning the node. This is a weak reference.
+    GtkWidget *widget;
+    uint64_t id;
+
+    char *text_value;
+    int has_range;
+    double value, min_value, max_value;
+    uint32_t states;
+    int actions[TCW_ACCESS_MAX_ACTIONS];
+    int num_actions;
+
+    /// The bounding rectangle in the widget's coordinates.
+    float bounds[4];
+
+    GPtrArray *children;
+};
+
+struct _TcwAccessibleClass {
+    AtkObjectClass parent_class;
+};
+
+GType tcw_accessible_get_type(void);
+
+static void tcw_accessible_component_init(AtkComponentIface *iface);
+static void tcw_accessible_action_init(AtkActionIface *iface);
+static void tcw_accessible_value_init(AtkValueIface *iface);
+static void tcw_accessible_text_init(AtkTextIface *iface);
+
+G_DEFINE_TYPE_WITH_CODE(
+    TcwAccessible, tcw_accessible, ATK_TYPE_OBJECT,
+    G_IMPLEMENT_INTERFACE(ATK_TYPE_COMPONENT, tcw_accessible_component_init)
+        G_IMPLEMENT_INTERFACE(ATK_TYPE_ACTION, tcw_accessible_action_init)
+            G_IMPLEMENT_INTERFACE(ATK_TYPE_VALUE, tcw_accessible_value_init)
+                G_IMPLEMENT_INTERFACE(ATK_TYPE_TEXT, tcw_accessible_text_init))
+
+static void tcw_accessible_finalize(GObject *object);
+static gint tcw_accessible_get_n_children(AtkObject *obj);
+static AtkObject *tcw_accessible_ref_child(AtkObject *obj, gint i);
+static gint tcw_accessible_get_index_in_parent(AtkObject *obj);
+static AtkStateSet *tcw_accessible_ref_state_set(AtkObject *obj);
+
+static void tcw_accessible_class_init(TcwAccessibleClass *klass) {
+    GObjectClass *object_class = G_OBJECT_CLASS(klass);
+    object_class->finalize = tcw_accessible_finalize;
+
+    AtkObjectClass *atk_class = ATK_OBJECT_CLASS(klass);
+    atk_class->get_n_children = tcw_accessible_get_n_children;
+    atk_class->ref_child = tcw_accessible_ref_child;
+    atk_class->get_index_in_parent = tcw_accessible_get_index_in_parent;
+    atk_class->ref_state_set = tcw_accessible_ref_state_set;
+}
+
+static void tcw_accessible_init(TcwAccessible *self) {
+    self->widget = NULL;
+    self->id = 0;
+    self->text_value = NULL;
+    self->has_range = 0;
+    self->value = self->min_value = self->max_value = 0.0;
+    self->states = 0;
+    self->num_actions = 0;
+    memset(self->bounds, 0, sizeof(self->bounds));
+    self->children = g_ptr_array_new_with_free_func(g_object_unref);
+}
+
+static void tcw_accessible_finalize(GObject *object) {
+    TcwAccessible *self = TCW_ACCESSIBLE(object);
+
+    if (self->widget) {
+        g_object_remove_weak_pointer(G_OBJECT(self->widget),
+                                     (gpointer *)&self->widget);
+    }
+    g_free(self->text_value);
+    g_ptr_array_unref(self->children);
+
+    G_OBJECT_CLASS(tcw_accessible_parent_class)->finalize(object);
+}
+
+// ============================================================================
+// `TcwWndAccessible` is the accessible object of `TcwWndWidget`. It hosts
+// the top-level nodes of the window's accessibility tree.
+
+#define TCW_TYPE_WND_ACCESSIBLE (tcw_wnd_accessible_get_type())
+#define TCW_WND_ACCESSIBLE(obj)                                                \
+    (G_TYPE_CHECK_INSTANCE_CAST((obj), TCW_TYPE_WND_ACCESSIBLE,                \
+                                TcwWndAccessible))
+#define TCW_IS_WND_ACCESSIBLE(obj)                                             \
+    (G_TYPE_CHECK_INSTANCE_TYPE((obj), TCW_TYPE_WND_ACCESSIBLE))
+
+typedef struct _TcwWndAccessible TcwWndAccessible;
+typedef struct _TcwWndAccessibleClass TcwWndAccessibleClass;
+
+struct _TcwWndAccessible {
+    GtkWidgetAccessible parent_instance;
+    GPtrArray *children;
+};
+
+struct _TcwWndAccessibleClass {
+    GtkWidgetAccessibleClass parent_class;
+};
+
+G_DEFINE_TYPE(TcwWndAccessible, tcw_wnd_accessible,
+              GTK_TYPE_WIDGET_ACCESSIBLE)
+
+static void tcw_wnd_accessible_finalize(GObject *object);
+static void tcw_wnd_accessible_initialize(AtkObject *obj, gpointer data);
+static gint tcw_wnd_accessible_get_n_children(AtkObject *obj);
+static AtkObject *tcw_wnd_accessible_ref_child(AtkObject *obj, gint i);
+
+static void tcw_wnd_accessible_class_init(TcwWndAccessibleClass *klass) {
+    GObjectClass *object_class = G_OBJECT_CLASS(klass);
+    object_class->finalize = tcw_wnd_accessible_finalize;
+
+    AtkObjectClass *atk_class = ATK_OBJECT_CLASS(klass);
+    atk_class->initialize = tcw_wnd_accessible_initialize;
+    atk_class->get_n_children = tcw_wnd_accessible_get_n_children;
+    atk_class->ref_child = tcw_wnd_accessible_ref_child;
+}
+
+static void tcw_wnd_accessible_init(TcwWndAccessible *self) {
+    self->children = g_ptr_array_new_with_free_func(g_object_unref);
+}
+
+static void tcw_wnd_accessible_finalize(GObject *object) {
+    TcwWndAccessible *self = TCW_WND_ACCESSIBLE(object);
+    g_ptr_array_unref(self->children);
+
+    G_OBJECT_CLASS(tcw_wnd_accessible_parent_class)->finalize(object);
+}
+
+/// The accessible object of a widget is created on demand, i.e., when an
+/// assistive technology starts to inspect the widget. Let the window know that
+/// it should start providing the accessibility tree.
+static void tcw_wnd_accessible_initialize(AtkObject *obj, gpointer data) {
+    ATK_OBJECT_CLASS(tcw_wnd_accessible_parent_class)->initialize(obj, data);
+
+    size_t wnd_ptr = tcw_wnd_widget_get_wnd_ptr(GTK_WIDGET(data));
+    if (wnd_ptr) {
+        tcw_wnd_widget_access_activated_handler(wnd_ptr);
+    }
+}
+
+// ============================================================================
+// Tree structure
+
+/// Get the children list of `TcwAccessible` or `TcwWndAccessible`.
+static GPtrArray *children_of(AtkObject *obj) {
+    if (TCW_IS_ACCESSIBLE(obj)) {
+        return TCW_ACCESSIBLE(obj)->children;
+    } else if (TCW_IS_WND_ACCESSIBLE(obj)) {
+        return TCW_WND_ACCESSIBLE(obj)->children;
+    } else {
+        return NULL;
+    }
+}
+
+static AtkObject *ref_child_of(GPtrArray *children, gint i) {
+    if (i < 0 || (guint)i >= children->len) {
+        return NULL;
+    }
+    return g_object_ref(g_ptr_array_index(children, i));
+}
+
+static gint tcw_accessible_get_n_children(AtkObject *obj) {
+    return (gint)TCW_ACCESSIBLE(obj)->children->len;
+}
+
+static AtkObject *tcw_accessible_ref_child(AtkObject *obj, gint i) {
+    return ref_child_of(TCW_ACCESSIBLE(obj)->children, i);
+}
+
+static gint tcw_wnd_accessible_get_n_children(AtkObject *obj) {
+    return (gint)TCW_WND_ACCESSIBLE(obj)->children->len;
+}
+
+static AtkObject *tcw_wnd_accessible_ref_child(AtkObject *obj, gint i) {
+    return ref_child_of(TCW_WND_ACCESSIBLE(obj)->children, i);
+}
+
+static gint tcw_accessible_get_index_in_parent(AtkObject *obj) {
+    AtkObject *parent = atk_object_get_parent(obj);
+    GPtrArray *siblings = parent ? children_of(parent) : NULL;
+    guint index;
+
+    if (siblings && g_ptr_array_find(siblings, obj, &index)) {
+        return (gint)index;
+    }
+    return -1;
+}
+
+static AtkStateSet *tcw_accessible_ref_state_set(AtkObject *obj) {
+    TcwAccessible *self = TCW_ACCESSIBLE(obj);
+    AtkStateSet *state_set =
+        ATK_OBJECT_CLASS(tcw_accessible_parent_class)->ref_state_set(obj);
+
+    if (!self->widget) {
+        atk_state_set_add_state(state_set, ATK_STATE_DEFUNCT);
+        return state_set;
+    }
+
+    atk_state_set_add_state(state_set, ATK_STATE_VISIBLE);
+    atk_state_set_add_state(state_set, ATK_STATE_SHOWING);
+
+    if (!(self->states & TCW_ACCESS_STATE_DISABLED)) {
+        atk_state_set_add_state(state_set, ATK_STATE_ENABLED);
+        atk_state_set_add_state(state_set, ATK_STATE_SENSITIVE);
+    }
+    if (self->states & TCW_ACCESS_STATE_FOCUSABLE) {
+        atk_state_set_add_state(state_set, ATK_STATE_FOCUSABLE);
+    }
+    if (self->states & TCW_ACCESS_STATE_FOCUSED) {
+        atk_state_set_add_state(state_set, ATK_STATE_FOCUSED);
+    }
+    if (self->states & TCW_ACCESS_STATE_CHECKED) {
+        atk_state_set_add_state(state_set, ATK_STATE_CHECKED);
+    }
+    if (self->states & TCW_ACCESS_STATE_VERTICAL) {
+        atk_state_set_add_state(state_set, ATK_STATE_VERTICAL);
+    } else {
+        atk_state_set_add_state(state_set, ATK_STATE_HORIZONTAL);
+    }
+    if (atk_object_get_role(obj) == ATK_ROLE_ENTRY) {
+        atk_state_set_add_state(state_set, ATK_STATE_EDITABLE);
+        atk_state_set_add_state(state_set, ATK_STATE_SINGLE_LINE);
+    }
+
+    return state_set;
+}
+
+// ============================================================================
+// `AtkComponent`
+
+static void tcw_accessible_get_extents(AtkComponent *component, gint *x,
+                                       gint *y, gint *width, gint *height,
+                                       AtkCoordType coord_type) {
+    TcwAccessible *self = TCW_ACCESSIBLE(component);
+    GtkWidget *widget = self->widget;
+    gint origin_x = 0, origin_y = 0;
+
+    *x = *y = *width = *height = 0;
+
+    if (!widget || !gtk_widget_get_realized(widget)) {
+        return;
+    }
+
+    if (coord_type == ATK_XY_SCREEN) {
+        gdk_window_get_origin(gtk_widget_get_window(widget), &origin_x,
+                              &origin_y);
+    } else {
+        gtk_widget_translate_coordinates(widget,
+                                         gtk_widget_get_toplevel(widget), 0, 0,
+                                         &origin_x, &origin_y);
+    }
+
+    *x = origin_x + (gint)self->bounds[0];
+    *y = origin_y + (gint)self->bounds[1];
+    *width = (gint)(self->bounds[2] - self->bounds[0]);
+    *height = (gint)(self->bounds[3] - self->bounds[1]);
+}
+
+static void tcw_accessible_component_init(AtkComponentIface *iface) {
+    iface->get_extents = tcw_accessible_get_extents;
+}
+
+// ============================================================================
+// `AtkAction`
+
+static gint tcw_accessible_get_n_actions(AtkAction *action) {
+    return TCW_ACCESSIBLE(action)->num_actions;
+}
+
+static const gchar *tcw_accessible_action_get_name(AtkAction *action, gint i) {
+    TcwAccessible *self = TCW_ACCESSIBLE(action);
+    if (i < 0 || i >= self->num_actions) {
+        return NULL;
+    }
+    return action_names[self->actions[i]];
+}
+
+static gboolean tcw_accessible_do_action(AtkAction *action, gint i) {
+    TcwAccessible *self = TCW_ACCESSIBLE(action);
+    if (i < 0 || i >= self->num_actions || !self->widget) {
+        return FALSE;
+    }
+
+    size_t wnd_ptr = tcw_wnd_widget_get_wnd_ptr(self->widget);
+    if (!wnd_ptr) {
+        return FALSE;
+    }
+
+    tcw_wnd_widget_access_action_handler(wnd_ptr, self->id, self->actions[i]);
+    return TRUE;
+}
+
+static void tcw_accessible_action_init(AtkActionIface *iface) {
+    iface->get_n_actions = tcw_accessible_get_n_actions;
+    iface->get_name = tcw_accessible_action_get_name;
+    iface->do_action = tcw_accessible_do_action;
+}
+
+// ============================================================================
+// `AtkValue`
+
+static void tcw_accessible_get_value_and_text(AtkValue *obj, gdouble *value,
+                                              gchar **text) {
+    TcwAccessible *self = TCW_ACCESSIBLE(obj);
+    if (value) {
+        *value = self->value;
+    }
+    if (text) {
+        *text = NULL;
+    }
+}
+
+static AtkRange *tcw_accessible_get_range(AtkValue *obj) {
+    TcwAccessible *self = TCW_ACCESSIBLE(obj);
+    if (!self->has_range) {
+        return NULL;
+    }
+    return atk_range_new(self->min_value, self->max_value, NULL);
+}
+
+static void tcw_accessible_get_current_value(AtkValue *obj, GValue *value) {
+    g_value_init(value, G_TYPE_DOUBLE);
+    g_value_set_double(value, TCW_ACCESSIBLE(obj)->value);
+}
+
+static void tcw_accessible_get_minimum_value(AtkValue *obj, GValue *value) {
+    g_value_init(value, G_TYPE_DOUBLE);
+    g_value_set_double(value, TCW_ACCESSIBLE(obj)->min_value);
+}
+
+static void tcw_accessible_get_maximum_value(AtkValue *obj, GValue *value) {
+    g_value_init(value, G_TYPE_DOUBLE);
+    g_value_set_double(value, TCW_ACCESSIBLE(obj)->max_value);
+}
+
+static void tcw_accessible_value_init(AtkValueIface *iface) {
+    iface->get_value_and_text = tcw_accessible_get_value_and_text;
+    iface->get_range = tcw_accessible_get_range;
+    iface->get_current_value = tcw_accessible_get_current_value;
+    iface->get_minimum_value = tcw_accessible_get_minimum_value;
+    iface->get_maximum_value = tcw_accessible_get_maximum_value;
+}
+
+// ============================================================================
+// `AtkText`
+
+static gchar *tcw_accessible_get_text(AtkText *text, gint start_offset,
+                                      gint end_offset) {
+    TcwAccessible *self = TCW_ACCESSIBLE(text);
+    const char *value = self->text_value ? self->text_value : "";
+    glong len = g_utf8_strlen(value, -1);
+
+    if (end_offset < 0 || end_offset > len) {
+        end_offset = (gint)len;
+    }
+    if (start_offset < 0) {
+        start_offset = 0;
+    }
+    if (start_offset > end_offset) {
+        start_offset = end_offset;
+    }
+
+    return g_utf8_substring(value, start_offset, end_offset);
+}
+
+static gint tcw_accessible_get_character_count(AtkText *text) {
+    TcwAccessible *self = TCW_ACCESSIBLE(text);
+    return self->text_value ? (gint)g_utf8_strlen(self->text_value, -1) : 0;
+}
+
+static gint tcw_accessible_get_caret_offset(AtkText *text) {
+    (void)text;
+    return -1;
+}
+
+static void tcw_accessible_text_init(AtkTextIface *iface) {
+    iface->get_text = tcw_accessible_get_text;
+    iface->get_character_count = tcw_accessible_get_character_count;
+    iface->get_caret_offset = tcw_accessible_get_caret_offset;
+}
+
+// ============================================================================
+// Called by `access.rs`
+
+/// Construct a `TcwAccessible` for the node `id` owned by `widget`.
+extern AtkObject *tcw_accessible_new(GtkWidget *widget, uint64_t id) {
+    TcwAccessible *self = g_object_new(TCW_TYPE_ACCESSIBLE, NULL);
+
+    self->widget = widget;
+    g_object_add_weak_pointer(G_OBJECT(widget), (gpointer *)&self->widget);
+    self->id = id;
+
+    atk_object_set_role(ATK_OBJECT(self), ATK_ROLE_PANEL);
+
+    return ATK_OBJECT(self);
+}
+
+/// Update the properties of a `TcwAccessible` and notify the changes.
+extern void tcw_accessible_update(AtkObject *obj, int role, const char *name,
+                                  const char *text_value, int has_range,
+                                  double value, double min_value,
+                                  double max_value, uint32_t states,
+                                  const int *actions, size_t num_actions,
+                                  const float *bounds) {
+    TcwAccessible *self = TCW_ACCESSIBLE(obj);
+
+    if (role >= 0 && role < TCW_ACCESS_ROLE_COUNT &&
+        atk_object_get_role(obj) != atk_roles[role]) {
+        atk_object_set_role(obj, atk_roles[role]);
+    }
+
+    const char *old_name = atk_object_get_name(obj);
+    if (g_strcmp0(old_name, name ? name : "") != 0) {
+        atk_object_set_name(obj, name ? name : "");
+    }
+
+    int value_changed =
+        g_strcmp0(self->text_value, text_value) != 0 ||
+        self->has_range != has_range || self->value != value ||
+        self->min_value != min_value || self->max_value != max_value;
+
+    g_free(self->text_value);
+    self->text_value = g_strdup(text_value);
+    self->has_range = has_range;
+    self->value = value;
+    self->min_value = min_value;
+    self->max_value = max_value;
+
+    if (num_actions > TCW_ACCESS_MAX_ACTIONS) {
+        num_actions = TCW_ACCESS_MAX_ACTIONS;
+    }
+    memcpy(self->actions, actions, sizeof(int) * num_actions);
+    self->num_actions = (int)num_actions;
+
+    memcpy(self->bounds, bounds, sizeof(self->bounds));
+
+    uint32_t old_states = self->states;
+    self->states = states;
+
+    static const struct {
+        uint32_t bit;
+        AtkState state;
+    } state_map[] = {
+        {TCW_ACCESS_STATE_FOCUSABLE, ATK_STATE_FOCUSABLE},
+        {TCW_ACCESS_STATE_FOCUSED, ATK_STATE_FOCUSED},
+        {TCW_ACCESS_STATE_CHECKED, ATK_STATE_CHECKED},
+        {TCW_ACCESS_STATE_DISABLED, ATK_STATE_ENABLED},
+    };
+    for (size_t i = 0; i < G_N_ELEMENTS(state_map); ++i) {
+        uint32_t bit = state_map[i].bit;
+        if ((old_states ^ states) & bit) {
+            gboolean value = (states & bit) != 0;
+            if (bit == TCW_ACCESS_STATE_DISABLED) {
+                value = !value;
+            }
+            atk_object_notify_state_change(obj, state_map[i].state, value);
+        }
+    }
+
+    if (value_changed) {
+        if (has_range) {
+            g_signal_emit_by_name(obj, "value-changed", value, NULL);
+        }
+        g_signal_emit_by_name(obj, "visible-data-changed");
+    }
+}
+
+/// Replace the children of a `TcwAccessible` or `TcwWndAccessible`.
+extern void tcw_accessible_set_children(AtkObject *obj,
+                                        AtkObject *const *children,
+                                        size_t num_children) {
+    GPtrArray *list = children_of(obj);
+    if (!list) {
+        return;
+    }
+
+    // Do nothing if the list did not change
+    if (list->len == num_children &&
+        (num_children == 0 ||
+         memcmp(list->pdata, children, sizeof(gpointer) * num_children) ==
+             0)) {
+        return;
+    }
+
+    // Hold the old children until the notifications are sent
+    GPtrArray *old_list = list;
+    GPtrArray *new_list = g_ptr_array_new_full((guint)num_children,
+                                               g_object_unref);
+    for (size_t i = 0; i < num_children; ++i) {
+        g_ptr_array_add(new_list, g_object_ref(children[i]));
+        atk_object_set_parent(children[i], obj);
+    }
+
+    if (TCW_IS_ACCESSIBLE(obj)) {
+        TCW_ACCESSIBLE(obj)->children = new_list;
+    } else {
+        TCW_WND_ACCESSIBLE(obj)->children = new_list;
+    }
+
+    for (guint i = old_list->len; i-- > 0;) {
+        AtkObject *child = g_ptr_array_index(old_list, i);
+        if (!g_ptr_array_find(new_list, child, NULL)) {
+            g_signal_emit_by_name(obj, "children-changed::remove", i, child);
+        }
+    }
+    for (guint i = 0; i < new_list->len; ++i) {
+        AtkObject *child = g_ptr_array_index(new_list, i);
+        if (!g_ptr_array_find(old_list, child, NULL)) {
+            g_signal_emit_by_name(obj, "children-changed::add", i, child);
+        }
+    }
+
+    g_ptr_array_unref(old_list);
+}
+
+/// Mark a `TcwAccessible` as removed from the tree.
+extern void tcw_accessible_invalidate(AtkObject *obj) {
+    TcwAccessible *self = TCW_ACCESSIBLE(obj);
+
+    if (self->widget) {
+        g_object_remove_weak_pointer(G_OBJECT(self->widget),
+                                     (gpointer *)&self->widget);
+        self->widget = NULL;
+    }
+    g_ptr_array_set_size(self->children, 0);
+
+    atk_object_notify_state_change(obj, ATK_STATE_DEFUNCT, TRUE);
+}
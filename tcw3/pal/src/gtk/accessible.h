@@ -0,0 +1,10 @@
+#pragma once
+
+#include <gtk/gtk.h>
+#include <stdint.h>
+
+// Defined in `accessible.c`
+GType tcw_wnd_accessible_get_type(void);
+
+// Defined in `wndwidget.c`
+size_t tcw_wnd_widget_get_wnd_ptr(GtkWidget *widget);
@@ -0,0 +1,62 @@
+//! Tracks the system appearance setting using `GtkSettings`.
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+
+use super::Wm;
+use crate::{iface, prelude::*, MtLock, MtSticky};
+
+static LISTENER: MtSticky<RefCell<Option<Box<dyn Fn(Wm)>>>, Wm> = {
+    // `None` contains no unsendable value, so this is safe
+    unsafe { MtSticky::new_unchecked(RefCell::new(None)) }
+};
+static SIGNALS_CONNECTED: MtLock<Cell<bool>, Wm> = MtLock::new(Cell::new(false));
+
+pub(super) fn appearance(_: Wm) -> iface::Appearance {
+    let settings = if let Some(settings) = gtk::Settings::get_default() {
+        settings
+    } else {
+        return iface::Appearance::Light;
+    };
+
+    let theme_name = settings.get_property_gtk_theme_name();
+    let theme_name = theme_name.as_ref().map(|s| s.as_str()).unwrap_or("");
+
+    if theme_name.contains("HighContrast") {
+        iface::Appearance::HighContrast
+    } else if settings.get_property_gtk_application_prefer_dark_theme()
+        || theme_name.ends_with("-dark")
+        || theme_name.ends_with("-Dark")
+    {
+        iface::Appearance::Dark
+    } else {
+        iface::Appearance::Light
+    }
+}
+
+pub(super) fn set_listener(wm: Wm, listener: Box<dyn Fn(Wm)>) {
+    LISTENER.get_with_wm(wm).replace(Some(listener));
+
+    let signals_connected = SIGNALS_CONNECTED.get_with_wm(wm);
+    if signals_connected.get() {
+        return;
+    }
+
+    if let Some(settings) = gtk::Settings::get_default() {
+        settings.connect_property_gtk_theme_name_notify(|_| notify());
+        settings.connect_property_gtk_application_prefer_dark_theme_notify(|_| notify());
+        signals_connected.set(true);
+    }
+}
+
+fn notify() {
+    // This is safe because we know we are already in the main thread
+    let wm = unsafe { Wm::global_unchecked() };
+
+    // Defer the call so that the listener can freely call back into the
+    // backend
+    wm.invoke(|wm| {
+        if let Some(listener) = &*LISTENER.get_with_wm(wm).borrow() {
+            listener(wm);
+        }
+    });
+}
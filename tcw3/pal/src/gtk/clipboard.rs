@@ -0,0 +1,117 @@
+//! Clipboard access using `GtkClipboard`.
+//!
+//! The `wait_for_*` methods of `GtkClipboard` run a nested main loop, which
+//! would call arbitrary event handlers from within our methods. To avoid that,
+//! the clipboard contents are retrieved asynchronously whenever the clipboard
+//! owner changes, and the synchronous methods return the cached result.
+use std::cell::{Cell, RefCell};
+
+use super::Wm;
+use crate::{iface, prelude::*, MtLock};
+
+fn gtk_clipboard() -> gtk::Clipboard {
+    gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD)
+}
+
+/// The cached set of formats available in the clipboard. `None` means it's
+/// being queried.
+static FORMATS: MtLock<Cell<Option<iface::ClipboardFormatFlags>>, Wm> =
+    MtLock::new(Cell::new(None));
+/// The cached text contents of the clipboard. `None` means the clipboard
+/// doesn't contain text or it's being retrieved.
+static TEXT: MtLock<RefCell<Option<String>>, Wm> = MtLock::new(RefCell::new(None));
+/// Incremented on every owner change so that the replies to outdated queries
+/// can be discarded.
+static GENERATION: MtLock<Cell<u64>, Wm> = MtLock::new(Cell::new(0));
+static SIGNALS_CONNECTED: MtLock<Cell<bool>, Wm> = MtLock::new(Cell::new(false));
+
+/// Start monitoring the clipboard if we haven't yet.
+fn ensure_monitoring(wm: Wm) {
+    let signals_connected = SIGNALS_CONNECTED.get_with_wm(wm);
+    if !signals_connected.get() {
+        gtk_clipboard().connect_owner_change(|_, _| {
+            // This is safe because we know we are already in the main thread
+            let wm = unsafe { Wm::global_unchecked() };
+            update_cache(wm);
+        });
+        signals_connected.set(true);
+        update_cache(wm);
+    }
+}
+
+fn update_cache(wm: Wm) {
+    let generation = GENERATION.get_with_wm(wm);
+    generation.set(generation.get().wrapping_add(1));
+    let current = generation.get();
+
+    FORMATS.get_with_wm(wm).set(None);
+    TEXT.get_with_wm(wm).replace(None);
+
+    gtk_clipboard().request_targets(move |_, targets| {
+        // This is safe because we know we are already in the main thread
+        let wm = unsafe { Wm::global_unchecked() };
+
+        if GENERATION.get_with_wm(wm).get() != current {
+            return;
+        }
+
+        let has_text = gtk::targets_include_text(targets);
+
+        let mut flags = iface::ClipboardFormatFlags::empty();
+        flags.set(iface::ClipboardFormatFlags::TEXT, has_text);
+
+        FORMATS.get_with_wm(wm).set(Some(flags));
+
+        if has_text {
+            gtk_clipboard().request_text(move |_, text| {
+                let wm = unsafe { Wm::global_unchecked() };
+
+                if GENERATION.get_with_wm(wm).get() == current {
+                    TEXT.get_with_wm(wm).replace(text.map(str::to_owned));
+                }
+            });
+        }
+    });
+}
+
+pub(super) fn formats(wm: Wm) -> iface::ClipboardFormatFlags {
+    // `wait_is_text_available` would run a nested main loop, which is not
+    // allowed in the contexts where this method is usually called (e.g.,
+    // action validation)
+    ensure_monitoring(wm);
+
+    // If the query is still in progress, assume the clipboard contains text.
+    // Pasting is a no-op if it turns out it doesn't.
+    FORMATS
+        .get_with_wm(wm)
+        .get()
+        .unwrap_or(iface::ClipboardFormatFlags::TEXT)
+}
+
+/// Get the cached text contents. Returns `None` if they are still being
+/// retrieved, which is the case when this is called for the first time or
+/// right after the clipboard owner changes. Use `text_async` to wait for the
+/// retrieval to complete.
+pub(super) fn text(wm: Wm) -> Option<String> {
+    ensure_monitoring(wm);
+
+    TEXT.get_with_wm(wm).borrow().clone()
+}
+
+pub(super) fn text_async(_: Wm, f: impl FnOnce(Wm, Option<String>) + 'static) {
+    gtk_clipboard().request_text(move |_, text| {
+        // This is safe because we know we are already in the main thread
+        let wm = unsafe { Wm::global_unchecked() };
+
+        let text = text.map(str::to_owned);
+
+        // GTK might call the callback before `request_text` returns if the
+        // clipboard is owned by this process. `Wm::get_clipboard_text_async`
+        // doesn't allow that, so defer the call.
+        wm.invoke(move |wm| f(wm, text));
+    });
+}
+
+pub(super) fn set_text(_: Wm, text: &str) {
+    gtk_clipboard().set_text(text);
+}
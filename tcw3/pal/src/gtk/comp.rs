@@ -4,6 +4,7 @@
 //! or by a Cairo backend if the adoption of GTK 4 is not fast enough.
 use cairo::ImageSurface;
 use cggeom::{box2, prelude::*, Box2};
+use log::trace;
 
 use super::{Bitmap, LayerAttrs};
 use crate::{iface, swrast};
@@ -41,6 +42,9 @@ impl Compositor {
     }
 
     pub(super) fn new_wnd(&mut self, layer: Option<HLayer>) -> Wnd {
+        // `new` is `const fn`, so the environment is checked here
+        self.sr_scrn.set_color_mode(swrast::ColorMode::from_env());
+
         let wnd = Wnd {
             cairo_img: None,
             sr_wnd: self.sr_scrn.new_wnd(),
@@ -135,19 +139,23 @@ impl Compositor {
 
     /// Render the contents of `Wnd::cairo_surface()`. Returns a rectangle
     /// encompassing the re-rendered rectangle.
+    ///
+    /// Only the tiles intersecting with the dirty region are re-rendered.
+    /// The statistics of the rendering are logged at the trace level.
     pub(super) fn paint_wnd(&mut self, wnd: &mut Wnd) -> Option<Box2<usize>> {
         if let Some(dirty_rect) = wnd.dirty_rect.take() {
             // Paint the image
             let image = wnd.cairo_img.as_mut().unwrap();
             let image_stride = image.get_stride() as usize;
             let mut image_data = image.get_data().unwrap();
-            self.sr_scrn.render_wnd(
+            let stats = self.sr_scrn.render_wnd(
                 &wnd.sr_wnd,
                 &mut image_data[dirty_rect.min.x * 4 + dirty_rect.min.y * image_stride..],
                 image_stride,
                 dirty_rect,
                 &mut self.binner,
             );
+            trace!("Rendered {:?} of the window: {:?}", dirty_rect, stats);
             Some(dirty_rect)
         } else {
             None
@@ -174,6 +182,8 @@ fn layer_attrs_to_sr_layer_attrs(
         }),
         opacity: attrs.opacity,
         flags: attrs.flags,
+        corner_radii: attrs.corner_radii,
+        shadow: attrs.shadow,
     }
 }
 
@@ -0,0 +1,318 @@
+//! Drag and drop using GTK's drag-and-drop API.
+use cgmath::Point2;
+use gtk::prelude::*;
+use std::cell::RefCell;
+
+use super::{HWnd, Wm};
+use crate::{iface, prelude::*, MtSticky};
+
+/// The `info` value of the target entries for `DragDataTypes::TEXT`.
+const INFO_TEXT: u32 = 0;
+/// The `info` value of the target entries for `DragDataTypes::FILES`.
+const INFO_FILES: u32 = 1;
+
+const TARGET_URI_LIST: &str = "text/uri-list";
+
+/// The target names recognized as `DragDataTypes::TEXT`.
+const TEXT_TARGETS: &[&str] = &[
+    "UTF8_STRING",
+    "text/plain;charset=utf-8",
+    "text/plain",
+    "STRING",
+    "TEXT",
+];
+
+/// The state of the drag-and-drop operation hovering over one of our windows.
+struct DropState {
+    hwnd: HWnd,
+    /// The last location reported by `drag-motion`.
+    loc: Point2<f32>,
+    /// The types accepted by the last call to `WndListener::drag_motion`.
+    accepted_types: iface::DragDataTypes,
+    /// `true` if `drag-drop` has been received, and we are waiting for
+    /// `drag-data-received`.
+    dropping: bool,
+}
+
+/// The state of the drag-and-drop operation started by `start_drag`.
+struct DragSourceState {
+    hwnd: HWnd,
+    data: iface::DragData,
+    on_finish: Box<dyn FnOnce(Wm, bool)>,
+}
+
+static DROP_STATE: MtSticky<RefCell<Option<DropState>>, Wm> = {
+    // `None` contains no unsendable value, so this is safe
+    unsafe { MtSticky::new_unchecked(RefCell::new(None)) }
+};
+
+static DRAG_SOURCE: MtSticky<RefCell<Option<DragSourceState>>, Wm> = {
+    // `None` contains no unsendable value, so this is safe
+    unsafe { MtSticky::new_unchecked(RefCell::new(None)) }
+};
+
+/// Make the window a drop target and a potential drag source. Called by
+/// `HWnd::new_wnd`.
+pub(super) fn connect_wnd_signals(wm: Wm, hwnd: &HWnd, gtk_widget: &gtk::Widget) {
+    let mut targets = vec![gtk::TargetEntry::new(
+        TARGET_URI_LIST,
+        gtk::TargetFlags::empty(),
+        INFO_FILES,
+    )];
+    targets.extend(
+        TEXT_TARGETS
+            .iter()
+            .map(|name| gtk::TargetEntry::new(name, gtk::TargetFlags::empty(), INFO_TEXT)),
+    );
+
+    // We handle everything by ourselves
+    gtk_widget.drag_dest_set(gtk::DestDefaults::empty(), &targets, gdk::DragAction::COPY);
+
+    let hwnd_1 = hwnd.clone();
+    gtk_widget.connect_drag_motion(move |_, context, x, y, time| {
+        let hwnd = &hwnd_1;
+        let listener = if let Some(listener) = hwnd.listener(wm) {
+            listener
+        } else {
+            return Inhibit(false);
+        };
+
+        let loc = Point2::new(x as f32, y as f32);
+        let types = offered_types(context);
+        let accepted_types = listener.drag_motion(wm, hwnd, loc, types) & types;
+
+        let action = if accepted_types.is_empty() {
+            gdk::DragAction::empty()
+        } else {
+            gdk::DragAction::COPY
+        };
+        context.drag_status(action, time);
+
+        DROP_STATE.get_with_wm(wm).replace(Some(DropState {
+            hwnd: hwnd.clone(),
+            loc,
+            accepted_types,
+            dropping: false,
+        }));
+
+        Inhibit(true)
+    });
+
+    let hwnd_1 = hwnd.clone();
+    gtk_widget.connect_drag_leave(move |_, _, _| {
+        // `drag-leave` is also emitted right before `drag-drop`. Defer the
+        // call so that we can tell if it's followed by `drag-drop`.
+        let hwnd = hwnd_1.clone();
+        wm.invoke(move |wm| {
+            let state = {
+                let mut state_cell = DROP_STATE.get_with_wm(wm).borrow_mut();
+                match &*state_cell {
+                    Some(state) if state.hwnd == hwnd && !state.dropping => state_cell.take(),
+                    _ => None,
+                }
+            };
+
+            if state.is_some() {
+                if let Some(listener) = hwnd.listener(wm) {
+                    listener.drag_leave(wm, &hwnd);
+                }
+            }
+        });
+    });
+
+    let hwnd_1 = hwnd.clone();
+    gtk_widget.connect_drag_drop(move |gtk_widget, context, x, y, time| {
+        let mut state_cell = DROP_STATE.get_with_wm(wm).borrow_mut();
+        let state = match &mut *state_cell {
+            Some(state) if state.hwnd == hwnd_1 && !state.accepted_types.is_empty() => state,
+            _ => return Inhibit(false),
+        };
+
+        state.loc = Point2::new(x as f32, y as f32);
+        state.dropping = true;
+
+        // Request the data in the preferred format
+        let target_names: &[&str] = if state.accepted_types.contains(iface::DragDataTypes::FILES) {
+            &[TARGET_URI_LIST]
+        } else {
+            TEXT_TARGETS
+        };
+        let target = context
+            .list_targets()
+            .into_iter()
+            .find(|atom| target_names.contains(&atom.name().as_str()));
+
+        drop(state_cell);
+
+        if let Some(target) = target {
+            gtk_widget.drag_get_data(context, &target, time);
+        } else {
+            DROP_STATE.get_with_wm(wm).replace(None);
+            context.drag_finish(false, false, time);
+        }
+
+        Inhibit(true)
+    });
+
+    let hwnd_1 = hwnd.clone();
+    gtk_widget.connect_drag_data_received(move |_, context, _, _, selection, info, time| {
+        let state = {
+            let mut state_cell = DROP_STATE.get_with_wm(wm).borrow_mut();
+            match &*state_cell {
+                Some(state) if state.hwnd == hwnd_1 && state.dropping => state_cell.take(),
+                _ => None,
+            }
+        };
+
+        let accepted = (|| {
+            let state = state?;
+            let listener = hwnd_1.listener(wm)?;
+
+            let mut data = iface::DragData::default();
+            if info == INFO_FILES {
+                data.files = selection
+                    .get_uris()
+                    .iter()
+                    .filter_map(|uri| glib::filename_from_uri(uri).ok())
+                    .map(|(path, _)| path)
+                    .collect();
+            } else {
+                data.text = selection.get_text().map(String::from);
+            }
+            data.retain_types(state.accepted_types);
+
+            if data.types().is_empty() {
+                listener.drag_leave(wm, &hwnd_1);
+                return None;
+            }
+
+            Some(listener.drag_drop(wm, &hwnd_1, state.loc, &data))
+        })()
+        .unwrap_or(false);
+
+        context.drag_finish(accepted, false, time);
+    });
+
+    let hwnd_1 = hwnd.clone();
+    gtk_widget.connect_drag_data_get(move |_, _, selection, info, _| {
+        let source_cell = DRAG_SOURCE.get_with_wm(wm).borrow();
+        let source = match &*source_cell {
+            Some(source) if source.hwnd == hwnd_1 => source,
+            _ => return,
+        };
+
+        if info == INFO_FILES {
+            let uris: Vec<_> = (source.data.files.iter())
+                .filter_map(|path| glib::filename_to_uri(path, None).ok())
+                .collect();
+            let uris: Vec<&str> = uris.iter().map(|uri| uri.as_str()).collect();
+            selection.set_uris(&uris);
+        } else if let Some(text) = &source.data.text {
+            selection.set_text(text, -1);
+        }
+    });
+
+    let hwnd_1 = hwnd.clone();
+    gtk_widget.connect_drag_end(move |_, context| {
+        let source = {
+            let mut source_cell = DRAG_SOURCE.get_with_wm(wm).borrow_mut();
+            match &*source_cell {
+                Some(source) if source.hwnd == hwnd_1 => source_cell.take(),
+                _ => None,
+            }
+        };
+
+        if let Some(source) = source {
+            (source.on_finish)(wm, context.drag_drop_succeeded());
+        }
+    });
+}
+
+/// Forget the drag-and-drop operations associated with a window being
+/// removed.
+pub(super) fn wnd_removed(wm: Wm, hwnd: &HWnd) {
+    {
+        let mut state_cell = DROP_STATE.get_with_wm(wm).borrow_mut();
+        if state_cell.as_ref().map(|state| &state.hwnd) == Some(hwnd) {
+            *state_cell = None;
+        }
+    }
+
+    let source = {
+        let mut source_cell = DRAG_SOURCE.get_with_wm(wm).borrow_mut();
+        if source_cell.as_ref().map(|source| &source.hwnd) == Some(hwnd) {
+            source_cell.take()
+        } else {
+            None
+        }
+    };
+
+    if let Some(source) = source {
+        wm.invoke(move |wm| (source.on_finish)(wm, false));
+    }
+}
+
+/// Implements `Wm::start_drag`.
+pub(super) fn start_drag(
+    wm: Wm,
+    hwnd: &HWnd,
+    data: iface::DragData,
+    on_finish: Box<dyn FnOnce(Wm, bool)>,
+) {
+    let target_list = gtk::TargetList::new(&[]);
+    if !data.files.is_empty() {
+        target_list.add_uri_targets(INFO_FILES);
+    }
+    if data.text.is_some() {
+        target_list.add_text_targets(INFO_TEXT);
+    }
+
+    let button = hwnd.pressed_mouse_button(wm).unwrap_or(1);
+
+    // Cancel the current operation
+    let old_source = DRAG_SOURCE.get_with_wm(wm).replace(Some(DragSourceState {
+        hwnd: hwnd.clone(),
+        data,
+        on_finish,
+    }));
+    if let Some(old_source) = old_source {
+        wm.invoke(move |wm| (old_source.on_finish)(wm, false));
+    }
+
+    // Use the current event (which is supposed to be a motion event) to
+    // determine the starting location
+    let context = hwnd.gtk_widget(wm).drag_begin_with_coordinates(
+        &target_list,
+        gdk::DragAction::COPY,
+        button as i32,
+        None,
+        -1,
+        -1,
+    );
+
+    if context.is_none() {
+        let source = DRAG_SOURCE.get_with_wm(wm).replace(None);
+        if let Some(source) = source {
+            wm.invoke(move |wm| (source.on_finish)(wm, false));
+        }
+    }
+
+    // GTK grabs the pointer, so our widget won't receive the button release
+    // event for the current mouse drag gesture
+    let hwnd = hwnd.clone();
+    wm.invoke(move |wm| hwnd.cancel_mouse_drag(wm));
+}
+
+/// Get the data types offered by a drag source.
+fn offered_types(context: &gdk::DragContext) -> iface::DragDataTypes {
+    let mut types = iface::DragDataTypes::empty();
+    for atom in context.list_targets() {
+        let name = atom.name();
+        if name.as_str() == TARGET_URI_LIST {
+            types |= iface::DragDataTypes::FILES;
+        } else if TEXT_TARGETS.contains(&name.as_str()) {
+            types |= iface::DragDataTypes::TEXT;
+        }
+    }
+    types
+}
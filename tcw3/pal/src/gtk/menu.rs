@@ -0,0 +1,241 @@
+//! Popup menus using `GtkMenu` and menu bars using `GtkMenuBar`.
+use cggeom::Box2;
+use gtk::prelude::*;
+use std::{cell::RefCell, rc::Rc};
+
+use super::{HWnd, Wm};
+use crate::{iface, prelude::*, MtSticky};
+
+/// The currently open popup menu.
+static POPUP_MENU: MtSticky<RefCell<Option<gtk::Menu>>, Wm> = {
+    // `None` contains no unsendable value, so this is safe
+    unsafe { MtSticky::new_unchecked(RefCell::new(None)) }
+};
+
+pub(super) fn popup_menu(
+    wm: Wm,
+    hwnd: &HWnd,
+    items: &[iface::MenuItem],
+    anchor: Box2<f32>,
+    listener: Box<dyn iface::MenuListener<Wm>>,
+) {
+    // Close the current popup menu. Its `deactivate` handler calls
+    // `MenuListener::dismissed`.
+    close_popup_menu(wm);
+
+    let gtk_widget = hwnd.gtk_widget(wm);
+    let gdk_window = if let Some(gdk_window) = gtk_widget.get_window() {
+        gdk_window
+    } else {
+        // The window isn't realized yet
+        let hwnd = hwnd.clone();
+        wm.invoke(move |wm| listener.dismissed(wm, &hwnd));
+        return;
+    };
+
+    let listener: Rc<dyn iface::MenuListener<Wm>> = Rc::from(listener);
+
+    let gtk_menu = build_menu(wm, hwnd, items, &listener);
+
+    {
+        let hwnd = hwnd.clone();
+        gtk_menu.connect_deactivate(move |gtk_menu| {
+            // `deactivate` is emitted before the chosen item's `activate`, so
+            // defer the call to maintain the documented order
+            let hwnd = hwnd.clone();
+            let listener = Rc::clone(&listener);
+            let gtk_menu = gtk_menu.clone();
+            wm.invoke(move |wm| {
+                {
+                    let mut popup_menu = POPUP_MENU.get_with_wm(wm).borrow_mut();
+                    if popup_menu.as_ref() == Some(&gtk_menu) {
+                        *popup_menu = None;
+                    }
+                }
+                gtk_menu.destroy();
+                listener.dismissed(wm, &hwnd);
+            });
+        });
+    }
+
+    gtk_menu.set_attach_widget(Some(&gtk_widget));
+    gtk_menu.show_all();
+
+    let rect = gdk::Rectangle {
+        x: anchor.min.x.floor() as i32,
+        y: anchor.min.y.floor() as i32,
+        width: (anchor.max.x.ceil() - anchor.min.x.floor()).max(1.0) as i32,
+        height: (anchor.max.y.ceil() - anchor.min.y.floor()).max(1.0) as i32,
+    };
+
+    // The menu grabs the keyboard, so it can be navigated with arrow keys
+    gtk_menu.popup_at_rect(
+        &gdk_window,
+        &rect,
+        gdk::Gravity::SouthWest,
+        gdk::Gravity::NorthWest,
+        None,
+    );
+
+    POPUP_MENU.get_with_wm(wm).replace(Some(gtk_menu));
+}
+
+fn close_popup_menu(wm: Wm) {
+    let gtk_menu = POPUP_MENU.get_with_wm(wm).borrow_mut().take();
+    if let Some(gtk_menu) = gtk_menu {
+        // This emits `deactivate`
+        gtk_menu.deactivate();
+    }
+}
+
+fn build_menu(
+    wm: Wm,
+    hwnd: &HWnd,
+    items: &[iface::MenuItem],
+    listener: &Rc<dyn iface::MenuListener<Wm>>,
+) -> gtk::Menu {
+    let gtk_menu = gtk::Menu::new();
+
+    for item in items.iter() {
+        gtk_menu.append(&build_menu_item(wm, hwnd, item, listener));
+    }
+
+    gtk_menu
+}
+
+fn build_menu_item(
+    wm: Wm,
+    hwnd: &HWnd,
+    item: &iface::MenuItem,
+    listener: &Rc<dyn iface::MenuListener<Wm>>,
+) -> gtk::MenuItem {
+    match item {
+        iface::MenuItem::Action(action) => {
+            let status = listener.validate_action(wm, hwnd, action.action);
+
+            let gtk_item: gtk::MenuItem = if status.contains(iface::ActionStatus::CHECKED) {
+                let gtk_item = gtk::CheckMenuItem::new_with_label(&action.label);
+                // Do this before connecting `activate` because this
+                // emits `activate`
+                gtk_item.set_active(true);
+                gtk_item.upcast()
+            } else {
+                gtk::MenuItem::new_with_label(&action.label)
+            };
+
+            gtk_item.set_sensitive(
+                status.contains(iface::ActionStatus::VALID | iface::ActionStatus::ENABLED),
+            );
+
+            if let Some(shortcut) = &action.shortcut {
+                set_shortcut(&gtk_item, shortcut);
+            }
+
+            let hwnd = hwnd.clone();
+            let listener = Rc::clone(listener);
+            let action = action.action;
+            gtk_item.connect_activate(move |_| {
+                listener.perform_action(wm, &hwnd, action);
+            });
+
+            gtk_item
+        }
+        iface::MenuItem::Submenu(label, items) => {
+            let gtk_item = gtk::MenuItem::new_with_label(label);
+            let gtk_submenu = build_menu(wm, hwnd, items, listener);
+            gtk_item.set_submenu(Some(&gtk_submenu));
+            gtk_item
+        }
+        iface::MenuItem::Sep => gtk::SeparatorMenuItem::new().upcast(),
+    }
+}
+
+/// Construct a `GtkMenuBar` for a window's menu bar.
+///
+/// The submenus are rebuilt every time they are about to be opened, so that
+/// the states of the items reflect the current return values of
+/// `WndListener::validate_action`.
+pub(super) fn new_menu_bar(wm: Wm, hwnd: &HWnd, items: &[iface::MenuItem]) -> gtk::MenuBar {
+    let gtk_menu_bar = gtk::MenuBar::new();
+    let listener: Rc<dyn iface::MenuListener<Wm>> = Rc::new(WndMenuListener);
+
+    for item in items.iter() {
+        let gtk_item = match item {
+            iface::MenuItem::Submenu(label, items) => {
+                let gtk_item = gtk::MenuItem::new_with_label(label);
+
+                // The item must have a submenu to be displayed as such
+                gtk_item.set_submenu(Some(&gtk::Menu::new()));
+
+                let hwnd = hwnd.clone();
+                let items = items.clone();
+                let listener = Rc::clone(&listener);
+                gtk_item.connect_select(move |gtk_item| {
+                    let gtk_submenu = build_menu(wm, &hwnd, &items, &listener);
+                    gtk_submenu.show_all();
+                    gtk_item.set_submenu(Some(&gtk_submenu));
+                });
+
+                gtk_item
+            }
+            _ => build_menu_item(wm, hwnd, item, &listener),
+        };
+
+        gtk_menu_bar.append(&gtk_item);
+    }
+
+    gtk_menu_bar.show_all();
+    gtk_menu_bar
+}
+
+/// Routes the actions of a window's menu bar to the window's `WndListener`.
+struct WndMenuListener;
+
+impl iface::MenuListener<Wm> for WndMenuListener {
+    fn validate_action(&self, wm: Wm, hwnd: &HWnd, action: iface::ActionId) -> iface::ActionStatus {
+        if let Some(listener) = hwnd.listener(wm) {
+            listener.validate_action(wm, hwnd, action)
+        } else {
+            iface::ActionStatus::empty()
+        }
+    }
+
+    fn perform_action(&self, wm: Wm, hwnd: &HWnd, action: iface::ActionId) {
+        if let Some(listener) = hwnd.listener(wm) {
+            listener.perform_action(wm, hwnd, action);
+        }
+    }
+}
+
+/// Display a keyboard shortcut (e.g., `Ctrl+Shift+Z`) in a menu item.
+fn set_shortcut(gtk_item: &gtk::MenuItem, shortcut: &str) {
+    let accel_label = match gtk_item
+        .get_child()
+        .and_then(|w| w.downcast::<gtk::AccelLabel>().ok())
+    {
+        Some(x) => x,
+        None => return,
+    };
+
+    // Convert the shortcut to the format accepted by `gtk_accelerator_parse`
+    // (e.g., `<Control><Shift>z`)
+    let mut accel = String::new();
+    let mut parts: Vec<&str> = shortcut.split('+').collect();
+    let key = parts.pop().unwrap_or("");
+    for part in parts {
+        accel.push_str(match part {
+            "Ctrl" => "<Control>",
+            "Shift" => "<Shift>",
+            "Alt" => "<Alt>",
+            "Super" => "<Super>",
+            "Meta" => "<Meta>",
+            _ => return,
+        });
+    }
+    accel.push_str(key);
+
+    let (keyval, mods) = gtk::accelerator_parse(&accel);
+    if keyval != 0 {
+        accel_label.set_accel(keyval, mods);
+    }
+}
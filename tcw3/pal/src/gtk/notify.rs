@@ -0,0 +1,535 @@
+//! Desktop notifications using the `org.freedesktop.Notifications` D-Bus
+//! interface.
+//!
+//! <https://specifications.freedesktop.org/notification-spec/latest/>
+//!
+//! The session bus connection is opened asynchronously when a notification is
+//! shown and released when all notifications are closed. Notifications shown
+//! while connecting are queued and sent once the connection is established.
+use glib::translate::{FromGlibPtrFull, ToGlibPtr};
+use log::warn;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::CStr,
+    os::raw::{c_char, c_uint},
+    ptr::{null, null_mut},
+    rc::Rc,
+};
+
+use super::{access::c_string_lossy, Bitmap, Wm};
+use crate::{iface, prelude::*, swrast::Bmp, MtSticky};
+
+const BUS_NAME: &[u8] = b"org.freedesktop.Notifications\0";
+const OBJECT_PATH: &[u8] = b"/org/freedesktop/Notifications\0";
+const INTERFACE_NAME: &[u8] = b"org.freedesktop.Notifications\0";
+
+/// The action key reported when the notification body is clicked.
+const DEFAULT_ACTION: &str = "default";
+
+static STATE: MtSticky<RefCell<Option<State>>, Wm> = {
+    // `None` contains no unsendable value, so this is safe
+    unsafe { MtSticky::new_unchecked(RefCell::new(None)) }
+};
+
+struct State {
+    conn: Conn,
+    next_id: u64,
+    notes: HashMap<iface::NotificationId, Note>,
+}
+
+enum Conn {
+    /// There is no connection. It's opened when a notification is shown.
+    Disconnected,
+    /// Waiting for `g_bus_get` to complete. Contains the notifications to send
+    /// once connected.
+    Connecting(Vec<(iface::NotificationId, iface::Notification<Bitmap>)>),
+    Connected {
+        conn: gio::DBusConnection,
+        /// The subscription to the signals of the notification server.
+        subscription: c_uint,
+    },
+    /// We couldn't connect to the bus.
+    Unavailable,
+}
+
+struct Note {
+    /// The identifier assigned by the notification server. `None` until
+    /// `Notify` returns.
+    server_id: Option<u32>,
+    listener: Rc<dyn iface::NotificationListener<Wm>>,
+}
+
+/// Describes how `show_notification` should proceed.
+enum Route {
+    /// Send the notification through the connection.
+    Send(gio::DBusConnection, iface::Notification<Bitmap>),
+    /// The notification was queued. `connect` indicates whether a connection
+    /// should be opened.
+    Queued { connect: bool },
+    /// Notifications are unavailable.
+    Unavailable,
+}
+
+impl State {
+    fn new() -> Self {
+        Self {
+            conn: Conn::Disconnected,
+            next_id: 1,
+            notes: HashMap::new(),
+        }
+    }
+
+    fn find_by_server_id(&self, server_id: u32) -> Option<iface::NotificationId> {
+        (self.notes.iter())
+            .find(|(_, note)| note.server_id == Some(server_id))
+            .map(|(&id, _)| id)
+    }
+
+    /// Get the connection if it's established.
+    fn conn(&self) -> Option<gio::DBusConnection> {
+        match &self.conn {
+            Conn::Connected { conn, .. } => Some(conn.clone()),
+            _ => None,
+        }
+    }
+
+    /// Release the connection if there are no notifications left.
+    fn release_if_idle(&mut self) {
+        if !self.notes.is_empty() {
+            return;
+        }
+
+        if let Conn::Connected { conn, subscription } = &self.conn {
+            unsafe {
+                gio_sys::g_dbus_connection_signal_unsubscribe(conn.to_glib_none().0, *subscription);
+            }
+
+            // This drops the reference to the connection
+            self.conn = Conn::Disconnected;
+        }
+    }
+}
+
+fn with_state<R>(wm: Wm, f: impl FnOnce(&mut State) -> R) -> R {
+    let mut state = STATE.get_with_wm(wm).borrow_mut();
+    f(state.get_or_insert_with(State::new))
+}
+
+/// Start connecting to the session bus. `handle_bus_get` is called when done.
+fn connect() {
+    unsafe {
+        gio_sys::g_bus_get(
+            gio_sys::G_BUS_TYPE_SESSION,
+            null_mut(),
+            Some(handle_bus_get),
+            null_mut(),
+        );
+    }
+}
+
+unsafe extern "C" fn handle_bus_get(
+    _source: *mut gobject_sys::GObject,
+    res: *mut gio_sys::GAsyncResult,
+    _user_data: glib_sys::gpointer,
+) {
+    let wm = Wm::global_unchecked();
+
+    let mut error = null_mut();
+    let conn = gio_sys::g_bus_get_finish(res, &mut error);
+
+    if conn.is_null() {
+        let error = glib::Error::from_glib_full(error);
+        warn!("Could not connect to the session bus: {}", error);
+
+        let pending = with_state(wm, |state| {
+            match std::mem::replace(&mut state.conn, Conn::Unavailable) {
+                Conn::Connecting(pending) => pending,
+                _ => unreachable!(),
+            }
+        });
+
+        // Notifications are unavailable
+        for (id, _) in pending {
+            close_notification(wm, id);
+        }
+        return;
+    }
+
+    let conn: gio::DBusConnection = FromGlibPtrFull::from_glib_full(conn);
+
+    // Subscribe to the signals of the notification server
+    let subscription = gio_sys::g_dbus_connection_signal_subscribe(
+        conn.to_glib_none().0,
+        BUS_NAME.as_ptr() as _,
+        INTERFACE_NAME.as_ptr() as _,
+        null(), // all signals
+        OBJECT_PATH.as_ptr() as _,
+        null(),
+        gio_sys::G_DBUS_SIGNAL_FLAGS_NONE,
+        Some(handle_signal),
+        null_mut(),
+        None,
+    );
+
+    let pending = with_state(wm, |state| {
+        let connected = Conn::Connected {
+            conn: conn.clone(),
+            subscription,
+        };
+        let pending = match std::mem::replace(&mut state.conn, connected) {
+            Conn::Connecting(pending) => pending,
+            _ => unreachable!(),
+        };
+
+        // The notifications might have been closed while connecting
+        state.release_if_idle();
+
+        (pending.into_iter())
+            .filter(|(id, _)| state.notes.contains_key(id))
+            .collect::<Vec<_>>()
+    });
+
+    for (id, notification) in pending {
+        // The server hasn't assigned an identifier to any of the queued
+        // notifications, so there's nothing to replace
+        call_notify(&conn, id, &notification, 0);
+    }
+}
+
+/// Implements `Wm::show_notification`.
+pub(super) fn show_notification(
+    wm: Wm,
+    notification: iface::Notification<Bitmap>,
+    replaces: Option<iface::NotificationId>,
+    listener: Box<dyn iface::NotificationListener<Wm>>,
+) -> iface::NotificationId {
+    let listener: Rc<dyn iface::NotificationListener<Wm>> = Rc::from(listener);
+
+    let (id, replaces_server_id, old_listener, route) = with_state(wm, |state| {
+        let old_note = replaces.and_then(|id| Some((id, state.notes.remove(&id)?)));
+
+        let (id, replaces_server_id, old_listener) = if let Some((id, old_note)) = old_note {
+            (id, old_note.server_id, Some(old_note.listener))
+        } else {
+            let id = iface::NotificationId(state.next_id);
+            state.next_id += 1;
+            (id, None, None)
+        };
+
+        state.notes.insert(
+            id,
+            Note {
+                server_id: replaces_server_id,
+                listener: Rc::clone(&listener),
+            },
+        );
+
+        let route = match &mut state.conn {
+            Conn::Connected { conn, .. } => Route::Send(conn.clone(), notification),
+            Conn::Connecting(pending) => {
+                // Supersede the replaced notification if it's still queued
+                pending.retain(|(i, _)| *i != id);
+                pending.push((id, notification));
+                Route::Queued { connect: false }
+            }
+            Conn::Disconnected => {
+                state.conn = Conn::Connecting(vec![(id, notification)]);
+                Route::Queued { connect: true }
+            }
+            Conn::Unavailable => Route::Unavailable,
+        };
+
+        (id, replaces_server_id, old_listener, route)
+    });
+
+    if let Some(old_listener) = old_listener {
+        wm.invoke(move |wm| old_listener.closed(wm, id));
+    }
+
+    match route {
+        Route::Send(conn, notification) => unsafe {
+            call_notify(&conn, id, &notification, replaces_server_id.unwrap_or(0));
+        },
+        Route::Queued { connect: true } => connect(),
+        Route::Queued { connect: false } => {}
+        Route::Unavailable => close_notification(wm, id),
+    }
+
+    id
+}
+
+/// Call the `Notify` method. `handle_notify_reply` receives the identifier
+/// assigned by the server.
+unsafe fn call_notify(
+    conn: &gio::DBusConnection,
+    id: iface::NotificationId,
+    notification: &iface::Notification<Bitmap>,
+    replaces_server_id: u32,
+) {
+    let params = notify_params(notification, replaces_server_id);
+
+    gio_sys::g_dbus_connection_call(
+        conn.to_glib_none().0,
+        BUS_NAME.as_ptr() as _,
+        OBJECT_PATH.as_ptr() as _,
+        INTERFACE_NAME.as_ptr() as _,
+        b"Notify\0".as_ptr() as _,
+        params,
+        b"(u)\0".as_ptr() as _,
+        gio_sys::G_DBUS_CALL_FLAGS_NONE,
+        -1,
+        null_mut(),
+        Some(handle_notify_reply),
+        Box::into_raw(Box::new(id)) as _,
+    );
+}
+
+/// Implements `Wm::close_notification`.
+pub(super) fn close_notification(wm: Wm, id: iface::NotificationId) {
+    let (note, conn) = with_state(wm, |state| {
+        let note = state.notes.remove(&id);
+        let conn = state.conn();
+        state.release_if_idle();
+        (note, conn)
+    });
+
+    let note = if let Some(note) = note {
+        note
+    } else {
+        return;
+    };
+
+    // If `Notify` hasn't returned yet, `handle_notify_reply` closes the
+    // notification
+    if let (Some(conn), Some(server_id)) = (conn, note.server_id) {
+        unsafe { call_close_notification(conn.to_glib_none().0, server_id) };
+    }
+
+    let listener = note.listener;
+    wm.invoke(move |wm| listener.closed(wm, id));
+}
+
+unsafe fn call_close_notification(conn: *mut gio_sys::GDBusConnection, server_id: u32) {
+    let params = glib_sys::g_variant_new_tuple(&glib_sys::g_variant_new_uint32(server_id), 1);
+
+    gio_sys::g_dbus_connection_call(
+        conn,
+        BUS_NAME.as_ptr() as _,
+        OBJECT_PATH.as_ptr() as _,
+        INTERFACE_NAME.as_ptr() as _,
+        b"CloseNotification\0".as_ptr() as _,
+        params,
+        null(),
+        gio_sys::G_DBUS_CALL_FLAGS_NONE,
+        -1,
+        null_mut(),
+        None,
+        null_mut(),
+    );
+}
+
+/// Construct the parameters (`susssasa{sv}i`) of the `Notify` method.
+unsafe fn notify_params(
+    notification: &iface::Notification<Bitmap>,
+    replaces_server_id: u32,
+) -> *mut glib_sys::GVariant {
+    let app_name = glib::get_application_name()
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+
+    // `actions` is a list of alternating keys and labels
+    let actions = glib_sys::g_variant_builder_new(b"as\0".as_ptr() as _);
+    for (key, label) in std::iter::once((DEFAULT_ACTION, ""))
+        .chain((notification.actions.iter()).map(|a| (&a.id[..], &a.label[..])))
+    {
+        glib_sys::g_variant_builder_add_value(actions, new_string(key));
+        glib_sys::g_variant_builder_add_value(actions, new_string(label));
+    }
+
+    let hints = glib_sys::g_variant_builder_new(b"a{sv}\0".as_ptr() as _);
+    if let Some(icon) = &notification.icon {
+        glib_sys::g_variant_builder_add_value(
+            hints,
+            glib_sys::g_variant_new_dict_entry(
+                new_string("image-data"),
+                glib_sys::g_variant_new_variant(new_image_data(icon)),
+            ),
+        );
+    }
+
+    let children = [
+        new_string(&app_name),
+        glib_sys::g_variant_new_uint32(replaces_server_id),
+        new_string(""), // app_icon
+        new_string(&notification.title),
+        new_string(&notification.body),
+        glib_sys::g_variant_builder_end(actions),
+        glib_sys::g_variant_builder_end(hints),
+        glib_sys::g_variant_new_int32(-1), // expire_timeout
+    ];
+
+    glib_sys::g_variant_builder_unref(actions);
+    glib_sys::g_variant_builder_unref(hints);
+
+    glib_sys::g_variant_new_tuple(children.as_ptr(), children.len())
+}
+
+unsafe fn new_string(s: &str) -> *mut glib_sys::GVariant {
+    glib_sys::g_variant_new_string(c_string_lossy(s).as_ptr())
+}
+
+/// Construct an `image-data` hint (`iiibiiay`), which is a non-premultiplied
+/// RGBA image.
+unsafe fn new_image_data(bmp: &Bitmap) -> *mut glib_sys::GVariant {
+    let [width, height] = Bmp::size(bmp);
+    let stride = Bmp::stride(bmp);
+    let data = Bmp::data(bmp);
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        let row = &data[y * stride..][..width * 4];
+        for px in row.chunks_exact(4) {
+            // Cairo's `ARGB32` (premultiplied, native endian)
+            let px = u32::from_ne_bytes([px[0], px[1], px[2], px[3]]);
+            let a = px >> 24;
+            let unpremul = |c: u32| {
+                if a == 0 {
+                    0
+                } else {
+                    ((c * 255 + a / 2) / a).min(255) as u8
+                }
+            };
+            rgba.extend_from_slice(&[
+                unpremul((px >> 16) & 0xff),
+                unpremul((px >> 8) & 0xff),
+                unpremul(px & 0xff),
+                a as u8,
+            ]);
+        }
+    }
+
+    let children = [
+        glib_sys::g_variant_new_int32(width as i32),
+        glib_sys::g_variant_new_int32(height as i32),
+        glib_sys::g_variant_new_int32(width as i32 * 4), // rowstride
+        glib_sys::g_variant_new_boolean(glib_sys::GTRUE), // has_alpha
+        glib_sys::g_variant_new_int32(8),                // bits_per_sample
+        glib_sys::g_variant_new_int32(4),                // channels
+        glib_sys::g_variant_new_fixed_array(
+            b"y\0".as_ptr() as _,
+            rgba.as_ptr() as _,
+            rgba.len(),
+            1,
+        ),
+    ];
+
+    glib_sys::g_variant_new_tuple(children.as_ptr(), children.len())
+}
+
+unsafe fn child_u32(v: *mut glib_sys::GVariant, i: usize) -> u32 {
+    let child = glib_sys::g_variant_get_child_value(v, i);
+    let x = glib_sys::g_variant_get_uint32(child);
+    glib_sys::g_variant_unref(child);
+    x
+}
+
+unsafe fn child_string(v: *mut glib_sys::GVariant, i: usize) -> String {
+    let child = glib_sys::g_variant_get_child_value(v, i);
+    let x = CStr::from_ptr(glib_sys::g_variant_get_string(child, null_mut()))
+        .to_string_lossy()
+        .into_owned();
+    glib_sys::g_variant_unref(child);
+    x
+}
+
+unsafe extern "C" fn handle_notify_reply(
+    source: *mut gobject_sys::GObject,
+    res: *mut gio_sys::GAsyncResult,
+    user_data: glib_sys::gpointer,
+) {
+    let id = *Box::from_raw(user_data as *mut iface::NotificationId);
+    let conn = source as *mut gio_sys::GDBusConnection;
+    let wm = Wm::global_unchecked();
+
+    let mut error = null_mut();
+    let reply = gio_sys::g_dbus_connection_call_finish(conn, res, &mut error);
+
+    if reply.is_null() {
+        let error = glib::Error::from_glib_full(error);
+        warn!("Could not display a notification: {}", error);
+        close_notification(wm, id);
+        return;
+    }
+
+    let server_id = child_u32(reply, 0);
+    glib_sys::g_variant_unref(reply);
+
+    let stale_server_id = with_state(wm, |state| {
+        if let Some(note) = state.notes.get_mut(&id) {
+            // If the notification was replaced before the server assigned an
+            // identifier, the server created two notifications. Close the
+            // older one.
+            note.server_id
+                .replace(server_id)
+                .filter(|&old| old != server_id)
+        } else {
+            // `close_notification` was called before `Notify` returned
+            Some(server_id)
+        }
+    });
+
+    if let Some(stale_server_id) = stale_server_id {
+        call_close_notification(conn, stale_server_id);
+    }
+}
+
+unsafe extern "C" fn handle_signal(
+    _conn: *mut gio_sys::GDBusConnection,
+    _sender_name: *const c_char,
+    _object_path: *const c_char,
+    _interface_name: *const c_char,
+    signal_name: *const c_char,
+    params: *mut glib_sys::GVariant,
+    _user_data: glib_sys::gpointer,
+) {
+    let wm = Wm::global_unchecked();
+
+    match CStr::from_ptr(signal_name).to_bytes() {
+        b"ActionInvoked" => {
+            if glib_sys::g_variant_is_of_type(params, b"(us)\0".as_ptr() as _) == 0 {
+                return;
+            }
+            let server_id = child_u32(params, 0);
+            let key = child_string(params, 1);
+
+            let found = with_state(wm, |state| {
+                let id = state.find_by_server_id(server_id)?;
+                Some((id, Rc::clone(&state.notes[&id].listener)))
+            });
+
+            if let Some((id, listener)) = found {
+                let action = Some(&key[..]).filter(|&k| k != DEFAULT_ACTION);
+                listener.activated(wm, id, action);
+            }
+        }
+        b"NotificationClosed" => {
+            if glib_sys::g_variant_is_of_type(params, b"(uu)\0".as_ptr() as _) == 0 {
+                return;
+            }
+            let server_id = child_u32(params, 0);
+
+            let found = with_state(wm, |state| {
+                let id = state.find_by_server_id(server_id)?;
+                let note = state.notes.remove(&id).unwrap();
+                state.release_if_idle();
+                Some((id, note.listener))
+            });
+
+            if let Some((id, listener)) = found {
+                listener.closed(wm, id);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,108 @@
+//! Tooltips using a popup `GtkWindow`.
+use cggeom::Box2;
+use gdk::prelude::*;
+use gtk::prelude::*;
+use std::cell::RefCell;
+
+use super::{HWnd, Wm};
+use crate::MtSticky;
+
+/// The gap between the anchor rectangle and a tooltip.
+const GAP: i32 = 4;
+
+struct Tooltip {
+    owner: HWnd,
+    gtk_wnd: gtk::Window,
+    gtk_label: gtk::Label,
+}
+
+/// The currently displayed tooltip.
+static TOOLTIP: MtSticky<RefCell<Option<Tooltip>>, Wm> = {
+    // `None` contains no unsendable value, so this is safe
+    unsafe { MtSticky::new_unchecked(RefCell::new(None)) }
+};
+
+pub(super) fn show_tooltip(wm: Wm, hwnd: &HWnd, text: &str, anchor: Box2<f32>) {
+    let gdk_window = if let Some(gdk_window) = hwnd.gdk_window(wm) {
+        gdk_window
+    } else {
+        // The window isn't realized yet
+        return;
+    };
+
+    let mut tooltip_cell = TOOLTIP.get_with_wm(wm).borrow_mut();
+
+    let tooltip = tooltip_cell.get_or_insert_with(|| {
+        let gtk_wnd = gtk::Window::new(gtk::WindowType::Popup);
+        gtk_wnd.set_type_hint(gdk::WindowTypeHint::Tooltip);
+        gtk_wnd.set_resizable(false);
+        gtk_wnd.set_accept_focus(false);
+
+        // Use the theme's tooltip style
+        gtk_wnd.get_style_context().add_class("tooltip");
+
+        let gtk_label = gtk::Label::new(None);
+        gtk_label.set_line_wrap(true);
+        gtk_label.set_max_width_chars(60);
+        gtk_wnd.add(&gtk_label);
+
+        Tooltip {
+            owner: hwnd.clone(),
+            gtk_wnd,
+            gtk_label,
+        }
+    });
+
+    tooltip.owner = hwnd.clone();
+    tooltip.gtk_label.set_text(text);
+    tooltip
+        .gtk_wnd
+        .set_transient_for(Some(&hwnd.gtk_window(wm)));
+    tooltip.gtk_label.show();
+
+    // Calculate the anchor rectangle in the screen coordinates
+    let (_, origin_x, origin_y) = gdk_window.get_origin();
+    let anchor_x = [
+        origin_x + anchor.min.x.floor() as i32,
+        origin_x + anchor.max.x.ceil() as i32,
+    ];
+    let anchor_y = [
+        origin_y + anchor.min.y.floor() as i32,
+        origin_y + anchor.max.y.ceil() as i32,
+    ];
+
+    let (_, size) = tooltip.gtk_wnd.get_preferred_size();
+
+    // Place the tooltip below the anchor, horizontally centered
+    let mut x = (anchor_x[0] + anchor_x[1] - size.width) / 2;
+    let mut y = anchor_y[1] + GAP;
+
+    // Keep the tooltip inside the work area of the monitor
+    if let Some(monitor) = gdk_window.get_display().get_monitor_at_window(&gdk_window) {
+        let area = monitor.get_workarea();
+
+        if y + size.height > area.y + area.height {
+            // There isn't enough space below the anchor; place it above
+            y = anchor_y[0] - GAP - size.height;
+        }
+
+        x = x.min(area.x + area.width - size.width).max(area.x);
+        y = y.min(area.y + area.height - size.height).max(area.y);
+    }
+
+    tooltip.gtk_wnd.move_(x, y);
+    tooltip.gtk_wnd.resize(size.width, size.height);
+    tooltip.gtk_wnd.show();
+}
+
+pub(super) fn hide_tooltip(wm: Wm, hwnd: &HWnd) {
+    let mut tooltip_cell = TOOLTIP.get_with_wm(wm).borrow_mut();
+
+    if let Some(tooltip) = &*tooltip_cell {
+        if tooltip.owner == *hwnd {
+            let tooltip = tooltip_cell.take().unwrap();
+            drop(tooltip_cell);
+            tooltip.gtk_wnd.destroy();
+        }
+    }
+}
@@ -22,3 +22,6 @@ extern void tcw_wnd_widget_smooth_scroll_handler(size_t wnd_ptr, float x,
                                                  float delta_y, uint32_t time);
 extern void tcw_wnd_widget_smooth_scroll_stop_handler(size_t wnd_ptr,
                                                       uint32_t time);
+extern void tcw_wnd_widget_access_action_handler(size_t wnd_ptr, uint64_t id,
+                                                 int action);
+extern void tcw_wnd_widget_access_activated_handler(size_t wnd_ptr);
@@ -7,6 +7,7 @@ use glib::{
 use gtk::prelude::*;
 use leakypool::{LazyToken, LeakyPool, PoolPtr, SingletonToken, SingletonTokenId};
 use std::{
+    borrow::Cow,
     cell::{Cell, RefCell, RefMut},
     num::Wrapping,
     os::raw::{c_int, c_uint},
@@ -14,7 +15,7 @@ use std::{
     rc::Rc,
 };
 
-use super::{comp, Wm, WndAttrs};
+use super::{access, comp, Wm, WndAttrs};
 use crate::{actions, iface, prelude::*, MtSticky};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -35,7 +36,10 @@ static DRAWING_WND: MtSticky<Cell<Option<WndPoolPtr>>, Wm> = MtSticky::new(Cell:
 
 struct Wnd {
     gtk_wnd: gtk::Window,
+    /// Contains `gtk_menu_bar` and `gtk_widget`.
+    gtk_box: gtk::Box,
     gtk_widget: WndWidget,
+    gtk_menu_bar: Option<gtk::MenuBar>,
     comp_wnd: comp::Wnd,
     listener: Rc<dyn iface::WndListener<Wm>>,
     flags: iface::WndFlags,
@@ -43,11 +47,63 @@ struct Wnd {
     /// The last known size of the window.
     size: [i32; 2],
 
+    /// The minimum and maximum size of the content region, which excludes
+    /// the menu bar.
+    min_size: Option<[u32; 2]>,
+    max_size: Option<[u32; 2]>,
+
     tick_callback_active: bool,
     tick_callback_continue: bool,
 
     drag_state: Option<MouseDragState>,
     scroll_state: Option<ScrollState>,
+
+    access_objects: access::AccessObjects,
+}
+
+impl Wnd {
+    /// Get the height of the menu bar, or `0` if there's none.
+    fn menu_bar_height(&self) -> i32 {
+        (self.gtk_menu_bar.as_ref())
+            .map(|w| w.get_preferred_height().1)
+            .unwrap_or(0)
+    }
+
+    /// Apply `min_size` and `max_size` to the window. The hints apply to the
+    /// window's contents as a whole (GTK adds the client-side decorations by
+    /// itself), so the menu bar's height must be added.
+    fn update_geometry_hints(&self) {
+        let menu_bar_height = self.menu_bar_height();
+        let mut mask = gdk::WindowHints::empty();
+        let mut geom = gdk::Geometry {
+            min_width: 0,
+            min_height: 0,
+            max_width: 0,
+            max_height: 0,
+            base_width: 0,
+            base_height: 0,
+            width_inc: 0,
+            height_inc: 0,
+            min_aspect: 0.0,
+            max_aspect: 0.0,
+            win_gravity: gdk::Gravity::NorthWest,
+        };
+
+        if let Some([w, h]) = self.min_size {
+            mask |= gdk::WindowHints::MIN_SIZE;
+            geom.min_width = w as i32;
+            geom.min_height = h as i32 + menu_bar_height;
+        }
+        if let Some([w, h]) = self.max_size {
+            mask |= gdk::WindowHints::MAX_SIZE;
+            geom.max_width = w.min(i32::max_value() as u32) as i32;
+            geom.max_height =
+                (h.min(i32::max_value() as u32) as i32).saturating_add(menu_bar_height);
+        }
+
+        self.gtk_wnd
+            .set_geometry_hints(None::<&gtk::Widget>, Some(&geom), mask);
+    }
 }
 
 struct MouseDragState {
@@ -86,7 +142,9 @@ impl HWnd {
 
         let gtk_widget = WndWidget::new(wm);
 
-        gtk_wnd.add(&gtk_widget);
+        let gtk_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        gtk_box.pack_end(&gtk_widget, true, true, 0);
+        gtk_wnd.add(&gtk_box);
         gtk_widget.set_hexpand(true);
         gtk_widget.set_vexpand(true);
 
@@ -107,15 +165,20 @@ impl HWnd {
 
         let wnd = Wnd {
             gtk_wnd,
+            gtk_box,
             gtk_widget,
+            gtk_menu_bar: None,
             comp_wnd,
             flags: iface::WndFlags::default(),
             listener: Rc::new(()),
             size: [0, 0],
+            min_size: None,
+            max_size: None,
             tick_callback_active: false,
             tick_callback_continue: false,
             drag_state: None,
             scroll_state: None,
+            access_objects: access::AccessObjects::default(),
         };
 
         let mut wnds = WNDS.get_with_wm(wm).borrow_mut();
@@ -145,6 +208,8 @@ impl HWnd {
             listener.focus(wm, &Self { ptr });
         });
 
+        super::dnd::connect_wnd_signals(wm, &Self { ptr }, wnd.gtk_widget.upcast_ref());
+
         // `set_wnd_attr` borrows `WNDS`, so unborrow it before calling that
         drop(wnds);
 
@@ -156,20 +221,49 @@ impl HWnd {
     /// Implements `Wm::set_wnd_attr`.
     pub(super) fn set_wnd_attr(&self, wm: Wm, attrs: WndAttrs<'_>) {
         let mut wnds = WNDS.get_with_wm(wm).borrow_mut();
+
+        if let Some(owner) = attrs.owner {
+            let owner_gtk_wnd = owner.map(|owner| wnds[owner.ptr].gtk_wnd.clone());
+            wnds[self.ptr]
+                .gtk_wnd
+                .set_transient_for(owner_gtk_wnd.as_ref());
+        }
+
         let wnd = &mut wnds[self.ptr];
 
+        if let Some(modal) = attrs.modal {
+            wnd.gtk_wnd.set_modal(modal);
+        }
+
+        if let Some(items) = attrs.menu_bar {
+            if let Some(gtk_menu_bar) = wnd.gtk_menu_bar.take() {
+                wnd.gtk_box.remove(&gtk_menu_bar);
+            }
+
+            if !items.is_empty() {
+                let gtk_menu_bar = super::menu::new_menu_bar(wm, self, &items);
+                wnd.gtk_box.pack_start(&gtk_menu_bar, false, false, 0);
+                wnd.gtk_menu_bar = Some(gtk_menu_bar);
+            }
+        }
+
+        // `size`, `min_size`, and `max_size` specify the size of the content
+        // region, which excludes the menu bar
         if let Some(size) = attrs.size {
-            wnd.gtk_wnd.resize(size[0] as i32, size[1] as i32);
-            wnd.gtk_wnd.set_default_size(size[0] as i32, size[1] as i32);
+            let height = size[1] as i32 + wnd.menu_bar_height();
+            wnd.gtk_wnd.resize(size[0] as i32, height);
+            wnd.gtk_wnd.set_default_size(size[0] as i32, height);
         }
 
         if let Some(size) = attrs.min_size {
-            wnd.gtk_widget
-                .set_size_request(size[0] as i32, size[1] as i32);
+            wnd.min_size = Some(size);
+        }
+        if let Some(size) = attrs.max_size {
+            wnd.max_size = Some(size);
+        }
+        if attrs.menu_bar.is_some() || attrs.min_size.is_some() || attrs.max_size.is_some() {
+            wnd.update_geometry_hints();
         }
-
-        // TODO: `max_size`. Dimensions passed to `set_geometry_hints` must
-        //       include the window title bar and the border
 
         if let Some(flags) = attrs.flags {
             // TODO: BORDERLESS
@@ -269,12 +363,17 @@ impl HWnd {
 
     /// Implements `Wm::remove_wnd`.
     pub(super) fn remove_wnd(&self, wm: Wm) {
-        let wnd = WNDS
+        super::tooltip::hide_tooltip(wm, self);
+        super::dnd::wnd_removed(wm, self);
+
+        let mut wnd = WNDS
             .get_with_wm(wm)
             .borrow_mut()
             .deallocate(self.ptr)
             .unwrap();
 
+        wnd.access_objects.invalidate();
+
         // Delete scroll tick callback
         if let Some(scroll_state) = &wnd.scroll_state {
             if let Some(momentum_state) = &scroll_state.momentum {
@@ -432,12 +531,63 @@ impl HWnd {
         wnd.gtk_wnd.clone()
     }
 
+    pub(super) fn gtk_widget(&self, wm: Wm) -> gtk::Widget {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        let wnd = &wnds[self.ptr];
+        wnd.gtk_widget.clone().upcast()
+    }
+
     pub(super) fn gdk_window(&self, wm: Wm) -> Option<gdk::Window> {
         let wnds = WNDS.get_with_wm(wm).borrow();
         let wnd = &wnds[self.ptr];
         wnd.gtk_wnd.get_window()
     }
 
+    pub(super) fn listener(&self, wm: Wm) -> Option<Rc<dyn iface::WndListener<Wm>>> {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        let wnd = wnds.get(self.ptr)?;
+        Some(Rc::clone(&wnd.listener))
+    }
+
+    /// Implements `Wm::update_access_tree`.
+    pub(super) fn update_access_tree(&self, wm: Wm, root: &iface::AccessNode) {
+        let mut wnds = WNDS.get_with_wm(wm).borrow_mut();
+        let wnd = &mut wnds[self.ptr];
+        let widget: gtk::Widget = wnd.gtk_widget.clone().upcast();
+        wnd.access_objects.update(&widget, root);
+    }
+
+    pub(super) fn is_access_active(&self, wm: Wm) -> bool {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        wnds.get(self.ptr)
+            .map_or(false, |wnd| wnd.access_objects.is_active())
+    }
+
+    /// Get the GTK button number of the lowest-numbered mouse button pressed
+    /// in the current mouse drag gesture.
+    pub(super) fn pressed_mouse_button(&self, wm: Wm) -> Option<u32> {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        let drag_state = wnds.get(self.ptr)?.drag_state.as_ref()?;
+        if drag_state.pressed_buttons == 0 {
+            None
+        } else {
+            Some(drag_state.pressed_buttons.trailing_zeros() + 1)
+        }
+    }
+
+    /// Cancel the current mouse drag gesture.
+    pub(super) fn cancel_mouse_drag(&self, wm: Wm) {
+        let drag_state = {
+            let mut wnds = WNDS.get_with_wm(wm).borrow_mut();
+            wnds.get_mut(self.ptr).and_then(|wnd| wnd.drag_state.take())
+        };
+
+        // Unborrow `WNDS` before calling into user code
+        if let Some(drag_state) = drag_state {
+            drag_state.listener.cancel(wm, self);
+        }
+    }
+
     pub(super) fn set_im_ctx_active(
         &self,
         wm: Wm,
@@ -1025,6 +1175,53 @@ extern "C" fn tcw_wnd_widget_smooth_scroll_stop_handler(wnd_ptr: WndPtr, time: W
     })();
 }
 
+/// Handles `AtkObject::initialize` of `TcwWndAccessible`.
+#[no_mangle]
+extern "C" fn tcw_wnd_widget_access_activated_handler(wnd_ptr: WndPtr) {
+    let wm = unsafe { Wm::global_unchecked() };
+    let ptr = if let Some(ptr) = wnd_ptr {
+        ptr
+    } else {
+        return;
+    };
+
+    // This might be called while `WNDS` is borrowed, so defer the call
+    wm.invoke(move |wm| {
+        {
+            let mut wnds = WNDS.get_with_wm(wm).borrow_mut();
+            if let Some(wnd) = wnds.get_mut(ptr) {
+                wnd.access_objects.activate();
+            } else {
+                return;
+            }
+        }
+
+        let hwnd = HWnd { ptr };
+        if let Some(listener) = hwnd.listener(wm) {
+            listener.access_activated(wm, &hwnd);
+        }
+    });
+}
+
+/// Handles `AtkAction::do_action` of `TcwAccessible`.
+#[no_mangle]
+extern "C" fn tcw_wnd_widget_access_action_handler(wnd_ptr: WndPtr, id: u64, action: c_int) {
+    let wm = unsafe { Wm::global_unchecked() };
+    let (ptr, action) = match (wnd_ptr, access::action_from_int(action)) {
+        (Some(ptr), Some(action)) => (ptr, action),
+        _ => return,
+    };
+
+    // This might be called while `WNDS` is borrowed (e.g., while we are
+    // updating the accessibility tree), so defer the call
+    wm.invoke(move |wm| {
+        let hwnd = HWnd { ptr };
+        if let Some(listener) = hwnd.listener(wm) {
+            listener.access_action(wm, &hwnd, iface::AccessNodeId(id), action);
+        }
+    });
+}
+
 extern "C" fn handle_momentum_scroll_tick_callback(
     _: *mut gtk_sys::GtkWidget,
     frame_clock: *mut gdk_sys::GdkFrameClock,
@@ -1164,11 +1361,11 @@ fn stop_scroll(wm: Wm, mut wnds: RefMut<'_, WndPool>, hwnd: HWnd) -> RefMut<'_,
 #[derive(Debug)]
 pub struct AccelTable {
     #[doc(hidden)]
-    pub key: &'static [ActionKeyBinding],
+    pub key: Cow<'static, [ActionKeyBinding]>,
 }
 
 #[doc(hidden)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ActionKeyBinding {
     pub action: iface::ActionId,
     pub mod_flags: u8,
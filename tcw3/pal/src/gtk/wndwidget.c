@@ -1,6 +1,7 @@
 #include <gtk/gtk.h>
 #include <stdint.h>
 
+#include "accessible.h"
 #include "window.h"
 
 #define TCW_TYPE_WND_WIDGET (tcw_wnd_widget_get_type())
@@ -62,6 +63,9 @@ static void tcw_wnd_widget_class_init(TcwWndWidgetClass *klass) {
     widget_class->scroll_event = tcw_wnd_widget_scroll_event;
     widget_class->key_press_event = tcw_wnd_widget_key_press_event;
     widget_class->key_release_event = tcw_wnd_widget_key_release_event;
+
+    gtk_widget_class_set_accessible_type(widget_class,
+                                         tcw_wnd_accessible_get_type());
 }
 
 static void tcw_wnd_widget_init(TcwWndWidget *self) {
@@ -221,3 +225,8 @@ static gboolean tcw_wnd_widget_key_release_event(GtkWidget *widget,
 extern TcwWndWidget *tcw_wnd_widget_new(void) {
     return g_object_new(TCW_TYPE_WND_WIDGET, NULL);
 }
+
+/// Called by `accessible.c`. Returns `wnd_ptr` of a `TcwWndWidget`.
+extern size_t tcw_wnd_widget_get_wnd_ptr(GtkWidget *widget) {
+    return TCW_WND_WIDGET(widget)->wnd_ptr;
+}
@@ -10,7 +10,15 @@ use bitflags::bitflags;
 use cggeom::{box2, Box2};
 use cgmath::{Matrix3, Point2, Vector2};
 use rgb::RGBA;
-use std::{borrow::Cow, fmt, fmt::Debug, hash::Hash, ops::Range, time::Duration};
+use std::{
+    borrow::Cow,
+    fmt,
+    fmt::Debug,
+    hash::Hash,
+    ops::Range,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 pub type RGBAF32 = RGBA<f32>;
 
@@ -38,7 +46,9 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     ///
     /// `Wm` doesn't provide a method for constructing this type. You should use
     /// the [`accel_table!`](accel_table) macro to create an accelerator table.
-    type AccelTable: Debug + Send + Sync;
+    /// Accelerator tables can also be constructed at runtime by
+    /// [`AccelTableNew`].
+    type AccelTable: Debug + Send + Sync + AccelTableNew;
 
     /// A bitmap type.
     type Bitmap: Bitmap;
@@ -100,6 +110,16 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     /// associated function will never be called.
     fn cancel_invoke(self, hinv: &Self::HInvoke);
 
+    /// Get the current time for the purpose of animation.
+    ///
+    /// Animations should use this method instead of `Instant::now` so that
+    /// they can be controlled by the testing backend.
+    ///
+    /// The default implementation returns `Instant::now()`.
+    fn now(self) -> Instant {
+        Instant::now()
+    }
+
     /// Enter the main loop. This method will never return.
     ///
     /// It's not allowed to call this method from a `WndListener`.
@@ -226,6 +246,463 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     ///
     /// [`TextInputCtxListener::edit`] may be called in this method.
     fn remove_text_input_ctx(self, ctx: &Self::HTextInputCtx);
+
+    /// Get the set of data formats currently available in the system
+    /// clipboard.
+    ///
+    /// This method doesn't run a nested event loop, so it can be called from
+    /// any event handlers including action validation. Consequently, the
+    /// returned value may be slightly outdated or a conservative guess. The
+    /// actual contents may turn out to be unavailable when read.
+    ///
+    /// The default implementation behaves as if the clipboard is always empty.
+    fn clipboard_formats(self) -> ClipboardFormatFlags {
+        ClipboardFormatFlags::empty()
+    }
+
+    /// Read plain text from the system clipboard. Returns `None` if the
+    /// clipboard doesn't contain plain text.
+    ///
+    /// This method never runs a nested event loop, so it doesn't call other
+    /// event handlers. Instead, some backends return the contents retrieved
+    /// in the background, which may be slightly outdated or `None` if the
+    /// retrieval is still in progress. Prefer [`get_clipboard_text_async`]
+    /// if you need the up-to-date contents.
+    ///
+    /// [`get_clipboard_text_async`]: Wm::get_clipboard_text_async
+    ///
+    /// The default implementation behaves as if the clipboard is always empty.
+    fn get_clipboard_text(self) -> Option<String> {
+        None
+    }
+
+    /// Read plain text from the system clipboard asynchronously.
+    ///
+    /// `f` is called on the main thread when the contents become available.
+    /// It's never called before this method returns. It receives `None` if
+    /// the clipboard doesn't contain plain text.
+    ///
+    /// The default implementation calls [`get_clipboard_text`] and defers the
+    /// call to `f` using [`invoke`].
+    ///
+    /// [`get_clipboard_text`]: Wm::get_clipboard_text
+    /// [`invoke`]: Wm::invoke
+    fn get_clipboard_text_async(self, f: impl FnOnce(Self, Option<String>) + 'static) {
+        let text = self.get_clipboard_text();
+        self.invoke(move |wm| f(wm, text));
+    }
+
+    /// Replace the contents of the system clipboard with the given plain text.
+    ///
+    /// The default implementation does nothing.
+    fn set_clipboard_text(self, _text: &str) {}
+
+    /// Get the system-wide appearance setting.
+    ///
+    /// The default implementation always returns [`Appearance::Light`].
+    fn appearance(self) -> Appearance {
+        Appearance::Light
+    }
+
+    /// Register a function to be called when the system-wide appearance
+    /// setting (returned by [`appearance`]) is updated. Replaces the
+    /// previously registered function.
+    ///
+    /// This method is intended to be used by the theming system of the UI
+    /// framework. Application code should not call it directly.
+    ///
+    /// [`appearance`]: Wm::appearance
+    ///
+    /// The default implementation does nothing.
+    fn set_appearance_listener(self, _listener: Box<dyn Fn(Self)>) {}
+
+    /// Open a popup menu.
+    ///
+    /// The menu is placed next to `anchor`, a rectangle specified in the
+    /// window's client coordinates. Usually, it's placed below `anchor`, but
+    /// the system may choose a different location if there isn't enough
+    /// space.
+    ///
+    /// The state of each item is determined by calling
+    /// [`MenuListener::validate_action`] when the menu is opened. When the
+    /// user chooses an item, [`MenuListener::perform_action`] is called.
+    /// [`MenuListener::dismissed`] is called when the menu is closed for any
+    /// reason. None of these methods are called before this method returns.
+    ///
+    /// There can be only one popup menu at a time. Opening a popup menu closes
+    /// the current one. The user can navigate through the menu with a
+    /// keyboard.
+    ///
+    /// The default implementation does not show anything and calls
+    /// `MenuListener::dismissed` using [`invoke`].
+    ///
+    /// [`invoke`]: Wm::invoke
+    fn popup_menu(
+        self,
+        window: &Self::HWnd,
+        _items: &[MenuItem],
+        _anchor: Box2<f32>,
+        listener: Box<dyn MenuListener<Self>>,
+    ) {
+        let window = window.clone();
+        self.invoke(move |wm| listener.dismissed(wm, &window));
+    }
+
+    /// Show a tooltip.
+    ///
+    /// The tooltip is a small borderless popup window displaying `text` with
+    /// the system's tooltip style. It's placed below `anchor`, a rectangle
+    /// specified in the window's client coordinates, or above it if there
+    /// isn't enough space. The tooltip is moved as needed to keep it inside
+    /// the screen.
+    ///
+    /// There can be only one tooltip at a time. Showing a tooltip replaces the
+    /// current one. Tooltips don't receive mouse or keyboard inputs.
+    ///
+    /// The default implementation does nothing.
+    fn show_tooltip(self, _window: &Self::HWnd, _text: &str, _anchor: Box2<f32>) {}
+
+    /// Hide the tooltip shown by [`show_tooltip`] if it belongs to `window`.
+    ///
+    /// [`show_tooltip`]: Wm::show_tooltip
+    ///
+    /// The default implementation does nothing.
+    fn hide_tooltip(self, _window: &Self::HWnd) {}
+
+    /// Start a drag-and-drop operation carrying `data` from `window`.
+    ///
+    /// This method should be called from a handler of [`MouseDragListener`]
+    /// while a mouse button is pressed. The system takes over the handling of
+    /// the mouse input, and the current mouse drag gesture is cancelled
+    /// ([`MouseDragListener::cancel`] is called) after this method returns.
+    ///
+    /// `on_finish` is called when the operation is complete. The second
+    /// parameter indicates whether the data was accepted by a drop target.
+    /// It's never called before this method returns.
+    ///
+    /// The default implementation does nothing and calls `on_finish` with
+    /// `false` using [`invoke`].
+    ///
+    /// [`invoke`]: Wm::invoke
+    fn start_drag(
+        self,
+        _window: &Self::HWnd,
+        _data: DragData,
+        on_finish: Box<dyn FnOnce(Self, bool)>,
+    ) {
+        self.invoke(move |wm| on_finish(wm, false));
+    }
+
+    /// Replace the accessibility tree of `window`, which is exposed to
+    /// assistive technologies such as screen readers.
+    ///
+    /// `root` represents the window itself. The nodes' `bounds` are specified
+    /// in the window's client coordinates. When an assistive technology
+    /// requests an action on a node, [`WndListener::access_action`] is called.
+    ///
+    /// The default implementation does nothing.
+    fn update_access_tree(self, _window: &Self::HWnd, _root: AccessNode) {}
+
+    /// Get a flag indicating whether an assistive technology is listening to
+    /// the accessibility tree of `window`.
+    ///
+    /// Clients may skip calling [`Wm::update_access_tree`] while this returns
+    /// `false`. [`WndListener::access_activated`] is called when this flag is
+    /// set.
+    ///
+    /// The default implementation returns `false`.
+    fn is_access_active(self, _window: &Self::HWnd) -> bool {
+        false
+    }
+
+    /// Show a desktop notification.
+    ///
+    /// If `replaces` refers to a notification that is still displayed, the
+    /// notification is updated in place with the new contents. Otherwise, a
+    /// new notification is displayed. Returns an identifier of the
+    /// notification, which can be passed to [`close_notification`] or used as
+    /// `replaces` later.
+    ///
+    /// [`NotificationListener::activated`] is called when the user clicks the
+    /// notification or one of its action buttons.
+    /// [`NotificationListener::closed`] is called exactly once when the
+    /// notification is closed for any reason, including replacement and
+    /// `close_notification`. These methods are called on the main thread and
+    /// never before this method returns.
+    ///
+    /// The default implementation does not show anything, calls
+    /// `NotificationListener::closed` using [`invoke`], and returns `replaces`
+    /// or an arbitrary identifier.
+    ///
+    /// [`close_notification`]: Wm::close_notification
+    /// [`invoke`]: Wm::invoke
+    fn show_notification(
+        self,
+        _notification: Notification<Self::Bitmap>,
+        replaces: Option<NotificationId>,
+        listener: Box<dyn NotificationListener<Self>>,
+    ) -> NotificationId {
+        let id = replaces.unwrap_or(NotificationId(0));
+        self.invoke(move |wm| listener.closed(wm, id));
+        id
+    }
+
+    /// Close a notification displayed by [`show_notification`]. Does nothing
+    /// if the notification is already closed.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// [`show_notification`]: Wm::show_notification
+    fn close_notification(self, _id: NotificationId) {}
+}
+
+/// Represents a system-wide appearance setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Appearance {
+    /// Dark text on a light background.
+    Light,
+    /// Light text on a dark background.
+    Dark,
+    /// A high-contrast appearance intended for users with low vision.
+    HighContrast,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Appearance::Light
+    }
+}
+
+bitflags! {
+    /// Represents a set of data formats stored in the system clipboard.
+    pub struct ClipboardFormatFlags: u8 {
+        /// Plain text.
+        const TEXT = 1;
+    }
+}
+
+bitflags! {
+    /// Represents a set of data types carried by a drag-and-drop operation.
+    pub struct DragDataTypes: u8 {
+        /// Plain text.
+        const TEXT = 1;
+        /// A list of files.
+        const FILES = 1 << 1;
+    }
+}
+
+/// The data carried by a drag-and-drop operation.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DragData {
+    /// Plain text.
+    pub text: Option<String>,
+    /// The paths of files. An empty `Vec` means no files.
+    pub files: Vec<PathBuf>,
+}
+
+impl DragData {
+    /// Construct a `DragData` carrying plain text.
+    pub fn with_text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Construct a `DragData` carrying a list of files.
+    pub fn with_files(files: Vec<PathBuf>) -> Self {
+        Self {
+            files,
+            ..Self::default()
+        }
+    }
+
+    /// Get the set of data types included in `self`.
+    pub fn types(&self) -> DragDataTypes {
+        let mut types = DragDataTypes::empty();
+        types.set(DragDataTypes::TEXT, self.text.is_some());
+        types.set(DragDataTypes::FILES, !self.files.is_empty());
+        types
+    }
+
+    /// Remove the data not included in `types`.
+    pub fn retain_types(&mut self, types: DragDataTypes) {
+        if !types.contains(DragDataTypes::TEXT) {
+            self.text = None;
+        }
+        if !types.contains(DragDataTypes::FILES) {
+            self.files.clear();
+        }
+    }
+}
+
+/// The role of a node in an accessibility tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessRole {
+    /// A window.
+    Window,
+    /// A generic container.
+    Group,
+    /// A push button.
+    Button,
+    /// A checkbox.
+    CheckBox,
+    /// A radio button.
+    RadioButton,
+    /// An editable text field.
+    TextField,
+    /// A slider for choosing a value in a range.
+    Slider,
+    /// A static text.
+    Label,
+    /// A container divided into resizable panels.
+    Split,
+    /// A table.
+    Table,
+    /// A row in a table.
+    Row,
+    /// A cell in a table.
+    Cell,
+}
+
+impl Default for AccessRole {
+    fn default() -> Self {
+        AccessRole::Group
+    }
+}
+
+impl AccessRole {
+    /// Get a flag indicating whether assistive technologies should treat the
+    /// descendants of a node with this role as a part of the node's
+    /// presentation, rather than as separate nodes.
+    pub fn has_presentational_children(self) -> bool {
+        match self {
+            AccessRole::Button
+            | AccessRole::CheckBox
+            | AccessRole::RadioButton
+            | AccessRole::TextField
+            | AccessRole::Slider
+            | AccessRole::Label => true,
+            _ => false,
+        }
+    }
+}
+
+bitflags! {
+    /// The states of a node in an accessibility tree.
+    pub struct AccessStates: u8 {
+        /// The node can receive a keyboard focus.
+        const FOCUSABLE = 1;
+        /// The node has a keyboard focus.
+        const FOCUSED = 1 << 1;
+        /// The node is checked (e.g., a checked checkbox).
+        const CHECKED = 1 << 2;
+        /// The node is disabled.
+        const DISABLED = 1 << 3;
+        /// The node is oriented vertically.
+        const VERTICAL = 1 << 4;
+    }
+}
+
+/// The value of a node in an accessibility tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessValue {
+    /// A text value, e.g., the contents of a text field.
+    Text(String),
+    /// A numeric value in a range, e.g., the position of a slider.
+    Range { value: f64, min: f64, max: f64 },
+}
+
+/// An action performed on a node in an accessibility tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessAction {
+    /// Activate the node, e.g., click a push button.
+    Press,
+    /// Increase the value.
+    Increment,
+    /// Decrease the value.
+    Decrement,
+}
+
+/// Describes a node in an accessibility tree.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AccessInfo {
+    pub role: AccessRole,
+    /// The text describing the node, e.g., the caption of a push button.
+    pub name: Option<String>,
+    pub value: Option<AccessValue>,
+    pub states: AccessStates,
+    /// The actions supported by the node.
+    pub actions: Vec<AccessAction>,
+}
+
+impl AccessInfo {
+    /// Construct an `AccessInfo` with a role.
+    pub fn new(role: AccessRole) -> Self {
+        Self {
+            role,
+            ..Self::default()
+        }
+    }
+
+    /// Update the name.
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Update the value.
+    pub fn with_value(self, value: AccessValue) -> Self {
+        Self {
+            value: Some(value),
+            ..self
+        }
+    }
+
+    /// Update the states.
+    pub fn with_states(self, states: AccessStates) -> Self {
+        Self { states, ..self }
+    }
+
+    /// Update the supported actions.
+    pub fn with_actions(self, actions: Vec<AccessAction>) -> Self {
+        Self { actions, ..self }
+    }
+}
+
+/// Identifies a node in an accessibility tree of a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AccessNodeId(pub u64);
+
+/// A node in an accessibility tree, passed to [`Wm::update_access_tree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessNode {
+    /// The identifier of the node, which must be unique within the tree.
+    pub id: AccessNodeId,
+    pub info: AccessInfo,
+    /// The bounding rectangle in the window's client coordinates.
+    pub bounds: Box2<f32>,
+    pub children: Vec<AccessNode>,
+}
+
+impl AccessNode {
+    /// Find the first node satisfying `pred` in the subtree rooted at `self`
+    /// in a depth-first order.
+    pub fn find(&self, pred: &mut impl FnMut(&AccessNode) -> bool) -> Option<&AccessNode> {
+        if pred(self) {
+            Some(self)
+        } else {
+            self.children.iter().find_map(|child| child.find(pred))
+        }
+    }
+
+    /// Find the first node with the specified role and name in the subtree
+    /// rooted at `self`.
+    pub fn find_by_name(&self, role: AccessRole, name: &str) -> Option<&AccessNode> {
+        self.find(&mut |node| node.info.role == role && node.info.name.as_deref() == Some(name))
+    }
 }
 
 /// Returned when a function/method is called from an invalid thread.
@@ -252,6 +729,28 @@ pub struct WndAttrs<'a, T: Wm, TLayer> {
     pub listener: Option<Box<dyn WndListener<T>>>,
     pub layer: Option<Option<TLayer>>,
     pub cursor_shape: Option<CursorShape>,
+    /// The menu bar of the window. An empty slice removes the menu bar.
+    ///
+    /// The state of each item is determined by calling
+    /// [`WndListener::validate_action`] when the containing menu is opened.
+    /// When the user chooses an item, [`WndListener::perform_action`] is
+    /// called.
+    ///
+    /// This is ignored on macOS, where the menu bar is shared by all windows
+    /// of an application. The Windows backend doesn't support menu bars yet.
+    pub menu_bar: Option<Cow<'a, [MenuItem]>>,
+    /// The owner window. An owned window (e.g., a dialog box) is kept above
+    /// its owner. `Some(None)` removes the owner.
+    ///
+    /// This is ignored by the macOS and Windows backends for now.
+    pub owner: Option<Option<T::HWnd>>,
+    /// Requests the system to treat the window as a modal dialog of its owner
+    /// (e.g., by attaching the window to the owner). This does not prevent
+    /// the owner from receiving input events; the client must filter them
+    /// by itself.
+    ///
+    /// This is ignored by the macOS and Windows backends for now.
+    pub modal: Option<bool>,
 }
 
 impl<'a, T: Wm, TLayer> Default for WndAttrs<'a, T, TLayer> {
@@ -266,6 +765,9 @@ impl<'a, T: Wm, TLayer> Default for WndAttrs<'a, T, TLayer> {
             listener: None,
             layer: None,
             cursor_shape: None,
+            menu_bar: None,
+            owner: None,
+            modal: None,
         }
     }
 }
@@ -394,6 +896,27 @@ pub struct LayerAttrs<TBitmap, TLayer> {
 
     /// Specifies additional options on the layer.
     pub flags: Option<LayerFlags>,
+
+    /// Specifies the radii of the layer's corners in the order of top-left,
+    /// top-right, bottom-right, and bottom-left.
+    ///
+    /// Defaults to `[0.0; 4]`. The corners of the content image and the
+    /// background color are rounded with the specified radii. If
+    /// `MASK_TO_BOUNDS` is set, the sublayers are clipped by the same rounded
+    /// shape.
+    ///
+    /// This is currently only supported by the `swrast`-based backends (GTK
+    /// and testing). The other backends ignore this field.
+    pub corner_radii: Option<[f32; 4]>,
+
+    /// Specifies the shadow drawn behind the layer.
+    ///
+    /// The shadow takes the shape of the layer's bounds with rounded corners
+    /// (`corner_radii`) and doesn't depend on the content image.
+    ///
+    /// This is currently only supported by the `swrast`-based backends (GTK
+    /// and testing). The other backends ignore this field.
+    pub shadow: Option<Option<LayerShadow>>,
 }
 
 impl<TBitmap, TLayer> LayerAttrs<TBitmap, TLayer> {
@@ -415,6 +938,8 @@ impl<TBitmap, TLayer> LayerAttrs<TBitmap, TLayer> {
         process_one!(sublayers);
         process_one!(opacity);
         process_one!(flags);
+        process_one!(corner_radii);
+        process_one!(shadow);
     }
 }
 
@@ -430,10 +955,27 @@ impl<TBitmap, TLayer> Default for LayerAttrs<TBitmap, TLayer> {
             bg_color: None,
             opacity: None,
             flags: None,
+            corner_radii: None,
+            shadow: None,
         }
     }
 }
 
+/// Describes the shadow of a layer. See [`LayerAttrs::shadow`].
+///
+/// The lengths are measured in the layer's coordinate space (the one used to
+/// express [`LayerAttrs::bounds`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerShadow {
+    /// The displacement of the shadow relative to the layer.
+    pub offset: Vector2<f32>,
+    /// The blur radius. This is twice the standard deviation of the Gaussian
+    /// blur applied to the shadow shape.
+    pub blur_radius: f32,
+    /// The color of the shadow.
+    pub color: RGBAF32,
+}
+
 bitflags! {
     pub struct LayerFlags: u32 {
         /// Clip sublayers to the content bounds.
@@ -591,6 +1133,47 @@ pub trait WndListener<T: Wm> {
         Box::new(())
     }
 
+    /// The mouse pointer carrying a drag-and-drop operation has entered or
+    /// moved inside a window. `types` specifies the data types offered by
+    /// the drag source.
+    ///
+    /// Returns the data types the window is willing to accept at `loc`. An
+    /// empty set means the window rejects the drop at the location.
+    fn drag_motion(
+        &self,
+        _: T,
+        _: &T::HWnd,
+        _loc: Point2<f32>,
+        _types: DragDataTypes,
+    ) -> DragDataTypes {
+        DragDataTypes::empty()
+    }
+
+    /// The mouse pointer carrying a drag-and-drop operation has left a window
+    /// or the operation was cancelled.
+    fn drag_leave(&self, _: T, _: &T::HWnd) {}
+
+    /// The data was dropped at `loc`. This is only called if the last call to
+    /// `drag_motion` returned a non-empty set. `data` only includes the types
+    /// returned by the last call to `drag_motion`.
+    ///
+    /// Returns `true` if the data was accepted.
+    fn drag_drop(&self, _: T, _: &T::HWnd, _loc: Point2<f32>, _data: &DragData) -> bool {
+        false
+    }
+
+    /// An assistive technology requested `action` to be performed on the
+    /// node `node` of the window's accessibility tree.
+    ///
+    /// Returns `true` if the node exists and supports the action.
+    fn access_action(&self, _: T, _: &T::HWnd, _node: AccessNodeId, _action: AccessAction) -> bool {
+        false
+    }
+
+    /// An assistive technology started to listen to the window's
+    /// accessibility tree. See [`Wm::is_access_active`].
+    fn access_activated(&self, _: T, _: &T::HWnd) {}
+
     // TODO: more events
     //  - Pointer device gestures (swipe, zoom, rotate)
 }
@@ -610,6 +1193,20 @@ pub trait InterpretEventCtx<AccelTable> {
     fn use_accel(&mut self, haccel: &AccelTable);
 }
 
+/// Constructs an accelerator table from key bindings specified at runtime.
+pub trait AccelTableNew: Sized {
+    /// Construct an accelerator table from key bindings.
+    ///
+    /// The key bindings are interpreted as the ones for the current backend,
+    /// i.e., `Ctrl+C` matches the same key stroke as `gtk("Ctrl+C")` does on
+    /// the `gtk` backend. Key bindings not supported by the backend are
+    /// ignored.
+    ///
+    /// [`KeyBindings::to_accel_table`](crate::accel::KeyBindings::to_accel_table)
+    /// provides a convenient way to call this.
+    fn from_key_bindings(bindings: &[crate::accel::KeyBinding]) -> Self;
+}
+
 /// Identifies a type of action.
 ///
 /// See [`actions`] for the list of standard actions.
@@ -740,6 +1337,166 @@ bitflags! {
     }
 }
 
+/// An item in a menu.
+///
+/// A menu is represented by a slice of `MenuItem`s. Items bound to actions
+/// are validated when the menu is opened. An item is shown as disabled unless
+/// the action is both `VALID` and `ENABLED`, and a check mark is displayed if
+/// the action is `CHECKED`.
+///
+///     use tcw3_pal::{actions, MenuItem};
+///     let items = vec![
+///         MenuItem::action("Undo", actions::UNDO).with_shortcut("Ctrl+Z"),
+///         MenuItem::Sep,
+///         MenuItem::submenu("Transformations", vec![
+///             MenuItem::action("Make Upper Case", actions::UPPERCASE_WORD),
+///             MenuItem::action("Make Lower Case", actions::LOWERCASE_WORD),
+///         ]),
+///     ];
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MenuItem {
+    /// An item that performs an action when chosen.
+    Action(MenuAction),
+    /// An item that opens a submenu.
+    Submenu(String, Vec<MenuItem>),
+    /// A separator.
+    Sep,
+}
+
+/// The contents of [`MenuItem::Action`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuAction {
+    pub label: String,
+    pub action: ActionId,
+    /// The text describing the keyboard shortcut of the action, e.g.,
+    /// `"Ctrl+Z"`. It's only used for display.
+    pub shortcut: Option<String>,
+}
+
+impl MenuItem {
+    /// Construct a `MenuItem::Action`.
+    pub fn action(label: impl Into<String>, action: ActionId) -> Self {
+        MenuItem::Action(MenuAction {
+            label: label.into(),
+            action,
+            shortcut: None,
+        })
+    }
+
+    /// Construct a `MenuItem::Submenu`.
+    pub fn submenu(label: impl Into<String>, items: Vec<MenuItem>) -> Self {
+        MenuItem::Submenu(label.into(), items)
+    }
+
+    /// Update the keyboard shortcut text. Panics if `self` is not
+    /// `MenuItem::Action`.
+    pub fn with_shortcut(self, shortcut: impl Into<String>) -> Self {
+        match self {
+            MenuItem::Action(action) => MenuItem::Action(MenuAction {
+                shortcut: Some(shortcut.into()),
+                ..action
+            }),
+            _ => panic!("only `MenuItem::Action` can have a shortcut"),
+        }
+    }
+
+    /// Get the label of the item. Returns `None` for a separator.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            MenuItem::Action(action) => Some(&action.label),
+            MenuItem::Submenu(label, _) => Some(label),
+            MenuItem::Sep => None,
+        }
+    }
+}
+
+/// Event handlers for a popup menu opened by [`Wm::popup_menu`].
+pub trait MenuListener<T: Wm> {
+    /// Query the state of a menu item's action.
+    fn validate_action(&self, _: T, _: &T::HWnd, _: ActionId) -> ActionStatus {
+        ActionStatus::empty()
+    }
+
+    /// The user has chosen a menu item.
+    fn perform_action(&self, _: T, _: &T::HWnd, _: ActionId) {}
+
+    /// The menu was closed. This is called after `perform_action` if the menu
+    /// was closed by choosing an item.
+    fn dismissed(&self, _: T, _: &T::HWnd) {}
+}
+
+/// A default implementation of [`MenuListener`].
+impl<T: Wm> MenuListener<T> for () {}
+
+/// A desktop notification displayed by [`Wm::show_notification`].
+#[derive(Debug, Clone)]
+pub struct Notification<TBitmap> {
+    pub title: String,
+    pub body: String,
+    /// The image displayed in the notification. The system may display the
+    /// application's icon instead if this is `None`.
+    pub icon: Option<TBitmap>,
+    /// The buttons displayed in the notification. They might not be displayed
+    /// depending on the system.
+    pub actions: Vec<NotificationAction>,
+}
+
+impl<TBitmap> Notification<TBitmap> {
+    /// Construct a `Notification` without an icon or actions.
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: body.into(),
+            icon: None,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Update the icon.
+    pub fn with_icon(self, icon: TBitmap) -> Self {
+        Self {
+            icon: Some(icon),
+            ..self
+        }
+    }
+
+    /// Add an action button.
+    pub fn with_action(mut self, id: impl Into<String>, label: impl Into<String>) -> Self {
+        self.actions.push(NotificationAction {
+            id: id.into(),
+            label: label.into(),
+        });
+        self
+    }
+}
+
+/// An action button in [`Notification`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NotificationAction {
+    /// The string passed to [`NotificationListener::activated`] when the
+    /// button is clicked.
+    pub id: String,
+    pub label: String,
+}
+
+/// Identifies a notification displayed by [`Wm::show_notification`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NotificationId(pub u64);
+
+/// Event handlers for a notification displayed by [`Wm::show_notification`].
+pub trait NotificationListener<T: Wm> {
+    /// The user has clicked the notification (`action` is `None`) or one of
+    /// its action buttons (`action` is the button's
+    /// [`id`](NotificationAction::id)).
+    fn activated(&self, _: T, _: NotificationId, _action: Option<&str>) {}
+
+    /// The notification was closed. No methods are called after this.
+    fn closed(&self, _: T, _: NotificationId) {}
+}
+
+/// A default implementation of [`NotificationListener`].
+impl<T: Wm> NotificationListener<T> for () {}
+
 /// Result type of [`WndListener::nc_hit_test`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NcHit {
@@ -1126,6 +1883,11 @@ pub trait Canvas: Debug {
     /// After the operation, this method resets the current path to an empty
     /// path.
     fn fill(&mut self);
+    /// Fill the area within the current path, using the even-odd rule.
+    ///
+    /// After the operation, this method resets the current path to an empty
+    /// path.
+    fn fill_even_odd(&mut self);
     /// Draw a line along the current path.
     ///
     /// After the operation, this method resets the current path to an empty
@@ -1166,11 +1928,13 @@ pub trait Canvas: Debug {
 
     /// Set the current fill brush to a solid color.
     fn set_fill_rgb(&mut self, rgb: RGBAF32);
-    // TODO: generic brush
+    /// Set the current fill brush to a gradient.
+    fn set_fill_gradient(&mut self, gradient: &Gradient<'_>);
 
     /// Set the current stroke brush to a solid color.
     fn set_stroke_rgb(&mut self, rgb: RGBAF32);
-    // TODO: generic brush
+    /// Set the current stroke brush to a gradient.
+    fn set_stroke_gradient(&mut self, gradient: &Gradient<'_>);
 
     fn set_line_cap(&mut self, cap: LineCap);
     fn set_line_join(&mut self, join: LineJoin);
@@ -1190,9 +1954,59 @@ pub trait Canvas: Debug {
     /// be expressed. `m.z.z` must be positive.
     fn mult_transform(&mut self, m: Matrix3<f32>);
 
+    /// Push a copy of the current graphics state onto the state stack and
+    /// redirect the subsequent drawing operations to a new transparent layer
+    /// (a *group*).
+    ///
+    /// When the matching [`end_group`] is called, the contents of the group
+    /// are composited onto the previous drawing target with the specified
+    /// opacity. This is different from modulating the alpha value of each
+    /// drawing operation in that overlapping shapes inside a group do not
+    /// show through each other.
+    ///
+    /// [`end_group`]: Canvas::end_group
+    fn begin_group(&mut self, opacity: f32);
+    /// Composite the current group onto the previous drawing target and pop a
+    /// graphics state from the state stack.
+    fn end_group(&mut self);
+
     // TODO: image rendering
 }
 
+/// A gradient brush.
+#[derive(Debug, Clone, Copy)]
+pub struct Gradient<'a> {
+    pub kind: GradientKind,
+    /// The transformation from the gradient space (see [`GradientKind`]) to
+    /// the local coordinate space at the point of time when the brush is used
+    /// by a drawing operation.
+    pub xform: Matrix3<f32>,
+    /// The color stops. Must be sorted by [`GradientStop::offset`] and must
+    /// contain at least one element.
+    pub stops: &'a [GradientStop],
+}
+
+/// Specifies how a gradient position is calculated from a point in the
+/// gradient space.
+///
+/// Positions outside the range `[0, 1]` are clamped to the range, i.e., the
+/// colors of the first and last color stops are extended indefinitely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GradientKind {
+    /// The position is the X coordinate.
+    Linear,
+    /// The position is the distance from the origin.
+    Radial,
+}
+
+/// A color stop of [`Gradient`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// The position of the color stop, in range `[0, 1]`.
+    pub offset: f32,
+    pub color: RGBAF32,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LineCap {
     Butt,
@@ -1218,6 +2032,18 @@ pub trait BitmapBuilder: Canvas {
 pub trait BitmapBuilderNew: BitmapBuilder + Sized {
     /// Create a [`BitmapBuilder`] with a R8G8B8A8 backing bitmap.
     fn new(size: [u32; 2]) -> Self;
+
+    /// Create a [`BitmapBuilder`] with a R8G8B8A8 backing bitmap initialized
+    /// with the specified pixels.
+    ///
+    /// `data` contains the pixels in the R8G8B8A8 format with premultiplied
+    /// alpha. The rows are tightly packed and ordered from top to bottom.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `data.len()` is not equal to
+    /// `size[0] * size[1] * 4`.
+    fn with_rgba8_premul(size: [u32; 2], data: &[u8]) -> Self;
 }
 
 /// Encapsulates information needed to layout a given text.
@@ -1234,7 +2060,20 @@ pub trait TextLayout: Send + Sync + Sized {
     type CharStyle: CharStyle;
 
     fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self;
-    // TODO: construct a `TextLayout` from an attributed text
+
+    /// Construct a `TextLayout` from an attributed text. The character styles
+    /// of `text` are applied on top of `style`.
+    ///
+    /// The default implementation ignores the character styles and the inline
+    /// boxes of `text` and lays out the text using `style`. Backends that
+    /// support attributed texts override this.
+    fn from_attr_text(
+        text: &AttrText<Self::CharStyle>,
+        style: &Self::CharStyle,
+        width: Option<f32>,
+    ) -> Self {
+        Self::from_text(text.text(), style, width)
+    }
 
     /// Get the visual bounds of a `TextLayout`.
     fn visual_bounds(&self) -> Box2<f32>;
@@ -1425,7 +2264,6 @@ pub trait TextLayout: Send + Sync + Sized {
     fn next_word(&self, i: usize, forward: bool) -> usize;
 
     // TODO: alignment
-    // TODO: inline/foreign object
 }
 
 /// Represents the geometric position of an insertion cursor within a text
@@ -1510,12 +2348,19 @@ pub trait CharStyle: Clone + Send + Sync + Sized {
     fn size(&self) -> f32;
 }
 
+/// The attributes of a character style. The fields set to `None` are
+/// inherited from `template` (or set to the default values if `template` is
+/// `None`).
 #[allow(clippy::option_option)] // for consistency between fields
 #[derive(Debug, Clone)]
 pub struct CharStyleAttrs<TCharStyle> {
     pub template: Option<TCharStyle>,
     pub sys: Option<SysFontType>,
     pub size: Option<f32>,
+    /// Use a bold font face. Overrides the font weight specified by `sys`.
+    pub bold: Option<bool>,
+    /// Use an italic font face.
+    pub italic: Option<bool>,
     pub decor: Option<TextDecorFlags>,
     /// The text color.
     ///
@@ -1530,12 +2375,200 @@ impl<TCharStyle> Default for CharStyleAttrs<TCharStyle> {
             template: None,
             sys: None,
             size: None,
+            bold: None,
+            italic: None,
             decor: None,
             color: None,
         }
     }
 }
 
+/// An immutable text with character styles applied to ranges and inline
+/// boxes. Constructed by [`AttrTextBuilder`] and laid out by
+/// [`TextLayout::from_attr_text`].
+#[derive(Debug, Clone)]
+pub struct AttrText<TCharStyle> {
+    text: String,
+    spans: Vec<(Range<usize>, CharStyleAttrs<TCharStyle>)>,
+    inline_boxes: Vec<(usize, InlineBox)>,
+}
+
+/// The size of an inline box, which reserves a space in a text layout for
+/// an embedded object such as an emoji or image.
+///
+/// An inline box is represented by U+FFFC OBJECT REPLACEMENT CHARACTER in
+/// the source text. The location of an inline box at the UTF-8 offset `i`
+/// can be found by [`TextLayout::run_metrics_of_range`]`(i..i + 3)` and
+/// [`TextLayout::line_baseline`]. The embedded object is not drawn by
+/// [`CanvasText::draw_text`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InlineBox {
+    /// The width of the box.
+    pub width: f32,
+    /// The distance from the top edge of the box to the baseline.
+    pub ascent: f32,
+    /// The distance from the baseline to the bottom edge of the box.
+    pub descent: f32,
+}
+
+impl InlineBox {
+    /// The character representing an inline box in the source text.
+    pub const CHAR: char = '\u{fffc}';
+}
+
+impl<TCharStyle> AttrText<TCharStyle> {
+    /// Get the source text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the character styles applied to ranges, in the order in which they
+    /// were added. A later one takes precedence over earlier ones.
+    pub fn spans(&self) -> &[(Range<usize>, CharStyleAttrs<TCharStyle>)] {
+        &self.spans
+    }
+
+    /// Get the inline boxes and their UTF-8 offsets in an ascending order.
+    pub fn inline_boxes(&self) -> &[(usize, InlineBox)] {
+        &self.inline_boxes
+    }
+
+    /// Convert the character styles using the given function.
+    pub fn map_char_styles<T>(&self, mut f: impl FnMut(&TCharStyle) -> T) -> AttrText<T> {
+        AttrText {
+            text: self.text.clone(),
+            spans: self
+                .spans
+                .iter()
+                .map(|(range, attrs)| {
+                    let attrs = CharStyleAttrs {
+                        template: attrs.template.as_ref().map(&mut f),
+                        sys: attrs.sys,
+                        size: attrs.size,
+                        bold: attrs.bold,
+                        italic: attrs.italic,
+                        decor: attrs.decor,
+                        color: attrs.color,
+                    };
+                    (range.clone(), attrs)
+                })
+                .collect(),
+            inline_boxes: self.inline_boxes.clone(),
+        }
+    }
+}
+
+impl<TCharStyle: CharStyle> AttrText<TCharStyle> {
+    /// Split the source text into ranges having uniform character styles and
+    /// compute their effective character styles based on `style`.
+    ///
+    /// The returned ranges are a partition of the source text.
+    pub fn resolve_char_styles(&self, style: &TCharStyle) -> Vec<(Range<usize>, TCharStyle)> {
+        let mut bounds = vec![0, self.text.len()];
+        for (range, _) in self.spans.iter() {
+            bounds.push(range.start);
+            bounds.push(range.end);
+        }
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        bounds
+            .windows(2)
+            .map(|w| {
+                let range = w[0]..w[1];
+                let mut style = style.clone();
+                for (span_range, attrs) in self.spans.iter() {
+                    if span_range.start <= range.start && span_range.end >= range.end {
+                        style = TCharStyle::new(CharStyleAttrs {
+                            template: Some(attrs.template.clone().unwrap_or(style)),
+                            ..attrs.clone()
+                        });
+                    }
+                }
+                (range, style)
+            })
+            .collect()
+    }
+}
+
+/// A builder type for [`AttrText`].
+#[derive(Debug, Clone)]
+pub struct AttrTextBuilder<TCharStyle> {
+    inner: AttrText<TCharStyle>,
+}
+
+impl<TCharStyle> Default for AttrTextBuilder<TCharStyle> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TCharStyle> AttrTextBuilder<TCharStyle> {
+    /// Construct an `AttrTextBuilder` with an empty text.
+    pub fn new() -> Self {
+        Self {
+            inner: AttrText {
+                text: String::new(),
+                spans: Vec::new(),
+                inline_boxes: Vec::new(),
+            },
+        }
+    }
+
+    /// Get the text constructed so far.
+    pub fn text(&self) -> &str {
+        &self.inner.text
+    }
+
+    /// Append a string with the base character style. Returns the UTF-8
+    /// offset range of the appended string.
+    pub fn push_str(&mut self, s: &str) -> Range<usize> {
+        let start = self.inner.text.len();
+        self.inner.text.push_str(s);
+        start..self.inner.text.len()
+    }
+
+    /// Append a string with the specified character style. Returns the UTF-8
+    /// offset range of the appended string.
+    pub fn push_styled_str(&mut self, s: &str, attrs: CharStyleAttrs<TCharStyle>) -> Range<usize> {
+        let range = self.push_str(s);
+        self.set_char_style(range.clone(), attrs);
+        range
+    }
+
+    /// Apply a character style to the specified UTF-8 offset range of the text
+    /// constructed so far. The fields of `attrs` set to `None` are inherited
+    /// from the character styles previously applied to the range.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `range` does not lie on UTF-8 codepoint
+    /// boundaries.
+    pub fn set_char_style(&mut self, range: Range<usize>, attrs: CharStyleAttrs<TCharStyle>) {
+        assert!(
+            self.inner.text.get(range.clone()).is_some(),
+            "invalid range: {:?}",
+            range
+        );
+        if range.start < range.end {
+            self.inner.spans.push((range, attrs));
+        }
+    }
+
+    /// Append an inline box. Returns the UTF-8 offset of the inline box.
+    pub fn push_inline_box(&mut self, inline_box: InlineBox) -> usize {
+        let i = self.inner.text.len();
+        self.inner.text.push(InlineBox::CHAR);
+        self.inner.inline_boxes.push((i, inline_box));
+        i
+    }
+
+    /// Construct an `AttrText`.
+    pub fn build(self) -> AttrText<TCharStyle> {
+        self.inner
+    }
+}
+
 bitflags! {
     pub struct TextDecorFlags: u8 {
         const UNDERLINE = 1;
@@ -10,6 +10,7 @@
 // deduced to `()`. Thus a call to `msg_send!` needs a unit value binding
 #![allow(clippy::let_unit_value)]
 
+pub mod accel;
 mod canvas;
 pub mod futuresext;
 pub mod iface;
@@ -21,9 +22,9 @@ pub mod iface;
 pub mod prelude {
     pub use super::cells::{Init, MtLazyStatic, SendInit};
     pub use super::iface::{
-        Bitmap, BitmapBuilder, BitmapBuilderNew, Canvas, CanvasText, CharStyle, KeyEvent,
-        MouseDragListener, ScrollListener, TextInputCtxEdit, TextInputCtxListener, TextLayout,
-        Wm as WmTrait, WndListener,
+        AccelTableNew, Bitmap, BitmapBuilder, BitmapBuilderNew, Canvas, CanvasText, CharStyle,
+        KeyEvent, MouseDragListener, ScrollListener, TextInputCtxEdit, TextInputCtxListener,
+        TextLayout, Wm as WmTrait, WndListener,
     };
 
     pub use super::futuresext::WmFuturesExt;
@@ -205,9 +206,12 @@ pub type TextLayout = current::TextLayout;
 // the default backend.
 
 pub use self::iface::{
-    actions, ActionId, ActionStatus, BadThread, Beam, CursorShape, IndexFromPointFlags,
-    InterpretEventCtx, LayerFlags, LineCap, LineJoin, NcHit, RunFlags, RunMetrics, ScrollDelta,
-    SysFontType, TextDecorFlags, TextInputCtxEventFlags, WndFlags, RGBAF32,
+    actions, AccessAction, AccessInfo, AccessNode, AccessNodeId, AccessRole, AccessStates,
+    AccessValue, ActionId, ActionStatus, Appearance, BadThread, Beam, ClipboardFormatFlags,
+    CursorShape, DragData, DragDataTypes, Gradient, GradientKind, GradientStop,
+    IndexFromPointFlags, InlineBox, InterpretEventCtx, LayerFlags, LayerShadow, LineCap, LineJoin,
+    MenuAction, MenuItem, NcHit, NotificationAction, NotificationId, RunFlags, RunMetrics,
+    ScrollDelta, SysFontType, TextDecorFlags, TextInputCtxEventFlags, WndFlags, RGBAF32,
 };
 
 /// The window handle type of [`Wm`].
@@ -231,9 +235,18 @@ pub type WndAttrs<'a> = iface::WndAttrs<'a, Wm, HLayer>;
 /// A specialization of `LayerAttrs` for the default backend.
 pub type LayerAttrs = iface::LayerAttrs<Bitmap, HLayer>;
 
+/// A specialization of `Notification` for the default backend.
+pub type Notification = iface::Notification<Bitmap>;
+
 /// A specialization of `CharStyleAttrs` for the default backend.
 pub type CharStyleAttrs = iface::CharStyleAttrs<CharStyle>;
 
+/// A specialization of `AttrText` for the default backend.
+pub type AttrText = iface::AttrText<CharStyle>;
+
+/// A specialization of `AttrTextBuilder` for the default backend.
+pub type AttrTextBuilder = iface::AttrTextBuilder<CharStyle>;
+
 // Trait aliases (unstable at the point of writing) actually do not work
 // exactly like type aliases. Specifically, they cannot be used in every place
 // where traits can be used, like `impl` blocks.
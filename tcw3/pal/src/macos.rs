@@ -23,6 +23,7 @@ use cocoa::{
 };
 use objc::{msg_send, sel, sel_impl};
 
+mod acceltable;
 mod timer;
 mod window;
 pub use self::{
@@ -0,0 +1,171 @@
+//! Accelerator tables constructed at runtime
+use std::{borrow::Cow, convert::TryFrom};
+
+use super::{AccelTable, ActionKeyBinding};
+use crate::{
+    accel::{Key, KeyBinding, ModFlags},
+    iface,
+};
+
+// `NSEventModifierFlags`
+const NS_SHIFT_KEY_MASK: u32 = 1 << 17;
+const NS_CONTROL_KEY_MASK: u32 = 1 << 18;
+const NS_ALTERNATE_KEY_MASK: u32 = 1 << 19;
+const NS_COMMAND_KEY_MASK: u32 = 1 << 20;
+const NS_NUMERIC_PAD_KEY_MASK: u32 = 1 << 21;
+
+impl iface::AccelTableNew for AccelTable {
+    fn from_key_bindings(bindings: &[KeyBinding]) -> Self {
+        let key = bindings
+            .iter()
+            .filter_map(|binding| {
+                // See `tcw3_pal_macro::accel::macos`
+                let pat = &binding.pattern;
+
+                let charcode = if let Some(charcode) = charcode_for_key(pat.key, pat.mod_flags) {
+                    charcode
+                } else {
+                    log::warn!("Unsupported key: {:?}", pat.key);
+                    return None;
+                };
+
+                // Some characters are included in both of a normal keyboard and
+                // a numerical keypad. `Key` distinguishes between them.
+                let needs_keypad_disambiguation = if charcode < 128 {
+                    b"0123456789*+,-./".contains(&(charcode as u8))
+                } else {
+                    false
+                };
+
+                let mut mod_mask = NS_SHIFT_KEY_MASK
+                    | NS_CONTROL_KEY_MASK
+                    | NS_ALTERNATE_KEY_MASK
+                    | NS_COMMAND_KEY_MASK;
+                if needs_keypad_disambiguation {
+                    mod_mask |= NS_NUMERIC_PAD_KEY_MASK;
+                }
+
+                let mut mod_flags = 0;
+                if pat.mod_flags.contains(ModFlags::SHIFT) {
+                    mod_flags |= NS_SHIFT_KEY_MASK;
+                }
+                if pat.mod_flags.contains(ModFlags::CONTROL) {
+                    mod_flags |= NS_CONTROL_KEY_MASK;
+                }
+                if pat.mod_flags.contains(ModFlags::ALT) {
+                    mod_flags |= NS_ALTERNATE_KEY_MASK;
+                }
+                if pat.mod_flags.contains(ModFlags::SUPER) {
+                    mod_flags |= NS_COMMAND_KEY_MASK;
+                }
+                if is_keypad_key(pat.key) {
+                    mod_flags |= NS_NUMERIC_PAD_KEY_MASK;
+                }
+
+                Some(ActionKeyBinding {
+                    action: binding.action,
+                    // Lower bits (presumably) only have device-dependent
+                    // flags, so we simply ignore them
+                    mod_mask: (mod_mask >> 16) as u16,
+                    mod_flags: (mod_flags >> 16) as u16,
+                    charcode,
+                })
+            })
+            .collect();
+
+        Self {
+            key: Cow::Owned(key),
+            sel: &[],
+        }
+    }
+}
+
+fn charcode_for_key(key: Key, mod_flags: ModFlags) -> Option<u16> {
+    Some(match key {
+        Key::Char(c) => return u16::try_from(c as u32).ok(),
+        Key::Escape => 0x001b,
+        // <https://developer.apple.com/documentation/appkit/1540619-common_unicode_characters>
+        Key::Backspace => 0x0008,
+        Key::Return => 0x0003,
+        Key::Tab => {
+            if mod_flags.contains(ModFlags::SHIFT) {
+                0x0019
+            } else {
+                0x0009
+            }
+        }
+        Key::Delete => 0x007f,
+        Key::Left => 0xf702,
+        Key::Up => 0xf700,
+        Key::Right => 0xf703,
+        Key::Down => 0xf701,
+        // <https://developer.apple.com/documentation/appkit/1535851-function-key_unicodes>
+        Key::PageUp => 0xf72c,
+        Key::PageDown => 0xf72d,
+        Key::End => 0xf72b,
+        Key::Home => 0xf729,
+        Key::Insert => 0xf727,
+        Key::Numpad0 => b'0' as u16,
+        Key::Numpad1 => b'1' as u16,
+        Key::Numpad2 => b'2' as u16,
+        Key::Numpad3 => b'3' as u16,
+        Key::Numpad4 => b'4' as u16,
+        Key::Numpad5 => b'5' as u16,
+        Key::Numpad6 => b'6' as u16,
+        Key::Numpad7 => b'7' as u16,
+        Key::Numpad8 => b'8' as u16,
+        Key::Numpad9 => b'9' as u16,
+        Key::NumpadMultiply => b'*' as u16,
+        Key::NumpadAdd => b'+' as u16,
+        Key::NumpadSeparator => b',' as u16,
+        Key::NumpadSubtract => b'-' as u16,
+        Key::NumpadDecimal => b'.' as u16,
+        Key::NumpadDivide => b'/' as u16,
+        Key::F1 => 0xf704,
+        Key::F2 => 0xf705,
+        Key::F3 => 0xf706,
+        Key::F4 => 0xf707,
+        Key::F5 => 0xf708,
+        Key::F6 => 0xf709,
+        Key::F7 => 0xf70a,
+        Key::F8 => 0xf70b,
+        Key::F9 => 0xf70c,
+        Key::F10 => 0xf70d,
+        Key::F11 => 0xf70e,
+        Key::F12 => 0xf70f,
+        Key::F13 => 0xf710,
+        Key::F14 => 0xf711,
+        Key::F15 => 0xf712,
+        Key::F16 => 0xf713,
+        Key::F17 => 0xf714,
+        Key::F18 => 0xf715,
+        Key::F19 => 0xf716,
+        Key::F20 => 0xf717,
+        Key::F21 => 0xf718,
+        Key::F22 => 0xf719,
+        Key::F23 => 0xf71a,
+        Key::F24 => 0xf71b,
+    })
+}
+
+fn is_keypad_key(key: Key) -> bool {
+    matches!(
+        key,
+        Key::Numpad0
+            | Key::Numpad1
+            | Key::Numpad2
+            | Key::Numpad3
+            | Key::Numpad4
+            | Key::Numpad5
+            | Key::Numpad6
+            | Key::Numpad7
+            | Key::Numpad8
+            | Key::Numpad9
+            | Key::NumpadMultiply
+            | Key::NumpadAdd
+            | Key::NumpadSeparator
+            | Key::NumpadSubtract
+            | Key::NumpadDecimal
+            | Key::NumpadDivide
+    )
+}
@@ -2,11 +2,14 @@ use cggeom::Box2;
 use cgmath::{Matrix3, Point2};
 use core_foundation::base::TCFType;
 use core_graphics::{
+    base::CGFloat,
     color::SysCGColorRef,
+    color_space::CGColorSpaceRef,
     context::{CGContext, CGContextRef, CGLineCap, CGLineJoin},
+    geometry::CGPoint,
     image::{CGImage, CGImageAlphaInfo},
 };
-use std::fmt;
+use std::{fmt, os::raw::c_void, ptr::null, rc::Rc};
 
 use super::super::{iface, LineCap, LineJoin, RGBAF32};
 use super::drawutils::{
@@ -38,6 +41,63 @@ impl iface::Bitmap for Bitmap {
 
 pub struct BitmapBuilder {
     pub(super) cg_context: CGContext,
+    /// The graphics state not tracked by `CGContext`.
+    state: State,
+    state_stack: Vec<State>,
+}
+
+#[derive(Clone, Default)]
+struct State {
+    /// The current fill brush if it's a gradient.
+    fill_gradient: Option<Rc<GradientBrush>>,
+    /// The current stroke brush if it's a gradient.
+    stroke_gradient: Option<Rc<GradientBrush>>,
+}
+
+struct GradientBrush {
+    cg_gradient: CGGradientRef,
+    kind: iface::GradientKind,
+    xform: Matrix3<f32>,
+}
+
+impl GradientBrush {
+    fn new(gradient: &iface::Gradient<'_>) -> Self {
+        let components: Vec<CGFloat> = gradient
+            .stops
+            .iter()
+            .flat_map(|stop| {
+                let c = stop.color;
+                vec![c.r as f64, c.g as f64, c.b as f64, c.a as f64]
+            })
+            .collect();
+        let locations: Vec<CGFloat> = gradient
+            .stops
+            .iter()
+            .map(|stop| stop.offset as f64)
+            .collect();
+
+        let cg_gradient = unsafe {
+            CGGradientCreateWithColorComponents(
+                (&**cg_color_space_srgb()) as *const CGColorSpaceRef as *const u8,
+                components.as_ptr(),
+                locations.as_ptr(),
+                locations.len(),
+            )
+        };
+        assert!(!cg_gradient.is_null(), "could not create a gradient");
+
+        Self {
+            cg_gradient,
+            kind: gradient.kind,
+            xform: gradient.xform,
+        }
+    }
+}
+
+impl Drop for GradientBrush {
+    fn drop(&mut self) {
+        unsafe { CGGradientRelease(self.cg_gradient) };
+    }
 }
 
 impl fmt::Debug for BitmapBuilder {
@@ -74,15 +134,99 @@ impl iface::BitmapBuilderNew for BitmapBuilder {
         cg_context.scale(1.0, -1.0);
         cg_context.translate(0.0, -(size[1] as f64));
 
-        Self { cg_context }
+        Self {
+            cg_context,
+            state: State::default(),
+            state_stack: Vec::new(),
+        }
+    }
+
+    fn with_rgba8_premul(size: [u32; 2], data: &[u8]) -> Self {
+        let row_len = size[0] as usize * 4;
+        assert_eq!(
+            data.len(),
+            row_len * size[1] as usize,
+            "data has a wrong length"
+        );
+
+        let mut this = Self::new(size);
+
+        // The backing store uses the same pixel format. The first row in the
+        // memory is the topmost one.
+        let bytes_per_row = this.cg_context.bytes_per_row();
+        let dst = this.cg_context.data();
+        if row_len > 0 {
+            for (dst, src) in dst
+                .chunks_exact_mut(bytes_per_row)
+                .zip(data.chunks_exact(row_len))
+            {
+                dst[..row_len].copy_from_slice(src);
+            }
+        }
+
+        this
+    }
+}
+
+impl BitmapBuilder {
+    fn cg_context_ptr(&self) -> *const u8 {
+        (&*self.cg_context) as *const CGContextRef as *const u8
+    }
+
+    /// Fill the current clipping region with `gradient`.
+    fn draw_gradient(&mut self, gradient: &GradientBrush) {
+        // Extend the first and last colors indefinitely
+        const OPTIONS: u32 =
+            K_CG_GRADIENT_DRAWS_BEFORE_START_LOCATION | K_CG_GRADIENT_DRAWS_AFTER_END_LOCATION;
+
+        let origin = CGPoint::new(0.0, 0.0);
+
+        self.cg_context.concat_ctm(cg_affine_transform_from_matrix3(
+            gradient.xform.cast().unwrap(),
+        ));
+
+        unsafe {
+            match gradient.kind {
+                iface::GradientKind::Linear => CGContextDrawLinearGradient(
+                    self.cg_context_ptr(),
+                    gradient.cg_gradient,
+                    origin,
+                    CGPoint::new(1.0, 0.0),
+                    OPTIONS,
+                ),
+                iface::GradientKind::Radial => CGContextDrawRadialGradient(
+                    self.cg_context_ptr(),
+                    gradient.cg_gradient,
+                    origin,
+                    0.0,
+                    origin,
+                    1.0,
+                    OPTIONS,
+                ),
+            }
+        }
+    }
+
+    /// Fill the current path with the gradient fill brush.
+    fn fill_gradient(&mut self, gradient: &GradientBrush, even_odd: bool) {
+        self.cg_context.save();
+        if even_odd {
+            unsafe { CGContextEOClip(self.cg_context_ptr()) };
+        } else {
+            self.cg_context.clip();
+        }
+        self.draw_gradient(gradient);
+        self.cg_context.restore();
     }
 }
 
 impl iface::Canvas for BitmapBuilder {
     fn save(&mut self) {
         self.cg_context.save();
+        self.state_stack.push(self.state.clone());
     }
     fn restore(&mut self) {
+        self.state = self.state_stack.pop().expect("stack is empty");
         self.cg_context.restore();
     }
 
@@ -115,22 +259,53 @@ impl iface::Canvas for BitmapBuilder {
     }
 
     fn fill(&mut self) {
-        self.cg_context.fill_path();
+        if let Some(gradient) = self.state.fill_gradient.clone() {
+            self.fill_gradient(&gradient, false);
+        } else {
+            self.cg_context.fill_path();
+        }
+    }
+    fn fill_even_odd(&mut self) {
+        if let Some(gradient) = self.state.fill_gradient.clone() {
+            self.fill_gradient(&gradient, true);
+        } else {
+            unsafe { CGContextEOFillPath(self.cg_context_ptr()) };
+        }
     }
     fn stroke(&mut self) {
-        self.cg_context.stroke_path();
+        if let Some(gradient) = self.state.stroke_gradient.clone() {
+            self.cg_context.save();
+            unsafe { CGContextReplacePathWithStrokedPath(self.cg_context_ptr()) };
+            self.cg_context.clip();
+            self.draw_gradient(&gradient);
+            self.cg_context.restore();
+        } else {
+            self.cg_context.stroke_path();
+        }
     }
     fn clip(&mut self) {
         self.cg_context.clip();
     }
 
     fn stroke_rect(&mut self, bx: Box2<f32>) {
-        self.cg_context
-            .stroke_rect(cg_rect_from_box2(bx.cast().unwrap()));
+        if self.state.stroke_gradient.is_some() {
+            self.begin_path();
+            self.rect(bx);
+            self.stroke();
+        } else {
+            self.cg_context
+                .stroke_rect(cg_rect_from_box2(bx.cast().unwrap()));
+        }
     }
     fn fill_rect(&mut self, bx: Box2<f32>) {
-        self.cg_context
-            .fill_rect(cg_rect_from_box2(bx.cast().unwrap()));
+        if self.state.fill_gradient.is_some() {
+            self.begin_path();
+            self.rect(bx);
+            self.fill();
+        } else {
+            self.cg_context
+                .fill_rect(cg_rect_from_box2(bx.cast().unwrap()));
+        }
     }
     fn clip_rect(&mut self, bx: Box2<f32>) {
         self.cg_context
@@ -139,14 +314,22 @@ impl iface::Canvas for BitmapBuilder {
 
     fn set_fill_rgb(&mut self, rgb: RGBAF32) {
         self.cg_context.set_fill_color(&cg_color_from_rgbaf32(rgb));
+        self.state.fill_gradient = None;
+    }
+    fn set_fill_gradient(&mut self, gradient: &iface::Gradient<'_>) {
+        self.state.fill_gradient = Some(Rc::new(GradientBrush::new(gradient)));
     }
     fn set_stroke_rgb(&mut self, rgb: RGBAF32) {
         unsafe {
             CGContextSetStrokeColorWithColor(
-                (&*self.cg_context) as *const CGContextRef as *const u8,
+                self.cg_context_ptr(),
                 cg_color_from_rgbaf32(rgb).as_concrete_TypeRef(),
             );
         }
+        self.state.stroke_gradient = None;
+    }
+    fn set_stroke_gradient(&mut self, gradient: &iface::Gradient<'_>) {
+        self.state.stroke_gradient = Some(Rc::new(GradientBrush::new(gradient)));
     }
 
     fn set_line_cap(&mut self, cap: LineCap) {
@@ -178,9 +361,59 @@ impl iface::Canvas for BitmapBuilder {
         self.cg_context
             .concat_ctm(cg_affine_transform_from_matrix3(m.cast().unwrap()));
     }
+
+    fn begin_group(&mut self, opacity: f32) {
+        self.save();
+        unsafe {
+            // The alpha value is applied when the layer is composited.
+            // `CGContextBeginTransparencyLayer` resets the alpha value to `1`
+            // for the drawing operations inside the layer.
+            CGContextSetAlpha(self.cg_context_ptr(), opacity as CGFloat);
+            CGContextBeginTransparencyLayer(self.cg_context_ptr(), null());
+        }
+    }
+    fn end_group(&mut self) {
+        unsafe { CGContextEndTransparencyLayer(self.cg_context_ptr()) };
+        self.restore();
+    }
 }
 
+type CGGradientRef = *const c_void;
+
+const K_CG_GRADIENT_DRAWS_BEFORE_START_LOCATION: u32 = 1 << 0;
+const K_CG_GRADIENT_DRAWS_AFTER_END_LOCATION: u32 = 1 << 1;
+
 #[link(name = "CoreGraphics", kind = "framework")]
 extern "C" {
     fn CGContextSetStrokeColorWithColor(context: *const u8, color: SysCGColorRef);
+    fn CGContextEOFillPath(context: *const u8);
+    fn CGContextEOClip(context: *const u8);
+    fn CGContextReplacePathWithStrokedPath(context: *const u8);
+    fn CGContextSetAlpha(context: *const u8, alpha: CGFloat);
+    fn CGContextBeginTransparencyLayer(context: *const u8, aux_info: *const c_void);
+    fn CGContextEndTransparencyLayer(context: *const u8);
+
+    fn CGGradientCreateWithColorComponents(
+        space: *const u8,
+        components: *const CGFloat,
+        locations: *const CGFloat,
+        count: usize,
+    ) -> CGGradientRef;
+    fn CGGradientRelease(gradient: CGGradientRef);
+    fn CGContextDrawLinearGradient(
+        context: *const u8,
+        gradient: CGGradientRef,
+        start_point: CGPoint,
+        end_point: CGPoint,
+        options: u32,
+    );
+    fn CGContextDrawRadialGradient(
+        context: *const u8,
+        gradient: CGGradientRef,
+        start_center: CGPoint,
+        start_radius: CGFloat,
+        end_center: CGPoint,
+        end_radius: CGFloat,
+        options: u32,
+    );
 }
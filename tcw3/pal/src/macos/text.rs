@@ -86,7 +86,7 @@ impl iface::CharStyle for CharStyle {
             )
         });
 
-        // TODO: other attributes: `decor`, `color`
+        // TODO: other attributes: `bold`, `italic`, `decor`, `color`
 
         Self { font }
     }
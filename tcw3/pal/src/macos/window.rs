@@ -22,6 +22,7 @@ use cocoa::{
 use flags_macro::flags;
 use objc::{msg_send, runtime::BOOL, sel, sel_impl};
 use std::{
+    borrow::Cow,
     cell::{Cell, RefCell},
     cmp::min,
     ffi::CStr,
@@ -344,13 +345,13 @@ impl HTextInputCtx {
 #[derive(Debug)]
 pub struct AccelTable {
     #[doc(hidden)]
-    pub key: &'static [ActionKeyBinding],
+    pub key: Cow<'static, [ActionKeyBinding]>,
     #[doc(hidden)]
     pub sel: &'static [ActionSelBinding],
 }
 
 #[doc(hidden)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ActionKeyBinding {
     pub action: iface::ActionId,
     pub mod_mask: u16,
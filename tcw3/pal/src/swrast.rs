@@ -5,9 +5,16 @@
 //!  - The maximum render target size is 16384×16384.
 //!  - The coordinates of all elements (including those clipped) must fit in
 //!    range ±16384.
-//!  - The only supported pixel format is ARGB8888.
+//!  - The only supported pixel format is ARGB8888. It's assumed to be in
+//!    the sRGB color space when blending in linear light
+//!    (`ColorMode::Linear16`).
+//!  - In `ColorMode::Linear16`, backdrops are blurred in the sRGB color space.
 //!  - There's some limit on the nesting level of layers.
 //!  - There's a reasonable limit on the number of displayed layers.
+//!  - `LayerFlags::BACKDROP_BLUR` blurs the contents behind the layer in the
+//!    same window. Layers with this flag must be axis-aligned.
+//!  - Layer shadows (`LayerAttrs::shadow`) are not clipped by the layer's
+//!    shape, so they show through translucent layers.
 //!
 
 /// `log2(TILE)`
@@ -31,13 +38,23 @@ const NUM_LAYERS: usize = 16;
 /// The number of groups.
 const NUM_GROUPS: usize = 32;
 
+/// The standard deviation of the Gaussian blur applied to the backdrop of
+/// layers having `LayerFlags::BACKDROP_BLUR`, measured in points.
+const BACKDROP_BLUR_SIGMA: f32 = 8.0;
+
+/// The maximum radius of the blur kernel, measured in pixels. This bounds
+/// the margin each tile has to read around itself.
+const BLUR_MAX_RADIUS: usize = 48;
+
 mod binner;
 mod binrast;
+mod color;
 mod layers;
 mod rast;
 mod utils;
 
 pub(crate) use self::{
     binner::{Binner, Bmp},
+    color::ColorMode,
     layers::{HLayer, HWnd, Screen},
 };
@@ -9,6 +9,7 @@ use itertools::iproduct;
 use std::{
     cmp::{max, min},
     ops::Range,
+    sync::Arc,
 };
 
 use super::{CLIP_SUB, NUM_GROUPS, NUM_LAYERS, TILE, UV_SUB};
@@ -28,6 +29,12 @@ pub struct Binner<TBmp> {
     /// Invalid for groups that don't have their own layers
     /// (i.e., their `elem_i` is `None`).
     group_bins: [Vec<u32>; NUM_GROUPS],
+
+    /// The number of elements culled by the last `BinnerBuilder`.
+    elems_culled: usize,
+
+    /// The number of fragments culled by the last `BinnerBuilder`.
+    frags_culled: usize,
 }
 
 /// A clonable reference to a color-matched bitmap image.
@@ -64,7 +71,10 @@ struct Bin {
     /// `groups[max_layered_group_i - 1].layer` must be `Some(_)`.
     max_layered_group_i: u32,
 
-    _pad: u32,
+    /// Used by `BinnerBuilder`. `true` if the bin is completely covered by
+    /// an opaque element on layer 0. Fragments added after this is set are
+    /// occluded by the element and thus culled.
+    occluded: bool,
 }
 
 /// A rendered element, referenced by one or more fragments
@@ -82,6 +92,9 @@ pub(super) struct Elem<TBmp> {
 
     /// Clip planes (enabled by `ElemFlags::CLIP_PLANES`).
     pub clip_planes: [ClipPlanes; 2],
+
+    /// A rounded rectangle further shaping the element.
+    pub rounded_rect: Option<Box<RoundedRect>>,
 }
 
 /// Clip planes (enabled by `ElemFlags::CLIP_PLANES`). Given window
@@ -170,6 +183,60 @@ pub(super) enum Content<TBmp> {
     ///
     /// The layer number `x` must be greater than `Elem::layer`.
     Layer(u8),
+
+    /// The blurred contents behind a layer having
+    /// `LayerFlags::BACKDROP_BLUR`.
+    Backdrop {
+        backdrop: Arc<Backdrop>,
+        /// The position of the top-left pixel of `backdrop` in the render
+        /// target.
+        origin: Vector2<i32>,
+    },
+}
+
+/// The contents behind a layer having `LayerFlags::BACKDROP_BLUR`. They are
+/// blurred as they are rasterized so that the blur can be calculated for each
+/// bin independently.
+#[derive(Debug)]
+pub(super) struct Backdrop {
+    /// The pixel data in the ARGB8 format with premultiplied alpha. Rows are
+    /// stored without padding.
+    pub data: Vec<u8>,
+
+    /// The dimensions of the image.
+    pub size: [usize; 2],
+
+    /// The weights of the blur kernel. See `binrast::gaussian_kernel`.
+    pub kernel: Vec<u32>,
+}
+
+/// A rounded rectangle shaping an element (see `Elem::rounded_rect`). The
+/// coverage value is calculated by `binrast::rounded_rect_coverage`.
+#[derive(Debug, Clone)]
+pub(super) struct RoundedRect {
+    /// Transforms render target coordinates to the local coordinate space,
+    /// in which the shape is defined.
+    pub inv_xform: Matrix3<f32>,
+    /// The rectangle in the local coordinate space.
+    pub rect: Box2<f32>,
+    /// The radii of the corners in the order of top-left, top-right,
+    /// bottom-right, and bottom-left. None of them exceeds the half of
+    /// the shorter side of `rect`.
+    pub radii: [f32; 4],
+    /// The approximate size of a pixel in the local coordinate space.
+    pub pixel_size: f32,
+    pub mode: RoundedRectMode,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum RoundedRectMode {
+    /// Only the corners are cut out. The straight edges are supposed to be
+    /// handled by a scissor rectangle and/or clip planes.
+    Corners,
+    /// The shape is blurred by a Gaussian blur with the specified standard
+    /// deviation (measured in the local coordinate space). This is used to
+    /// draw shadows.
+    Blurred(f32),
 }
 
 bitflags! {
@@ -177,8 +244,9 @@ bitflags! {
         /// Enable clipping by clip planes
         ///
         /// Clip planes are more flexible than a scissor rectangle, but render
-        /// slower. Hence, we avoid clip planes whenever possible. We don't
-        /// cull the rendering of regions masked by clip planes.
+        /// slower. Hence, we avoid clip planes whenever possible. Bins
+        /// entirely outside the clip planes are culled, but the pixels masked
+        /// by clip planes inside a bin are still processed.
         const CLIP_PLANES = 1 << 0;
 
         /// Enable antialiased clipping by clip planes. Requires `CLIP_PLANES`.
@@ -220,6 +288,8 @@ impl<TBmp: Bmp> Binner<TBmp> {
             target_size: [0, 0],
             bin_count: [0, 0],
             group_bins: arr_macro::arr![Vec::new(); 32],
+            elems_culled: 0,
+            frags_culled: 0,
         }
     }
 
@@ -234,6 +304,8 @@ impl<TBmp: Bmp> Binner<TBmp> {
         self.frags.clear();
         self.elems.clear();
         self.bins.clear();
+        self.elems_culled = 0;
+        self.frags_culled = 0;
 
         self.target_size = size;
         self.bin_count = [
@@ -249,7 +321,7 @@ impl<TBmp: Bmp> Binner<TBmp> {
             frag_first_i: NONE,
             frag_last_i: NONE,
             max_layered_group_i: 0,
-            _pad: 0,
+            occluded: false,
         }));
 
         BinnerBuilder {
@@ -304,6 +376,19 @@ impl<TBmp: Bmp> Binner<TBmp> {
     pub(super) fn bin_count(&self) -> [usize; 2] {
         self.bin_count
     }
+
+    /// Get the number of elements that were culled entirely (i.e., didn't
+    /// produce any fragments) because they were occluded by opaque elements
+    /// in the front or clipped by their clip planes.
+    pub(super) fn elems_culled(&self) -> usize {
+        self.elems_culled
+    }
+
+    /// Get the number of fragments that were culled for the same reasons as
+    /// `elems_culled`.
+    pub(super) fn frags_culled(&self) -> usize {
+        self.frags_culled
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -316,6 +401,8 @@ pub(super) struct ElemInfo<TBmp> {
     /// The background color in the BGRA format.
     pub bg_color: [u8; 4],
     pub opacity: f32,
+    /// The corner radii. See `RoundedRect::radii`.
+    pub corner_radii: [f32; 4],
 }
 
 /// This type is used to add rendered elements to `Binner`.
@@ -386,14 +473,23 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
     /// row of the matrix must be `[0 0 1]`. The resulting parallelogram is
     /// used as the mask shape. Masking is not applied if it's `None`.
     ///
+    /// `mask_corners` rounds the corners of the mask shape. It requires
+    /// `mask_xform`.
+    ///
     /// `opacity` is the opacity of the group in range `0.0..=1.0`. The opacity
     /// is applied after all elements in the group are flattened into a single
     /// image.
-    pub(super) fn open_group(&mut self, mask_xform: Option<Matrix3<f32>>, opacity: f32) {
+    pub(super) fn open_group(
+        &mut self,
+        mask_xform: Option<Matrix3<f32>>,
+        mask_corners: Option<RoundedRect>,
+        opacity: f32,
+    ) {
         // The last row of the matrix must be `[0 0 1]`.
         if let Some(xform) = mask_xform {
             debug_assert!(is_affine_xform(xform));
         }
+        debug_assert!(mask_corners.is_none() || mask_xform.is_some());
 
         assert!(
             self.groups.len() < self.groups.capacity(),
@@ -439,8 +535,9 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
             false
         };
 
-        // f `opacity` is less than `1`, the group needs a layer.
-        let needs_layer = needs_clip_planes || opacity < 1.0;
+        // If `opacity` is less than `1` or the mask has rounded corners, the
+        // group needs a layer.
+        let needs_layer = needs_clip_planes || opacity < 1.0 || mask_corners.is_some();
 
         // Create a compositing `Elem` if needed
         let layer = if needs_layer {
@@ -459,6 +556,7 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                 content: Content::Layer(self.layer as u8),
                 scissor: new_scissor.unwrap(),
                 clip_planes: clip_planes.unwrap_or_default(),
+                rounded_rect: mask_corners.map(Box::new),
             });
 
             // We don't generate compositing `Frag`s at this point. That happens
@@ -901,6 +999,7 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                         content,
                         scissor,
                         clip_planes,
+                        rounded_rect: None,
                     });
                 }
             } else if !use_slicing {
@@ -916,6 +1015,7 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                     content,
                     scissor,
                     clip_planes: clip_planes.clone(),
+                    rounded_rect: None,
                 });
             } else {
                 // `slice_by_clip == false && use_slicing == true`
@@ -1012,13 +1112,21 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                         content,
                         scissor,
                         clip_planes: clip_planes.clone(),
+                        rounded_rect: None,
                     });
                 }
             } // endif slice_by_clip, !use_slicing
         }
 
         if info.bg_color[3] > 0 {
+            // `Elem::opacity` uses `256` for full opacity. Map the alpha value
+            // from `0..=255` to `0..=256` so that an opaque background color
+            // completely hides the contents behind it. Otherwise, they would
+            // leak into the pixels sampled by backdrop blur, and an opaque
+            // layer in a window with a transparent backdrop would come out
+            // with alpha `254`.
             let mut bg_op = info.bg_color[3] as u32;
+            bg_op += bg_op / 128;
 
             if !use_proxy {
                 bg_op = (bg_op as f32 * info.opacity) as u32;
@@ -1034,51 +1142,195 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                 content: Content::Solid(info.bg_color.into()),
                 scissor: bb,
                 clip_planes: clip_planes.clone(),
+                rounded_rect: None,
             });
         }
 
+        // Round the corners
+        if let Some(corners) = RoundedRect::corners(xform, info.bounds, info.corner_radii) {
+            let corners = Box::new(corners);
+            for elem in elems.iter_mut() {
+                elem.rounded_rect = Some(corners.clone());
+            }
+        }
+
         // -------------------------------------------------------------------
         // Generate fragments
         if use_proxy {
-            self.open_group(Some(par), info.opacity);
+            self.open_group(Some(par), None, info.opacity);
         }
 
         for elem in elems.into_iter() {
-            let elem_i = self.binner.elems.len() as u32;
-            let scissor = elem.scissor;
+            self.insert_elem(elem);
+        }
 
-            let sci_min = scissor.min.cast::<usize>().unwrap();
-            let sci_max = scissor.max.cast::<usize>().unwrap();
-            let bin_xs = sci_min.x / TILE..(sci_max.x + TILE - 1) / TILE;
-            let bin_ys = sci_min.y / TILE..(sci_max.y + TILE - 1) / TILE;
+        if use_proxy {
+            self.close_group();
+        }
+    }
 
-            for (bin_x, bin_y) in iproduct!(bin_xs, bin_ys) {
-                // TODO: Clip plane cull
-                let bin_i = bin_x + bin_y * self.binner.bin_count[0];
+    /// Insert an element drawing the blurred image of `backdrop`.
+    ///
+    /// `rect` specifies the region to draw and must be an axis-aligned
+    /// rectangle in the render target. `corners` optionally rounds its corners.
+    /// `origin` is the position of the top-left pixel of `backdrop` in the
+    /// render target.
+    pub(super) fn push_backdrop(
+        &mut self,
+        rect: Box2<f32>,
+        corners: Option<RoundedRect>,
+        backdrop: Arc<Backdrop>,
+        origin: Vector2<i32>,
+        opacity: f32,
+    ) {
+        let scissor = if let Some(x) = self.scissor.and_then(|scissor| {
+            let rect = box2! {
+                min: [rect.min.x.round(), rect.min.y.round()],
+                max: [rect.max.x.round(), rect.max.y.round()],
+            };
+            saturating_aabb_f32_to_u16(rect)?.intersection(&scissor)
+        }) {
+            x
+        } else {
+            return;
+        };
 
-                self.prepare_bin(bin_i);
+        self.insert_elem(Elem {
+            flags: ElemFlags::empty(),
+            opacity: (opacity * 256.0) as u16,
+            content: Content::Backdrop { backdrop, origin },
+            scissor,
+            clip_planes: Default::default(),
+            rounded_rect: corners.map(Box::new),
+        });
+    }
 
-                let frag_i = self.binner.frags.len() as u32;
+    /// Insert an element drawing a shadow.
+    ///
+    /// `shape` specifies the shape of the shadow and must be in the
+    /// `RoundedRectMode::Blurred` mode. `color` is the color of the shadow in
+    /// the BGRA format.
+    pub(super) fn push_shadow(&mut self, shape: RoundedRect, color: [u8; 4], opacity: f32) {
+        let sigma = match shape.mode {
+            RoundedRectMode::Blurred(sigma) => sigma,
+            RoundedRectMode::Corners => unreachable!(),
+        };
 
-                let bin = &mut self.binner.bins[bin_i];
-                self.binner.frags.push(Frag {
-                    elem_i,
-                    next_frag_i: bin.frag_first_i,
-                    layer: self.layer,
-                });
+        let xform = if let Some(x) = shape.inv_xform.invert() {
+            x
+        } else {
+            return;
+        };
 
-                // Link the new fragment to the front of the fragment list
-                bin.frag_first_i = frag_i;
-                if bin.frag_last_i == NONE {
-                    bin.frag_last_i = frag_i;
-                }
+        // The Gaussian blur practically vanishes at `3σ`
+        let margin = sigma * 3.0;
+        let rect = box2! {
+            min: [shape.rect.min.x - margin, shape.rect.min.y - margin],
+            max: [shape.rect.max.x + margin, shape.rect.max.y + margin],
+        };
+
+        let scissor = if let Some(x) = self.scissor.and_then(|scissor| {
+            let bb = round_aabb_conservative(xform_aabb(xform, rect));
+            saturating_aabb_f32_to_u16(bb)?.intersection(&scissor)
+        }) {
+            x
+        } else {
+            return;
+        };
+
+        self.insert_elem(Elem {
+            flags: ElemFlags::empty(),
+            opacity: (color[3] as f32 / 255.0 * opacity * 256.0) as u16,
+            content: Content::Solid(color),
+            scissor,
+            clip_planes: Default::default(),
+            rounded_rect: Some(Box::new(shape)),
+        });
+    }
+
+    /// Add an element to the bins overlapping with its scissor rectangle.
+    ///
+    /// The element is not added to the bins which are occluded by opaque
+    /// elements added earlier or are entirely outside the element's clip
+    /// planes.
+    fn insert_elem(&mut self, elem: Elem<TBmp>) {
+        let elem_i = self.binner.elems.len() as u32;
+        let scissor = elem.scissor.cast::<usize>().unwrap();
+        let target_size = self.binner.target_size;
+
+        // An opaque element on layer 0 occludes everything behind it. On
+        // other layers, it would only occlude the contents of the same group.
+        let opaque = self.layer == 0 && elem.is_opaque();
+
+        let bin_xs = scissor.min.x / TILE..(scissor.max.x + TILE - 1) / TILE;
+        let bin_ys = scissor.min.y / TILE..(scissor.max.y + TILE - 1) / TILE;
+
+        let mut num_frags = 0;
+
+        for (bin_x, bin_y) in iproduct!(bin_xs, bin_ys) {
+            let bin_i = bin_x + bin_y * self.binner.bin_count[0];
+
+            if self.binner.bins[bin_i].occluded {
+                self.binner.frags_culled += 1;
+                continue;
             }
 
-            self.binner.elems.push(elem);
+            // The pixels of the bin (clipped by the render target)
+            let bin_rect = box2! {
+                min: [bin_x * TILE, bin_y * TILE],
+                max: [
+                    min((bin_x + 1) * TILE, target_size[0]),
+                    min((bin_y + 1) * TILE, target_size[1]),
+                ],
+            };
+
+            // The pixels of the bin the element may render
+            let elem_rect = if let Some(x) = bin_rect.intersection(&scissor) {
+                x
+            } else {
+                self.binner.frags_culled += 1;
+                continue;
+            };
+
+            let coverage = if elem.flags.contains(ElemFlags::CLIP_PLANES) {
+                clip_planes_coverage(&elem.clip_planes, elem_rect)
+            } else {
+                Coverage::Full
+            };
+
+            if coverage == Coverage::None {
+                self.binner.frags_culled += 1;
+                continue;
+            }
+
+            self.prepare_bin(bin_i);
+
+            let frag_i = self.binner.frags.len() as u32;
+
+            let bin = &mut self.binner.bins[bin_i];
+            self.binner.frags.push(Frag {
+                elem_i,
+                next_frag_i: bin.frag_first_i,
+                layer: self.layer,
+            });
+
+            // Link the new fragment to the front of the fragment list
+            bin.frag_first_i = frag_i;
+            if bin.frag_last_i == NONE {
+                bin.frag_last_i = frag_i;
+            }
+
+            if opaque && coverage == Coverage::Full && elem_rect == bin_rect {
+                bin.occluded = true;
+            }
+
+            num_frags += 1;
         }
 
-        if use_proxy {
-            self.close_group();
+        if num_frags > 0 {
+            self.binner.elems.push(elem);
+        } else {
+            self.binner.elems_culled += 1;
         }
     }
 
@@ -1086,8 +1338,6 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
     fn prepare_bin(&mut self, bin_i: usize) {
         let bin = &mut self.binner.bins[bin_i];
 
-        // TODO: Occlusion culling
-
         if bin.max_layered_group_i >= self.top_layered_group_i {
             debug_assert_eq!(bin.max_layered_group_i, self.top_layered_group_i);
             return;
@@ -1261,6 +1511,124 @@ fn is_clip_planes_aligned_to_axis(clip_planes: &ClipPlanes) -> bool {
     clip_planes.n.x == 0 || clip_planes.n.y == 0
 }
 
+/// The coverage of a region by clip planes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Coverage {
+    /// No pixels are covered.
+    None,
+    /// Some pixels may be partially covered.
+    Partial,
+    /// All pixels are fully covered.
+    Full,
+}
+
+/// Classify the coverage of the pixels in `region` by `clip_planes`. This is
+/// conservative: `Coverage::Partial` may be returned for a region that is
+/// actually fully covered or not covered at all.
+///
+/// The result is valid for both of aliased and antialiased clipping. See
+/// `ClipPlanes` for how the coverage value is calculated.
+fn clip_planes_coverage(clip_planes: &[ClipPlanes; 2], region: Box2<usize>) -> Coverage {
+    let mut full = true;
+
+    for cp in clip_planes.iter() {
+        // The range of `p.dot(n)` for the pixels in `region`
+        let dist = |x: usize, y: usize| cp.n.x as i64 * x as i64 + cp.n.y as i64 * y as i64;
+        let corners = [
+            dist(region.min.x, region.min.y),
+            dist(region.max.x - 1, region.min.y),
+            dist(region.min.x, region.max.y - 1),
+            dist(region.max.x - 1, region.max.y - 1),
+        ];
+        let dist_min = *corners.iter().min().unwrap();
+        let dist_max = *corners.iter().max().unwrap();
+
+        let (start, end) = (cp.d.start as i64, cp.d.end as i64);
+        let sub = CLIP_SUB as i64;
+
+        if dist_max + sub <= start || dist_min >= end {
+            return Coverage::None;
+        }
+
+        if dist_min < start || dist_max + sub > end {
+            full = false;
+        }
+    }
+
+    if full {
+        Coverage::Full
+    } else {
+        Coverage::Partial
+    }
+}
+
+impl<TBmp> Elem<TBmp> {
+    /// Check if the element completely hides the pixels behind it wherever
+    /// its scissor rectangle and clip planes fully cover.
+    ///
+    /// `binrast` blends a pixel value of alpha `255` (not `256`) in a way that
+    /// completely replaces the destination, so `opacity` doesn't have to be
+    /// `256`.
+    fn is_opaque(&self) -> bool {
+        self.opacity >= 255
+            && self.rounded_rect.is_none()
+            && matches!(self.content, Content::Solid(_))
+    }
+}
+
+impl RoundedRect {
+    /// Construct a `RoundedRect` in the `RoundedRectMode::Corners` mode.
+    ///
+    /// The rectangle `bounds` is transformed into the render target by
+    /// `xform`. Returns `None` if none of the corners is rounded.
+    pub(super) fn corners(xform: Matrix3<f32>, bounds: Box2<f32>, radii: [f32; 4]) -> Option<Self> {
+        if radii.iter().all(|&r| r <= 0.0) {
+            return None;
+        }
+        Self::new(xform, bounds, radii, RoundedRectMode::Corners)
+    }
+
+    /// Construct a `RoundedRect` in the `RoundedRectMode::Blurred` mode.
+    ///
+    /// The standard deviation `sigma` is clamped so that the edges are at
+    /// least antialiased.
+    pub(super) fn blurred(
+        xform: Matrix3<f32>,
+        bounds: Box2<f32>,
+        radii: [f32; 4],
+        sigma: f32,
+    ) -> Option<Self> {
+        let mut this = Self::new(xform, bounds, radii, RoundedRectMode::Corners)?;
+        this.mode = RoundedRectMode::Blurred(sigma.fmax(this.pixel_size * 0.5));
+        Some(this)
+    }
+
+    fn new(
+        xform: Matrix3<f32>,
+        bounds: Box2<f32>,
+        radii: [f32; 4],
+        mode: RoundedRectMode,
+    ) -> Option<Self> {
+        use array::*;
+
+        debug_assert!(is_affine_xform(xform));
+
+        let inv_xform = xform.invert()?;
+        let det = inv_xform.x.x * inv_xform.y.y - inv_xform.x.y * inv_xform.y.x;
+
+        let size = bounds.size();
+        let max_radius = size.x.fmin(size.y).fmax(0.0) * 0.5;
+
+        Some(Self {
+            inv_xform,
+            rect: bounds,
+            radii: radii.map(|r| r.fmax(0.0).fmin(max_radius)),
+            pixel_size: det.abs().sqrt(),
+            mode,
+        })
+    }
+}
+
 impl<TBmp> Content<TBmp> {
     /// Construct a `Content` from a bitmap image.
     ///
@@ -1574,7 +1942,7 @@ mod tests {
         ) {
             dbg!((xform, gr_xform, gr_op, ct_center));
             let mut builder = binner.build([200, 100]);
-            builder.open_group(gr_xform, gr_op);
+            builder.open_group(gr_xform, None, gr_op);
 
             builder.push_elem(ElemInfo {
                 xform,
@@ -1584,6 +1952,7 @@ mod tests {
                 bitmap: Some(TestBmp),
                 bg_color,
                 opacity: op,
+                corner_radii: [0.0; 4],
             });
 
             builder.close_group();
@@ -1604,6 +1973,7 @@ mod tests {
             bitmap: Some(TestBmp),
             bg_color: [40, 60, 80, 255].into(),
             opacity: 0.8,
+            corner_radii: [0.0; 4],
         });
         builder.finish();
 
@@ -1628,6 +1998,7 @@ mod tests {
             bitmap: Some(TestBmp),
             bg_color: [40, 60, 80, 255].into(),
             opacity: 0.8,
+            corner_radii: [0.0; 4],
         });
         builder.push_elem(ElemInfo {
             xform: Matrix3::identity(),
@@ -1637,6 +2008,7 @@ mod tests {
             bitmap: Some(TestBmp),
             bg_color: [40, 60, 80, 255].into(),
             opacity: 0.8,
+            corner_radii: [0.0; 4],
         });
         builder.push_elem(ElemInfo {
             xform: Matrix3::identity(),
@@ -1646,6 +2018,7 @@ mod tests {
             bitmap: Some(TestBmp),
             bg_color: [40, 60, 80, 255].into(),
             opacity: 0.8,
+            corner_radii: [0.0; 4],
         });
         builder.finish();
 
@@ -1656,4 +2029,77 @@ mod tests {
             assert!(elems.is_empty(), "{:?}", (bin_x, bin_y, &binner));
         }
     }
+
+    fn solid_elem([x0, y0, x1, y1]: [f32; 4], opacity: f32) -> ElemInfo<TestBmp> {
+        ElemInfo {
+            xform: Matrix3::identity(),
+            bounds: box2! { min: [x0, y0], max: [x1, y1] },
+            contents_center: box2! { min: [0.0, 0.0], max: [1.0, 1.0] },
+            contents_scale: 1.0,
+            bitmap: None,
+            bg_color: [40, 60, 80, 255].into(),
+            opacity,
+            corner_radii: [0.0; 4],
+        }
+    }
+
+    #[test]
+    fn occlusion_cull() {
+        let mut binner = Binner::new();
+
+        let mut builder = binner.build([64, 64]);
+        builder.push_elem(solid_elem([0.0, 0.0, 32.0, 64.0], 1.0));
+        // Completely occluded
+        builder.push_elem(solid_elem([4.0, 4.0, 20.0, 20.0], 1.0));
+        // Partially occluded
+        builder.push_elem(solid_elem([0.0, 0.0, 64.0, 64.0], 1.0));
+        builder.finish();
+
+        assert_eq!(binner.elems_culled(), 1);
+        assert_eq!(binner.frags_culled(), 4 + 8);
+
+        for (bin_x, bin_y) in iproduct!(0..4, 0..4) {
+            let elems: Vec<_> = binner.bin_elems([bin_x, bin_y]).collect();
+            assert_eq!(elems.len(), 1, "{:?}", (bin_x, bin_y, &binner));
+        }
+    }
+
+    #[test]
+    fn no_occlusion_cull_by_translucent_elem() {
+        let mut binner = Binner::new();
+
+        let mut builder = binner.build([64, 64]);
+        builder.push_elem(solid_elem([0.0, 0.0, 64.0, 64.0], 0.9));
+        builder.push_elem(solid_elem([0.0, 0.0, 64.0, 64.0], 1.0));
+        builder.finish();
+
+        assert_eq!(binner.elems_culled(), 0);
+        assert_eq!(binner.frags_culled(), 0);
+    }
+
+    #[test]
+    fn clip_plane_cull() {
+        let mut binner = Binner::new();
+
+        // A square rotated by 45 degrees. Its corners are at (100, 0),
+        // (170.7, 70.7), (100, 141.4), and (29.3, 70.7).
+        let s = std::f32::consts::FRAC_1_SQRT_2;
+        let mut builder = binner.build([200, 160]);
+        builder.push_elem(ElemInfo {
+            xform: Matrix3::new(s, s, 0.0, -s, s, 0.0, 100.0, 0.0, 1.0),
+            ..solid_elem([0.0, 0.0, 100.0, 100.0], 1.0)
+        });
+        builder.finish();
+
+        assert_eq!(binner.elems_culled(), 0);
+        assert_ne!(binner.frags_culled(), 0);
+
+        // Outside the square but inside its AABB
+        for &bin in &[[1, 0], [9, 0], [1, 8], [9, 8]] {
+            assert_eq!(binner.bin_elems(bin).count(), 0, "{:?}", bin);
+        }
+
+        // Inside the square
+        assert_eq!(binner.bin_elems([6, 4]).count(), 1);
+    }
 }
@@ -1,31 +1,49 @@
 //! A bin rasterizer.
+use alt_fp::FloatOrd;
 use arrayvec::ArrayVec;
-use cgmath::vec2;
+use cggeom::prelude::*;
+use cgmath::{prelude::*, vec2, Point2};
 use itertools::izip;
 use std::cmp::{max, min};
 use zerocopy::LayoutVerified;
 
 use super::{
-    binner::{Binner, Bmp, Content, Elem, ElemFlags},
-    CLIP_SUB, CLIP_SUB_SHIFT, NUM_LAYERS, TILE, UV_SUB, UV_SUB_SHIFT,
+    binner::{Backdrop, Binner, Bmp, Content, Elem, ElemFlags, RoundedRect, RoundedRectMode},
+    color::PixelFormat,
+    BLUR_MAX_RADIUS, CLIP_SUB, CLIP_SUB_SHIFT, NUM_LAYERS, TILE, UV_SUB, UV_SUB_SHIFT,
 };
 
-/// A working area for bin rasterization.
-pub struct BinRast {
+/// `log2` of the sum of the weights of a blur kernel.
+const BLUR_WEIGHT_SHIFT: u32 = 16;
+
+/// A working area for bin rasterization. `F` specifies the pixel format of
+/// tile buffers.
+pub struct BinRast<F: PixelFormat> {
     /// Tile buffers for layers, continuously holding
-    /// `TILE * TILE * 4 * NUM_LAYERS` bytes. The actual structure is like this:
-    /// `[[[[u8; TILE]; TILE]; 4]; NUM_LAYERS]`.
-    layers: Box<[[[u8; TILE * TILE]; 4]; NUM_LAYERS]>,
+    /// `TILE * TILE * 4 * NUM_LAYERS` channel values. The actual structure is
+    /// like this: `[[[[F::Chan; TILE]; TILE]; 4]; NUM_LAYERS]`.
+    layers: Box<[[[F::Chan; TILE * TILE]; 4]; NUM_LAYERS]>,
+
+    /// The output of `blur_backdrop`, structured like an element of `layers`.
+    /// Unlike `layers`, this is always in the output format because backdrops
+    /// are rendered and blurred in the output format.
+    blur_out: Box<[[u8; TILE * TILE]; 4]>,
+
+    /// The intermediate buffer used by `blur_backdrop`.
+    blur_tmp: Vec<[u32; 4]>,
 }
 
-impl BinRast {
+impl<F: PixelFormat> BinRast<F> {
     pub fn new() -> Self {
         Self {
-            layers: Box::new([[[0; TILE * TILE]; 4]; NUM_LAYERS]),
+            layers: Box::new([[[F::Chan::default(); TILE * TILE]; 4]; NUM_LAYERS]),
+            blur_out: Box::new([[0; TILE * TILE]; 4]),
+            blur_tmp: Vec::with_capacity((TILE + BLUR_MAX_RADIUS * 2) * TILE),
         }
     }
 
-    /// Copy the result image (of size `TILE`²) to a supplied image buffer.
+    /// Copy the result image (of size `TILE`²) to a supplied image buffer,
+    /// converting it to the output format.
     ///
     /// For each `(x, y)` in range `0 ≤ x < clip_width` (`clip_width` must be
     /// ≤ `TILE`) and `0 ≤ y < clip_height` (`clip_height` must be ≤ `TILE`),
@@ -55,10 +73,8 @@ impl BinRast {
                 src_row3,
                 to_row.chunks_exact_mut(4)
             ) {
-                t[0] = *s0;
-                t[1] = *s1;
-                t[2] = *s2;
-                t[3] = *s3;
+                let c = F::encode_premul([(*s0).into(), (*s1).into(), (*s2).into(), (*s3).into()]);
+                t.copy_from_slice(&c);
             }
         }
     }
@@ -67,7 +83,7 @@ impl BinRast {
     pub fn rasterize<TBmp: Bmp>(&mut self, binner: &Binner<TBmp>, bin_index: [usize; 2]) {
         for chan in self.layers[0].iter_mut() {
             for x in chan.iter_mut() {
-                *x = 0;
+                *x = F::Chan::default();
             }
         }
 
@@ -103,6 +119,21 @@ impl BinRast {
             sci_clip_g[3] - bin_coords[1] as u32,
         ];
 
+        if let Content::Backdrop { backdrop, origin } = &elem.content {
+            // Blur the backdrop beforehand. `RastContent::Backdrop` reads
+            // the result.
+            blur_backdrop(
+                backdrop,
+                [
+                    origin.x - bin_coords[0] as i32,
+                    origin.y - bin_coords[1] as i32,
+                ],
+                sci_clip_l,
+                &mut self.blur_tmp,
+                &mut self.blur_out,
+            );
+        }
+
         // The layer buffers
         let (dest_layer, rest_layers) = self.layers[layer..].split_first_mut().unwrap();
 
@@ -111,20 +142,21 @@ impl BinRast {
         let [dest_l0, dest_l1, dest_l2, dest_l3] = dest_layer;
 
         // Content
-        enum RastContent<'a> {
-            Solid([u8; 4]),
+        enum RastContent<'a, Chan> {
+            Solid([u32; 4]),
             Bmp {
                 bmp_data: &'a [[u8; 4]],
                 bmp_size: [usize; 2],
                 bmp_stride: usize,
             },
-            Layer(&'a mut [[u8; TILE * TILE]; 4]),
+            Layer(&'a mut [[Chan; TILE * TILE]; 4]),
+            Backdrop(&'a [[u8; TILE * TILE]; 4]),
         }
 
         let [mut uv_origin, mut duv_dx, mut duv_dy] = [vec2(0, 0); 3];
 
-        let cont: RastContent<'_> = match elem.content {
-            Content::Solid(x) => RastContent::Solid(x),
+        let cont: RastContent<'_, F::Chan> = match elem.content {
+            Content::Solid(x) => RastContent::Solid(F::decode_opaque(x)),
 
             Content::Bmp {
                 ref bmp,
@@ -154,6 +186,8 @@ impl BinRast {
             Content::Layer(src_layer) => {
                 RastContent::Layer(&mut rest_layers[src_layer as usize - layer - 1])
             }
+
+            Content::Backdrop { .. } => RastContent::Backdrop(&self.blur_out),
         };
 
         // Clip planes
@@ -190,20 +224,29 @@ impl BinRast {
 
                 // Get the content color value
                 let c = match cont {
-                    RastContent::Solid([c0, c1, c2, _]) => [c0 as u32, c1 as u32, c2 as u32, 255],
+                    RastContent::Solid(c) => c,
 
                     RastContent::Bmp {
                         bmp_data,
                         bmp_size,
                         bmp_stride,
-                    } => sample_bilinear(bmp_data, bmp_size, bmp_stride, uv.into()),
+                    } => sample_bilinear::<F>(bmp_data, bmp_size, bmp_stride, uv.into()),
 
                     RastContent::Layer(ref src_layer) => src_layer
                         .iter()
-                        .map(|chan| chan[i] as u32)
+                        .map(|chan| Into::<u32>::into(chan[i]))
                         .collect::<ArrayVec<[_; 4]>>()
                         .into_inner()
                         .unwrap(),
+
+                    RastContent::Backdrop(blurred) => F::decode_premul(
+                        blurred
+                            .iter()
+                            .map(|chan| chan[i])
+                            .collect::<ArrayVec<[_; 4]>>()
+                            .into_inner()
+                            .unwrap(),
+                    ),
                 };
 
                 // Mask
@@ -233,6 +276,11 @@ impl BinRast {
                     mask = elem.opacity as u32;
                 }
 
+                if let Some(rounded_rect) = &elem.rounded_rect {
+                    let p = [x_g as f32 + 0.5, y_g as f32 + 0.5].into();
+                    mask = mask * rounded_rect_coverage(rounded_rect, p) / 256;
+                }
+
                 debug_assert!(mask <= 256);
 
                 // Apply the mask
@@ -243,12 +291,14 @@ impl BinRast {
                     (c[3] as u32 * mask) / 256,
                 ];
 
-                // Map the alpha value from `0..=255` to `0..=256`
-                let alpha = c[3] + c[3] / 128;
+                // Map the alpha value from `0..=F::MAX` to `0..=F::MAX + 1`
+                let alpha = c[3] + (c[3] >> (F::BITS - 1));
 
                 // Blend over (with premultiplied alpha)
+                let one = 1 << F::BITS;
                 for (d, c) in izip!(&mut [d0, d1, d2, d3], &c) {
-                    **d = min(*c + **d as u32 * (256 - alpha) / 256, 255) as u8;
+                    let d_val: u32 = (**d).into();
+                    **d = F::chan(min(*c + ((d_val * (one - alpha)) >> F::BITS), F::MAX));
                 }
             }
         }
@@ -257,19 +307,243 @@ impl BinRast {
             // Clear the source layer
             for chan in src_layer.iter_mut() {
                 for x in chan.iter_mut() {
-                    *x = 0;
+                    *x = F::Chan::default();
                 }
             }
         }
     }
 }
 
+/// Construct a one-dimensional Gaussian kernel with the standard deviation
+/// `sigma` (measured in pixels). The kernel is centered at the middle
+/// element, and its weights add up to exactly `1 << BLUR_WEIGHT_SHIFT`.
+pub(super) fn gaussian_kernel(sigma: f32) -> Vec<u32> {
+    let one = 1u32 << BLUR_WEIGHT_SHIFT;
+    let radius = gaussian_kernel_radius(sigma);
+    if radius == 0 {
+        return vec![one];
+    }
+
+    let weights: Vec<f32> = (0..radius * 2 + 1)
+        .map(|i| {
+            let x = i as f32 - radius as f32;
+            (-x * x / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let sum: f32 = weights.iter().sum();
+
+    let mut kernel: Vec<u32> = weights
+        .iter()
+        .map(|w| (w / sum * one as f32).round() as u32)
+        .collect();
+
+    // Assign the rounding error to the center element
+    let rest: u32 = kernel.iter().sum::<u32>() - kernel[radius];
+    kernel[radius] = one - rest;
+
+    kernel
+}
+
+/// Get the radius of the kernel constructed by `gaussian_kernel(sigma)`.
+pub(super) fn gaussian_kernel_radius(sigma: f32) -> usize {
+    min((sigma * 3.0).ceil().max(0.0) as usize, BLUR_MAX_RADIUS)
+}
+
+/// Blur `backdrop` using its kernel and store the result in `out` for the
+/// tile-local rectangle `rect` (`[min_x, min_y, max_x, max_y]`).
+///
+/// `origin` is the position of the top-left pixel of `backdrop` relative to
+/// the tile. Pixels outside `backdrop` are substituted with the nearest edge
+/// pixels.
+fn blur_backdrop(
+    backdrop: &Backdrop,
+    origin: [i32; 2],
+    rect: [u32; 4],
+    tmp: &mut Vec<[u32; 4]>,
+    out: &mut [[u8; TILE * TILE]; 4],
+) {
+    let kernel = &backdrop.kernel[..];
+    let radius = kernel.len() / 2;
+    let size = backdrop.size;
+    let data: &[[u8; 4]] = LayoutVerified::new_slice_unaligned(&backdrop.data[..])
+        .unwrap()
+        .into_slice();
+
+    debug_assert!(radius <= BLUR_MAX_RADIUS);
+
+    let width = (rect[2] - rect[0]) as usize;
+    let height = (rect[3] - rect[1]) as usize;
+    let clamp = |x: i32, size: usize| min(max(x, 0), size as i32 - 1) as usize;
+
+    // The horizontal pass. The intermediate values are stored with
+    // `BLUR_WEIGHT_SHIFT - 8` fractional bits so that the vertical pass
+    // doesn't overflow.
+    tmp.clear();
+    tmp.resize((height + radius * 2) * TILE, [0; 4]);
+
+    for (y, tmp_row) in (rect[1] as i32 - radius as i32..).zip(tmp.chunks_exact_mut(TILE)) {
+        let src_row = &data[clamp(y - origin[1], size[1]) * size[0]..][..size[0]];
+
+        for (x, t) in (rect[0] as i32..).zip(tmp_row[..width].iter_mut()) {
+            let x = x - radius as i32 - origin[0];
+            let mut acc = [0u32; 4];
+            for (dx, &weight) in (0..).zip(kernel.iter()) {
+                let p = src_row[clamp(x + dx, size[0])];
+                for (a, &p) in acc.iter_mut().zip(p.iter()) {
+                    *a += p as u32 * weight;
+                }
+            }
+            *t = [acc[0] >> 8, acc[1] >> 8, acc[2] >> 8, acc[3] >> 8];
+        }
+    }
+
+    // The vertical pass
+    let shift = BLUR_WEIGHT_SHIFT * 2 - 8;
+    for y in 0..height {
+        let row_start = (rect[1] as usize + y) * TILE + rect[0] as usize;
+
+        for x in 0..width {
+            let mut acc = [0u32; 4];
+            for (t, &weight) in tmp[y * TILE + x..].iter().step_by(TILE).zip(kernel.iter()) {
+                for (a, &t) in acc.iter_mut().zip(t.iter()) {
+                    *a += t * weight;
+                }
+            }
+            for (chan, a) in out.iter_mut().zip(acc.iter()) {
+                chan[row_start + x] = min((a + (1 << (shift - 1))) >> shift, 255) as u8;
+            }
+        }
+    }
+}
+
+/// Calculate the coverage value of the pixel centered at `p` (specified in
+/// render target coordinates) by `rr`. The returned value is in range
+/// `0..=256`.
+fn rounded_rect_coverage(rr: &RoundedRect, p: Point2<f32>) -> u32 {
+    let p = rr.inv_xform.transform_point(p);
+
+    let coverage = match rr.mode {
+        RoundedRectMode::Corners => corner_coverage(rr, p),
+        RoundedRectMode::Blurred(sigma) => blurred_rounded_rect_coverage(rr, p, sigma),
+    };
+
+    (coverage.fmax(0.0).fmin(1.0) * 256.0) as u32
+}
+
+/// The coverage value of `RoundedRectMode::Corners`. `p` is specified in
+/// the local coordinate space.
+fn corner_coverage(rr: &RoundedRect, p: Point2<f32>) -> f32 {
+    let rect = rr.rect;
+    let mid = rect.mid();
+    let (left, top) = (p.x < mid.x, p.y < mid.y);
+
+    let radius = rr.radii[match (left, top) {
+        (true, true) => 0,
+        (false, true) => 1,
+        (false, false) => 2,
+        (true, false) => 3,
+    }];
+    if radius <= 0.0 {
+        return 1.0;
+    }
+
+    // The displacement from the center of the corner's circle, pointing
+    // outward
+    let dx = if left {
+        rect.min.x + radius - p.x
+    } else {
+        p.x - (rect.max.x - radius)
+    };
+    let dy = if top {
+        rect.min.y + radius - p.y
+    } else {
+        p.y - (rect.max.y - radius)
+    };
+
+    if dx <= 0.0 || dy <= 0.0 {
+        // Not in the rounded part
+        return 1.0;
+    }
+
+    let dist = (dx * dx + dy * dy).sqrt() - radius;
+    0.5 - dist / rr.pixel_size
+}
+
+/// The coverage value of `RoundedRectMode::Blurred`. `p` is specified in
+/// the local coordinate space.
+///
+/// The blurred shape is integrated analytically along the X axis and
+/// numerically along the Y axis. This is based on the technique described in
+/// <https://madebyevan.com/shaders/fast-rounded-rectangle-shadows/>, but
+/// extended to support distinct radii for each corner.
+fn blurred_rounded_rect_coverage(rr: &RoundedRect, p: Point2<f32>, sigma: f32) -> f32 {
+    const NUM_SAMPLES: usize = 4;
+
+    let rect = rr.rect;
+    let mid = rect.mid();
+    let half_size = rect.size() * 0.5;
+    let p = p - mid;
+
+    // The Gaussian kernel is practically zero beyond `3σ`. The shape is
+    // empty beyond `half_size.y`.
+    let low = p.y - half_size.y;
+    let high = p.y + half_size.y;
+    let start = (-3.0 * sigma).fmax(low).fmin(high);
+    let end = (3.0 * sigma).fmax(low).fmin(high);
+
+    let step = (end - start) / NUM_SAMPLES as f32;
+    let erf_scale = std::f32::consts::FRAC_1_SQRT_2 / sigma;
+    let gaussian_scale = 1.0 / ((2.0 * std::f32::consts::PI).sqrt() * sigma);
+
+    (0..NUM_SAMPLES)
+        .map(|i| {
+            // The kernel offset and the Y coordinate of the row being sampled
+            let t = start + step * (i as f32 + 0.5);
+            let y = p.y - t;
+
+            let (radius_left, radius_right) = if y < 0.0 {
+                (rr.radii[0], rr.radii[1])
+            } else {
+                (rr.radii[3], rr.radii[2])
+            };
+
+            // The horizontal extent of the row
+            let extent = |radius: f32| {
+                let delta = (half_size.y - radius - y.abs()).fmin(0.0);
+                half_size.x - radius + (radius * radius - delta * delta).fmax(0.0).sqrt()
+            };
+            let (x0, x1) = (-extent(radius_left), extent(radius_right));
+
+            let integral = 0.5 * (erf((p.x - x0) * erf_scale) - erf((p.x - x1) * erf_scale));
+            let weight = (-t * t / (2.0 * sigma * sigma)).exp() * gaussian_scale;
+
+            integral * weight * step
+        })
+        .sum()
+}
+
+/// An approximation of the error function.
+fn erf(x: f32) -> f32 {
+    let a = x.abs();
+    let y = 1.0 + (0.278_393 + (0.230_389 + 0.078_108 * (a * a)) * a) * a;
+    let y = y * y;
+    (1.0 - 1.0 / (y * y)).copysign(x)
+}
+
 /// Integrate `step(x)` over `x..x + CLIP_SUB`.
 fn integrate_step(x: i32) -> i32 {
     max(x + CLIP_SUB, 0) - max(x, 0)
 }
 
-fn sample_bilinear(data: &[[u8; 4]], size: [usize; 2], stride: usize, uv: [i32; 2]) -> [u32; 4] {
+/// Sample a bitmap with bilinear filtering. The texels are converted to `F`
+/// before filtering so that filtering is done in the same color space as
+/// blending.
+fn sample_bilinear<F: PixelFormat>(
+    data: &[[u8; 4]],
+    size: [usize; 2],
+    stride: usize,
+    uv: [i32; 2],
+) -> [u32; 4] {
     let [x1, y1] = [uv[0] >> UV_SUB_SHIFT, uv[1] >> UV_SUB_SHIFT];
     let [x2, y2] = [x1 + 1, y1 + 1];
 
@@ -282,10 +556,7 @@ fn sample_bilinear(data: &[[u8; 4]], size: [usize; 2], stride: usize, uv: [i32;
     let [x1, y1] = clamp([x1, y1]);
     let [x2, y2] = clamp([x2, y2]);
 
-    let get = |x, y| {
-        let [b, g, r, a]: [u8; 4] = data[x + y * stride];
-        [b as u32, g as u32, r as u32, a as u32]
-    };
+    let get = |x, y| F::decode_premul(data[x + y * stride]);
     let [p0, p1] = [get(x1, y1), get(x2, y1)];
     let [p2, p3] = [get(x1, y2), get(x2, y2)];
 
@@ -0,0 +1,222 @@
+//! Color spaces and pixel formats of tile buffers
+use lazy_static::lazy_static;
+use std::env::var_os;
+
+/// Specifies how `Screen` blends colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colors are blended in the sRGB (gamma-encoded) space with 8-bit
+    /// intermediates. This is the fastest mode.
+    Gamma8,
+
+    /// Colors are blended in linear light with 16-bit intermediates. sRGB
+    /// colors are decoded when bitmaps and other inputs are fetched, and
+    /// encoded when writing the output.
+    Linear16,
+}
+
+impl Default for ColorMode {
+    fn default() -> Self {
+        ColorMode::Gamma8
+    }
+}
+
+/// The environment variable to select `ColorMode::Linear16` for the backends
+/// based on `swrast`.
+const LINEAR_ENV_VAR: &str = "TCW3_LINEAR_COMPOSITING";
+
+impl ColorMode {
+    /// Get the color mode specified by the environment variable
+    /// `TCW3_LINEAR_COMPOSITING`. `Linear16` is chosen if it's set to
+    /// a non-empty value other than `0`.
+    pub fn from_env() -> Self {
+        match var_os(LINEAR_ENV_VAR) {
+            Some(value) if !value.is_empty() && value != "0" => ColorMode::Linear16,
+            _ => ColorMode::Gamma8,
+        }
+    }
+}
+
+/// The pixel format of tile buffers. Inputs and outputs are ARGB8 with
+/// premultiplied alpha, and are converted from/to this format.
+pub trait PixelFormat: 'static {
+    /// The type of a channel value.
+    type Chan: Copy + Default + Into<u32> + Send + 'static;
+
+    /// The number of bits of a channel value.
+    const BITS: u32;
+
+    /// The maximum channel value, representing `1.0`.
+    const MAX: u32 = (1 << Self::BITS) - 1;
+
+    /// Convert a channel value in range `0..=MAX` to `Chan`.
+    fn chan(x: u32) -> Self::Chan;
+
+    /// Convert an opaque color (only BGR channels are used) to this format.
+    /// The alpha channel of the result is `MAX`.
+    fn decode_opaque(c: [u8; 4]) -> [u32; 4];
+
+    /// Convert a color with premultiplied alpha to this format.
+    fn decode_premul(c: [u8; 4]) -> [u32; 4];
+
+    /// Convert a color with premultiplied alpha in this format to the output
+    /// format.
+    fn encode_premul(c: [u32; 4]) -> [u8; 4];
+}
+
+/// The pixel format for `ColorMode::Gamma8`. Conversion is no-op.
+pub enum Gamma8 {}
+
+impl PixelFormat for Gamma8 {
+    type Chan = u8;
+
+    const BITS: u32 = 8;
+
+    #[inline]
+    fn chan(x: u32) -> u8 {
+        x as u8
+    }
+
+    #[inline]
+    fn decode_opaque([c0, c1, c2, _]: [u8; 4]) -> [u32; 4] {
+        [c0 as u32, c1 as u32, c2 as u32, 255]
+    }
+
+    #[inline]
+    fn decode_premul([c0, c1, c2, c3]: [u8; 4]) -> [u32; 4] {
+        [c0 as u32, c1 as u32, c2 as u32, c3 as u32]
+    }
+
+    #[inline]
+    fn encode_premul([c0, c1, c2, c3]: [u32; 4]) -> [u8; 4] {
+        [c0 as u8, c1 as u8, c2 as u8, c3 as u8]
+    }
+}
+
+/// The pixel format for `ColorMode::Linear16`.
+pub enum Linear16 {}
+
+impl PixelFormat for Linear16 {
+    type Chan = u16;
+
+    const BITS: u32 = 16;
+
+    #[inline]
+    fn chan(x: u32) -> u16 {
+        x as u16
+    }
+
+    #[inline]
+    fn decode_opaque([c0, c1, c2, _]: [u8; 4]) -> [u32; 4] {
+        let lut = &*SRGB_DECODE_LUT;
+        [
+            lut[c0 as usize] as u32,
+            lut[c1 as usize] as u32,
+            lut[c2 as usize] as u32,
+            0xffff,
+        ]
+    }
+
+    #[inline]
+    fn decode_premul(c: [u8; 4]) -> [u32; 4] {
+        let a = c[3] as u32;
+        if a == 255 {
+            return Self::decode_opaque(c);
+        } else if a == 0 {
+            return [0; 4];
+        }
+
+        // Unpremultiply, decode, and premultiply again
+        let lut = &*SRGB_DECODE_LUT;
+        let decode = |x: u8| {
+            let straight = ((x as u32 * 255 + a / 2) / a).min(255);
+            (lut[straight as usize] as u32 * a + 127) / 255
+        };
+        [decode(c[0]), decode(c[1]), decode(c[2]), a * 0x101]
+    }
+
+    #[inline]
+    fn encode_premul(c: [u32; 4]) -> [u8; 4] {
+        let a = c[3];
+        if a == 0 {
+            return [0; 4];
+        }
+
+        // Unpremultiply, encode, and premultiply again
+        let lut = &*SRGB_ENCODE_LUT;
+        let a8 = (a + 0x80) / 0x101;
+        let encode = |x: u32| {
+            let straight = ((x * 0xffff + a / 2) / a).min(0xffff);
+            ((lut[straight as usize] as u32 * a8 + 127) / 255) as u8
+        };
+        [encode(c[0]), encode(c[1]), encode(c[2]), a8 as u8]
+    }
+}
+
+/// Convert a gamma-encoded value to linear light.
+pub(super) fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.040_45 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear-light value to gamma-encoded.
+pub(super) fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.003_130_8 {
+        x * 12.92
+    } else {
+        x.powf(1.0 / 2.4) * 1.055 - 0.055
+    }
+}
+
+lazy_static! {
+    /// Maps 8-bit sRGB values to 16-bit linear values.
+    static ref SRGB_DECODE_LUT: [u16; 256] = {
+        let mut lut = [0; 256];
+        for (i, x) in lut.iter_mut().enumerate() {
+            *x = (srgb_to_linear(i as f32 / 255.0) * 65535.0 + 0.5) as u16;
+        }
+        lut
+    };
+
+    /// Maps 16-bit linear values to 8-bit sRGB values.
+    static ref SRGB_ENCODE_LUT: Box<[u8]> = (0..=0xffffu32)
+        .map(|i| (linear_to_srgb(i as f32 / 65535.0) * 255.0 + 0.5) as u8)
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_lut() {
+        assert_eq!(SRGB_DECODE_LUT[0], 0);
+        assert_eq!(SRGB_DECODE_LUT[255], 0xffff);
+        // 0.2158605 × 65535
+        assert!((14145..=14147).contains(&SRGB_DECODE_LUT[128]));
+    }
+
+    #[test]
+    fn roundtrip_opaque() {
+        for i in 0..=255 {
+            let c = [i, 0, 255 - i, 255];
+            assert_eq!(Linear16::encode_premul(Linear16::decode_opaque(c)), c);
+        }
+    }
+
+    #[test]
+    fn roundtrip_premul() {
+        for a in 0..=255u8 {
+            for x in 0..=a {
+                let c = [x, x / 2, 0, a];
+                let c2 = Linear16::encode_premul(Linear16::decode_premul(c));
+                for (&x1, &x2) in c.iter().zip(c2.iter()) {
+                    assert!((x1 as i32 - x2 as i32).abs() <= 1, "{:?}", (c, c2));
+                }
+            }
+        }
+    }
+}
@@ -14,17 +14,20 @@ use bitflags::bitflags;
 use cggeom::{box2, prelude::*, Box2};
 use cgmath::{prelude::*, Matrix3, Vector2};
 use leakypool::{LeakyPool, PoolPtr};
-use std::fmt;
+use std::{cell::Cell, fmt, sync::Arc};
 
 use super::super::iface;
 
 use super::{
     binner::{
-        round_aabb_conservative, xform_aabb, xform_and_aabb_to_parallelogram, Binner,
-        BinnerBuilder, Bmp, ElemInfo,
+        round_aabb_conservative, xform_aabb, xform_and_aabb_to_parallelogram, Backdrop, Binner,
+        BinnerBuilder, Bmp, ElemInfo, RoundedRect,
     },
+    binrast::{gaussian_kernel, gaussian_kernel_radius},
+    color::ColorMode,
     rast::rasterize,
     utils::Box2UsizeUnion,
+    BACKDROP_BLUR_SIGMA,
 };
 
 /// The window handle type of [`Screen`].
@@ -101,6 +104,32 @@ impl<TBmp> fmt::Debug for HLayer<TBmp> {
 pub struct Screen<TBmp: 'static> {
     layers: LeakyPool<Layer<TBmp>>,
     wnds: LeakyPool<Wnd<TBmp>>,
+    color_mode: ColorMode,
+}
+
+/// Statistics about a call to `Screen::render_wnd`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    /// The number of rasterized tiles, including those of backdrops.
+    pub tiles_touched: usize,
+    /// The number of elements culled because they were occluded by opaque
+    /// elements or clipped out.
+    pub elems_culled: usize,
+    /// The number of fragments (pairs of an element and a tile) culled for
+    /// the same reasons as `elems_culled`.
+    pub frags_culled: usize,
+    /// The number of layers (including their sublayers) skipped because they
+    /// were outside the rendered region.
+    pub layers_culled: usize,
+}
+
+impl RenderStats {
+    fn add_binner(&mut self, binner: &Binner<impl Bmp>) {
+        let [bins_x, bins_y] = binner.bin_count();
+        self.tiles_touched += bins_x * bins_y;
+        self.elems_culled += binner.elems_culled();
+        self.frags_culled += binner.frags_culled();
+    }
 }
 
 #[derive(Debug)]
@@ -133,6 +162,10 @@ struct Layer<TBmp: 'static> {
     /// Valid only if `MASK_TO_BOUNDS` is set.
     bbox_mask: Option<Box2<usize>>,
 
+    /// `true` if one or more sublayers or their descendants have
+    /// `BACKDROP_BLUR`.
+    has_backdrop_sublayers: bool,
+
     // ----------- The following fields are only used during an update -----------
     sublayers_i: usize,
     dirty_rect: Option<Box2<usize>>,
@@ -192,6 +225,8 @@ struct LayerAttrs<TBmp> {
     bg_color: iface::RGBAF32,
     opacity: f32,
     flags: iface::LayerFlags,
+    corner_radii: [f32; 4],
+    shadow: Option<iface::LayerShadow>,
 }
 
 impl<TBmp> Default for LayerAttrs<TBmp> {
@@ -205,11 +240,44 @@ impl<TBmp> Default for LayerAttrs<TBmp> {
             bg_color: [0.0; 4].into(),
             opacity: 1.0,
             flags: iface::LayerFlags::empty(),
+            corner_radii: [0.0; 4],
+            shadow: None,
         }
     }
 }
 
+impl<TBmp> Layer<TBmp> {
+    /// Get a flag indicating whether the layer or one of its descendants has
+    /// `BACKDROP_BLUR`.
+    fn has_backdrop(&self) -> bool {
+        (self.attrs.flags).contains(iface::LayerFlags::BACKDROP_BLUR) || self.has_backdrop_sublayers
+    }
+}
+
 impl<TBmp> LayerAttrs<TBmp> {
+    /// Get a flag indicating whether the layer draws anything by itself.
+    fn has_content(&self) -> bool {
+        self.contents.is_some()
+            || self.bg_color.a > 0.0
+            || self.flags.contains(iface::LayerFlags::BACKDROP_BLUR)
+            || self.shadow.is_some()
+    }
+
+    /// Get the rectangle occupied by the shadow (including the blur) in
+    /// the layer's coordinate space.
+    fn shadow_bounds(&self) -> Option<Box2<f32>> {
+        let shadow = self.shadow.as_ref()?;
+
+        // The Gaussian blur practically vanishes at `3σ`
+        let margin = shadow.blur_radius.fmax(0.0) * 1.5;
+        let bounds = self.bounds.translate(shadow.offset);
+
+        Some(box2! {
+            min: [bounds.min.x - margin, bounds.min.y - margin],
+            max: [bounds.max.x + margin, bounds.max.y + margin],
+        })
+    }
+
     fn assign<TLayer>(&mut self, attrs: iface::LayerAttrs<TBmp, TLayer>) {
         if let Some(x) = attrs.transform {
             self.transform = x;
@@ -235,6 +303,12 @@ impl<TBmp> LayerAttrs<TBmp> {
         if let Some(x) = attrs.flags {
             self.flags = x;
         }
+        if let Some(x) = attrs.corner_radii {
+            self.corner_radii = x;
+        }
+        if let Some(x) = attrs.shadow {
+            self.shadow = x;
+        }
     }
 }
 
@@ -246,6 +320,8 @@ struct Wnd<TBmp: 'static> {
     size: [usize; 2],
     dpi_scale: f32,
     root: Option<HLayer<TBmp>>,
+    /// The color mode used for the last update.
+    color_mode: ColorMode,
 }
 
 impl<TBmp: Bmp> Screen<TBmp> {
@@ -253,15 +329,29 @@ impl<TBmp: Bmp> Screen<TBmp> {
         Self {
             layers: LeakyPool::new(),
             wnds: LeakyPool::new(),
+            color_mode: ColorMode::Gamma8,
         }
     }
 
+    /// Get the color mode used for rendering.
+    pub fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+
+    /// Set the color mode used for rendering. The entire area of every
+    /// window will be included in the dirty region returned by the next call
+    /// to `update_wnd` if the mode was changed.
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.color_mode = mode;
+    }
+
     pub fn new_wnd(&mut self) -> HWnd<TBmp> {
         let ptr = self.wnds.allocate(Wnd {
             dirty: true,
             size: [0; 2],
             dpi_scale: 1.0,
             root: None,
+            color_mode: self.color_mode,
         });
 
         HWnd { ptr }
@@ -322,6 +412,7 @@ impl<TBmp: Bmp> Screen<TBmp> {
             bbox_content: None,
             bbox_sublayers: None,
             bbox_mask: None,
+            has_backdrop_sublayers: false,
             old_bbox: None,
             new_sublayers: None,
             sublayers: Vec::new(),
@@ -396,7 +487,9 @@ impl<TBmp: Bmp> Screen<TBmp> {
             | attrs.contents_scale.is_some()
             | attrs.bg_color.is_some()
             | attrs.opacity.is_some()
-            | attrs.flags.is_some();
+            | attrs.flags.is_some()
+            | attrs.corner_radii.is_some()
+            | attrs.shadow.is_some();
 
         let opacity_modified = attrs.opacity.is_some();
 
@@ -470,7 +563,13 @@ impl<TBmp: Bmp> Screen<TBmp> {
     /// Calculate the portion of a window which has been updated since the last
     /// time `update_wnd` was called.
     pub fn update_wnd(&mut self, hwnd: &HWnd<TBmp>) -> Option<Box2<usize>> {
+        let color_mode = self.color_mode;
         let wnd = &mut self.wnds[hwnd.ptr];
+        if wnd.color_mode != color_mode {
+            wnd.color_mode = color_mode;
+            wnd.dirty = true;
+        }
+
         let root = wnd.root.clone();
         let ctx = UpdateCtx {
             wnd_size_f32: [wnd.size[0] as f32, wnd.size[1] as f32],
@@ -485,6 +584,13 @@ impl<TBmp: Bmp> Screen<TBmp> {
 
             let layer = &self.layers[hlayer.ptr];
             dirty_region = layer.dirty_rect;
+
+            // The pixels of a `BACKDROP_BLUR` layer depend on the contents
+            // behind it within the blur radius
+            if let (Some(dirty), true) = (&mut dirty_region, layer.has_backdrop()) {
+                let radius = gaussian_kernel_radius(BACKDROP_BLUR_SIGMA * ctx.dpi_scale);
+                self.expand_dirty_rect_by_backdrop(&hlayer, radius, dirty);
+            }
         }
 
         let wnd = &mut self.wnds[hwnd.ptr];
@@ -496,6 +602,37 @@ impl<TBmp: Bmp> Screen<TBmp> {
         dirty_region
     }
 
+    /// Expand `dirty` to include the layers having `BACKDROP_BLUR` whose
+    /// backdrops intersect with `dirty`. The layers are visited in a drawing
+    /// order so that a change is propagated through stacked backdrops.
+    fn expand_dirty_rect_by_backdrop(
+        &self,
+        hlayer: &HLayer<TBmp>,
+        radius: usize,
+        dirty: &mut Box2<usize>,
+    ) {
+        let layer = &self.layers[hlayer.ptr];
+
+        if let (Some(bx), true) = (
+            layer.bbox_content,
+            (layer.attrs.flags).contains(iface::LayerFlags::BACKDROP_BLUR),
+        ) {
+            let backdrop = box2! {
+                min: [bx.min.x.saturating_sub(radius), bx.min.y.saturating_sub(radius)].into(),
+                max: [bx.max.x + radius, bx.max.y + radius].into(),
+            };
+            if backdrop.intersection(dirty).is_some() {
+                dirty.union_assign(&bx);
+            }
+        }
+
+        if layer.has_backdrop_sublayers {
+            for hlayer in layer.sublayers.iter() {
+                self.expand_dirty_rect_by_backdrop(hlayer, radius, dirty);
+            }
+        }
+    }
+
     /// Clear the dirty flag of a layer, updating fields including: `dirty_rect`,
     /// `bbox`, `bbox_content`, `bbox_sublayers`, and `bbox_clip`.
     fn update_layer(&mut self, hlayer: &HLayer<TBmp>, ctx: &UpdateCtx) {
@@ -538,6 +675,11 @@ impl<TBmp: Bmp> Screen<TBmp> {
                 self.update_layer(&hlayer, ctx);
             }
 
+            let has_backdrop_sublayers = sublayers
+                .iter()
+                .any(|hlayer| self.layers[hlayer.ptr].has_backdrop());
+            self.layers[hlayer.ptr].has_backdrop_sublayers = has_backdrop_sublayers;
+
             let mut uni_dirty_rect = Box2UsizeUnion::new();
 
             // Do not utilize `old_sublayers` if a full update is requested
@@ -677,20 +819,29 @@ impl<TBmp: Bmp> Screen<TBmp> {
 
         if should_check_content {
             let tx = scale_mat3(layer.attrs.transform, ctx.dpi_scale);
-            let bx = xform_aabb(tx, layer.attrs.bounds);
-            let bx = round_aabb_conservative(bx);
             let size = ctx.wnd_size_f32;
-            let bx = box2! {
-                min: [bx.min.x.fmax(0.0) as usize, bx.min.y.fmax(0.0) as usize],
-                max: [bx.max.x.fmin(size[0]) as usize, bx.max.y.fmin(size[1]) as usize],
+            let to_bbox = |bounds: Box2<f32>| {
+                let bx = round_aabb_conservative(xform_aabb(tx, bounds));
+                let bx = box2! {
+                    min: [bx.min.x.fmax(0.0) as usize, bx.min.y.fmax(0.0) as usize],
+                    max: [bx.max.x.fmin(size[0]) as usize, bx.max.y.fmin(size[1]) as usize],
+                };
+                if bx.is_empty() {
+                    None
+                } else {
+                    Some(bx)
+                }
             };
-            let bx = if bx.is_empty() { None } else { Some(bx) };
+            let bx = to_bbox(layer.attrs.bounds);
 
             // Does this layer has a content? But even if it doesn't, `bx` is
             // used for sublayer masking.
-            let has_content = layer.attrs.contents.is_some() || layer.attrs.bg_color.a > 0.0;
+            let has_content = layer.attrs.has_content();
 
-            let new_bbox_content = bx.filter(|_| has_content);
+            // The shadow may extend beyond the layer bounds
+            let shadow_bx = layer.attrs.shadow_bounds().and_then(to_bbox);
+
+            let new_bbox_content = bbox2_union(bx.filter(|_| has_content), shadow_bx);
             let new_bbox_mask = bx;
 
             let dirty_content = bbox2_union(new_bbox_content, layer.bbox_content);
@@ -772,6 +923,9 @@ impl<TBmp: Bmp> Screen<TBmp> {
     /// `out_stride * (size[1] - 1) + size[0] * 4`.
     ///
     /// `binner` is used as a temporary storage.
+    ///
+    /// Layers outside `rect` are skipped if the layer tree has no changes
+    /// uncommitted by `update_wnd`. Returns statistics about the rendering.
     pub fn render_wnd(
         &mut self,
         hwnd: &HWnd<TBmp>,
@@ -779,30 +933,182 @@ impl<TBmp: Bmp> Screen<TBmp> {
         out_stride: usize,
         bx: Box2<usize>,
         binner: &mut Binner<TBmp>,
-    ) {
+    ) -> RenderStats {
         let wnd = &self.wnds[hwnd.ptr];
         assert!(bx.max.x <= wnd.size[0] && bx.max.y <= wnd.size[1]);
         assert!(bx.is_valid());
 
+        let mut stats = RenderStats::default();
+
+        // Layers' bounding boxes are valid only if there are no uncommitted
+        // changes
+        let bbox_valid = !wnd.dirty
+            && (wnd.root.as_ref()).map_or(true, |root| self.layers[root.ptr].dirty.is_empty());
+
+        let backdrops = if let Some(root) = &wnd.root {
+            let region = Some(bx).filter(|_| bbox_valid);
+            self.render_backdrops(wnd, root, region, binner, &mut stats)
+        } else {
+            Vec::new()
+        };
+
         let mut builder = binner.build(bx.size().into());
         if let Some(root) = &wnd.root {
             let ctx = RenderCtx {
                 dpi_scale: wnd.dpi_scale,
                 offset: [bx.min.x as f32, bx.min.y as f32].into(),
+                region: Some(bx).filter(|_| bbox_valid),
+                backdrops: &backdrops,
+                stop_at: None,
+                skipping: Cell::new(false),
+                layers_culled: Cell::new(0),
             };
-            self.binner_build_layer(&mut builder, &ctx, &self.layers[root.ptr]);
+            self.binner_build_layer(&mut builder, &ctx, root);
+            stats.layers_culled += ctx.layers_culled.get();
         }
         builder.finish();
 
-        rasterize(&binner, out, out_stride);
+        rasterize(&binner, out, out_stride, self.color_mode);
+        stats.add_binner(binner);
+
+        stats
+    }
+
+    /// Render the contents behind each layer having `BACKDROP_BLUR` in
+    /// the subtree `root`.
+    ///
+    /// Each rendered region encompasses the layer's bounding box plus the blur
+    /// kernel's radius. The layers are processed in a drawing order so that
+    /// the backdrop of a layer can include other layers' blurred backdrops.
+    ///
+    /// If `visible_region` is specified, the backdrops that don't affect the
+    /// region are skipped.
+    fn render_backdrops(
+        &self,
+        wnd: &Wnd<TBmp>,
+        root: &HLayer<TBmp>,
+        visible_region: Option<Box2<usize>>,
+        binner: &mut Binner<TBmp>,
+        stats: &mut RenderStats,
+    ) -> Vec<(HLayer<TBmp>, Arc<Backdrop>, Vector2<usize>)> {
+        let mut backdrops = Vec::new();
+
+        let mut hlayers = Vec::new();
+        self.collect_backdrop_layers(root, &mut hlayers);
+
+        if hlayers.is_empty() {
+            return backdrops;
+        }
+
+        let kernel = gaussian_kernel(BACKDROP_BLUR_SIGMA * wnd.dpi_scale);
+        let radius = (kernel.len() / 2) as f32;
+
+        let mut regions: Vec<_> = hlayers
+            .iter()
+            .map(|hlayer| {
+                let attrs = &self.layers[hlayer.ptr].attrs;
+
+                let tx = scale_mat3(attrs.transform, wnd.dpi_scale);
+                let bx = round_aabb_conservative(xform_aabb(tx, attrs.bounds));
+                let size = [wnd.size[0] as f32, wnd.size[1] as f32];
+                let region = box2! {
+                    min: [
+                        (bx.min.x - radius).fmax(0.0) as usize,
+                        (bx.min.y - radius).fmax(0.0) as usize,
+                    ],
+                    max: [
+                        (bx.max.x + radius).fmin(size[0]) as usize,
+                        (bx.max.y + radius).fmin(size[1]) as usize,
+                    ],
+                };
+                Some(region).filter(|r| !r.is_empty())
+            })
+            .collect();
+
+        if let Some(visible_region) = visible_region {
+            // A backdrop is needed if its layer is visible, or if it's
+            // included in another needed backdrop. Visit the layers in a
+            // reverse drawing order to find the latter.
+            let mut needed_regions = vec![visible_region];
+            for region in regions.iter_mut().rev() {
+                if let Some(r) = *region {
+                    if needed_regions.iter().any(|n| n.intersection(&r).is_some()) {
+                        needed_regions.push(r);
+                    } else {
+                        *region = None;
+                    }
+                }
+            }
+        }
+
+        for (hlayer, region) in hlayers.into_iter().zip(regions) {
+            let region = if let Some(x) = region {
+                x
+            } else {
+                continue;
+            };
+
+            let region_size: [usize; 2] = region.size().into();
+            let stride = region_size[0] * 4;
+            let mut data = vec![0u8; stride * region_size[1]];
+
+            let mut builder = binner.build(region_size);
+            let ctx = RenderCtx {
+                dpi_scale: wnd.dpi_scale,
+                offset: [region.min.x as f32, region.min.y as f32].into(),
+                region: None,
+                backdrops: &backdrops,
+                stop_at: Some(hlayer.clone()),
+                skipping: Cell::new(true),
+                layers_culled: Cell::new(0),
+            };
+            self.binner_build_layer(&mut builder, &ctx, root);
+            builder.finish();
+
+            rasterize(&binner, &mut data, stride, self.color_mode);
+            stats.add_binner(binner);
+
+            let backdrop = Backdrop {
+                data,
+                size: region_size,
+                kernel: kernel.clone(),
+            };
+            backdrops.push((hlayer, Arc::new(backdrop), region.min.to_vec()));
+        }
+
+        backdrops
+    }
+
+    /// Enumerate the layers having `BACKDROP_BLUR` in the subtree `hlayer`
+    /// in a drawing order (back to front).
+    fn collect_backdrop_layers(&self, hlayer: &HLayer<TBmp>, out: &mut Vec<HLayer<TBmp>>) {
+        let layer = &self.layers[hlayer.ptr];
+
+        if (layer.attrs.flags).contains(iface::LayerFlags::BACKDROP_BLUR) {
+            out.push(hlayer.clone());
+        }
+
+        for hlayer in layer.sublayers.iter() {
+            self.collect_backdrop_layers(hlayer, out);
+        }
     }
 
     fn binner_build_layer(
         &self,
         builder: &mut BinnerBuilder<'_, TBmp>,
-        ctx: &RenderCtx,
-        layer: &Layer<TBmp>,
+        ctx: &RenderCtx<'_, TBmp>,
+        hlayer: &HLayer<TBmp>,
     ) {
+        let layer = &self.layers[hlayer.ptr];
+
+        // Skip the layer if it's outside the rendered region
+        if let Some(region) = ctx.region {
+            if bbox2_intersect(layer.bbox, Some(region)).is_none() {
+                ctx.layers_culled.set(ctx.layers_culled.get() + 1);
+                return;
+            }
+        }
+
         // If the layer has both of a content and sublayers, and it's translucent,
         // then we have to create an outer group for group opacity effect.
         // TODO: Actually, `push_elem` creates an implicit group under a variety of
@@ -810,7 +1116,7 @@ impl<TBmp: Bmp> Screen<TBmp> {
         //       i.e., sublayers are masked by this layer's bounds.
         let attrs = &layer.attrs;
         let has_sublayers = layer.sublayers.len() > 0;
-        let has_content = attrs.bg_color.a > 0.0 || attrs.contents.is_some();
+        let has_content = attrs.has_content();
 
         let use_opacity_group = has_sublayers && has_content && attrs.opacity < 1.0;
 
@@ -821,28 +1127,40 @@ impl<TBmp: Bmp> Screen<TBmp> {
         };
 
         if use_opacity_group {
-            builder.open_group(None, attrs.opacity);
+            builder.open_group(None, None, attrs.opacity);
         }
 
         let transform = scale_mat3(attrs.transform, ctx.dpi_scale);
         let transform = translate_neg_mat3(transform, ctx.offset);
 
         if has_sublayers {
-            let mask_xform = if (attrs.flags).contains(iface::LayerFlags::MASK_TO_BOUNDS) {
-                Some(xform_and_aabb_to_parallelogram(transform, attrs.bounds))
-            } else {
-                None
-            };
-            builder.open_group(mask_xform, inner_opacity);
+            let (mask_xform, mask_corners) =
+                if (attrs.flags).contains(iface::LayerFlags::MASK_TO_BOUNDS) {
+                    (
+                        Some(xform_and_aabb_to_parallelogram(transform, attrs.bounds)),
+                        RoundedRect::corners(transform, attrs.bounds, attrs.corner_radii),
+                    )
+                } else {
+                    (None, None)
+                };
+            builder.open_group(mask_xform, mask_corners, inner_opacity);
 
             for hlayer in layer.sublayers.iter().rev() {
-                self.binner_build_layer(builder, ctx, &self.layers[hlayer.ptr]);
+                self.binner_build_layer(builder, ctx, hlayer);
             }
 
             builder.close_group();
         }
 
-        if has_content {
+        // When rendering a backdrop, skip everything in front of the layer
+        // `stop_at`, including the layer itself
+        let mut skip = ctx.skipping.get();
+        if ctx.stop_at.as_ref() == Some(hlayer) {
+            ctx.skipping.set(false);
+            skip = true;
+        }
+
+        if has_content && !skip {
             let bg_color = attrs.bg_color;
             let to_u8 = |x: f32| (x.fmax(0.0).fmin(1.0) * 255.0 + 0.5) as u8;
 
@@ -860,7 +1178,50 @@ impl<TBmp: Bmp> Screen<TBmp> {
                 ]
                 .into(),
                 opacity: inner_opacity,
+                corner_radii: attrs.corner_radii,
             });
+
+            if let Some((_, backdrop, origin)) =
+                (ctx.backdrops.iter()).find(|(hlayer2, _, _)| hlayer2 == hlayer)
+            {
+                // `BACKDROP_BLUR` requires the layer to be axis-aligned
+                if transform.x.y == 0.0 && transform.y.x == 0.0 {
+                    let origin = [
+                        origin.x as i32 - ctx.offset.x as i32,
+                        origin.y as i32 - ctx.offset.y as i32,
+                    ];
+                    builder.push_backdrop(
+                        xform_aabb(transform, attrs.bounds),
+                        RoundedRect::corners(transform, attrs.bounds, attrs.corner_radii),
+                        Arc::clone(backdrop),
+                        origin.into(),
+                        inner_opacity,
+                    );
+                }
+            }
+
+            // The shadow is drawn behind everything else
+            if let Some(shadow) = &attrs.shadow {
+                let shape = RoundedRect::blurred(
+                    transform,
+                    attrs.bounds.translate(shadow.offset),
+                    attrs.corner_radii,
+                    shadow.blur_radius.fmax(0.0) * 0.5,
+                );
+                if let Some(shape) = shape {
+                    let color = shadow.color;
+                    builder.push_shadow(
+                        shape,
+                        [
+                            to_u8(color.b),
+                            to_u8(color.g),
+                            to_u8(color.r),
+                            to_u8(color.a),
+                        ],
+                        inner_opacity,
+                    );
+                }
+            }
         }
 
         if use_opacity_group {
@@ -869,9 +1230,25 @@ impl<TBmp: Bmp> Screen<TBmp> {
     }
 }
 
-struct RenderCtx {
+struct RenderCtx<'a, TBmp: 'static> {
     dpi_scale: f32,
     offset: Vector2<f32>,
+
+    /// The rendered region in the window. If specified, the layers outside
+    /// this region are skipped. This requires valid `Layer::bbox`.
+    region: Option<Box2<usize>>,
+
+    /// The rendered backdrops and their positions in the window.
+    backdrops: &'a [(HLayer<TBmp>, Arc<Backdrop>, Vector2<usize>)],
+
+    /// Specifies the layer whose backdrop is being rendered.
+    stop_at: Option<HLayer<TBmp>>,
+
+    /// `true` while visiting the layers in front of `stop_at`.
+    skipping: Cell<bool>,
+
+    /// The number of layers skipped because of `region`.
+    layers_culled: Cell<usize>,
 }
 
 fn bbox2_intersect(x: Option<Box2<usize>>, y: Option<Box2<usize>>) -> Option<Box2<usize>> {
@@ -1483,4 +1860,107 @@ mod tests {
             Some(box2! { min: [20, 30], max: [80, 50] })
         );
     }
+
+    #[test]
+    fn render_partial() {
+        let mut screen: Screen<TestBmp> = Screen::new();
+        let mut binner = Binner::new();
+
+        // `layer0` is completely hidden behind `layer1`
+        let layer0 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [0.0, 0.0], max: [16.0, 16.0] }),
+            bg_color: Some([0.0, 0.0, 1.0, 1.0].into()),
+            ..Default::default()
+        });
+        let layer1 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [0.0, 0.0], max: [16.0, 16.0] }),
+            bg_color: Some([1.0, 0.0, 0.0, 1.0].into()),
+            ..Default::default()
+        });
+        let layer2 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [40.0, 40.0], max: [60.0, 60.0] }),
+            bg_color: Some([0.0, 1.0, 0.0, 1.0].into()),
+            ..Default::default()
+        });
+        let root = screen.new_layer(iface::LayerAttrs {
+            sublayers: Some(vec![layer0.clone(), layer1.clone(), layer2.clone()]),
+            ..Default::default()
+        });
+
+        let wnd = screen.new_wnd();
+        screen.set_wnd_size(&wnd, [64, 64]);
+        screen.set_wnd_layer(&wnd, Some(root.clone()));
+        screen.update_wnd(&wnd);
+
+        let bx = box2! { min: [0, 0], max: [16, 16] };
+        let mut out = vec![0u8; 64 * 16];
+
+        // `layer2` is outside `bx`
+        let stats = screen.render_wnd(&wnd, &mut out, 64, bx, &mut binner);
+        assert_eq!(stats.tiles_touched, 1);
+        assert_eq!(stats.layers_culled, 1);
+        // `layer0` is occluded by `layer1`
+        assert_eq!(stats.elems_culled, 1);
+        assert_eq!(stats.frags_culled, 1);
+        assert_eq!(out[0..4], [0, 0, 255, 255]);
+
+        // Layers are not culled if there are uncommitted changes
+        screen.set_layer_attr(
+            &layer2,
+            iface::LayerAttrs {
+                opacity: Some(0.5),
+                ..Default::default()
+            },
+        );
+        let stats = screen.render_wnd(&wnd, &mut out, 64, bx, &mut binner);
+        assert_eq!(stats.tiles_touched, 1);
+        assert_eq!(stats.layers_culled, 0);
+        assert!(stats.elems_culled >= 1);
+        assert_eq!(out[0..4], [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn color_mode() {
+        let mut screen: Screen<TestBmp> = Screen::new();
+        let mut binner = Binner::new();
+
+        let layer1 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [0.0, 0.0], max: [16.0, 16.0] }),
+            bg_color: Some([1.0, 1.0, 1.0, 1.0].into()),
+            ..Default::default()
+        });
+        let layer2 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [0.0, 0.0], max: [16.0, 16.0] }),
+            bg_color: Some([0.0, 0.0, 0.0, 0.5].into()),
+            ..Default::default()
+        });
+        let root = screen.new_layer(iface::LayerAttrs {
+            sublayers: Some(vec![layer1.clone(), layer2.clone()]),
+            ..Default::default()
+        });
+
+        let wnd = screen.new_wnd();
+        screen.set_wnd_size(&wnd, [16, 16]);
+        screen.set_wnd_layer(&wnd, Some(root.clone()));
+        screen.update_wnd(&wnd);
+
+        let bx = box2! { min: [0, 0], max: [16, 16] };
+        let mut out = vec![0u8; 64 * 16];
+
+        // Blend in the sRGB color space
+        assert_eq!(screen.color_mode(), ColorMode::Gamma8);
+        screen.render_wnd(&wnd, &mut out, 64, bx, &mut binner);
+        assert!((126..=128).contains(&out[0]));
+        assert_eq!(out[3], 255);
+
+        // Changing the mode invalidates the window
+        screen.set_color_mode(ColorMode::Linear16);
+        assert_eq!(screen.update_wnd(&wnd), Some(bx));
+        assert_eq!(screen.update_wnd(&wnd), None);
+
+        // Blend in linear light. 50% of white is about `188` in sRGB.
+        screen.render_wnd(&wnd, &mut out, 64, bx, &mut binner);
+        assert!((187..=189).contains(&out[0]));
+        assert_eq!(out[3], 255);
+    }
 }
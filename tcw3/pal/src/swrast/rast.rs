@@ -1,15 +1,17 @@
 use checked::Checked;
 use rayon::prelude::*;
-use std::{cell::RefCell, cmp::min};
+use std::{cell::RefCell, cmp::min, thread::LocalKey};
 
 use super::{
     binner::{Binner, Bmp},
     binrast::BinRast,
+    color::{ColorMode, Gamma8, Linear16, PixelFormat},
     TILE,
 };
 
 thread_local! {
-    static BIN_RAST: RefCell<BinRast> = RefCell::new(BinRast::new());
+    static BIN_RAST_GAMMA8: RefCell<BinRast<Gamma8>> = RefCell::new(BinRast::new());
+    static BIN_RAST_LINEAR16: RefCell<BinRast<Linear16>> = RefCell::new(BinRast::new());
 }
 
 /// Rasterize the contents of `binner` into the specified image buffer.
@@ -18,7 +20,21 @@ thread_local! {
 /// `out_stride * (size[1] - 1) + size[0] * 4`.
 ///
 /// `out_stride` must be at least `size[0] * 4`.
-pub fn rasterize(binner: &Binner<impl Bmp>, out: &mut [u8], out_stride: usize) {
+///
+/// `mode` specifies the color space and the precision used for blending.
+pub fn rasterize(binner: &Binner<impl Bmp>, out: &mut [u8], out_stride: usize, mode: ColorMode) {
+    match mode {
+        ColorMode::Gamma8 => rasterize_inner(binner, out, out_stride, &BIN_RAST_GAMMA8),
+        ColorMode::Linear16 => rasterize_inner(binner, out, out_stride, &BIN_RAST_LINEAR16),
+    }
+}
+
+fn rasterize_inner<F: PixelFormat>(
+    binner: &Binner<impl Bmp>,
+    out: &mut [u8],
+    out_stride: usize,
+    tls: &'static LocalKey<RefCell<BinRast<F>>>,
+) {
     let target_size = binner.target_size();
     let bin_count = binner.bin_count();
 
@@ -39,7 +55,7 @@ pub fn rasterize(binner: &Binner<impl Bmp>, out: &mut [u8], out_stride: usize) {
         .enumerate()
         .take(bin_count[1])
         .for_each(|(y, out)| {
-            BIN_RAST.with(|cell| {
+            tls.with(|cell| {
                 let mut bin_rast = cell.borrow_mut();
                 let bin_h = min(TILE, target_size[1] - y * TILE);
                 for x in 0..bin_count[0] {
@@ -54,11 +70,14 @@ pub fn rasterize(binner: &Binner<impl Bmp>, out: &mut [u8], out_stride: usize) {
 
 #[cfg(test)]
 mod tests {
-    use super::super::binner::ElemInfo;
+    use super::super::{
+        binner::{Content, ElemInfo},
+        color::{linear_to_srgb, srgb_to_linear},
+    };
     use super::*;
 
     use cggeom::box2;
-    use cgmath::Matrix3;
+    use cgmath::{prelude::*, Matrix3};
     use quickcheck::TestResult;
     use quickcheck_macros::quickcheck;
 
@@ -78,7 +97,7 @@ mod tests {
     }
 
     #[quickcheck]
-    fn smoke_test(size_x: usize, size_y: usize, extra_stride: usize) -> TestResult {
+    fn smoke_test(size_x: usize, size_y: usize, extra_stride: usize, linear: bool) -> TestResult {
         let size = [size_x, size_y];
         // Limit the memory usage and the test execution time
         if size[0] > 400 || size[1] > 400 || extra_stride > 400 {
@@ -98,6 +117,7 @@ mod tests {
                 bitmap: None,
                 bg_color: [50, 80, 100, 200].into(),
                 opacity: 0.8,
+                corner_radii: [0.0; 4],
             });
 
             builder.finish();
@@ -117,7 +137,12 @@ mod tests {
             return TestResult::discard();
         }
 
-        rasterize(&binner, &mut out_image, stride);
+        let mode = if linear {
+            ColorMode::Linear16
+        } else {
+            ColorMode::Gamma8
+        };
+        rasterize(&binner, &mut out_image, stride, mode);
 
         for (i, line) in out_image.chunks(stride).enumerate() {
             let inner = &line[0..size_x * 4];
@@ -134,4 +159,80 @@ mod tests {
 
         TestResult::passed()
     }
+
+    #[quickcheck]
+    fn linear_blending(colors: Vec<(u8, u8, u8, u8)>) -> TestResult {
+        if colors.is_empty() || colors.len() > 8 {
+            return TestResult::discard();
+        }
+
+        let size = [TILE, TILE];
+        let mut binner = Binner::<TestBmp>::new();
+        {
+            let mut builder = binner.build(size);
+
+            for &(c0, c1, c2, c3) in colors.iter() {
+                builder.push_elem(ElemInfo {
+                    xform: Matrix3::identity(),
+                    bounds: box2! { min: [-1.0, -1.0], max: [TILE as f32 + 1.0; 2] },
+                    contents_center: box2! { min: [0.0, 0.0], max: [1.0, 1.0] },
+                    contents_scale: 1.0,
+                    bitmap: None,
+                    bg_color: [c0, c1, c2, c3],
+                    opacity: 1.0,
+                    corner_radii: [0.0; 4],
+                });
+            }
+
+            builder.finish();
+        }
+
+        // Blend the elements in linear light using floating-point numbers
+        let mut expected = [0.0f32; 4];
+        for (elem, _) in binner.bin_elems([0, 0]) {
+            let c = match elem.content {
+                Content::Solid(c) => c,
+                _ => unreachable!(),
+            };
+            let alpha = elem.opacity as f32 / 256.0;
+            for i in 0..3 {
+                let src = srgb_to_linear(c[i] as f32 / 255.0) * alpha;
+                expected[i] = src + expected[i] * (1.0 - alpha);
+            }
+            expected[3] = alpha + expected[3] * (1.0 - alpha);
+        }
+
+        // Convert it to the output format
+        let alpha = expected[3];
+        let encode = |x: f32| {
+            if alpha > 0.0 {
+                linear_to_srgb((x / alpha).min(1.0)) * alpha * 255.0
+            } else {
+                0.0
+            }
+        };
+        let expected = [
+            encode(expected[0]),
+            encode(expected[1]),
+            encode(expected[2]),
+            alpha * 255.0,
+        ];
+
+        let stride = size[0] * 4;
+        let mut out_image = vec![0u8; stride * size[1]];
+        rasterize(&binner, &mut out_image, stride, ColorMode::Linear16);
+
+        for (i, pixel) in out_image.chunks_exact(4).enumerate() {
+            for (&actual, &expected) in pixel.iter().zip(expected.iter()) {
+                if (actual as f32 - expected).abs() > 2.0 {
+                    return TestResult::error(format!(
+                        "Pixel {} is {:?}, but the reference color is {:?}",
+                        i, pixel, expected
+                    ));
+                }
+            }
+        }
+
+        TestResult::passed()
+    }
 }
@@ -118,13 +118,20 @@ use std::{
     time::Duration,
 };
 
-use super::{iface, native, prelude::MtLazyStatic, prelude::*};
+use super::{accel, iface, native, prelude::MtLazyStatic, prelude::*};
 
+mod appearance;
+mod clipboard;
+mod clock;
+mod dnd;
 mod eventloop;
 mod logging;
+mod menu;
+mod notify;
 mod screen;
 mod textinput;
 mod tictxlistenershim;
+mod tooltip;
 mod uniqpool;
 pub mod wmapi;
 mod wndlistenershim;
@@ -338,6 +345,13 @@ impl Wm {
         self.eradicate_events();
         SCREEN.get_with_wm(self).reset();
         textinput::reset(self);
+        clipboard::reset(self);
+        appearance::reset(self);
+        clock::reset(self);
+        dnd::reset(self);
+        menu::reset(self);
+        notify::reset(self);
+        tooltip::reset(self);
     }
 }
 
@@ -440,6 +454,67 @@ impl wmapi::TestingWm for Wm {
             .raise_scroll_gesture(*self, hwnd, loc)
     }
 
+    fn raise_drag_motion(
+        &self,
+        hwnd: &HWnd,
+        loc: Point2<f32>,
+        types: iface::DragDataTypes,
+    ) -> iface::DragDataTypes {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_drag_motion(*self, hwnd, loc, types)
+    }
+
+    fn raise_drag_leave(&self, hwnd: &HWnd) {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).raise_drag_leave(*self, hwnd)
+    }
+
+    fn raise_drag_drop(&self, hwnd: &HWnd, loc: Point2<f32>, data: &iface::DragData) -> bool {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_drag_drop(*self, hwnd, loc, data)
+    }
+
+    fn simulate_drop(&self, hwnd: &HWnd, loc: Point2<f32>, data: &iface::DragData) -> bool {
+        let accepted_types = self.raise_drag_motion(hwnd, loc, data.types()) & data.types();
+
+        if accepted_types.is_empty() {
+            self.raise_drag_leave(hwnd);
+            false
+        } else {
+            let mut data = data.clone();
+            data.retain_types(accepted_types);
+            self.raise_drag_drop(hwnd, loc, &data)
+        }
+    }
+
+    fn access_tree(&self, hwnd: &HWnd) -> Option<iface::AccessNode> {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).access_tree(hwnd)
+    }
+
+    fn set_access_active(&self, hwnd: &HWnd, active: bool) {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .set_access_active(*self, hwnd, active)
+    }
+
+    fn raise_access_action(
+        &self,
+        hwnd: &HWnd,
+        node: iface::AccessNodeId,
+        action: iface::AccessAction,
+    ) -> bool {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_access_action(*self, hwnd, node, action)
+    }
+
     fn active_text_input_ctxs(&self) -> Vec<HTextInputCtx> {
         textinput::HTextInputCtx::active_ctxs(*self)
             .into_iter()
@@ -526,6 +601,78 @@ impl wmapi::TestingWm for Wm {
 
         self.raise_key_up(hwnd, source, pattern);
     }
+
+    fn clipboard_text(&self) -> Option<String> {
+        clipboard::text(*self)
+    }
+
+    fn set_clipboard_text(&self, text: Option<&str>) {
+        clipboard::set_text(*self, text)
+    }
+
+    fn set_appearance(&self, appearance: iface::Appearance) {
+        appearance::set_appearance(*self, appearance)
+    }
+
+    fn enable_virtual_clock(&self) {
+        clock::enable_virtual_clock(*self)
+    }
+
+    fn advance_clock(&self, duration: Duration) {
+        clock::advance_clock(*self, duration)
+    }
+
+    fn active_popup_menu(&self) -> Option<wmapi::PopupMenu> {
+        menu::active_popup_menu(*self)
+    }
+
+    fn pick_menu_item(&self, path: &[usize]) {
+        menu::pick_menu_item(*self, path)
+    }
+
+    fn dismiss_menu(&self) {
+        menu::dismiss_menu(*self)
+    }
+
+    fn simulate_menu_key(&self, key: wmapi::MenuKey) {
+        menu::simulate_menu_key(*self, key)
+    }
+
+    fn wnd_menu_bar(&self, hwnd: &HWnd) -> wmapi::MenuBar {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).wnd_menu_bar(*self, hwnd)
+    }
+
+    fn pick_menu_bar_item(&self, hwnd: &HWnd, path: &[usize]) {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .pick_menu_bar_item(*self, hwnd, path)
+    }
+
+    fn active_tooltip(&self) -> Option<wmapi::Tooltip> {
+        tooltip::active_tooltip(*self)
+    }
+
+    fn active_drag(&self) -> Option<wmapi::ActiveDrag> {
+        dnd::active_drag(*self)
+    }
+
+    fn finish_drag(&self, accepted: bool) {
+        dnd::finish_drag(*self, accepted)
+    }
+
+    fn notifications(&self) -> Vec<wmapi::ActiveNotification> {
+        notify::notifications(*self)
+    }
+
+    fn activate_notification(&self, id: iface::NotificationId, action: Option<&str>) {
+        notify::activate_notification(*self, id, action)
+    }
+
+    fn dismiss_notification(&self, id: iface::NotificationId) {
+        notify::dismiss_notification(*self, id)
+    }
 }
 
 impl iface::Wm for Wm {
@@ -667,6 +814,7 @@ impl iface::Wm for Wm {
             }
             (BackendAndWm::Testing, HWndInner::Testing(ts_hwnd)) => {
                 debug!("remove_wnd({:?})", hwnd);
+                tooltip::hide_tooltip(self, hwnd);
                 SCREEN.get_with_wm(self).remove_wnd(ts_hwnd);
             }
             _ => unreachable!(),
@@ -694,17 +842,20 @@ impl iface::Wm for Wm {
             (BackendAndWm::Testing, HWndInner::Testing(ts_hwnd)) => {
                 debug!("request_update_ready_wnd({:?})", hwnd);
 
+                if clock::defer_update_ready(self, ts_hwnd) {
+                    trace!("Deferring the request until the virtual clock is advanced");
+                    return;
+                }
+
                 let hwnd = hwnd.clone();
                 let ts_hwnd = ts_hwnd.clone();
-                // TODO: Add methods to `TestingWm` to customize this behavior
                 self.invoke_unsend(move |_| {
-                    // TODO: Bail out if `ts_hwnd` is not valid anymore
                     trace!(
                         "Automatically calling raise_update_ready({:?}) \
                          (triggererd by request_update_ready_wnd)",
                         hwnd
                     );
-                    SCREEN.get_with_wm(self).raise_update_ready(self, &ts_hwnd);
+                    clock::raise_update_ready(self, &ts_hwnd);
                 });
             }
             _ => unreachable!(),
@@ -884,6 +1035,175 @@ impl iface::Wm for Wm {
             _ => unreachable!(),
         }
     }
+
+    fn clipboard_formats(self) -> iface::ClipboardFormatFlags {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.clipboard_formats(),
+            BackendAndWm::Testing => clipboard::formats(self),
+        }
+    }
+
+    fn get_clipboard_text(self) -> Option<String> {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.get_clipboard_text(),
+            BackendAndWm::Testing => clipboard::text(self),
+        }
+    }
+
+    fn get_clipboard_text_async(self, f: impl FnOnce(Self, Option<String>) + 'static) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.get_clipboard_text_async(move |native_wm, text| {
+                f(Self::from_native_wm(native_wm), text);
+            }),
+            BackendAndWm::Testing => {
+                let text = clipboard::text(self);
+                self.invoke_unsend(move |wm| f(wm, text));
+            }
+        }
+    }
+
+    fn set_clipboard_text(self, text: &str) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.set_clipboard_text(text),
+            BackendAndWm::Testing => clipboard::set_text(self, Some(text)),
+        }
+    }
+
+    fn now(self) -> std::time::Instant {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.now(),
+            BackendAndWm::Testing => clock::now(self),
+        }
+    }
+
+    fn appearance(self) -> iface::Appearance {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.appearance(),
+            BackendAndWm::Testing => appearance::appearance(self),
+        }
+    }
+
+    fn set_appearance_listener(self, listener: Box<dyn Fn(Self)>) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.set_appearance_listener(Box::new(move |native_wm| {
+                listener(Self::from_native_wm(native_wm))
+            })),
+            BackendAndWm::Testing => appearance::set_listener(self, listener),
+        }
+    }
+
+    fn popup_menu(
+        self,
+        hwnd: &Self::HWnd,
+        items: &[iface::MenuItem],
+        anchor: Box2<f32>,
+        listener: Box<dyn iface::MenuListener<Self>>,
+    ) {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => {
+                let listener = Box::new(wndlistenershim::NativeMenuListener(listener));
+                wm.popup_menu(hwnd, items, anchor, listener);
+            }
+            (BackendAndWm::Testing, HWndInner::Testing(_)) => {
+                menu::popup_menu(self, hwnd, items, anchor, listener);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn show_tooltip(self, hwnd: &Self::HWnd, text: &str, anchor: Box2<f32>) {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => {
+                wm.show_tooltip(hwnd, text, anchor);
+            }
+            (BackendAndWm::Testing, HWndInner::Testing(_)) => {
+                tooltip::show_tooltip(self, hwnd, text, anchor);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn hide_tooltip(self, hwnd: &Self::HWnd) {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => {
+                wm.hide_tooltip(hwnd);
+            }
+            (BackendAndWm::Testing, HWndInner::Testing(_)) => {
+                tooltip::hide_tooltip(self, hwnd);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn start_drag(
+        self,
+        hwnd: &Self::HWnd,
+        data: iface::DragData,
+        on_finish: Box<dyn FnOnce(Self, bool)>,
+    ) {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => {
+                wm.start_drag(
+                    hwnd,
+                    data,
+                    Box::new(move |native_wm, accepted| {
+                        on_finish(Self::from_native_wm(native_wm), accepted)
+                    }),
+                );
+            }
+            (BackendAndWm::Testing, HWndInner::Testing(_)) => {
+                dnd::start_drag(self, hwnd, data, on_finish);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn update_access_tree(self, hwnd: &Self::HWnd, root: iface::AccessNode) {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => {
+                wm.update_access_tree(hwnd, root);
+            }
+            (BackendAndWm::Testing, HWndInner::Testing(hwnd)) => {
+                SCREEN.get_with_wm(self).update_access_tree(hwnd, root);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn is_access_active(self, hwnd: &Self::HWnd) -> bool {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => wm.is_access_active(hwnd),
+            (BackendAndWm::Testing, HWndInner::Testing(hwnd)) => {
+                SCREEN.get_with_wm(self).is_access_active(hwnd)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn show_notification(
+        self,
+        notification: iface::Notification<Self::Bitmap>,
+        replaces: Option<iface::NotificationId>,
+        listener: Box<dyn iface::NotificationListener<Self>>,
+    ) -> iface::NotificationId {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => {
+                let notification = notification_to_native(notification);
+                let listener = Box::new(wndlistenershim::NativeNotificationListener(listener));
+                wm.show_notification(notification, replaces, listener)
+            }
+            BackendAndWm::Testing => {
+                notify::show_notification(self, notification, replaces, listener)
+            }
+        }
+    }
+
+    fn close_notification(self, id: iface::NotificationId) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.close_notification(id),
+            BackendAndWm::Testing => notify::close_notification(self, id),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -909,6 +1229,13 @@ impl From<&screen::HWnd> for HWnd {
 }
 
 impl HWnd {
+    fn native_hwnd(self) -> Option<native::HWnd> {
+        match self.inner {
+            HWndInner::Native(imp) => Some(imp),
+            HWndInner::Testing(_) => None,
+        }
+    }
+
     fn testing_hwnd_ref(&self) -> Option<&screen::HWnd> {
         match &self.inner {
             HWndInner::Native(_) => None,
@@ -977,6 +1304,11 @@ fn wnd_attrs_to_native(attrs: WndAttrs<'_>) -> native::WndAttrs<'_> {
             .map(|listener| Box::new(wndlistenershim::NativeWndListener(listener)) as _),
         layer,
         cursor_shape: attrs.cursor_shape,
+        menu_bar: attrs.menu_bar,
+        owner: attrs
+            .owner
+            .map(|owner_or_none| owner_or_none.map(|hwnd| hwnd.native_hwnd().unwrap())),
+        modal: attrs.modal,
     }
 }
 
@@ -1040,6 +1372,9 @@ enum HTextInputCtxInner {
 #[derive(Debug)]
 pub struct AccelTable {
     testing: &'static [wmapi::ActionBinding],
+    /// The key bindings supplied to `AccelTableNew::from_key_bindings`. They
+    /// are matched against the key patterns of all key-based sources.
+    key: Vec<accel::KeyBinding>,
     native: native::AccelTable,
 }
 
@@ -1049,7 +1384,39 @@ impl AccelTable {
         testing: &'static [wmapi::ActionBinding],
         native: native::AccelTable,
     ) -> Self {
-        Self { testing, native }
+        Self {
+            testing,
+            key: Vec::new(),
+            native,
+        }
+    }
+
+    /// Find the action bound to the specified trigger.
+    fn find_action(&self, source: &str, pattern: &str) -> Option<iface::ActionId> {
+        if let Some(binding) = (self.testing.iter())
+            .find(|binding| (binding.source, binding.pattern) == (source, pattern))
+        {
+            return Some(binding.action);
+        }
+
+        if !["windows", "macos", "gtk"].contains(&source) {
+            return None;
+        }
+
+        let pattern: accel::KeyPattern = pattern.parse().ok()?;
+        (self.key.iter())
+            .find(|binding| binding.pattern == pattern)
+            .map(|binding| binding.action)
+    }
+}
+
+impl iface::AccelTableNew for AccelTable {
+    fn from_key_bindings(bindings: &[accel::KeyBinding]) -> Self {
+        Self {
+            testing: &[],
+            key: bindings.to_vec(),
+            native: native::AccelTable::from_key_bindings(bindings),
+        }
     }
 }
 
@@ -1069,6 +1436,9 @@ fn wnd_attrs_to_testing(attrs: WndAttrs<'_>) -> screen::WndAttrs<'_> {
         listener: attrs.listener,
         layer,
         cursor_shape: attrs.cursor_shape,
+        menu_bar: attrs.menu_bar,
+        owner: attrs.owner,
+        modal: attrs.modal,
     }
 }
 
@@ -1098,6 +1468,25 @@ fn layer_attrs_to_native(attrs: LayerAttrs) -> native::LayerAttrs {
         sublayers,
         opacity: attrs.opacity,
         flags: attrs.flags,
+        corner_radii: attrs.corner_radii,
+        shadow: attrs.shadow,
+    }
+}
+
+/// Convert `Notification<Bitmap>` to `Notification<native::Bitmap>`. Panics if
+/// the icon was created by the wrong backend.
+fn notification_to_native(
+    notification: iface::Notification<Bitmap>,
+) -> iface::Notification<native::Bitmap> {
+    let icon = notification.icon.map(|icon| match icon.inner {
+        BitmapInner::Native(bitmap) => bitmap,
+        BitmapInner::Testing(_) => panic!("Bitmap was created by the wrong backend"),
+    });
+    iface::Notification {
+        title: notification.title,
+        body: notification.body,
+        icon,
+        actions: notification.actions,
     }
 }
 
@@ -1127,6 +1516,8 @@ fn layer_attrs_to_testing(attrs: LayerAttrs) -> screen::LayerAttrs {
         sublayers,
         opacity: attrs.opacity,
         flags: attrs.flags,
+        corner_radii: attrs.corner_radii,
+        shadow: attrs.shadow,
     }
 }
 
@@ -1223,6 +1614,21 @@ impl iface::BitmapBuilderNew for BitmapBuilder {
             },
         }
     }
+
+    fn with_rgba8_premul(size: [u32; 2], data: &[u8]) -> Self {
+        match Wm::backend() {
+            Backend::Native { .. } => Self {
+                inner: BitmapBuilderInner::Native(native::BitmapBuilder::with_rgba8_premul(
+                    size, data,
+                )),
+            },
+            Backend::Testing { .. } => Self {
+                inner: BitmapBuilderInner::Testing(bitmap::BitmapBuilder::with_rgba8_premul(
+                    size, data,
+                )),
+            },
+        }
+    }
 }
 
 impl iface::BitmapBuilder for BitmapBuilder {
@@ -1252,16 +1658,21 @@ impl iface::Canvas for BitmapBuilder {
         fn cubic_bezier_to(&mut self, cp1: Point2<f32>, cp2: Point2<f32>, p: Point2<f32>);
         fn quad_bezier_to(&mut self, cp: Point2<f32>, p: Point2<f32>);
         fn fill(&mut self);
+        fn fill_even_odd(&mut self);
         fn stroke(&mut self);
         fn clip(&mut self);
         fn set_fill_rgb(&mut self, rgb: iface::RGBAF32);
+        fn set_fill_gradient(&mut self, gradient: &iface::Gradient<'_>);
         fn set_stroke_rgb(&mut self, rgb: iface::RGBAF32);
+        fn set_stroke_gradient(&mut self, gradient: &iface::Gradient<'_>);
         fn set_line_cap(&mut self, cap: iface::LineCap);
         fn set_line_join(&mut self, join: iface::LineJoin);
         fn set_line_dash(&mut self, phase: f32, lengths: &[f32]);
         fn set_line_width(&mut self, width: f32);
         fn set_line_miter_limit(&mut self, miter_limit: f32);
         fn mult_transform(&mut self, m: Matrix3<f32>);
+        fn begin_group(&mut self, opacity: f32);
+        fn end_group(&mut self);
     }
 }
 
@@ -1326,6 +1737,8 @@ impl iface::CharStyle for CharStyle {
                     template: style,
                     sys: attrs.sys,
                     size: attrs.size,
+                    bold: attrs.bold,
+                    italic: attrs.italic,
                     decor: attrs.decor,
                     color: attrs.color,
                 })),
@@ -1335,6 +1748,8 @@ impl iface::CharStyle for CharStyle {
                     template: style,
                     sys: attrs.sys,
                     size: attrs.size,
+                    bold: attrs.bold,
+                    italic: attrs.italic,
                     decor: attrs.decor,
                     color: attrs.color,
                 })),
@@ -1380,6 +1795,37 @@ impl iface::TextLayout for TextLayout {
         }
     }
 
+    fn from_attr_text(
+        text: &iface::AttrText<Self::CharStyle>,
+        style: &Self::CharStyle,
+        width: Option<f32>,
+    ) -> Self {
+        match &style.inner {
+            CharStyleInner::Native(style) => {
+                let text = text.map_char_styles(|s| match &s.inner {
+                    CharStyleInner::Native(s) => s.clone(),
+                    CharStyleInner::Testing(_) => panic!("CharStyles belong to different backends"),
+                });
+                Self {
+                    inner: TextLayoutInner::Native(native::TextLayout::from_attr_text(
+                        &text, style, width,
+                    )),
+                }
+            }
+            CharStyleInner::Testing(style) => {
+                let text = text.map_char_styles(|s| match &s.inner {
+                    CharStyleInner::Testing(s) => s.clone(),
+                    CharStyleInner::Native(_) => panic!("CharStyles belong to different backends"),
+                });
+                Self {
+                    inner: TextLayoutInner::Testing(text::TextLayout::from_attr_text(
+                        &text, style, width,
+                    )),
+                }
+            }
+        }
+    }
+
     forward! {
         inner_type: TextLayoutInner;
         fn visual_bounds(&self) -> Box2<f32>;
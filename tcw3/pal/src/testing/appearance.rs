@@ -0,0 +1,50 @@
+//! The virtual system appearance setting.
+use log::debug;
+use std::{cell::RefCell, rc::Rc};
+
+use super::Wm;
+use crate::iface::Appearance;
+
+struct AppearanceState {
+    appearance: Appearance,
+    listener: Option<Rc<dyn Fn(Wm)>>,
+}
+
+mt_lazy_static! {
+    static <Wm> ref APPEARANCE: RefCell<AppearanceState> =>
+        |_| RefCell::new(AppearanceState {
+            appearance: Appearance::default(),
+            listener: None,
+        });
+}
+
+pub fn reset(wm: Wm) {
+    // Keep the listener because it's usually registered by a global object
+    // that outlives the test
+    set_appearance(wm, Appearance::default());
+}
+
+pub fn appearance(wm: Wm) -> Appearance {
+    APPEARANCE.get_with_wm(wm).borrow().appearance
+}
+
+pub fn set_appearance(wm: Wm, appearance: Appearance) {
+    debug!("set_appearance({:?})", appearance);
+
+    let listener = {
+        let mut state = APPEARANCE.get_with_wm(wm).borrow_mut();
+        if state.appearance == appearance {
+            return;
+        }
+        state.appearance = appearance;
+        state.listener.clone()
+    };
+
+    if let Some(listener) = listener {
+        listener(wm);
+    }
+}
+
+pub fn set_listener(wm: Wm, listener: Box<dyn Fn(Wm)>) {
+    APPEARANCE.get_with_wm(wm).borrow_mut().listener = Some(listener.into());
+}
@@ -0,0 +1,38 @@
+//! The virtual clipboard.
+use log::{debug, trace};
+use std::cell::RefCell;
+
+use super::Wm;
+use crate::iface::ClipboardFormatFlags;
+
+struct Clipboard {
+    text: Option<String>,
+}
+
+mt_lazy_static! {
+    static <Wm> ref CLIPBOARD: RefCell<Clipboard> =>
+        |_| RefCell::new(Clipboard { text: None });
+}
+
+pub fn reset(wm: Wm) {
+    CLIPBOARD.get_with_wm(wm).borrow_mut().text = None;
+}
+
+pub fn formats(wm: Wm) -> ClipboardFormatFlags {
+    let clipboard = CLIPBOARD.get_with_wm(wm).borrow();
+    let mut flags = ClipboardFormatFlags::empty();
+    flags.set(ClipboardFormatFlags::TEXT, clipboard.text.is_some());
+    trace!("clipboard_formats() -> {:?}", flags);
+    flags
+}
+
+pub fn text(wm: Wm) -> Option<String> {
+    let text = CLIPBOARD.get_with_wm(wm).borrow().text.clone();
+    trace!("get_clipboard_text() -> {:?}", text);
+    text
+}
+
+pub fn set_text(wm: Wm, text: Option<&str>) {
+    debug!("set_clipboard_text({:?})", text);
+    CLIPBOARD.get_with_wm(wm).borrow_mut().text = text.map(str::to_owned);
+}
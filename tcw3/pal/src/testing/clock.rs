@@ -0,0 +1,105 @@
+//! The virtual clock.
+//!
+//! When the virtual clock is enabled, `Wm::now` returns a virtual time that
+//! only advances when `TestingWm::advance_clock` is called. Additionally,
+//! frame requests (`request_update_ready_wnd`) made by a window's
+//! `update_ready` handler are deferred until the clock is advanced. This
+//! prevents animations from repeating frames indefinitely while allowing
+//! a test to observe each frame deterministically.
+use log::{debug, trace};
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+use super::{screen::HWnd, Wm, SCREEN};
+
+struct Clock {
+    /// The current virtual time. `None` if the virtual clock is disabled.
+    time: Option<Instant>,
+    /// Windows whose `update_ready` handlers are being called.
+    updating: Vec<HWnd>,
+    /// Windows for which `update_ready` was requested by their own
+    /// `update_ready` handlers.
+    deferred: Vec<HWnd>,
+}
+
+mt_lazy_static! {
+    static <Wm> ref CLOCK: RefCell<Clock> =>
+        |_| RefCell::new(Clock {
+            time: None,
+            updating: Vec::new(),
+            deferred: Vec::new(),
+        });
+}
+
+pub fn reset(wm: Wm) {
+    let mut clock = CLOCK.get_with_wm(wm).borrow_mut();
+    clock.time = None;
+    clock.deferred.clear();
+}
+
+pub fn now(wm: Wm) -> Instant {
+    CLOCK
+        .get_with_wm(wm)
+        .borrow()
+        .time
+        .unwrap_or_else(Instant::now)
+}
+
+pub fn enable_virtual_clock(wm: Wm) {
+    let mut clock = CLOCK.get_with_wm(wm).borrow_mut();
+    if clock.time.is_none() {
+        debug!("enable_virtual_clock()");
+        clock.time = Some(Instant::now());
+    }
+}
+
+pub fn advance_clock(wm: Wm, duration: Duration) {
+    let deferred = {
+        let mut clock = CLOCK.get_with_wm(wm).borrow_mut();
+        let time = clock
+            .time
+            .as_mut()
+            .expect("the virtual clock is not enabled");
+        *time += duration;
+        debug!("advance_clock({:?})", duration);
+
+        std::mem::replace(&mut clock.deferred, Vec::new())
+    };
+
+    for hwnd in deferred {
+        trace!("Calling the deferred raise_update_ready({:?})", hwnd);
+        raise_update_ready(wm, &hwnd);
+    }
+}
+
+/// Returns `true` if the frame request for `hwnd` should be deferred until
+/// the virtual clock is advanced. In this case, the request is recorded.
+pub fn defer_update_ready(wm: Wm, hwnd: &HWnd) -> bool {
+    let mut clock = CLOCK.get_with_wm(wm).borrow_mut();
+    if clock.time.is_none() || !clock.updating.contains(hwnd) {
+        return false;
+    }
+
+    if !clock.deferred.contains(hwnd) {
+        clock.deferred.push(hwnd.clone());
+    }
+    true
+}
+
+/// Call `WndListener::update_ready` while keeping track of the window being
+/// updated.
+pub fn raise_update_ready(wm: Wm, hwnd: &HWnd) {
+    CLOCK
+        .get_with_wm(wm)
+        .borrow_mut()
+        .updating
+        .push(hwnd.clone());
+
+    SCREEN.get_with_wm(wm).raise_update_ready(wm, hwnd);
+
+    let mut clock = CLOCK.get_with_wm(wm).borrow_mut();
+    let i = clock.updating.iter().rposition(|h| h == hwnd).unwrap();
+    clock.updating.remove(i);
+}
@@ -0,0 +1,61 @@
+//! The virtual drag-and-drop operation.
+use log::debug;
+use std::cell::RefCell;
+
+use super::{wmapi::ActiveDrag, HWnd, Wm};
+use crate::iface;
+
+struct DragState {
+    drag: ActiveDrag,
+    on_finish: Box<dyn FnOnce(Wm, bool)>,
+}
+
+mt_lazy_static! {
+    static <Wm> ref ACTIVE_DRAG: RefCell<Option<DragState>> => |_| RefCell::new(None);
+}
+
+pub fn reset(wm: Wm) {
+    // Drop the handler outside the borrow because it might execute user code
+    let state = ACTIVE_DRAG.get_with_wm(wm).borrow_mut().take();
+    drop(state);
+}
+
+pub fn start_drag(
+    wm: Wm,
+    hwnd: &HWnd,
+    data: iface::DragData,
+    on_finish: Box<dyn FnOnce(Wm, bool)>,
+) {
+    debug!("start_drag({:?}, {:?})", hwnd, data);
+
+    let state = DragState {
+        drag: ActiveDrag {
+            hwnd: hwnd.clone(),
+            data,
+        },
+        on_finish,
+    };
+
+    // Cancel the current operation
+    let old_state = ACTIVE_DRAG.get_with_wm(wm).borrow_mut().replace(state);
+    if let Some(old_state) = old_state {
+        wm.invoke_unsend(move |wm| (old_state.on_finish)(wm, false));
+    }
+}
+
+pub fn active_drag(wm: Wm) -> Option<ActiveDrag> {
+    let state = ACTIVE_DRAG.get_with_wm(wm).borrow();
+    state.as_ref().map(|state| state.drag.clone())
+}
+
+pub fn finish_drag(wm: Wm, accepted: bool) {
+    debug!("finish_drag({:?})", accepted);
+
+    let state = ACTIVE_DRAG
+        .get_with_wm(wm)
+        .borrow_mut()
+        .take()
+        .expect("there is no active drag-and-drop operation");
+
+    (state.on_finish)(wm, accepted);
+}
@@ -0,0 +1,216 @@
+//! The virtual popup menu.
+use cggeom::Box2;
+use log::debug;
+use std::{cell::RefCell, rc::Rc};
+
+use super::{
+    wmapi::{MenuKey, PopupMenu, PopupMenuItem},
+    HWnd, Wm,
+};
+use crate::iface;
+
+struct MenuState {
+    menu: PopupMenu,
+    listener: Rc<dyn iface::MenuListener<Wm>>,
+}
+
+mt_lazy_static! {
+    static <Wm> ref POPUP_MENU: RefCell<Option<MenuState>> => |_| RefCell::new(None);
+}
+
+pub fn reset(wm: Wm) {
+    // Drop the listener outside the borrow because it might execute user code
+    let state = POPUP_MENU.get_with_wm(wm).borrow_mut().take();
+    drop(state);
+}
+
+pub fn popup_menu(
+    wm: Wm,
+    hwnd: &HWnd,
+    items: &[iface::MenuItem],
+    anchor: Box2<f32>,
+    listener: Box<dyn iface::MenuListener<Wm>>,
+) {
+    debug!("popup_menu({:?}, {:?}, {:?})", hwnd, items, anchor);
+
+    // Close the current popup menu
+    let old_state = POPUP_MENU.get_with_wm(wm).borrow_mut().take();
+    if let Some(old_state) = old_state {
+        wm.invoke_unsend(move |wm| old_state.listener.dismissed(wm, &old_state.menu.hwnd));
+    }
+
+    let items = validate_items(items, &mut |action| {
+        listener.validate_action(wm, hwnd, action)
+    });
+
+    let state = MenuState {
+        menu: PopupMenu {
+            hwnd: hwnd.clone(),
+            anchor,
+            items,
+            highlight: Vec::new(),
+        },
+        listener: Rc::from(listener),
+    };
+
+    let old_state = POPUP_MENU.get_with_wm(wm).borrow_mut().replace(state);
+    assert!(
+        old_state.is_none(),
+        "`popup_menu` was called by `MenuListener::validate_action`"
+    );
+}
+
+/// Convert `items` to `PopupMenuItem`s, determining the state of each action
+/// item by `validate_action`.
+pub(super) fn validate_items(
+    items: &[iface::MenuItem],
+    validate_action: &mut dyn FnMut(iface::ActionId) -> iface::ActionStatus,
+) -> Vec<PopupMenuItem> {
+    items
+        .iter()
+        .map(|item| match item {
+            iface::MenuItem::Action(action) => PopupMenuItem::Action {
+                label: action.label.clone(),
+                action: action.action,
+                shortcut: action.shortcut.clone(),
+                status: validate_action(action.action),
+            },
+            iface::MenuItem::Submenu(label, items) => PopupMenuItem::Submenu {
+                label: label.clone(),
+                items: validate_items(items, validate_action),
+            },
+            iface::MenuItem::Sep => PopupMenuItem::Sep,
+        })
+        .collect()
+}
+
+pub fn active_popup_menu(wm: Wm) -> Option<PopupMenu> {
+    let state = POPUP_MENU.get_with_wm(wm).borrow();
+    state.as_ref().map(|state| state.menu.clone())
+}
+
+pub fn pick_menu_item(wm: Wm, path: &[usize]) {
+    debug!("pick_menu_item({:?})", path);
+
+    let state = POPUP_MENU
+        .get_with_wm(wm)
+        .borrow_mut()
+        .take()
+        .expect("there is no open popup menu");
+
+    let action = match state.menu.item(path) {
+        Some(item @ PopupMenuItem::Action { .. }) if !item.is_enabled() => {
+            panic!("the menu item at {:?} is disabled: {:?}", path, item)
+        }
+        Some(PopupMenuItem::Action { action, .. }) => *action,
+        item => panic!(
+            "the path {:?} does not refer to an action item: {:?}",
+            path, item
+        ),
+    };
+
+    state.listener.perform_action(wm, &state.menu.hwnd, action);
+    state.listener.dismissed(wm, &state.menu.hwnd);
+}
+
+pub fn dismiss_menu(wm: Wm) {
+    debug!("dismiss_menu()");
+
+    let state = POPUP_MENU
+        .get_with_wm(wm)
+        .borrow_mut()
+        .take()
+        .expect("there is no open popup menu");
+
+    state.listener.dismissed(wm, &state.menu.hwnd);
+}
+
+pub fn simulate_menu_key(wm: Wm, key: MenuKey) {
+    debug!("simulate_menu_key({:?})", key);
+
+    let mut state_cell = POPUP_MENU.get_with_wm(wm).borrow_mut();
+    let menu = &mut state_cell
+        .as_mut()
+        .expect("there is no open popup menu")
+        .menu;
+
+    let highlighted_item = menu.item(&menu.highlight);
+
+    match key {
+        MenuKey::Up | MenuKey::Down => {
+            let level = menu.highlight.len().saturating_sub(1);
+            let items = if level == 0 {
+                &menu.items[..]
+            } else {
+                menu.item(&menu.highlight[..level])
+                    .and_then(PopupMenuItem::submenu_items)
+                    .unwrap()
+            };
+
+            let current = menu.highlight.get(level).cloned();
+            if let Some(i) = next_enabled_item(items, current, key == MenuKey::Down) {
+                menu.highlight.truncate(level);
+                menu.highlight.push(i);
+            }
+        }
+        MenuKey::Right => {
+            if let Some(i) = highlighted_item
+                .and_then(PopupMenuItem::submenu_items)
+                .and_then(|items| next_enabled_item(items, None, true))
+            {
+                menu.highlight.push(i);
+            }
+        }
+        MenuKey::Left => {
+            if menu.highlight.len() > 1 {
+                menu.highlight.pop();
+            }
+        }
+        MenuKey::Enter => match highlighted_item {
+            Some(PopupMenuItem::Action { .. }) => {
+                let path = menu.highlight.clone();
+                drop(state_cell);
+                pick_menu_item(wm, &path);
+            }
+            Some(PopupMenuItem::Submenu { items, .. }) => {
+                if let Some(i) = next_enabled_item(items, None, true) {
+                    menu.highlight.push(i);
+                }
+            }
+            _ => {}
+        },
+        MenuKey::Escape => {
+            if menu.highlight.len() > 1 {
+                menu.highlight.pop();
+            } else {
+                drop(state_cell);
+                dismiss_menu(wm);
+            }
+        }
+    }
+}
+
+/// Find the next enabled item after (or before if `forward` is `false`)
+/// `current`, wrapping around at the ends.
+fn next_enabled_item(
+    items: &[PopupMenuItem],
+    current: Option<usize>,
+    forward: bool,
+) -> Option<usize> {
+    let len = items.len();
+    let start = match (current, forward) {
+        (Some(i), _) => i,
+        (None, true) => len.checked_sub(1)?,
+        (None, false) => 0,
+    };
+
+    (1..=len)
+        .map(|k| {
+            if forward {
+                (start + k) % len
+            } else {
+                (start + len - k) % len
+            }
+        })
+        .find(|&i| items[i].is_enabled())
+}
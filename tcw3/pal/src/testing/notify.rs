@@ -0,0 +1,113 @@
+//! The virtual notification center.
+use log::debug;
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use super::{wmapi::ActiveNotification, Bitmap, Wm};
+use crate::iface;
+
+struct NotifyState {
+    next_id: u64,
+    /// The displayed notifications, ordered by identifiers
+    notes: BTreeMap<u64, Note>,
+}
+
+struct Note {
+    notification: iface::Notification<Bitmap>,
+    listener: Rc<dyn iface::NotificationListener<Wm>>,
+}
+
+mt_lazy_static! {
+    static <Wm> ref STATE: RefCell<NotifyState> => |_| RefCell::new(NotifyState {
+        next_id: 1,
+        notes: BTreeMap::new(),
+    });
+}
+
+pub fn reset(wm: Wm) {
+    // Drop the listeners outside the borrow because they might execute user
+    // code
+    let notes = std::mem::take(&mut STATE.get_with_wm(wm).borrow_mut().notes);
+    drop(notes);
+}
+
+pub fn show_notification(
+    wm: Wm,
+    notification: iface::Notification<Bitmap>,
+    replaces: Option<iface::NotificationId>,
+    listener: Box<dyn iface::NotificationListener<Wm>>,
+) -> iface::NotificationId {
+    debug!("show_notification({:?}, {:?})", notification, replaces);
+
+    let mut state = STATE.get_with_wm(wm).borrow_mut();
+
+    let old_note = replaces.and_then(|id| Some((id, state.notes.remove(&id.0)?)));
+
+    let id = if let Some((id, old_note)) = old_note {
+        wm.invoke_unsend(move |wm| old_note.listener.closed(wm, id));
+        id
+    } else {
+        let id = iface::NotificationId(state.next_id);
+        state.next_id += 1;
+        id
+    };
+
+    state.notes.insert(
+        id.0,
+        Note {
+            notification,
+            listener: Rc::from(listener),
+        },
+    );
+
+    id
+}
+
+pub fn close_notification(wm: Wm, id: iface::NotificationId) {
+    let note = STATE.get_with_wm(wm).borrow_mut().notes.remove(&id.0);
+
+    if let Some(note) = note {
+        debug!("close_notification({:?})", id);
+        wm.invoke_unsend(move |wm| note.listener.closed(wm, id));
+    }
+}
+
+pub fn notifications(wm: Wm) -> Vec<ActiveNotification> {
+    (STATE.get_with_wm(wm).borrow().notes.iter())
+        .map(|(&id, note)| ActiveNotification {
+            id: iface::NotificationId(id),
+            notification: note.notification.clone(),
+        })
+        .collect()
+}
+
+pub fn activate_notification(wm: Wm, id: iface::NotificationId, action: Option<&str>) {
+    debug!("activate_notification({:?}, {:?})", id, action);
+
+    let listener = {
+        let state = STATE.get_with_wm(wm).borrow();
+        let note = (state.notes.get(&id.0))
+            .unwrap_or_else(|| panic!("the notification {:?} is not displayed", id));
+
+        if let Some(action) = action {
+            assert!(
+                note.notification.actions.iter().any(|a| a.id == action),
+                "the notification {:?} does not have an action {:?}",
+                id,
+                action
+            );
+        }
+
+        Rc::clone(&note.listener)
+    };
+
+    listener.activated(wm, id, action);
+}
+
+pub fn dismiss_notification(wm: Wm, id: iface::NotificationId) {
+    debug!("dismiss_notification({:?})", id);
+
+    let note = (STATE.get_with_wm(wm).borrow_mut().notes.remove(&id.0))
+        .unwrap_or_else(|| panic!("the notification {:?} is not displayed", id));
+
+    note.listener.closed(wm, id);
+}
@@ -2,11 +2,12 @@
 use cggeom::{box2, prelude::*, Box2};
 use cgmath::{Point2, Vector2};
 use log::warn;
-use std::{cell::RefCell, fmt, rc::Rc};
+use std::{borrow::Cow, cell::RefCell, fmt, rc::Rc};
 
 use super::super::{iface, swrast};
 use super::{
     bitmap::Bitmap,
+    menu,
     uniqpool::{PoolPtr, UniqPool},
     wmapi, AccelTable, Wm,
 };
@@ -59,15 +60,19 @@ pub struct Wnd {
     img_size: [usize; 2],
     img_data: Vec<u8>,
     img_dpi_scale: f32,
+
+    access_tree: Option<iface::AccessNode>,
+    access_active: bool,
 }
 
 impl Screen {
     pub(super) fn new() -> Self {
-        let state = State {
+        let mut state = State {
             binner: swrast::Binner::new(),
             sr_scrn: swrast::Screen::new(),
             wnds: UniqPool::new(),
         };
+        state.sr_scrn.set_color_mode(swrast::ColorMode::from_env());
 
         Self {
             state: RefCell::new(state),
@@ -82,6 +87,7 @@ impl Screen {
         }
 
         state.sr_scrn = swrast::Screen::new();
+        state.sr_scrn.set_color_mode(swrast::ColorMode::from_env());
         state.wnds = UniqPool::new();
     }
 
@@ -103,11 +109,16 @@ impl Screen {
                 caption: attrs.caption.unwrap_or("Default title".into()).into_owned(),
                 visible: attrs.visible.unwrap_or(false),
                 cursor_shape: attrs.cursor_shape.unwrap_or_default(),
+                menu_bar: attrs.menu_bar.map(Cow::into_owned).unwrap_or_default(),
+                owner: attrs.owner.unwrap_or(None),
+                modal: attrs.modal.unwrap_or(false),
             },
             listener: Rc::from(attrs.listener.unwrap_or_else(|| Box::new(()))),
             img_size: [0, 0],
             img_data: Vec::new(),
             img_dpi_scale: 1.0,
+            access_tree: None,
+            access_active: true,
         };
 
         state
@@ -138,6 +149,9 @@ impl Screen {
         apply!(caption);
         apply!(visible);
         apply!(cursor_shape);
+        apply!(menu_bar);
+        apply!(owner);
+        apply!(modal);
 
         if let Some(layer) = attrs.layer {
             state
@@ -237,10 +251,12 @@ impl Screen {
         listener.close_requested(wm, &hwnd.into());
     }
 
+    /// Call `WndListener::update_ready`. Does nothing if `hwnd` is not valid
+    /// anymore.
     pub(super) fn raise_update_ready(&self, wm: Wm, hwnd: &HWnd) {
-        let listener = self.wnd_listener(hwnd).unwrap();
-
-        listener.update_ready(wm, &hwnd.into());
+        if let Ok(listener) = self.wnd_listener(hwnd) {
+            listener.update_ready(wm, &hwnd.into());
+        }
     }
 
     /// Implements `TestingWm::set_wnd_dpi_scale`.
@@ -315,7 +331,7 @@ impl Screen {
         if let Some(dirty_rect) = wnd.dirty_rect.take() {
             state.sr_scrn.render_wnd(
                 &wnd.sr_wnd,
-                &mut wnd.img_data,
+                &mut wnd.img_data[dirty_rect.min.x * 4 + dirty_rect.min.y * img_stride..],
                 img_stride,
                 dirty_rect,
                 &mut state.binner,
@@ -393,6 +409,124 @@ impl Screen {
         })
     }
 
+    /// Implements `TestingWm::raise_drag_motion`.
+    pub(super) fn raise_drag_motion(
+        &self,
+        wm: Wm,
+        hwnd: &HWnd,
+        loc: Point2<f32>,
+        types: iface::DragDataTypes,
+    ) -> iface::DragDataTypes {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.drag_motion(wm, &hwnd.into(), loc, types)
+    }
+
+    /// Implements `TestingWm::raise_drag_leave`.
+    pub(super) fn raise_drag_leave(&self, wm: Wm, hwnd: &HWnd) {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.drag_leave(wm, &hwnd.into());
+    }
+
+    /// Implements `TestingWm::raise_drag_drop`.
+    pub(super) fn raise_drag_drop(
+        &self,
+        wm: Wm,
+        hwnd: &HWnd,
+        loc: Point2<f32>,
+        data: &iface::DragData,
+    ) -> bool {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.drag_drop(wm, &hwnd.into(), loc, data)
+    }
+
+    /// Implements `Wm::update_access_tree`.
+    pub(super) fn update_access_tree(&self, hwnd: &HWnd, root: iface::AccessNode) {
+        let mut state = self.state.borrow_mut();
+        state.wnds[hwnd.ptr].access_tree = Some(root);
+    }
+
+    /// Implements `TestingWm::access_tree`.
+    pub(super) fn access_tree(&self, hwnd: &HWnd) -> Option<iface::AccessNode> {
+        let state = self.state.borrow();
+        state.wnds.get(hwnd.ptr)?.access_tree.clone()
+    }
+
+    /// Implements `Wm::is_access_active`.
+    pub(super) fn is_access_active(&self, hwnd: &HWnd) -> bool {
+        let state = self.state.borrow();
+        state
+            .wnds
+            .get(hwnd.ptr)
+            .map_or(false, |wnd| wnd.access_active)
+    }
+
+    /// Implements `TestingWm::set_access_active`.
+    pub(super) fn set_access_active(&self, wm: Wm, hwnd: &HWnd, active: bool) {
+        {
+            let mut state = self.state.borrow_mut();
+            let wnd = &mut state.wnds[hwnd.ptr];
+            if wnd.access_active == active {
+                return;
+            }
+            wnd.access_active = active;
+        }
+
+        if active {
+            let listener = self.wnd_listener(hwnd).unwrap();
+            listener.access_activated(wm, &hwnd.into());
+        }
+    }
+
+    /// Implements `TestingWm::raise_access_action`.
+    pub(super) fn raise_access_action(
+        &self,
+        wm: Wm,
+        hwnd: &HWnd,
+        node: iface::AccessNodeId,
+        action: iface::AccessAction,
+    ) -> bool {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.access_action(wm, &hwnd.into(), node, action)
+    }
+
+    /// Implements `TestingWm::wnd_menu_bar`.
+    pub(super) fn wnd_menu_bar(&self, wm: Wm, hwnd: &HWnd) -> wmapi::MenuBar {
+        let items = {
+            let state = self.state.borrow();
+            state.wnds[hwnd.ptr].attrs.menu_bar.clone()
+        };
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        let items = menu::validate_items(&items, &mut |action| {
+            listener.validate_action(wm, &hwnd.into(), action)
+        });
+
+        wmapi::MenuBar { items }
+    }
+
+    /// Implements `TestingWm::pick_menu_bar_item`.
+    pub(super) fn pick_menu_bar_item(&self, wm: Wm, hwnd: &HWnd, path: &[usize]) {
+        let menu_bar = self.wnd_menu_bar(wm, hwnd);
+
+        let action = match menu_bar.item(path) {
+            Some(item @ wmapi::PopupMenuItem::Action { .. }) if !item.is_enabled() => {
+                panic!("the menu item at {:?} is disabled: {:?}", path, item)
+            }
+            Some(wmapi::PopupMenuItem::Action { action, .. }) => *action,
+            item => panic!(
+                "the path {:?} does not refer to an action item: {:?}",
+                path, item
+            ),
+        };
+
+        let listener = self.wnd_listener(hwnd).unwrap();
+        listener.perform_action(wm, &hwnd.into(), action);
+    }
+
     /// Implements `TestingWm::translate_action`.
     pub(super) fn translate_action(
         &self,
@@ -416,11 +550,7 @@ impl Screen {
             &hwnd.into(),
             &mut EnumAccel(|accel_table| {
                 if action.is_none() {
-                    action = accel_table
-                        .testing
-                        .iter()
-                        .find(|binding| (binding.source, binding.pattern) == (source, pattern))
-                        .map(|binding| binding.action);
+                    action = accel_table.find_action(source, pattern);
                 }
             }),
         );
@@ -481,6 +611,8 @@ fn layer_attrs_to_sr_layer_attrs(
         }),
         opacity: attrs.opacity,
         flags: attrs.flags,
+        corner_radii: attrs.corner_radii,
+        shadow: attrs.shadow,
     }
 }
 
@@ -533,10 +665,6 @@ struct SimulatedKeyEvent<'a> {
 
 impl iface::KeyEvent<AccelTable> for SimulatedKeyEvent<'_> {
     fn translate_accel(&self, accel_table: &AccelTable) -> Option<iface::ActionId> {
-        accel_table
-            .testing
-            .iter()
-            .find(|binding| (binding.source, binding.pattern) == (self.source, self.pattern))
-            .map(|binding| binding.action)
+        accel_table.find_action(self.source, self.pattern)
     }
 }
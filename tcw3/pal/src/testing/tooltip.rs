@@ -0,0 +1,36 @@
+//! The virtual tooltip.
+use cggeom::Box2;
+use log::debug;
+use std::cell::RefCell;
+
+use super::{wmapi::Tooltip, HWnd, Wm};
+
+mt_lazy_static! {
+    static <Wm> ref TOOLTIP: RefCell<Option<Tooltip>> => |_| RefCell::new(None);
+}
+
+pub fn reset(wm: Wm) {
+    TOOLTIP.get_with_wm(wm).replace(None);
+}
+
+pub fn show_tooltip(wm: Wm, hwnd: &HWnd, text: &str, anchor: Box2<f32>) {
+    debug!("show_tooltip({:?}, {:?}, {:?})", hwnd, text, anchor);
+
+    TOOLTIP.get_with_wm(wm).replace(Some(Tooltip {
+        hwnd: hwnd.clone(),
+        text: text.to_owned(),
+        anchor,
+    }));
+}
+
+pub fn hide_tooltip(wm: Wm, hwnd: &HWnd) {
+    let mut tooltip = TOOLTIP.get_with_wm(wm).borrow_mut();
+    if tooltip.as_ref().map(|t| &t.hwnd) == Some(hwnd) {
+        debug!("hide_tooltip({:?})", hwnd);
+        *tooltip = None;
+    }
+}
+
+pub fn active_tooltip(wm: Wm) -> Option<Tooltip> {
+    TOOLTIP.get_with_wm(wm).borrow().clone()
+}
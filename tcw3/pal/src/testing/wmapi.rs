@@ -1,7 +1,8 @@
+use cggeom::Box2;
 use cgmath::{Point2, Vector2};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use crate::{iface, HTextInputCtx, HWnd};
+use crate::{iface, HTextInputCtx, HWnd, Notification};
 
 /// Provides access to a virtual environment.
 ///
@@ -70,6 +71,46 @@ pub trait TestingWm: 'static {
     /// Trigger `WndListener::scroll_gesture`.
     fn raise_scroll_gesture(&self, hwnd: &HWnd, loc: Point2<f32>) -> Box<dyn ScrollGesture>;
 
+    /// Trigger `WndListener::drag_motion`.
+    fn raise_drag_motion(
+        &self,
+        hwnd: &HWnd,
+        loc: Point2<f32>,
+        types: iface::DragDataTypes,
+    ) -> iface::DragDataTypes;
+
+    /// Trigger `WndListener::drag_leave`.
+    fn raise_drag_leave(&self, hwnd: &HWnd);
+
+    /// Trigger `WndListener::drag_drop`.
+    fn raise_drag_drop(&self, hwnd: &HWnd, loc: Point2<f32>, data: &iface::DragData) -> bool;
+
+    /// Simulate dropping `data` at `loc`.
+    ///
+    /// This method calls `WndListener::drag_motion` and then either
+    /// `WndListener::drag_drop` (if the window accepts any of the offered data
+    /// types) or `WndListener::drag_leave` (otherwise). Returns `true` if the
+    /// data was accepted.
+    fn simulate_drop(&self, hwnd: &HWnd, loc: Point2<f32>, data: &iface::DragData) -> bool;
+
+    /// Get the accessibility tree of a window last set by
+    /// `Wm::update_access_tree`.
+    fn access_tree(&self, hwnd: &HWnd) -> Option<iface::AccessNode>;
+
+    /// Set the value returned by `Wm::is_access_active` for a window. When
+    /// the value changes to `true`, `WndListener::access_activated` is called.
+    ///
+    /// The initial value is `true`.
+    fn set_access_active(&self, hwnd: &HWnd, active: bool);
+
+    /// Trigger `WndListener::access_action`.
+    fn raise_access_action(
+        &self,
+        hwnd: &HWnd,
+        node: iface::AccessNodeId,
+        action: iface::AccessAction,
+    ) -> bool;
+
     /// Get the list of currently active text input contexts.
     fn active_text_input_ctxs(&self) -> Vec<HTextInputCtx>;
 
@@ -113,6 +154,114 @@ pub trait TestingWm: 'static {
     ///
     /// It doesn't simulate the pressing and releasing of modifier keys, though.
     fn simulate_key(&self, hwnd: &HWnd, source: &str, pattern: &str);
+
+    /// Get the plain text stored in the virtual clipboard.
+    fn clipboard_text(&self) -> Option<String>;
+
+    /// Replace the contents of the virtual clipboard. `None` empties the
+    /// clipboard.
+    ///
+    /// The virtual clipboard is emptied every time the backend is reset.
+    fn set_clipboard_text(&self, text: Option<&str>);
+
+    /// Change the virtual system appearance setting. The listener registered
+    /// by [`Wm::set_appearance_listener`] is called if the setting changes.
+    ///
+    /// [`Wm::set_appearance_listener`]: crate::iface::Wm::set_appearance_listener
+    ///
+    /// The setting is reset to [`Appearance::Light`] every time the backend
+    /// is reset. The listener is not unregistered by a reset.
+    ///
+    /// [`Appearance::Light`]: crate::iface::Appearance::Light
+    fn set_appearance(&self, appearance: iface::Appearance);
+
+    /// Switch [`Wm::now`] to a virtual clock, which only advances when
+    /// [`advance_clock`] is called.
+    ///
+    /// While the virtual clock is enabled, calls to
+    /// [`Wm::request_update_ready_wnd`] made by a window's
+    /// [`WndListener::update_ready`] handler are deferred until the next call
+    /// to `advance_clock`. This way, each frame of an animation can be
+    /// observed deterministically.
+    ///
+    /// The virtual clock is disabled every time the backend is reset.
+    ///
+    /// [`Wm::now`]: crate::iface::Wm::now
+    /// [`Wm::request_update_ready_wnd`]: crate::iface::Wm::request_update_ready_wnd
+    /// [`WndListener::update_ready`]: crate::iface::WndListener::update_ready
+    /// [`advance_clock`]: TestingWm::advance_clock
+    fn enable_virtual_clock(&self);
+
+    /// Advance the virtual clock by `duration` and process the deferred calls
+    /// to `WndListener::update_ready`.
+    ///
+    /// Panics if the virtual clock is not enabled.
+    fn advance_clock(&self, duration: Duration);
+
+    /// Get the state of the popup menu opened by [`Wm::popup_menu`]. Returns
+    /// `None` if there is no open popup menu.
+    ///
+    /// [`Wm::popup_menu`]: crate::iface::Wm::popup_menu
+    fn active_popup_menu(&self) -> Option<PopupMenu>;
+
+    /// Choose the item at `path` (see [`PopupMenu::item`]) in the open popup
+    /// menu and trigger `MenuListener::{perform_action, dismissed}`.
+    ///
+    /// Panics if there is no open popup menu or `path` does not refer to an
+    /// enabled action item.
+    fn pick_menu_item(&self, path: &[usize]);
+
+    /// Close the open popup menu without choosing an item and trigger
+    /// `MenuListener::dismissed`.
+    ///
+    /// Panics if there is no open popup menu.
+    fn dismiss_menu(&self);
+
+    /// Simulate a key stroke in the open popup menu. The highlighted item is
+    /// reported by [`PopupMenu::highlight`].
+    ///
+    /// Panics if there is no open popup menu.
+    fn simulate_menu_key(&self, key: MenuKey);
+
+    /// Get the menu bar of a window set by `WndAttrs::menu_bar`. The state
+    /// of each item is determined by calling `WndListener::validate_action`.
+    fn wnd_menu_bar(&self, hwnd: &HWnd) -> MenuBar;
+
+    /// Choose the item at `path` (see [`MenuBar::item`]) in a window's menu
+    /// bar and trigger `WndListener::perform_action`.
+    ///
+    /// Panics if `path` does not refer to an enabled action item.
+    fn pick_menu_bar_item(&self, hwnd: &HWnd, path: &[usize]);
+
+    /// Get the currently displayed tooltip.
+    fn active_tooltip(&self) -> Option<Tooltip>;
+
+    /// Get the drag-and-drop operation started by `Wm::start_drag`.
+    fn active_drag(&self) -> Option<ActiveDrag>;
+
+    /// Complete the drag-and-drop operation started by `Wm::start_drag`.
+    /// `accepted` is passed to the completion handler.
+    ///
+    /// Panics if there is no active drag-and-drop operation.
+    fn finish_drag(&self, accepted: bool);
+
+    /// Get the notifications displayed by `Wm::show_notification` and not
+    /// closed yet, in the order they were first displayed.
+    fn notifications(&self) -> Vec<ActiveNotification>;
+
+    /// Simulate a click on a notification (`action` is `None`) or one of its
+    /// action buttons and trigger `NotificationListener::activated`. The
+    /// notification stays open.
+    ///
+    /// Panics if the notification is not displayed or does not have the
+    /// specified action.
+    fn activate_notification(&self, id: iface::NotificationId, action: Option<&str>);
+
+    /// Close a notification as if the user dismissed it and trigger
+    /// `NotificationListener::closed`.
+    ///
+    /// Panics if the notification is not displayed.
+    fn dismiss_notification(&self, id: iface::NotificationId);
 }
 
 /// A snapshot of window attributes.
@@ -125,6 +274,9 @@ pub struct WndAttrs {
     pub caption: String,
     pub visible: bool,
     pub cursor_shape: iface::CursorShape,
+    pub menu_bar: Vec<iface::MenuItem>,
+    pub owner: Option<HWnd>,
+    pub modal: bool,
 }
 
 /// Provides an interface for simulating a mouse drag geature.
@@ -177,6 +329,166 @@ impl WndSnapshot {
     }
 }
 
+/// A snapshot of a popup menu.
+#[derive(Debug, Clone)]
+pub struct PopupMenu {
+    pub hwnd: HWnd,
+    pub anchor: Box2<f32>,
+    pub items: Vec<PopupMenuItem>,
+    /// The path to the highlighted item. For example, `[2, 0]` means the
+    /// submenu at index `2` is open, and the first item in the submenu is
+    /// highlighted. Empty if no items are highlighted.
+    pub highlight: Vec<usize>,
+}
+
+impl PopupMenu {
+    /// Get the item at `path`. A path is a list of indices, each of which
+    /// specifies an item in the top-level menu or the submenu referred to by
+    /// the preceding indices.
+    pub fn item(&self, path: &[usize]) -> Option<&PopupMenuItem> {
+        menu_item_at(&self.items, path)
+    }
+
+    /// Find the path to the item with the specified labels. For example,
+    /// `["Edit", "Copy"]` refers to the item labeled `Copy` in the submenu
+    /// labeled `Edit`.
+    pub fn path_of(&self, labels: &[&str]) -> Option<Vec<usize>> {
+        menu_path_of(&self.items, labels)
+    }
+}
+
+/// A snapshot of a window's menu bar.
+#[derive(Debug, Clone)]
+pub struct MenuBar {
+    /// The top-level items. Their states are the values returned by
+    /// `WndListener::validate_action` when the snapshot was taken.
+    pub items: Vec<PopupMenuItem>,
+}
+
+impl MenuBar {
+    /// Get the item at `path`. See [`PopupMenu::item`].
+    pub fn item(&self, path: &[usize]) -> Option<&PopupMenuItem> {
+        menu_item_at(&self.items, path)
+    }
+
+    /// Find the path to the item with the specified labels. See
+    /// [`PopupMenu::path_of`].
+    pub fn path_of(&self, labels: &[&str]) -> Option<Vec<usize>> {
+        menu_path_of(&self.items, labels)
+    }
+}
+
+fn menu_item_at<'a>(items: &'a [PopupMenuItem], path: &[usize]) -> Option<&'a PopupMenuItem> {
+    let (&last, parents) = path.split_last()?;
+    let mut items = items;
+    for &i in parents.iter() {
+        items = items.get(i)?.submenu_items()?;
+    }
+    items.get(last)
+}
+
+fn menu_path_of(items: &[PopupMenuItem], labels: &[&str]) -> Option<Vec<usize>> {
+    let mut path = Vec::with_capacity(labels.len());
+    let mut items = items;
+    for (k, &label) in labels.iter().enumerate() {
+        let i = items.iter().position(|item| item.label() == Some(label))?;
+        path.push(i);
+        if k + 1 < labels.len() {
+            items = items[i].submenu_items()?;
+        }
+    }
+    Some(path)
+}
+
+/// An item in [`PopupMenu`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PopupMenuItem {
+    Action {
+        label: String,
+        action: iface::ActionId,
+        shortcut: Option<String>,
+        /// The value returned by `MenuListener::validate_action` when the
+        /// menu was opened.
+        status: iface::ActionStatus,
+    },
+    Submenu {
+        label: String,
+        items: Vec<PopupMenuItem>,
+    },
+    Sep,
+}
+
+impl PopupMenuItem {
+    /// Get the label of the item. Returns `None` for a separator.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            PopupMenuItem::Action { label, .. } | PopupMenuItem::Submenu { label, .. } => {
+                Some(label)
+            }
+            PopupMenuItem::Sep => None,
+        }
+    }
+
+    /// Get the items in the submenu. Returns `None` if the item is not
+    /// `Submenu`.
+    pub fn submenu_items(&self) -> Option<&[PopupMenuItem]> {
+        match self {
+            PopupMenuItem::Submenu { items, .. } => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Get a flag indicating whether the item can be highlighted or chosen.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            PopupMenuItem::Action { status, .. } => {
+                status.contains(iface::ActionStatus::VALID | iface::ActionStatus::ENABLED)
+            }
+            PopupMenuItem::Submenu { .. } => true,
+            PopupMenuItem::Sep => false,
+        }
+    }
+}
+
+/// A key used for navigating through a popup menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MenuKey {
+    /// Highlight the previous enabled item.
+    Up,
+    /// Highlight the next enabled item.
+    Down,
+    /// Close the current submenu.
+    Left,
+    /// Open the highlighted submenu.
+    Right,
+    /// Choose the highlighted item or open the highlighted submenu.
+    Enter,
+    /// Close the current submenu or the popup menu.
+    Escape,
+}
+
+/// A snapshot of a tooltip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tooltip {
+    pub hwnd: HWnd,
+    pub text: String,
+    pub anchor: Box2<f32>,
+}
+
+/// A snapshot of a drag-and-drop operation started by `Wm::start_drag`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveDrag {
+    pub hwnd: HWnd,
+    pub data: iface::DragData,
+}
+
+/// A snapshot of a notification displayed by `Wm::show_notification`.
+#[derive(Debug, Clone)]
+pub struct ActiveNotification {
+    pub id: iface::NotificationId,
+    pub notification: Notification,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ActionBinding {
     pub source: &'static str,
@@ -134,6 +134,40 @@ impl iface::WndListener<native::Wm> for NativeWndListener {
 
         Box::new(NativeScrollListener(scroll_listener))
     }
+
+    fn drag_motion(
+        &self,
+        wm: native::Wm,
+        hwnd: &native::HWnd,
+        loc: Point2<f32>,
+        types: iface::DragDataTypes,
+    ) -> iface::DragDataTypes {
+        forward!(self.0, drag_motion, [wm: wm], [hwnd: hwnd], loc, types)
+    }
+
+    fn drag_leave(&self, wm: native::Wm, hwnd: &native::HWnd) {
+        forward!(self.0, drag_leave, [wm: wm], [hwnd: hwnd])
+    }
+
+    fn drag_drop(
+        &self,
+        wm: native::Wm,
+        hwnd: &native::HWnd,
+        loc: Point2<f32>,
+        data: &iface::DragData,
+    ) -> bool {
+        forward!(self.0, drag_drop, [wm: wm], [hwnd: hwnd], loc, data)
+    }
+
+    fn access_action(
+        &self,
+        wm: native::Wm,
+        hwnd: &native::HWnd,
+        node: iface::AccessNodeId,
+        action: iface::AccessAction,
+    ) -> bool {
+        forward!(self.0, access_action, [wm: wm], [hwnd: hwnd], node, action)
+    }
 }
 
 /// Wraps `InterpretEventCtx<native::AccelTable>` to create a `InterpretEventCtx<AccelTable>`.
@@ -201,3 +235,39 @@ impl iface::ScrollListener<native::Wm> for NativeScrollListener {
         forward!(self.0, cancel, [wm: wm], [hwnd: hwnd])
     }
 }
+
+/// Wraps `MenuListener<Wm>` to create a `MenuListener<native::Wm>`.
+pub struct NativeMenuListener(pub Box<dyn iface::MenuListener<Wm>>);
+
+impl iface::MenuListener<native::Wm> for NativeMenuListener {
+    fn validate_action(
+        &self,
+        wm: native::Wm,
+        hwnd: &native::HWnd,
+        action: iface::ActionId,
+    ) -> iface::ActionStatus {
+        forward!(self.0, validate_action, [wm: wm], [hwnd: hwnd], action)
+    }
+
+    fn perform_action(&self, wm: native::Wm, hwnd: &native::HWnd, action: iface::ActionId) {
+        forward!(self.0, perform_action, [wm: wm], [hwnd: hwnd], action)
+    }
+
+    fn dismissed(&self, wm: native::Wm, hwnd: &native::HWnd) {
+        forward!(self.0, dismissed, [wm: wm], [hwnd: hwnd])
+    }
+}
+
+/// Wraps `NotificationListener<Wm>` to create a
+/// `NotificationListener<native::Wm>`.
+pub struct NativeNotificationListener(pub Box<dyn iface::NotificationListener<Wm>>);
+
+impl iface::NotificationListener<native::Wm> for NativeNotificationListener {
+    fn activated(&self, wm: native::Wm, id: iface::NotificationId, action: Option<&str>) {
+        forward!(self.0, activated, [wm: wm], id, action)
+    }
+
+    fn closed(&self, wm: native::Wm, id: iface::NotificationId) {
+        forward!(self.0, closed, [wm: wm], id)
+    }
+}
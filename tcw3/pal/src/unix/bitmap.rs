@@ -1,6 +1,6 @@
 use cairo::{Context, ImageSurface};
-use cgmath::{Matrix3, Point2};
-use std::{cell::UnsafeCell, sync::Arc};
+use cgmath::{Matrix3, Point2, SquareMatrix};
+use std::{cell::UnsafeCell, rc::Rc, sync::Arc};
 
 use super::super::{iface, swrast};
 use super::text::TextLayout;
@@ -54,13 +54,94 @@ pub struct BitmapBuilder {
 #[derive(Debug)]
 struct StateStackEntry {
     state: State,
+    /// The opacity of the group if this entry was created by `begin_group`.
+    group_opacity: Option<f32>,
     next: Option<Box<StateStackEntry>>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct State {
-    fill_col: [f64; 4],
-    stroke_col: [f64; 4],
+    fill_brush: Brush,
+    stroke_brush: Brush,
+}
+
+#[derive(Debug, Clone)]
+enum Brush {
+    Solid([f64; 4]),
+    Gradient(Rc<GradientBrush>),
+}
+
+#[derive(Debug)]
+struct GradientBrush {
+    kind: iface::GradientKind,
+    xform: Matrix3<f32>,
+    stops: Vec<iface::GradientStop>,
+}
+
+impl Brush {
+    fn from_gradient(gradient: &iface::Gradient<'_>) -> Self {
+        assert!(
+            !gradient.stops.is_empty(),
+            "the gradient has no color stops"
+        );
+
+        Brush::Gradient(Rc::new(GradientBrush {
+            kind: gradient.kind,
+            xform: gradient.xform,
+            stops: gradient.stops.to_vec(),
+        }))
+    }
+
+    /// Make `self` the current source of `cairo_ctx`.
+    fn set_as_source(&self, cairo_ctx: &Context) {
+        let gradient = match self {
+            Brush::Solid(col) => {
+                cairo_ctx.set_source_rgba(col[0], col[1], col[2], col[3]);
+                return;
+            }
+            Brush::Gradient(gradient) => gradient,
+        };
+
+        let m = if let Some(m) = gradient.xform.invert() {
+            (m / m.z.z).cast::<f64>().unwrap()
+        } else {
+            // The gradient space is degenerate. Use the color of the last
+            // color stop.
+            let col = gradient.stops.last().unwrap().color;
+            cairo_ctx.set_source_rgba(col.r as f64, col.g as f64, col.b as f64, col.a as f64);
+            return;
+        };
+
+        let linear;
+        let radial;
+        let pattern: &cairo::Gradient = match gradient.kind {
+            iface::GradientKind::Linear => {
+                linear = cairo::LinearGradient::new(0.0, 0.0, 1.0, 0.0);
+                &linear
+            }
+            iface::GradientKind::Radial => {
+                radial = cairo::RadialGradient::new(0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+                &radial
+            }
+        };
+
+        for stop in gradient.stops.iter() {
+            let col = stop.color;
+            pattern.add_color_stop_rgba(
+                stop.offset as f64,
+                col.r as f64,
+                col.g as f64,
+                col.b as f64,
+                col.a as f64,
+            );
+        }
+
+        // The pattern matrix maps the user space to the pattern space
+        pattern.set_matrix(cairo::Matrix::new(m.x.x, m.x.y, m.y.x, m.y.y, m.z.x, m.z.y));
+        pattern.set_extend(cairo::Extend::Pad);
+
+        cairo_ctx.set_source(pattern);
+    }
 }
 
 impl iface::BitmapBuilderNew for BitmapBuilder {
@@ -118,13 +199,36 @@ impl iface::BitmapBuilderNew for BitmapBuilder {
 
             state_top: Box::new(StateStackEntry {
                 state: State {
-                    fill_col: [1.0; 4],
-                    stroke_col: [1.0; 4],
+                    fill_brush: Brush::Solid([1.0; 4]),
+                    stroke_brush: Brush::Solid([1.0; 4]),
                 },
+                group_opacity: None,
                 next: None,
             }),
         }
     }
+
+    fn with_rgba8_premul(size: [u32; 2], data: &[u8]) -> Self {
+        let num_pixels = size[0] as usize * size[1] as usize;
+        assert_eq!(data.len(), num_pixels * 4, "data has a wrong length");
+
+        let this = Self::new(size);
+
+        this.cairo_surface.flush();
+        {
+            // `stride == size[0] * 4`, so the rows are tightly packed as well
+            let dst = unsafe { &mut *this.data.get() };
+
+            // `ARgb32` stores each pixel as a native-endian 32-bit integer
+            for (dst, src) in dst.chunks_exact_mut(4).zip(data.chunks_exact(4)) {
+                let pixel = u32::from_be_bytes([src[3], src[0], src[1], src[2]]);
+                dst.copy_from_slice(&pixel.to_ne_bytes());
+            }
+        }
+        this.cairo_surface.mark_dirty();
+
+        this
+    }
 }
 
 impl iface::BitmapBuilder for BitmapBuilder {
@@ -150,18 +254,10 @@ impl iface::BitmapBuilder for BitmapBuilder {
 impl iface::Canvas for BitmapBuilder {
     fn save(&mut self) {
         self.cairo_ctx.save();
-
-        let new_top = Box::new(StateStackEntry {
-            state: self.state_top.state.clone(),
-            next: None,
-        });
-        let next = std::mem::replace(&mut self.state_top, new_top);
-        self.state_top.next = Some(next);
+        self.push_state();
     }
     fn restore(&mut self) {
-        let next = self.state_top.next.take().expect("stack is emtpy");
-        self.state_top = next;
-
+        self.pop_state();
         self.cairo_ctx.restore();
     }
     fn begin_path(&mut self) {
@@ -199,27 +295,44 @@ impl iface::Canvas for BitmapBuilder {
             .curve_to(cp1.x, cp1.y, cp2.x, cp2.y, p2.x, p2.y);
     }
     fn fill(&mut self) {
-        let col = self.state_top.state.fill_col;
-        self.cairo_ctx
-            .set_source_rgba(col[0], col[1], col[2], col[3]);
-
+        self.state_top
+            .state
+            .fill_brush
+            .set_as_source(&self.cairo_ctx);
+        self.cairo_ctx.fill();
+    }
+    fn fill_even_odd(&mut self) {
+        self.state_top
+            .state
+            .fill_brush
+            .set_as_source(&self.cairo_ctx);
+        self.cairo_ctx.set_fill_rule(cairo::FillRule::EvenOdd);
         self.cairo_ctx.fill();
+        self.cairo_ctx.set_fill_rule(cairo::FillRule::Winding);
     }
     fn stroke(&mut self) {
-        let col = self.state_top.state.stroke_col;
-        self.cairo_ctx
-            .set_source_rgba(col[0], col[1], col[2], col[3]);
-
+        self.state_top
+            .state
+            .stroke_brush
+            .set_as_source(&self.cairo_ctx);
         self.cairo_ctx.stroke();
     }
     fn clip(&mut self) {
         self.cairo_ctx.clip();
     }
     fn set_fill_rgb(&mut self, rgb: iface::RGBAF32) {
-        self.state_top.state.fill_col = [rgb.r as f64, rgb.g as f64, rgb.b as f64, rgb.a as f64];
+        self.state_top.state.fill_brush =
+            Brush::Solid([rgb.r as f64, rgb.g as f64, rgb.b as f64, rgb.a as f64]);
+    }
+    fn set_fill_gradient(&mut self, gradient: &iface::Gradient<'_>) {
+        self.state_top.state.fill_brush = Brush::from_gradient(gradient);
     }
     fn set_stroke_rgb(&mut self, rgb: iface::RGBAF32) {
-        self.state_top.state.stroke_col = [rgb.r as f64, rgb.g as f64, rgb.b as f64, rgb.a as f64];
+        self.state_top.state.stroke_brush =
+            Brush::Solid([rgb.r as f64, rgb.g as f64, rgb.b as f64, rgb.a as f64]);
+    }
+    fn set_stroke_gradient(&mut self, gradient: &iface::Gradient<'_>) {
+        self.state_top.state.stroke_brush = Brush::from_gradient(gradient);
     }
     fn set_line_cap(&mut self, cap: iface::LineCap) {
         use cairo::LineCap;
@@ -252,6 +365,42 @@ impl iface::Canvas for BitmapBuilder {
         self.cairo_ctx
             .transform(cairo::Matrix::new(m.x.x, m.x.y, m.y.x, m.y.y, m.z.x, m.z.y));
     }
+    fn begin_group(&mut self, opacity: f32) {
+        // `push_group` saves the Cairo graphics state
+        self.cairo_ctx.push_group();
+        self.push_state();
+        self.state_top.group_opacity = Some(opacity);
+    }
+    fn end_group(&mut self) {
+        let opacity = self
+            .state_top
+            .group_opacity
+            .expect("the current state was not pushed by `begin_group`");
+        self.pop_state();
+
+        // `pop_group_to_source` restores the Cairo graphics state
+        self.cairo_ctx.pop_group_to_source();
+        self.cairo_ctx.paint_with_alpha(opacity as f64);
+    }
+}
+
+impl BitmapBuilder {
+    /// Push a copy of the current `State` onto the state stack.
+    fn push_state(&mut self) {
+        let new_top = Box::new(StateStackEntry {
+            state: self.state_top.state.clone(),
+            group_opacity: None,
+            next: None,
+        });
+        let next = std::mem::replace(&mut self.state_top, new_top);
+        self.state_top.next = Some(next);
+    }
+
+    /// Pop a `State` from the state stack.
+    fn pop_state(&mut self) {
+        let next = self.state_top.next.take().expect("stack is emtpy");
+        self.state_top = next;
+    }
 }
 
 impl iface::CanvasText<TextLayout> for BitmapBuilder {
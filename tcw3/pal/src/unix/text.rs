@@ -31,38 +31,36 @@ unsafe impl Send for ImmutableFontDesc {}
 unsafe impl Sync for ImmutableFontDesc {}
 
 impl iface::CharStyle for CharStyle {
-    fn new(mut attrs: CharStyleAttrs) -> Self {
-        let mut font_desc = FontDescription::new();
-
-        if attrs.template.is_none() {
-            // Set default values
-            use iface::SysFontType;
+    fn new(attrs: CharStyleAttrs) -> Self {
+        use iface::SysFontType;
 
-            if attrs.sys.is_none() {
-                attrs.sys = Some(iface::SysFontType::Normal);
-            }
+        let mut font_desc = FontDescription::new();
 
-            if attrs.size.is_none() {
-                attrs.size = Some(match attrs.sys.unwrap() {
-                    SysFontType::Normal
-                    | SysFontType::Emph
-                    | SysFontType::User
-                    | SysFontType::UserMonospace => 12.0,
-                    SysFontType::Small | SysFontType::SmallEmph => 10.0,
-                });
-            }
+        // Use the default values for the fields not inherited from `template`
+        let (sys, size) = if attrs.template.is_none() {
+            let sys = attrs.sys.unwrap_or(SysFontType::Normal);
+            let size = attrs.size.unwrap_or(match sys {
+                SysFontType::Normal
+                | SysFontType::Emph
+                | SysFontType::User
+                | SysFontType::UserMonospace => 12.0,
+                SysFontType::Small | SysFontType::SmallEmph => 10.0,
+            });
+            (Some(sys), Some(size))
+        } else {
+            (attrs.sys, attrs.size)
+        };
 
-            match attrs.sys.unwrap() {
+        if let Some(sys) = sys {
+            font_desc.set_weight(match sys {
                 SysFontType::Normal
                 | SysFontType::Small
                 | SysFontType::User
-                | SysFontType::UserMonospace => {}
-                SysFontType::Emph | SysFontType::SmallEmph => {
-                    font_desc.set_weight(pango::Weight::Bold);
-                }
-            }
+                | SysFontType::UserMonospace => pango::Weight::Normal,
+                SysFontType::Emph | SysFontType::SmallEmph => pango::Weight::Bold,
+            });
 
-            match attrs.sys.unwrap() {
+            match sys {
                 SysFontType::Normal
                 | SysFontType::Small
                 | SysFontType::User
@@ -77,27 +75,43 @@ impl iface::CharStyle for CharStyle {
             }
         }
 
-        if let Some(size) = attrs.size {
+        if let Some(bold) = attrs.bold {
+            font_desc.set_weight(if bold {
+                pango::Weight::Bold
+            } else {
+                pango::Weight::Normal
+            });
+        }
+
+        if let Some(italic) = attrs.italic {
+            font_desc.set_style(if italic {
+                pango::Style::Italic
+            } else {
+                pango::Style::Normal
+            });
+        }
+
+        if let Some(size) = size {
             // pangocairo's default DPI is 96 and we don't want to change it, so
             // apply a scaling factor here
             const FACTOR: f32 = 72.0 / 96.0;
             font_desc.set_size((size * (pango::SCALE as f32 * FACTOR)) as i32);
         }
 
-        let mut color = attrs.color.unwrap_or(None).map(rgbaf32_to_rgba16);
-
-        let mut decor = attrs.decor.unwrap_or(iface::TextDecorFlags::empty());
+        let mut color = attrs.color.map(|c| c.map(rgbaf32_to_rgba16));
+        let mut decor = attrs.decor;
 
+        // Inherit the unspecified fields from `template`
         if let Some(tmpl) = attrs.template {
             font_desc.merge(Some(&tmpl.pango_font_desc.inner), false);
-            color = tmpl.color;
-            decor = tmpl.decor;
+            color = color.or(Some(tmpl.color));
+            decor = decor.or(Some(tmpl.decor));
         }
 
         Self {
             pango_font_desc: ImmutableFontDesc { inner: font_desc },
-            color,
-            decor,
+            color: color.unwrap_or(None),
+            decor: decor.unwrap_or(iface::TextDecorFlags::empty()),
         }
     }
 
@@ -140,12 +154,15 @@ impl TextLayout {
     pub(super) fn lock_layout(&self) -> impl std::ops::Deref<Target = Layout> + '_ {
         self.pango_layout.inner.lock().unwrap()
     }
-}
-
-impl iface::TextLayout for TextLayout {
-    type CharStyle = CharStyle;
 
-    fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self {
+    /// Construct a `TextLayout`. `styles` must be a partition of `text`.
+    fn with_styles(
+        text: &str,
+        style: &CharStyle,
+        styles: &[(Range<usize>, CharStyle)],
+        inline_boxes: &[(usize, iface::InlineBox)],
+        width: Option<f32>,
+    ) -> Self {
         let font_map = pangocairo::FontMap::get_default().expect("failed to get a Pango font map");
 
         let ctx = font_map
@@ -166,8 +183,7 @@ impl iface::TextLayout for TextLayout {
         }
 
         layout.set_text(text);
-
-        // TODO: `decor`
+        layout.set_attributes_raw(&new_attr_list(styles, inline_boxes));
 
         let num_lines = layout.get_line_count() as usize;
         log::trace!("The text {:?} generated {:?} line(s)", text, num_lines);
@@ -205,6 +221,28 @@ impl iface::TextLayout for TextLayout {
             line_metrics,
         }
     }
+}
+
+impl iface::TextLayout for TextLayout {
+    type CharStyle = CharStyle;
+
+    fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self {
+        Self::with_styles(text, style, &[(0..text.len(), style.clone())], &[], width)
+    }
+
+    fn from_attr_text(
+        text: &iface::AttrText<Self::CharStyle>,
+        style: &Self::CharStyle,
+        width: Option<f32>,
+    ) -> Self {
+        Self::with_styles(
+            text.text(),
+            style,
+            &text.resolve_char_styles(style),
+            text.inline_boxes(),
+            width,
+        )
+    }
 
     // TODO: see if `update_layout` messes up the extents
 
@@ -524,11 +562,113 @@ fn point_to_pango_xy(x: Point2<f32>) -> [i32; 2] {
     [(x.x * scale) as i32, (x.y * scale) as i32]
 }
 
+/// An owned pointer of `PangoAttrList`.
+#[derive(Debug)]
+struct UniqueAttrList(*mut pango_sys::PangoAttrList);
+
+impl Drop for UniqueAttrList {
+    fn drop(&mut self) {
+        unsafe { pango_sys::pango_attr_list_unref(self.0) };
+    }
+}
+
+impl UniqueAttrList {
+    fn new() -> Self {
+        Self(unsafe { pango_sys::pango_attr_list_new() })
+    }
+
+    /// Insert a newly-created attribute `attr` applied to the UTF-8 offset
+    /// range `range`, taking the ownership of `attr`.
+    unsafe fn insert(&mut self, attr: *mut pango_sys::PangoAttribute, range: Range<usize>) {
+        (*attr).start_index = range.start.try_into().expect("integer overflow");
+        (*attr).end_index = range.end.try_into().expect("integer overflow");
+        pango_sys::pango_attr_list_insert(self.0, attr);
+    }
+}
+
+extern "C" {
+    // Not exposed by `pango-sys` unless the `v1_38` feature is enabled
+    fn pango_attr_foreground_alpha_new(alpha: u16) -> *mut pango_sys::PangoAttribute;
+}
+
+/// Create Pango attributes representing the character styles and inline
+/// boxes.
+fn new_attr_list(
+    styles: &[(Range<usize>, CharStyle)],
+    inline_boxes: &[(usize, iface::InlineBox)],
+) -> UniqueAttrList {
+    use glib::translate::ToGlibPtr;
+
+    let mut list = UniqueAttrList::new();
+
+    for (range, style) in styles.iter() {
+        let font_desc: *const pango_sys::PangoFontDescription =
+            style.pango_font_desc.inner.to_glib_none().0;
+
+        unsafe {
+            list.insert(
+                pango_sys::pango_attr_font_desc_new(font_desc),
+                range.clone(),
+            );
+
+            if let Some(c) = style.color {
+                list.insert(
+                    pango_sys::pango_attr_foreground_new(c.r, c.g, c.b),
+                    range.clone(),
+                );
+                list.insert(pango_attr_foreground_alpha_new(c.a), range.clone());
+            }
+
+            if style.decor.contains(iface::TextDecorFlags::UNDERLINE) {
+                list.insert(
+                    pango_sys::pango_attr_underline_new(pango_sys::PANGO_UNDERLINE_SINGLE),
+                    range.clone(),
+                );
+            }
+
+            if style.decor.contains(iface::TextDecorFlags::STRIKETHROUGH) {
+                list.insert(pango_sys::pango_attr_strikethrough_new(1), range.clone());
+            }
+
+            // TODO: `OVERLINE` (requires Pango 1.46)
+        }
+    }
+
+    // An inline box is represented by a shaped character. The glyph is
+    // replaced with an empty rectangle of the specified logical extents.
+    let scale = pango::SCALE as f32;
+    for &(i, ref inline_box) in inline_boxes.iter() {
+        let rect = pango_sys::PangoRectangle {
+            x: 0,
+            y: (-inline_box.ascent * scale) as i32,
+            width: (inline_box.width * scale) as i32,
+            height: ((inline_box.ascent + inline_box.descent) * scale) as i32,
+        };
+        unsafe {
+            list.insert(
+                pango_sys::pango_attr_shape_new(&rect, &rect),
+                i..i + iface::InlineBox::CHAR.len_utf8(),
+            );
+        }
+    }
+
+    list
+}
+
 trait LayoutExt {
     fn get_log_attrs_readonly(&self) -> &[LogAttrFlags];
+
+    /// Set the attribute list of the layout.
+    fn set_attributes_raw(&self, list: &UniqueAttrList);
 }
 
 impl LayoutExt for Layout {
+    fn set_attributes_raw(&self, list: &UniqueAttrList) {
+        use glib::translate::ToGlibPtr;
+        // `pango_layout_set_attributes` adds a reference to `list`
+        unsafe { pango_sys::pango_layout_set_attributes(self.to_glib_none().0, list.0) };
+    }
+
     fn get_log_attrs_readonly(&self) -> &[LogAttrFlags] {
         use glib::translate::ToGlibPtr;
         unsafe {
@@ -2,18 +2,22 @@
 //!
 //! Most of these definitions are implementation details and thus hidden. They
 //! still need to be `pub` because they are instantiated by `accel_table!`.
+use std::borrow::Cow;
 use winapi::um::winuser;
 
-use crate::{actions, iface};
+use crate::{
+    accel::{Key, KeyBinding, ModFlags},
+    actions, iface,
+};
 
 #[derive(Debug)]
 pub struct AccelTable {
     #[doc(hidden)]
-    pub key: &'static [ActionKeyBinding],
+    pub key: Cow<'static, [ActionKeyBinding]>,
 }
 
 #[doc(hidden)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ActionKeyBinding {
     pub action: iface::ActionId,
     pub flags: u8,
@@ -54,6 +58,117 @@ impl AccelTable {
     }
 }
 
+impl iface::AccelTableNew for AccelTable {
+    fn from_key_bindings(bindings: &[KeyBinding]) -> Self {
+        let key = bindings
+            .iter()
+            .filter_map(|binding| {
+                let pat = &binding.pattern;
+
+                // See `tcw3_pal_macro::accel::windows`
+                let key = if let Some(key) = vk_for_key(pat.key) {
+                    key
+                } else {
+                    log::warn!("Unsupported key: {:?}", pat.key);
+                    return None;
+                };
+
+                if pat.mod_flags.contains(ModFlags::SUPER) {
+                    log::warn!("The `Super` modifier is not supported on Windows");
+                    return None;
+                }
+
+                let mut flags = 0;
+                if pat.mod_flags.contains(ModFlags::SHIFT) {
+                    flags |= Self::MOD_SHIFT;
+                }
+                if pat.mod_flags.contains(ModFlags::CONTROL) {
+                    flags |= Self::MOD_CONTROL;
+                }
+                if pat.mod_flags.contains(ModFlags::ALT) {
+                    flags |= Self::MOD_MENU;
+                }
+
+                Some(ActionKeyBinding {
+                    action: binding.action,
+                    flags,
+                    key,
+                })
+            })
+            .collect();
+
+        Self {
+            key: Cow::Owned(key),
+        }
+    }
+}
+
+/// Get the virtual key code for `key`.
+fn vk_for_key(key: Key) -> Option<u16> {
+    // `WinUser.h`
+    let vk = match key {
+        Key::Char(c) if c.is_ascii_lowercase() || c.is_ascii_digit() || c == ' ' => {
+            c.to_ascii_uppercase() as i32
+        }
+        Key::Char(_) => return None,
+        Key::Escape => winuser::VK_ESCAPE,
+        Key::Backspace => winuser::VK_BACK,
+        Key::Return => winuser::VK_RETURN,
+        Key::Tab => winuser::VK_TAB,
+        Key::Delete => winuser::VK_DELETE,
+        Key::Left => winuser::VK_LEFT,
+        Key::Up => winuser::VK_UP,
+        Key::Right => winuser::VK_RIGHT,
+        Key::Down => winuser::VK_DOWN,
+        Key::PageUp => winuser::VK_PRIOR,
+        Key::PageDown => winuser::VK_NEXT,
+        Key::End => winuser::VK_END,
+        Key::Home => winuser::VK_HOME,
+        Key::Insert => winuser::VK_INSERT,
+        Key::Numpad0 => winuser::VK_NUMPAD0,
+        Key::Numpad1 => winuser::VK_NUMPAD1,
+        Key::Numpad2 => winuser::VK_NUMPAD2,
+        Key::Numpad3 => winuser::VK_NUMPAD3,
+        Key::Numpad4 => winuser::VK_NUMPAD4,
+        Key::Numpad5 => winuser::VK_NUMPAD5,
+        Key::Numpad6 => winuser::VK_NUMPAD6,
+        Key::Numpad7 => winuser::VK_NUMPAD7,
+        Key::Numpad8 => winuser::VK_NUMPAD8,
+        Key::Numpad9 => winuser::VK_NUMPAD9,
+        Key::NumpadMultiply => winuser::VK_MULTIPLY,
+        Key::NumpadAdd => winuser::VK_ADD,
+        Key::NumpadSeparator => winuser::VK_SEPARATOR,
+        Key::NumpadSubtract => winuser::VK_SUBTRACT,
+        Key::NumpadDecimal => winuser::VK_DECIMAL,
+        Key::NumpadDivide => winuser::VK_DIVIDE,
+        Key::F1 => winuser::VK_F1,
+        Key::F2 => winuser::VK_F2,
+        Key::F3 => winuser::VK_F3,
+        Key::F4 => winuser::VK_F4,
+        Key::F5 => winuser::VK_F5,
+        Key::F6 => winuser::VK_F6,
+        Key::F7 => winuser::VK_F7,
+        Key::F8 => winuser::VK_F8,
+        Key::F9 => winuser::VK_F9,
+        Key::F10 => winuser::VK_F10,
+        Key::F11 => winuser::VK_F11,
+        Key::F12 => winuser::VK_F12,
+        Key::F13 => winuser::VK_F13,
+        Key::F14 => winuser::VK_F14,
+        Key::F15 => winuser::VK_F15,
+        Key::F16 => winuser::VK_F16,
+        Key::F17 => winuser::VK_F17,
+        Key::F18 => winuser::VK_F18,
+        Key::F19 => winuser::VK_F19,
+        Key::F20 => winuser::VK_F20,
+        Key::F21 => winuser::VK_F21,
+        Key::F22 => winuser::VK_F22,
+        Key::F23 => winuser::VK_F23,
+        Key::F24 => winuser::VK_F24,
+    };
+    Some(vk as u16)
+}
+
 pub(super) static TEXT_INPUT_ACCEL: AccelTable = tcw3_pal_macro::accel_table_inner!(
     crate,
     "windows",
@@ -8,7 +8,8 @@ use winapi::{
         gdiplusenums::GraphicsState,
         gdiplusflat as gp,
         gdiplusgpstubs::{
-            GpBitmap, GpGraphics, GpMatrix, GpPath, GpPen, GpRect, GpSolidFill, GpStatus,
+            GpBitmap, GpBrush, GpGraphics, GpMatrix, GpPath, GpPen, GpPointF, GpRect, GpRegion,
+            GpSolidFill, GpStatus,
         },
         gdiplusimaging,
         gdiplusimaging::BitmapData,
@@ -301,6 +302,34 @@ impl Drop for UniqueGpSolidFill {
     }
 }
 
+/// An owned pointer of `GpBrush`.
+#[derive(Debug)]
+struct UniqueGpBrush {
+    gp_brush: *mut GpBrush,
+}
+
+impl Drop for UniqueGpBrush {
+    fn drop(&mut self) {
+        unsafe {
+            assert_gp_ok(gp::GdipDeleteBrush(self.gp_brush));
+        }
+    }
+}
+
+/// An owned pointer of `GpRegion`.
+#[derive(Debug)]
+struct UniqueGpRegion {
+    gp_region: *mut GpRegion,
+}
+
+impl Drop for UniqueGpRegion {
+    fn drop(&mut self) {
+        unsafe {
+            assert_gp_ok(gp::GdipDeleteRegion(self.gp_region));
+        }
+    }
+}
+
 /// An owned pointer of `GpPen`.
 #[derive(Debug)]
 struct UniqueGpPen {
@@ -337,6 +366,143 @@ fn rgbaf32_to_argb(c: iface::RGBAF32) -> ARGB {
     gdipluscolor::Color::MakeARGB(c.a, c.r, c.g, c.b)
 }
 
+fn new_gp_matrix(m: Matrix3<f32>) -> UniqueGpMatrix {
+    let m = m / m.z.z;
+    UniqueGpMatrix {
+        gp_mat: unsafe {
+            create_gp_obj_with(|out| {
+                gp::GdipCreateMatrix2(m.x.x, m.x.y, m.y.x, m.y.y, m.z.x, m.z.y, out)
+            })
+        },
+    }
+}
+
+/// A gradient brush.
+#[derive(Debug)]
+struct GradientBrush {
+    brush: UniqueGpBrush,
+    /// GDI+'s path gradient brushes do not paint outside the boundary path.
+    /// If this is `Some(_)`, the area to be filled should be filled with this
+    /// color first to emulate the padding behavior.
+    outer_color: Option<ARGB>,
+}
+
+impl GradientBrush {
+    fn new(gradient: &iface::Gradient<'_>) -> Self {
+        let stops = gradient.stops;
+        let first = stops.first().expect("the gradient has no color stops");
+        let last = stops.last().unwrap();
+
+        let mat = new_gp_matrix(gradient.xform);
+        let mut colors: Vec<ARGB> = Vec::with_capacity(stops.len() + 2);
+        let mut positions: Vec<REAL> = Vec::with_capacity(stops.len() + 2);
+        let clamp = |x: f32| x.max(0.0).min(1.0);
+
+        match gradient.kind {
+            iface::GradientKind::Linear => {
+                // Line gradient brushes repeat the gradient outside the
+                // specified line segment. Emulate the padding behavior by
+                // stretching the line segment and squeezing the color stops
+                // into the middle of it.
+                const PAD: REAL = 1024.0;
+                let squeeze = |x: f32| (clamp(x) + PAD) / (1.0 + PAD * 2.0);
+
+                colors.push(rgbaf32_to_argb(first.color));
+                positions.push(0.0);
+                for stop in stops.iter() {
+                    colors.push(rgbaf32_to_argb(stop.color));
+                    positions.push(squeeze(stop.offset));
+                }
+                colors.push(rgbaf32_to_argb(last.color));
+                positions.push(1.0);
+
+                unsafe {
+                    let gp_brush = create_gp_obj_with(|out| {
+                        gp::GdipCreateLineBrush(
+                            &GpPointF { X: -PAD, Y: 0.0 },
+                            &GpPointF {
+                                X: 1.0 + PAD,
+                                Y: 0.0,
+                            },
+                            colors[0],
+                            colors[colors.len() - 1],
+                            gdiplusenums::WrapModeTile,
+                            out,
+                        )
+                    });
+                    let brush = UniqueGpBrush {
+                        gp_brush: gp_brush as _,
+                    };
+
+                    assert_gp_ok(gp::GdipSetLinePresetBlend(
+                        gp_brush,
+                        colors.as_ptr(),
+                        positions.as_ptr(),
+                        colors.len() as INT,
+                    ));
+                    assert_gp_ok(gp::GdipSetLineTransform(gp_brush, mat.gp_mat));
+
+                    Self {
+                        brush,
+                        outer_color: None,
+                    }
+                }
+            }
+            iface::GradientKind::Radial => {
+                // The positions of path gradient brushes are measured from
+                // the boundary path toward the center
+                colors.push(rgbaf32_to_argb(last.color));
+                positions.push(0.0);
+                for stop in stops.iter().rev() {
+                    colors.push(rgbaf32_to_argb(stop.color));
+                    positions.push(1.0 - clamp(stop.offset));
+                }
+                colors.push(rgbaf32_to_argb(first.color));
+                positions.push(1.0);
+
+                unsafe {
+                    let path = UniqueGpPath {
+                        gp_path: create_gp_obj_with(|out| {
+                            gp::GdipCreatePath(gdiplusenums::FillModeWinding, out)
+                        }),
+                    };
+                    assert_gp_ok(gp::GdipAddPathEllipse(path.gp_path, -1.0, -1.0, 2.0, 2.0));
+
+                    let gp_brush = create_gp_obj_with(|out| {
+                        gp::GdipCreatePathGradientFromPath(path.gp_path, out)
+                    });
+                    let brush = UniqueGpBrush {
+                        gp_brush: gp_brush as _,
+                    };
+
+                    assert_gp_ok(gp::GdipSetPathGradientPresetBlend(
+                        gp_brush,
+                        colors.as_ptr(),
+                        positions.as_ptr(),
+                        colors.len() as INT,
+                    ));
+                    assert_gp_ok(gp::GdipSetPathGradientTransform(gp_brush, mat.gp_mat));
+
+                    Self {
+                        brush,
+                        outer_color: Some(colors[0]),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The previous drawing target saved by `begin_group`.
+#[derive(Debug)]
+struct Group {
+    // Drop `gr` first
+    gr: UniqueGpGraphics,
+    bmp: BitmapInner,
+    state_stack_len: usize,
+    opacity: f32,
+}
+
 /// Implements `crate::iface::BitmapBuilder`.
 #[derive(Debug)]
 pub struct BitmapBuilder {
@@ -345,9 +511,12 @@ pub struct BitmapBuilder {
     path: UniqueGpPath,
     brush: UniqueGpSolidFill,
     brush2: UniqueGpSolidFill,
+    /// The current fill brush if it's a gradient. Supersedes `brush`.
+    gradient_brush: Option<GradientBrush>,
     pen: UniqueGpPen,
     mat: UniqueGpMatrix,
     state_stack: ArrayVec<[GraphicsState; 16]>,
+    group_stack: Vec<Group>,
     cur_pt: [REAL; 2],
 }
 
@@ -356,15 +525,9 @@ impl iface::BitmapBuilderNew for BitmapBuilder {
         ensure_gdip_inited();
 
         let bmp = BitmapInner::new(size);
-
-        let gr = UniqueGpGraphics {
-            gp_gr: unsafe {
-                create_gp_obj_with(|out| gp::GdipGetImageGraphicsContext(bmp.gp_bmp as _, out))
-            },
-        };
+        let gr = new_bmp_graphics(&bmp);
 
         unsafe {
-            gp::GdipSetSmoothingMode(gr.gp_gr, gdiplusenums::SmoothingModeAntiAlias);
             gp::GdipTranslateWorldTransform(gr.gp_gr, -0.5, -0.5, gdiplusenums::MatrixOrderPrepend);
         }
 
@@ -403,18 +566,65 @@ impl iface::BitmapBuilderNew for BitmapBuilder {
             path,
             brush,
             brush2,
+            gradient_brush: None,
             pen,
             mat,
             state_stack: ArrayVec::new(),
+            group_stack: Vec::new(),
             cur_pt: [0.0; 2],
         }
     }
+
+    fn with_rgba8_premul(size: [u32; 2], data: &[u8]) -> Self {
+        let row_len = size[0] as usize * 4;
+        assert_eq!(
+            data.len(),
+            row_len * size[1] as usize,
+            "data has a wrong length"
+        );
+
+        let this = Self::new(size);
+
+        {
+            let bmp_data = this.bmp.write();
+            let stride = bmp_data.stride() as usize;
+
+            for (y, src) in data.chunks_exact(row_len.max(1)).enumerate() {
+                let dst = unsafe {
+                    std::slice::from_raw_parts_mut(bmp_data.as_ptr().add(y * stride), row_len)
+                };
+
+                // `PixelFormat32bppPARGB` uses the BGRA byte order
+                for (dst, src) in dst.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+                    dst.copy_from_slice(&[src[2], src[1], src[0], src[3]]);
+                }
+            }
+        }
+
+        this
+    }
+}
+
+/// Create a `GpGraphics` for drawing into `bmp`.
+fn new_bmp_graphics(bmp: &BitmapInner) -> UniqueGpGraphics {
+    let gr = UniqueGpGraphics {
+        gp_gr: unsafe {
+            create_gp_obj_with(|out| gp::GdipGetImageGraphicsContext(bmp.gp_bmp as _, out))
+        },
+    };
+
+    unsafe {
+        gp::GdipSetSmoothingMode(gr.gp_gr, gdiplusenums::SmoothingModeAntiAlias);
+    }
+
+    gr
 }
 
 impl iface::BitmapBuilder for BitmapBuilder {
     type Bitmap = Bitmap;
 
     fn into_bitmap(self) -> Self::Bitmap {
+        assert!(self.group_stack.is_empty(), "unterminated group");
         Bitmap {
             inner: Arc::new(self.bmp),
         }
@@ -490,13 +700,42 @@ impl iface::Canvas for BitmapBuilder {
     }
     fn fill(&mut self) {
         unsafe {
-            assert_gp_ok(gp::GdipFillPath(
-                self.gr.gp_gr,
-                self.brush.gp_solid_fill as _,
+            if let Some(gradient_brush) = &self.gradient_brush {
+                if let Some(outer_color) = gradient_brush.outer_color {
+                    assert_gp_ok(gp::GdipSetSolidFillColor(
+                        self.brush2.gp_solid_fill,
+                        outer_color,
+                    ));
+                    assert_gp_ok(gp::GdipFillPath(
+                        self.gr.gp_gr,
+                        self.brush2.gp_solid_fill as _,
+                        self.path.gp_path,
+                    ));
+                }
+                assert_gp_ok(gp::GdipFillPath(
+                    self.gr.gp_gr,
+                    gradient_brush.brush.gp_brush,
+                    self.path.gp_path,
+                ));
+            } else {
+                assert_gp_ok(gp::GdipFillPath(
+                    self.gr.gp_gr,
+                    self.brush.gp_solid_fill as _,
+                    self.path.gp_path,
+                ));
+            }
+        }
+        self.begin_path();
+    }
+    fn fill_even_odd(&mut self) {
+        unsafe {
+            assert_gp_ok(gp::GdipSetPathFillMode(
                 self.path.gp_path,
+                gdiplusenums::FillModeAlternate,
             ));
         }
-        self.begin_path();
+        // This resets the fill mode
+        self.fill();
     }
     fn stroke(&mut self) {
         unsafe {
@@ -525,12 +764,29 @@ impl iface::Canvas for BitmapBuilder {
                 rgbaf32_to_argb(rgb),
             ));
         }
+        self.gradient_brush = None;
+    }
+    fn set_fill_gradient(&mut self, gradient: &iface::Gradient<'_>) {
+        self.gradient_brush = Some(GradientBrush::new(gradient));
     }
     fn set_stroke_rgb(&mut self, rgb: iface::RGBAF32) {
         unsafe {
+            // This also makes the pen's brush a solid one
             assert_gp_ok(gp::GdipSetPenColor(self.pen.gp_pen, rgbaf32_to_argb(rgb)));
         }
     }
+    fn set_stroke_gradient(&mut self, gradient: &iface::Gradient<'_>) {
+        // The pen makes a copy of the brush, so `gradient_brush` can be
+        // dropped right after this. The padding behavior of radial gradients
+        // is not emulated for strokes.
+        let gradient_brush = GradientBrush::new(gradient);
+        unsafe {
+            assert_gp_ok(gp::GdipSetPenBrushFill(
+                self.pen.gp_pen,
+                gradient_brush.brush.gp_brush,
+            ));
+        }
+    }
     fn set_line_cap(&mut self, cap: iface::LineCap) {
         let cap = match cap {
             iface::LineCap::Butt => gdiplusenums::LineCapFlat,
@@ -600,6 +856,99 @@ impl iface::Canvas for BitmapBuilder {
             ));
         }
     }
+    fn begin_group(&mut self, opacity: f32) {
+        self.save();
+
+        // Create a new drawing target inheriting the transformation and
+        // clipping region of the current one
+        let bmp = BitmapInner::new(self.bmp.size());
+        let gr = new_bmp_graphics(&bmp);
+
+        unsafe {
+            assert_gp_ok(gp::GdipGetWorldTransform(self.gr.gp_gr, self.mat.gp_mat));
+            assert_gp_ok(gp::GdipSetWorldTransform(gr.gp_gr, self.mat.gp_mat));
+
+            let region = UniqueGpRegion {
+                gp_region: create_gp_obj_with(|out| gp::GdipCreateRegion(out)),
+            };
+            assert_gp_ok(gp::GdipGetClip(self.gr.gp_gr, region.gp_region));
+            assert_gp_ok(gp::GdipSetClipRegion(
+                gr.gp_gr,
+                region.gp_region,
+                gdiplusenums::CombineModeReplace,
+            ));
+        }
+
+        let bmp = std::mem::replace(&mut self.bmp, bmp);
+        let gr = std::mem::replace(&mut self.gr, gr);
+
+        self.group_stack.push(Group {
+            gr,
+            bmp,
+            state_stack_len: self.state_stack.len(),
+            opacity,
+        });
+    }
+    fn end_group(&mut self) {
+        let group = self.group_stack.pop().expect("no active group");
+        assert_eq!(
+            group.state_stack_len,
+            self.state_stack.len(),
+            "unbalanced save/restore in a group"
+        );
+
+        let layer_bmp = std::mem::replace(&mut self.bmp, group.bmp);
+        let layer_gr = std::mem::replace(&mut self.gr, group.gr);
+        drop(layer_gr);
+
+        // Apply the opacity. The pixels are in the pre-multiplied alpha
+        // format, so all channels are scaled uniformly.
+        let opacity = group.opacity.max(0.0).min(1.0);
+        if opacity < 1.0 {
+            let layer_data = layer_bmp.write();
+            let [width, height] = layer_data.size();
+            let stride = layer_data.stride() as usize;
+            let scale = (opacity * 256.0) as u32;
+            for y in 0..height as usize {
+                let row = unsafe {
+                    std::slice::from_raw_parts_mut(
+                        layer_data.as_ptr().add(y * stride),
+                        width as usize * 4,
+                    )
+                };
+                for x in row.iter_mut() {
+                    *x = ((*x as u32 * scale) >> 8) as u8;
+                }
+            }
+        }
+
+        // Composite the layer in the device space
+        let [width, height] = layer_bmp.size();
+        unsafe {
+            let st = create_gp_obj_with(|out| gp::GdipSaveGraphics(self.gr.gp_gr, out));
+            assert_gp_ok(gp::GdipResetWorldTransform(self.gr.gp_gr));
+            assert_gp_ok(gp::GdipResetClip(self.gr.gp_gr));
+            assert_gp_ok(gp::GdipSetInterpolationMode(
+                self.gr.gp_gr,
+                gdiplusenums::InterpolationModeNearestNeighbor,
+            ));
+            assert_gp_ok(gp::GdipSetPixelOffsetMode(
+                self.gr.gp_gr,
+                gdiplusenums::PixelOffsetModeHalf,
+            ));
+            assert_gp_ok(gp::GdipDrawImageRectI(
+                self.gr.gp_gr,
+                layer_bmp.gp_bmp as _,
+                0,
+                0,
+                width as INT,
+                height as INT,
+            ));
+            assert_gp_ok(gp::GdipRestoreGraphics(self.gr.gp_gr, st));
+        }
+
+        self.restore();
+    }
 }
 
 /// Create a monochrome noise image.
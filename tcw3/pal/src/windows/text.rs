@@ -79,6 +79,16 @@ impl iface::CharStyle for CharStyle {
             cs.size = size;
         }
 
+        if let Some(bold) = attrs.bold {
+            cs.weight = if bold {
+                FontWeight::Bold
+            } else {
+                FontWeight::Normal
+            };
+        }
+
+        // TODO: `italic`
+
         if let Some(decor) = attrs.decor {
             cs.decor = decor;
         }
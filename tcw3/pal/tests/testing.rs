@@ -2,7 +2,7 @@ use cggeom::{box2, prelude::*, Box2};
 use cgmath::{Deg, Matrix3, Point2, Vector2};
 use log::info;
 use std::{
-    cell::Cell,
+    cell::{Cell, RefCell},
     ops::Range,
     rc::Rc,
     sync::{
@@ -248,6 +248,23 @@ fn bitmap_size() {
     });
 }
 
+#[test]
+fn bitmap_with_rgba8_premul() {
+    init_logger();
+    testing::run_test(|_| {
+        const SIZE: [u32; 2] = [3, 2];
+        let data: Vec<u8> = (0..SIZE[0] * SIZE[1] * 4).map(|i| i as u8).collect();
+        let mut b = pal::BitmapBuilder::with_rgba8_premul(SIZE, &data);
+
+        // The builder can be drawn onto as usual
+        b.set_fill_rgb([0.2, 0.3, 0.4, 0.6].into());
+        b.fill_rect(box2! { min: [0.0, 0.0], max: [1.0, 1.0] });
+
+        let bitmap = b.into_bitmap();
+        assert_eq!(bitmap.size(), SIZE);
+    });
+}
+
 /// Execute drawing commands on a `BitmapBuilder` and see if it doesn't panic.
 /// The rendering result is not checked because there is currently no API to
 /// get a bitmap content. (When we do, I'd like to see conformance tests for all
@@ -284,6 +301,35 @@ fn bitmap_canvas() {
         b.stroke();
         b.clip();
 
+        let stops = [
+            pal::GradientStop {
+                offset: 0.0,
+                color: [0.2, 0.3, 0.4, 0.6].into(),
+            },
+            pal::GradientStop {
+                offset: 1.0,
+                color: [0.5, 0.3, 0.1, 1.0].into(),
+            },
+        ];
+        b.set_fill_gradient(&pal::Gradient {
+            kind: pal::GradientKind::Linear,
+            xform: Matrix3::from_scale_2d(4.0),
+            stops: &stops,
+        });
+        b.set_stroke_gradient(&pal::Gradient {
+            kind: pal::GradientKind::Radial,
+            xform: Matrix3::from_translation(Vector2::new(5.0, 6.0)),
+            stops: &stops,
+        });
+
+        b.begin_group(0.5);
+        b.begin_path();
+        b.move_to([5.0, 6.0].into());
+        b.line_to([6.0, 6.0].into());
+        b.line_to([6.0, 8.0].into());
+        b.fill_even_odd();
+        b.end_group();
+
         b.restore();
     });
 }
@@ -596,6 +642,204 @@ fn defer_layer_changes_until_update_wnd() {
     });
 }
 
+fn snapshot_pixel(ss: &wmapi::WndSnapshot, [x, y]: [usize; 2]) -> [u8; 4] {
+    let p = &ss.data[y * ss.stride + x * 4..][..4];
+    [p[0], p[1], p[2], p[3]]
+}
+
+#[test]
+fn backdrop_blur() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        let left = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([1.0, 0.0, 0.0, 1.0].into()),
+            bounds: Some(box2! { top_left: [0.0, 0.0], size: [50.0, 100.0] }),
+            ..Default::default()
+        });
+        let right = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([0.0, 0.0, 1.0, 1.0].into()),
+            bounds: Some(box2! { top_left: [50.0, 0.0], size: [50.0, 100.0] }),
+            ..Default::default()
+        });
+        let blur = wm.new_layer(pal::LayerAttrs {
+            bounds: Some(box2! { top_left: [20.0, 20.0], size: [60.0, 40.0] }),
+            flags: Some(pal::LayerFlags::BACKDROP_BLUR),
+            ..Default::default()
+        });
+        let root = wm.new_layer(pal::LayerAttrs {
+            sublayers: Some(vec![left.clone(), right.clone(), blur.clone()]),
+            ..Default::default()
+        });
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100, 100]),
+            flags: Some(pal::WndFlags::TRANSPARENT_BACKDROP_BLUR),
+            layer: Some(Some(root.clone())),
+            ..Default::default()
+        });
+
+        wm.update_wnd(&hwnd);
+
+        let mut ss = wmapi::WndSnapshot::new();
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+
+        // The pixels are in the BGRA format. The edge between the two layers
+        // is sharp outside the blurred region...
+        assert_eq!(snapshot_pixel(&ss, [48, 80]), [0, 0, 255, 255]);
+        assert_eq!(snapshot_pixel(&ss, [52, 80]), [255, 0, 0, 255]);
+
+        // ... but not inside it
+        for &x in &[48, 52] {
+            let [b, g, r, a] = snapshot_pixel(&ss, [x, 40]);
+            log::info!("[{}, 40] = {:?}", x, [b, g, r, a]);
+            assert!(b > 64 && r > 64 && g == 0 && a == 255);
+        }
+
+        // The blur is not visible far from the edge
+        let [b, _, r, _] = snapshot_pixel(&ss, [22, 40]);
+        assert!(b < 8 && r > 247);
+
+        // Modifying a layer behind the blurred region should update the
+        // blurred region even if the modified layer doesn't overlap with
+        // the updated pixels
+        wm.set_layer_attr(
+            &right,
+            pal::LayerAttrs {
+                bg_color: Some([0.0, 1.0, 0.0, 1.0].into()),
+                ..Default::default()
+            },
+        );
+        wm.update_wnd(&hwnd);
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+
+        let [b, g, r, _] = snapshot_pixel(&ss, [48, 40]);
+        assert!(b == 0 && g > 64 && r > 64);
+
+        wm.remove_wnd(&hwnd);
+        for hlayer in &[root, left, right, blur] {
+            wm.remove_layer(hlayer);
+        }
+    });
+}
+
+#[test]
+fn opaque_bg_color() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        let back = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([1.0, 1.0, 1.0, 1.0].into()),
+            bounds: Some(box2! { top_left: [0.0, 0.0], size: [100.0, 100.0] }),
+            ..Default::default()
+        });
+        let front = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([0.0, 0.0, 0.0, 1.0].into()),
+            bounds: Some(box2! { top_left: [0.0, 0.0], size: [50.0, 100.0] }),
+            ..Default::default()
+        });
+        let root = wm.new_layer(pal::LayerAttrs {
+            sublayers: Some(vec![back.clone(), front.clone()]),
+            ..Default::default()
+        });
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100, 100]),
+            flags: Some(pal::WndFlags::TRANSPARENT_BACKDROP_BLUR),
+            layer: Some(Some(root.clone())),
+            ..Default::default()
+        });
+
+        wm.update_wnd(&hwnd);
+
+        let mut ss = wmapi::WndSnapshot::new();
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+
+        // An opaque layer completely hides the layer behind it, and
+        // the window's transparent backdrop doesn't show through either
+        assert_eq!(snapshot_pixel(&ss, [20, 50]), [0, 0, 0, 255]);
+        assert_eq!(snapshot_pixel(&ss, [80, 50]), [255, 255, 255, 255]);
+
+        wm.remove_wnd(&hwnd);
+        for hlayer in &[root, back, front] {
+            wm.remove_layer(hlayer);
+        }
+    });
+}
+
+#[test]
+fn rounded_corners_and_shadow() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        let card = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([1.0, 0.0, 0.0, 1.0].into()),
+            bounds: Some(box2! { top_left: [20.0, 20.0], size: [60.0, 60.0] }),
+            corner_radii: Some([10.0; 4]),
+            shadow: Some(Some(pal::LayerShadow {
+                offset: [0.0, 5.0].into(),
+                blur_radius: 4.0,
+                color: [0.0, 0.0, 0.0, 0.5].into(),
+            })),
+            ..Default::default()
+        });
+        let child = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([0.0, 1.0, 0.0, 1.0].into()),
+            bounds: Some(box2! { top_left: [100.0, 0.0], size: [100.0, 100.0] }),
+            ..Default::default()
+        });
+        let clip = wm.new_layer(pal::LayerAttrs {
+            bounds: Some(box2! { top_left: [120.0, 20.0], size: [60.0, 60.0] }),
+            corner_radii: Some([10.0; 4]),
+            flags: Some(pal::LayerFlags::MASK_TO_BOUNDS),
+            sublayers: Some(vec![child.clone()]),
+            ..Default::default()
+        });
+        let root = wm.new_layer(pal::LayerAttrs {
+            sublayers: Some(vec![card.clone(), clip.clone()]),
+            ..Default::default()
+        });
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([200, 100]),
+            layer: Some(Some(root.clone())),
+            ..Default::default()
+        });
+
+        wm.update_wnd(&hwnd);
+
+        let mut ss = wmapi::WndSnapshot::new();
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+
+        // The pixels are in the BGRA format. The corners are cut out...
+        assert!(snapshot_pixel(&ss, [20, 20])[3] < 8);
+        assert_eq!(snapshot_pixel(&ss, [50, 50]), [0, 0, 255, 255]);
+        assert_eq!(snapshot_pixel(&ss, [50, 20]), [0, 0, 255, 255]);
+
+        // ... and the shadow is visible below the layer
+        let [b, g, r, a] = snapshot_pixel(&ss, [50, 82]);
+        log::info!("[50, 82] = {:?}", [b, g, r, a]);
+        assert!(b == 0 && g == 0 && r == 0 && a > 64 && a < 160);
+        assert_eq!(snapshot_pixel(&ss, [50, 95]), [0, 0, 0, 0]);
+
+        // The sublayer is clipped by the rounded shape
+        assert!(snapshot_pixel(&ss, [120, 20])[3] < 8);
+        assert_eq!(snapshot_pixel(&ss, [150, 50]), [0, 255, 0, 255]);
+        assert_eq!(snapshot_pixel(&ss, [150, 20]), [0, 255, 0, 255]);
+
+        wm.remove_wnd(&hwnd);
+        for hlayer in &[root, card, child, clip] {
+            wm.remove_layer(hlayer);
+        }
+    });
+}
+
 #[test]
 fn wnd_close_event() {
     init_logger();
@@ -1083,3 +1327,460 @@ fn wnd_key_events() {
         assert_eq!(state.get(), 3);
     });
 }
+
+#[test]
+fn runtime_accel_table() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        let mut defaults = pal::accel::KeyBindings::new();
+        defaults.bind(42, "Ctrl+Y".parse().unwrap());
+        defaults.bind(43, "Ctrl+Q".parse().unwrap());
+
+        let mut user = pal::accel::KeyBindings::new();
+        user.bind(42, "Shift+Ctrl+Z".parse().unwrap());
+
+        let accel: pal::AccelTable = defaults.overlay(&user).to_accel_table();
+
+        struct Listener(pal::AccelTable);
+        impl WndListener<pal::Wm> for Listener {
+            fn interpret_event(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                ctx: &mut dyn pal::InterpretEventCtx<pal::AccelTable>,
+            ) {
+                ctx.use_accel(&self.0);
+            }
+        }
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            listener: Some(Box::new(Listener(accel))),
+            ..Default::default()
+        });
+
+        // The modifiers are matched regardless of their order
+        assert_eq!(twm.translate_action(&hwnd, "gtk", "Ctrl+Shift+Z"), Some(42));
+        assert_eq!(twm.translate_action(&hwnd, "windows", "Ctrl+Y"), None);
+        assert_eq!(twm.translate_action(&hwnd, "windows", "Ctrl+Q"), Some(43));
+        assert_eq!(twm.translate_action(&hwnd, "macos_sel", "Ctrl+Q"), None);
+    });
+}
+
+#[test]
+fn clipboard() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        // The clipboard is empty at the start
+        assert_eq!(wm.clipboard_formats(), pal::ClipboardFormatFlags::empty());
+        assert_eq!(wm.get_clipboard_text(), None);
+
+        // Write something
+        wm.set_clipboard_text("hello");
+        assert_eq!(wm.clipboard_formats(), pal::ClipboardFormatFlags::TEXT);
+        assert_eq!(wm.get_clipboard_text().as_deref(), Some("hello"));
+        assert_eq!(twm.clipboard_text().as_deref(), Some("hello"));
+
+        // Read it asynchronously
+        let text = Rc::new(Cell::new(None));
+        {
+            let text = Rc::clone(&text);
+            wm.get_clipboard_text_async(move |_, t| text.set(Some(t)));
+        }
+        assert_eq!(text.take(), None, "the callback was called too early");
+        twm.step_unsend();
+        assert_eq!(text.take(), Some(Some("hello".to_owned())));
+
+        // Modify the virtual clipboard by `TestingWm`
+        twm.set_clipboard_text(Some("world"));
+        assert_eq!(wm.get_clipboard_text().as_deref(), Some("world"));
+
+        twm.set_clipboard_text(None);
+        assert_eq!(wm.clipboard_formats(), pal::ClipboardFormatFlags::empty());
+        assert_eq!(wm.get_clipboard_text(), None);
+    });
+}
+
+#[test]
+fn appearance() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        assert_eq!(wm.appearance(), pal::Appearance::Light);
+
+        let count = Rc::new(Cell::new(0));
+        {
+            let count = Rc::clone(&count);
+            wm.set_appearance_listener(Box::new(move |_| count.set(count.get() + 1)));
+        }
+
+        twm.set_appearance(pal::Appearance::Dark);
+        assert_eq!(wm.appearance(), pal::Appearance::Dark);
+        assert_eq!(count.get(), 1);
+
+        // The listener isn't called if nothing has changed
+        twm.set_appearance(pal::Appearance::Dark);
+        assert_eq!(count.get(), 1);
+
+        twm.set_appearance(pal::Appearance::HighContrast);
+        assert_eq!(wm.appearance(), pal::Appearance::HighContrast);
+        assert_eq!(count.get(), 2);
+    });
+}
+
+#[test]
+fn virtual_clock() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        twm.enable_virtual_clock();
+
+        // The clock only advances when instructed to
+        let t0 = wm.now();
+        twm.step_unsend();
+        assert_eq!(wm.now(), t0);
+        twm.advance_clock(Duration::from_millis(100));
+        assert_eq!(wm.now(), t0 + Duration::from_millis(100));
+
+        // A listener that keeps requesting frames, like an animation
+        struct Listener(Rc<Cell<u32>>);
+        impl WndListener<pal::Wm> for Listener {
+            fn update_ready(&self, wm: pal::Wm, hwnd: &pal::HWnd) {
+                self.0.set(self.0.get() + 1);
+                wm.request_update_ready_wnd(hwnd);
+            }
+        }
+
+        let count = Rc::new(Cell::new(0));
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            listener: Some(Box::new(Listener(Rc::clone(&count)))),
+            ..Default::default()
+        });
+
+        // Requests made outside `update_ready` are processed immediately
+        wm.request_update_ready_wnd(&hwnd);
+        twm.step_unsend();
+        assert_eq!(count.get(), 1);
+
+        // Requests made by `update_ready` are deferred until the clock is
+        // advanced
+        twm.step_unsend();
+        assert_eq!(count.get(), 1);
+
+        twm.advance_clock(Duration::from_millis(16));
+        assert_eq!(count.get(), 2);
+        twm.advance_clock(Duration::from_millis(16));
+        assert_eq!(count.get(), 3);
+
+        wm.remove_wnd(&hwnd);
+        twm.advance_clock(Duration::from_millis(16));
+        assert_eq!(count.get(), 3);
+    });
+}
+
+#[test]
+fn popup_menu() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            ..Default::default()
+        });
+
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            Perform(pal::ActionId),
+            Dismissed,
+        }
+
+        struct Listener(Rc<RefCell<Vec<Event>>>);
+        impl pal::iface::MenuListener<pal::Wm> for Listener {
+            fn validate_action(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                action: pal::ActionId,
+            ) -> pal::ActionStatus {
+                match action {
+                    2 => pal::ActionStatus::VALID,
+                    _ => pal::ActionStatus::VALID | pal::ActionStatus::ENABLED,
+                }
+            }
+
+            fn perform_action(&self, _: pal::Wm, _: &pal::HWnd, action: pal::ActionId) {
+                self.0.borrow_mut().push(Event::Perform(action));
+            }
+
+            fn dismissed(&self, _: pal::Wm, _: &pal::HWnd) {
+                self.0.borrow_mut().push(Event::Dismissed);
+            }
+        }
+
+        let items = [
+            pal::MenuItem::action("Undo", 1).with_shortcut("Ctrl+Z"),
+            pal::MenuItem::Sep,
+            pal::MenuItem::action("Redo", 2),
+            pal::MenuItem::submenu(
+                "Edit",
+                vec![
+                    pal::MenuItem::action("Cut", 3),
+                    pal::MenuItem::action("Copy", 4),
+                ],
+            ),
+            pal::MenuItem::action("Quit", 5),
+        ];
+        let anchor = box2! { min: [10.0, 20.0], max: [30.0, 40.0] };
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let take_events = || events.replace(Vec::new());
+        let open_menu = || {
+            wm.popup_menu(
+                &hwnd,
+                &items,
+                anchor,
+                Box::new(Listener(Rc::clone(&events))),
+            )
+        };
+
+        assert!(twm.active_popup_menu().is_none());
+
+        open_menu();
+
+        let menu = twm.active_popup_menu().unwrap();
+        assert_eq!(menu.hwnd, hwnd);
+        assert_eq!(menu.anchor, anchor);
+        assert_eq!(menu.path_of(&["Edit", "Copy"]), Some(vec![3, 1]));
+        assert_eq!(menu.path_of(&["Paste"]), None);
+        assert_eq!(
+            menu.items[0],
+            wmapi::PopupMenuItem::Action {
+                label: "Undo".to_owned(),
+                action: 1,
+                shortcut: Some("Ctrl+Z".to_owned()),
+                status: pal::ActionStatus::VALID | pal::ActionStatus::ENABLED,
+            }
+        );
+        assert!(!menu.items[2].is_enabled(), "Redo should be disabled");
+
+        // Keyboard navigation skips separators and disabled items
+        let highlight = || twm.active_popup_menu().unwrap().highlight;
+        twm.simulate_menu_key(wmapi::MenuKey::Down);
+        assert_eq!(highlight(), [0]);
+        twm.simulate_menu_key(wmapi::MenuKey::Down);
+        assert_eq!(highlight(), [3]);
+        twm.simulate_menu_key(wmapi::MenuKey::Right);
+        assert_eq!(highlight(), [3, 0]);
+        twm.simulate_menu_key(wmapi::MenuKey::Down);
+        assert_eq!(highlight(), [3, 1]);
+        twm.simulate_menu_key(wmapi::MenuKey::Left);
+        assert_eq!(highlight(), [3]);
+        twm.simulate_menu_key(wmapi::MenuKey::Up);
+        assert_eq!(highlight(), [0]);
+        twm.simulate_menu_key(wmapi::MenuKey::Up);
+        assert_eq!(highlight(), [4]);
+        assert_eq!(take_events(), []);
+
+        // `perform_action` is followed by `dismissed`
+        twm.simulate_menu_key(wmapi::MenuKey::Enter);
+        assert_eq!(take_events(), [Event::Perform(5), Event::Dismissed]);
+        assert!(twm.active_popup_menu().is_none());
+
+        // Pick an item in a submenu
+        open_menu();
+        twm.pick_menu_item(&[3, 1]);
+        assert_eq!(take_events(), [Event::Perform(4), Event::Dismissed]);
+
+        // Dismiss the menu by Escape
+        open_menu();
+        twm.simulate_menu_key(wmapi::MenuKey::Escape);
+        assert_eq!(take_events(), [Event::Dismissed]);
+        assert!(twm.active_popup_menu().is_none());
+
+        // Opening a new menu dismisses the current one
+        open_menu();
+        open_menu();
+        twm.step_unsend();
+        assert_eq!(take_events(), [Event::Dismissed]);
+        twm.dismiss_menu();
+        assert_eq!(take_events(), [Event::Dismissed]);
+    });
+}
+
+#[test]
+fn menu_bar() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        struct Listener(Rc<RefCell<Vec<pal::ActionId>>>);
+        impl WndListener<pal::Wm> for Listener {
+            fn validate_action(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                action: pal::ActionId,
+            ) -> pal::ActionStatus {
+                match action {
+                    2 => pal::ActionStatus::VALID,
+                    3 => {
+                        pal::ActionStatus::VALID
+                            | pal::ActionStatus::ENABLED
+                            | pal::ActionStatus::CHECKED
+                    }
+                    _ => pal::ActionStatus::VALID | pal::ActionStatus::ENABLED,
+                }
+            }
+
+            fn perform_action(&self, _: pal::Wm, _: &pal::HWnd, action: pal::ActionId) {
+                self.0.borrow_mut().push(action);
+            }
+        }
+
+        let performed = Rc::new(RefCell::new(Vec::new()));
+
+        let items = [
+            pal::MenuItem::submenu("File", vec![pal::MenuItem::action("Quit", 1)]),
+            pal::MenuItem::submenu(
+                "View",
+                vec![
+                    pal::MenuItem::action("Zoom", 2),
+                    pal::MenuItem::Sep,
+                    pal::MenuItem::action("Show Sidebar", 3),
+                ],
+            ),
+        ];
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            listener: Some(Box::new(Listener(Rc::clone(&performed)))),
+            menu_bar: Some((&items[..]).into()),
+            ..Default::default()
+        });
+
+        assert_eq!(twm.wnd_attrs(&hwnd).unwrap().menu_bar, items);
+
+        let menu_bar = twm.wnd_menu_bar(&hwnd);
+        assert_eq!(
+            menu_bar.path_of(&["View", "Show Sidebar"]),
+            Some(vec![1, 2])
+        );
+        assert!(!menu_bar.item(&[1, 0]).unwrap().is_enabled());
+        assert_eq!(
+            menu_bar.item(&[1, 2]),
+            Some(&wmapi::PopupMenuItem::Action {
+                label: "Show Sidebar".to_owned(),
+                action: 3,
+                shortcut: None,
+                status: pal::ActionStatus::VALID
+                    | pal::ActionStatus::ENABLED
+                    | pal::ActionStatus::CHECKED,
+            })
+        );
+
+        twm.pick_menu_bar_item(&hwnd, &[0, 0]);
+        assert_eq!(*performed.borrow(), [1]);
+
+        // Remove the menu bar
+        wm.set_wnd_attr(
+            &hwnd,
+            pal::WndAttrs {
+                menu_bar: Some((&[][..]).into()),
+                ..Default::default()
+            },
+        );
+        assert!(twm.wnd_menu_bar(&hwnd).items.is_empty());
+
+        wm.remove_wnd(&hwnd);
+    });
+}
+
+#[test]
+fn notification() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            Activated(pal::NotificationId, Option<String>),
+            Closed(pal::NotificationId),
+        }
+
+        struct Listener(Rc<RefCell<Vec<Event>>>);
+        impl pal::iface::NotificationListener<pal::Wm> for Listener {
+            fn activated(&self, _: pal::Wm, id: pal::NotificationId, action: Option<&str>) {
+                (self.0.borrow_mut()).push(Event::Activated(id, action.map(str::to_owned)));
+            }
+
+            fn closed(&self, _: pal::Wm, id: pal::NotificationId) {
+                self.0.borrow_mut().push(Event::Closed(id));
+            }
+        }
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let listener = || Box::new(Listener(Rc::clone(&events)));
+
+        let id1 = wm.show_notification(
+            pal::Notification::new("Alice", "Hello").with_action("reply", "Reply"),
+            None,
+            listener(),
+        );
+        let id2 = wm.show_notification(pal::Notification::new("Bob", "Hi"), None, listener());
+        assert_ne!(id1, id2);
+
+        let notes = twm.notifications();
+        assert_eq!(notes.iter().map(|n| n.id).collect::<Vec<_>>(), [id1, id2]);
+        assert_eq!(notes[0].notification.title, "Alice");
+        assert_eq!(notes[0].notification.actions[0].id, "reply");
+
+        twm.activate_notification(id1, None);
+        twm.activate_notification(id1, Some("reply"));
+        assert_eq!(
+            events.borrow_mut().drain(..).collect::<Vec<_>>(),
+            [
+                Event::Activated(id1, None),
+                Event::Activated(id1, Some("reply".to_owned()))
+            ]
+        );
+
+        // Replace the first notification. The old listener is notified of the
+        // replacement asynchronously.
+        let id1b = wm.show_notification(
+            pal::Notification::new("Alice", "Hello again"),
+            Some(id1),
+            listener(),
+        );
+        assert_eq!(id1b, id1);
+        assert!(events.borrow().is_empty());
+        twm.step_unsend();
+        assert_eq!(
+            events.borrow_mut().drain(..).collect::<Vec<_>>(),
+            [Event::Closed(id1)]
+        );
+        assert_eq!(twm.notifications()[0].notification.body, "Hello again");
+
+        twm.dismiss_notification(id2);
+        wm.close_notification(id1);
+        twm.step_unsend();
+        assert_eq!(
+            events.borrow_mut().drain(..).collect::<Vec<_>>(),
+            [Event::Closed(id2), Event::Closed(id1)]
+        );
+        assert!(twm.notifications().is_empty());
+
+        // Closing an already closed notification does nothing
+        wm.close_notification(id1);
+        twm.step_unsend();
+        assert!(events.borrow().is_empty());
+    });
+}
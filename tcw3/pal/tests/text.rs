@@ -528,3 +528,76 @@ fn draw_text_should_not_change_layout_properties() {
         assert_eq!(props1, props2);
     }
 }
+
+#[test]
+fn attr_text_inline_box() {
+    common::try_init_logger_for_default_harness();
+
+    let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+        ..Default::default()
+    });
+
+    let mut builder = pal::AttrTextBuilder::new();
+    builder.push_str("ab");
+    let i = builder.push_inline_box(pal::InlineBox {
+        width: 30.0,
+        ascent: 10.0,
+        descent: 2.0,
+    });
+    builder.push_str("cd");
+    let text = builder.build();
+    assert_eq!(i, 2);
+    assert_eq!(text.text(), "ab\u{fffc}cd");
+
+    let text_layout = pal::TextLayout::from_attr_text(&text, &char_style, None);
+    log::debug!("text_layout = {:?}", text_layout);
+
+    let run_metrics = text_layout.run_metrics_of_range(i..i + 3);
+    log::debug!("run_metrics = {:?}", run_metrics);
+    assert_eq!(run_metrics.len(), 1);
+
+    let bounds = run_metrics[0].bounds.clone();
+    assert!((bounds.end - bounds.start - 30.0).abs() < 1.0);
+
+    // Hit testing should treat the inline box as a single character
+    let y = text_layout.line_baseline(0) - 1.0;
+    let left = text_layout.cursor_index_from_point([bounds.start + 5.0, y].into());
+    let right = text_layout.cursor_index_from_point([bounds.end - 5.0, y].into());
+    assert_eq!(left, i);
+    assert_eq!(right, i + 3);
+}
+
+#[test]
+fn attr_text_span_size() {
+    common::try_init_logger_for_default_harness();
+
+    let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+        size: Some(12.0),
+        ..Default::default()
+    });
+
+    let mut builder = pal::AttrTextBuilder::new();
+    builder.push_str("small ");
+    let large = builder.push_styled_str(
+        "large",
+        pal::CharStyleAttrs {
+            size: Some(36.0),
+            ..Default::default()
+        },
+    );
+    let text = builder.build();
+
+    let styles = text.resolve_char_styles(&char_style);
+    let ranges: Vec<_> = styles.iter().map(|(range, _)| range.clone()).collect();
+    assert_eq!(ranges, vec![0..large.start, large.clone()]);
+    assert!(styles[1].1.size() > styles[0].1.size());
+
+    let plain_layout = pal::TextLayout::from_text(text.text(), &char_style, None);
+    let attr_layout = pal::TextLayout::from_attr_text(&text, &char_style, None);
+    log::debug!("plain_layout = {:?}", plain_layout);
+    log::debug!("attr_layout = {:?}", attr_layout);
+
+    let plain_height = plain_layout.layout_bounds().size().y;
+    let attr_height = attr_layout.layout_bounds().size().y;
+    assert!(attr_height > plain_height * 1.5);
+}
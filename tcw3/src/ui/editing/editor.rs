@@ -0,0 +1,398 @@
+//! Provides the editing logic shared by the text input widgets ([`EntryCore`]
+//! and [`TextArea`]).
+//!
+//! [`EntryCore`]: crate::ui::views::EntryCore
+//! [`TextArea`]: crate::ui::views::TextArea
+use rc_borrow::RcBorrow;
+use std::{borrow::Cow, cell::RefMut, ops::Range, rc::Rc};
+
+use super::undo::{History, HistoryText, HistoryTx};
+use crate::{
+    pal,
+    pal::prelude::*,
+    uicore::{actions, ActionId, ActionStatus, HView, HViewRef},
+};
+
+/// The text storage edited by [`Editor`].
+pub(crate) trait EditText: HistoryText {
+    /// Get the length of the text in bytes.
+    fn len(&self) -> usize;
+
+    /// Replace the text in the given range. `range` must lie on character
+    /// boundaries.
+    fn replace_range(&mut self, range: Range<usize>, new_text: &str);
+}
+
+impl EditText for String {
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+
+    fn replace_range(&mut self, range: Range<usize>, new_text: &str) {
+        String::replace_range(self, range, new_text);
+    }
+}
+
+/// Borrows the parts of a text input widget's state which are modified by
+/// editing operations, and performs the operations while keeping the undo
+/// history up-to-date.
+pub(crate) struct Editor<'a, T> {
+    pub(crate) text: &'a mut T,
+    pub(crate) sel_range: &'a mut [usize; 2],
+    pub(crate) history: &'a mut History,
+}
+
+impl<T: EditText> Editor<'_, T> {
+    /// Get the selected text. Returns `None` if the selection is empty.
+    pub(crate) fn selected_text(&self) -> Option<String> {
+        let range = sorted_range(*self.sel_range);
+        if range.start == range.end {
+            None
+        } else {
+            Some(self.text.slice(range))
+        }
+    }
+
+    /// Delete the text in `range`, whose endpoints can be in any order.
+    /// Returns `false` if there's nothing to delete.
+    pub(crate) fn delete(&mut self, range: [usize; 2]) -> bool {
+        let range = sorted_range(range);
+
+        log::trace!("... deletion range = {:?}", range);
+
+        if range.start == range.end {
+            // There's nothing to delete
+            return false;
+        }
+
+        // Record the change to the undo history
+        {
+            let mut tx = self.history.start_transaction();
+            tx.replace_range(self.history, &*self.text, range.clone(), String::new());
+            tx.finish(self.history, &*self.text);
+        }
+
+        // Update the text
+        self.text.replace_range(range.clone(), "");
+        *self.sel_range = [range.start, range.start];
+
+        true
+    }
+
+    /// Replace the selected text with `new_text`. Returns `false` if nothing
+    /// has changed.
+    pub(crate) fn insert(&mut self, new_text: &str) -> bool {
+        let range = sorted_range(*self.sel_range);
+
+        log::trace!("... replacing {:?} with {:?}", range, new_text);
+
+        if range.start == range.end && new_text.is_empty() {
+            return false;
+        }
+
+        // Record the change to the undo history. Don't coalesce the change
+        // with neighboring ones.
+        {
+            self.history.mark_logical_op_break();
+            let mut tx = self.history.start_transaction();
+            tx.replace_range(
+                self.history,
+                &*self.text,
+                range.clone(),
+                new_text.to_owned(),
+            );
+            tx.finish(self.history, &*self.text);
+            self.history.mark_logical_op_break();
+        }
+
+        // Update the text
+        self.text.replace_range(range.clone(), new_text);
+        let end = range.start + new_text.len();
+        *self.sel_range = [end, end];
+
+        true
+    }
+
+    /// Replace the text in `range` as a part of the undo transaction `tx`,
+    /// adjusting the selection to follow the change. This is used to
+    /// implement `TextInputCtxEdit::replace`.
+    pub(crate) fn replace(&mut self, tx: &mut HistoryTx, range: Range<usize>, new_text: &str) {
+        // Record the change to the undo history
+        tx.replace_range(
+            self.history,
+            &*self.text,
+            range.clone(),
+            new_text.to_owned(),
+        );
+
+        // Update the selection
+        for i in self.sel_range.iter_mut() {
+            if *i >= range.end {
+                *i = *i - range.len() + new_text.len();
+            } else if *i >= range.start {
+                *i = range.start;
+            }
+        }
+
+        // Update the text
+        self.text.replace_range(range, new_text);
+    }
+
+    /// Revert the last undoable operation. Returns `false` if there's no such
+    /// operation.
+    pub(crate) fn undo(&mut self) -> bool {
+        if let Some(edit) = self.history.undo() {
+            log::debug!("Undoing {:?}", edit);
+
+            // Revert `edit`
+            debug_assert_eq!(self.text.slice(edit.range_new()), edit.new[..]);
+            self.text.replace_range(edit.range_new(), &edit.old);
+
+            let sel_range = edit.range_old();
+            *self.sel_range = [sel_range.start, sel_range.end];
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Re-apply the last redoable operation. Returns `false` if there's no
+    /// such operation.
+    pub(crate) fn redo(&mut self) -> bool {
+        if let Some(edit) = self.history.redo() {
+            log::debug!("Redoing {:?}", edit);
+
+            // Re-apply `edit`
+            debug_assert_eq!(self.text.slice(edit.range_old()), edit.old[..]);
+            self.text.replace_range(edit.range_old(), &edit.new);
+
+            let sel_range = edit.range_new();
+            *self.sel_range = [sel_range.start, sel_range.end];
+
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Convert a selection range, whose endpoints can be in any order, to
+/// `Range<usize>`.
+pub(crate) fn sorted_range([start, end]: [usize; 2]) -> Range<usize> {
+    if start <= end {
+        start..end
+    } else {
+        end..start
+    }
+}
+
+/// Validate the clipboard and undo actions shared by the text input widgets.
+/// Returns an empty set for other actions.
+pub(crate) fn validate_edit_action(
+    wm: pal::Wm,
+    action: ActionId,
+    sel_range: [usize; 2],
+    history: &History,
+) -> ActionStatus {
+    let enabled = match action {
+        actions::COPY | actions::CUT => sel_range[0] != sel_range[1],
+        actions::PASTE | actions::PASTE_AS_PLAIN_TEXT => wm
+            .clipboard_formats()
+            .contains(pal::ClipboardFormatFlags::TEXT),
+        actions::UNDO => history.can_undo(),
+        actions::REDO => history.can_redo(),
+        _ => return ActionStatus::empty(),
+    };
+
+    if enabled {
+        ActionStatus::VALID | ActionStatus::ENABLED
+    } else {
+        ActionStatus::VALID
+    }
+}
+
+/// Implemented by the shared state (`Inner`) of a text input widget to provide
+/// the clipboard operations and the caret-blinking timer.
+pub(crate) trait EditorHost: Sized + 'static {
+    fn wm(&self) -> pal::Wm;
+
+    /// Get the view hosting the text, or `None` if it has been destroyed.
+    fn view(&self) -> Option<HView>;
+
+    /// Borrow the caret-blinking timer state.
+    fn caret_blink(&self) -> RefMut<'_, CaretBlink>;
+
+    /// Get the selected text. Returns `None` if the selection is empty.
+    ///
+    /// The implementation must not keep the widget's state borrowed after
+    /// returning because the caller proceeds to call `set_clipboard_text`,
+    /// which might run a nested event loop.
+    fn selected_text(&self) -> Option<String>;
+
+    /// Normalize the line breaks in a text pasted from the clipboard.
+    fn normalize_pasted_text(text: &str) -> Cow<'_, str>;
+
+    /// Replace the selected text with `text`.
+    fn insert(self: Rc<Self>, view: HViewRef<'_>, text: &str);
+}
+
+/// Copy the selected text to the clipboard. Returns `false` if there is no
+/// selected text.
+pub(crate) fn copy_selection(host: &impl EditorHost) -> bool {
+    if let Some(text) = host.selected_text() {
+        log::trace!("... copying {:?}", text);
+        host.wm().set_clipboard_text(&text);
+        true
+    } else {
+        false
+    }
+}
+
+/// Replace the selected text with the clipboard contents.
+pub(crate) fn paste<H: EditorHost>(host: &Rc<H>) {
+    let wm = host.wm();
+    let host = Rc::downgrade(host);
+
+    wm.get_clipboard_text_async(move |_, text| {
+        let host = if let Some(host) = host.upgrade() {
+            host
+        } else {
+            return;
+        };
+        let view = if let Some(view) = host.view() {
+            view
+        } else {
+            return;
+        };
+
+        if let Some(text) = text {
+            log::trace!("... pasting {:?}", text);
+            let text = H::normalize_pasted_text(&text);
+            host.insert(view.as_ref(), &text);
+        } else {
+            log::debug!("The clipboard doesn't contain a text, ignoring PASTE");
+        }
+    });
+}
+
+/// The state of the timer used for making the caret blink.
+#[derive(Debug)]
+pub(crate) struct CaretBlink {
+    /// `true` if the caret is in the visible phase.
+    visible: bool,
+    timer: Option<pal::HInvoke>,
+}
+
+impl CaretBlink {
+    pub(crate) fn new() -> Self {
+        Self {
+            visible: true,
+            timer: None,
+        }
+    }
+
+    /// Get a flag indicating whether the caret is in the visible phase.
+    pub(crate) fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Restart the blinking cycle from the visible phase.
+    pub(crate) fn show(&mut self) {
+        self.visible = true;
+    }
+
+    /// Reset the timer. This method is also responsible for starting or
+    /// stopping the timer as needed.
+    ///
+    /// `has_caret` indicates whether the selection is empty, i.e., whether
+    /// there's a caret to blink. `override_focus` overrides the result of
+    /// `improper_subview_is_focused` used while deciding whether the timer
+    /// should be running or not.
+    pub(crate) fn reset<H: EditorHost>(
+        &mut self,
+        hview: HViewRef<'_>,
+        host: RcBorrow<'_, H>,
+        has_caret: bool,
+        override_focus: Option<bool>,
+    ) {
+        let wm = host.wm();
+        if let Some(hinv) = self.timer.take() {
+            wm.cancel_invoke(&hinv);
+        }
+
+        let should_start_timer =
+            override_focus.unwrap_or_else(|| hview.improper_subview_is_focused()) && has_caret;
+
+        if should_start_timer {
+            self.timer = Some(Self::schedule(RcBorrow::upgrade(host)));
+        } else {
+            log::trace!("Not scheduling a deferred invocation because the caret is invisible now");
+        }
+    }
+
+    /// Schedule a deferred invocation which toggles `visible` and get the
+    /// handle representing the invocation.
+    ///
+    /// This is implemented as a free function to allow recursive calls.
+    fn schedule<H: EditorHost>(host: Rc<H>) -> pal::HInvoke {
+        use std::time::Duration;
+
+        log::trace!("Scheduling a deferred invocation for blinking the caret");
+
+        // TODO: Retrieve the preferred period from the operating system
+        host.wm().invoke_after(
+            Duration::from_millis(400)..Duration::from_millis(700),
+            move |_| {
+                if let Some(hview) = host.view() {
+                    // Toggle the caret's visibility
+                    let mut caret_blink = host.caret_blink();
+                    caret_blink.visible = !caret_blink.visible;
+                    hview.pend_update();
+
+                    // Schedule the next invocation
+                    caret_blink.timer = Some(Self::schedule(Rc::clone(&host)));
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_undo_redo() {
+        let mut text = "Pony".to_owned();
+        let mut sel_range = [4, 4];
+        let mut history = History::new();
+        let mut editor = Editor {
+            text: &mut text,
+            sel_range: &mut sel_range,
+            history: &mut history,
+        };
+
+        assert!(editor.insert(" ipsum"));
+        assert_eq!(*editor.sel_range, [10, 10]);
+
+        // The endpoints can be in any order
+        assert!(editor.delete([4, 0]));
+        assert!(!editor.delete([0, 0]));
+        assert_eq!(editor.text, " ipsum");
+        assert_eq!(*editor.sel_range, [0, 0]);
+
+        assert!(editor.undo());
+        assert_eq!(editor.text, "Pony ipsum");
+        assert_eq!(*editor.sel_range, [0, 4]);
+        assert_eq!(editor.selected_text().as_deref(), Some("Pony"));
+
+        assert!(editor.undo());
+        assert_eq!(editor.text, "Pony");
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert_eq!(editor.text, "Pony ipsum");
+        assert_eq!(*editor.sel_range, [4, 10]);
+    }
+}
@@ -1,11 +1,34 @@
+//! Provides an undo history for text editing widgets, built upon the history
+//! coalescing algorithm implemented by [`history`].
+//!
+//! [`history`]: super::history
 use neo_linked_list::LinkedList;
 use std::ops::Range;
 
-use crate::ui::editing::history;
+use super::history;
+
+/// The text storage from which [`History`] extracts the original text of
+/// replaced ranges.
+pub(crate) trait HistoryText {
+    /// Get a slice of the text. `range` must lie on character boundaries.
+    fn slice(&self, range: Range<usize>) -> String;
+}
+
+impl HistoryText for str {
+    fn slice(&self, range: Range<usize>) -> String {
+        self[range].to_owned()
+    }
+}
+
+impl HistoryText for String {
+    fn slice(&self, range: Range<usize>) -> String {
+        self[range].to_owned()
+    }
+}
 
 /// Undo history
 #[derive(Debug)]
-pub(super) struct History {
+pub(crate) struct History {
     coalescing_state: history::CoalescingState<String>,
     op_list: OpList,
 }
@@ -13,7 +36,7 @@ pub(super) struct History {
 /// Transaction
 ///
 /// Invariant: `redo_depth` is `0` when a transaction is active.
-pub(super) struct HistoryTx {
+pub(crate) struct HistoryTx {
     coalescing_state_tx: history::CoalescingStateTx<String>,
 }
 
@@ -38,7 +61,7 @@ type Edit = history::Edit<String>;
 const MAX_HISTORY_SIZE: usize = 2048;
 
 impl History {
-    pub(super) fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             coalescing_state: history::CoalescingState::new(),
             op_list: OpList {
@@ -49,7 +72,7 @@ impl History {
         }
     }
 
-    pub(super) fn start_transaction(&mut self) -> HistoryTx {
+    pub(crate) fn start_transaction(&mut self) -> HistoryTx {
         self.op_list.clear_redo();
         debug_assert_eq!(self.op_list.redo_depth, 0);
 
@@ -60,24 +83,24 @@ impl History {
         }
     }
 
-    pub(super) fn mark_logical_op_break(&mut self) {
+    pub(crate) fn mark_logical_op_break(&mut self) {
         log::trace!("Resetting `CoalescingState`");
         self.coalescing_state.reset();
     }
 
-    pub(super) fn can_undo(&self) -> bool {
+    pub(crate) fn can_undo(&self) -> bool {
         // Complexity note: `len()` takes an `O(len())` time because
         // `neo_linked_list::LinkedList` doesn't track the element count
         self.op_list.ops.len() - self.op_list.redo_depth > 0
     }
 
-    pub(super) fn can_redo(&self) -> bool {
+    pub(crate) fn can_redo(&self) -> bool {
         self.op_list.redo_depth > 0
     }
 
     /// Get the first undoable operation. The history state is modified assuming
     /// that the operation is "undo-ed" by the caller.
-    pub(super) fn undo(&mut self) -> Option<&Edit> {
+    pub(crate) fn undo(&mut self) -> Option<&Edit> {
         if !self.can_undo() {
             return None;
         }
@@ -95,7 +118,7 @@ impl History {
 
     /// Get the first redoable operation. The history state is modified assuming
     /// that the operation is "redo-ed" by the caller.
-    pub(super) fn redo(&mut self) -> Option<&Edit> {
+    pub(crate) fn redo(&mut self) -> Option<&Edit> {
         if !self.can_redo() {
             return None;
         }
@@ -113,7 +136,7 @@ impl History {
 }
 
 impl HistoryTx {
-    pub fn finish(self, history: &mut History, text: &str) {
+    pub(crate) fn finish(self, history: &mut History, text: &dyn HistoryText) {
         log::trace!("Finishing `CoalescingStateTx`");
 
         self.coalescing_state_tx.finish(
@@ -122,14 +145,14 @@ impl HistoryTx {
         );
     }
 
-    pub fn set_composition_active(&mut self, active: bool) {
+    pub(crate) fn set_composition_active(&mut self, active: bool) {
         self.coalescing_state_tx.set_composition_active(active);
     }
 
-    pub fn replace_range(
+    pub(crate) fn replace_range(
         &mut self,
         history: &mut History,
-        text: &str,
+        text: &dyn HistoryText,
         range: Range<usize>,
         new_text: String,
     ) {
@@ -155,13 +178,13 @@ impl OpList {
 }
 
 struct CoalescingCb<'a> {
-    text: &'a str,
+    text: &'a dyn HistoryText,
     op_list: &'a mut OpList,
     pending_last_edit_size_accounting: bool,
 }
 
 impl<'a> CoalescingCb<'a> {
-    fn new(text: &'a str, op_list: &'a mut OpList) -> Self {
+    fn new(text: &'a dyn HistoryText, op_list: &'a mut OpList) -> Self {
         debug_assert_eq!(op_list.redo_depth, 0);
         Self {
             text,
@@ -213,7 +236,7 @@ impl Drop for CoalescingCb<'_> {
 
 impl history::CoalescingCb<String> for CoalescingCb<'_> {
     fn slice(&mut self, range: Range<usize>) -> String {
-        self.text[range].to_owned()
+        self.text.slice(range)
     }
 
     fn push_edit(&mut self, edit: Edit) {
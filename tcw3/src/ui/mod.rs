@@ -20,21 +20,25 @@ pub mod views {
     mod checkbox;
     mod entry;
     mod label;
+    mod messagebox;
     pub mod scrollbar;
     pub mod slider;
     mod spacer;
     pub mod split;
     pub mod table;
+    mod textarea;
     pub use self::{
         button::Button,
         checkbox::{Checkbox, RadioButton},
         entry::{Entry, EntryCore},
         label::Label,
+        messagebox::MessageBox,
         scrollbar::ScrollbarRaw,
         slider::{Slider, SliderRaw},
         spacer::{new_spacer, Spacer},
         split::Split,
         table::{ScrollableTable, Table},
+        textarea::TextArea,
     };
     tcw3_meta::designer_impl! { crate::ui::views::SpacerWidget }
     tcw3_meta::designer_impl! { crate::ui::views::FixedSpacer }
@@ -42,6 +46,7 @@ pub mod views {
 
 /// Theming support
 pub mod theming {
+    mod anim;
     mod manager;
     mod style;
     mod stylesheet;
@@ -52,9 +57,9 @@ pub mod theming {
         manager::{Elem, ElemChangeCb, HElem, Manager, PropKindFlags},
         style::{
             elem_id, mk_prop_by_snake_name, mk_prop_value_by_prop_snake_name,
-            mk_wrap_dynvalue_by_prop_snake_name, mk_wrap_value_by_prop_snake_name, roles, ClassSet,
-            Col, ElemClassPath, GetPropValue, LayerId, LayerXform, Layouter, Metrics, Prop,
-            PropValue, Role, Row,
+            mk_wrap_dynvalue_by_prop_snake_name, mk_wrap_value_by_prop_snake_name, roles,
+            transition_kinds, ClassSet, Col, Easing, ElemClassPath, GetPropValue, LayerId,
+            LayerXform, Layouter, Metrics, Prop, PropValue, Role, Row, Transition, TransitionKind,
         },
         stylesheet::*,
         view::{ModifyArrangementArgs, StyledBox, StyledBoxOverride},
@@ -73,7 +78,9 @@ mod scrolling {
 
 /// Text editing support
 pub mod editing {
+    pub(crate) mod editor;
     pub mod history;
+    pub(crate) mod undo;
 }
 
 /// Re-exports some traits from the `ui` module.
@@ -0,0 +1,273 @@
+//! Interpolates the computed values of styling props for [`Transition`]s.
+use cgmath::{Point2, Rad, Vector2};
+use std::time::Instant;
+
+use super::style::{LayerXform, Metrics, Transition};
+use crate::pal::RGBAF32;
+
+/// A prop value type that can be animated.
+pub(super) trait Interpolate: Clone {
+    /// Calculate the value at the point `t` (usually but not necessarily in
+    /// range `[0, 1]`) between `self` and `to`.
+    fn interpolate(&self, to: &Self, t: f32) -> Self;
+
+    /// Return `true` if `self` and `other` are indistinguishable. A change
+    /// between such values does not start a new transition.
+    fn same_as(&self, other: &Self) -> bool;
+}
+
+fn lerp(x: f32, y: f32, t: f32) -> f32 {
+    x + (y - x) * t
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        // Non-finite values (e.g., NaN, which is used to mean "unspecified"
+        // by `Metrics`) cannot be interpolated, so switch to the target value
+        // immediately
+        if self.is_finite() && to.is_finite() {
+            lerp(*self, *to, t)
+        } else {
+            *to
+        }
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        self == other || (self.is_nan() && other.is_nan())
+    }
+}
+
+impl Interpolate for RGBAF32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        // Interpolate in the premultiplied space so that the color channels of
+        // a fully transparent endpoint do not show up
+        let a = lerp(self.a, to.a, t);
+        if a <= 0.0 {
+            return RGBAF32::new(to.r, to.g, to.b, 0.0);
+        }
+        let channel = |x: f32, y: f32| lerp(x * self.a, y * to.a, t) / a;
+        RGBAF32::new(
+            channel(self.r, to.r),
+            channel(self.g, to.g),
+            channel(self.b, to.b),
+            a,
+        )
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl Interpolate for Metrics {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        let mut out = *to;
+        for (out, (x, y)) in out
+            .margin
+            .iter_mut()
+            .zip(self.margin.iter().zip(&to.margin))
+        {
+            *out = x.interpolate(y, t);
+        }
+        out.size.x = self.size.x.interpolate(&to.size.x, t);
+        out.size.y = self.size.y.interpolate(&to.size.y, t);
+        out
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        self.margin
+            .iter()
+            .zip(other.margin.iter())
+            .all(|(x, y)| x.same_as(y))
+            && self.size.x.same_as(&other.size.x)
+            && self.size.y.same_as(&other.size.y)
+    }
+}
+
+impl Interpolate for LayerXform {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        LayerXform {
+            anchor: Point2::new(
+                self.anchor.x.interpolate(&to.anchor.x, t),
+                self.anchor.y.interpolate(&to.anchor.y, t),
+            ),
+            scale: [
+                self.scale[0].interpolate(&to.scale[0], t),
+                self.scale[1].interpolate(&to.scale[1], t),
+            ],
+            rotate: Rad(self.rotate.0.interpolate(&to.rotate.0, t)),
+            translate: Vector2::new(
+                self.translate.x.interpolate(&to.translate.x, t),
+                self.translate.y.interpolate(&to.translate.y, t),
+            ),
+        }
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        self.anchor == other.anchor
+            && self.scale == other.scale
+            && self.rotate == other.rotate
+            && self.translate == other.translate
+    }
+}
+
+/// Tracks the transition of a single prop value.
+#[derive(Debug, Clone)]
+pub(super) struct AnimatedValue<T> {
+    from: T,
+    to: T,
+    /// The starting time of the current transition. `None` if there is no
+    /// transition in progress.
+    start: Option<Instant>,
+    transition: Transition,
+}
+
+impl<T: Interpolate> AnimatedValue<T> {
+    /// Construct an `AnimatedValue` holding `value` with no transition in
+    /// progress.
+    pub fn new(value: T) -> Self {
+        Self {
+            from: value.clone(),
+            to: value,
+            start: None,
+            transition: Transition::default(),
+        }
+    }
+
+    /// Change the target value. A new transition described by `transition`
+    /// starts from the current value if `value` is different from the
+    /// current target.
+    pub fn set_target(&mut self, value: T, transition: Transition, now: Instant) {
+        if value.same_as(&self.to) {
+            return;
+        }
+
+        if transition.is_animated() {
+            self.from = self.value_at(now);
+            self.start = Some(now);
+            self.transition = transition;
+        } else {
+            self.start = None;
+        }
+
+        self.to = value;
+    }
+
+    /// Get the progress of the current transition, which is in range
+    /// `[0, 1]`.
+    fn progress(&self, now: Instant) -> Option<f32> {
+        self.start.map(|start| {
+            let elapsed = now.saturating_duration_since(start).as_secs_f32();
+            (elapsed / self.transition.duration).min(1.0)
+        })
+    }
+
+    /// Get the value at the time `now`.
+    pub fn value_at(&self, now: Instant) -> T {
+        match self.progress(now) {
+            Some(t) if t < 1.0 => {
+                let t = self.transition.easing.eval(t);
+                self.from.interpolate(&self.to, t)
+            }
+            _ => self.to.clone(),
+        }
+    }
+
+    /// Return `true` if the value is still changing at the time `now`.
+    pub fn is_running(&self, now: Instant) -> bool {
+        self.progress(now).map_or(false, |t| t < 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::theming::Easing;
+    use std::time::Duration;
+
+    #[test]
+    fn easing_endpoints() {
+        for &easing in &[
+            Easing::Linear,
+            Easing::Ease,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::CubicBezier(0.1, 0.7, 1.0, 0.1),
+        ] {
+            assert!(easing.eval(0.0).abs() < 1.0e-4, "{:?}", easing);
+            assert!((easing.eval(1.0) - 1.0).abs() < 1.0e-4, "{:?}", easing);
+        }
+
+        assert_eq!(Easing::Linear.eval(0.25), 0.25);
+        assert!((Easing::EaseInOut.eval(0.5) - 0.5).abs() < 1.0e-4);
+        assert!(Easing::EaseIn.eval(0.5) < 0.5);
+        assert!(Easing::EaseOut.eval(0.5) > 0.5);
+    }
+
+    #[test]
+    fn animated_value() {
+        let t0 = Instant::now();
+        let at = |ms: u64| t0 + Duration::from_millis(ms);
+        let transition = Transition {
+            duration: 0.2,
+            easing: Easing::Linear,
+        };
+
+        let mut value = AnimatedValue::new(0.0f32);
+        assert!(!value.is_running(t0));
+
+        value.set_target(1.0, transition, t0);
+        assert!(value.is_running(at(100)));
+        assert!((value.value_at(at(100)) - 0.5).abs() < 1.0e-4);
+        assert!(!value.is_running(at(200)));
+        assert_eq!(value.value_at(at(300)), 1.0);
+
+        // Setting the same target does not restart the transition
+        value.set_target(1.0, transition, at(300));
+        assert!(!value.is_running(at(300)));
+
+        // A new transition starts from the current value
+        value.set_target(0.0, transition, at(300));
+        value.set_target(1.0, transition, at(350));
+        assert!((value.value_at(at(350)) - 0.75).abs() < 1.0e-4);
+
+        // A zero duration makes the change instant
+        value.set_target(0.0, Transition::default(), at(400));
+        assert_eq!(value.value_at(at(400)), 0.0);
+        assert!(!value.is_running(at(400)));
+    }
+
+    #[test]
+    fn interpolate_metrics() {
+        let from = Metrics {
+            margin: [0.0, std::f32::NAN, 10.0, 4.0],
+            size: Vector2::new(10.0, std::f32::NAN),
+        };
+        let to = Metrics {
+            margin: [10.0, 2.0, 20.0, std::f32::NAN],
+            size: Vector2::new(20.0, std::f32::NAN),
+        };
+
+        let m = from.interpolate(&to, 0.5);
+        assert_eq!(m.margin[0], 5.0);
+        assert_eq!(m.margin[1], 2.0);
+        assert_eq!(m.margin[2], 15.0);
+        assert!(m.margin[3].is_nan());
+        assert_eq!(m.size.x, 15.0);
+        assert!(m.size.y.is_nan());
+
+        assert!(from.same_as(&from.clone()));
+        assert!(!from.same_as(&to));
+    }
+
+    #[test]
+    fn interpolate_color() {
+        let from = RGBAF32::new(1.0, 0.0, 0.0, 0.0);
+        let to = RGBAF32::new(0.0, 0.0, 1.0, 1.0);
+
+        // The color of the transparent endpoint does not contribute
+        let c = from.interpolate(&to, 0.5);
+        assert_eq!(c, RGBAF32::new(0.0, 0.0, 1.0, 0.5));
+    }
+}
@@ -12,12 +12,17 @@ use tcw3_pal::mt_lazy_static;
 
 use super::{
     style::{ClassSet, ElemClassPath, GetPropValue, Prop, PropValue},
-    stylesheet::{DefaultStylesheet, RuleId, Stylesheet},
+    stylesheet::{
+        DefaultDarkStylesheet, DefaultStylesheet, RuleId, Stylesheet, MAX_RULES_PER_STYLESHEET,
+    },
 };
 use crate::{pal, prelude::*};
 
 pub(crate) type SheetId = usize;
 
+/// The maximum number of stylesheets in a stylesheet set.
+const MAX_SHEETS: usize = 0x10;
+
 pub type ManagerNewSheetSetCb = Box<dyn Fn(pal::Wm, &Manager, &mut NewSheetSetCtx<'_>)>;
 
 /// The maxiumum supported depth of styling element hierarchy.
@@ -120,6 +125,11 @@ impl Manager {
         let sheet_set = this.new_sheet_set();
         *this.sheet_set.borrow_mut() = sheet_set;
 
+        // Recreate the `SheetSet` when the system appearance changes
+        wm.set_appearance_listener(Box::new(|wm| {
+            Manager::global(wm).update_sheet_set();
+        }));
+
         this
     }
 
@@ -164,16 +174,24 @@ impl Manager {
     /// Construct a new `SheetSet` using the default stylesheet and
     /// `new_set_handlers`.
     fn new_sheet_set(&self) -> SheetSet {
+        let appearance = self.wm.appearance();
+
         let mut sheet_set = SheetSet {
             sheets: vec![Box::new(DefaultStylesheet)],
         };
 
+        if appearance == pal::Appearance::Dark {
+            // Overrides some rules of `DefaultStylesheet`
+            sheet_set.sheets.push(Box::new(DefaultDarkStylesheet));
+        }
+
         for handler in self.new_set_handlers.borrow().iter() {
             handler(
                 self.wm,
                 self,
                 &mut NewSheetSetCtx {
                     sheet_set: &mut sheet_set,
+                    appearance,
                 },
             );
         }
@@ -265,11 +283,32 @@ impl Manager {
 /// The context type passed to callback functions of type [`ManagerNewSheetSetCb`].
 pub struct NewSheetSetCtx<'a> {
     sheet_set: &'a mut SheetSet,
+    appearance: pal::Appearance,
 }
 
 impl NewSheetSetCtx<'_> {
+    /// Get the system appearance setting for which the stylesheet set is
+    /// being created.
+    ///
+    /// Stylesheets inserted later override those inserted earlier, so a
+    /// variant for a specific appearance can be implemented as a stylesheet
+    /// that only contains overriding rules.
+    pub fn appearance(&self) -> pal::Appearance {
+        self.appearance
+    }
+
     /// Insert a new `Stylesheet`.
+    ///
+    /// # Panics
+    ///
+    /// A stylesheet set can contain at most 16 stylesheets, including the
+    /// default ones.
     pub fn insert_stylesheet(&mut self, stylesheet: impl Stylesheet + 'static) {
+        assert!(
+            self.sheet_set.sheets.len() < MAX_SHEETS,
+            "too many stylesheets (the maximum is {})",
+            MAX_SHEETS
+        );
         self.sheet_set.sheets.push(Box::new(stylesheet));
     }
 }
@@ -339,9 +378,15 @@ impl fmt::Debug for RuleTag {
 
 impl RuleTag {
     fn new(sheet_id: SheetId, rule_id: RuleId, pri: i16) -> Self {
-        // These limitations are based on Internet Explorer 8 and earlier
-        debug_assert!(sheet_id < 0x10);
-        debug_assert!(rule_id < 0x1000);
+        // These limitations are based on Internet Explorer 8 and earlier.
+        // Exceeding them would silently corrupt the other fields.
+        debug_assert!(sheet_id < MAX_SHEETS);
+        assert!(
+            rule_id < MAX_RULES_PER_STYLESHEET,
+            "rule ID {} is out of range (a stylesheet can contain at most {} rules)",
+            rule_id,
+            MAX_RULES_PER_STYLESHEET
+        );
 
         let pri = pri as u32 + 0x8000;
 
@@ -403,7 +448,7 @@ bitflags! {
     /// properties have to be recomputed. It would be inefficient to precisely
     /// track every property, so we categorize the properties into coarse groups
     /// and track changes in this unit.
-    pub struct PropKindFlags: u16 {
+    pub struct PropKindFlags: u32 {
         const NUM_LAYERS = 1;
         const LAYER_IMG = 1 << 1;
         const LAYER_BOUNDS = 1 << 2;
@@ -412,6 +457,8 @@ bitflags! {
         const LAYER_CENTER = 1 << 5;
         const LAYER_XFORM = 1 << 6;
         const LAYER_FLAGS = 1 << 7;
+        const LAYER_CORNER_RADII = 1 << 8;
+        const LAYER_SHADOW = 1 << 9;
         /// Any properties of decorative layers.
         const LAYER_ALL = Self::NUM_LAYERS.bits |
             Self::LAYER_IMG.bits |
@@ -420,13 +467,16 @@ bitflags! {
             Self::LAYER_OPACITY.bits |
             Self::LAYER_CENTER.bits |
             Self::LAYER_XFORM.bits |
-            Self::LAYER_FLAGS.bits;
-        const CLIP_LAYER = 1 << 8;
-        const LAYOUT = 1 << 9;
-        const FONT = 1 << 10;
-        const FG_COLOR = 1 << 11;
-        const BG_COLOR = 1 << 12;
-        const PADDING = 1 << 13;
+            Self::LAYER_FLAGS.bits |
+            Self::LAYER_CORNER_RADII.bits |
+            Self::LAYER_SHADOW.bits;
+        const CLIP_LAYER = 1 << 10;
+        const LAYOUT = 1 << 11;
+        const FONT = 1 << 12;
+        const FG_COLOR = 1 << 13;
+        const BG_COLOR = 1 << 14;
+        const PADDING = 1 << 15;
+        const TRANSITION = 1 << 16;
     }
 }
 
@@ -441,6 +491,8 @@ impl Prop {
             Prop::LayerCenter(_) => PropKindFlags::LAYER_CENTER,
             Prop::LayerXform(_) => PropKindFlags::LAYER_XFORM,
             Prop::LayerFlags(_) => PropKindFlags::LAYER_FLAGS,
+            Prop::LayerCornerRadii(_) => PropKindFlags::LAYER_CORNER_RADII,
+            Prop::LayerShadow(_) => PropKindFlags::LAYER_SHADOW,
             Prop::SubviewLayouter => PropKindFlags::LAYOUT,
             Prop::SubviewPadding => PropKindFlags::LAYOUT,
             Prop::SubviewMetrics(_) => PropKindFlags::LAYOUT,
@@ -456,6 +508,7 @@ impl Prop {
             Prop::BgColor => PropKindFlags::BG_COLOR,
             Prop::Font => PropKindFlags::FONT,
             Prop::Padding => PropKindFlags::PADDING,
+            Prop::Transition(_) => PropKindFlags::TRANSITION,
         }
     }
 }
@@ -789,3 +842,34 @@ impl ElemRules {
         flags
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{prelude::*, use_testing_wm};
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn appearance_change(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let manager = Manager::global(wm);
+
+        let elem = Elem::new(manager);
+        elem.set_class_set(ClassSet::SLIDER);
+        twm.step_unsend();
+
+        let light_color = elem.computed_values().layer_bg_color(0);
+
+        // The stylesheet set is recreated when the appearance changes
+        twm.set_appearance(pal::Appearance::Dark);
+        twm.step_unsend();
+
+        let dark_color = elem.computed_values().layer_bg_color(0);
+        assert_ne!(light_color, dark_color);
+
+        twm.set_appearance(pal::Appearance::Light);
+        twm.step_unsend();
+
+        assert_eq!(elem.computed_values().layer_bg_color(0), light_color);
+    }
+}
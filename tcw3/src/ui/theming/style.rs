@@ -4,7 +4,7 @@ use cgmath::{Matrix3, Point2, Rad, Vector2};
 use rob::Rob;
 
 use crate::{
-    pal::{LayerFlags, SysFontType, RGBAF32},
+    pal::{LayerFlags, LayerShadow, SysFontType, RGBAF32},
     ui::AlignFlags,
 };
 
@@ -165,8 +165,10 @@ def_prop_value! {
         LayerXform(Rob<'static, LayerXform>),
         SysFontType(SysFontType),
         LayerFlags(LayerFlags),
+        LayerShadow(Option<LayerShadow>),
         Layouter(Layouter),
         AlignFlags(AlignFlags),
+        Transition(Transition),
     }
 }
 
@@ -184,6 +186,26 @@ pub type Col = u32;
 /// Zero-based row index.
 pub type Row = u32;
 
+/// Identifies a group of props animated by [`Prop::Transition`]. See
+/// [`transition_kinds`] for the possible values.
+pub type TransitionKind = u32;
+
+/// Transition kinds
+///
+/// Each constant is named after the prop it animates. For example,
+/// `LAYER_OPACITY` controls how [`Prop::LayerOpacity`] is animated for every
+/// layer of an element.
+///
+/// [`Prop::LayerOpacity`]: super::Prop::LayerOpacity
+pub mod transition_kinds {
+    iota::iota! {
+        pub const LAYER_BG_COLOR: super::TransitionKind = iota;
+                , LAYER_OPACITY
+                , LAYER_METRICS
+                , LAYER_XFORM
+    }
+}
+
 def_prop! {
     /// Represents a single styling property.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -237,6 +259,19 @@ def_prop! {
         #[default(PropValue::LayerFlags(LayerFlags::default()))]
         LayerFlags(LayerId),
 
+        /// The corner radii of the `n`-th layer in the order of top-left,
+        /// top-right, bottom-right, and bottom-left.
+        #[snake_case(layer_corner_radii)]
+        #[default(PropValue::F32x4([0.0; 4]))]
+        LayerCornerRadii(LayerId),
+
+        /// The [`LayerShadow`] of the `n`-th layer.
+        ///
+        /// [`LayerShadow`]: crate::pal::LayerShadow
+        #[snake_case(layer_shadow)]
+        #[default(PropValue::LayerShadow(None))]
+        LayerShadow(LayerId),
+
         /// The layout algorithm for subviews. Defaults to [`Layouter::Abs`].
         #[snake_case(subview_layouter)]
         #[default(PropValue::Layouter(Layouter::Abs))]
@@ -317,6 +352,12 @@ def_prop! {
         #[snake_case(padding)]
         #[default(PropValue::F32x4([0.0; 4]))]
         Padding,
+
+        /// The [`Transition`] applied when the props of the specified
+        /// [`TransitionKind`] change.
+        #[snake_case(transition)]
+        #[default(PropValue::Transition(Transition::default()))]
+        Transition(TransitionKind),
     }
 
     /// Provides accessor methods for the computed values of styling props.
@@ -468,3 +509,96 @@ impl LayerXform {
             * Matrix3::from_translation(-anchor)
     }
 }
+
+/// Describes how a prop's computed value changes over time when it is
+/// updated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    /// The duration of the transition, measured in seconds. The change
+    /// happens instantly if this is zero.
+    pub duration: f32,
+    /// The timing function.
+    pub easing: Easing,
+}
+
+impl Transition {
+    pub const fn default() -> Self {
+        Self {
+            duration: 0.0,
+            easing: Easing::Ease,
+        }
+    }
+
+    /// Return `true` if the transition has a non-zero duration.
+    pub fn is_animated(&self) -> bool {
+        self.duration > 0.0
+    }
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
+/// A timing function, which maps the elapsed fraction of a transition to
+/// the progress of the animated value. The variants correspond to CSS's
+/// `transition-timing-function`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    Ease,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// A cubic Bézier curve defined by two control points `(x1, y1)` and
+    /// `(x2, y2)`. `x1` and `x2` must be in range `[0, 1]`.
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// Get the control points of the cubic Bézier curve representing the
+    /// timing function.
+    fn control_points(&self) -> [f32; 4] {
+        match *self {
+            Easing::Linear => [0.0, 0.0, 1.0, 1.0],
+            Easing::Ease => [0.25, 0.1, 0.25, 1.0],
+            Easing::EaseIn => [0.42, 0.0, 1.0, 1.0],
+            Easing::EaseOut => [0.0, 0.0, 0.58, 1.0],
+            Easing::EaseInOut => [0.42, 0.0, 0.58, 1.0],
+            Easing::CubicBezier(x1, y1, x2, y2) => [x1, y1, x2, y2],
+        }
+    }
+
+    /// Evaluate the timing function at `t` (the elapsed fraction of the
+    /// transition). `t` is clamped to range `[0, 1]`.
+    pub fn eval(&self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        if let Easing::Linear = self {
+            return t;
+        }
+
+        let [x1, y1, x2, y2] = self.control_points();
+
+        // Find the curve parameter `s` such that `x(s) = t` by bisection.
+        // `x(s)` is monotonically increasing because `x1, x2 ∈ [0, 1]`.
+        let (mut lo, mut hi) = (0.0f32, 1.0f32);
+        for _ in 0..24 {
+            let mid = (lo + hi) * 0.5;
+            if eval_cubic_bezier_1d(x1, x2, mid) < t {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        eval_cubic_bezier_1d(y1, y2, (lo + hi) * 0.5)
+    }
+}
+
+/// Evaluate a one-dimensional cubic Bézier curve with end points `0` and
+/// `1` and control points `c1` and `c2`.
+fn eval_cubic_bezier_1d(c1: f32, c2: f32, s: f32) -> f32 {
+    let r = 1.0 - s;
+    3.0 * r * r * s * c1 + 3.0 * r * s * s * c2 + s * s * s
+}
@@ -73,6 +73,22 @@ macro_rules! def_prop {
             }
         }
 
+        impl Prop {
+            /// Construct a `Prop` from its snake-cased name (the one used by
+            /// [`stylesheet!`]) and an optional parameter.
+            ///
+            /// Returns `None` if there is no such prop or the presence of
+            /// `param` does not match the prop's definition.
+            pub fn from_snake_name(name: &str, param: Option<u32>) -> Option<Self> {
+                $(
+                    if name == stringify!($snake_name) {
+                        return prop_var_from_param!($name$(($param_ty))?, param);
+                    }
+                )*
+                None
+            }
+        }
+
         /// Helper items for `stylesheet!`. Reexports `Prop`'s constructors
         /// under snake-cased names.
         #[doc(hidden)]
@@ -163,6 +179,20 @@ macro_rules! prop_var {
     };
 }
 
+/// Map `X` → `Prop::X` if `$p` is `None`, `X(u32)` → `Prop::X(x)` if `$p` is
+/// `Some(x)`. Used by `def_prop`.
+macro_rules! prop_var_from_param {
+    ($name:ident, $p:expr) => {
+        match $p {
+            None => Some(Prop::$name),
+            Some(_) => None,
+        }
+    };
+    ($name:ident($t:ty), $p:expr) => {
+        $p.map(Prop::$name)
+    };
+}
+
 /// Generates a function that preprocesses a value before passing it to
 /// `PropValue`'s constructor. Used by `def_prop`.
 macro_rules! def_wrap_value {
@@ -6,9 +6,16 @@ use super::{
     style::{elem_id, roles, ClassSet, ElemClassPath, Metrics, Prop, PropValue},
 };
 
+mod text;
+pub use self::text::{TextStylesheet, TextStylesheetError, TextStylesheetSymbols};
+
 /// Represents a single stylesheet rule in [`Stylesheet`].
 pub type RuleId = usize;
 
+/// The maximum number of rules in a single [`Stylesheet`]. Every `RuleId`
+/// must be less than this value.
+pub const MAX_RULES_PER_STYLESHEET: usize = 0x1000;
+
 pub trait Stylesheet {
     /// Enumerate rules that apply to the specifed `ElemClassPath`.
     ///
@@ -66,7 +73,7 @@ pub struct Selector {
 }
 
 #[doc(hidden)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct ElemCriteria {
     pub pos: ClassSet,
     pub neg: ClassSet,
@@ -115,41 +122,51 @@ impl Rule {
 
 impl Selector {
     fn matches(&self, path: &ElemClassPath) -> bool {
-        let mut it = path.iter().rev();
-        if !self.target.matches(&it.next().unwrap()) {
-            return false;
-        }
+        selector_matches(&self.target, self.ancestors, path)
+    }
+}
 
-        let mut cur_maybe = it.next();
+/// The implementation of `Selector::matches`. This is shared with other
+/// `Stylesheet` implementations that store selectors in a different way.
+fn selector_matches(
+    target: &ElemCriteria,
+    ancestors: &[(bool, ElemCriteria)],
+    path: &ElemClassPath,
+) -> bool {
+    let mut it = path.iter().rev();
+    if !target.matches(&it.next().unwrap()) {
+        return false;
+    }
 
-        for (direct, criteria) in self.ancestors.iter() {
-            if *direct {
-                if let Some(cur) = cur_maybe {
-                    if !criteria.matches(&cur) {
-                        return false;
-                    }
-                    cur_maybe = it.next();
-                } else {
+    let mut cur_maybe = it.next();
+
+    for (direct, criteria) in ancestors.iter() {
+        if *direct {
+            if let Some(cur) = cur_maybe {
+                if !criteria.matches(&cur) {
                     return false;
                 }
+                cur_maybe = it.next();
             } else {
-                loop {
-                    if let Some(cur) = cur_maybe {
-                        if criteria.matches(&cur) {
-                            cur_maybe = it.next();
-                            break;
-                        } else {
-                            cur_maybe = it.next();
-                        }
+                return false;
+            }
+        } else {
+            loop {
+                if let Some(cur) = cur_maybe {
+                    if criteria.matches(&cur) {
+                        cur_maybe = it.next();
+                        break;
                     } else {
-                        return false;
+                        cur_maybe = it.next();
                     }
+                } else {
+                    return false;
                 }
             }
         }
-
-        true
     }
+
+    true
 }
 
 impl ElemCriteria {
@@ -508,8 +525,6 @@ macro_rules! stylesheet {
 // -----------------------------------------------------------------------------
 //  Default  stylesheet definition
 //
-// TODO: Make it dynamic (based on the operating system's configuration)
-//
 use crate::{
     images::{figures, himg_figures, himg_from_figures_with_size, HImg},
     pal::RGBAF32,
@@ -593,6 +608,32 @@ fn recolor_tint(data: &(&'static [u8], [f32; 2])) -> HImg {
     StvgImg::new(*data).with_color_xform(map_color).into_himg()
 }
 
+/// Like `recolor_tint`, but also inverts the brightness of neutral colors for
+/// a dark appearance.
+fn recolor_tint_dark(data: &(&'static [u8], [f32; 2])) -> HImg {
+    #[inline(never)]
+    fn map_color(c: RGBAF32) -> RGBAF32 {
+        let tint = [0.3, 0.6, 1.0];
+
+        // The source images are drawn in shades of gray (`r = g = b`) and
+        // blue (`r = g < b`). Decompose the color into a neutral level and
+        // the amount of blue (`s`).
+        let s = (c.b - c.g).max(0.0);
+        let level = if s < 1.0 { c.g / (1.0 - s) } else { 0.0 };
+
+        // Invert the neutral level, mapping white to a dark gray
+        let neutral = 0.15 + (1.0 - level) * 0.7;
+
+        RGBAF32::new(
+            neutral + (tint[0] - neutral) * s,
+            neutral + (tint[1] - neutral) * s,
+            neutral + (tint[2] - neutral) * s,
+            c.a,
+        )
+    }
+    StvgImg::new(*data).with_color_xform(map_color).into_himg()
+}
+
 // Import IDs (e.g., `#SHOW_MENU`) into the scope
 use elem_id::*;
 
@@ -815,6 +856,16 @@ lazy_static! {
             fg_color: RGBAF32::new(0.0, 0.0, 0.0, 1.0),
             padding: [0.0, 3.0, 0.0, 3.0],
         },
+        // `TextArea` fills the box instead of having a fixed height
+        ([.ENTRY.SCROLL_CONTAINER]) (priority = 150) {
+            subview_metrics[roles::GENERIC]: Metrics {
+                margin: [0.0; 4],
+                .. Metrics::default()
+            },
+        },
+        ([] < [.ENTRY.SCROLL_CONTAINER]) (priority = 150) {
+            padding: [3.0; 4],
+        },
         // Text selection rectangle in a text entry widget
         ([#TEXT_SELECTION]) (priority = 100) {
             bg_color: RGBAF32::new(0.3, 0.6, 1.0, 0.5),
@@ -1058,6 +1109,154 @@ lazy_static! {
     };
 }
 
+lazy_static! {
+    /// The rules of [`DefaultDarkStylesheet`].
+    static ref DEFAULT_DARK_STYLESHEET: StylesheetMacroOutput = stylesheet! {
+        // Labels don't have a default color in `DEFAULT_STYLESHEET` (i.e., the
+        // default value of `fg_color` is used), so specify one here
+        ([.LABEL]) (priority = 100) {
+            fg_color: RGBAF32::new(0.92, 0.92, 0.92, 1.0),
+        },
+
+        ([.BUTTON]) (priority = 100) {
+            // Focus ring
+            #[dyn] layer_img[0]: Some(himg_figures![
+                rect(FOCUS_RING_COLOR)
+                    .radius(2.0 + BUTTON_CORNER_RADIUS),
+                rect([0.2, 0.2, 0.2, 1.0])
+                    .radius(BUTTON_CORNER_RADIUS)
+                    .margin([2.0; 4]),
+            ]),
+
+            #[dyn] layer_img[1]: Some(himg_figures![
+                // Shadow
+                rect([0.0, 0.0, 0.0, 0.1])
+                    .radius(BUTTON_CORNER_RADIUS + 0.5)
+                    .margin([0.5; 4]),
+                rect([0.0, 0.0, 0.0, 0.2])
+                    .radius(BUTTON_CORNER_RADIUS + 0.5)
+                    .margin([1.0, 0.5, 0.0, 0.5]),
+                rect([0.0, 0.0, 0.0, 0.4])
+                    .radius(BUTTON_CORNER_RADIUS)
+                    .margin([1.5, 1.0, 0.5, 1.0]),
+                // Button face
+                rect([0.35, 0.35, 0.35, 1.0])
+                    .radius(BUTTON_CORNER_RADIUS)
+                    .margin([1.0; 4]),
+            ]),
+        },
+        ([.BUTTON.ACTIVE]) (priority = 200) {
+            #[dyn] layer_img[1]: Some(himg_figures![
+                // Shadow
+                rect([0.0, 0.0, 0.0, 0.1])
+                    .radius(BUTTON_CORNER_RADIUS + 0.5)
+                    .margin([0.5; 4]),
+                rect([0.0, 0.0, 0.0, 0.2])
+                    .radius(BUTTON_CORNER_RADIUS + 0.5)
+                    .margin([1.0, 0.5, 0.0, 0.5]),
+                rect([0.0, 0.0, 0.0, 0.4])
+                    .radius(BUTTON_CORNER_RADIUS)
+                    .margin([1.5, 1.0, 0.5, 1.0]),
+                // Button face
+                rect([0.28, 0.28, 0.28, 1.0])
+                    .radius(BUTTON_CORNER_RADIUS)
+                    .margin([1.0; 4]),
+                // Obscure the button face layer completely except the topmost
+                // 0.5px-wide area for a subtle highlight effect
+                rect([0.24, 0.24, 0.24, 1.0])
+                    .radius(BUTTON_CORNER_RADIUS)
+                    .margin([1.5, 1.0, 1.0, 1.0]),
+            ]),
+        },
+        // Button label
+        ([] < [.BUTTON]) (priority = 100) {
+            fg_color: RGBAF32::new(0.92, 0.92, 0.92, 1.0),
+        },
+
+        // Checkbox
+        ([.CHECKBOX]) (priority = 100) {
+            // Focus ring
+            #[dyn] layer_img[0]: Some(himg_from_figures_with_size(
+                figures![
+                    rect(FOCUS_RING_COLOR)
+                        .radius(4.0)
+                        .margin([1.0; 4]),
+                    rect([0.2, 0.2, 0.2, 1.0])
+                        .radius(2.0)
+                        .margin([3.0; 4]),
+                ],
+                [CHECKBOX_IMG_SIZE.x + 4.0; 2],
+            )),
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::CHECKBOX_LIGHT)),
+        },
+        ([.CHECKBOX.ACTIVE]) (priority = 200) {
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::CHECKBOX_LIGHT_ACT)),
+        },
+        ([.CHECKBOX.CHECKED]) (priority = 300) {
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::CHECKBOX_LIGHT_CHECKED)),
+        },
+        ([.CHECKBOX.ACTIVE.CHECKED]) (priority = 400) {
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::CHECKBOX_LIGHT_CHECKED_ACT)),
+        },
+
+        // Radio button
+        ([.RADIO_BUTTON]) (priority = 100) {
+            // Focus ring
+            #[dyn] layer_img[0]: Some(himg_from_figures_with_size(
+                figures![
+                    rect(FOCUS_RING_COLOR)
+                        .radius(CHECKBOX_IMG_SIZE.x * 0.5 + 1.0)
+                        .margin([1.0; 4]),
+                    rect([0.2, 0.2, 0.2, 1.0])
+                        .radius(CHECKBOX_IMG_SIZE.x * 0.5 - 1.0)
+                        .margin([3.0; 4]),
+                ],
+                [CHECKBOX_IMG_SIZE.x + 4.0; 2],
+            )),
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::RADIO_LIGHT)),
+        },
+        ([.RADIO_BUTTON.ACTIVE]) (priority = 200) {
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::RADIO_LIGHT_ACT)),
+        },
+        ([.RADIO_BUTTON.CHECKED]) (priority = 300) {
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::RADIO_LIGHT_CHECKED)),
+        },
+        ([.RADIO_BUTTON.ACTIVE.CHECKED]) (priority = 400) {
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::RADIO_LIGHT_CHECKED_ACT)),
+        },
+
+        // Checkbox label
+        ([] < [.CHECKBOX]) (priority = 100) {
+            fg_color: RGBAF32::new(0.92, 0.92, 0.92, 1.0),
+        },
+        ([] < [.RADIO_BUTTON]) (priority = 100) {
+            fg_color: RGBAF32::new(0.92, 0.92, 0.92, 1.0),
+        },
+
+        // Entry wrapper
+        ([.ENTRY]) (priority = 100) {
+            // Background
+            #[dyn] layer_img[1]: Some(himg_figures![rect([0.12, 0.12, 0.12, 1.0]).radius(3.0)]),
+        },
+        // Entry text in `Entry`
+        ([] < [.ENTRY]) (priority = 100) {
+            fg_color: RGBAF32::new(0.92, 0.92, 0.92, 1.0),
+        },
+
+        // Slider
+        ([.SLIDER]) (priority = 100) {
+            layer_bg_color[0]: RGBAF32::new(0.45, 0.45, 0.45, 1.0),
+        },
+        // Slider knob
+        ([#SLIDER_KNOB] < [.SLIDER]) (priority = 100) {
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::SLIDER_KNOB)),
+        },
+        ([#SLIDER_KNOB] < [.SLIDER.ACTIVE]) (priority = 150) {
+            #[dyn] layer_img[1]: Some(recolor_tint_dark(&assets::SLIDER_KNOB_ACT)),
+        },
+    };
+}
+
 pub(crate) struct DefaultStylesheet;
 
 impl Stylesheet for DefaultStylesheet {
@@ -1075,3 +1274,27 @@ impl Stylesheet for DefaultStylesheet {
         DEFAULT_STYLESHEET.get_rule_prop_value(id, prop)
     }
 }
+
+/// The stylesheet inserted after [`DefaultStylesheet`] when the system
+/// appearance is [`Appearance::Dark`](crate::pal::Appearance::Dark).
+///
+/// It only contains the rules that override some rules of
+/// `DefaultStylesheet`. Each rule uses the same selector and priority as the
+/// one it overrides, so it takes precedence by being inserted later.
+pub(crate) struct DefaultDarkStylesheet;
+
+impl Stylesheet for DefaultDarkStylesheet {
+    fn match_rules(&self, path: &ElemClassPath, out_rules: &mut dyn FnMut(RuleId)) {
+        DEFAULT_DARK_STYLESHEET.match_rules(path, out_rules)
+    }
+
+    fn get_rule_priority(&self, id: RuleId) -> Option<i16> {
+        DEFAULT_DARK_STYLESHEET.get_rule_priority(id)
+    }
+    fn get_rule_prop_kinds(&self, id: RuleId) -> Option<PropKindFlags> {
+        DEFAULT_DARK_STYLESHEET.get_rule_prop_kinds(id)
+    }
+    fn get_rule_prop_value(&self, id: RuleId, prop: &Prop) -> Option<Option<&PropValue>> {
+        DEFAULT_DARK_STYLESHEET.get_rule_prop_value(id, prop)
+    }
+}
@@ -0,0 +1,1328 @@
+//! Parses stylesheets written in a text format at runtime.
+use cggeom::Box2;
+use cgmath::{Point2, Rad};
+use rob::Rob;
+use std::{collections::HashMap, fmt, ops::Range, str::FromStr};
+
+use super::{selector_matches, ElemCriteria, RuleId, Stylesheet, MAX_RULES_PER_STYLESHEET};
+use crate::{
+    images::{himg_from_figures, Figure, HImg},
+    pal::{LayerFlags, LayerShadow, SysFontType, RGBAF32},
+    ui::{
+        theming::{
+            elem_id, roles, transition_kinds, ClassSet, Easing, ElemClassPath, LayerXform,
+            Layouter, Metrics, Prop, PropKindFlags, PropValue, Role, Transition, TransitionKind,
+        },
+        AlignFlags,
+    },
+};
+
+/// A [`Stylesheet`] parsed from a text representation at runtime.
+///
+/// The syntax closely follows that of [`stylesheet!`]. The main difference is
+/// that arbitrary Rust expressions are not allowed. Instead, each kind of prop
+/// value has a dedicated syntax:
+///
+/// | [`PropValue`] variant | Syntax |
+/// | --------------------- | ------ |
+/// | `Bool` | `true`, `false` |
+/// | `Bool2` | `[true, false]` |
+/// | `Float` | `1.5`, `-2`, `nan`, `inf` |
+/// | `Usize` | `42` |
+/// | `U32x2` | `[1, 2]` |
+/// | `F32x4` | `[1, 2, 3, 4]`, `[0; 4]` |
+/// | `Himg` | `none`, `figures(rect(#fff).radius(3).margin([1; 4]).line_width(1), ...)`, a name registered by [`TextStylesheetSymbols::with_img`] |
+/// | `Rgbaf32` | `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb(1, 0.5, 0)`, `rgba(1, 0.5, 0, 1)`, `[1, 0.5, 0, 1]` |
+/// | `Metrics` | `{ margin: [nan, 8, nan, nan], size: [16, 16] }` |
+/// | `Vector2`, `Point2` | `[1, 2]` |
+/// | `Box2` | `{ min: [0, 0], max: [1, 1] }`, `{ point: [0.5, 0.5] }` |
+/// | `LayerXform` | `{ anchor: [0.5, 0.5], scale: [1, 1], rotate: 0.5, translate: [0, 0] }` |
+/// | `SysFontType` | `Normal`, `Emph`, `Small`, `SmallEmph`, `User`, `UserMonospace` |
+/// | `LayerFlags` | `MASK_TO_BOUNDS \| BACKDROP_BLUR`, `empty` |
+/// | `LayerShadow` | `none`, `{ offset: [0, 2], blur_radius: 8, color: #0004 }` |
+/// | `Layouter` | `Abs`, `Table` |
+/// | `AlignFlags` | `LEFT \| TOP`, `CENTER`, `JUSTIFY`, etc. |
+/// | `Transition` | `{ duration: 0.2, easing: EaseInOut }`, `{ duration: 0.2, easing: CubicBezier(0.1, 0.7, 1, 0.1) }` |
+///
+/// Omitted fields of `Metrics`, `LayerXform`, and `Transition` take their
+/// default values. Omitted fields of `LayerShadow` default to zero (`offset`
+/// and `blur_radius`) and opaque black (`color`). Angles (`rotate`) are
+/// measured in radians. Durations are measured in seconds.
+///
+/// Class names (`.HOVER`) refer to the constants of [`ClassSet`]. ID values
+/// (`#SPLITTER`) and prop parameters (`subview_metrics[GENERIC]`) can refer
+/// to the constants defined in [`elem_id`] and [`roles`], respectively, as
+/// well as to the names registered by [`TextStylesheetSymbols`]. The
+/// parameter of `transition` refers to the constants defined in
+/// [`transition_kinds`] (`transition[LAYER_OPACITY]`). Prop parameters can
+/// also be integer literals.
+///
+/// Comments (`// ...` and `/* ... */`) are ignored.
+///
+/// [`stylesheet!`]: crate::stylesheet
+///
+/// # Examples
+///
+///     use tcw3::ui::theming::{
+///         ClassSet, Stylesheet, TextStylesheet, TextStylesheetSymbols,
+///     };
+///
+///     const CUSTOM_ID: ClassSet = ClassSet::id(42);
+///
+///     let symbols = TextStylesheetSymbols::new().with_id("CUSTOM_ID", CUSTOM_ID);
+///     let stylesheet = TextStylesheet::parse(r"
+///         ([#CUSTOM_ID.LABEL] < [.BUTTON.ACTIVE]) (priority = 100) {
+///             fg_color: #ffffff,
+///             layer_img[0]: figures(rect([0.1, 0.4, 0.8, 1.0])),
+///         }
+///     ", &symbols).unwrap();
+///
+///     let mut rules = Vec::new();
+///     stylesheet.match_rules(
+///         &[ClassSet::BUTTON | ClassSet::ACTIVE, CUSTOM_ID | ClassSet::LABEL],
+///         &mut |rule_id| rules.push(rule_id),
+///     );
+///     assert_eq!(rules.len(), 1);
+///
+#[derive(Debug, Clone)]
+pub struct TextStylesheet {
+    rules: Vec<Rule>,
+    props: Vec<(Prop, PropValue)>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    priority: i16,
+    prop_kinds: PropKindFlags,
+    /// An index range into `TextStylesheet::props`.
+    props_range: Range<usize>,
+    target: ElemCriteria,
+    ancestors: Vec<(bool, ElemCriteria)>,
+}
+
+impl TextStylesheet {
+    /// Parse a text stylesheet.
+    ///
+    /// `symbols` supplies application-defined names in addition to the
+    /// predefined ones. Pass `&Default::default()` if there are none.
+    pub fn parse(
+        source: &str,
+        symbols: &TextStylesheetSymbols,
+    ) -> Result<Self, TextStylesheetError> {
+        Parser {
+            source,
+            pos: 0,
+            symbols,
+        }
+        .stylesheet()
+    }
+}
+
+impl Stylesheet for TextStylesheet {
+    fn match_rules(&self, path: &ElemClassPath, out_rules: &mut dyn FnMut(RuleId)) {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if selector_matches(&rule.target, &rule.ancestors, path) {
+                out_rules(i);
+            }
+        }
+    }
+
+    fn get_rule_priority(&self, id: RuleId) -> Option<i16> {
+        self.rules.get(id).map(|r| r.priority)
+    }
+    fn get_rule_prop_kinds(&self, id: RuleId) -> Option<PropKindFlags> {
+        self.rules.get(id).map(|r| r.prop_kinds)
+    }
+    fn get_rule_prop_value(&self, id: RuleId, prop: &Prop) -> Option<Option<&PropValue>> {
+        self.rules.get(id).map(|r| {
+            self.props[r.props_range.clone()]
+                .iter()
+                .find(|p| p.0 == *prop)
+                .map(|p| &p.1)
+        })
+    }
+}
+
+/// Application-defined names that can be referred to by a [`TextStylesheet`].
+///
+/// A name defined here shadows a predefined one with the same name.
+#[derive(Debug, Clone, Default)]
+pub struct TextStylesheetSymbols {
+    ids: HashMap<String, ClassSet>,
+    classes: HashMap<String, ClassSet>,
+    roles: HashMap<String, Role>,
+    imgs: HashMap<String, HImg>,
+}
+
+impl TextStylesheetSymbols {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define an ID value ([`ClassSet::id`]) referred to as `#name`.
+    pub fn with_id(mut self, name: impl Into<String>, id: ClassSet) -> Self {
+        debug_assert!(ClassSet::ID_MASK.contains(id));
+        self.ids.insert(name.into(), id);
+        self
+    }
+
+    /// Define a set of class bits referred to as `.name`.
+    pub fn with_class(mut self, name: impl Into<String>, class_set: ClassSet) -> Self {
+        debug_assert!(!class_set.intersects(ClassSet::ID_MASK));
+        self.classes.insert(name.into(), class_set);
+        self
+    }
+
+    /// Define a [`Role`] that can be used as a prop parameter like
+    /// `subview_metrics[name]`.
+    pub fn with_role(mut self, name: impl Into<String>, role: Role) -> Self {
+        self.roles.insert(name.into(), role);
+        self
+    }
+
+    /// Define an image that can be used as a value of image props like
+    /// `layer_img[0]: name`.
+    pub fn with_img(mut self, name: impl Into<String>, img: HImg) -> Self {
+        self.imgs.insert(name.into(), img);
+        self
+    }
+
+    fn id(&self, name: &str) -> Option<ClassSet> {
+        self.ids
+            .get(name)
+            .cloned()
+            .or_else(|| lookup(BUILTIN_IDS, name))
+    }
+
+    fn class(&self, name: &str) -> Option<ClassSet> {
+        self.classes
+            .get(name)
+            .cloned()
+            .or_else(|| lookup(BUILTIN_CLASSES, name))
+    }
+
+    fn role(&self, name: &str) -> Option<Role> {
+        self.roles
+            .get(name)
+            .cloned()
+            .or_else(|| lookup(BUILTIN_ROLES, name))
+    }
+}
+
+const BUILTIN_IDS: &[(&str, ClassSet)] = &[
+    ("SPLITTER", elem_id::SPLITTER),
+    ("TEXT_SELECTION", elem_id::TEXT_SELECTION),
+    ("SLIDER_KNOB", elem_id::SLIDER_KNOB),
+    ("SLIDER_TICKS", elem_id::SLIDER_TICKS),
+    ("SLIDER_LABELS", elem_id::SLIDER_LABELS),
+];
+
+const BUILTIN_CLASSES: &[(&str, ClassSet)] = &[
+    ("HOVER", ClassSet::HOVER),
+    ("ACTIVE", ClassSet::ACTIVE),
+    ("FOCUS", ClassSet::FOCUS),
+    ("BUTTON", ClassSet::BUTTON),
+    ("LABEL", ClassSet::LABEL),
+    ("SCROLLBAR", ClassSet::SCROLLBAR),
+    ("VERTICAL", ClassSet::VERTICAL),
+    ("SCROLL_CONTAINER", ClassSet::SCROLL_CONTAINER),
+    (
+        "HAS_HORIZONTAL_SCROLLBAR",
+        ClassSet::HAS_HORIZONTAL_SCROLLBAR,
+    ),
+    ("HAS_VERTICAL_SCROLLBAR", ClassSet::HAS_VERTICAL_SCROLLBAR),
+    ("SLIDER", ClassSet::SLIDER),
+    ("ENTRY", ClassSet::ENTRY),
+    ("CHECKBOX", ClassSet::CHECKBOX),
+    ("CHECKED", ClassSet::CHECKED),
+    ("RADIO_BUTTON", ClassSet::RADIO_BUTTON),
+    ("USER1", ClassSet::USER1),
+];
+
+const BUILTIN_ROLES: &[(&str, Role)] = &[
+    ("GENERIC", roles::GENERIC),
+    ("HORZ_SCROLLBAR", roles::HORZ_SCROLLBAR),
+    ("VERT_SCROLLBAR", roles::VERT_SCROLLBAR),
+    ("SLIDER_KNOB", roles::SLIDER_KNOB),
+    ("SLIDER_TICKS", roles::SLIDER_TICKS),
+    ("SLIDER_LABELS", roles::SLIDER_LABELS),
+];
+
+const SYS_FONT_TYPES: &[(&str, SysFontType)] = &[
+    ("Normal", SysFontType::Normal),
+    ("Emph", SysFontType::Emph),
+    ("Small", SysFontType::Small),
+    ("SmallEmph", SysFontType::SmallEmph),
+    ("User", SysFontType::User),
+    ("UserMonospace", SysFontType::UserMonospace),
+];
+
+const LAYOUTERS: &[(&str, Layouter)] = &[("Abs", Layouter::Abs), ("Table", Layouter::Table)];
+
+const TRANSITION_KINDS: &[(&str, TransitionKind)] = &[
+    ("LAYER_BG_COLOR", transition_kinds::LAYER_BG_COLOR),
+    ("LAYER_OPACITY", transition_kinds::LAYER_OPACITY),
+    ("LAYER_METRICS", transition_kinds::LAYER_METRICS),
+    ("LAYER_XFORM", transition_kinds::LAYER_XFORM),
+];
+
+const EASINGS: &[(&str, Easing)] = &[
+    ("Linear", Easing::Linear),
+    ("Ease", Easing::Ease),
+    ("EaseIn", Easing::EaseIn),
+    ("EaseOut", Easing::EaseOut),
+    ("EaseInOut", Easing::EaseInOut),
+];
+
+const LAYER_FLAGS: &[(&str, LayerFlags)] = &[
+    ("MASK_TO_BOUNDS", LayerFlags::MASK_TO_BOUNDS),
+    ("BACKDROP_BLUR", LayerFlags::BACKDROP_BLUR),
+];
+
+const ALIGN_FLAGS: &[(&str, AlignFlags)] = &[
+    ("LEFT", AlignFlags::LEFT),
+    ("RIGHT", AlignFlags::RIGHT),
+    ("HORZ_CENTER", AlignFlags::HORZ_CENTER),
+    ("HORZ_JUSTIFY", AlignFlags::HORZ_JUSTIFY),
+    ("TOP", AlignFlags::TOP),
+    ("BOTTOM", AlignFlags::BOTTOM),
+    ("VERT_CENTER", AlignFlags::VERT_CENTER),
+    ("VERT_JUSTIFY", AlignFlags::VERT_JUSTIFY),
+    ("CENTER", AlignFlags::CENTER),
+    ("JUSTIFY", AlignFlags::JUSTIFY),
+];
+
+fn lookup<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table.iter().find(|e| e.0 == name).map(|e| e.1)
+}
+
+/// An error encountered while parsing a [`TextStylesheet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextStylesheetError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl TextStylesheetError {
+    /// Get the one-based line number of the error location.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Get the one-based column number (measured in `char`s) of the error
+    /// location.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Get the error message without the location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for TextStylesheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for TextStylesheetError {}
+
+type Result<T> = std::result::Result<T, TextStylesheetError>;
+
+/// A recursive descent parser. Most methods skip whitespace characters and
+/// comments before examining the input.
+struct Parser<'a> {
+    source: &'a str,
+    /// The current byte offset into `source`.
+    pos: usize,
+    symbols: &'a TextStylesheetSymbols,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl<'a> Parser<'a> {
+    fn error_at(&self, pos: usize, message: impl Into<String>) -> TextStylesheetError {
+        let before = &self.source[..pos];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        TextStylesheetError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    fn error(&mut self, message: impl Into<String>) -> TextStylesheetError {
+        if let Err(e) = self.skip_ws() {
+            return e;
+        }
+        self.error_at(self.pos, message)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn skip_ws(&mut self) -> Result<()> {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or_else(|| trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                if let Some(i) = trimmed.find("*/") {
+                    self.pos += i + 2;
+                } else {
+                    return Err(self.error_at(self.pos, "unterminated block comment"));
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Skip whitespace characters and return the current position.
+    fn skip_ws_pos(&mut self) -> Result<usize> {
+        self.skip_ws()?;
+        Ok(self.pos)
+    }
+
+    fn peek(&mut self) -> Result<Option<char>> {
+        self.skip_ws()?;
+        Ok(self.rest().chars().next())
+    }
+
+    /// Consume `token` if it's the next token.
+    fn eat(&mut self, token: &str) -> Result<bool> {
+        self.skip_ws()?;
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if self.eat(token)? {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", token)))
+        }
+    }
+
+    /// Consume an identifier. Returns the identifier and its position.
+    fn ident(&mut self) -> Result<(usize, &'a str)> {
+        let start = self.skip_ws_pos()?;
+        let rest = self.rest();
+        if !rest.starts_with(is_ident_start) {
+            return Err(self.error_at(start, "expected an identifier"));
+        }
+        let len = rest
+            .find(|c: char| !is_ident_continue(c))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        Ok((start, &rest[..len]))
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        let (pos, ident) = self.ident()?;
+        if ident == keyword {
+            Ok(())
+        } else {
+            Err(self.error_at(pos, format!("expected `{}`", keyword)))
+        }
+    }
+
+    /// Consume a sequence of `item`s separated by `,` and terminated by
+    /// `close`. A trailing `,` is permitted.
+    fn list(&mut self, close: &str, mut item: impl FnMut(&mut Self) -> Result<()>) -> Result<()> {
+        loop {
+            if self.eat(close)? {
+                return Ok(());
+            }
+            item(self)?;
+            if !self.eat(",")? {
+                return self.expect(close);
+            }
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    //  Rules
+
+    fn stylesheet(&mut self) -> Result<TextStylesheet> {
+        let mut sheet = TextStylesheet {
+            rules: Vec::new(),
+            props: Vec::new(),
+        };
+
+        while self.peek()?.is_some() {
+            self.rule(&mut sheet)?;
+
+            // Rules are optionally separated by `,` (like `stylesheet!`)
+            self.eat(",")?;
+        }
+
+        Ok(sheet)
+    }
+
+    fn rule(&mut self, sheet: &mut TextStylesheet) -> Result<()> {
+        if sheet.rules.len() >= MAX_RULES_PER_STYLESHEET {
+            return Err(self.error(format!(
+                "too many rules (the maximum is {})",
+                MAX_RULES_PER_STYLESHEET
+            )));
+        }
+
+        self.expect("(")?;
+        let target = self.elem()?;
+        let mut ancestors = Vec::new();
+        loop {
+            let direct = if self.eat("<")? {
+                true
+            } else if self.eat("..")? {
+                false
+            } else {
+                break;
+            };
+            ancestors.push((direct, self.elem()?));
+        }
+        self.expect(")")?;
+
+        self.expect("(")?;
+        self.expect_keyword("priority")?;
+        self.expect("=")?;
+        let priority = self.integer()?;
+        self.expect(")")?;
+
+        let start = sheet.props.len();
+        let mut prop_kinds = PropKindFlags::empty();
+
+        self.expect("{")?;
+        self.list("}", |this| {
+            let (pos, prop) = this.prop()?;
+            if sheet.props[start..].iter().any(|p| p.0 == prop) {
+                return Err(this.error_at(pos, "duplicate prop"));
+            }
+            this.expect(":")?;
+            let value = this.prop_value(&prop)?;
+
+            prop_kinds |= prop.kind_flags();
+            sheet.props.push((prop, value));
+            Ok(())
+        })?;
+
+        sheet.rules.push(Rule {
+            priority,
+            prop_kinds,
+            props_range: start..sheet.props.len(),
+            target,
+            ancestors,
+        });
+
+        Ok(())
+    }
+
+    /// Parse `[#ID.CLASS:not(.CLASS)]`.
+    fn elem(&mut self) -> Result<ElemCriteria> {
+        let mut criteria = ElemCriteria {
+            pos: ClassSet::empty(),
+            neg: ClassSet::empty(),
+        };
+
+        self.expect("[")?;
+        loop {
+            if self.eat("]")? {
+                break;
+            } else if self.eat("#")? {
+                let (pos, name) = self.ident()?;
+                let id = self
+                    .symbols
+                    .id(name)
+                    .ok_or_else(|| self.error_at(pos, format!("unknown ID `{}`", name)))?;
+                // Same as `elem_pos!` and `elem_neg!`
+                criteria.pos |= id;
+                criteria.neg |= ClassSet::ID_MASK ^ id;
+            } else if self.eat(".")? {
+                criteria.pos |= self.class()?;
+            } else if self.eat(":")? {
+                self.expect_keyword("not")?;
+                self.expect("(")?;
+                self.expect(".")?;
+                criteria.neg |= self.class()?;
+                self.expect(")")?;
+            } else {
+                return Err(self.error("expected `#`, `.`, `:not`, or `]`"));
+            }
+        }
+
+        Ok(criteria)
+    }
+
+    fn class(&mut self) -> Result<ClassSet> {
+        let (pos, name) = self.ident()?;
+        self.symbols
+            .class(name)
+            .ok_or_else(|| self.error_at(pos, format!("unknown class `{}`", name)))
+    }
+
+    /// Parse `name` or `name[param]`. Returns the prop and its position.
+    fn prop(&mut self) -> Result<(usize, Prop)> {
+        let (pos, name) = self.ident()?;
+
+        let param = if self.eat("[")? {
+            let param = self.param(name)?;
+            self.expect("]")?;
+            Some(param)
+        } else {
+            None
+        };
+
+        if let Some(prop) = Prop::from_snake_name(name, param) {
+            return Ok((pos, prop));
+        }
+
+        let message = if Prop::from_snake_name(name, Some(0)).is_some() {
+            format!("`{}` requires a parameter", name)
+        } else if Prop::from_snake_name(name, None).is_some() {
+            format!("`{}` does not take a parameter", name)
+        } else {
+            format!("unknown prop `{}`", name)
+        };
+        Err(self.error_at(pos, message))
+    }
+
+    /// Parse the parameter of the prop named `prop_name`.
+    fn param(&mut self, prop_name: &str) -> Result<u32> {
+        if self.peek()?.map_or(false, |c| c.is_ascii_digit()) {
+            self.integer()
+        } else if prop_name == "transition" {
+            self.keyword("transition kind", TRANSITION_KINDS)
+        } else {
+            let (pos, name) = self.ident()?;
+            self.symbols
+                .role(name)
+                .ok_or_else(|| self.error_at(pos, format!("unknown role `{}`", name)))
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    //  Values
+
+    fn prop_value(&mut self, prop: &Prop) -> Result<PropValue> {
+        // The default value tells the value type of the prop
+        Ok(match PropValue::default_for_prop(prop) {
+            PropValue::Bool(_) => PropValue::Bool(self.bool()?),
+            PropValue::Bool2(_) => PropValue::Bool2(self.array2(Self::bool)?),
+            PropValue::Float(_) => PropValue::Float(self.float()?),
+            PropValue::Usize(_) => PropValue::Usize(self.integer()?),
+            PropValue::U32x2(_) => PropValue::U32x2(self.array2(Self::integer)?),
+            PropValue::F32x4(_) => PropValue::F32x4(self.array4(Self::float)?),
+            PropValue::Himg(_) => PropValue::Himg(self.himg()?),
+            PropValue::Rgbaf32(_) => PropValue::Rgbaf32(self.color()?),
+            PropValue::Metrics(_) => PropValue::Metrics(Rob::from_box(Box::new(self.metrics()?))),
+            PropValue::Vector2(_) => PropValue::Vector2(self.array2(Self::float)?.into()),
+            PropValue::Point2(_) => PropValue::Point2(self.array2(Self::float)?.into()),
+            PropValue::Box2(_) => PropValue::Box2(self.box2()?),
+            PropValue::LayerXform(_) => {
+                PropValue::LayerXform(Rob::from_box(Box::new(self.layer_xform()?)))
+            }
+            PropValue::SysFontType(_) => {
+                PropValue::SysFontType(self.keyword("font type", SYS_FONT_TYPES)?)
+            }
+            PropValue::LayerFlags(_) => {
+                PropValue::LayerFlags(self.flags("layer flag", LAYER_FLAGS, LayerFlags::empty())?)
+            }
+            PropValue::LayerShadow(_) => PropValue::LayerShadow(self.layer_shadow()?),
+            PropValue::Layouter(_) => PropValue::Layouter(self.keyword("layouter", LAYOUTERS)?),
+            PropValue::AlignFlags(_) => PropValue::AlignFlags(self.flags(
+                "alignment flag",
+                ALIGN_FLAGS,
+                AlignFlags::empty(),
+            )?),
+            PropValue::Transition(_) => PropValue::Transition(self.transition()?),
+        })
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        self.keyword("boolean", &[("true", true), ("false", false)])
+    }
+
+    fn integer<T: FromStr>(&mut self) -> Result<T> {
+        let start = self.skip_ws_pos()?;
+        let rest = self.rest();
+        let sign_len = if rest.starts_with('-') { 1 } else { 0 };
+        let len = rest[sign_len..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - sign_len)
+            + sign_len;
+        if len == sign_len {
+            return Err(self.error_at(start, "expected an integer"));
+        }
+        self.pos += len;
+        rest[..len]
+            .parse()
+            .map_err(|_| self.error_at(start, "integer out of range"))
+    }
+
+    fn float(&mut self) -> Result<f32> {
+        let start = self.skip_ws_pos()?;
+        let rest = self.rest();
+        let sign_len = if rest.starts_with('-') { 1 } else { 0 };
+        let body = &rest[sign_len..];
+
+        if body.starts_with(is_ident_start) {
+            let len = body
+                .find(|c: char| !is_ident_continue(c))
+                .unwrap_or(body.len());
+            let value = match &body[..len] {
+                "nan" | "NAN" => std::f32::NAN,
+                "inf" | "INFINITY" => std::f32::INFINITY,
+                _ => return Err(self.error_at(start, "expected a number")),
+            };
+            self.pos += sign_len + len;
+            return Ok(if sign_len > 0 { -value } else { value });
+        }
+
+        // Find the end of the number
+        let mut len = 0;
+        let mut prev = ' ';
+        for c in body.chars() {
+            let ok = c.is_ascii_digit()
+                || c == '.'
+                || c == 'e'
+                || c == 'E'
+                || ((c == '-' || c == '+') && (prev == 'e' || prev == 'E'));
+            if !ok {
+                break;
+            }
+            len += 1;
+            prev = c;
+        }
+
+        let text = &rest[..sign_len + len];
+        let value = text
+            .parse()
+            .map_err(|_| self.error_at(start, "expected a number"))?;
+        self.pos += text.len();
+        Ok(value)
+    }
+
+    /// Parse `[x, y, ...]` or `[x; len]`.
+    fn array<T: Copy>(
+        &mut self,
+        len: usize,
+        mut elem: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let start = self.skip_ws_pos()?;
+        self.expect("[")?;
+
+        let first = elem(self)?;
+
+        if self.eat(";")? {
+            let count_pos = self.skip_ws_pos()?;
+            let count: usize = self.integer()?;
+            if count != len {
+                return Err(self.error_at(count_pos, format!("expected {} elements", len)));
+            }
+            self.expect("]")?;
+            return Ok(vec![first; len]);
+        }
+
+        let mut elems = vec![first];
+        if self.eat(",")? {
+            self.list("]", |this| {
+                elems.push(elem(this)?);
+                Ok(())
+            })?;
+        } else {
+            self.expect("]")?;
+        }
+
+        if elems.len() != len {
+            return Err(self.error_at(
+                start,
+                format!("expected {} elements, found {}", len, elems.len()),
+            ));
+        }
+
+        Ok(elems)
+    }
+
+    fn array2<T: Copy>(&mut self, elem: impl FnMut(&mut Self) -> Result<T>) -> Result<[T; 2]> {
+        let x = self.array(2, elem)?;
+        Ok([x[0], x[1]])
+    }
+
+    fn array4<T: Copy>(&mut self, elem: impl FnMut(&mut Self) -> Result<T>) -> Result<[T; 4]> {
+        let x = self.array(4, elem)?;
+        Ok([x[0], x[1], x[2], x[3]])
+    }
+
+    /// Parse `{ field: value, ... }`. `field` is called with the position and
+    /// name of each field and is responsible for parsing the value.
+    fn fields(
+        &mut self,
+        mut field: impl FnMut(&mut Self, usize, &'a str) -> Result<()>,
+    ) -> Result<()> {
+        self.expect("{")?;
+        self.list("}", |this| {
+            let (pos, name) = this.ident()?;
+            this.expect(":")?;
+            field(this, pos, name)
+        })
+    }
+
+    fn keyword<T: Copy>(&mut self, what: &str, table: &[(&str, T)]) -> Result<T> {
+        let (pos, name) = self.ident()?;
+        lookup(table, name)
+            .ok_or_else(|| self.error_at(pos, format!("unknown {} `{}`", what, name)))
+    }
+
+    /// Parse `empty` or `FLAG1 | FLAG2 | ...`.
+    fn flags<T: Copy + std::ops::BitOr<Output = T>>(
+        &mut self,
+        what: &str,
+        table: &[(&str, T)],
+        empty: T,
+    ) -> Result<T> {
+        let start = self.pos;
+        if self.ident()?.1 == "empty" {
+            return Ok(empty);
+        }
+        self.pos = start;
+
+        let mut flags = self.keyword(what, table)?;
+        while self.eat("|")? {
+            flags = flags | self.keyword(what, table)?;
+        }
+        Ok(flags)
+    }
+
+    fn color(&mut self) -> Result<RGBAF32> {
+        let start = self.skip_ws_pos()?;
+
+        if self.peek()? == Some('[') {
+            let [r, g, b, a] = self.array4(Self::float)?;
+            return Ok(RGBAF32::new(r, g, b, a));
+        }
+
+        if self.eat("#")? {
+            // No whitespace is allowed after `#`
+            let rest = self.rest();
+            let len = rest
+                .find(|c: char| !c.is_ascii_hexdigit())
+                .unwrap_or(rest.len());
+            let digits: Vec<u32> = rest[..len]
+                .chars()
+                .map(|c| c.to_digit(16).unwrap())
+                .collect();
+            let bytes = match digits[..] {
+                [r, g, b] => [r * 17, g * 17, b * 17, 255],
+                [r, g, b, a] => [r * 17, g * 17, b * 17, a * 17],
+                [r1, r2, g1, g2, b1, b2] => [r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2, 255],
+                [r1, r2, g1, g2, b1, b2, a1, a2] => {
+                    [r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2, a1 * 16 + a2]
+                }
+                _ => return Err(self.error_at(start, "invalid color code")),
+            };
+            self.pos += len;
+            let [r, g, b, a] = bytes;
+            return Ok(RGBAF32::new(
+                r as f32 / 255.0,
+                g as f32 / 255.0,
+                b as f32 / 255.0,
+                a as f32 / 255.0,
+            ));
+        }
+
+        let (pos, name) = self.ident()?;
+        let num_components = match name {
+            "rgb" => 3,
+            "rgba" => 4,
+            _ => return Err(self.error_at(pos, "expected a color")),
+        };
+        self.expect("(")?;
+        let mut c = [1.0; 4];
+        for (i, x) in c[..num_components].iter_mut().enumerate() {
+            if i > 0 {
+                self.expect(",")?;
+            }
+            *x = self.float()?;
+        }
+        self.eat(",")?;
+        self.expect(")")?;
+        Ok(RGBAF32::new(c[0], c[1], c[2], c[3]))
+    }
+
+    fn himg(&mut self) -> Result<Option<HImg>> {
+        let (pos, name) = self.ident()?;
+        match name {
+            "none" => Ok(None),
+            "figures" => {
+                let mut figures = Vec::new();
+                self.expect("(")?;
+                self.list(")", |this| {
+                    figures.push(this.figure()?);
+                    Ok(())
+                })?;
+                Ok(Some(himg_from_figures(figures)))
+            }
+            _ => self
+                .symbols
+                .imgs
+                .get(name)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| self.error_at(pos, format!("unknown image `{}`", name))),
+        }
+    }
+
+    /// Parse `rect(color).radius(r).margin([...]).line_width(w)`. This
+    /// resembles the syntax of `figures!`.
+    fn figure(&mut self) -> Result<Figure> {
+        self.expect_keyword("rect")?;
+        self.expect("(")?;
+        let mut figure = Figure::rect(self.color()?);
+        self.expect(")")?;
+
+        while self.eat(".")? {
+            let (pos, name) = self.ident()?;
+            self.expect("(")?;
+            figure = match name {
+                "radius" => figure.with_corner_radius(self.float()?),
+                "margin" => figure.with_margin(self.array4(Self::float)?),
+                "line_width" => figure.with_line_width(self.float()?),
+                _ => return Err(self.error_at(pos, format!("unknown figure method `{}`", name))),
+            };
+            self.expect(")")?;
+        }
+
+        Ok(figure)
+    }
+
+    fn metrics(&mut self) -> Result<Metrics> {
+        let mut metrics = Metrics::default();
+        self.fields(|this, pos, name| {
+            match name {
+                "margin" => metrics.margin = this.array4(Self::float)?,
+                "size" => metrics.size = this.array2(Self::float)?.into(),
+                _ => return Err(this.error_at(pos, format!("unknown field `{}`", name))),
+            }
+            Ok(())
+        })?;
+        Ok(metrics)
+    }
+
+    fn box2(&mut self) -> Result<Box2<f32>> {
+        let start = self.skip_ws_pos()?;
+        let (mut min, mut max) = (None, None);
+        self.fields(|this, pos, name| {
+            match name {
+                "min" => min = Some(this.array2(Self::float)?),
+                "max" => max = Some(this.array2(Self::float)?),
+                "point" => {
+                    let point = this.array2(Self::float)?;
+                    min = Some(point);
+                    max = Some(point);
+                }
+                _ => return Err(this.error_at(pos, format!("unknown field `{}`", name))),
+            }
+            Ok(())
+        })?;
+
+        match (min, max) {
+            (Some(min), Some(max)) => Ok(Box2::new(Point2::from(min), Point2::from(max))),
+            _ => Err(self.error_at(start, "expected `min` and `max`, or `point`")),
+        }
+    }
+
+    fn layer_xform(&mut self) -> Result<LayerXform> {
+        let mut xform = LayerXform::default();
+        self.fields(|this, pos, name| {
+            match name {
+                "anchor" => xform.anchor = this.array2(Self::float)?.into(),
+                "scale" => xform.scale = this.array2(Self::float)?,
+                "rotate" => xform.rotate = Rad(this.float()?),
+                "translate" => xform.translate = this.array2(Self::float)?.into(),
+                _ => return Err(this.error_at(pos, format!("unknown field `{}`", name))),
+            }
+            Ok(())
+        })?;
+        Ok(xform)
+    }
+
+    fn layer_shadow(&mut self) -> Result<Option<LayerShadow>> {
+        if self.peek()? != Some('{') {
+            let (pos, name) = self.ident()?;
+            if name != "none" {
+                return Err(self.error_at(pos, "expected `none` or `{ ... }`"));
+            }
+            return Ok(None);
+        }
+
+        let mut shadow = LayerShadow {
+            offset: [0.0, 0.0].into(),
+            blur_radius: 0.0,
+            color: RGBAF32::new(0.0, 0.0, 0.0, 1.0),
+        };
+        self.fields(|this, pos, name| {
+            match name {
+                "offset" => shadow.offset = this.array2(Self::float)?.into(),
+                "blur_radius" => shadow.blur_radius = this.float()?,
+                "color" => shadow.color = this.color()?,
+                _ => return Err(this.error_at(pos, format!("unknown field `{}`", name))),
+            }
+            Ok(())
+        })?;
+        Ok(Some(shadow))
+    }
+
+    fn transition(&mut self) -> Result<Transition> {
+        let mut transition = Transition::default();
+        self.fields(|this, pos, name| {
+            match name {
+                "duration" => transition.duration = this.float()?,
+                "easing" => transition.easing = this.easing()?,
+                _ => return Err(this.error_at(pos, format!("unknown field `{}`", name))),
+            }
+            Ok(())
+        })?;
+        Ok(transition)
+    }
+
+    /// Parse `EaseInOut` or `CubicBezier(x1, y1, x2, y2)`.
+    fn easing(&mut self) -> Result<Easing> {
+        let start = self.pos;
+        let (pos, name) = self.ident()?;
+        if name != "CubicBezier" {
+            self.pos = start;
+            return self.keyword("easing", EASINGS);
+        }
+
+        self.expect("(")?;
+        let mut c = [0.0; 4];
+        for (i, x) in c.iter_mut().enumerate() {
+            if i > 0 {
+                self.expect(",")?;
+            }
+            *x = self.float()?;
+        }
+        self.eat(",")?;
+        self.expect(")")?;
+
+        let [x1, y1, x2, y2] = c;
+        if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+            return Err(self.error_at(pos, "the x coordinates must be in range `[0, 1]`"));
+        }
+        Ok(Easing::CubicBezier(x1, y1, x2, y2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use super::*;
+    use crate::ui::theming::GetPropValue;
+
+    fn parse(source: &str) -> TextStylesheet {
+        TextStylesheet::parse(source, &Default::default()).unwrap()
+    }
+
+    fn parse_err(source: &str) -> TextStylesheetError {
+        TextStylesheet::parse(source, &Default::default()).unwrap_err()
+    }
+
+    fn matching_rules(sheet: &TextStylesheet, path: &ElemClassPath) -> Vec<RuleId> {
+        let mut rules = Vec::new();
+        sheet.match_rules(path, &mut |id| rules.push(id));
+        rules
+    }
+
+    /// Get the value of `prop` assigned by the rule `id`.
+    fn value(sheet: &TextStylesheet, id: RuleId, prop: Prop) -> PropValue {
+        sheet
+            .get_rule_prop_value(id, &prop)
+            .unwrap()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn selectors() {
+        const CUSTOM_ID: ClassSet = ClassSet::id(42);
+        let symbols = TextStylesheetSymbols::new().with_id("CUSTOM_ID", CUSTOM_ID);
+
+        let sheet = TextStylesheet::parse(
+            r"
+            // Rule 0
+            ([.BUTTON]) (priority = 100) {}
+            // Rule 1
+            ([.BUTTON:not(.HOVER)]) (priority = -5) {},
+            /* Rule 2 */
+            ([#CUSTOM_ID]) (priority = 100) {}
+            // Rule 3
+            ([.LABEL] < [.BUTTON]) (priority = 100) {}
+            // Rule 4
+            ([.LABEL] .. [#SPLITTER]) (priority = 100) {}
+            ",
+            &symbols,
+        )
+        .unwrap();
+
+        assert_eq!(sheet.get_rule_priority(1), Some(-5));
+        assert_eq!(sheet.get_rule_priority(5), None);
+
+        assert_eq!(matching_rules(&sheet, &[ClassSet::BUTTON]), vec![0, 1]);
+        assert_eq!(
+            matching_rules(&sheet, &[ClassSet::BUTTON | ClassSet::HOVER]),
+            vec![0]
+        );
+        assert_eq!(
+            matching_rules(&sheet, &[CUSTOM_ID | ClassSet::LABEL]),
+            vec![2]
+        );
+        assert_eq!(
+            matching_rules(&sheet, &[ClassSet::id(43) | ClassSet::LABEL]),
+            Vec::<RuleId>::new()
+        );
+        assert_eq!(
+            matching_rules(&sheet, &[ClassSet::BUTTON, ClassSet::LABEL]),
+            vec![3]
+        );
+        assert_eq!(
+            matching_rules(
+                &sheet,
+                &[elem_id::SPLITTER, ClassSet::BUTTON, ClassSet::LABEL]
+            ),
+            vec![3, 4]
+        );
+        assert_eq!(
+            matching_rules(
+                &sheet,
+                &[ClassSet::BUTTON, ClassSet::empty(), ClassSet::LABEL]
+            ),
+            Vec::<RuleId>::new()
+        );
+    }
+
+    #[test]
+    fn prop_values() {
+        let sheet = parse(
+            r"
+            ([]) (priority = 100) {
+                num_layers: 2,
+                layer_img[0]: none,
+                layer_img[1]: figures(rect(#fff).radius(3).margin([1; 4]), rect(rgb(0, 0, 0))),
+                layer_bg_color[0]: #ff000080,
+                layer_metrics[0]: { margin: [nan, 8, nan, -1e1], size: [16, 16] },
+                layer_opacity[0]: 0.5,
+                layer_center[0]: { point: [0.5, 0.5] },
+                layer_xform[0]: { rotate: 1.5, translate: [1, 2] },
+                layer_flags[0]: MASK_TO_BOUNDS | BACKDROP_BLUR,
+                layer_corner_radii[0]: [4; 4],
+                layer_shadow[0]: { offset: [0, 2], blur_radius: 8, color: #0004 },
+                layer_shadow[1]: none,
+                subview_layouter: Table,
+                subview_table_cell[GENERIC]: [1, 2],
+                subview_table_align[GENERIC]: LEFT | TOP,
+                subview_visibility[HORZ_SCROLLBAR]: false,
+                allow_grow: [true, false],
+                fg_color: rgba(0.25, 0.5, 0.75, 1),
+                bg_color: #123,
+                font: SmallEmph,
+                padding: [1, 2, 3, 4],
+            }
+            ",
+        );
+
+        let v = |prop: Prop| value(&sheet, 0, prop);
+
+        assert_eq!(v.num_layers(), 2);
+        assert!(v.layer_img(0).is_none());
+        assert!(v.layer_img(1).is_some());
+        assert_eq!(
+            v.layer_bg_color(0),
+            RGBAF32::new(1.0, 0.0, 0.0, 128.0 / 255.0)
+        );
+
+        let metrics = v.layer_metrics(0);
+        assert!(metrics.margin[0].is_nan());
+        assert_eq!(metrics.margin[1], 8.0);
+        assert!(metrics.margin[2].is_nan());
+        assert_eq!(metrics.margin[3], -10.0);
+        assert_eq!(metrics.size, Vector2::new(16.0, 16.0));
+
+        assert_eq!(v.layer_opacity(0), 0.5);
+        assert_eq!(v.layer_center(0).min, Point2::new(0.5, 0.5));
+        assert_eq!(v.layer_center(0).max, Point2::new(0.5, 0.5));
+
+        let xform = v.layer_xform(0);
+        assert_eq!(xform.rotate, Rad(1.5));
+        assert_eq!(xform.translate, Vector2::new(1.0, 2.0));
+        assert_eq!(xform.scale, [1.0, 1.0]);
+
+        assert_eq!(
+            v.layer_flags(0),
+            LayerFlags::MASK_TO_BOUNDS | LayerFlags::BACKDROP_BLUR
+        );
+        assert_eq!(v.layer_corner_radii(0), [4.0; 4]);
+        assert_eq!(
+            v.layer_shadow(0),
+            Some(LayerShadow {
+                offset: Vector2::new(0.0, 2.0),
+                blur_radius: 8.0,
+                color: RGBAF32::new(0.0, 0.0, 0.0, 68.0 / 255.0),
+            })
+        );
+        assert_eq!(v.layer_shadow(1), None);
+        assert_eq!(v.subview_layouter(), Layouter::Table);
+        assert_eq!(v.subview_table_cell(roles::GENERIC), [1, 2]);
+        assert_eq!(
+            v.subview_table_align(roles::GENERIC),
+            AlignFlags::LEFT | AlignFlags::TOP
+        );
+        assert!(!v.subview_visibility(roles::HORZ_SCROLLBAR));
+        assert_eq!(v.allow_grow(), [true, false]);
+        assert_eq!(v.fg_color(), RGBAF32::new(0.25, 0.5, 0.75, 1.0));
+        assert_eq!(
+            v.bg_color(),
+            RGBAF32::new(17.0 / 255.0, 34.0 / 255.0, 51.0 / 255.0, 1.0)
+        );
+        assert_eq!(v.font(), SysFontType::SmallEmph);
+        assert_eq!(v.padding(), [1.0, 2.0, 3.0, 4.0]);
+
+        assert_eq!(
+            sheet.get_rule_prop_kinds(0).unwrap(),
+            PropKindFlags::LAYER_ALL
+                | PropKindFlags::LAYOUT
+                | PropKindFlags::FG_COLOR
+                | PropKindFlags::BG_COLOR
+                | PropKindFlags::FONT
+                | PropKindFlags::PADDING
+        );
+        assert!(sheet
+            .get_rule_prop_value(0, &Prop::MinSize)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn transitions() {
+        let sheet = parse(
+            r"
+            ([]) (priority = 100) {
+                transition[LAYER_OPACITY]: { duration: 0.25, easing: EaseInOut },
+                transition[LAYER_BG_COLOR]: { easing: CubicBezier(0.1, 0.7, 1, 0.1) },
+                transition[3]: { duration: 1 },
+            }
+            ",
+        );
+
+        let v = |prop: Prop| value(&sheet, 0, prop);
+
+        assert_eq!(
+            v.transition(transition_kinds::LAYER_OPACITY),
+            Transition {
+                duration: 0.25,
+                easing: Easing::EaseInOut,
+            }
+        );
+        assert_eq!(
+            v.transition(transition_kinds::LAYER_BG_COLOR),
+            Transition {
+                duration: 0.0,
+                easing: Easing::CubicBezier(0.1, 0.7, 1.0, 0.1),
+            }
+        );
+        assert_eq!(v.transition(transition_kinds::LAYER_XFORM).duration, 1.0);
+        assert_eq!(
+            sheet.get_rule_prop_kinds(0).unwrap(),
+            PropKindFlags::TRANSITION
+        );
+
+        let e = parse_err("([]) (priority = 100) { transition[GENERIC]: {} }");
+        assert_eq!(e.message(), "unknown transition kind `GENERIC`");
+
+        let e = parse_err(
+            "([]) (priority = 100) { transition[0]: { easing: CubicBezier(2, 0, 1, 1) } }",
+        );
+        assert_eq!(e.message(), "the x coordinates must be in range `[0, 1]`");
+    }
+
+    #[test]
+    fn symbols() {
+        let symbols = TextStylesheetSymbols::new()
+            .with_class("PRIMARY", ClassSet::USER1)
+            .with_role("BADGE", 3);
+
+        let sheet = TextStylesheet::parse(
+            "([.PRIMARY]) (priority = 1) { subview_metrics[BADGE]: { size: [1, 2] } }",
+            &symbols,
+        )
+        .unwrap();
+
+        assert_eq!(matching_rules(&sheet, &[ClassSet::USER1]), vec![0]);
+        let metrics = match value(&sheet, 0, Prop::SubviewMetrics(3)) {
+            PropValue::Metrics(m) => *m,
+            _ => unreachable!(),
+        };
+        assert_eq!(metrics.size, Vector2::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn too_many_rules() {
+        let rule = "([]) (priority = 1) {}\n";
+        let sheet = parse(&rule.repeat(MAX_RULES_PER_STYLESHEET));
+        assert_eq!(sheet.rules.len(), MAX_RULES_PER_STYLESHEET);
+
+        let e = parse_err(&rule.repeat(MAX_RULES_PER_STYLESHEET + 1));
+        assert_eq!((e.line(), e.column()), (MAX_RULES_PER_STYLESHEET + 1, 1));
+        assert_eq!(e.message(), "too many rules (the maximum is 4096)");
+    }
+
+    #[test]
+    fn error_location() {
+        let e = parse_err(
+            "([.BUTTON]) (priority = 100) {\n    fg_color: #fff,\n    bg_colour: #000,\n}",
+        );
+        assert_eq!((e.line(), e.column()), (3, 5));
+        assert_eq!(e.message(), "unknown prop `bg_colour`");
+        assert_eq!(e.to_string(), "3:5: unknown prop `bg_colour`");
+
+        let e = parse_err("([.BUTTON]) (priority = 100) {\n  fg_color: #ffff0,\n}");
+        assert_eq!((e.line(), e.column()), (2, 13));
+
+        let e = parse_err("([.BUTON]) (priority = 100) {}");
+        assert_eq!((e.line(), e.column()), (1, 4));
+        assert_eq!(e.message(), "unknown class `BUTON`");
+
+        let e = parse_err("([]) (priority = 100) { padding: [1, 2, 3] }");
+        assert_eq!((e.line(), e.column()), (1, 34));
+
+        let e = parse_err("([]) (priority = 100) { layer_opacity: 1 }");
+        assert_eq!(e.message(), "`layer_opacity` requires a parameter");
+
+        let e = parse_err("([]) (priority = 100) { font: Normal, font: Emph }");
+        assert_eq!((e.line(), e.column()), (1, 39));
+        assert_eq!(e.message(), "duplicate prop");
+
+        let e = parse_err("([]) (priority = 100) {\n  /* unterminated");
+        assert_eq!((e.line(), e.column()), (2, 3));
+
+        let e = parse_err("([]) (priority = 100000) {}");
+        assert_eq!(e.message(), "integer out of range");
+    }
+}
@@ -8,15 +8,17 @@ use std::{
     cell::{Cell, RefCell},
     fmt,
     rc::{Rc, Weak},
+    time::Instant,
 };
 
 use super::{
+    anim::AnimatedValue,
     manager::{Elem, HElem, Manager, PropKindFlags},
-    style::{roles, ClassSet, GetPropValue, Layouter, Metrics, Role},
+    style::{roles, transition_kinds, ClassSet, GetPropValue, LayerXform, Layouter, Metrics, Role},
     widget::Widget,
 };
 use crate::{
-    pal,
+    images, pal,
     pal::prelude::*,
     ui::layouts::TableLayout,
     uicore::{
@@ -36,6 +38,9 @@ use crate::{
 ///  - `LayerOpacity`
 ///  - `LayerCenter`
 ///  - `LayerXform`
+///  - `LayerFlags`
+///  - `LayerCornerRadii`
+///  - `LayerShadow`
 ///  - `SubviewLayouter`
 ///  - `SubviewPadding`
 ///  - `SubviewMetrics`
@@ -47,8 +52,15 @@ use crate::{
 ///  - `ClipMetrics`
 ///  - `MinSize`
 ///  - `AllowGrow`
+///  - `Transition`
+///
+/// Changes in `LayerBgColor`, `LayerMetrics`, `LayerOpacity`, and
+/// `LayerXform` are animated according to `Transition`. The animation is
+/// driven by [`HWnd::invoke_on_next_frame`] and timed by [`Wm::now`].
 ///
 /// [`Prop`]: crate::ui::theming::Prop
+/// [`HWnd::invoke_on_next_frame`]: crate::uicore::HWnd::invoke_on_next_frame
+/// [`Wm::now`]: crate::pal::iface::Wm::now
 #[derive(Debug)]
 pub struct StyledBox {
     view: HView,
@@ -103,6 +115,11 @@ struct Shared {
     overrider: RefCell<Rc<dyn StyledBoxOverride>>,
 
     has_layer_group: bool,
+
+    /// The kinds of props being animated.
+    animating: Cell<PropKindFlags>,
+    /// `true` if a frame handler for animation is pending.
+    frame_pending: Cell<bool>,
 }
 
 impl fmt::Debug for Shared {
@@ -116,6 +133,8 @@ impl fmt::Debug for Shared {
             .field("subelems", &self.subelems)
             .field("overrider", &())
             .field("has_layer_group", &self.has_layer_group)
+            .field("animating", &self.animating)
+            .field("frame_pending", &self.frame_pending)
             .finish()
     }
 }
@@ -146,6 +165,8 @@ impl StyledBox {
             // the dirty flags
             dirty: Cell::new(PropKindFlags::all() - PropKindFlags::LAYOUT),
             has_layer_group: view_flags.contains(ViewFlags::LAYER_GROUP),
+            animating: Cell::new(PropKindFlags::empty()),
+            frame_pending: Cell::new(false),
         });
 
         view.set_listener(SbListener::new(Rc::downgrade(&shared)));
@@ -362,6 +383,22 @@ impl Shared {
 
         dirty.set(diff - PropKindFlags::LAYOUT);
     }
+
+    /// Schedule the next animation frame if there are running animations.
+    fn request_animation_frame(this: &Rc<Self>, hwnd: HWndRef<'_>) {
+        if this.animating.get().is_empty() || this.frame_pending.get() {
+            return;
+        }
+        this.frame_pending.set(true);
+
+        let shared_weak = Rc::downgrade(this);
+        hwnd.invoke_on_next_frame(move |_, _| {
+            if let Some(shared) = shared_weak.upgrade() {
+                shared.frame_pending.set(false);
+                shared.set_dirty(shared.animating.get());
+            }
+        });
+    }
 }
 
 struct SbLayout {
@@ -640,7 +677,41 @@ struct SbListener {
 struct Layers {
     clip: Option<pal::HLayer>,
     styled: Vec<pal::HLayer>,
+    /// The animation states of the elements of `styled`.
+    anims: Vec<LayerAnims>,
     sub: Option<Sub>,
+    img_sub: Option<Sub>,
+}
+
+/// The animation states of a styled layer.
+struct LayerAnims {
+    bg_color: AnimatedValue<pal::RGBAF32>,
+    opacity: AnimatedValue<f32>,
+    metrics: AnimatedValue<Metrics>,
+    xform: AnimatedValue<LayerXform>,
+}
+
+impl LayerAnims {
+    /// Construct a `LayerAnims` holding the current computed values with no
+    /// transitions in progress.
+    fn new(props: &impl GetPropValue, layer_id: u32) -> Self {
+        Self {
+            bg_color: AnimatedValue::new(props.layer_bg_color(layer_id)),
+            opacity: AnimatedValue::new(props.layer_opacity(layer_id)),
+            metrics: AnimatedValue::new(*props.layer_metrics(layer_id)),
+            xform: AnimatedValue::new(*props.layer_xform(layer_id)),
+        }
+    }
+
+    /// Get the kinds of props being animated at the time `now`.
+    fn running_kinds(&self, now: Instant) -> PropKindFlags {
+        let mut kinds = PropKindFlags::empty();
+        kinds.set(PropKindFlags::LAYER_BG_COLOR, self.bg_color.is_running(now));
+        kinds.set(PropKindFlags::LAYER_OPACITY, self.opacity.is_running(now));
+        kinds.set(PropKindFlags::LAYER_BOUNDS, self.metrics.is_running(now));
+        kinds.set(PropKindFlags::LAYER_XFORM, self.xform.is_running(now));
+        kinds
+    }
 }
 
 impl SbListener {
@@ -705,6 +776,25 @@ impl ViewListener for SbListener {
                 }))
             };
 
+            // Watch for updates of the layer images (e.g., a raster image
+            // finished loading)
+            let img_sub = {
+                let shared = self.shared.clone();
+                images::subscribe_imgs_invalidated(
+                    wm,
+                    Box::new(move |_, himg| {
+                        if let Some(shared) = shared.upgrade() {
+                            let props = shared.style_elem.computed_values();
+                            let uses_img = (0..props.num_layers() as u32)
+                                .any(|i| props.layer_img(i).as_ref() == Some(himg));
+                            if uses_img {
+                                shared.set_dirty(PropKindFlags::LAYER_IMG);
+                            }
+                        }
+                    }),
+                )
+            };
+
             // Create layers. Properties are set later in `update` (This happens
             // because of the fake dirty flags we inserted).
             *layers = Some(Layers {
@@ -717,9 +807,15 @@ impl ViewListener for SbListener {
                     None
                 },
                 styled: Vec::new(),
+                anims: Vec::new(),
                 sub: Some(sub),
+                img_sub: Some(img_sub),
             });
 
+            // A frame handler registered to the previous window might never
+            // be called
+            shared.frame_pending.set(false);
+
             shared.view.pend_update();
         } else {
             *layers = Some(Layers::default());
@@ -742,6 +838,9 @@ impl ViewListener for SbListener {
         if let Some(sub) = layers.sub {
             sub.unsubscribe().unwrap();
         }
+        if let Some(sub) = layers.img_sub {
+            sub.unsubscribe().unwrap();
+        }
     }
 
     fn mouse_enter(&self, _: pal::Wm, _: HViewRef<'_>) {
@@ -794,19 +893,38 @@ impl ViewListener for SbListener {
                 wm.remove_layer(&styled.pop().unwrap());
             }
             styled.resize_with(num_layers, || wm.new_layer(pal::LayerAttrs::default()));
+
+            // New layers start with the current values without animation
+            let anims = &mut layers.anims;
+            anims.truncate(num_layers);
+            while anims.len() < num_layers {
+                anims.push(LayerAnims::new(&props, anims.len() as u32));
+            }
         }
 
         let container = view.global_frame();
+        let now = wm.now();
+        let mut animating = PropKindFlags::empty();
 
         // Update layer properties
         let prop_flags = PropKindFlags::LAYER_ALL - PropKindFlags::NUM_LAYERS;
         if dirty.intersects(prop_flags) {
-            for (i, layer) in layers.styled.iter().enumerate() {
+            for (i, (layer, anims)) in layers
+                .styled
+                .iter()
+                .zip(layers.anims.iter_mut())
+                .enumerate()
+            {
                 let layer_id = i as u32;
                 let mut layer_attrs = pal::LayerAttrs::default();
 
                 if dirty.intersects(PropKindFlags::LAYER_BOUNDS) {
-                    let met = props.layer_metrics(layer_id);
+                    anims.metrics.set_target(
+                        *props.layer_metrics(layer_id),
+                        props.transition(transition_kinds::LAYER_METRICS),
+                        now,
+                    );
+                    let met = anims.metrics.value_at(now);
                     let bounds = met.arrange(container, Vector2::new(0.0, 0.0));
                     layer_attrs.bounds = Some(bounds);
                 }
@@ -825,11 +943,21 @@ impl ViewListener for SbListener {
                 }
 
                 if dirty.intersects(PropKindFlags::LAYER_BG_COLOR) {
-                    layer_attrs.bg_color = Some(props.layer_bg_color(layer_id));
+                    anims.bg_color.set_target(
+                        props.layer_bg_color(layer_id),
+                        props.transition(transition_kinds::LAYER_BG_COLOR),
+                        now,
+                    );
+                    layer_attrs.bg_color = Some(anims.bg_color.value_at(now));
                 }
 
                 if dirty.intersects(PropKindFlags::LAYER_OPACITY) {
-                    layer_attrs.opacity = Some(props.layer_opacity(layer_id));
+                    anims.opacity.set_target(
+                        props.layer_opacity(layer_id),
+                        props.transition(transition_kinds::LAYER_OPACITY),
+                        now,
+                    );
+                    layer_attrs.opacity = Some(anims.opacity.value_at(now));
                 }
 
                 if dirty.intersects(PropKindFlags::LAYER_CENTER) {
@@ -840,10 +968,23 @@ impl ViewListener for SbListener {
                     layer_attrs.flags = Some(props.layer_flags(layer_id));
                 }
 
-                if dirty.intersects(PropKindFlags::LAYER_XFORM | PropKindFlags::LAYER_BOUNDS) {
-                    let xform = props.layer_xform(layer_id);
+                if dirty.intersects(PropKindFlags::LAYER_CORNER_RADII) {
+                    layer_attrs.corner_radii = Some(props.layer_corner_radii(layer_id));
+                }
+
+                if dirty.intersects(PropKindFlags::LAYER_SHADOW) {
+                    layer_attrs.shadow = Some(props.layer_shadow(layer_id));
+                }
 
-                    let met = props.layer_metrics(layer_id);
+                if dirty.intersects(PropKindFlags::LAYER_XFORM | PropKindFlags::LAYER_BOUNDS) {
+                    anims.xform.set_target(
+                        *props.layer_xform(layer_id),
+                        props.transition(transition_kinds::LAYER_XFORM),
+                        now,
+                    );
+                    let xform = anims.xform.value_at(now);
+
+                    let met = anims.metrics.value_at(now);
                     let bounds = met.arrange(container, Vector2::new(0.0, 0.0));
 
                     let mat = xform.to_matrix3(bounds);
@@ -851,8 +992,14 @@ impl ViewListener for SbListener {
                     layer_attrs.transform = Some(mat);
                 }
 
+                animating |= anims.running_kinds(now);
+
                 wm.set_layer_attr(layer, layer_attrs);
             }
+
+            // Keep updating the layers until all transitions complete
+            shared.animating.set(animating);
+            Shared::request_animation_frame(&shared, ctx.hwnd());
         }
 
         // Update the clip layer's properties
@@ -883,3 +1030,143 @@ impl ViewListener for SbListener {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use try_match::try_match;
+
+    use super::*;
+    use crate::{
+        pal::testing::wmapi::WndSnapshot,
+        testing::{prelude::*, use_testing_wm},
+        ui::{layouts::FillLayout, theming::TextStylesheet},
+        uicore::HWnd,
+    };
+
+    /// Get the alpha value of the pixel at `[5, 5]`.
+    fn read_alpha(twm: &dyn TestingWm, pal_hwnd: &pal::HWnd) -> u8 {
+        read_alpha_at(twm, pal_hwnd, [5, 5])
+    }
+
+    /// Get the alpha value of the pixel at `[x, y]`.
+    fn read_alpha_at(twm: &dyn TestingWm, pal_hwnd: &pal::HWnd, [x, y]: [usize; 2]) -> u8 {
+        let mut ss = WndSnapshot::new();
+        twm.read_wnd_snapshot(pal_hwnd, &mut ss);
+        ss.data[ss.stride * y + 4 * x + 3]
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn opacity_transition(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        twm.enable_virtual_clock();
+
+        let style_manager = Manager::global(wm);
+        let _sub = style_manager.subscribe_new_sheet_set(Box::new(|_, _, ctx| {
+            let sheet = TextStylesheet::parse(
+                r"
+                ([.USER1]) (priority = 10000) {
+                    num_layers: 1,
+                    layer_bg_color[0]: #fff,
+                    layer_opacity[0]: 0,
+                    min_size: [20, 20],
+                    transition[LAYER_OPACITY]: { duration: 1, easing: Linear },
+                }
+                ([.USER1.ACTIVE]) (priority = 10000) {
+                    layer_opacity[0]: 1,
+                }
+                ",
+                &Default::default(),
+            )
+            .unwrap();
+            ctx.insert_stylesheet(sheet);
+        }));
+        style_manager.update_sheet_set();
+
+        let sb = StyledBox::new(style_manager, ViewFlags::default());
+        sb.set_class_set(ClassSet::USER1);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(sb.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+            .expect("could not get a single window");
+
+        // The initial value is applied without animation
+        assert_eq!(read_alpha(twm, &pal_hwnd), 0);
+
+        // Start a transition
+        sb.set_class_set(ClassSet::USER1 | ClassSet::ACTIVE);
+        twm.step_unsend();
+        assert_eq!(read_alpha(twm, &pal_hwnd), 0);
+
+        // The transition is in progress
+        twm.advance_clock(Duration::from_millis(500));
+        twm.step_unsend();
+        let alpha = read_alpha(twm, &pal_hwnd);
+        assert!((alpha as i32 - 128).abs() <= 2, "alpha = {}", alpha);
+
+        // The transition is complete
+        twm.advance_clock(Duration::from_millis(500));
+        twm.step_unsend();
+        assert_eq!(read_alpha(twm, &pal_hwnd), 255);
+        assert!(sb.shared.animating.get().is_empty());
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn corner_radii_and_shadow(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let style_manager = Manager::global(wm);
+        let _sub = style_manager.subscribe_new_sheet_set(Box::new(|_, _, ctx| {
+            let sheet = TextStylesheet::parse(
+                r"
+                ([.USER1]) (priority = 10000) {
+                    num_layers: 1,
+                    layer_bg_color[0]: #fff,
+                    layer_metrics[0]: { margin: [10; 4] },
+                    layer_corner_radii[0]: [10; 4],
+                    layer_shadow[0]: { offset: [0, 5], blur_radius: 2, color: #000 },
+                    min_size: [60, 60],
+                }
+                ([.USER1.ACTIVE]) (priority = 10000) {
+                    layer_corner_radii[0]: [0; 4],
+                    layer_shadow[0]: none,
+                }
+                ",
+                &Default::default(),
+            )
+            .unwrap();
+            ctx.insert_stylesheet(sheet);
+        }));
+        style_manager.update_sheet_set();
+
+        let sb = StyledBox::new(style_manager, ViewFlags::default());
+        sb.set_class_set(ClassSet::USER1);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(sb.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+            .expect("could not get a single window");
+
+        // The layer spans `[10, 10]`–`[50, 50]`. Its corners are cut out, and
+        // the shadow is visible below it.
+        assert!(read_alpha_at(twm, &pal_hwnd, [10, 10]) < 8);
+        assert_eq!(read_alpha_at(twm, &pal_hwnd, [30, 30]), 255);
+        assert!(read_alpha_at(twm, &pal_hwnd, [30, 52]) > 64);
+
+        // Remove the effects
+        sb.set_class_set(ClassSet::USER1 | ClassSet::ACTIVE);
+        twm.step_unsend();
+
+        assert_eq!(read_alpha_at(twm, &pal_hwnd, [10, 10]), 255);
+        assert_eq!(read_alpha_at(twm, &pal_hwnd, [30, 52]), 0);
+    }
+}
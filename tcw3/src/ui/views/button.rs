@@ -11,7 +11,10 @@ use crate::{
         theming::{roles, ClassSet, HElem, Manager, StyledBox, Widget},
         views::Label,
     },
-    uicore::{HView, HViewRef, KeyEvent, Sub, ViewFlags, ViewListener},
+    uicore::{
+        AccessAction, AccessInfo, AccessRole, AccessStates, HView, HViewRef, KeyEvent, Sub,
+        ViewFlags, ViewListener,
+    },
 };
 
 /// A push button widget.
@@ -66,7 +69,9 @@ impl Button {
             inner: Rc::clone(&inner),
         });
 
-        Self { view, inner }
+        let this = Self { view, inner };
+        this.update_access_info();
+        this
     }
 
     /// Get an owned handle to the view representing a push button widget.
@@ -87,6 +92,17 @@ impl Button {
     /// Set the text displayed in a push button widget.
     pub fn set_caption(&self, value: impl Into<String>) {
         self.inner.label.set_text(value);
+        self.update_access_info();
+    }
+
+    /// Set the tooltip text of a push button widget. An empty string removes
+    /// the tooltip.
+    ///
+    /// This is useful for describing buttons only displaying icons.
+    pub fn set_tooltip(&self, value: impl Into<String>) {
+        let value = value.into();
+        self.view
+            .set_tooltip(if value.is_empty() { None } else { Some(value) });
     }
 
     /// Set the class set of the inner `StyledBox`.
@@ -101,6 +117,8 @@ impl Button {
         class_set -= protected;
         class_set |= styled_box.class_set() & protected;
         styled_box.set_class_set(class_set);
+
+        self.update_access_info();
     }
 
     /// Get the class set of the inner `StyledBox`.
@@ -108,6 +126,31 @@ impl Button {
         self.inner.styled_box.class_set()
     }
 
+    /// Update the accessibility information based on the caption and the class
+    /// set. This covers `Checkbox` and `RadioButton`, which are built on top of
+    /// `Button`.
+    fn update_access_info(&self) {
+        let class_set = self.class_set();
+
+        let role = if class_set.contains(ClassSet::CHECKBOX) {
+            AccessRole::CheckBox
+        } else if class_set.contains(ClassSet::RADIO_BUTTON) {
+            AccessRole::RadioButton
+        } else {
+            AccessRole::Button
+        };
+
+        let mut states = AccessStates::FOCUSABLE;
+        states.set(AccessStates::CHECKED, class_set.contains(ClassSet::CHECKED));
+
+        self.view.set_access_info(Some(
+            AccessInfo::new(role)
+                .with_name(self.inner.label.text())
+                .with_states(states)
+                .with_actions(vec![AccessAction::Press]),
+        ));
+    }
+
     /// Add a function called when a push button widget is activated.
     ///
     /// The function is called via `Wm::invoke`, thus allowed to modify
@@ -174,6 +217,12 @@ impl ViewListener for ButtonViewListener {
             .button_mixin
             .key_up(wm, view, e, self.build_button_mixin_listener())
     }
+
+    fn perform_access_action(&self, wm: pal::Wm, view: HViewRef<'_>, action: AccessAction) {
+        if action == AccessAction::Press {
+            self.build_button_mixin_listener().activate(wm, view);
+        }
+    }
 }
 
 struct ButtonMixinListener {
@@ -7,6 +7,7 @@ use flags_macro::flags;
 use momo::momo;
 use rc_borrow::RcBorrow;
 use std::{
+    borrow::Cow,
     cell::{Cell, RefCell, RefMut},
     fmt,
     ops::Range,
@@ -19,6 +20,10 @@ use crate::{
     pal,
     pal::prelude::*,
     ui::{
+        editing::{
+            editor::{self, CaretBlink, Editor, EditorHost},
+            undo::{History, HistoryTx},
+        },
         layouts::EmptyLayout,
         mixins::CanvasMixin,
         theming::{
@@ -26,12 +31,12 @@ use crate::{
         },
     },
     uicore::{
-        actions, ActionId, ActionStatus, CursorShape, HView, HViewRef, HWndRef, MouseDragListener,
-        SizeTraits, Sub, UpdateCtx, ViewFlags, ViewListener, WeakHView, WmExt,
+        actions, AccessInfo, AccessRole, AccessStates, AccessValue, ActionId, ActionStatus,
+        CursorShape, HView, HViewRef, HWndRef, MouseDragListener, SizeTraits, Sub, UpdateCtx,
+        ViewFlags, ViewListener, WeakHView, WmExt,
     },
 };
 
-mod history;
 #[cfg(test)]
 mod tests;
 
@@ -173,9 +178,8 @@ struct State {
     /// whenever the selection range is updated.
     caret: Option<[pal::Beam; 2]>,
     caret_layers: Option<[pal::HLayer; 2]>,
-    caret_blink: bool,
-    caret_blink_timer: Option<pal::HInvoke>,
-    history: history::History,
+    caret_blink: CaretBlink,
+    history: History,
 }
 
 #[derive(Debug)]
@@ -224,9 +228,8 @@ impl EntryCore {
                     comp_range: None,
                     caret: None,
                     caret_layers: None,
-                    caret_blink: true,
-                    caret_blink_timer: None,
-                    history: history::History::new(),
+                    caret_blink: CaretBlink::new(),
+                    history: History::new(),
                 }),
                 style_elem,
                 style_sel_elem,
@@ -264,6 +267,8 @@ impl EntryCore {
         this.view
             .set_listener(EntryCoreListener::new(Rc::clone(&this.inner)));
 
+        update_access_info(this.view.as_ref(), &this.inner);
+
         this
     }
 
@@ -304,7 +309,7 @@ impl EntryCore {
             &mut |state| {
                 state.text = value.take().unwrap();
                 state.sel_range = [0, 0];
-                state.history = history::History::new();
+                state.history = History::new();
 
                 UpdateStateFlags::ANY
             },
@@ -366,58 +371,26 @@ impl State {
         }
     }
 
-    /// Reset the timer used for making the caret blink. This method is also
-    /// responsible for starting or stopping the timer as needed by inspecting
-    /// the current state.
-    ///
-    /// `override_focus` overrides the result of `improper_subview_is_focused`
-    /// used while deciding whether the timer should be running or not.
+    /// Reset the timer used for making the caret blink. See
+    /// [`CaretBlink::reset`].
     fn reset_timer(
         &mut self,
         hview: HViewRef<'_>,
         inner: RcBorrow<'_, Inner>,
         override_focus: Option<bool>,
     ) {
-        let wm = inner.wm;
-        if let Some(hinv) = self.caret_blink_timer.take() {
-            wm.cancel_invoke(&hinv);
-        }
-
-        let should_start_timer = override_focus
-            .unwrap_or_else(|| hview.improper_subview_is_focused())
-            && self.sel_range[0] == self.sel_range[1];
-
-        if should_start_timer {
-            self.caret_blink_timer = Some(State::schedule_timer(RcBorrow::upgrade(inner)));
-        } else {
-            log::trace!("Not scheduling a deferred invocation because the caret is invisible now");
-        }
+        let has_caret = self.sel_range[0] == self.sel_range[1];
+        self.caret_blink
+            .reset(hview, inner, has_caret, override_focus);
     }
 
-    /// Schedule a deferred invocation which toggles `caret_blink` and get the
-    /// handle representing the invocation.
-    ///
-    /// This is implemented as a free function to allow recursive calls.
-    fn schedule_timer(inner: Rc<Inner>) -> pal::HInvoke {
-        use std::time::Duration;
-
-        log::trace!("Scheduling a deferred invocation for blinking the caret");
-
-        // TODO: Retrieve the preferred period from the operating system
-        inner.wm.invoke_after(
-            Duration::from_millis(400)..Duration::from_millis(700),
-            move |_| {
-                if let Some(hview) = inner.view.upgrade() {
-                    // Toggle the caret's visibility
-                    let mut state = inner.state.borrow_mut();
-                    state.caret_blink = !state.caret_blink;
-                    hview.pend_update();
-
-                    // Schedule the next invocation
-                    state.caret_blink_timer = Some(Self::schedule_timer(Rc::clone(&inner)));
-                }
-            },
-        )
+    /// Borrow the fields modified by editing operations.
+    fn editor(&mut self) -> Editor<'_, String> {
+        Editor {
+            text: &mut self.text,
+            sel_range: &mut self.sel_range,
+            history: &mut self.history,
+        }
     }
 
     fn scroll_cursor_into_view(&mut self, hview: HViewRef<'_>, elem: &theming::Elem) -> bool {
@@ -443,6 +416,32 @@ impl State {
     }
 }
 
+impl EditorHost for Inner {
+    fn wm(&self) -> pal::Wm {
+        self.wm
+    }
+
+    fn view(&self) -> Option<HView> {
+        self.view.upgrade()
+    }
+
+    fn caret_blink(&self) -> RefMut<'_, CaretBlink> {
+        RefMut::map(self.state.borrow_mut(), |state| &mut state.caret_blink)
+    }
+
+    fn selected_text(&self) -> Option<String> {
+        self.state.borrow_mut().editor().selected_text()
+    }
+
+    fn normalize_pasted_text(text: &str) -> Cow<'_, str> {
+        flatten_line_breaks(text)
+    }
+
+    fn insert(self: Rc<Self>, view: HViewRef<'_>, text: &str) {
+        EntryCoreListener::new(self).handle_insert(view, text);
+    }
+}
+
 impl TextLayoutInfo {
     fn text_origin(&self, view: HViewRef<'_>, scroll: f32, elem: &theming::Elem) -> Vector2<f32> {
         let baseline = self.text_layout.line_baseline(0);
@@ -507,6 +506,25 @@ fn reapply_style_sel(inner: &Rc<Inner>, view: HViewRef<'_>, kind_flags: PropKind
     }
 }
 
+/// Replace line breaks with spaces because `EntryCore` only supports a single
+/// line of text.
+///
+/// `\r\n`, `\r`, and `\n` are recognized as line breaks. (`str::lines` doesn't
+/// recognize a lone `\r`.) A trailing line break is removed.
+fn flatten_line_breaks(text: &str) -> Cow<'_, str> {
+    if !text.contains(|c| c == '\r' || c == '\n') {
+        return text.into();
+    }
+
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let text = if text.ends_with('\n') {
+        &text[..text.len() - 1]
+    } else {
+        &text[..]
+    };
+    text.replace('\n', " ").into()
+}
+
 /// Implements `ViewListener` and `TextInputCtxListener`.
 #[derive(Clone)]
 struct EntryCoreListener {
@@ -526,9 +544,7 @@ impl EntryCoreListener {
         get_range: fn(usize, &pal::TextLayout, &str) -> usize,
     ) {
         update_state(view, RcBorrow::from(&self.inner), &mut |state| {
-            state.ensure_text_layout(&self.inner.style_elem);
-            let layout = &state.text_layout_info.as_ref().unwrap().text_layout;
-            let [mut start, mut end] = state.sel_range;
+            let [start, mut end] = state.sel_range;
 
             if start == end {
                 log::trace!(
@@ -539,34 +555,29 @@ impl EntryCoreListener {
 
                 // If nothing is selected, derive the deletion range using
                 // the given function
+                state.ensure_text_layout(&self.inner.style_elem);
+                let layout = &state.text_layout_info.as_ref().unwrap().text_layout;
                 end = get_range(start, layout, &state.text);
             } else {
                 log::trace!("... deleting the selection at {:?}", start..end);
             }
 
-            log::trace!("... deletion range = {:?}", start..end);
-
-            if start == end {
-                // There's nothing to delete
-                return UpdateStateFlags::empty();
-            }
-
-            if start > end {
-                std::mem::swap(&mut start, &mut end);
+            if state.editor().delete([start, end]) {
+                UpdateStateFlags::ANY
+            } else {
+                UpdateStateFlags::empty()
             }
+        });
+    }
 
-            // Record the change to the undo history
-            {
-                let mut tx = state.history.start_transaction();
-                tx.replace_range(&mut state.history, &state.text, start..end, String::new());
-                tx.finish(&mut state.history, &state.text);
+    /// Replace the selected text with `text`.
+    fn handle_insert(&self, view: HViewRef<'_>, text: &str) {
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            if state.editor().insert(text) {
+                UpdateStateFlags::ANY
+            } else {
+                UpdateStateFlags::empty()
             }
-
-            // Update `text`
-            state.text.replace_range(start..end, "");
-            state.sel_range = [start, start];
-
-            UpdateStateFlags::ANY
         });
     }
 
@@ -602,16 +613,7 @@ impl EntryCoreListener {
 
     fn handle_undo(&self, view: HViewRef<'_>) {
         update_state(view, RcBorrow::from(&self.inner), &mut |state| {
-            if let Some(edit) = state.history.undo() {
-                log::debug!("Undoing {:?}", edit);
-
-                // Revert `edit`
-                debug_assert_eq!(state.text[edit.range_new()], edit.new[..]);
-                state.text.replace_range(edit.range_new(), &edit.old);
-
-                let sel_range = edit.range_old();
-                state.sel_range = [sel_range.start, sel_range.end];
-
+            if state.editor().undo() {
                 UpdateStateFlags::ANY
             } else {
                 UpdateStateFlags::empty()
@@ -621,16 +623,7 @@ impl EntryCoreListener {
 
     fn handle_redo(&self, view: HViewRef<'_>) {
         update_state(view, RcBorrow::from(&self.inner), &mut |state| {
-            if let Some(edit) = state.history.redo() {
-                log::debug!("Redoing {:?}", edit);
-
-                // Re-apply `edit`
-                debug_assert_eq!(state.text[edit.range_old()], edit.old[..]);
-                state.text.replace_range(edit.range_old(), &edit.new);
-
-                let sel_range = edit.range_new();
-                state.sel_range = [sel_range.start, sel_range.end];
-
+            if state.editor().redo() {
                 UpdateStateFlags::ANY
             } else {
                 UpdateStateFlags::empty()
@@ -681,7 +674,7 @@ impl ViewListener for EntryCoreListener {
         }
 
         let mut state = self.inner.state.borrow_mut();
-        state.caret_blink = true;
+        state.caret_blink.show();
         state.pend_update_after_focus_event(hview);
 
         // Start the caret-blinking timer if needed.
@@ -710,7 +703,7 @@ impl ViewListener for EntryCoreListener {
         state.reset_timer(hview, RcBorrow::from(&self.inner), Some(false));
     }
 
-    fn validate_action(&self, _: pal::Wm, _: HViewRef<'_>, action: ActionId) -> ActionStatus {
+    fn validate_action(&self, wm: pal::Wm, _: HViewRef<'_>, action: ActionId) -> ActionStatus {
         let mut status = ActionStatus::empty();
         match action {
             actions::SELECT_ALL
@@ -764,30 +757,10 @@ impl ViewListener for EntryCoreListener {
             | actions::MOVE_DOWN_PAGE_SELECTING => {
                 status |= ActionStatus::VALID | ActionStatus::ENABLED;
             }
-            actions::COPY | actions::CUT => {
+            _ => {
                 let state = self.inner.state.borrow();
-                if state.sel_range[0] != state.sel_range[1] {
-                    status |= ActionStatus::ENABLED;
-                }
-                status |= ActionStatus::VALID;
+                status |= editor::validate_edit_action(wm, action, state.sel_range, &state.history);
             }
-            actions::PASTE => {
-                // TODO: Check if the clipboard contains a text
-                status |= ActionStatus::VALID;
-            }
-            actions::UNDO => {
-                if self.inner.state.borrow().history.can_undo() {
-                    status |= ActionStatus::ENABLED;
-                }
-                status |= ActionStatus::VALID;
-            }
-            actions::REDO => {
-                if self.inner.state.borrow().history.can_redo() {
-                    status |= ActionStatus::ENABLED;
-                }
-                status |= ActionStatus::VALID;
-            }
-            _ => {}
         }
         status
     }
@@ -852,13 +825,19 @@ impl ViewListener for EntryCoreListener {
                 });
             }
             actions::COPY => {
-                log::warn!("TODO: Copy");
+                log::trace!("Handling COPY");
+                editor::copy_selection(&*self.inner);
             }
             actions::CUT => {
-                log::warn!("TODO: Cut");
+                log::trace!("Handling CUT");
+                if editor::copy_selection(&*self.inner) {
+                    // Delete the selected text
+                    self.handle_delete(view, |i, _, _| i);
+                }
             }
-            actions::PASTE => {
-                log::warn!("TODO: Paste");
+            actions::PASTE | actions::PASTE_AS_PLAIN_TEXT => {
+                log::trace!("Handling PASTE or PASTE_AS_PLAIN_TEXT");
+                editor::paste(&self.inner);
             }
             actions::DELETE_BACKWARD => {
                 log::trace!("Handling DELETE_BACKWARD");
@@ -1178,9 +1157,9 @@ impl ViewListener for EntryCoreListener {
             }
             layer_attrs[0].bounds = Some(rect0);
 
-            // Hide the caret if it's out of view or `caret_blink == false`
+            // Hide the caret if it's out of view or in the invisible phase
             for i in 0..2 {
-                if !state.caret_blink
+                if !state.caret_blink.is_visible()
                     || !(0.0..global_frame.size().x).contains(&(pos[i].x + text_origin.x))
                 {
                     layer_attrs[i].opacity = Some(0.0);
@@ -1246,7 +1225,7 @@ struct Edit<'a> {
     state: RefMut<'a, State>,
     inner: RcBorrow<'a, Inner>,
     view: HView,
-    history_tx: Option<history::HistoryTx>,
+    history_tx: Option<HistoryTx>,
 }
 
 impl Edit<'_> {
@@ -1309,7 +1288,7 @@ impl pal::iface::TextInputCtxEdit<pal::Wm> for Edit<'_> {
 
         // Update the timer's state
         self.state.reset_timer(self.view.as_ref(), self.inner, None);
-        self.state.caret_blink = true;
+        self.state.caret_blink.show();
     }
 
     fn set_composition_range(&mut self, range: Option<Range<usize>>) {
@@ -1338,32 +1317,18 @@ impl pal::iface::TextInputCtxEdit<pal::Wm> for Edit<'_> {
 
         let state = &mut *self.state; // enable split borrow
 
-        // Record the change to the undo history
-        self.history_tx.as_mut().unwrap().replace_range(
-            &mut state.history,
-            &state.text,
-            range.clone(),
-            text.to_owned(),
-        );
-
-        // Update the selection
-        for i in state.sel_range.iter_mut() {
-            if *i >= range.end {
-                *i = *i - range.len() + text.len();
-            } else if *i >= range.start {
-                *i = range.start;
-            }
-        }
-
-        // Update `text`
-        state.text.replace_range(range, text);
+        // Update `text` and the selection, recording the change to the undo
+        // history
+        state
+            .editor()
+            .replace(self.history_tx.as_mut().unwrap(), range, text);
 
         state.invalidate_text_layout();
         state.canvas.pend_draw(self.view.as_ref());
 
         // Reset the timer's phase
         state.reset_timer(self.view.as_ref(), self.inner, None);
-        state.caret_blink = true;
+        state.caret_blink.show();
     }
 
     fn slice(&mut self, range: Range<usize>) -> String {
@@ -1526,7 +1491,7 @@ fn update_state(
     }
 
     // Update the caret-blinking timer
-    state.caret_blink = true;
+    state.caret_blink.show();
     state.reset_timer(hview, inner, None);
 
     // Raise `changed`
@@ -1566,6 +1531,10 @@ fn pend_raise_change(inner: RcBorrow<'_, Inner>) {
         if let Some(inner) = inner_weak.upgrade() {
             inner.pending_change_handler.set(false);
 
+            if let Some(view) = inner.view.upgrade() {
+                update_access_info(view.as_ref(), &inner);
+            }
+
             let handlers = inner.change_handlers.borrow();
             for handler in handlers.iter() {
                 handler(wm);
@@ -1574,6 +1543,16 @@ fn pend_raise_change(inner: RcBorrow<'_, Inner>) {
     });
 }
 
+/// Expose the text content to assistive technologies.
+fn update_access_info(hview: HViewRef<'_>, inner: &Inner) {
+    let text = inner.state.borrow().text.clone();
+    hview.set_access_info(Some(
+        AccessInfo::new(AccessRole::TextField)
+            .with_value(AccessValue::Text(text))
+            .with_states(AccessStates::FOCUSABLE),
+    ));
+}
+
 struct EntryCoreDragListener {
     view: HView,
     inner: Rc<Inner>,
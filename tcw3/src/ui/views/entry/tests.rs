@@ -7,7 +7,7 @@ use crate::{
         views::Spacer,
         AlignFlags,
     },
-    uicore::{HView, HWnd, SizeTraits, ViewFlags},
+    uicore::{actions, ActionStatus, HView, HWnd, SizeTraits, ViewFlags},
 };
 use cggeom::prelude::*;
 use enclose::enc;
@@ -166,3 +166,88 @@ fn set_text(twm: &dyn TestingWm) {
     // .. and a `changed` event should be generated
     assert_eq!(changed_events.borrow()[..], ["hello", "world"][..]);
 }
+
+#[use_testing_wm(testing = "crate::testing")]
+#[test]
+fn clipboard(twm: &dyn TestingWm) {
+    let TestWithOneEntry {
+        entry,
+        hwnd: _hwnd,
+        pal_hwnd,
+        ..
+    } = init_test_with_one_entry(twm);
+
+    // Focus the text field by clicking it
+    let bounds = entry.view_ref().global_frame();
+    simulate_click(twm, &pal_hwnd, bounds.min.average2(&bounds.min));
+
+    entry.set_text("hello world");
+    twm.step_unsend();
+
+    let status = |action| twm.raise_validate_action(&pal_hwnd, action);
+
+    // Nothing is selected, so copying is impossible. The clipboard is empty,
+    // so pasting is impossible as well.
+    assert!(!status(actions::COPY).contains(ActionStatus::ENABLED));
+    assert!(!status(actions::CUT).contains(ActionStatus::ENABLED));
+    assert!(!status(actions::PASTE).contains(ActionStatus::ENABLED));
+
+    // Select "world" and cut it
+    {
+        let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), true);
+        edit.set_selected_range(6..11);
+    }
+    twm.step_unsend();
+
+    assert!(status(actions::COPY).contains(ActionStatus::ENABLED));
+    assert!(status(actions::CUT).contains(ActionStatus::ENABLED));
+    twm.raise_perform_action(&pal_hwnd, actions::CUT);
+    twm.step_unsend();
+
+    assert_eq!(entry.text(), "hello ");
+    assert_eq!(twm.clipboard_text().as_deref(), Some("world"));
+
+    // Paste it at the beginning (`PASTE` is processed asynchronously)
+    {
+        let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), true);
+        edit.set_selected_range(0..0);
+    }
+    assert!(status(actions::PASTE).contains(ActionStatus::ENABLED));
+    twm.raise_perform_action(&pal_hwnd, actions::PASTE);
+    twm.step_unsend();
+
+    assert_eq!(entry.text(), "worldhello ");
+
+    // Line breaks are replaced with spaces
+    twm.set_clipboard_text(Some("a\nb\r\nc"));
+    twm.raise_perform_action(&pal_hwnd, actions::PASTE_AS_PLAIN_TEXT);
+    twm.step_unsend();
+
+    assert_eq!(entry.text(), "worlda b chello ");
+
+    // Undo the paste
+    twm.raise_perform_action(&pal_hwnd, actions::UNDO);
+    twm.step_unsend();
+
+    assert_eq!(entry.text(), "worldhello ");
+
+    // Copy everything
+    twm.raise_perform_action(&pal_hwnd, actions::SELECT_ALL);
+    twm.raise_perform_action(&pal_hwnd, actions::COPY);
+    twm.step_unsend();
+
+    assert_eq!(entry.text(), "worldhello ");
+    assert_eq!(twm.clipboard_text().as_deref(), Some("worldhello "));
+}
+
+#[test]
+fn flatten_line_breaks() {
+    use super::flatten_line_breaks;
+
+    assert_eq!(flatten_line_breaks("abc"), "abc");
+    assert_eq!(flatten_line_breaks("a\nb\r\nc\rd"), "a b c d");
+    assert_eq!(flatten_line_breaks("a\r\rb"), "a  b");
+    assert_eq!(flatten_line_breaks("a\n\nb"), "a  b");
+    assert_eq!(flatten_line_breaks("ab\r"), "ab");
+    assert_eq!(flatten_line_breaks("ab\r\n"), "ab");
+}
@@ -9,7 +9,8 @@ use crate::{
     ui::mixins::CanvasMixin,
     ui::theming::{ClassSet, Elem, GetPropValue, HElem, Manager, PropKindFlags, Widget},
     uicore::{
-        HView, HViewRef, HWndRef, Layout, LayoutCtx, SizeTraits, UpdateCtx, ViewFlags, ViewListener,
+        AccessInfo, AccessRole, HView, HViewRef, HWndRef, Layout, LayoutCtx, SizeTraits, UpdateCtx,
+        ViewFlags, ViewListener,
     },
 };
 
@@ -29,6 +30,9 @@ struct Inner {
 #[derive(Debug)]
 struct State {
     text: String,
+    /// The attributed text set by `set_attr_text`. Takes precedence over
+    /// `text` if set.
+    attr_text: Option<pal::AttrText>,
     text_layout_info: Option<TextLayoutInfo>,
     canvas: CanvasMixin,
 }
@@ -50,6 +54,7 @@ impl Label {
             inner: Rc::new(Inner {
                 state: RefCell::new(State {
                     text: String::new(),
+                    attr_text: None,
                     text_layout_info: None,
                     canvas: CanvasMixin::new(),
                 }),
@@ -73,6 +78,8 @@ impl Label {
         this.view
             .set_listener(LabelListener::new(Rc::clone(&this.inner)));
 
+        this.update_access_info();
+
         this
     }
 
@@ -102,10 +109,40 @@ impl Label {
         let value = value.into();
         {
             let mut state = self.inner.state.borrow_mut();
-            if state.text == value {
+            if state.text == value && state.attr_text.is_none() {
                 return;
             }
             state.text = value;
+            state.attr_text = None;
+            state.invalidate_text_layout();
+            state.canvas.pend_draw(self.view.as_ref());
+        }
+
+        // Invalidate the layout, since the label size might be changed
+        self.view
+            .set_layout(LabelListener::new(Rc::clone(&self.inner)));
+
+        self.update_access_info();
+    }
+
+    /// Get the text displayed in a label widget.
+    pub fn text(&self) -> String {
+        let state = self.inner.state.borrow();
+        if let Some(attr_text) = &state.attr_text {
+            attr_text.text().to_owned()
+        } else {
+            state.text.clone()
+        }
+    }
+
+    /// Set the attributed text displayed in a label widget.
+    ///
+    /// The character styles of `value` are applied on top of the font
+    /// specified by the styling properties. The inline boxes are left empty.
+    pub fn set_attr_text(&self, value: pal::AttrText) {
+        {
+            let mut state = self.inner.state.borrow_mut();
+            state.attr_text = Some(value);
             state.invalidate_text_layout();
             state.canvas.pend_draw(self.view.as_ref());
         }
@@ -113,6 +150,14 @@ impl Label {
         // Invalidate the layout, since the label size might be changed
         self.view
             .set_layout(LabelListener::new(Rc::clone(&self.inner)));
+
+        self.update_access_info();
+    }
+
+    fn update_access_info(&self) {
+        self.view.set_access_info(Some(
+            AccessInfo::new(AccessRole::Label).with_name(self.text()),
+        ));
     }
 
     /// Set the styling class set.
@@ -135,6 +180,15 @@ impl Label {
         self.set_text(value);
         self
     }
+
+    /// Call `set_attr_text`, retuning `self`.
+    ///
+    /// This method is useful for constructing `Label` using the builder
+    /// pattern.
+    pub fn with_attr_text(self, value: pal::AttrText) -> Self {
+        self.set_attr_text(value);
+        self
+    }
 }
 
 impl Widget for Label {
@@ -170,7 +224,11 @@ impl State {
                 sys: Some(font_type),
                 ..Default::default()
             });
-            let text_layout = pal::TextLayout::from_text(&self.text, &char_style, None);
+            let text_layout = if let Some(attr_text) = &self.attr_text {
+                pal::TextLayout::from_attr_text(attr_text, &char_style, None)
+            } else {
+                pal::TextLayout::from_text(&self.text, &char_style, None)
+            };
 
             let visual_bounds = text_layout.visual_bounds();
             let layout_bounds = text_layout.layout_bounds();
@@ -0,0 +1,244 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    rc::{Rc, Weak},
+};
+use subscriber_list::SubscriberList;
+
+use crate::{
+    pal,
+    ui::{
+        layouts::TableLayout,
+        theming::Manager,
+        views::{Button, Label},
+        AlignFlags,
+    },
+    uicore::{HView, HWnd, HWndRef, Sub, WndListener, WndStyleFlags},
+};
+
+/// A modal dialog box displaying a message and a row of buttons.
+///
+/// A message box can be shown only once. It's dismissed when the user clicks
+/// one of the buttons, presses the Enter or Escape key (if the default or
+/// cancel button is set, respectively), or closes the window.
+#[derive(Debug)]
+pub struct MessageBox {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    style_manager: &'static Manager,
+    hwnd: HWnd,
+    message: Label,
+    button_row: HView,
+    buttons: RefCell<Vec<(Button, Sub)>>,
+    default_button: Cell<Option<usize>>,
+    cancel_button: Cell<Option<usize>>,
+    dismissed: Cell<bool>,
+    dismiss_handlers: RefCell<SubscriberList<Box<dyn Fn(pal::Wm, Option<usize>)>>>,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("hwnd", &self.hwnd)
+            .field("message", &self.message)
+            .field("button_row", &self.button_row)
+            .field(
+                "buttons",
+                &self
+                    .buttons
+                    .borrow()
+                    .iter()
+                    .map(|(b, _)| b)
+                    .collect::<Vec<_>>(),
+            )
+            .field("default_button", &self.default_button)
+            .field("cancel_button", &self.cancel_button)
+            .field("dismissed", &self.dismissed)
+            .field("dismiss_handlers", &())
+            .finish()
+    }
+}
+
+impl MessageBox {
+    pub fn new(wm: pal::Wm, style_manager: &'static Manager) -> Self {
+        let hwnd = HWnd::new(wm);
+        hwnd.set_style_flags(WndStyleFlags::empty());
+
+        let message = Label::new(style_manager);
+        let button_row = HView::new(Default::default());
+
+        hwnd.content_view().set_layout(
+            TableLayout::stack_vert(vec![
+                (message.view(), AlignFlags::VERT_JUSTIFY | AlignFlags::LEFT),
+                (button_row.clone(), AlignFlags::RIGHT),
+            ])
+            .with_uniform_margin(20.0)
+            .with_uniform_spacing(15.0),
+        );
+
+        let inner = Rc::new(Inner {
+            style_manager,
+            hwnd,
+            message,
+            button_row,
+            buttons: RefCell::new(Vec::new()),
+            default_button: Cell::new(None),
+            cancel_button: Cell::new(None),
+            dismissed: Cell::new(false),
+            dismiss_handlers: RefCell::new(SubscriberList::new()),
+        });
+
+        inner.hwnd.set_listener(MessageBoxWndListener {
+            inner: Rc::downgrade(&inner),
+        });
+
+        {
+            let inner_weak = Rc::downgrade(&inner);
+            inner.hwnd.set_default_action(Some(Box::new(move |wm, _| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    if let Some(i) = inner.default_button.get() {
+                        Inner::dismiss(inner, wm, Some(i));
+                    }
+                }
+            })));
+        }
+        {
+            let inner_weak = Rc::downgrade(&inner);
+            inner.hwnd.set_cancel_action(Some(Box::new(move |wm, _| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    if let Some(i) = inner.cancel_button.get() {
+                        Inner::dismiss(inner, wm, Some(i));
+                    }
+                }
+            })));
+        }
+
+        Self { inner }
+    }
+
+    /// Get the window representing the message box.
+    pub fn hwnd(&self) -> &HWnd {
+        &self.inner.hwnd
+    }
+
+    /// Set the title of the window.
+    pub fn set_caption(&self, value: impl Into<String>) {
+        self.inner.hwnd.set_caption(value);
+    }
+
+    /// Set the message text.
+    pub fn set_message(&self, value: impl Into<String>) {
+        self.inner.message.set_text(value);
+    }
+
+    /// Replace the buttons with the new ones having the specified captions.
+    /// The buttons are identified by their indices in `captions`.
+    pub fn set_buttons(&self, captions: impl IntoIterator<Item = impl Into<String>>) {
+        let buttons: Vec<_> = captions
+            .into_iter()
+            .enumerate()
+            .map(|(i, caption)| {
+                let button = Button::new(self.inner.style_manager);
+                button.set_caption(caption);
+
+                let inner_weak = Rc::downgrade(&self.inner);
+                let sub = button.subscribe_activated(Box::new(move |wm| {
+                    if let Some(inner) = inner_weak.upgrade() {
+                        Inner::dismiss(inner, wm, Some(i));
+                    }
+                }));
+
+                (button, sub)
+            })
+            .collect();
+
+        self.inner.button_row.set_layout(
+            TableLayout::stack_horz(
+                buttons
+                    .iter()
+                    .map(|(button, _)| (button.view(), AlignFlags::JUSTIFY)),
+            )
+            .with_uniform_spacing(10.0),
+        );
+
+        *self.inner.buttons.borrow_mut() = buttons;
+    }
+
+    /// Set the button activated by the Enter key. The button also receives
+    /// the keyboard focus when the message box is shown.
+    pub fn set_default_button(&self, i: Option<usize>) {
+        self.inner.default_button.set(i);
+    }
+
+    /// Set the button activated by the Escape key.
+    pub fn set_cancel_button(&self, i: Option<usize>) {
+        self.inner.cancel_button.set(i);
+    }
+
+    /// Add a function called when the message box is dismissed. The function
+    /// receives the index of the chosen button, or `None` if the window was
+    /// closed without choosing one.
+    ///
+    /// The function is called via `Wm::invoke`.
+    pub fn subscribe_dismissed(&self, cb: Box<dyn Fn(pal::Wm, Option<usize>)>) -> Sub {
+        self.inner.dismiss_handlers.borrow_mut().insert(cb).untype()
+    }
+
+    /// Show the message box as a modal dialog of `owner`. If `owner` is
+    /// `None`, the message box is displayed as an independent window.
+    pub fn show(&self, owner: Option<HWnd>) {
+        let hwnd = &self.inner.hwnd;
+        let modal = owner.is_some();
+        hwnd.set_owner(owner);
+        hwnd.set_modal(modal);
+        hwnd.set_visibility(true);
+
+        if let Some(i) = self.inner.default_button.get() {
+            if let Some((button, _)) = self.inner.buttons.borrow().get(i) {
+                hwnd.set_focused_view(Some(button.view()));
+            }
+        }
+    }
+
+    /// Close the message box without calling the handlers registered by
+    /// `subscribe_dismissed`.
+    pub fn close(&self) {
+        self.inner.dismissed.set(true);
+        self.inner.hwnd.close();
+    }
+}
+
+impl Inner {
+    fn dismiss(this: Rc<Self>, wm: pal::Wm, result: Option<usize>) {
+        if result.map_or(false, |i| i >= this.buttons.borrow().len()) {
+            return;
+        }
+
+        wm.invoke(move |wm| {
+            if this.dismissed.replace(true) {
+                return;
+            }
+
+            this.hwnd.close();
+
+            let handlers = this.dismiss_handlers.borrow();
+            for handler in handlers.iter() {
+                handler(wm, result);
+            }
+        });
+    }
+}
+
+struct MessageBoxWndListener {
+    inner: Weak<Inner>,
+}
+
+impl WndListener for MessageBoxWndListener {
+    fn close(&self, wm: pal::Wm, _: HWndRef<'_>) {
+        if let Some(inner) = self.inner.upgrade() {
+            Inner::dismiss(inner, wm, None);
+        }
+    }
+}
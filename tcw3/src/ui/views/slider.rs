@@ -21,7 +21,8 @@ use crate::{
         },
     },
     uicore::{
-        HView, HViewRef, HWndRef, KeyEvent, MouseDragListener, UpdateCtx, ViewFlags, ViewListener,
+        AccessAction, AccessInfo, AccessRole, AccessStates, AccessValue, HView, HViewRef, HWndRef,
+        KeyEvent, MouseDragListener, UpdateCtx, ViewFlags, ViewListener,
     },
     utils::resetiter,
 };
@@ -200,6 +201,7 @@ impl SliderRaw {
         });
 
         Shared::update_sb_override(&shared);
+        shared.update_access_info();
 
         shared.wrapper.set_listener(SlViewListener {
             shared: Rc::downgrade(&shared),
@@ -248,6 +250,7 @@ impl SliderRaw {
 
         self.shared.value.set(new_value);
         Shared::update_sb_override(&self.shared);
+        self.shared.update_access_info();
     }
 
     /// Set the tick mark positions.
@@ -426,6 +429,22 @@ impl Shared {
         })
     }
 
+    fn update_access_info(&self) {
+        let mut states = AccessStates::FOCUSABLE;
+        states.set(AccessStates::VERTICAL, self.vertical);
+
+        self.wrapper.set_access_info(Some(
+            AccessInfo::new(AccessRole::Slider)
+                .with_value(AccessValue::Range {
+                    value: self.value.get(),
+                    min: 0.0,
+                    max: 1.0,
+                })
+                .with_states(states)
+                .with_actions(vec![AccessAction::Increment, AccessAction::Decrement]),
+        ));
+    }
+
     fn set_active(&self, active: bool) {
         let frame = &self.frame;
 
@@ -585,6 +604,22 @@ impl ViewListener for SlViewListener {
         }
     }
 
+    fn perform_access_action(&self, wm: pal::Wm, _: HViewRef<'_>, action: AccessAction) {
+        let shared = if let Some(shared) = self.shared.upgrade() {
+            shared
+        } else {
+            return;
+        };
+
+        let dir = match action {
+            AccessAction::Increment => Dir::Incr,
+            AccessAction::Decrement => Dir::Decr,
+            AccessAction::Press => return,
+        };
+
+        shared.on_step.borrow()(wm, dir);
+    }
+
     fn mouse_drag(
         &self,
         _: pal::Wm,
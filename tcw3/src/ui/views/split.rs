@@ -16,8 +16,8 @@ use crate::{
         theming::{elem_id, ClassSet, Elem, HElem, Manager, StyledBox, Widget},
     },
     uicore::{
-        CursorShape, HView, HViewRef, Layout, LayoutCtx, MouseDragListener, SizeTraits, ViewFlags,
-        ViewListener,
+        AccessInfo, AccessRole, AccessStates, CursorShape, HView, HViewRef, Layout, LayoutCtx,
+        MouseDragListener, SizeTraits, ViewFlags, ViewListener,
     },
 };
 
@@ -161,6 +161,12 @@ impl Split {
 
         container.set_layout(shared.layout());
 
+        let mut access_states = AccessStates::empty();
+        access_states.set(AccessStates::VERTICAL, vertical);
+        container.set_access_info(Some(
+            AccessInfo::new(AccessRole::Split).with_states(access_states),
+        ));
+
         Self { container, shared }
     }
 
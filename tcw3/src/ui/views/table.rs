@@ -118,7 +118,7 @@ use crate::ui::scrolling::{
     lineset::{Index, Lineset, Size},
     tableremap::LineIdxMap,
 };
-use crate::uicore::{HView, HViewRef, SizeTraits, Sub, ViewFlags};
+use crate::uicore::{AccessInfo, AccessRole, HView, HViewRef, SizeTraits, Sub, ViewFlags};
 
 /// A scrollable widget displaying subviews along imaginary table cells.
 ///
@@ -521,6 +521,7 @@ impl Table {
             Rc::clone(&inner),
             &inner.state.borrow(),
         ));
+        view.set_access_info(Some(AccessInfo::new(AccessRole::Table)));
 
         Self { view, inner }
     }
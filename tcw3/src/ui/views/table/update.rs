@@ -21,7 +21,7 @@ use crate::{
         lineset::{DispCb, Index, LinesetModel, Size},
         tableremap::shuffle2d,
     },
-    uicore::{HView, HViewRef, Layout, LayoutCtx, SizeTraits},
+    uicore::{AccessInfo, AccessRole, HView, HViewRef, Layout, LayoutCtx, SizeTraits},
 };
 
 impl Inner {
@@ -142,6 +142,14 @@ impl Inner {
             new_cells_ranges[ty.i()] = line_grs_range_idx;
         }
 
+        // Cells without accessibility information are exposed as rows if
+        // the table only has one column
+        let cell_access_role = if state.linesets[LineTy::Col.i()].num_lines() == 1 {
+            AccessRole::Row
+        } else {
+            AccessRole::Cell
+        };
+
         // Remap `cells` using the new `cells_ranges`.
         //
         // We do not wish to re-create `cells` from scratch. We should be able
@@ -163,6 +171,9 @@ impl Inner {
                 let col = col as u64 + new_cells_ranges[0].start as u64;
                 let row = row as u64 + new_cells_ranges[1].start as u64;
                 let (view, ctrler) = model_query.new_view([col, row]);
+                if view.access_info().is_none() {
+                    view.set_access_info(Some(AccessInfo::new(cell_access_role)));
+                }
                 TableCell { view, ctrler }
             },
         );
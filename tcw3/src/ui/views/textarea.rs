@@ -0,0 +1,2107 @@
+//! Implements the multi-line text editor widget.
+use alt_fp::FloatOrd;
+use array::{Array, Array2};
+use arrayvec::ArrayVec;
+use cggeom::{box2, prelude::*, Box2};
+use cgmath::{Matrix3, Point2, Vector2};
+use flags_macro::flags;
+use momo::momo;
+use rc_borrow::RcBorrow;
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell, RefMut},
+    fmt,
+    ops::Range,
+    rc::{Rc, Weak},
+};
+use subscriber_list::SubscriberList;
+
+use crate::{
+    pal,
+    pal::prelude::*,
+    ui::{
+        editing::{
+            editor::{self, CaretBlink, Editor, EditorHost},
+            undo::{History, HistoryText, HistoryTx},
+        },
+        layouts::{EmptyLayout, FillLayout},
+        mixins::{
+            scrollwheel::{ScrollAxisFlags, ScrollModel},
+            CanvasMixin, ScrollWheelMixin,
+        },
+        theming::{
+            self, elem_id, roles, ClassSet, GetPropValue, HElem, Prop, PropKindFlags, StyledBox,
+            Widget,
+        },
+        views::scrollbar::{ScrollbarDragListener, ScrollbarRaw},
+    },
+    uicore::{
+        actions, ActionId, ActionStatus, CursorShape, HView, HViewRef, HWndRef, MouseDragListener,
+        ScrollDelta, ScrollListener, SizeTraits, Sub, UpdateCtx, ViewFlags, ViewListener,
+        WeakHView, WmExt,
+    },
+};
+
+mod doc;
+#[cfg(test)]
+mod tests;
+
+use self::doc::{Doc, ParaOff};
+
+/// A multi-line text editor widget.
+///
+/// The text is split into paragraphs by line breaks. Paragraphs are laid out
+/// lazily, so the cost of displaying and editing a long text is roughly
+/// proportional to the visible portion of the text.
+///
+/// # Styling
+///
+///  - `style_elem` - See [`StyledBox`](crate::ui::theming::StyledBox)
+///     - `subviews[`[`roles::GENERIC`]`]`: The text.
+///     - `subviews[`[`roles::VERT_SCROLLBAR`]`]`: The vertical scrollbar.
+///  - `style_elem > *` - `FgColor`, `Padding`, `Font`
+///  - `style_elem > * > #TEXT_SELECTION` - `BgColor`
+///
+#[derive(Debug)]
+pub struct TextArea {
+    /// The outermost view, which receives scroll wheel events.
+    wrapper: HView,
+    styled_box: Rc<StyledBox>,
+    view: HView,
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    wm: pal::Wm,
+    view: WeakHView,
+    styled_box: Weak<StyledBox>,
+    state: RefCell<State>,
+    style_elem: theming::Elem,
+    style_sel_elem: theming::Elem,
+    tictx_event_mask: Cell<pal::TextInputCtxEventFlags>,
+
+    scrollbar: ScrollbarRaw,
+    scroll_mixin: ScrollWheelMixin,
+    /// `true` means the scrollbar is being dragged, and its value is controlled
+    /// by `TextAreaScrollbarDragListener`.
+    scrollbar_drag_active: Cell<bool>,
+    /// `true` means a call to `update_scrollbar` is pended.
+    pending_scrollbar_update: Cell<bool>,
+
+    /// The list of subscribers of the `change` event.
+    change_handlers: RefCell<SubscriberList<Box<dyn Fn(pal::Wm)>>>,
+    /// `true` means the calls to `change_handlers` are pended.
+    pending_change_handler: Cell<bool>,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("wm", &self.wm)
+            .field("view", &self.view)
+            .field("state", &self.state)
+            .field("style_elem", &self.style_elem)
+            .field("style_sel_elem", &self.style_sel_elem)
+            .field("tictx_event_mask", &self.tictx_event_mask)
+            .field("scrollbar", &self.scrollbar)
+            .field("scroll_mixin", &self.scroll_mixin)
+            .field("scrollbar_drag_active", &self.scrollbar_drag_active)
+            .field("pending_scrollbar_update", &self.pending_scrollbar_update)
+            .field("pending_change_handler", &self.pending_change_handler)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    doc: Doc,
+    /// The vertical scroll position.
+    scroll: f32,
+    canvas: CanvasMixin,
+    tictx: Option<pal::HTextInputCtx>,
+    sel_range: [usize; 2],
+    comp_range: Option<[usize; 2]>,
+    /// The cached caret location in the document coordinate space. Should be
+    /// invalidated by assigning `None` whenever the selection range or the
+    /// document's layout is updated.
+    caret: Option<[pal::Beam; 2]>,
+    caret_layers: Option<[pal::HLayer; 2]>,
+    caret_blink: CaretBlink,
+    history: History,
+    /// The X coordinate the caret tries to stay at while it's moved
+    /// vertically.
+    preferred_x: Option<f32>,
+}
+
+impl TextArea {
+    pub fn new(wm: pal::Wm, style_manager: &'static theming::Manager) -> Self {
+        let style_elem = theming::Elem::new(style_manager);
+        let style_sel_elem = theming::Elem::new(style_manager);
+        style_sel_elem.set_class_set(elem_id::TEXT_SELECTION);
+        style_elem.insert_child(style_sel_elem.helem());
+
+        let view = HView::new(
+            ViewFlags::default()
+                | ViewFlags::ACCEPT_MOUSE_OVER
+                | ViewFlags::ACCEPT_MOUSE_DRAG
+                | ViewFlags::TAB_STOP
+                | ViewFlags::STRONG_FOCUS,
+        );
+        view.set_cursor_shape(Some(CursorShape::Text));
+        view.set_layout(EmptyLayout::new(SizeTraits::default()));
+
+        let scrollbar = ScrollbarRaw::new(style_manager, true);
+
+        let styled_box = Rc::new(StyledBox::new(style_manager, ViewFlags::default()));
+        styled_box.set_class_set(ClassSet::ENTRY | ClassSet::SCROLL_CONTAINER);
+        styled_box.set_auto_class_set(ClassSet::HOVER | ClassSet::FOCUS);
+        styled_box.set_subview(roles::GENERIC, Some(view.clone()));
+        styled_box.set_subelement(roles::GENERIC, Some(style_elem.helem()));
+        styled_box.set_child(roles::VERT_SCROLLBAR, Some(&scrollbar));
+
+        // Create a view for receiving scroll wheel events
+        let wrapper = HView::new(ViewFlags::ACCEPT_SCROLL);
+        wrapper.set_layout(FillLayout::new(styled_box.view()));
+
+        let mut doc = Doc::new();
+        doc.set_char_style(char_style(&style_elem));
+
+        let scroll_mixin = ScrollWheelMixin::new();
+        scroll_mixin.set_axes(ScrollAxisFlags::VERTICAL);
+
+        let inner = Rc::new(Inner {
+            wm,
+            view: view.downgrade(),
+            styled_box: Rc::downgrade(&styled_box),
+            state: RefCell::new(State {
+                doc,
+                scroll: 0.0,
+                canvas: CanvasMixin::new(),
+                tictx: None,
+                sel_range: [0; 2],
+                comp_range: None,
+                caret: None,
+                caret_layers: None,
+                caret_blink: CaretBlink::new(),
+                history: History::new(),
+                preferred_x: None,
+            }),
+            style_elem,
+            style_sel_elem,
+            tictx_event_mask: Cell::new(pal::TextInputCtxEventFlags::empty()),
+            scrollbar,
+            scroll_mixin,
+            scrollbar_drag_active: Cell::new(false),
+            pending_scrollbar_update: Cell::new(false),
+            change_handlers: RefCell::new(SubscriberList::new()),
+            pending_change_handler: Cell::new(false),
+        });
+
+        // Get notified when a styling property changes
+        let inner_weak = Rc::downgrade(&inner);
+        inner
+            .style_elem
+            .set_on_change(Box::new(move |_, kind_flags| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    reapply_style(RcBorrow::from(&inner), kind_flags);
+                }
+            }));
+
+        let inner_weak = Rc::downgrade(&inner);
+        inner
+            .style_sel_elem
+            .set_on_change(Box::new(move |_, kind_flags| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    if kind_flags.intersects(Prop::BgColor.kind_flags()) {
+                        reapply_style(RcBorrow::from(&inner), PropKindFlags::FG_COLOR);
+                    }
+                }
+            }));
+
+        // Scrollbar
+        let inner_weak = Rc::downgrade(&inner);
+        inner.scrollbar.set_on_drag(move |_| {
+            if let Some(inner) = inner_weak.upgrade() {
+                // Steal the control from `ScrollWheelMixin`
+                inner.scroll_mixin.stop();
+
+                Box::new(TextAreaScrollbarDragListener::new(inner)) as _
+            } else {
+                // The owner is gone, return a no-op listener
+                Box::new(()) as _
+            }
+        });
+
+        let inner_weak = Rc::downgrade(&inner);
+        inner.scrollbar.set_on_page_step(move |_, dir| {
+            if let Some(inner) = inner_weak.upgrade() {
+                inner.scroll_mixin.stop();
+
+                let page_height = inner
+                    .view
+                    .upgrade()
+                    .map_or(0.0, |view| view.frame().size().y);
+                let scroll = inner.state.borrow().scroll;
+                set_scroll(
+                    RcBorrow::from(&inner),
+                    scroll + page_height * dir as i32 as f32,
+                );
+            }
+        });
+
+        view.set_listener(TextAreaListener::new(Rc::clone(&inner)));
+        wrapper.set_listener(WrapperViewListener {
+            inner: Rc::downgrade(&inner),
+        });
+
+        inner.update_scrollbar();
+
+        Self {
+            wrapper,
+            styled_box,
+            view,
+            inner,
+        }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.wrapper.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.wrapper.as_ref()
+    }
+
+    /// Get the styling element representing the widget.
+    pub fn style_elem(&self) -> HElem {
+        self.styled_box.style_elem()
+    }
+
+    /// Set the class set of the inner `StyledBox`.
+    ///
+    /// It defaults to `ClassSet::ENTRY | ClassSet::SCROLL_CONTAINER`. Some bits
+    /// (e.g., `HAS_VERTICAL_SCROLLBAR`) are internally enforced and cannot be
+    /// modified.
+    pub fn set_class_set(&self, mut class_set: ClassSet) {
+        let styled_box = &self.styled_box;
+
+        // Protected bits
+        let protected = flags![ClassSet::{HAS_HORIZONTAL_SCROLLBAR | HAS_VERTICAL_SCROLLBAR}];
+        class_set -= protected;
+        class_set |= styled_box.class_set() & protected;
+        styled_box.set_class_set(class_set);
+    }
+
+    /// Get the class set of the inner `StyledBox`.
+    pub fn class_set(&self) -> ClassSet {
+        self.styled_box.class_set()
+    }
+
+    /// Get the text content.
+    pub fn text(&self) -> String {
+        self.inner.state.borrow().doc.text()
+    }
+
+    /// Set the text content.
+    ///
+    /// If the new value is different from the current one, it resets various
+    /// internal states such as an undo history. Otherwise, it does nothing.
+    #[momo]
+    pub fn set_text(&self, value: impl Into<String>) {
+        let value = value.into();
+        let value = normalize_line_breaks(&value);
+        if self.inner.state.borrow().doc.text() == value {
+            return;
+        }
+
+        update_state(
+            self.view.as_ref(),
+            RcBorrow::from(&self.inner),
+            &mut |state| {
+                state.doc.set_text(&value);
+                state.sel_range = [0, 0];
+                state.scroll = 0.0;
+                state.history = History::new();
+
+                UpdateStateFlags::ANY
+            },
+        );
+    }
+
+    /// Add a function called when the text content is modified.
+    ///
+    /// The function may be called spuriously, i.e., even when the text content
+    /// is not actually modified.
+    ///
+    /// The function is called via `Wm::invoke`, thus allowed to modify
+    /// view hierarchy and view attributes. However, it's not allowed to call
+    /// `subscribe_changed` when one of the handlers is being called.
+    pub fn subscribe_changed(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
+        self.inner.change_handlers.borrow_mut().insert(cb).untype()
+    }
+}
+
+impl Widget for TextArea {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        Some(self.style_elem())
+    }
+}
+
+fn char_style(elem: &theming::Elem) -> pal::CharStyle {
+    pal::CharStyle::new(pal::CharStyleAttrs {
+        sys: Some(elem.computed_values().font()),
+        ..Default::default()
+    })
+}
+
+fn reapply_style(inner: RcBorrow<'_, Inner>, kind_flags: PropKindFlags) {
+    let view = if let Some(view) = inner.view.upgrade() {
+        view
+    } else {
+        return;
+    };
+
+    let mut state = inner.state.borrow_mut();
+
+    if kind_flags.intersects(Prop::FgColor.kind_flags()) {
+        state.canvas.pend_draw(view.as_ref());
+    }
+
+    if kind_flags.intersects(Prop::Padding.kind_flags()) {
+        state.update_wrap_width(view.as_ref(), &inner.style_elem);
+        state.caret = None;
+        state.canvas.pend_draw(view.as_ref());
+        pend_update_scrollbar(inner);
+    }
+
+    if kind_flags.intersects(Prop::Font.kind_flags()) {
+        state.doc.set_char_style(char_style(&inner.style_elem));
+        state.caret = None;
+        state.canvas.pend_draw(view.as_ref());
+        pend_update_scrollbar(inner);
+    }
+}
+
+/// Convert all line breaks to `\n`.
+fn normalize_line_breaks(text: &str) -> Cow<'_, str> {
+    if text.contains('\r') {
+        text.replace("\r\n", "\n").replace('\r', "\n").into()
+    } else {
+        text.into()
+    }
+}
+
+impl Inner {
+    /// Update the scrollbar and the internally enforced class sets. Does not
+    /// change the scrollbar's value if it's being dragged.
+    fn update_scrollbar(&self) {
+        let view = if let Some(view) = self.view.upgrade() {
+            view
+        } else {
+            return;
+        };
+
+        let (max_scroll, scroll) = {
+            let state = self.state.borrow();
+            (
+                state.max_scroll(view.as_ref(), &self.style_elem),
+                state.scroll,
+            )
+        };
+        let max_scroll = max_scroll as f64;
+
+        if !self.scrollbar_drag_active.get() {
+            let value = (scroll as f64 / max_scroll.fmax(std::f64::MIN_POSITIVE)).fmin(1.0);
+            self.scrollbar.set_value(value.fmax(0.0));
+        }
+
+        // The page step can be infinity if the text is not scrollable.
+        // However, it must not be NaN.
+        let page_height = (view.frame().size().y as f64).fmax(std::f64::MIN_POSITIVE);
+        self.scrollbar.set_page_step(page_height / max_scroll);
+
+        if let Some(styled_box) = self.styled_box.upgrade() {
+            let mut class_set = styled_box.class_set();
+            class_set.set(ClassSet::HAS_VERTICAL_SCROLLBAR, max_scroll > 0.0);
+            if class_set != styled_box.class_set() {
+                styled_box.set_class_set(class_set);
+            }
+        }
+    }
+}
+
+/// Set the vertical scroll position. The value is clamped to the valid range.
+impl EditorHost for Inner {
+    fn wm(&self) -> pal::Wm {
+        self.wm
+    }
+
+    fn view(&self) -> Option<HView> {
+        self.view.upgrade()
+    }
+
+    fn caret_blink(&self) -> RefMut<'_, CaretBlink> {
+        RefMut::map(self.state.borrow_mut(), |state| &mut state.caret_blink)
+    }
+
+    fn selected_text(&self) -> Option<String> {
+        self.state.borrow_mut().editor().selected_text()
+    }
+
+    fn normalize_pasted_text(text: &str) -> Cow<'_, str> {
+        normalize_line_breaks(text)
+    }
+
+    fn insert(self: Rc<Self>, view: HViewRef<'_>, text: &str) {
+        TextAreaListener::new(self).handle_insert(view, text);
+    }
+}
+
+fn set_scroll(inner: RcBorrow<'_, Inner>, value: f32) {
+    let view = if let Some(view) = inner.view.upgrade() {
+        view
+    } else {
+        return;
+    };
+
+    let mut state = inner.state.borrow_mut();
+    let max_scroll = state.max_scroll(view.as_ref(), &inner.style_elem);
+    let value = value.fmin(max_scroll).fmax(0.0);
+
+    if value == state.scroll {
+        return;
+    }
+
+    state.scroll = value;
+    state.canvas.pend_draw(view.as_ref());
+    drop(state);
+
+    pend_update_scrollbar(inner);
+}
+
+/// Pend a call to `Inner::update_scrollbar`.
+fn pend_update_scrollbar(inner: RcBorrow<'_, Inner>) {
+    if inner.pending_scrollbar_update.get() {
+        return;
+    }
+    inner.pending_scrollbar_update.set(true);
+
+    let inner_weak = RcBorrow::to_weak(inner);
+
+    inner.wm.invoke_on_update(move |_| {
+        if let Some(inner) = inner_weak.upgrade() {
+            inner.pending_scrollbar_update.set(false);
+            inner.update_scrollbar();
+        }
+    });
+}
+
+impl State {
+    fn text_origin(&self, elem: &theming::Elem) -> Vector2<f32> {
+        let [padding_top, _, _, padding_left] = elem.computed_values().padding();
+        [padding_left, padding_top - self.scroll].into()
+    }
+
+    fn text_origin_global(&self, view: HViewRef<'_>, elem: &theming::Elem) -> Vector2<f32> {
+        let global_loc: [f32; 2] = view.global_frame().min.into();
+        self.text_origin(elem) + Vector2::from(global_loc)
+    }
+
+    /// Get the maximum scroll position.
+    fn max_scroll(&self, view: HViewRef<'_>, elem: &theming::Elem) -> f32 {
+        let [padding_top, _, padding_bottom, _] = elem.computed_values().padding();
+        let viewport_height = view.frame().size().y;
+        (self.doc.height() + padding_top + padding_bottom - viewport_height).fmax(0.0)
+    }
+
+    /// Update the wrapping width based on the view's current size. Returns
+    /// `true` if the width has changed.
+    fn update_wrap_width(&mut self, view: HViewRef<'_>, elem: &theming::Elem) -> bool {
+        let [_, padding_right, _, padding_left] = elem.computed_values().padding();
+        let width = (view.frame().size().x - padding_left - padding_right).fmax(0.0);
+        self.doc.set_width(width)
+    }
+
+    /// Find the paragraph containing the UTF-8 offset `i` and make sure it's
+    /// laid out.
+    fn locate(&mut self, i: usize) -> ParaOff {
+        let para_i = self.doc.para_index_at_byte(i);
+        self.doc.ensure_layout(para_i)
+    }
+
+    /// Get the caret location at the UTF-8 offset `i` in the document
+    /// coordinate space.
+    fn cursor_pos(&mut self, i: usize) -> [pal::Beam; 2] {
+        let off = self.locate(i);
+        let (para, _) = self.doc.para(off.index());
+        let pos = para.layout().unwrap().cursor_pos(i - off.bytes());
+        pos.map(|beam| pal::Beam::new(beam.x, beam.top + off.pos(), beam.bottom + off.pos()))
+    }
+
+    /// Get the caret location, using the cached value if possible.
+    fn caret(&mut self) -> [pal::Beam; 2] {
+        if let Some(caret) = self.caret {
+            caret
+        } else {
+            let caret = self.cursor_pos(self.sel_range[1]);
+            log::trace!("cursor_pos({:?}) = {:?}", self.sel_range[1], caret);
+            self.caret = Some(caret);
+            caret
+        }
+    }
+
+    /// Find the character boundary closest to the given point in the document
+    /// coordinate space.
+    fn index_from_point(&mut self, p: Point2<f32>) -> usize {
+        let para_i = self.doc.para_index_at_pos(p.y);
+        let off = self.doc.ensure_layout(para_i);
+        let (para, _) = self.doc.para(para_i);
+        let layout = para.layout().unwrap();
+        let local_y = (p.y - off.pos()).fmax(0.0);
+        off.bytes() + layout.cursor_index_from_point([p.x, local_y].into())
+    }
+
+    /// Find the next character boundary (as defined by `TextLayout::next_char`
+    /// or `TextLayout::next_word`) in the given direction. The boundary between
+    /// paragraphs is treated as a single character.
+    fn next_boundary(&mut self, i: usize, forward: bool, word: bool) -> usize {
+        let off = self.locate(i);
+        let (para, _) = self.doc.para(off.index());
+        let local_i = i - off.bytes();
+        let layout = para.layout().unwrap();
+
+        if forward && local_i >= para.content().len() {
+            // Move to the start of the next paragraph (if any)
+            off.bytes() + para.text().len()
+        } else if !forward && local_i == 0 {
+            // Move to the end of the previous paragraph (if any)
+            i.saturating_sub(1)
+        } else if word {
+            off.bytes() + layout.next_word(local_i, forward)
+        } else {
+            off.bytes() + layout.next_char(local_i, forward)
+        }
+    }
+
+    /// Get the range of the visual line containing the UTF-8 offset `i`,
+    /// excluding the trailing line break or wrapping whitespace characters.
+    fn line_range(&mut self, i: usize) -> Range<usize> {
+        let off = self.locate(i);
+        let (para, _) = self.doc.para(off.index());
+        let layout = para.layout().unwrap();
+        let line = layout.line_from_index(i - off.bytes());
+        let range = layout.line_index_range(line);
+
+        let end = if range.end < para.content().len() {
+            // The line is soft-wrapped. Exclude the character at the wrapping
+            // point so that the caret stays on this line.
+            layout.next_char(range.end, false).max(range.start)
+        } else {
+            para.content().len()
+        };
+
+        off.bytes() + range.start..off.bytes() + end
+    }
+
+    /// Find the character boundary on a visual line above or below the one
+    /// containing `i`, at the X coordinate `x`.
+    ///
+    /// It moves by at least one line. It continues moving until the traveled
+    /// distance reaches `distance`. If it hits the start or end of the
+    /// document, it returns `0` or `self.doc.len()`, respectively.
+    fn next_line(&mut self, i: usize, x: f32, forward: bool, distance: f32) -> usize {
+        let off = self.locate(i);
+        let mut para_i = off.index();
+        let mut line = {
+            let layout = self.doc.para(para_i).0.layout().unwrap();
+            layout.line_from_index(i - off.bytes())
+        };
+        let mut traveled = 0.0;
+
+        loop {
+            if forward {
+                let num_lines = self.doc.para(para_i).0.layout().unwrap().num_lines();
+                if line + 1 < num_lines {
+                    line += 1;
+                } else if para_i + 1 < self.doc.num_paras() {
+                    para_i += 1;
+                    self.doc.ensure_layout(para_i);
+                    line = 0;
+                } else {
+                    return self.doc.len();
+                }
+            } else if line > 0 {
+                line -= 1;
+            } else if para_i > 0 {
+                para_i -= 1;
+                self.doc.ensure_layout(para_i);
+                line = self.doc.para(para_i).0.layout().unwrap().num_lines() - 1;
+            } else {
+                return 0;
+            }
+
+            let layout = self.doc.para(para_i).0.layout().unwrap();
+            let bounds = layout.line_vertical_bounds(line);
+            traveled += bounds.end - bounds.start;
+
+            if traveled >= distance {
+                break;
+            }
+        }
+
+        let (para, off) = self.doc.para(para_i);
+        let layout = para.layout().unwrap();
+        let bounds = layout.line_vertical_bounds(line);
+        let y = (bounds.start + bounds.end) * 0.5;
+        off.bytes() + layout.cursor_index_from_point([x, y].into())
+    }
+
+    fn pend_update_after_focus_event(&mut self, hview: HViewRef<'_>) {
+        if self.sel_range[0] != self.sel_range[1] {
+            // A ranged selection is rendered using the `CanvasMixin`, so we
+            // have to set the redraw flag of `CanvasMixin` in addition to just
+            // calling `pend_update` (which is implicitly called by `pend_draw`)
+            self.canvas.pend_draw(hview);
+        } else {
+            hview.pend_update();
+        }
+    }
+
+    /// Reset the timer used for making the caret blink. See
+    /// [`CaretBlink::reset`].
+    fn reset_timer(
+        &mut self,
+        hview: HViewRef<'_>,
+        inner: RcBorrow<'_, Inner>,
+        override_focus: Option<bool>,
+    ) {
+        let has_caret = self.sel_range[0] == self.sel_range[1];
+        self.caret_blink
+            .reset(hview, inner, has_caret, override_focus);
+    }
+
+    /// Borrow the fields modified by editing operations.
+    fn editor(&mut self) -> Editor<'_, Doc> {
+        Editor {
+            text: &mut self.doc,
+            sel_range: &mut self.sel_range,
+            history: &mut self.history,
+        }
+    }
+
+    /// Adjust the scroll position so that the caret is visible. Returns `true`
+    /// if the scroll position has changed.
+    fn scroll_cursor_into_view(&mut self, hview: HViewRef<'_>, elem: &theming::Elem) -> bool {
+        let caret = self.caret();
+        let top = caret[0].top.fmin(caret[1].top);
+        let bottom = caret[0].bottom.fmax(caret[1].bottom);
+
+        let [padding_top, _, padding_bottom, _] = elem.computed_values().padding();
+        let viewport_height = hview.frame().size().y;
+
+        let new_scroll = self
+            .scroll
+            .fmax(bottom + padding_top + padding_bottom - viewport_height)
+            .fmin(top)
+            .fmin(self.max_scroll(hview, elem))
+            .fmax(0.0);
+
+        if new_scroll != self.scroll {
+            self.scroll = new_scroll;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Implements `ViewListener` and `TextInputCtxListener`.
+#[derive(Clone)]
+struct TextAreaListener {
+    inner: Rc<Inner>,
+}
+
+type MoveHandler = fn(&mut State, [usize; 2]) -> usize;
+
+impl TextAreaListener {
+    fn new(inner: Rc<Inner>) -> Self {
+        Self { inner }
+    }
+
+    fn handle_delete(&self, view: HViewRef<'_>, get_range: fn(&mut State, usize) -> usize) {
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            let [start, mut end] = state.sel_range;
+
+            if start == end {
+                log::trace!(
+                    "... there's no selection text. Deriving the deletion \
+                    range based on the cursor position ({:?})",
+                    start,
+                );
+
+                // If nothing is selected, derive the deletion range using
+                // the given function
+                end = get_range(state, start);
+            } else {
+                log::trace!("... deleting the selection at {:?}", start..end);
+            }
+
+            if state.editor().delete([start, end]) {
+                UpdateStateFlags::ANY
+            } else {
+                UpdateStateFlags::empty()
+            }
+        });
+    }
+
+    /// Replace the selected text with `text`.
+    fn handle_insert(&self, view: HViewRef<'_>, text: &str) {
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            if state.editor().insert(text) {
+                UpdateStateFlags::ANY
+            } else {
+                UpdateStateFlags::empty()
+            }
+        });
+    }
+
+    fn handle_move(&self, view: HViewRef<'_>, selecting: bool, get_new_pos: MoveHandler) {
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            log::trace!("... original sel_range = {:?}", state.sel_range);
+
+            if selecting {
+                // Move `state.sel_range[1]`.
+                state.sel_range[1] = get_new_pos(state, [state.sel_range[1]; 2]);
+            } else {
+                // Pass the current selection to `get_new_pos`. If the range is
+                // empty, the behavior is obvious (just move it around). If the
+                // range has a non-zero length, how to handle it is up to
+                // `get_new_pos`.
+                let [mut start, mut end] = state.sel_range;
+                if start > end {
+                    std::mem::swap(&mut start, &mut end);
+                }
+
+                state.sel_range = [get_new_pos(state, [start, end]); 2];
+            }
+
+            state.history.mark_logical_op_break();
+
+            log::trace!("... new sel_range = {:?}", state.sel_range);
+            UpdateStateFlags::SEL
+        });
+    }
+
+    /// Move the caret vertically. `page` specifies whether it should move by
+    /// a page or a line.
+    fn handle_move_vertical(&self, view: HViewRef<'_>, selecting: bool, forward: bool, page: bool) {
+        let [padding_top, _, padding_bottom, _] = self.inner.style_elem.computed_values().padding();
+        let distance = if page {
+            view.frame().size().y - padding_top - padding_bottom
+        } else {
+            0.0
+        };
+
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            log::trace!("... original sel_range = {:?}", state.sel_range);
+
+            let [mut start, mut end] = state.sel_range;
+            if !selecting && start != end {
+                // Move from the corresponding end of the selection
+                if start > end {
+                    std::mem::swap(&mut start, &mut end);
+                }
+                let i = if forward { end } else { start };
+                state.sel_range = [i, i];
+                state.caret = None;
+            }
+
+            // Remember the original X coordinate so that moving across a short
+            // line doesn't drag the caret to the left
+            let x = if let Some(x) = state.preferred_x {
+                x
+            } else {
+                state.caret()[0].x
+            };
+            state.preferred_x = Some(x);
+
+            let i = state.next_line(state.sel_range[1], x, forward, distance);
+
+            if selecting {
+                state.sel_range[1] = i;
+            } else {
+                state.sel_range = [i, i];
+            }
+
+            state.history.mark_logical_op_break();
+
+            log::trace!("... new sel_range = {:?}", state.sel_range);
+            UpdateStateFlags::SEL | UpdateStateFlags::VERTICAL_MOVE
+        });
+    }
+
+    fn handle_undo(&self, view: HViewRef<'_>) {
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            if state.editor().undo() {
+                UpdateStateFlags::ANY
+            } else {
+                UpdateStateFlags::empty()
+            }
+        });
+    }
+
+    fn handle_redo(&self, view: HViewRef<'_>) {
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            if state.editor().redo() {
+                UpdateStateFlags::ANY
+            } else {
+                UpdateStateFlags::empty()
+            }
+        });
+    }
+}
+
+impl ViewListener for TextAreaListener {
+    fn mount(&self, wm: pal::Wm, view: HViewRef<'_>, wnd: HWndRef<'_>) {
+        let mut state = self.inner.state.borrow_mut();
+        state.canvas.mount(wm, view, wnd);
+        state.caret_layers = Some(Array::from_fn(|_| wm.new_layer(Default::default())));
+
+        // `new_text_input_ctx` may get a document lock, so
+        // unborrow `state` first
+        drop(state);
+
+        let tictx = wm.new_text_input_ctx(&wnd.pal_hwnd().unwrap(), Box::new(self.clone()));
+
+        self.inner.state.borrow_mut().tictx = Some(tictx);
+    }
+
+    fn unmount(&self, wm: pal::Wm, view: HViewRef<'_>) {
+        let mut state = self.inner.state.borrow_mut();
+        state.canvas.unmount(wm, view);
+        for layer in state.caret_layers.as_ref().unwrap() {
+            wm.remove_layer(layer);
+        }
+        state.caret_layers = None;
+
+        // Stop the caret-blinking timer by specifying
+        // `override_focus = Some(false)`.
+        state.reset_timer(view, RcBorrow::from(&self.inner), Some(false));
+
+        drop(state);
+
+        let tictx = self.inner.state.borrow_mut().tictx.take();
+        if let Some(tictx) = tictx {
+            wm.remove_text_input_ctx(&tictx);
+        }
+    }
+
+    fn focus_enter(&self, wm: pal::Wm, hview: HViewRef<'_>) {
+        let tictx = self.inner.state.borrow().tictx.clone();
+        if let Some(tictx) = tictx {
+            wm.text_input_ctx_set_active(&tictx, true);
+        }
+
+        let mut state = self.inner.state.borrow_mut();
+        state.caret_blink.show();
+        state.pend_update_after_focus_event(hview);
+
+        // Start the caret-blinking timer if needed.
+        // `hview.is_focused() returns `false` at this point, so `reset_timer`
+        // would think the view is not focused yet. Override this behavior by
+        // specifying `override_focus = Some(true)`.
+        state.reset_timer(hview, RcBorrow::from(&self.inner), Some(true));
+
+        // Introduce a breakpoint in history coalescing
+        state.history.mark_logical_op_break();
+    }
+
+    fn focus_leave(&self, wm: pal::Wm, hview: HViewRef<'_>) {
+        let tictx = self.inner.state.borrow().tictx.clone();
+        if let Some(tictx) = tictx {
+            wm.text_input_ctx_set_active(&tictx, false);
+        }
+
+        let mut state = self.inner.state.borrow_mut();
+        state.pend_update_after_focus_event(hview);
+
+        // Stop the caret-blinking timer.
+        // `hview.is_focused() returns `true` at this point, so `reset_timer`
+        // would think the view is still focused. Override this behavior by
+        // specifying `override_focus = Some(false)`.
+        state.reset_timer(hview, RcBorrow::from(&self.inner), Some(false));
+    }
+
+    fn validate_action(&self, wm: pal::Wm, _: HViewRef<'_>, action: ActionId) -> ActionStatus {
+        let mut status = ActionStatus::empty();
+        match action {
+            actions::SELECT_ALL
+            | actions::SELECT_LINE
+            | actions::SELECT_PARAGRAPH
+            | actions::SELECT_WORD
+            | actions::INSERT_LINE_BREAK
+            | actions::INSERT_PARAGRAPH_BREAK
+            | actions::DELETE_BACKWARD
+            | actions::DELETE_BACKWARD_DECOMPOSING
+            | actions::DELETE_BACKWARD_WORD
+            | actions::DELETE_FORWARD
+            | actions::DELETE_FORWARD_WORD
+            | actions::MOVE_BACKWARD
+            | actions::MOVE_FORWARD
+            | actions::MOVE_LEFT
+            | actions::MOVE_RIGHT
+            | actions::MOVE_BACKWARD_WORD
+            | actions::MOVE_FORWARD_WORD
+            | actions::MOVE_LEFT_WORD
+            | actions::MOVE_RIGHT_WORD
+            | actions::MOVE_START_OF_LINE
+            | actions::MOVE_END_OF_LINE
+            | actions::MOVE_LEFT_END_OF_LINE
+            | actions::MOVE_RIGHT_END_OF_LINE
+            | actions::MOVE_START_OF_PARAGRAPH
+            | actions::MOVE_END_OF_PARAGRAPH
+            | actions::MOVE_START_OF_DOCUMENT
+            | actions::MOVE_END_OF_DOCUMENT
+            | actions::MOVE_UP
+            | actions::MOVE_DOWN
+            | actions::MOVE_UP_PAGE
+            | actions::MOVE_DOWN_PAGE
+            | actions::MOVE_BACKWARD_SELECTING
+            | actions::MOVE_FORWARD_SELECTING
+            | actions::MOVE_LEFT_SELECTING
+            | actions::MOVE_RIGHT_SELECTING
+            | actions::MOVE_BACKWARD_WORD_SELECTING
+            | actions::MOVE_FORWARD_WORD_SELECTING
+            | actions::MOVE_LEFT_WORD_SELECTING
+            | actions::MOVE_RIGHT_WORD_SELECTING
+            | actions::MOVE_START_OF_LINE_SELECTING
+            | actions::MOVE_END_OF_LINE_SELECTING
+            | actions::MOVE_LEFT_END_OF_LINE_SELECTING
+            | actions::MOVE_RIGHT_END_OF_LINE_SELECTING
+            | actions::MOVE_START_OF_PARAGRAPH_SELECTING
+            | actions::MOVE_END_OF_PARAGRAPH_SELECTING
+            | actions::MOVE_START_OF_DOCUMENT_SELECTING
+            | actions::MOVE_END_OF_DOCUMENT_SELECTING
+            | actions::MOVE_UP_SELECTING
+            | actions::MOVE_DOWN_SELECTING
+            | actions::MOVE_UP_PAGE_SELECTING
+            | actions::MOVE_DOWN_PAGE_SELECTING => {
+                status |= ActionStatus::VALID | ActionStatus::ENABLED;
+            }
+            _ => {
+                let state = self.inner.state.borrow();
+                status |= editor::validate_edit_action(wm, action, state.sel_range, &state.history);
+            }
+        }
+        status
+    }
+
+    fn perform_action(&self, _: pal::Wm, view: HViewRef<'_>, action: ActionId) {
+        let move_backward: MoveHandler = |state, sel| {
+            if sel[0] == sel[1] {
+                state.next_boundary(sel[0], false, false)
+            } else {
+                sel[0]
+            }
+        };
+        let move_forward: MoveHandler = |state, sel| {
+            if sel[0] == sel[1] {
+                state.next_boundary(sel[1], true, false)
+            } else {
+                sel[1]
+            }
+        };
+        let move_forward_word: MoveHandler = |state, sel| state.next_boundary(sel[1], true, true);
+        let move_backward_word: MoveHandler = |state, sel| state.next_boundary(sel[0], false, true);
+
+        let move_start_of_line: MoveHandler = |state, sel| state.line_range(sel[0]).start;
+        let move_end_of_line: MoveHandler = |state, sel| state.line_range(sel[1]).end;
+
+        let move_start_of_para: MoveHandler = |state, sel| state.locate(sel[0]).bytes();
+        let move_end_of_para: MoveHandler = |state, sel| {
+            let off = state.locate(sel[1]);
+            off.bytes() + state.doc.para(off.index()).0.content().len()
+        };
+
+        let move_start_of_doc: MoveHandler = |_, _| 0;
+        let move_end_of_doc: MoveHandler = |state, _| state.doc.len();
+
+        // TODO: Use the primary writing direction
+        let move_left = move_backward;
+        let move_right = move_forward;
+        let move_left_word = move_backward_word;
+        let move_right_word = move_forward_word;
+        let move_left_end = move_start_of_line;
+        let move_right_end = move_end_of_line;
+
+        match action {
+            actions::SELECT_ALL => {
+                log::trace!("Handling SELECT_ALL");
+                update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+                    log::trace!("... original sel_range = {:?}", state.sel_range);
+                    state.sel_range = [0, state.doc.len()];
+                    log::trace!("... new sel_range = {:?}", state.sel_range);
+                    UpdateStateFlags::SEL
+                });
+            }
+            actions::SELECT_LINE | actions::SELECT_PARAGRAPH | actions::SELECT_WORD => {
+                log::trace!("Handling SELECT_LINE, SELECT_PARAGRAPH, or SELECT_WORD");
+                let expand: fn(&mut State, usize, usize) -> [usize; 2] = match action {
+                    actions::SELECT_LINE => |state, start, end| {
+                        [state.line_range(start).start, state.line_range(end).end]
+                    },
+                    actions::SELECT_PARAGRAPH => |state, start, end| {
+                        [
+                            move_start_of_para(state, [start; 2]),
+                            move_end_of_para(state, [end; 2]),
+                        ]
+                    },
+                    _ => |state, start, end| {
+                        let start = state.next_boundary(start, true, false);
+                        let start = state.next_boundary(start, false, true);
+                        let end = state.next_boundary(end, false, false);
+                        let end = state.next_boundary(end, true, true);
+                        [start, end]
+                    },
+                };
+
+                update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+                    let [mut start, mut end] = state.sel_range;
+                    log::trace!("... original sel_range = {:?}", state.sel_range);
+                    if start > end {
+                        std::mem::swap(&mut start, &mut end);
+                    }
+
+                    state.sel_range = expand(state, start, end);
+
+                    log::trace!("... new sel_range = {:?}", state.sel_range);
+                    UpdateStateFlags::SEL
+                });
+            }
+            actions::INSERT_LINE_BREAK | actions::INSERT_PARAGRAPH_BREAK => {
+                log::trace!("Handling INSERT_LINE_BREAK or INSERT_PARAGRAPH_BREAK");
+                self.handle_insert(view, "\n");
+            }
+            actions::COPY => {
+                log::trace!("Handling COPY");
+                editor::copy_selection(&*self.inner);
+            }
+            actions::CUT => {
+                log::trace!("Handling CUT");
+                if editor::copy_selection(&*self.inner) {
+                    // Delete the selected text
+                    self.handle_delete(view, |_, i| i);
+                }
+            }
+            actions::PASTE | actions::PASTE_AS_PLAIN_TEXT => {
+                log::trace!("Handling PASTE or PASTE_AS_PLAIN_TEXT");
+                editor::paste(&self.inner);
+            }
+            actions::DELETE_BACKWARD => {
+                log::trace!("Handling DELETE_BACKWARD");
+                self.handle_delete(view, |state, i| state.next_boundary(i, false, false));
+            }
+            actions::DELETE_BACKWARD_DECOMPOSING => {
+                log::trace!("Handling DELETE_BACKWARD_DECOMPOSING");
+                self.handle_delete(view, |state, i| {
+                    if i == 0 {
+                        0
+                    } else {
+                        state.doc.floor_index(i - 1)
+                    }
+                });
+            }
+            actions::DELETE_BACKWARD_WORD => {
+                log::trace!("Handling DELETE_BACKWARD_WORD");
+                self.handle_delete(view, |state, i| state.next_boundary(i, false, true));
+            }
+            actions::DELETE_FORWARD => {
+                log::trace!("Handling DELETE_FORWARD");
+                self.handle_delete(view, |state, i| state.next_boundary(i, true, false));
+            }
+            actions::DELETE_FORWARD_WORD => {
+                log::trace!("Handling DELETE_FORWARD_WORD");
+                self.handle_delete(view, |state, i| state.next_boundary(i, true, true));
+            }
+
+            actions::MOVE_BACKWARD => {
+                log::trace!("Handling MOVE_BACKWARD");
+                self.handle_move(view, false, move_backward);
+            }
+            actions::MOVE_BACKWARD_SELECTING => {
+                log::trace!("Handling MOVE_BACKWARD_SELECTING");
+                self.handle_move(view, true, move_backward);
+            }
+            actions::MOVE_FORWARD => {
+                log::trace!("Handling MOVE_FORWARD");
+                self.handle_move(view, false, move_forward);
+            }
+            actions::MOVE_FORWARD_SELECTING => {
+                log::trace!("Handling MOVE_FORWARD_SELECTING");
+                self.handle_move(view, true, move_forward);
+            }
+            actions::MOVE_LEFT => {
+                log::trace!("Handling MOVE_LEFT");
+                self.handle_move(view, false, move_left);
+            }
+            actions::MOVE_LEFT_SELECTING => {
+                log::trace!("Handling MOVE_LEFT_SELECTING");
+                self.handle_move(view, true, move_left);
+            }
+            actions::MOVE_RIGHT => {
+                log::trace!("Handling MOVE_RIGHT");
+                self.handle_move(view, false, move_right);
+            }
+            actions::MOVE_RIGHT_SELECTING => {
+                log::trace!("Handling MOVE_RIGHT_SELECTING");
+                self.handle_move(view, true, move_right);
+            }
+
+            actions::MOVE_BACKWARD_WORD => {
+                log::trace!("Handling MOVE_BACKWARD_WORD");
+                self.handle_move(view, false, move_backward_word);
+            }
+            actions::MOVE_BACKWARD_WORD_SELECTING => {
+                log::trace!("Handling MOVE_BACKWARD_WORD_SELECTING");
+                self.handle_move(view, true, move_backward_word);
+            }
+            actions::MOVE_FORWARD_WORD => {
+                log::trace!("Handling MOVE_FORWARD_WORD");
+                self.handle_move(view, false, move_forward_word);
+            }
+            actions::MOVE_FORWARD_WORD_SELECTING => {
+                log::trace!("Handling MOVE_FORWARD_WORD_SELECTING");
+                self.handle_move(view, true, move_forward_word);
+            }
+            actions::MOVE_LEFT_WORD => {
+                log::trace!("Handling MOVE_LEFT_WORD");
+                self.handle_move(view, false, move_left_word);
+            }
+            actions::MOVE_LEFT_WORD_SELECTING => {
+                log::trace!("Handling MOVE_LEFT_WORD_SELECTING");
+                self.handle_move(view, true, move_left_word);
+            }
+            actions::MOVE_RIGHT_WORD => {
+                log::trace!("Handling MOVE_RIGHT_WORD");
+                self.handle_move(view, false, move_right_word);
+            }
+            actions::MOVE_RIGHT_WORD_SELECTING => {
+                log::trace!("Handling MOVE_RIGHT_WORD_SELECTING");
+                self.handle_move(view, true, move_right_word);
+            }
+
+            actions::MOVE_START_OF_LINE => {
+                log::trace!("Handling MOVE_START_OF_LINE");
+                self.handle_move(view, false, move_start_of_line);
+            }
+            actions::MOVE_START_OF_LINE_SELECTING => {
+                log::trace!("Handling MOVE_START_OF_LINE_SELECTING");
+                self.handle_move(view, true, move_start_of_line);
+            }
+            actions::MOVE_END_OF_LINE => {
+                log::trace!("Handling MOVE_END_OF_LINE");
+                self.handle_move(view, false, move_end_of_line);
+            }
+            actions::MOVE_END_OF_LINE_SELECTING => {
+                log::trace!("Handling MOVE_END_OF_LINE_SELECTING");
+                self.handle_move(view, true, move_end_of_line);
+            }
+            actions::MOVE_LEFT_END_OF_LINE => {
+                log::trace!("Handling MOVE_LEFT_END_OF_LINE");
+                self.handle_move(view, false, move_left_end);
+            }
+            actions::MOVE_LEFT_END_OF_LINE_SELECTING => {
+                log::trace!("Handling MOVE_LEFT_END_OF_LINE_SELECTING");
+                self.handle_move(view, true, move_left_end);
+            }
+            actions::MOVE_RIGHT_END_OF_LINE => {
+                log::trace!("Handling MOVE_RIGHT_END_OF_LINE");
+                self.handle_move(view, false, move_right_end);
+            }
+            actions::MOVE_RIGHT_END_OF_LINE_SELECTING => {
+                log::trace!("Handling MOVE_RIGHT_END_OF_LINE_SELECTING");
+                self.handle_move(view, true, move_right_end);
+            }
+
+            actions::MOVE_START_OF_PARAGRAPH => {
+                log::trace!("Handling MOVE_START_OF_PARAGRAPH");
+                self.handle_move(view, false, move_start_of_para);
+            }
+            actions::MOVE_START_OF_PARAGRAPH_SELECTING => {
+                log::trace!("Handling MOVE_START_OF_PARAGRAPH_SELECTING");
+                self.handle_move(view, true, move_start_of_para);
+            }
+            actions::MOVE_END_OF_PARAGRAPH => {
+                log::trace!("Handling MOVE_END_OF_PARAGRAPH");
+                self.handle_move(view, false, move_end_of_para);
+            }
+            actions::MOVE_END_OF_PARAGRAPH_SELECTING => {
+                log::trace!("Handling MOVE_END_OF_PARAGRAPH_SELECTING");
+                self.handle_move(view, true, move_end_of_para);
+            }
+
+            actions::MOVE_START_OF_DOCUMENT => {
+                log::trace!("Handling MOVE_START_OF_DOCUMENT");
+                self.handle_move(view, false, move_start_of_doc);
+            }
+            actions::MOVE_START_OF_DOCUMENT_SELECTING => {
+                log::trace!("Handling MOVE_START_OF_DOCUMENT_SELECTING");
+                self.handle_move(view, true, move_start_of_doc);
+            }
+            actions::MOVE_END_OF_DOCUMENT => {
+                log::trace!("Handling MOVE_END_OF_DOCUMENT");
+                self.handle_move(view, false, move_end_of_doc);
+            }
+            actions::MOVE_END_OF_DOCUMENT_SELECTING => {
+                log::trace!("Handling MOVE_END_OF_DOCUMENT_SELECTING");
+                self.handle_move(view, true, move_end_of_doc);
+            }
+
+            actions::MOVE_UP => {
+                log::trace!("Handling MOVE_UP");
+                self.handle_move_vertical(view, false, false, false);
+            }
+            actions::MOVE_UP_SELECTING => {
+                log::trace!("Handling MOVE_UP_SELECTING");
+                self.handle_move_vertical(view, true, false, false);
+            }
+            actions::MOVE_DOWN => {
+                log::trace!("Handling MOVE_DOWN");
+                self.handle_move_vertical(view, false, true, false);
+            }
+            actions::MOVE_DOWN_SELECTING => {
+                log::trace!("Handling MOVE_DOWN_SELECTING");
+                self.handle_move_vertical(view, true, true, false);
+            }
+            actions::MOVE_UP_PAGE => {
+                log::trace!("Handling MOVE_UP_PAGE");
+                self.handle_move_vertical(view, false, false, true);
+            }
+            actions::MOVE_UP_PAGE_SELECTING => {
+                log::trace!("Handling MOVE_UP_PAGE_SELECTING");
+                self.handle_move_vertical(view, true, false, true);
+            }
+            actions::MOVE_DOWN_PAGE => {
+                log::trace!("Handling MOVE_DOWN_PAGE");
+                self.handle_move_vertical(view, false, true, true);
+            }
+            actions::MOVE_DOWN_PAGE_SELECTING => {
+                log::trace!("Handling MOVE_DOWN_PAGE_SELECTING");
+                self.handle_move_vertical(view, true, true, true);
+            }
+
+            actions::UNDO => {
+                log::trace!("Handling UNDO");
+                self.handle_undo(view);
+            }
+            actions::REDO => {
+                log::trace!("Handling REDO");
+                self.handle_redo(view);
+            }
+
+            unknown_action => {
+                log::warn!("Unknown action: {}", unknown_action);
+            }
+        }
+    }
+
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        hview: HViewRef<'_>,
+        _loc: Point2<f32>,
+        button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        if button == 0 {
+            Box::new(TextAreaDragListener::new(
+                hview.cloned(),
+                Rc::clone(&self.inner),
+            ))
+        } else {
+            Box::new(())
+        }
+    }
+
+    fn position(&self, wm: pal::Wm, view: HViewRef<'_>) {
+        let mut state = self.inner.state.borrow_mut();
+        state.canvas.position(wm, view);
+
+        if state.update_wrap_width(view, &self.inner.style_elem) {
+            state.caret = None;
+            state.canvas.pend_draw(view);
+        }
+
+        // The view might have been resized, so make sure the scroll position is
+        // still in the valid range
+        let max_scroll = state.max_scroll(view, &self.inner.style_elem);
+        if state.scroll > max_scroll {
+            state.scroll = max_scroll;
+            state.canvas.pend_draw(view);
+        }
+
+        if state.scroll_cursor_into_view(view, &self.inner.style_elem) {
+            state.canvas.pend_draw(view);
+        }
+
+        // Unborrow `state` before calling `text_input_ctx_on_layout_change`
+        drop(state);
+
+        pend_update_scrollbar(RcBorrow::from(&self.inner));
+
+        if (self.inner.tictx_event_mask.get()).contains(pal::TextInputCtxEventFlags::LAYOUT_CHANGE)
+        {
+            let tictx = self.inner.state.borrow().tictx.clone();
+            if let Some(tictx) = tictx {
+                wm.text_input_ctx_on_layout_change(&tictx);
+            }
+        }
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let mut state = self.inner.state.borrow_mut();
+        let state = &mut *state; // enable split borrow
+
+        let elem = &self.inner.style_elem;
+        let color = elem.computed_values().fg_color();
+        let sel_color = self.inner.style_sel_elem.computed_values().bg_color();
+
+        let text_origin = state.text_origin(elem);
+        let view_size = view.frame().size();
+        let is_focused = view.improper_subview_is_focused();
+
+        // Lay out the visible paragraphs
+        let old_doc_height = state.doc.height();
+        let visible_top = -text_origin.y;
+        let visible_bottom = visible_top + view_size.y;
+        let first_para = state.doc.para_index_at_pos(visible_top);
+        let mut end_para = first_para;
+        while end_para < state.doc.num_paras() {
+            let off = state.doc.ensure_layout(end_para);
+            if off.pos() >= visible_bottom {
+                break;
+            }
+            end_para += 1;
+        }
+
+        if state.doc.height() != old_doc_height {
+            // The estimated heights were replaced with the actual ones, which
+            // might have moved the caret
+            state.caret = None;
+            pend_update_scrollbar(RcBorrow::from(&self.inner));
+        }
+
+        // Calculate the location of the caret
+        let caret = state.caret();
+
+        let doc = &state.doc;
+        let sel_range = &state.sel_range;
+        let comp_range = &state.comp_range;
+
+        let visual_bounds = Box2::with_size(Point2::new(0.0, 0.0), view_size);
+
+        state
+            .canvas
+            .update_layer(wm, view, ctx.hwnd(), visual_bounds, |draw_ctx| {
+                let c = &mut draw_ctx.canvas;
+
+                let mut sel_range = *sel_range;
+                if sel_range[1] < sel_range[0] {
+                    sel_range.reverse();
+                }
+
+                c.save();
+                c.mult_transform(Matrix3::from_translation(text_origin));
+
+                for para_i in first_para..end_para {
+                    let (para, off) = doc.para(para_i);
+                    let layout = para.layout().unwrap();
+                    let para_y = off.pos();
+                    let to_local = |range: [usize; 2]| -> Option<Range<usize>> {
+                        let start = range[0].max(off.bytes()) - off.bytes();
+                        let end = range[1].min(off.bytes() + para.content().len());
+                        if end > off.bytes() + start {
+                            Some(start..end - off.bytes())
+                        } else {
+                            None
+                        }
+                    };
+
+                    if is_focused {
+                        if let Some(local_sel) = to_local(sel_range) {
+                            // Fill the selection
+                            c.set_fill_rgb(sel_color);
+                            for (range, vert_bounds) in line_ranges(layout, local_sel) {
+                                for run in layout.run_metrics_of_range(range).iter() {
+                                    c.fill_rect(box2! {
+                                        min: [run.bounds.start, vert_bounds.start + para_y],
+                                        max: [run.bounds.end, vert_bounds.end + para_y],
+                                    });
+                                }
+                            }
+                        }
+                    }
+
+                    c.draw_text(layout, Point2::new(0.0, para_y), color);
+
+                    if let Some(local_comp) = comp_range.and_then(to_local) {
+                        // Draw an underline below the preedit text
+                        c.set_fill_rgb([color.r, color.g, color.b, color.a * 0.6].into());
+                        for (range, _) in line_ranges(layout, local_comp) {
+                            let line = layout.line_from_index(range.start);
+                            let y = layout.line_baseline(line) + para_y;
+                            for run in layout.run_metrics_of_range(range).iter() {
+                                c.fill_rect(box2! {
+                                    min: [run.bounds.start, y + 1.0],
+                                    max: [run.bounds.end, y + 2.0],
+                                });
+                            }
+                        }
+                    }
+                }
+
+                c.restore();
+            });
+
+        // Display the caret
+        let caret_layers = state.caret_layers.as_ref().unwrap();
+        if sel_range[0] == sel_range[1] {
+            let mut layer_attrs: ArrayVec<[_; 2]> = (0..2)
+                .map(|_| pal::LayerAttrs {
+                    opacity: Some(1.0),
+                    bg_color: Some(color),
+                    ..Default::default()
+                })
+                .collect();
+
+            let global_frame = view.global_frame();
+            let offset: [f32; 2] = global_frame.min.into();
+            let mut offset: cgmath::Vector2<f32> = offset.into();
+            offset += text_origin;
+
+            let [mut rect0, mut rect1] = caret.map(|beam| beam.as_wide_box2(1.0).translate(offset));
+
+            if caret[0].x != caret[1].x {
+                // If there are a strong cursor and a weak cursor,
+                // display the former in the upper half and the latter
+                // in the lower half
+                rect0.max.y = rect0.mid().y;
+                rect1.min.y = rect0.max.y;
+                layer_attrs[1].bounds = Some(rect1);
+            } else {
+                layer_attrs[1].opacity = Some(0.0);
+            }
+            layer_attrs[0].bounds = Some(rect0);
+
+            // Hide the caret if it's out of view or in the invisible phase
+            for i in 0..2 {
+                let x = caret[i].x + text_origin.x;
+                let top = caret[i].top + text_origin.y;
+                let bottom = caret[i].bottom + text_origin.y;
+                if !state.caret_blink.is_visible()
+                    || !(0.0..global_frame.size().x).contains(&x)
+                    || top < 0.0
+                    || bottom > global_frame.size().y
+                {
+                    layer_attrs[i].opacity = Some(0.0);
+                }
+            }
+
+            for (layer, attrs) in caret_layers.iter().zip(layer_attrs.drain(..)) {
+                wm.set_layer_attr(layer, attrs);
+            }
+        } else {
+            for layer in caret_layers.iter() {
+                wm.set_layer_attr(
+                    layer,
+                    pal::LayerAttrs {
+                        opacity: Some(0.0),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+
+        let expected_num_layers = 1 + is_focused as usize * 2;
+
+        if ctx.layers().len() != expected_num_layers {
+            let mut layers = Vec::with_capacity(3);
+            layers.push(state.canvas.layer().unwrap().clone());
+            if is_focused {
+                layers.push(caret_layers[0].clone());
+                layers.push(caret_layers[1].clone());
+            }
+            ctx.set_layers(layers);
+        }
+    }
+}
+
+/// Split a UTF-8 offset range in a `TextLayout` into per-line ranges, which
+/// can be passed to `TextLayout::run_metrics_of_range`. Each range is returned
+/// with the vertical bounds of the line.
+fn line_ranges(
+    layout: &pal::TextLayout,
+    range: Range<usize>,
+) -> impl Iterator<Item = (Range<usize>, Range<f32>)> + '_ {
+    let first_line = layout.line_from_index(range.start);
+    let last_line = layout.line_from_index(range.end);
+
+    (first_line..=last_line).filter_map(move |line| {
+        let line_range = layout.line_index_range(line);
+        let start = line_range.start.max(range.start);
+        let end = line_range.end.min(range.end);
+        if start < end {
+            Some((start..end, layout.line_vertical_bounds(line)))
+        } else {
+            None
+        }
+    })
+}
+
+impl pal::iface::TextInputCtxListener<pal::Wm> for TextAreaListener {
+    fn edit(
+        &self,
+        _: pal::Wm,
+        _: &pal::HTextInputCtx,
+        _mutating: bool,
+    ) -> Box<dyn pal::iface::TextInputCtxEdit<pal::Wm> + '_> {
+        Box::new(Edit {
+            state: self.inner.state.borrow_mut(),
+            view: self.inner.view.upgrade().unwrap(),
+            inner: RcBorrow::from(&self.inner),
+            history_tx: None,
+        })
+    }
+
+    fn set_event_mask(
+        &self,
+        _: pal::Wm,
+        _: &pal::HTextInputCtx,
+        flags: pal::TextInputCtxEventFlags,
+    ) {
+        self.inner.tictx_event_mask.set(flags);
+    }
+}
+
+/// Implements `TextInputCtxEdit`.
+struct Edit<'a> {
+    state: RefMut<'a, State>,
+    inner: RcBorrow<'a, Inner>,
+    view: HView,
+    history_tx: Option<HistoryTx>,
+}
+
+impl Edit<'_> {
+    fn check_range(&self, range: &Range<usize>) {
+        let len = self.state.doc.len();
+        debug_assert!(
+            range.start <= len && range.end <= len,
+            "{:?} is out of range (len=({:?})",
+            range,
+            len
+        );
+    }
+
+    /// Start a transaction of updating the undo history if it hasn't started
+    /// yet.
+    fn ensure_history_tx(&mut self) {
+        if self.history_tx.is_none() {
+            self.history_tx = Some(self.state.history.start_transaction());
+        }
+    }
+}
+
+impl Drop for Edit<'_> {
+    fn drop(&mut self) {
+        let state = &mut *self.state; // enable split borrow
+
+        if let Some(history_tx) = self.history_tx.take() {
+            history_tx.finish(&mut state.history, &state.doc);
+
+            // `text` might have changed, so raise `changed`
+            // (False positives are positive because of `set_composition_range`)
+            pend_raise_change(self.inner);
+
+            // Keep the caret visible while typing
+            if state.scroll_cursor_into_view(self.view.as_ref(), &self.inner.style_elem) {
+                state.canvas.pend_draw(self.view.as_ref());
+            }
+
+            pend_update_scrollbar(self.inner);
+        }
+    }
+}
+
+impl pal::iface::TextInputCtxEdit<pal::Wm> for Edit<'_> {
+    fn selected_range(&mut self) -> Range<usize> {
+        let [i1, i2] = self.state.sel_range;
+        i1..i2
+    }
+
+    fn set_selected_range(&mut self, range: Range<usize>) {
+        self.check_range(&range);
+
+        let range = [range.start, range.end];
+        if range == self.state.sel_range {
+            return;
+        }
+        self.state.sel_range = range;
+        self.state.canvas.pend_draw(self.view.as_ref());
+        self.state.caret = None;
+        self.state.preferred_x = None;
+
+        // Update the timer's state
+        self.state.reset_timer(self.view.as_ref(), self.inner, None);
+        self.state.caret_blink.show();
+    }
+
+    fn set_composition_range(&mut self, range: Option<Range<usize>>) {
+        if let Some(r) = &range {
+            self.check_range(r)
+        }
+
+        let range = range.map(|r| [r.start, r.end]);
+        if range == self.state.comp_range {
+            return;
+        }
+        self.state.comp_range = range;
+        self.state.canvas.pend_draw(self.view.as_ref());
+
+        self.ensure_history_tx();
+        self.history_tx
+            .as_mut()
+            .unwrap()
+            .set_composition_active(range.is_some());
+    }
+
+    fn replace(&mut self, range: Range<usize>, text: &str) {
+        self.check_range(&range);
+
+        self.ensure_history_tx();
+
+        let state = &mut *self.state; // enable split borrow
+
+        // Update `doc` and the selection, recording the change to the undo
+        // history
+        state
+            .editor()
+            .replace(self.history_tx.as_mut().unwrap(), range, text);
+
+        state.caret = None;
+        state.preferred_x = None;
+        state.canvas.pend_draw(self.view.as_ref());
+
+        // Reset the timer's phase
+        state.reset_timer(self.view.as_ref(), self.inner, None);
+        state.caret_blink.show();
+    }
+
+    fn slice(&mut self, range: Range<usize>) -> String {
+        self.check_range(&range);
+
+        self.state.doc.slice(range)
+    }
+
+    fn floor_index(&mut self, i: usize) -> usize {
+        self.state.doc.floor_index(i)
+    }
+
+    fn ceil_index(&mut self, i: usize) -> usize {
+        self.state.doc.ceil_index(i)
+    }
+
+    fn len(&mut self) -> usize {
+        self.state.doc.len()
+    }
+
+    fn index_from_point(
+        &mut self,
+        point: Point2<f32>,
+        _flags: pal::IndexFromPointFlags,
+    ) -> Option<usize> {
+        let origin = self
+            .state
+            .text_origin_global(self.view.as_ref(), &self.inner.style_elem);
+        Some(self.state.index_from_point(point - origin))
+    }
+
+    fn frame(&mut self) -> Box2<f32> {
+        self.view.global_frame()
+    }
+
+    fn slice_bounds(&mut self, range: Range<usize>) -> (Box2<f32>, usize) {
+        self.check_range(&range);
+
+        let origin = self
+            .state
+            .text_origin_global(self.view.as_ref(), &self.inner.style_elem);
+
+        let state = &mut *self.state;
+        let off = state.locate(range.start);
+        let (para, _) = state.doc.para(off.index());
+        let layout = para.layout().unwrap();
+        let local_start = range.start - off.bytes();
+
+        // Limit the range to the line containing `range.start`
+        let line = layout.line_from_index(local_start);
+        let line_range = layout.line_index_range(line);
+        let local_end = (range.end - off.bytes()).min(line_range.end);
+
+        if local_end <= local_start {
+            // `range` is empty or starts with a line break. Return the caret
+            // position calculated by `cursor_pos`.
+            let strong_cursor = layout.cursor_pos(local_start)[0];
+            let bounds = strong_cursor
+                .as_box2()
+                .translate(origin + Vector2::new(0.0, off.pos()));
+            let end = if range.len() == 0 {
+                range.start
+            } else {
+                off.bytes() + para.text().len()
+            };
+            return (bounds, end.min(range.end));
+        }
+
+        let runs = layout.run_metrics_of_range(local_start..local_end);
+        let vert_bounds = layout.line_vertical_bounds(line);
+        let (min_x, max_x) = runs.iter().fold(
+            (std::f32::INFINITY, std::f32::NEG_INFINITY),
+            |(min, max), run| (min.fmin(run.bounds.start), max.fmax(run.bounds.end)),
+        );
+
+        let bounds = box2! {
+            min: [min_x, vert_bounds.start + off.pos()],
+            max: [max_x, vert_bounds.end + off.pos()],
+        };
+        (bounds.translate(origin), off.bytes() + local_end)
+    }
+}
+
+bitflags::bitflags! {
+    struct UpdateStateFlags: u8 {
+        /// The selection might have changed.
+        const SEL = 1;
+        const LAYOUT = 1 << 1;
+        const ANY = 1 << 2;
+        /// The caret was moved vertically. `preferred_x` is preserved.
+        const VERTICAL_MOVE = 1 << 3;
+    }
+}
+
+/// Update the text and/or selection using a given closure. This method mustn't
+/// be used in an implementation of `TextInputCtxEdit` because it calls
+/// `text_input_ctx_on_selection_change` and/or `text_input_ctx_reset`.
+///
+/// If the provided closure modifies the text, it is responsible for updating
+/// the undo history accordingly.
+fn update_state(
+    hview: HViewRef<'_>,
+    inner: RcBorrow<'_, Inner>,
+    f: &mut dyn FnMut(&mut State) -> UpdateStateFlags,
+) {
+    let wm = inner.wm;
+    let mut state = inner.state.borrow_mut();
+    let old_sel_range = state.sel_range;
+
+    // Call the given function
+    let mut flags = f(&mut *state);
+
+    // Clear `UpdateStateFlags::SEL` if the selection did not change.
+    if old_sel_range == state.sel_range {
+        flags.set(UpdateStateFlags::SEL, false);
+    }
+
+    // Return early if nothing has changed
+    if !flags.intersects(flags![UpdateStateFlags::{SEL | LAYOUT | ANY}]) {
+        return;
+    }
+
+    if !flags.contains(UpdateStateFlags::VERTICAL_MOVE) {
+        state.preferred_x = None;
+    }
+
+    let tictx = state.tictx.clone();
+
+    // Invalidate the remembered caret position
+    state.caret = None;
+
+    if flags.intersects(flags![UpdateStateFlags::{ANY | SEL}]) {
+        let scroll_changed = state.scroll_cursor_into_view(hview, &inner.style_elem);
+        if scroll_changed {
+            flags |= UpdateStateFlags::LAYOUT;
+        }
+    }
+
+    if flags.intersects(flags![UpdateStateFlags::{ANY | LAYOUT}])
+        || (old_sel_range[0] != old_sel_range[1])
+        || (state.sel_range[0] != state.sel_range[1])
+    {
+        // A ranged selection is rendered using the `CanvasMixin`, so we
+        // have to set the redraw flag of `CanvasMixin` in addition to just
+        // calling `pend_update` (which is implicitly called by `pend_draw`)
+        state.canvas.pend_draw(hview);
+    } else {
+        hview.pend_update();
+    }
+
+    // Update the caret-blinking timer
+    state.caret_blink.show();
+    state.reset_timer(hview, inner, None);
+
+    // Raise `changed`
+    if flags.contains(UpdateStateFlags::ANY) {
+        pend_raise_change(inner);
+    }
+
+    if flags.intersects(flags![UpdateStateFlags::{ANY | LAYOUT}]) {
+        pend_update_scrollbar(inner);
+    }
+
+    // Unborrow `state` before calling `text_input_ctx_on_selection_change`,
+    // which might request a document lock
+    drop(state);
+    if let Some(tictx) = tictx {
+        if flags.contains(UpdateStateFlags::ANY) {
+            wm.text_input_ctx_reset(&tictx);
+        } else {
+            if flags.contains(UpdateStateFlags::LAYOUT) {
+                wm.text_input_ctx_on_layout_change(&tictx);
+            }
+            if flags.contains(UpdateStateFlags::SEL) {
+                wm.text_input_ctx_on_selection_change(&tictx);
+            }
+        }
+    }
+}
+
+/// Pend calls to the `change` event handlers.
+fn pend_raise_change(inner: RcBorrow<'_, Inner>) {
+    if inner.pending_change_handler.get() {
+        return;
+    }
+
+    inner.pending_change_handler.set(true);
+
+    let inner_weak = RcBorrow::to_weak(inner);
+
+    inner.wm.invoke_on_update(move |wm| {
+        if let Some(inner) = inner_weak.upgrade() {
+            inner.pending_change_handler.set(false);
+
+            let handlers = inner.change_handlers.borrow();
+            for handler in handlers.iter() {
+                handler(wm);
+            }
+        }
+    });
+}
+
+struct TextAreaDragListener {
+    view: HView,
+    inner: Rc<Inner>,
+    orig_sel_range: [usize; 2],
+}
+
+impl TextAreaDragListener {
+    fn new(view: HView, inner: Rc<Inner>) -> Self {
+        let orig_sel_range = inner.state.borrow().sel_range;
+        Self {
+            view,
+            inner,
+            orig_sel_range,
+        }
+    }
+
+    fn update_selection(&self, mut f: impl FnMut(&mut State)) {
+        update_state(
+            self.view.as_ref(),
+            RcBorrow::from(&self.inner),
+            &mut move |state| {
+                state.history.mark_logical_op_break();
+                f(state);
+                UpdateStateFlags::SEL
+            },
+        );
+    }
+
+    fn index_from_global_point(&self, state: &mut State, loc: Point2<f32>) -> usize {
+        let origin = state.text_origin_global(self.view.as_ref(), &self.inner.style_elem);
+        state.index_from_point(loc - origin)
+    }
+}
+
+impl MouseDragListener for TextAreaDragListener {
+    fn mouse_down(&self, _: pal::Wm, _: HViewRef<'_>, loc: Point2<f32>, _button: u8) {
+        self.update_selection(|state| {
+            let i = self.index_from_global_point(state, loc);
+            state.sel_range = [i, i];
+        });
+    }
+
+    fn mouse_motion(&self, _: pal::Wm, _: HViewRef<'_>, loc: Point2<f32>) {
+        self.update_selection(|state| {
+            let i = self.index_from_global_point(state, loc);
+            state.sel_range[1] = i;
+        });
+    }
+
+    fn cancel(&self, _: pal::Wm, _: HViewRef<'_>) {
+        let orig_sel_range = &self.orig_sel_range;
+        self.update_selection(|state| {
+            // Before resetting the selection, make sure `orig_sel_range` is
+            // still a valid selection range
+            if orig_sel_range.iter().all(|&i| i <= state.doc.len()) {
+                state.sel_range = *orig_sel_range;
+            }
+        });
+    }
+}
+
+/// Implements `ScrollbarDragListener` for `TextArea`'s scrollbar.
+struct TextAreaScrollbarDragListener {
+    inner: Rc<Inner>,
+    orig: Cell<Option<(f32, f64)>>,
+}
+
+impl TextAreaScrollbarDragListener {
+    fn new(inner: Rc<Inner>) -> Self {
+        // Temporarily give the control of the scrollbar's value to
+        // `TextAreaScrollbarDragListener`. This flag is reset when it's dropped.
+        inner.scrollbar_drag_active.set(true);
+
+        Self {
+            inner,
+            orig: Cell::new(None),
+        }
+    }
+}
+
+impl Drop for TextAreaScrollbarDragListener {
+    fn drop(&mut self) {
+        self.inner.scrollbar_drag_active.set(false);
+        pend_update_scrollbar(RcBorrow::from(&self.inner));
+    }
+}
+
+impl ScrollbarDragListener for TextAreaScrollbarDragListener {
+    fn down(&self, _: pal::Wm, new_value: f64) {
+        let scroll = self.inner.state.borrow().scroll;
+        self.orig.set(Some((scroll, new_value)));
+    }
+
+    fn motion(&self, _: pal::Wm, new_value: f64) {
+        if let Some(view) = self.inner.view.upgrade() {
+            let max_scroll =
+                (self.inner.state.borrow()).max_scroll(view.as_ref(), &self.inner.style_elem);
+            set_scroll(RcBorrow::from(&self.inner), new_value as f32 * max_scroll);
+            self.inner.scrollbar.set_value(new_value);
+        }
+    }
+
+    fn cancel(&self, _: pal::Wm) {
+        if let Some((scroll, value)) = self.orig.take() {
+            set_scroll(RcBorrow::from(&self.inner), scroll);
+            self.inner.scrollbar.set_value(value);
+        }
+    }
+}
+
+/// Implements `ViewListener` for the wrapper view to handle scroll events.
+struct WrapperViewListener {
+    inner: Weak<Inner>,
+}
+
+impl WrapperViewListener {
+    fn scroll_model_getter(&self) -> impl Fn() -> Box<dyn ScrollModel> + 'static {
+        let inner_weak = self.inner.clone();
+        move || {
+            if let Some(inner) = inner_weak.upgrade() {
+                Box::new(TextAreaScrollModel::new(inner))
+            } else {
+                Box::new(())
+            }
+        }
+    }
+}
+
+impl ViewListener for WrapperViewListener {
+    fn scroll_motion(&self, wm: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, delta: &ScrollDelta) {
+        if let Some(inner) = self.inner.upgrade() {
+            // Do not allow scrolling in two ways at the same time
+            if inner.scrollbar_drag_active.get() {
+                return;
+            }
+
+            inner
+                .scroll_mixin
+                .scroll_motion(wm, delta, self.scroll_model_getter())
+        }
+    }
+
+    fn scroll_gesture(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+    ) -> Box<dyn ScrollListener> {
+        if let Some(inner) = self.inner.upgrade() {
+            // Do not allow scrolling in two ways at the same time
+            if inner.scrollbar_drag_active.get() {
+                return Box::new(());
+            }
+
+            inner
+                .scroll_mixin
+                .scroll_gesture(self.scroll_model_getter())
+        } else {
+            Box::new(())
+        }
+    }
+}
+
+/// Implements [`ScrollModel`] to bridge between `TextArea` and
+/// `ScrollWheelMixin`.
+struct TextAreaScrollModel {
+    inner: Rc<Inner>,
+    orig_scroll: f32,
+}
+
+impl TextAreaScrollModel {
+    fn new(inner: Rc<Inner>) -> Self {
+        let orig_scroll = inner.state.borrow().scroll;
+        Self { inner, orig_scroll }
+    }
+}
+
+impl ScrollModel for TextAreaScrollModel {
+    fn bounds(&mut self) -> Box2<f64> {
+        let max_scroll = if let Some(view) = self.inner.view.upgrade() {
+            (self.inner.state.borrow()).max_scroll(view.as_ref(), &self.inner.style_elem)
+        } else {
+            0.0
+        };
+
+        box2! {
+            min: [0.0, 0.0],
+            max: [0.0, max_scroll as f64],
+        }
+    }
+
+    fn pos(&mut self) -> Point2<f64> {
+        [0.0, self.inner.state.borrow().scroll as f64].into()
+    }
+
+    fn set_pos(&mut self, value: Point2<f64>) {
+        // Over-scrolling is not supported; `set_scroll` clamps the value
+        set_scroll(RcBorrow::from(&self.inner), value.y as f32);
+    }
+
+    fn cancel(&mut self) {
+        set_scroll(RcBorrow::from(&self.inner), self.orig_scroll);
+    }
+}
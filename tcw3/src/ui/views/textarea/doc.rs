@@ -0,0 +1,441 @@
+//! The document model of `TextArea`.
+use cggeom::prelude::*;
+use derive_more::{Add, AddAssign, Neg};
+use rope::{by_key, One::FirstAfter, Rope};
+use std::ops::Range;
+use unicount::{str_ceil, str_floor};
+
+use crate::{
+    pal,
+    pal::prelude::*,
+    ui::editing::{editor::EditText, undo::HistoryText},
+};
+
+/// The number of fixed-point units per pixel used to store paragraph heights.
+///
+/// Rope offsets must be exactly additive, which rules out `f32`.
+const POS_SCALE: f32 = 64.0;
+
+/// A text document split into paragraphs.
+///
+/// Each paragraph is laid out independently and lazily, i.e., only when its
+/// geometry is actually needed. Until then, an estimated height is used to
+/// calculate the positions of paragraphs.
+///
+/// Invariant: There is at least one paragraph. Every paragraph but the last
+/// one ends with a line break. The last one does not contain any line breaks.
+#[derive(Debug)]
+pub(super) struct Doc {
+    paras: Rope<Para, ParaOff>,
+    /// The estimated height of a paragraph which hasn't been laid out yet.
+    est_height: i64,
+    /// The character style used to lay out paragraphs.
+    char_style: Option<pal::CharStyle>,
+    /// The wrapping width.
+    width: f32,
+}
+
+#[derive(Debug)]
+pub(super) struct Para {
+    /// The text content, including the trailing line break (if any).
+    text: String,
+    /// The height of the paragraph. It's only an estimation if `layout` is
+    /// `None`.
+    height: i64,
+    layout: Option<pal::TextLayout>,
+}
+
+/// The rope offset type for `Para`.
+#[derive(Debug, Clone, Copy, Add, AddAssign, Neg)]
+pub(super) struct ParaOff {
+    index: isize,
+    bytes: isize,
+    pos: i64,
+}
+
+impl rope::Offset for ParaOff {
+    fn zero() -> Self {
+        Self {
+            index: 0,
+            bytes: 0,
+            pos: 0,
+        }
+    }
+}
+
+impl rope::ToOffset<ParaOff> for Para {
+    fn to_offset(&self) -> ParaOff {
+        ParaOff {
+            index: 1,
+            bytes: self.text.len() as isize,
+            pos: self.height,
+        }
+    }
+}
+
+impl ParaOff {
+    /// Get the paragraph index.
+    pub(super) fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    /// Get the UTF-8 offset of the paragraph's first character.
+    pub(super) fn bytes(&self) -> usize {
+        self.bytes as usize
+    }
+
+    /// Get the Y coordinate of the paragraph's top edge.
+    pub(super) fn pos(&self) -> f32 {
+        pos_to_f32(self.pos)
+    }
+}
+
+impl Para {
+    fn new(text: String, height: i64) -> Self {
+        Self {
+            text,
+            height,
+            layout: None,
+        }
+    }
+
+    /// Get the text content, including the trailing line break (if any).
+    pub(super) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the text content, excluding the trailing line break.
+    pub(super) fn content(&self) -> &str {
+        if self.text.ends_with('\n') {
+            &self.text[..self.text.len() - 1]
+        } else {
+            &self.text
+        }
+    }
+
+    /// Get the `TextLayout` of the paragraph if it has been laid out.
+    pub(super) fn layout(&self) -> Option<&pal::TextLayout> {
+        self.layout.as_ref()
+    }
+}
+
+fn pos_from_f32(x: f32) -> i64 {
+    (x * POS_SCALE).ceil() as i64
+}
+
+fn pos_to_f32(x: i64) -> f32 {
+    x as f32 / POS_SCALE
+}
+
+impl Doc {
+    /// Construct an empty document.
+    pub(super) fn new() -> Self {
+        let est_height = pos_from_f32(16.0);
+        let mut paras = Rope::new();
+        paras.push_back(Para::new(String::new(), est_height));
+        Self {
+            paras,
+            est_height,
+            char_style: None,
+            width: 0.0,
+        }
+    }
+
+    /// Set the estimated height of a paragraph. This only affects paragraphs
+    /// created or invalidated after calling this method.
+    pub(super) fn set_est_height(&mut self, value: f32) {
+        self.est_height = pos_from_f32(value);
+    }
+
+    /// Set the character style used to lay out paragraphs. This invalidates
+    /// the layouts of all paragraphs.
+    pub(super) fn set_char_style(&mut self, value: pal::CharStyle) {
+        self.set_est_height(value.size() * 1.2);
+        self.char_style = Some(value);
+        self.invalidate_layouts();
+    }
+
+    /// Set the wrapping width. Returns `true` and invalidates the layouts of
+    /// all paragraphs if the value has changed.
+    pub(super) fn set_width(&mut self, value: f32) -> bool {
+        if value == self.width {
+            return false;
+        }
+        self.width = value;
+        self.invalidate_layouts();
+        true
+    }
+
+    /// Get the length of the text in bytes.
+    pub(super) fn len(&self) -> usize {
+        self.paras.offset_len().bytes()
+    }
+
+    /// Get the number of paragraphs.
+    pub(super) fn num_paras(&self) -> usize {
+        self.paras.offset_len().index()
+    }
+
+    /// Get the total height of the paragraphs.
+    pub(super) fn height(&self) -> f32 {
+        self.paras.offset_len().pos()
+    }
+
+    /// Get the paragraph at index `i` along with its offset.
+    pub(super) fn para(&self, i: usize) -> (&Para, ParaOff) {
+        self.paras
+            .get_with_offset(FirstAfter(by_key(|o: &ParaOff| o.index, i as isize)))
+            .unwrap_or_else(|| panic!("paragraph index {} is out of bounds", i))
+    }
+
+    /// Find the paragraph containing the UTF-8 offset `i`.
+    ///
+    /// The position just after a line break belongs to the next paragraph.
+    pub(super) fn para_index_at_byte(&self, i: usize) -> usize {
+        debug_assert!(i <= self.len(), "{} <= {}", i, self.len());
+
+        // `FirstAfter` never returns the last paragraph if `i == self.len()`
+        self.paras
+            .get_with_offset(FirstAfter(by_key(|o: &ParaOff| o.bytes, i as isize)))
+            .map(|(_, off)| off.index())
+            .unwrap_or_else(|| self.num_paras() - 1)
+    }
+
+    /// Find the paragraph containing the Y coordinate `y`. The result is
+    /// clamped to the valid range of paragraph indices.
+    pub(super) fn para_index_at_pos(&self, y: f32) -> usize {
+        self.paras
+            .get_with_offset(FirstAfter(by_key(|o: &ParaOff| o.pos, pos_from_f32(y))))
+            .map(|(_, off)| off.index())
+            .unwrap_or_else(|| self.num_paras() - 1)
+    }
+
+    /// Lay out the paragraph at index `i` if it hasn't been laid out yet.
+    /// Returns the paragraph's offset.
+    ///
+    /// This might change the paragraph's height and the positions of the
+    /// subsequent paragraphs.
+    /// Panics if the character style hasn't been set yet.
+    pub(super) fn ensure_layout(&mut self, i: usize) -> ParaOff {
+        let by_index = || by_key(|o: &ParaOff| o.index, i as isize);
+
+        let (para, off) = self.paras.get_with_offset(FirstAfter(by_index())).unwrap();
+        if para.layout.is_some() {
+            return off;
+        }
+
+        let char_style = self.char_style.as_ref().expect("char_style is not set");
+        let width = self.width;
+
+        self.paras
+            .update_with(FirstAfter(by_index()), |para, off| {
+                let layout = pal::TextLayout::from_text(para.content(), char_style, Some(width));
+                para.height = pos_from_f32(layout.layout_bounds().size().y);
+                para.layout = Some(layout);
+                off
+            })
+            .unwrap()
+    }
+
+    /// Discard the `TextLayout`s of all paragraphs. The current heights are
+    /// kept as estimations until the paragraphs are laid out again.
+    pub(super) fn invalidate_layouts(&mut self) {
+        self.paras.for_each_mut(|para| para.layout = None);
+    }
+
+    /// Get the entire text.
+    pub(super) fn text(&self) -> String {
+        self.paras.iter().map(|para| &para.text[..]).collect()
+    }
+
+    /// Replace the entire text.
+    pub(super) fn set_text(&mut self, text: &str) {
+        let est_height = self.est_height;
+        self.paras = split_paras(text, true)
+            .map(|text| Para::new(text.to_owned(), est_height))
+            .collect();
+    }
+
+    /// Replace the text in the given range.
+    pub(super) fn replace_range(&mut self, range: Range<usize>, new_text: &str) {
+        debug_assert!(range.start <= range.end, "{:?}", range);
+
+        let first = self.para_index_at_byte(range.start);
+        let last = self.para_index_at_byte(range.end);
+        let includes_last_para = last == self.num_paras() - 1;
+        let start_bytes = self.para(first).1.bytes();
+
+        // Remove the affected paragraphs, concatenating their text
+        let mut text = String::new();
+        for _ in first..=last {
+            let (para, _) = self
+                .paras
+                .remove(FirstAfter(by_key(|o: &ParaOff| o.index, first as isize)))
+                .unwrap();
+            text += &para.text;
+        }
+
+        text.replace_range(range.start - start_bytes..range.end - start_bytes, new_text);
+
+        // Split the new text into paragraphs and put them back
+        let mut i = first;
+        for para_text in split_paras(&text, includes_last_para) {
+            let para = Para::new(para_text.to_owned(), self.est_height);
+            if i < self.num_paras() {
+                let result = self
+                    .paras
+                    .insert_before(para, FirstAfter(by_key(|o: &ParaOff| o.index, i as isize)));
+                debug_assert!(result.is_ok());
+            } else {
+                self.paras.push_back(para);
+            }
+            i += 1;
+        }
+    }
+
+    /// Round `i` down to the nearest character boundary.
+    pub(super) fn floor_index(&self, i: usize) -> usize {
+        let (para, off) = self.para(self.para_index_at_byte(i));
+        off.bytes() + str_floor(&para.text, i - off.bytes())
+    }
+
+    /// Round `i` up to the nearest character boundary.
+    pub(super) fn ceil_index(&self, i: usize) -> usize {
+        let (para, off) = self.para(self.para_index_at_byte(i));
+        off.bytes() + str_ceil(&para.text, i - off.bytes())
+    }
+}
+
+impl HistoryText for Doc {
+    fn slice(&self, range: Range<usize>) -> String {
+        let mut out = String::with_capacity(range.len());
+        let mut i = self.para_index_at_byte(range.start);
+
+        while i < self.num_paras() {
+            let (para, off) = self.para(i);
+            let para_range = off.bytes()..off.bytes() + para.text.len();
+            if para_range.start >= range.end {
+                break;
+            }
+
+            let start = range.start.max(para_range.start) - para_range.start;
+            let end = range.end.min(para_range.end) - para_range.start;
+            out += &para.text[start..end];
+
+            i += 1;
+        }
+
+        out
+    }
+}
+
+impl EditText for Doc {
+    fn len(&self) -> usize {
+        Doc::len(self)
+    }
+
+    fn replace_range(&mut self, range: Range<usize>, new_text: &str) {
+        Doc::replace_range(self, range, new_text);
+    }
+}
+
+/// Split a text into paragraphs. Each paragraph except the last one includes
+/// a trailing line break.
+///
+/// If `include_tail` is `false`, the possibly-empty tail following the last
+/// line break is not returned. In this case, `text` must end with a line
+/// break.
+fn split_paras(text: &str, include_tail: bool) -> impl Iterator<Item = &str> {
+    let mut rest = Some(text);
+    std::iter::from_fn(move || {
+        let text = rest?;
+        if let Some(i) = text.find('\n') {
+            let (para, tail) = text.split_at(i + 1);
+            rest = Some(tail);
+            Some(para)
+        } else {
+            rest = None;
+            if include_tail {
+                Some(text)
+            } else {
+                debug_assert!(text.is_empty(), "{:?}", text);
+                None
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn para_texts(doc: &Doc) -> Vec<&str> {
+        doc.paras.iter().map(|p| &p.text[..]).collect()
+    }
+
+    #[test]
+    fn set_text() {
+        let mut doc = Doc::new();
+        assert_eq!(para_texts(&doc), [""]);
+
+        doc.set_text("Pony\nipsum\n");
+        assert_eq!(para_texts(&doc), ["Pony\n", "ipsum\n", ""]);
+        assert_eq!(doc.len(), 11);
+        assert_eq!(doc.num_paras(), 3);
+        assert_eq!(doc.text(), "Pony\nipsum\n");
+    }
+
+    #[test]
+    fn para_index_at_byte() {
+        let mut doc = Doc::new();
+        doc.set_text("Pony\nipsum\n");
+        let indices: Vec<_> = (0..=doc.len()).map(|i| doc.para_index_at_byte(i)).collect();
+        assert_eq!(indices, [0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn para_index_at_pos() {
+        let mut doc = Doc::new();
+        doc.set_est_height(10.0);
+        doc.set_text("a\nb\nc");
+        assert_eq!(doc.height(), 30.0);
+        assert_eq!(doc.para_index_at_pos(-5.0), 0);
+        assert_eq!(doc.para_index_at_pos(5.0), 0);
+        assert_eq!(doc.para_index_at_pos(15.0), 1);
+        assert_eq!(doc.para_index_at_pos(25.0), 2);
+        assert_eq!(doc.para_index_at_pos(100.0), 2);
+        assert_eq!(doc.para(2).1.pos(), 20.0);
+    }
+
+    #[test]
+    fn replace_range() {
+        let mut doc = Doc::new();
+        doc.set_text("Pony\nipsum\ndolor");
+
+        // Insert a line break
+        doc.replace_range(2..2, "\n");
+        assert_eq!(para_texts(&doc), ["Po\n", "ny\n", "ipsum\n", "dolor"]);
+
+        // Join paragraphs
+        doc.replace_range(5..9, "");
+        assert_eq!(para_texts(&doc), ["Po\n", "nyum\n", "dolor"]);
+
+        // Replace the trailing part, including the last paragraph
+        doc.replace_range(8..doc.len(), "sit\n");
+        assert_eq!(para_texts(&doc), ["Po\n", "nyum\n", "sit\n", ""]);
+
+        // Delete everything
+        doc.replace_range(0..doc.len(), "");
+        assert_eq!(para_texts(&doc), [""]);
+    }
+
+    #[test]
+    fn slice() {
+        let mut doc = Doc::new();
+        doc.set_text("Pony\nipsum\ndolor");
+        assert_eq!(doc.slice(0..doc.len()), "Pony\nipsum\ndolor");
+        assert_eq!(doc.slice(2..8), "ny\nips");
+        assert_eq!(doc.slice(5..5), "");
+        assert_eq!(doc.slice(4..5), "\n");
+    }
+}
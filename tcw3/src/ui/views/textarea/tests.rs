@@ -0,0 +1,225 @@
+use crate::{
+    pal,
+    testing::{prelude::*, use_testing_wm},
+    ui::{layouts::FillLayout, theming::Manager},
+    uicore::{actions, ActionId, HWnd},
+};
+use cggeom::prelude::*;
+use enclose::enc;
+use log::info;
+use std::{cell::RefCell, rc::Rc};
+use try_match::try_match;
+
+use super::TextArea;
+
+fn simulate_click(twm: &dyn TestingWm, pal_hwnd: &pal::HWnd, p: cgmath::Point2<f32>) {
+    info!("clicking at {:?}", p);
+    let drag = twm.raise_mouse_drag(&pal_hwnd, p, 0);
+    drag.mouse_down(p, 0);
+    twm.step_unsend();
+    drag.mouse_up(p, 0);
+}
+
+#[allow(dead_code)]
+struct TestWithOneTextArea {
+    wm: pal::Wm,
+    hwnd: HWnd,
+    pal_hwnd: pal::HWnd,
+    text_area: Rc<TextArea>,
+    changed_events: Rc<RefCell<Vec<String>>>,
+}
+
+fn init_test_with_one_text_area(twm: &dyn TestingWm) -> TestWithOneTextArea {
+    let wm = twm.wm();
+
+    let style_manager = Manager::global(wm);
+
+    let text_area = Rc::new(TextArea::new(wm, style_manager));
+
+    let wnd = HWnd::new(wm);
+    wnd.content_view()
+        .set_layout(FillLayout::new(text_area.view()));
+    wnd.set_visibility(true);
+
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    // Make the window small enough to require scrolling
+    twm.set_wnd_size(&pal_hwnd, [200, 100]);
+
+    // Focus the window
+    twm.set_wnd_focused(&pal_hwnd, true);
+    twm.step_unsend();
+
+    // Focus the text area by clicking it
+    let bounds = text_area.view_ref().global_frame();
+    simulate_click(twm, &pal_hwnd, bounds.mid());
+
+    // Register a `changed` event handler
+    let changed_events = Rc::new(RefCell::new(Vec::new()));
+    let text_area_weak = Rc::downgrade(&text_area);
+    text_area.subscribe_changed(Box::new(enc!((changed_events) move |_| {
+        if let Some(text_area) = text_area_weak.upgrade() {
+            changed_events.borrow_mut().push(text_area.text());
+        }
+    })));
+
+    TestWithOneTextArea {
+        wm,
+        hwnd: wnd,
+        pal_hwnd,
+        text_area,
+        changed_events,
+    }
+}
+
+fn set_selected_range(twm: &dyn TestingWm, range: std::ops::Range<usize>) {
+    let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), true);
+    edit.set_selected_range(range);
+}
+
+fn selected_range(twm: &dyn TestingWm) -> std::ops::Range<usize> {
+    let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), false);
+    edit.selected_range()
+}
+
+fn perform(twm: &dyn TestingWm, pal_hwnd: &pal::HWnd, action: ActionId) {
+    twm.raise_perform_action(pal_hwnd, action);
+    twm.step_unsend();
+}
+
+#[use_testing_wm(testing = "crate::testing")]
+#[test]
+fn set_text(twm: &dyn TestingWm) {
+    let TestWithOneTextArea {
+        text_area,
+        changed_events,
+        ..
+    } = init_test_with_one_text_area(twm);
+
+    text_area.set_text("Pony\r\nipsum\rdolor");
+    twm.step_unsend();
+
+    // Line breaks are normalized
+    assert_eq!(text_area.text(), "Pony\nipsum\ndolor");
+    assert_eq!(changed_events.borrow()[..], ["Pony\nipsum\ndolor"][..]);
+
+    {
+        let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), true);
+        assert_eq!(edit.len(), 16);
+        assert_eq!(edit.slice(2..8), "ny\nips");
+    }
+}
+
+#[use_testing_wm(testing = "crate::testing")]
+#[test]
+fn insert_line_break(twm: &dyn TestingWm) {
+    let TestWithOneTextArea {
+        text_area,
+        pal_hwnd,
+        ..
+    } = init_test_with_one_text_area(twm);
+
+    // Type something
+    {
+        let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), true);
+        edit.replace(0..0, "hello");
+        edit.set_selected_range(5..5);
+    }
+    twm.step_unsend();
+
+    perform(twm, &pal_hwnd, actions::INSERT_LINE_BREAK);
+    assert_eq!(text_area.text(), "hello\n");
+    assert_eq!(selected_range(twm), 6..6);
+
+    perform(twm, &pal_hwnd, actions::INSERT_PARAGRAPH_BREAK);
+    assert_eq!(text_area.text(), "hello\n\n");
+    assert_eq!(selected_range(twm), 7..7);
+
+    // Each line break is undone separately
+    perform(twm, &pal_hwnd, actions::UNDO);
+    assert_eq!(text_area.text(), "hello\n");
+    perform(twm, &pal_hwnd, actions::UNDO);
+    assert_eq!(text_area.text(), "hello");
+
+    perform(twm, &pal_hwnd, actions::REDO);
+    assert_eq!(text_area.text(), "hello\n");
+}
+
+#[use_testing_wm(testing = "crate::testing")]
+#[test]
+fn move_vertically(twm: &dyn TestingWm) {
+    let TestWithOneTextArea {
+        text_area,
+        pal_hwnd,
+        ..
+    } = init_test_with_one_text_area(twm);
+
+    text_area.set_text("abc\nabc\n\nabc");
+    twm.step_unsend();
+
+    set_selected_range(twm, 6..6);
+
+    perform(twm, &pal_hwnd, actions::MOVE_UP);
+    assert_eq!(selected_range(twm), 2..2);
+
+    perform(twm, &pal_hwnd, actions::MOVE_UP);
+    assert_eq!(selected_range(twm), 0..0);
+
+    set_selected_range(twm, 6..6);
+
+    // Go across the empty paragraph. The X coordinate is remembered.
+    perform(twm, &pal_hwnd, actions::MOVE_DOWN);
+    assert_eq!(selected_range(twm), 8..8);
+    perform(twm, &pal_hwnd, actions::MOVE_DOWN);
+    assert_eq!(selected_range(twm), 11..11);
+
+    perform(twm, &pal_hwnd, actions::MOVE_DOWN);
+    assert_eq!(selected_range(twm), 12..12);
+
+    // Extend the selection
+    perform(twm, &pal_hwnd, actions::MOVE_UP_SELECTING);
+    assert_eq!(selected_range(twm), 12..8);
+}
+
+#[use_testing_wm(testing = "crate::testing")]
+#[test]
+fn scroll_to_caret(twm: &dyn TestingWm) {
+    let TestWithOneTextArea {
+        text_area,
+        pal_hwnd,
+        ..
+    } = init_test_with_one_text_area(twm);
+
+    let text: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+    text_area.set_text(text.clone());
+    twm.step_unsend();
+
+    let scroll = || text_area.inner.state.borrow().scroll;
+    assert_eq!(scroll(), 0.0);
+
+    // Moving the caret to the end scrolls the view
+    perform(twm, &pal_hwnd, actions::MOVE_END_OF_DOCUMENT);
+    assert_eq!(selected_range(twm), text.len()..text.len());
+    assert!(scroll() > 0.0);
+
+    // Only the visible paragraphs are laid out
+    {
+        let state = text_area.inner.state.borrow();
+        assert!(state.doc.para(50).0.layout().is_none());
+        assert!(state.doc.para(100).0.layout().is_some());
+    }
+
+    // Move back by pages
+    let mut last_scroll = scroll();
+    for _ in 0..3 {
+        perform(twm, &pal_hwnd, actions::MOVE_UP_PAGE);
+        assert!(scroll() < last_scroll, "{} < {}", scroll(), last_scroll);
+        last_scroll = scroll();
+    }
+
+    perform(twm, &pal_hwnd, actions::MOVE_START_OF_DOCUMENT);
+    assert_eq!(scroll(), 0.0);
+}
@@ -0,0 +1,182 @@
+//! Accessibility
+use log::trace;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{window::WndDirtyFlags, HView, HViewRef, HWnd, HWndRef};
+use crate::pal::{
+    self, prelude::*, AccessAction, AccessInfo, AccessNode, AccessNodeId, AccessRole, AccessStates,
+};
+
+/// The identifier of the root node, which represents a window.
+const ROOT_NODE_ID: AccessNodeId = AccessNodeId(0);
+
+/// Allocate an identifier for the accessibility tree node representing a new
+/// view. Identifiers are never reused, so an assistive technology holding a
+/// stale node never ends up addressing an unrelated view.
+pub(super) fn new_access_node_id() -> AccessNodeId {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(ROOT_NODE_ID.0 + 1);
+    AccessNodeId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
+impl HViewRef<'_> {
+    /// Set the accessibility information of a view.
+    ///
+    /// A view having accessibility information appears as a node in the
+    /// accessibility tree of the containing window, which is exposed to
+    /// assistive technologies such as screen readers. A view without one is
+    /// transparent, i.e., the nodes found in its subviews are attached to the
+    /// closest ancestor node. The subviews of a view are not searched at all
+    /// if its role has presentational children (see
+    /// [`AccessRole::has_presentational_children`]).
+    ///
+    /// `AccessStates::FOCUSED` is managed by the framework and ignored. When
+    /// an assistive technology performs one of `info.actions`,
+    /// [`ViewListener::perform_access_action`] is called.
+    ///
+    /// [`ViewListener::perform_access_action`]: super::ViewListener::perform_access_action
+    pub fn set_access_info(self, info: Option<AccessInfo>) {
+        {
+            let mut info_cell = self.view.access_info.borrow_mut();
+            if *info_cell == info {
+                return;
+            }
+            *info_cell = info;
+        }
+
+        if let Some(hwnd) = self.containing_wnd() {
+            hwnd.as_ref().pend_access_tree_update();
+        }
+    }
+
+    /// Get the accessibility information of a view.
+    pub fn access_info(self) -> Option<AccessInfo> {
+        self.view.access_info.borrow().clone()
+    }
+
+    /// Get the identifier of the accessibility tree node representing the view.
+    fn access_node_id(self) -> AccessNodeId {
+        self.view.access_node_id
+    }
+
+    /// Construct the accessibility tree nodes in the subtree rooted at `self`
+    /// and append them to `out`.
+    fn collect_access_nodes(self, focused_view: Option<HViewRef<'_>>, out: &mut Vec<AccessNode>) {
+        let info = self.view.access_info.borrow();
+
+        let mut info = if let Some(info) = &*info {
+            info.clone()
+        } else {
+            self.collect_access_nodes_of_subviews(focused_view, out);
+            return;
+        };
+
+        let mut children = Vec::new();
+
+        let focused = if info.role.has_presentational_children() {
+            // The focus may be on one of the presentational subviews
+            focused_view.map_or(false, |v| v.is_improper_subview_of(self))
+        } else {
+            self.collect_access_nodes_of_subviews(focused_view, &mut children);
+            focused_view == Some(self)
+        };
+        info.states.set(AccessStates::FOCUSED, focused);
+
+        out.push(AccessNode {
+            id: self.access_node_id(),
+            info,
+            bounds: self.global_frame(),
+            children,
+        });
+    }
+
+    fn collect_access_nodes_of_subviews(
+        self,
+        focused_view: Option<HViewRef<'_>>,
+        out: &mut Vec<AccessNode>,
+    ) {
+        let layout = self.view.layout.borrow();
+        for subview in layout.subviews().iter() {
+            subview.as_ref().collect_access_nodes(focused_view, out);
+        }
+    }
+
+    /// Find the view represented by the accessibility tree node `id` in the
+    /// subtree rooted at `self`.
+    fn find_view_by_access_node_id(self, id: AccessNodeId) -> Option<HView> {
+        if self.access_node_id() == id {
+            return Some(self.cloned());
+        }
+
+        let layout = self.view.layout.borrow();
+        (layout.subviews().iter())
+            .find_map(|subview| subview.as_ref().find_view_by_access_node_id(id))
+    }
+}
+
+impl HWndRef<'_> {
+    /// Schedule the reconstruction of the accessibility tree.
+    pub(super) fn pend_access_tree_update(self) {
+        self.wnd.set_dirty_flags(WndDirtyFlags::ACCESS_TREE);
+        self.pend_update();
+    }
+
+    /// Construct the accessibility tree and pass it to the backend.
+    pub(super) fn update_access_tree(self, pal_wnd: &pal::HWnd) {
+        let content_view = self.wnd.content_view.borrow();
+        let content_view = content_view.as_ref().unwrap().as_ref();
+
+        let focused_view = self.wnd.focused_view.borrow();
+        let focused_view = focused_view.as_ref().map(|v| v.as_ref());
+
+        let mut children = Vec::new();
+        content_view.collect_access_nodes(focused_view, &mut children);
+
+        let root = AccessNode {
+            id: ROOT_NODE_ID,
+            info: AccessInfo::new(AccessRole::Window)
+                .with_name(self.wnd.style_attrs.borrow().caption.clone()),
+            bounds: content_view.global_frame(),
+            children,
+        };
+
+        self.wnd.wm.update_access_tree(pal_wnd, root);
+    }
+}
+
+impl HWnd {
+    /// The core implementation of `pal::WndListener::access_action`.
+    pub(super) fn handle_access_action(&self, node: AccessNodeId, action: AccessAction) -> bool {
+        let view = {
+            let content_view = self.wnd.content_view.borrow();
+            content_view
+                .as_ref()
+                .and_then(|v| v.as_ref().find_view_by_access_node_id(node))
+        };
+
+        let view = if let Some(view) = view {
+            view
+        } else {
+            trace!("{:?}: Access node {:?} was not found", self, node);
+            return false;
+        };
+
+        let supported = view
+            .view
+            .access_info
+            .borrow()
+            .as_ref()
+            .map_or(false, |info| info.actions.contains(&action));
+
+        if !supported {
+            trace!("{:?}: {:?} does not support {:?}", self, view, action);
+            return false;
+        }
+
+        trace!("{:?}: Performing {:?} on {:?}", self, action, view);
+
+        let listener = view.view.listener.borrow();
+        listener.perform_access_action(self.wnd.wm, view.as_ref(), action);
+
+        true
+    }
+}
@@ -0,0 +1,122 @@
+//! Owned windows and modal dialogs
+use log::trace;
+use std::rc::Rc;
+
+use super::{window::WndDirtyFlags, HWnd, HWndRef, KeyEvent, WndCb};
+use crate::pal::{self, prelude::*, Wm};
+
+/// The type of [`HWnd::set_default_action`]'s and
+/// [`HWnd::set_cancel_action`]'s handlers after being stored.
+pub(super) type DialogActionCb = Rc<dyn Fn(Wm, HWndRef<'_>)>;
+
+impl HWndRef<'_> {
+    /// Set the owner of a window.
+    ///
+    /// An owned window (e.g., a dialog box) is kept above its owner. If the
+    /// window is modal (see [`set_modal`]), the owner stops accepting input
+    /// while the window is visible.
+    ///
+    /// The owner should be materialized before this window is materialized
+    /// for the owner relationship to be reflected to the system.
+    ///
+    /// [`set_modal`]: HWndRef::set_modal
+    pub fn set_owner(self, owner: Option<HWnd>) {
+        let mut style_attrs = self.wnd.style_attrs.borrow_mut();
+        if style_attrs.owner == owner {
+            return;
+        }
+
+        if let Some(owner) = &owner {
+            assert!(owner.as_ref() != self, "a window can't own itself");
+
+            let mut owned_wnds = owner.wnd.owned_wnds.borrow_mut();
+            owned_wnds.retain(|w| w.upgrade().is_some());
+            owned_wnds.push(self.cloned().downgrade());
+        }
+
+        let old_owner = std::mem::replace(&mut style_attrs.owner, owner);
+        drop(style_attrs);
+
+        if let Some(old_owner) = old_owner {
+            (old_owner.wnd.owned_wnds.borrow_mut())
+                .retain(|w| w.upgrade().map_or(false, |w| w.as_ref() != self));
+        }
+
+        self.wnd.set_dirty_flags(WndDirtyFlags::STYLE_OWNER);
+        self.pend_update();
+    }
+
+    /// Get the owner of a window.
+    pub fn owner(self) -> Option<HWnd> {
+        self.wnd.style_attrs.borrow().owner.clone()
+    }
+
+    /// Set a flag indicating whether a window is modal to its owner.
+    ///
+    /// The default value is `false`. This has no effect if the window doesn't
+    /// have an owner.
+    pub fn set_modal(self, modal: bool) {
+        let mut style_attrs = self.wnd.style_attrs.borrow_mut();
+        if style_attrs.modal == modal {
+            return;
+        }
+        style_attrs.modal = modal;
+        self.wnd.set_dirty_flags(WndDirtyFlags::STYLE_OWNER);
+        self.pend_update();
+    }
+
+    /// Get a flag indicating whether a window is modal to its owner.
+    pub fn is_modal(self) -> bool {
+        self.wnd.style_attrs.borrow().modal
+    }
+
+    /// Get a flag indicating whether the input to a window is blocked by
+    /// a visible modal window owned by it.
+    pub fn is_input_blocked(self) -> bool {
+        self.wnd.owned_wnds.borrow().iter().any(|w| {
+            w.upgrade().map_or(false, |w| {
+                let style_attrs = w.wnd.style_attrs.borrow();
+                !w.wnd.closed.get() && style_attrs.visible && style_attrs.modal
+            })
+        })
+    }
+
+    /// Set the function called when the Enter key is pressed and no views
+    /// handled it. This is used to implement the default button of a dialog
+    /// box.
+    pub fn set_default_action(self, cb: Option<WndCb>) {
+        *self.wnd.default_action.borrow_mut() = cb.map(Rc::from);
+    }
+
+    /// Set the function called when the Escape key is pressed and no views
+    /// handled it. This is used to implement the cancel button of a dialog
+    /// box.
+    pub fn set_cancel_action(self, cb: Option<WndCb>) {
+        *self.wnd.cancel_action.borrow_mut() = cb.map(Rc::from);
+    }
+
+    /// Call the default or cancel action if `e` is the Enter or Escape key.
+    /// Returns `true` if a handler was called.
+    pub(super) fn handle_dialog_key(self, e: &KeyEvent<'_>) -> bool {
+        const DEFAULT: pal::ActionId = 0;
+        const CANCEL: pal::ActionId = 1;
+        static DIALOG_ACCEL_TABLE: pal::AccelTable = pal::accel_table![
+            (DEFAULT, windows("Return"), macos("Return"), gtk("Return")),
+            (CANCEL, windows("Escape"), macos("Escape"), gtk("Escape")),
+        ];
+
+        let cb = match e.translate_accel(&DIALOG_ACCEL_TABLE) {
+            Some(DEFAULT) => self.wnd.default_action.borrow().clone(),
+            Some(CANCEL) => self.wnd.cancel_action.borrow().clone(),
+            _ => None,
+        };
+
+        if let Some(cb) = cb {
+            trace!("{:?}: Calling the dialog action handler", self);
+            cb(self.wnd.wm, self);
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -50,6 +50,9 @@ impl HWndRef<'_> {
             let mut focused_view_cell = self.wnd.focused_view.borrow_mut();
 
             *focused_view_cell = new_focused_view;
+            drop(focused_view_cell);
+
+            self.pend_access_tree_update();
             return;
         }
 
@@ -105,6 +108,9 @@ impl HWndRef<'_> {
         let mut focused_view_cell = self.wnd.focused_view.borrow_mut();
 
         *focused_view_cell = new_focused_view;
+        drop(focused_view_cell);
+
+        self.pend_access_tree_update();
     }
 
     /// Get the currently focused view in the window.
@@ -149,7 +155,20 @@ impl HWndRef<'_> {
 
     /// The core implementation of `pal::WndListener::{validate_action, perform_action}`.
     pub(super) fn handle_action(self, action: ActionId, perform: bool) -> ActionStatus {
-        let mut focused_view = self.wnd.focused_view.borrow().clone();
+        let focused_view = self.wnd.focused_view.borrow().clone();
+        self.handle_action_from(focused_view, action, perform)
+    }
+
+    /// Like `handle_action`, but starts the search from `start_view` instead
+    /// of the focused view. Used by popup menus, whose actions are routed
+    /// from the view that opened them.
+    pub(super) fn handle_action_from(
+        self,
+        start_view: Option<HView>,
+        action: ActionId,
+        perform: bool,
+    ) -> ActionStatus {
+        let mut focused_view = start_view;
         let wm = self.wnd.wm;
 
         while let Some(hview) = focused_view {
@@ -233,11 +252,16 @@ impl HWndRef<'_> {
             return true;
         }
 
-        // Check tab key only if it's a key-down event
+        // Check special keys only if it's a key-down event
         if up {
             return false;
         }
 
+        // Check the keys for dialog boxes
+        if self.handle_dialog_key(e) {
+            return true;
+        }
+
         // Check tab key
         const TAB_FORWARD: ActionId = 0;
         const TAB_BACKWARD: ActionId = 1;
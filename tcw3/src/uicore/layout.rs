@@ -309,7 +309,9 @@ impl HViewRef<'_> {
     }
 
     /// Call `ViewListener::position` for subviews as necessary.
-    pub(super) fn flush_position_event(self, wm: Wm) {
+    ///
+    /// Returns `true` if `global_frame` of any view was updated.
+    pub(super) fn flush_position_event(self, wm: Wm) -> bool {
         #[derive(Copy, Clone)]
         #[repr(align(16))]
         struct Ctx {
@@ -375,9 +377,12 @@ impl HViewRef<'_> {
             }
         }
 
+        let mut repositioned = false;
+
         traverse(
             self,
             &mut |hview| {
+                repositioned = true;
                 hview.view.listener.borrow().position(wm, hview);
             },
             Ctx {
@@ -389,6 +394,8 @@ impl HViewRef<'_> {
                 extra_flags: ViewDirtyFlags::empty(),
             },
         );
+
+        repositioned
     }
 
     /// Perform a hit test for the point `p` specified in the window coordinate
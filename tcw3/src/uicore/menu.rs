@@ -0,0 +1,71 @@
+//! Popup menus
+use log::trace;
+
+use super::{ActionId, ActionStatus, HViewRef, MenuItem, WeakHView};
+use crate::pal::{self, prelude::*, Wm};
+
+impl HViewRef<'_> {
+    /// Open a popup menu anchored to this view.
+    ///
+    /// The actions of the menu items are validated and performed in the same
+    /// way as keyboard shortcuts, except that the search starts from this view
+    /// (instead of the focused view) and proceeds through its ancestors to the
+    /// window.
+    ///
+    /// This method does nothing if the view is not in a materialized window.
+    pub fn popup_menu(self, items: &[MenuItem]) {
+        let hwnd = if let Some(hwnd) = self.containing_wnd() {
+            hwnd
+        } else {
+            trace!("{:?}: popup_menu: The view is not in a window", self);
+            return;
+        };
+
+        let pal_hwnd = if let Some(pal_hwnd) = hwnd.pal_hwnd() {
+            pal_hwnd
+        } else {
+            trace!("{:?}: popup_menu: The window is not materialized", self);
+            return;
+        };
+
+        let wm = hwnd.wnd.wm;
+        let listener = MenuListener {
+            view: self.cloned().downgrade(),
+        };
+
+        trace!("{:?}: popup_menu({:?})", self, items);
+        wm.popup_menu(&pal_hwnd, items, self.global_frame(), Box::new(listener));
+    }
+}
+
+/// Routes the actions of a popup menu from the view that opened it.
+struct MenuListener {
+    view: WeakHView,
+}
+
+impl MenuListener {
+    fn handle_action(&self, action: ActionId, perform: bool) -> ActionStatus {
+        let view = if let Some(view) = self.view.upgrade() {
+            view
+        } else {
+            return ActionStatus::empty();
+        };
+
+        if let Some(hwnd) = view.containing_wnd() {
+            hwnd.as_ref()
+                .handle_action_from(Some(view), action, perform)
+        } else {
+            ActionStatus::empty()
+        }
+    }
+}
+
+impl pal::iface::MenuListener<Wm> for MenuListener {
+    fn validate_action(&self, _: Wm, _: &pal::HWnd, action: ActionId) -> ActionStatus {
+        self.handle_action(action, false)
+    }
+
+    fn perform_action(&self, _: Wm, _: &pal::HWnd, action: ActionId) {
+        self.handle_action(action, true);
+    }
+}
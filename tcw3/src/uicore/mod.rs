@@ -59,23 +59,31 @@ use subscriber_list::{SubscriberList, UntypedSubscription};
 
 use crate::pal::{self, prelude::*, Wm};
 
+mod access;
+mod dialog;
 mod images;
 mod invocation;
 mod keybd;
 mod layer;
 mod layout;
+mod menu;
 mod mount;
 mod mouse;
+mod notify;
 mod taborder;
+mod tooltip;
 mod window;
 
 pub use self::layer::{UpdateCtx, UpdateReason};
 pub use self::layout::{Layout, LayoutCtx, SizeTraits};
 pub use self::mouse::{MouseDragListener, ScrollListener};
+pub use self::notify::Notification;
 pub use self::taborder::TabOrderSibling;
 
 pub use crate::pal::{
-    actions, ActionId, ActionStatus, CursorShape, ScrollDelta, WndFlags as WndStyleFlags,
+    actions, AccessAction, AccessInfo, AccessRole, AccessStates, AccessValue, ActionId,
+    ActionStatus, CursorShape, DragData, DragDataTypes, MenuItem, NotificationAction,
+    NotificationId, ScrollDelta, WndFlags as WndStyleFlags,
 };
 
 /// The maxiumum supported depth of view hierarchy.
@@ -273,9 +281,16 @@ struct Wnd {
     // Mouse inputs
     mouse_state: RefCell<mouse::WndMouseState>,
     cursor_shape: Cell<CursorShape>,
+    tooltip_state: RefCell<tooltip::WndTooltipState>,
 
     // Keyboard inputs
     focused_view: RefCell<Option<HView>>,
+
+    // Dialogs
+    /// The windows owned by this window. Used to find modal windows.
+    owned_wnds: RefCell<Vec<WeakHWnd>>,
+    default_action: RefCell<Option<dialog::DialogActionCb>>,
+    cancel_action: RefCell<Option<dialog::DialogActionCb>>,
 }
 
 impl fmt::Debug for Wnd {
@@ -295,8 +310,12 @@ impl fmt::Debug for Wnd {
             .field("dpi_scale_changed_handlers", &())
             .field("frame_handlers", &())
             .field("mouse_state", &self.mouse_state)
+            .field("tooltip_state", &self.tooltip_state)
             .field("focus_handlers", &())
             .field("focused_view", &self.focused_view)
+            .field("owned_wnds", &self.owned_wnds)
+            .field("default_action", &())
+            .field("cancel_action", &())
             .finish()
     }
 }
@@ -321,8 +340,12 @@ impl Wnd {
             frame_handlers: LinkedListCell::new(),
             mouse_state: RefCell::new(mouse::WndMouseState::new()),
             cursor_shape: Cell::new(CursorShape::default()),
+            tooltip_state: RefCell::new(Default::default()),
             focus_handlers: RefCell::new(SubscriberList::new()),
             focused_view: RefCell::new(None),
+            owned_wnds: RefCell::new(Vec::new()),
+            default_action: RefCell::new(None),
+            cancel_action: RefCell::new(None),
         }
     }
 }
@@ -465,6 +488,9 @@ bitflags! {
         ///
         /// This flag cannot be added or removed once a view is created.
         const CLIP_VISIBLE_FRAME = 1 << 10;
+
+        /// The view accepts drag-and-drop events.
+        const ACCEPT_DROP = 1 << 11;
     }
 }
 
@@ -603,6 +629,46 @@ pub trait ViewListener {
         Box::new(())
     }
 
+    /// The mouse pointer carrying a drag-and-drop operation entered the view's
+    /// region. `types` specifies the data types offered by the drag source.
+    ///
+    /// You must set [`ViewFlags::ACCEPT_DROP`] for this to be called.
+    fn drag_enter(&self, _: Wm, _: HViewRef<'_>, _types: DragDataTypes) {}
+
+    /// The mouse pointer carrying a drag-and-drop operation moved inside the
+    /// view's region. This is called after `drag_enter`.
+    ///
+    /// Returns the data types the view is willing to accept at `loc`. An
+    /// empty set means the view rejects the drop at the location.
+    ///
+    /// You must set [`ViewFlags::ACCEPT_DROP`] for this to be called.
+    fn drag_over(
+        &self,
+        _: Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _types: DragDataTypes,
+    ) -> DragDataTypes {
+        DragDataTypes::empty()
+    }
+
+    /// The mouse pointer carrying a drag-and-drop operation left the view's
+    /// region, the operation was cancelled, or the data was dropped on the
+    /// view (in which case this is called after `drag_drop`).
+    ///
+    /// You must set [`ViewFlags::ACCEPT_DROP`] for this to be called.
+    fn drag_leave(&self, _: Wm, _: HViewRef<'_>) {}
+
+    /// The data was dropped on the view. `data` only includes the types
+    /// returned by the last call to `drag_over`.
+    ///
+    /// Returns `true` if the data was accepted.
+    ///
+    /// You must set [`ViewFlags::ACCEPT_DROP`] for this to be called.
+    fn drag_drop(&self, _: Wm, _: HViewRef<'_>, _loc: Point2<f32>, _data: &DragData) -> bool {
+        false
+    }
+
     /// `focus_got` is called for this view or its descendants.
     fn focus_enter(&self, _: Wm, _: HViewRef<'_>) {}
     /// `focus_lost` is called for this view or its descendants.
@@ -620,6 +686,11 @@ pub trait ViewListener {
 
     /// Perform the specified action.
     fn perform_action(&self, _: Wm, _: HViewRef<'_>, _: ActionId) {}
+
+    /// An assistive technology requested the specified action to be performed
+    /// on the view. This is only called for the actions included in the view's
+    /// [`AccessInfo`] (see [`HViewRef::set_access_info`]).
+    fn perform_access_action(&self, _: Wm, _: HViewRef<'_>, _: AccessAction) {}
 }
 
 /// A no-op implementation of `ViewListener`.
@@ -635,6 +706,9 @@ struct View {
     dirty: Cell<ViewDirtyFlags>,
     flags: Cell<ViewFlags>,
     cursor_shape: Cell<Option<CursorShape>>,
+    tooltip: RefCell<Option<String>>,
+    access_info: RefCell<Option<AccessInfo>>,
+    access_node_id: pal::AccessNodeId,
 
     listener: RefCell<Box<dyn ViewListener>>,
     layout: RefCell<Box<dyn Layout>>,
@@ -702,6 +776,9 @@ impl View {
             has_frame: Cell::new(false),
             layers: RefCell::new(Vec::new()),
             cursor_shape: Cell::new(None),
+            tooltip: RefCell::new(None),
+            access_info: RefCell::new(None),
+            access_node_id: access::new_access_node_id(),
             focus_link_override: RefCell::new(None),
         }
     }
@@ -840,11 +917,22 @@ impl HWnd {
         pub fn caption(&self) -> String;
         pub fn set_style_flags(&self, flags: WndStyleFlags);
         pub fn style_flags(&self) -> WndStyleFlags;
+        pub fn set_menu_bar(&self, items: Vec<MenuItem>);
+        pub fn menu_bar(&self) -> Vec<MenuItem>;
         pub fn invoke_on_next_frame(&self, f: impl FnOnce(pal::Wm, HWndRef<'_>) + 'static);
 
         // `keybd.rs`
         pub fn set_focused_view(&self, view: Option<HView>);
         pub fn focused_view(&self) -> Option<HView>;
+
+        // `dialog.rs`
+        pub fn set_owner(&self, owner: Option<HWnd>);
+        pub fn owner(&self) -> Option<HWnd>;
+        pub fn set_modal(&self, modal: bool);
+        pub fn is_modal(&self) -> bool;
+        pub fn is_input_blocked(&self) -> bool;
+        pub fn set_default_action(&self, cb: Option<WndCb>);
+        pub fn set_cancel_action(&self, cb: Option<WndCb>);
     }
 }
 
@@ -1015,8 +1103,10 @@ impl HWndRef<'_> {
             return;
         }
         style_attrs.caption = caption;
-        self.wnd
-            .set_dirty_flags(window::WndDirtyFlags::STYLE_CAPTION);
+        // The caption is also the name of the accessibility tree's root node
+        self.wnd.set_dirty_flags(
+            window::WndDirtyFlags::STYLE_CAPTION | window::WndDirtyFlags::ACCESS_TREE,
+        );
         self.pend_update();
     }
 
@@ -1043,6 +1133,28 @@ impl HWndRef<'_> {
         self.wnd.style_attrs.borrow().flags
     }
 
+    /// Set the menu bar of a window.
+    ///
+    /// The actions of the menu items are validated and performed in the same
+    /// way as keyboard shortcuts. The default value is empty, meaning the
+    /// window has no menu bar. This is ignored on macOS, where the menu bar is
+    /// shared by all windows of an application.
+    pub fn set_menu_bar(self, items: Vec<MenuItem>) {
+        let mut style_attrs = self.wnd.style_attrs.borrow_mut();
+        if style_attrs.menu_bar == items {
+            return;
+        }
+        style_attrs.menu_bar = items;
+        self.wnd
+            .set_dirty_flags(window::WndDirtyFlags::STYLE_MENU_BAR);
+        self.pend_update();
+    }
+
+    /// Get the menu bar of a window.
+    pub fn menu_bar(self) -> Vec<MenuItem> {
+        self.wnd.style_attrs.borrow().menu_bar.clone()
+    }
+
     /// Enqueue a call to the specified function. The function will be called
     /// when the system is ready to accept a new displayed frame.
     ///
@@ -1116,6 +1228,10 @@ impl HView {
         pub fn cursor_shape(&self) -> Option<CursorShape>;
         pub fn pend_update(&self);
 
+        // `access.rs`
+        pub fn set_access_info(&self, info: Option<AccessInfo>);
+        pub fn access_info(&self) -> Option<AccessInfo>;
+
         // `layout.rs`
         pub fn frame(&self) -> Box2<f32>;
         pub fn global_frame(&self) -> Box2<f32>;
@@ -1129,6 +1245,16 @@ impl HView {
         pub fn is_focused(&self) -> bool;
         pub fn improper_subview_is_focused(&self) -> bool;
 
+        // `menu.rs`
+        pub fn popup_menu(&self, items: &[MenuItem]);
+
+        // `mouse.rs`
+        pub fn start_drag(&self, data: DragData, on_finish: impl FnOnce(Wm, bool) + 'static);
+
+        // `tooltip.rs`
+        pub fn set_tooltip(&self, tooltip: Option<String>);
+        pub fn tooltip(&self) -> Option<String>;
+
         // `taborder.rs`
         pub fn override_tab_order_sibling(&self, prev: TabOrderSibling, next: TabOrderSibling);
         pub fn override_tab_order_child(&self, first_last: Option<[HView; 2]>);
@@ -1258,6 +1384,9 @@ impl<'a> HViewRef<'a> {
             // `MOUNTED` implies that the view is already added to some window
             let hwnd = self.containing_wnd().unwrap();
 
+            // Added or removed views may have accessibility tree nodes
+            hwnd.as_ref().pend_access_tree_update();
+
             // Check for disconnected views
             for hview_sub in old_layout.subviews().iter() {
                 if hview_sub.view.superview.borrow().is_empty() {
@@ -4,8 +4,10 @@ use log::{trace, warn};
 use std::fmt;
 use std::rc::{Rc, Weak};
 
-use super::{CursorShape, HView, HViewRef, HWnd, ScrollDelta, ViewFlags, Wnd};
-use crate::{pal, pal::Wm};
+use super::{
+    CursorShape, DragData, DragDataTypes, HView, HViewRef, HWnd, ScrollDelta, ViewFlags, Wnd,
+};
+use crate::{pal, pal::prelude::*, pal::Wm};
 
 /// Mouse event handlers for mouse drag gestures.
 ///
@@ -75,6 +77,8 @@ pub(super) struct WndMouseState {
     drag_gestures: Option<Rc<DragGesture>>,
     scroll_gestures: Option<Rc<ScrollGesture>>,
     hover_view: Option<HView>,
+    /// The view under the mouse pointer carrying a drag-and-drop operation.
+    drop_view: Option<HView>,
 }
 
 impl WndMouseState {
@@ -83,8 +87,14 @@ impl WndMouseState {
             drag_gestures: None,
             scroll_gestures: None,
             hover_view: None,
+            drop_view: None,
         }
     }
+
+    /// Get the hot view.
+    pub(super) fn hover_view(&self) -> Option<&HView> {
+        self.hover_view.as_ref()
+    }
 }
 
 /// Represents an active mouse drag gesture.
@@ -193,6 +203,9 @@ impl HWnd {
             .last()
             .unwrap_or_default();
         self.wnd.set_cursor_shape(cursor_shape);
+
+        drop(st);
+        self.update_tooltip(false);
     }
 
     /// The core implementation of `pal::WndListener::nc_hit_test`.
@@ -391,16 +404,126 @@ impl HWnd {
             Box::new(())
         }
     }
+
+    /// The core implementation of `pal::WndListener::drag_motion`.
+    pub(super) fn handle_drag_motion(
+        &self,
+        loc: Point2<f32>,
+        types: DragDataTypes,
+    ) -> DragDataTypes {
+        let new_drop_view = {
+            let content_view = self.wnd.content_view.borrow();
+            content_view.as_ref().unwrap().as_ref().hit_test(
+                loc,
+                ViewFlags::ACCEPT_DROP,
+                ViewFlags::DENY_MOUSE,
+            )
+        };
+
+        let old_drop_view = {
+            let mut st = self.wnd.mouse_state.borrow_mut();
+            if new_drop_view == st.drop_view {
+                None
+            } else {
+                trace!("{:?}: Drop target changed to {:?}", self, new_drop_view);
+                Some(std::mem::replace(&mut st.drop_view, new_drop_view.clone()))
+            }
+        };
+
+        // Make sure `mouse_state` is unborrowed before calling event handlers
+        if let Some(old_drop_view) = old_drop_view {
+            if let Some(hview) = &old_drop_view {
+                let listener = hview.view.listener.borrow();
+                listener.drag_leave(self.wnd.wm, hview.as_ref());
+            }
+            if let Some(hview) = &new_drop_view {
+                let listener = hview.view.listener.borrow();
+                listener.drag_enter(self.wnd.wm, hview.as_ref(), types);
+            }
+        }
+
+        if let Some(hview) = &new_drop_view {
+            let listener = hview.view.listener.borrow();
+            listener.drag_over(self.wnd.wm, hview.as_ref(), loc, types) & types
+        } else {
+            DragDataTypes::empty()
+        }
+    }
+
+    /// The core implementation of `pal::WndListener::drag_leave`.
+    pub(super) fn handle_drag_leave(&self) {
+        let drop_view = self.wnd.mouse_state.borrow_mut().drop_view.take();
+
+        if let Some(hview) = drop_view {
+            let listener = hview.view.listener.borrow();
+            listener.drag_leave(self.wnd.wm, hview.as_ref());
+        }
+    }
+
+    /// The core implementation of `pal::WndListener::drag_drop`.
+    pub(super) fn handle_drag_drop(&self, loc: Point2<f32>, data: &DragData) -> bool {
+        let drop_view = self.wnd.mouse_state.borrow_mut().drop_view.take();
+
+        trace!(
+            "{:?}: Drop at {:?} (data = {:?}) is handled by {:?}",
+            self,
+            loc,
+            data,
+            drop_view
+        );
+
+        if let Some(hview) = drop_view {
+            let listener = hview.view.listener.borrow();
+            let accepted = listener.drag_drop(self.wnd.wm, hview.as_ref(), loc, data);
+            listener.drag_leave(self.wnd.wm, hview.as_ref());
+            accepted
+        } else {
+            false
+        }
+    }
 }
 
 impl HViewRef<'_> {
+    /// Start a drag-and-drop operation carrying `data` from the view.
+    ///
+    /// This method should be called from a handler of [`MouseDragListener`]
+    /// while a mouse button is pressed. The current mouse drag gesture is
+    /// cancelled afterwards. `on_finish` is called when the operation is
+    /// complete, with a flag indicating whether the data was accepted by a
+    /// drop target.
+    ///
+    /// If the view is not in a materialized window, `on_finish` is called
+    /// with `false` in a deferred manner.
+    pub fn start_drag(self, data: DragData, on_finish: impl FnOnce(Wm, bool) + 'static) {
+        let wm = Wm::global();
+
+        if let Some(pal_hwnd) = self.containing_wnd().and_then(|hwnd| hwnd.pal_hwnd()) {
+            trace!("{:?}: start_drag({:?})", self, data);
+            wm.start_drag(&pal_hwnd, data, Box::new(on_finish));
+        } else {
+            trace!(
+                "{:?}: start_drag: The view is not in a materialized window",
+                self
+            );
+            wm.invoke(move |wm| on_finish(wm, false));
+        }
+    }
+
     /// Cancel all active mouse gestures for the specified view and its
     /// subviews.
     pub(super) fn cancel_mouse_gestures_of_subviews(self, wnd: &Wnd) {
-        let cancelled_drag = wnd
-            .mouse_state
-            .borrow_mut()
-            .cancel_drag_gestures(self, true);
+        let cancelled_drag = {
+            let mut st = wnd.mouse_state.borrow_mut();
+
+            // Forget the drop target being removed
+            if let Some(drop_view) = &st.drop_view {
+                if drop_view.as_ref().is_improper_subview_of(self) {
+                    st.drop_view = None;
+                }
+            }
+
+            st.cancel_drag_gestures(self, true)
+        };
 
         if let Some(drag) = cancelled_drag {
             drag.listener.cancel(wnd.wm, drag.view.as_ref());
@@ -0,0 +1,70 @@
+//! Desktop notifications
+use tcw3_images::HImg;
+
+use crate::pal::{self, prelude::*, NotificationAction, NotificationId};
+
+/// The DPI scale used to rasterize notification icons. Notification servers
+/// don't tell us the DPI scale of the screen, so we pick a value that looks
+/// good on high-density displays and let the server downscale it.
+const ICON_DPI_SCALE: f32 = 2.0;
+
+/// A desktop notification. This is a version of [`pal::Notification`] that
+/// accepts [`HImg`] as its icon.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub icon: Option<HImg>,
+    pub actions: Vec<NotificationAction>,
+}
+
+impl Notification {
+    /// Construct a `Notification` without an icon or actions.
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: body.into(),
+            icon: None,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Update the icon.
+    pub fn with_icon(self, icon: HImg) -> Self {
+        Self {
+            icon: Some(icon),
+            ..self
+        }
+    }
+
+    /// Add an action button.
+    pub fn with_action(mut self, id: impl Into<String>, label: impl Into<String>) -> Self {
+        self.actions.push(NotificationAction {
+            id: id.into(),
+            label: label.into(),
+        });
+        self
+    }
+
+    /// Display the notification. See [`pal::iface::Wm::show_notification`]
+    /// for the meaning of the parameters.
+    pub fn show(
+        self,
+        wm: pal::Wm,
+        replaces: Option<NotificationId>,
+        listener: Box<dyn pal::iface::NotificationListener<pal::Wm>>,
+    ) -> NotificationId {
+        let icon = self.icon.map(|img| img.new_bmp(wm, ICON_DPI_SCALE).0);
+
+        wm.show_notification(
+            pal::Notification {
+                title: self.title,
+                body: self.body,
+                icon,
+                actions: self.actions,
+            },
+            replaces,
+            listener,
+        )
+    }
+}
@@ -0,0 +1,141 @@
+//! Tooltips
+use log::trace;
+use std::{rc::Rc, time::Duration};
+
+use super::{HView, HViewRef, HWnd, Wnd};
+use crate::pal::{self, prelude::*};
+
+/// The hover delay before a tooltip is displayed.
+const TOOLTIP_DELAY: std::ops::Range<Duration> =
+    Duration::from_millis(500)..Duration::from_millis(600);
+
+#[derive(Debug, Default)]
+pub(super) struct WndTooltipState {
+    /// The view whose tooltip is pending or displayed.
+    view: Option<HView>,
+    /// The pending invocation for displaying the tooltip.
+    pending: Option<pal::HInvoke>,
+    /// `true` if the tooltip of `view` is currently displayed.
+    shown: bool,
+}
+
+impl HViewRef<'_> {
+    /// Set the tooltip text of a view.
+    ///
+    /// A tooltip is displayed when the mouse cursor stays on the hot view
+    /// (the view with `ViewFlags::ACCEPT_MOUSE_OVER` the mouse cursor is
+    /// currently on) for a while. A path from the root view to the hot view is
+    /// calculated, and the lowest view with a non-`None` tooltip is chosen.
+    /// The tooltip is hidden when the mouse cursor leaves the view or when a
+    /// key is pressed, a mouse button is pressed, or the view is scrolled.
+    pub fn set_tooltip(self, tooltip: Option<String>) {
+        *self.view.tooltip.borrow_mut() = tooltip;
+
+        if let Some(hwnd) = self.containing_wnd() {
+            hwnd.update_tooltip(true);
+        }
+    }
+
+    /// Get the tooltip text of a view.
+    pub fn tooltip(self) -> Option<String> {
+        self.view.tooltip.borrow().clone()
+    }
+}
+
+impl HWnd {
+    /// Re-evaluate which view's tooltip should be displayed. This should be
+    /// called when the hot view changes.
+    ///
+    /// If `text_changed` is `true`, the currently displayed tooltip is
+    /// updated with the latest text.
+    pub(super) fn update_tooltip(&self, text_changed: bool) {
+        let wm = self.wnd.wm;
+
+        let mouse_state = if let Ok(x) = self.wnd.mouse_state.try_borrow() {
+            x
+        } else {
+            // We are in a mouse event handler called by `handle_mouse_motion`,
+            // which calls this method when it's done
+            return;
+        };
+
+        // Find the lowest view having a tooltip
+        let mut new_view = None;
+        if let Some(hover_view) = mouse_state.hover_view() {
+            hover_view.as_ref().for_each_ancestor(|hview| {
+                if new_view.is_none() && hview.view.tooltip.borrow().is_some() {
+                    new_view = Some(hview);
+                }
+            });
+        }
+        drop(mouse_state);
+
+        let mut st = self.wnd.tooltip_state.borrow_mut();
+
+        if new_view == st.view {
+            if text_changed && st.shown {
+                let view = st.view.as_ref().unwrap().as_ref();
+                self.wnd.show_tooltip_of(view);
+            }
+            return;
+        }
+
+        trace!("{:?}: Tooltip view changed to {:?}", self, new_view);
+
+        self.wnd.dismiss_tooltip_inner(&mut st);
+        st.view = new_view;
+
+        if st.view.is_some() {
+            let weak_wnd = Rc::downgrade(&self.wnd);
+            st.pending = Some(wm.invoke_after(TOOLTIP_DELAY, move |_| {
+                if let Some(wnd) = weak_wnd.upgrade() {
+                    let mut st = wnd.tooltip_state.borrow_mut();
+                    st.pending = None;
+                    let view = st.view.clone();
+                    st.shown = view.map_or(false, |view| wnd.show_tooltip_of(view.as_ref()));
+                }
+            }));
+        }
+    }
+}
+
+impl Wnd {
+    /// Hide the tooltip and cancel the pending one. The tooltip won't be
+    /// displayed again until the hot view changes.
+    pub(super) fn dismiss_tooltip(&self) {
+        self.dismiss_tooltip_inner(&mut self.tooltip_state.borrow_mut());
+    }
+
+    fn dismiss_tooltip_inner(&self, st: &mut WndTooltipState) {
+        if let Some(hinvoke) = st.pending.take() {
+            self.wm.cancel_invoke(&hinvoke);
+        }
+
+        if st.shown {
+            st.shown = false;
+            if let Some(pal_wnd) = &*self.pal_wnd.borrow() {
+                self.wm.hide_tooltip(pal_wnd);
+            }
+        }
+    }
+
+    /// Display the tooltip of `view`. Returns `true` if the tooltip is
+    /// displayed.
+    fn show_tooltip_of(&self, view: HViewRef<'_>) -> bool {
+        let pal_wnd = self.pal_wnd.borrow();
+        let pal_wnd = if let Some(pal_wnd) = &*pal_wnd {
+            pal_wnd
+        } else {
+            return false;
+        };
+
+        if let Some(text) = &*view.view.tooltip.borrow() {
+            trace!("{:?}: Showing the tooltip {:?}", view, text);
+            self.wm.show_tooltip(pal_wnd, text, view.global_frame());
+            true
+        } else {
+            self.wm.hide_tooltip(pal_wnd);
+            false
+        }
+    }
+}
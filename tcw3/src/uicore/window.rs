@@ -3,6 +3,7 @@ use bitflags::bitflags;
 use cggeom::box2;
 use cgmath::Point2;
 use flags_macro::flags;
+use log::trace;
 use neo_linked_list::{linked_list::Node, AssertUnpin};
 use rc_borrow::RcBorrow;
 use std::{
@@ -13,9 +14,9 @@ use std::{
 };
 
 use super::{
-    invocation::process_pending_invocations, CursorShape, HView, HViewRef, HWnd, HWndRef,
-    Superview, SuperviewStrong, UpdateCtx, ViewDirtyFlags, ViewFlags, ViewListener, Wnd,
-    WndStyleFlags,
+    invocation::process_pending_invocations, AccessAction, CursorShape, DragData, DragDataTypes,
+    HView, HViewRef, HWnd, HWndRef, MenuItem, Superview, SuperviewStrong, UpdateCtx,
+    ViewDirtyFlags, ViewFlags, ViewListener, Wnd, WndStyleFlags,
 };
 use crate::pal::{self, prelude::*, Wm};
 
@@ -238,6 +239,13 @@ impl HWndRef<'_> {
         if update_contents {
             self.wnd.wm.update_wnd(pal_wnd);
         }
+
+        // Reconstructing the accessibility tree is costly, so skip it unless
+        // an assistive technology is listening. `WndListener::access_activated`
+        // pends a fresh update when one starts to listen.
+        if dirty.contains(WndDirtyFlags::ACCESS_TREE) && self.wnd.wm.is_access_active(pal_wnd) {
+            self.update_access_tree(pal_wnd);
+        }
     }
 
     /// Perform pending updates. Also, returns a new, min, and max window size
@@ -342,8 +350,11 @@ impl HWndRef<'_> {
                 continue;
             }
 
-            // Position views
-            view.as_ref().flush_position_event(self.wnd.wm);
+            // Position views. The accessibility tree includes the view
+            // frames, so it must be reconstructed if any of them has moved.
+            if view.as_ref().flush_position_event(self.wnd.wm) {
+                self.wnd.set_dirty_flags(WndDirtyFlags::ACCESS_TREE);
+            }
 
             // Update visual
             view.as_ref().update_layers(self.wnd.wm, self);
@@ -395,6 +406,8 @@ impl Wnd {
             view.as_ref().call_unmount(self.wm);
         }
 
+        self.dismiss_tooltip();
+
         if let Some(hwnd) = self.pal_wnd.borrow_mut().take() {
             self.wm.remove_wnd(&hwnd);
         }
@@ -443,6 +456,21 @@ impl PalWndListener {
         self.wnd.upgrade().map(|wnd| HWnd { wnd })
     }
 
+    /// Get `HWnd` if the underlying object is still alive and accepts input
+    /// (i.e., isn't blocked by a modal window).
+    fn input_hwnd(&self) -> Option<HWnd> {
+        self.hwnd().filter(|hwnd| {
+            let blocked = hwnd.is_input_blocked();
+            if blocked {
+                trace!(
+                    "{:?}: Ignoring an input event blocked by a modal window",
+                    hwnd
+                );
+            }
+            !blocked
+        })
+    }
+
     fn invoke_later_with_hwnd(&self, wm: Wm, f: impl FnOnce(HWnd) + 'static) {
         use super::WmExt;
 
@@ -457,7 +485,7 @@ impl PalWndListener {
 
 impl pal::iface::WndListener<Wm> for PalWndListener {
     fn close_requested(&self, wm: Wm, _: &pal::HWnd) {
-        if let Some(hwnd) = self.hwnd() {
+        if let Some(hwnd) = self.input_hwnd() {
             let hwnd = hwnd.as_ref();
             let listener = hwnd.wnd.listener.borrow();
             let should_close = listener.close_requested(wm, hwnd);
@@ -523,7 +551,8 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
         _: &pal::HWnd,
         e: &dyn pal::iface::KeyEvent<pal::AccelTable>,
     ) -> bool {
-        if let Some(hwnd) = self.hwnd() {
+        if let Some(hwnd) = self.input_hwnd() {
+            hwnd.wnd.dismiss_tooltip();
             hwnd.as_ref().handle_key(e, false)
         } else {
             false
@@ -536,7 +565,7 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
         _: &pal::HWnd,
         e: &dyn pal::iface::KeyEvent<pal::AccelTable>,
     ) -> bool {
-        if let Some(hwnd) = self.hwnd() {
+        if let Some(hwnd) = self.input_hwnd() {
             hwnd.as_ref().handle_key(e, true)
         } else {
             false
@@ -557,7 +586,7 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
     }
 
     fn validate_action(&self, _: Wm, _: &pal::HWnd, action: pal::ActionId) -> pal::ActionStatus {
-        if let Some(hwnd) = self.hwnd() {
+        if let Some(hwnd) = self.input_hwnd() {
             hwnd.as_ref().handle_action(action, false)
         } else {
             pal::ActionStatus::empty()
@@ -565,7 +594,8 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
     }
 
     fn perform_action(&self, _: Wm, _: &pal::HWnd, action: pal::ActionId) {
-        if let Some(hwnd) = self.hwnd() {
+        if let Some(hwnd) = self.input_hwnd() {
+            hwnd.wnd.dismiss_tooltip();
             hwnd.as_ref().handle_action(action, true);
         }
     }
@@ -585,7 +615,8 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
         loc: Point2<f32>,
         button: u8,
     ) -> Box<dyn pal::iface::MouseDragListener<Wm>> {
-        if let Some(hwnd) = self.hwnd() {
+        if let Some(hwnd) = self.input_hwnd() {
+            hwnd.wnd.dismiss_tooltip();
             hwnd.handle_mouse_drag(loc, button)
         } else {
             Box::new(())
@@ -594,7 +625,10 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
 
     fn mouse_motion(&self, _: Wm, _: &pal::HWnd, loc: Point2<f32>) {
         if let Some(hwnd) = self.hwnd() {
-            hwnd.handle_mouse_motion(Some(loc));
+            // Treat the mouse pointer as outside the window while blocked so
+            // that views don't display hover effects
+            let loc = Some(loc).filter(|_| !hwnd.is_input_blocked());
+            hwnd.handle_mouse_motion(loc);
         }
     }
 
@@ -605,7 +639,8 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
     }
 
     fn scroll_motion(&self, _: Wm, _: &pal::HWnd, loc: Point2<f32>, delta: &pal::ScrollDelta) {
-        if let Some(hwnd) = self.hwnd() {
+        if let Some(hwnd) = self.input_hwnd() {
+            hwnd.wnd.dismiss_tooltip();
             hwnd.handle_scroll_motion(loc, delta);
         }
     }
@@ -616,12 +651,62 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
         _: &pal::HWnd,
         loc: Point2<f32>,
     ) -> Box<dyn pal::iface::ScrollListener<Wm>> {
-        if let Some(hwnd) = self.hwnd() {
+        if let Some(hwnd) = self.input_hwnd() {
+            hwnd.wnd.dismiss_tooltip();
             hwnd.handle_scroll_gesture(loc)
         } else {
             Box::new(())
         }
     }
+
+    fn drag_motion(
+        &self,
+        _: Wm,
+        _: &pal::HWnd,
+        loc: Point2<f32>,
+        types: DragDataTypes,
+    ) -> DragDataTypes {
+        if let Some(hwnd) = self.input_hwnd() {
+            hwnd.wnd.dismiss_tooltip();
+            hwnd.handle_drag_motion(loc, types)
+        } else {
+            DragDataTypes::empty()
+        }
+    }
+
+    fn drag_leave(&self, _: Wm, _: &pal::HWnd) {
+        if let Some(hwnd) = self.hwnd() {
+            hwnd.handle_drag_leave();
+        }
+    }
+
+    fn drag_drop(&self, _: Wm, _: &pal::HWnd, loc: Point2<f32>, data: &DragData) -> bool {
+        if let Some(hwnd) = self.input_hwnd() {
+            hwnd.handle_drag_drop(loc, data)
+        } else {
+            false
+        }
+    }
+
+    fn access_action(
+        &self,
+        _: Wm,
+        _: &pal::HWnd,
+        node: pal::AccessNodeId,
+        action: AccessAction,
+    ) -> bool {
+        if let Some(hwnd) = self.input_hwnd() {
+            hwnd.handle_access_action(node, action)
+        } else {
+            false
+        }
+    }
+
+    fn access_activated(&self, _: Wm, _: &pal::HWnd) {
+        if let Some(hwnd) = self.hwnd() {
+            hwnd.as_ref().pend_access_tree_update();
+        }
+    }
 }
 
 pub(crate) fn new_root_content_view() -> HView {
@@ -682,7 +767,7 @@ bitflags! {
     /// called for the next time.
     ///
     /// Be aware that the usage is different from that of `ViewDirtyFlags`.
-    pub struct WndDirtyFlags: u8 {
+    pub struct WndDirtyFlags: u16 {
         /// The root layer should be updated.
         const LAYER = 1;
         /// The window should be resized to the default size.
@@ -696,6 +781,14 @@ bitflags! {
 
         /// `update` is queued to the main event queue.
         const UPDATE = 1 << 6;
+
+        /// The accessibility tree should be reconstructed.
+        const ACCESS_TREE = 1 << 7;
+
+        /// `menu_bar` has changed.
+        const STYLE_MENU_BAR = 1 << 8;
+        /// `owner` or `modal` has changed.
+        const STYLE_OWNER = 1 << 9;
     }
 }
 
@@ -707,7 +800,9 @@ impl Default for WndDirtyFlags {
 
 impl WndDirtyFlags {
     fn style() -> Self {
-        flags![WndDirtyFlags::{STYLE_VISIBLE | STYLE_FLAGS | STYLE_CAPTION}]
+        flags![WndDirtyFlags::{
+            STYLE_VISIBLE | STYLE_FLAGS | STYLE_CAPTION | STYLE_MENU_BAR | STYLE_OWNER
+        }]
     }
 }
 
@@ -716,6 +811,9 @@ pub(super) struct WndStyleAttrs {
     pub flags: WndStyleFlags,
     pub caption: String,
     pub visible: bool,
+    pub menu_bar: Vec<MenuItem>,
+    pub owner: Option<HWnd>,
+    pub modal: bool,
 }
 
 impl Default for WndStyleAttrs {
@@ -724,6 +822,9 @@ impl Default for WndStyleAttrs {
             flags: WndStyleFlags::default(),
             caption: "TCW3 Window".to_owned(),
             visible: false,
+            menu_bar: Vec::new(),
+            owner: None,
+            modal: false,
         }
     }
 }
@@ -739,5 +840,12 @@ impl WndStyleAttrs {
         if dirty.contains(WndDirtyFlags::STYLE_CAPTION) {
             attrs.caption = Some(self.caption[..].into());
         }
+        if dirty.contains(WndDirtyFlags::STYLE_MENU_BAR) {
+            attrs.menu_bar = Some(self.menu_bar[..].into());
+        }
+        if dirty.contains(WndDirtyFlags::STYLE_OWNER) {
+            attrs.owner = Some(self.owner.as_ref().and_then(HWnd::pal_hwnd));
+            attrs.modal = Some(self.modal);
+        }
     }
 }
@@ -1,9 +1,10 @@
 //! The TCW3 binding for StellaVG
 use cggeom::prelude::*;
 use cgmath::Matrix3;
-use stvg_io::{Cmd, CmdDecoder};
+use rgb::RGBA8;
+use stvg_io::{Cmd, CmdDecoder, FillRule};
 use tcw3_images::{himg_from_paint_fn, HImg};
-use tcw3_pal::{iface::Canvas, RGBAF32};
+use tcw3_pal::{iface::Canvas, Gradient, GradientKind, GradientStop, LineCap, LineJoin, RGBAF32};
 
 /// An extension trait for `Canvas` that provides methods for drawing
 /// StellaVG images.
@@ -40,6 +41,19 @@ impl<'a> Default for Options<'a> {
 
 impl<T: Canvas + ?Sized> CanvasStvgExt for T {
     fn draw_stellavg(&mut self, bytes: &[u8], options: &Options<'_>) {
+        let color = |color: RGBA8| {
+            (options.color_xform)(RGBAF32::new(
+                color.r as f32 / 255.0,
+                color.g as f32 / 255.0,
+                color.b as f32 / 255.0,
+                color.a as f32 / 255.0,
+            ))
+        };
+
+        let mut fill_rule = FillRule::NonZero;
+        let mut gradient_stops = Vec::new();
+        let mut line_dash = Vec::new();
+
         self.save();
         self.mult_transform(Matrix3::from_scale_2d(
             1.0 / (1 << stvg_io::FRAC_BITS) as f32,
@@ -47,7 +61,10 @@ impl<T: Canvas + ?Sized> CanvasStvgExt for T {
         for cmd in CmdDecoder::from_bytes(bytes) {
             match cmd {
                 Cmd::BeginPath => self.begin_path(),
-                Cmd::Fill => self.fill(),
+                Cmd::Fill => match fill_rule {
+                    FillRule::NonZero => self.fill(),
+                    FillRule::EvenOdd => self.fill_even_odd(),
+                },
                 Cmd::MoveTo(p) => self.move_to(p.cast().unwrap()),
                 Cmd::LineTo(p) => self.line_to(p.cast().unwrap()),
                 Cmd::QuadBezierTo(cps) => {
@@ -58,18 +75,69 @@ impl<T: Canvas + ?Sized> CanvasStvgExt for T {
                     cps[1].cast().unwrap(),
                     cps[2].cast().unwrap(),
                 ),
-                Cmd::SetFillRgb(color) => self.set_fill_rgb((options.color_xform)(RGBAF32::new(
-                    color.r as f32 / 255.0,
-                    color.g as f32 / 255.0,
-                    color.b as f32 / 255.0,
-                    color.a as f32 / 255.0,
-                ))),
+                Cmd::SetFillRgb(c) => self.set_fill_rgb(color(c)),
+                Cmd::SetFillRule(rule) => fill_rule = rule,
+                Cmd::SetFillGradient(gradient) => {
+                    self.set_fill_gradient(&pal_gradient(gradient, &gradient_stops));
+                    gradient_stops.clear();
+                }
+                Cmd::SetStrokeRgb(c) => self.set_stroke_rgb(color(c)),
+                Cmd::SetStrokeGradient(gradient) => {
+                    self.set_stroke_gradient(&pal_gradient(gradient, &gradient_stops));
+                    gradient_stops.clear();
+                }
+                Cmd::AddGradientStop(stop) => gradient_stops.push(GradientStop {
+                    offset: stop.offset as f32 / 65535.0,
+                    color: color(stop.color),
+                }),
+                Cmd::Stroke => self.stroke(),
+                // The line width and dash lengths are in the same fixed-point
+                // representation as coordinates, which is already taken
+                // care of by the current transformation
+                Cmd::SetLineWidth(width) => self.set_line_width(width as f32),
+                Cmd::SetLineCap(cap) => self.set_line_cap(match cap {
+                    stvg_io::LineCap::Butt => LineCap::Butt,
+                    stvg_io::LineCap::Round => LineCap::Round,
+                    stvg_io::LineCap::Square => LineCap::Square,
+                }),
+                Cmd::SetLineJoin(join) => self.set_line_join(match join {
+                    stvg_io::LineJoin::Miter => LineJoin::Miter,
+                    stvg_io::LineJoin::Round => LineJoin::Round,
+                    stvg_io::LineJoin::Bevel => LineJoin::Bevel,
+                }),
+                Cmd::SetMiterLimit(miter_limit) => {
+                    self.set_line_miter_limit(miter_limit as f32 / (1 << stvg_io::FRAC_BITS) as f32)
+                }
+                Cmd::AddLineDash(length) => line_dash.push(length as f32),
+                Cmd::SetLineDash(phase) => {
+                    self.set_line_dash(phase as f32, &line_dash);
+                    line_dash.clear();
+                }
+                Cmd::BeginGroup(opacity) => self.begin_group(opacity as f32 / 255.0),
+                Cmd::EndGroup => self.end_group(),
             }
         }
         self.restore();
     }
 }
 
+/// Convert `stvg_io::Gradient` to `tcw3_pal::Gradient`.
+fn pal_gradient(gradient: stvg_io::Gradient, stops: &[GradientStop]) -> Gradient<'_> {
+    let [origin, x, y] = gradient.frame;
+    let origin = origin.cast::<f32>().unwrap();
+    let x = x.cast::<f32>().unwrap() - origin;
+    let y = y.cast::<f32>().unwrap() - origin;
+
+    Gradient {
+        kind: match gradient.kind {
+            stvg_io::GradientKind::Linear => GradientKind::Linear,
+            stvg_io::GradientKind::Radial => GradientKind::Radial,
+        },
+        xform: Matrix3::new(x.x, x.y, 0.0, y.x, y.y, 0.0, origin.x, origin.y, 1.0),
+        stops,
+    }
+}
+
 /// The builder of `HImg` for StellaVG images.
 #[derive(Debug, Clone, Copy)]
 pub struct StvgImg<TBytes, TColorXform> {
@@ -65,6 +65,9 @@ pub fn try_init_logger() {
 #[cfg(not(feature = "testing"))]
 pub fn try_init_logger() {}
 
+#[cfg(feature = "testing")]
+pub mod snapshot;
+
 pub mod prelude {
     #[doc(no_inline)]
     pub use crate::pal_testing::TestingWm;
@@ -0,0 +1,346 @@
+//! Golden-image snapshot testing.
+//!
+//! This module is only available if the `testing` feature flag is enabled.
+//!
+//! [`assert_wnd_snapshot`] renders a window using
+//! [`TestingWm::read_wnd_snapshot`] and compares the result against a
+//! reference image (a PNG file) checked in to the repository.
+//!
+//!  - If the reference image does not exist, the snapshot is written as the
+//!    reference image and the assertion passes with a warning. That is, a new
+//!    test is blessed on its first run. The created reference image must be
+//!    checked in to the repository.
+//!
+//!  - If the snapshot does not match the reference image, the assertion fails.
+//!    The snapshot and an image highlighting the differing pixels are written
+//!    next to the reference image as `NAME.actual.png` and `NAME.diff.png`,
+//!    respectively.
+//!
+//!  - If the environment variable [`BLESS_ENV_VAR`] is set to a non-empty
+//!    value, the reference image is created or overwritten with the snapshot
+//!    instead. The reference images must be checked in to the repository
+//!    after that.
+//!
+//! The rendered text depends on the fonts installed on the system, so the
+//! tests using this module should allow for some tolerance by
+//! [`SnapshotOptions`].
+//!
+//!     # use tcw3_testing::{use_testing_wm, prelude::*};
+//!     use tcw3_pal::prelude::*;
+//!     use tcw3_testing::snapshot::{assert_wnd_snapshot, SnapshotOptions};
+//!
+//!     # #[use_testing_wm(testing = "tcw3_testing")]
+//!     fn empty_wnd(twm: &dyn TestingWm) {
+//!         let wm = twm.wm();
+//!         let hwnd = wm.new_wnd(tcw3_pal::WndAttrs {
+//!             size: Some([40, 30]),
+//!             visible: Some(true),
+//!             ..Default::default()
+//!         });
+//!         wm.update_wnd(&hwnd);
+//!
+//!         # let path = std::env::temp_dir().join("tcw3-snapshot-doctest.png");
+//!         # std::env::set_var(tcw3_testing::snapshot::BLESS_ENV_VAR, "1");
+//!         # assert_wnd_snapshot(twm, &hwnd, &path, &SnapshotOptions::new());
+//!         # std::env::remove_var(tcw3_testing::snapshot::BLESS_ENV_VAR);
+//!         assert_wnd_snapshot(twm, &hwnd, &path, &SnapshotOptions::new());
+//!         # std::fs::remove_file(&path).unwrap();
+//!     }
+//!     # empty_wnd();
+//!
+//! [`TestingWm::read_wnd_snapshot`]: tcw3_pal::testing::TestingWm::read_wnd_snapshot
+use png::HasParameters;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tcw3_pal::{
+    testing::{wmapi::WndSnapshot, TestingWm},
+    HWnd,
+};
+
+/// The name of the environment variable that causes [`assert_wnd_snapshot`]
+/// to create or overwrite reference images instead of comparing against them.
+pub const BLESS_ENV_VAR: &str = "TCW3_BLESS_SNAPSHOTS";
+
+/// Specifies how much a snapshot is allowed to differ from its reference
+/// image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotOptions {
+    /// The maximum absolute difference allowed for each channel of a pixel.
+    pub channel_tolerance: u8,
+    /// The maximum number of pixels allowed to exceed `channel_tolerance`.
+    pub max_differing_pixels: usize,
+}
+
+impl SnapshotOptions {
+    /// Construct a `SnapshotOptions` with a small per-channel tolerance to
+    /// absorb rounding errors. No pixels are allowed to exceed the tolerance.
+    pub const fn new() -> Self {
+        Self {
+            channel_tolerance: 2,
+            max_differing_pixels: 0,
+        }
+    }
+
+    /// Update `channel_tolerance`.
+    pub const fn with_channel_tolerance(self, channel_tolerance: u8) -> Self {
+        Self {
+            channel_tolerance,
+            ..self
+        }
+    }
+
+    /// Update `max_differing_pixels`.
+    pub const fn with_max_differing_pixels(self, max_differing_pixels: usize) -> Self {
+        Self {
+            max_differing_pixels,
+            ..self
+        }
+    }
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An RGBA8 image with tightly packed rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub size: [usize; 2],
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Copy the contents of `WndSnapshot`, removing the row padding.
+    pub fn from_wnd_snapshot(snapshot: &WndSnapshot) -> Self {
+        let [width, height] = snapshot.size;
+        let mut data = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let row = &snapshot.data[y * snapshot.stride..];
+            data.extend_from_slice(&row[..width * 4]);
+        }
+        Self {
+            size: snapshot.size,
+            data,
+        }
+    }
+
+    /// Decode a PNG image.
+    pub fn decode_png(reader: impl io::Read) -> Result<Self, png::DecodingError> {
+        let decoder = png::Decoder::new(reader);
+        let (info, mut reader) = decoder.read_info()?;
+
+        if info.color_type != png::ColorType::RGBA || info.bit_depth != png::BitDepth::Eight {
+            return Err(png::DecodingError::Other(
+                "the image is not in the 8-bit RGBA format".into(),
+            ));
+        }
+
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data)?;
+
+        Ok(Self {
+            size: [info.width as usize, info.height as usize],
+            data,
+        })
+    }
+
+    /// Encode the image in the PNG format.
+    pub fn encode_png(&self, writer: impl io::Write) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.size[0] as u32, self.size[1] as u32);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.data)
+    }
+
+    fn save_png(&self, path: &Path) {
+        let file =
+            fs::File::create(path).unwrap_or_else(|e| panic!("could not create {:?}: {}", path, e));
+        self.encode_png(io::BufWriter::new(file))
+            .unwrap_or_else(|e| panic!("could not write {:?}: {}", path, e));
+    }
+}
+
+/// The result of [`compare`].
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// The number of pixels exceeding the tolerance.
+    pub num_differing_pixels: usize,
+    /// An image highlighting the differing pixels in red.
+    pub diff: Image,
+}
+
+/// Compare two images of the same size. Returns `None` if their sizes differ.
+pub fn compare(actual: &Image, reference: &Image, channel_tolerance: u8) -> Option<Comparison> {
+    if actual.size != reference.size {
+        return None;
+    }
+
+    let mut num_differing_pixels = 0;
+    let mut diff = Vec::with_capacity(actual.data.len());
+
+    for (a, r) in actual
+        .data
+        .chunks_exact(4)
+        .zip(reference.data.chunks_exact(4))
+    {
+        let differs = a
+            .iter()
+            .zip(r.iter())
+            .any(|(&a, &r)| (a as i32 - r as i32).abs() > channel_tolerance as i32);
+
+        if differs {
+            num_differing_pixels += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            // Show the reference image faintly for context
+            let luma = ((r[0] as u32 * 2 + r[1] as u32 * 5 + r[2] as u32) / 8) as u8;
+            diff.extend_from_slice(&[luma, luma, luma, r[3] / 4]);
+        }
+    }
+
+    Some(Comparison {
+        num_differing_pixels,
+        diff: Image {
+            size: actual.size,
+            data: diff,
+        },
+    })
+}
+
+/// Render the window `hwnd` and compare the result against the reference
+/// image at `path`. See [the module documentation](self) for details.
+pub fn assert_wnd_snapshot(
+    twm: &dyn TestingWm,
+    hwnd: &HWnd,
+    path: impl AsRef<Path>,
+    options: &SnapshotOptions,
+) {
+    let mut snapshot = WndSnapshot::new();
+    twm.read_wnd_snapshot(hwnd, &mut snapshot);
+    assert_snapshot(&snapshot, path, options);
+}
+
+/// Compare `snapshot` against the reference image at `path`. See
+/// [the module documentation](self) for details.
+pub fn assert_snapshot(snapshot: &WndSnapshot, path: impl AsRef<Path>, options: &SnapshotOptions) {
+    let path = path.as_ref();
+    let actual = Image::from_wnd_snapshot(snapshot);
+
+    let bless = std::env::var_os(BLESS_ENV_VAR).map_or(false, |v| !v.is_empty());
+
+    if bless {
+        log::info!("Writing the reference image {:?}", path);
+        write_reference(&actual, path);
+        return;
+    }
+
+    let actual_path = sibling_path(path, "actual");
+    let diff_path = sibling_path(path, "diff");
+
+    let reference = match fs::File::open(path) {
+        Ok(file) => Image::decode_png(io::BufReader::new(file))
+            .unwrap_or_else(|e| panic!("could not read {:?}: {}", path, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            log::warn!(
+                "The reference image {:?} does not exist. Creating it from the snapshot. \
+                 Please check it in to the repository.",
+                path
+            );
+            write_reference(&actual, path);
+            return;
+        }
+        Err(e) => panic!("could not open {:?}: {}", path, e),
+    };
+
+    let message = match compare(&actual, &reference, options.channel_tolerance) {
+        Some(cmp) if cmp.num_differing_pixels <= options.max_differing_pixels => {
+            // Remove the outputs of previous failed runs
+            let _ = fs::remove_file(&actual_path);
+            let _ = fs::remove_file(&diff_path);
+            return;
+        }
+        Some(cmp) => {
+            cmp.diff.save_png(&diff_path);
+            format!(
+                "{} pixels differ (allowed: {}). See {:?} for the differing pixels.",
+                cmp.num_differing_pixels, options.max_differing_pixels, diff_path
+            )
+        }
+        None => format!(
+            "the size differs (expected: {:?}, got: {:?}).",
+            reference.size, actual.size
+        ),
+    };
+
+    actual.save_png(&actual_path);
+
+    panic!(
+        "The snapshot does not match the reference image {:?}: {} \
+         The snapshot was written to {:?}. Set the environment variable `{}` \
+         to update the reference image.",
+        path, message, actual_path, BLESS_ENV_VAR
+    );
+}
+
+fn write_reference(image: &Image, path: &Path) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap_or_else(|e| panic!("could not create {:?}: {}", dir, e));
+    }
+    image.save_png(path);
+}
+
+/// Get the path `DIR/STEM.suffix.png` for a given path `DIR/STEM.png`.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]]) -> Image {
+        Image {
+            size: [pixels.len(), 1],
+            data: pixels.iter().flatten().cloned().collect(),
+        }
+    }
+
+    #[test]
+    fn png_roundtrip() {
+        let img = image(&[[1, 2, 3, 4], [255, 0, 128, 255]]);
+        let mut png = Vec::new();
+        img.encode_png(&mut png).unwrap();
+        assert_eq!(Image::decode_png(&png[..]).unwrap(), img);
+    }
+
+    #[test]
+    fn compare_tolerance() {
+        let reference = image(&[[10, 10, 10, 255], [10, 10, 10, 255], [0; 4]]);
+        let actual = image(&[[12, 10, 10, 255], [10, 13, 10, 255], [0; 4]]);
+
+        let cmp = compare(&actual, &reference, 2).unwrap();
+        assert_eq!(cmp.num_differing_pixels, 1);
+        assert_eq!(&cmp.diff.data[4..8], &[255, 0, 0, 255]);
+
+        assert_eq!(
+            compare(&actual, &reference, 3)
+                .unwrap()
+                .num_differing_pixels,
+            0
+        );
+
+        assert!(compare(&actual, &image(&[[0; 4]]), 2).is_none());
+    }
+
+    #[test]
+    fn sibling_paths() {
+        assert_eq!(
+            sibling_path(Path::new("a/b/logview.png"), "diff"),
+            Path::new("a/b/logview.diff.png")
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use enclose::enc;
+use std::{cell::RefCell, rc::Rc};
+
+use tcw3::{
+    prelude::*,
+    testing::{prelude::*, use_testing_wm},
+    ui::{theming, views::MessageBox},
+    uicore::HWnd,
+};
+
+fn init_test(twm: &dyn TestingWm) -> (HWnd, MessageBox, Rc<RefCell<Vec<Option<usize>>>>) {
+    let wm = twm.wm();
+    let style_manager = theming::Manager::global(wm);
+
+    let owner = HWnd::new(wm);
+    owner.set_visibility(true);
+    twm.step_unsend();
+
+    let mbox = MessageBox::new(wm, style_manager);
+    mbox.set_caption("Question");
+    mbox.set_message("Do you want to proceed?");
+    mbox.set_buttons(vec!["OK", "Cancel"]);
+    mbox.set_default_button(Some(0));
+    mbox.set_cancel_button(Some(1));
+
+    let results = Rc::new(RefCell::new(Vec::new()));
+    mbox.subscribe_dismissed(Box::new(enc!((results) move |_, result| {
+        results.borrow_mut().push(result);
+    })));
+
+    mbox.show(Some(owner.clone()));
+    twm.step_unsend();
+
+    (owner, mbox, results)
+}
+
+#[use_testing_wm]
+#[test]
+fn owner_and_modal(twm: &dyn TestingWm) {
+    let (owner, mbox, _results) = init_test(twm);
+
+    let owner_pal = owner.pal_hwnd().unwrap();
+    let mbox_pal = mbox.hwnd().pal_hwnd().unwrap();
+
+    let attrs = twm.wnd_attrs(&mbox_pal).unwrap();
+    assert_eq!(attrs.owner, Some(owner_pal.clone()));
+    assert!(attrs.modal);
+    assert_eq!(attrs.caption, "Question");
+
+    // The owner doesn't accept input while the message box is open
+    assert!(owner.is_input_blocked());
+    twm.raise_close_requested(&owner_pal);
+    twm.step_unsend();
+    assert_eq!(twm.hwnds().len(), 2);
+}
+
+#[use_testing_wm]
+#[test]
+fn cancel_by_escape(twm: &dyn TestingWm) {
+    let (owner, mbox, results) = init_test(twm);
+
+    let mbox_pal = mbox.hwnd().pal_hwnd().unwrap();
+    twm.set_wnd_focused(&mbox_pal, true);
+    twm.simulate_key(&mbox_pal, "windows", "Escape");
+    twm.step_unsend();
+
+    assert_eq!(*results.borrow(), [Some(1)]);
+    assert!(!owner.is_input_blocked());
+    assert_eq!(twm.hwnds(), [owner.pal_hwnd().unwrap()]);
+}
+
+#[use_testing_wm]
+#[test]
+fn dismiss_by_closing(twm: &dyn TestingWm) {
+    let (owner, mbox, results) = init_test(twm);
+
+    let mbox_pal = mbox.hwnd().pal_hwnd().unwrap();
+    twm.raise_close_requested(&mbox_pal);
+    twm.step_unsend();
+
+    assert_eq!(*results.borrow(), [None]);
+    assert!(!owner.is_input_blocked());
+}
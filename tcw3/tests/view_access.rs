@@ -0,0 +1,151 @@
+use std::{cell::Cell, rc::Rc};
+use try_match::try_match;
+
+use tcw3::{
+    pal,
+    testing::{prelude::*, use_testing_wm},
+    ui::{
+        layouts::{FillLayout, TableLayout},
+        theming::Manager,
+        views::{Button, Checkbox, Label},
+        AlignFlags,
+    },
+    uicore::{AccessAction, AccessRole, AccessStates, HView, HWnd, ViewFlags},
+};
+
+#[use_testing_wm]
+#[test]
+fn access_tree(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let style_manager = Manager::global(wm);
+    let wnd = HWnd::new(wm);
+    wnd.set_caption("Window");
+
+    let button = Button::new(style_manager);
+    button.set_caption("Button");
+
+    let checkbox = Checkbox::new(style_manager);
+    checkbox.set_caption("Checkbox");
+    checkbox.set_checked(true);
+
+    // A view without accessibility information is transparent
+    let label = Label::new(style_manager);
+    label.set_text("Label");
+    let label_wrapper = HView::new(ViewFlags::default());
+    label_wrapper.set_layout(FillLayout::new(label.view()));
+
+    wnd.content_view().set_layout(TableLayout::stack_vert(vec![
+        (button.view(), AlignFlags::JUSTIFY),
+        (checkbox.view(), AlignFlags::JUSTIFY),
+        (label_wrapper, AlignFlags::JUSTIFY),
+    ]));
+
+    let activated = Rc::new(Cell::new(0));
+    {
+        let activated = activated.clone();
+        button.subscribe_activated(Box::new(move |_| activated.set(activated.get() + 1)));
+    }
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    let tree = twm.access_tree(&pal_hwnd).expect("no accessibility tree");
+
+    assert_eq!(tree.info.role, AccessRole::Window);
+    assert_eq!(tree.info.name.as_deref(), Some("Window"));
+
+    // The label inside the button is not exposed as a separate node
+    let roles: Vec<_> = tree.children.iter().map(|n| n.info.role).collect();
+    assert_eq!(
+        roles,
+        vec![AccessRole::Button, AccessRole::CheckBox, AccessRole::Label]
+    );
+
+    let button_node = tree.find_by_name(AccessRole::Button, "Button").unwrap();
+    assert!(button_node.children.is_empty());
+    assert!(button_node.info.actions.contains(&AccessAction::Press));
+    assert!(!button_node.info.states.contains(AccessStates::FOCUSED));
+    let button_node_id = button_node.id;
+
+    let checkbox_node = tree.find_by_name(AccessRole::CheckBox, "Checkbox").unwrap();
+    assert!(checkbox_node.info.states.contains(AccessStates::CHECKED));
+
+    let label_node = tree.find_by_name(AccessRole::Label, "Label").unwrap();
+    assert_eq!(label_node.bounds, label.view().global_frame());
+
+    // The tree is updated when the focus moves
+    button.view().focus();
+    twm.step_unsend();
+
+    let tree = twm.access_tree(&pal_hwnd).unwrap();
+    let button_node = tree.find_by_name(AccessRole::Button, "Button").unwrap();
+    assert!(button_node.info.states.contains(AccessStates::FOCUSED));
+
+    // ...and when the state of a widget changes
+    checkbox.set_checked(false);
+    twm.step_unsend();
+
+    let tree = twm.access_tree(&pal_hwnd).unwrap();
+    let checkbox_node = tree.find_by_name(AccessRole::CheckBox, "Checkbox").unwrap();
+    assert!(!checkbox_node.info.states.contains(AccessStates::CHECKED));
+    let checkbox_node_id = checkbox_node.id;
+
+    // Node identifiers are stable across updates
+    let button_node = tree.find_by_name(AccessRole::Button, "Button").unwrap();
+    assert_eq!(button_node.id, button_node_id);
+
+    // Perform an action
+    assert!(twm.raise_access_action(&pal_hwnd, button_node_id, AccessAction::Press));
+    twm.step_unsend();
+    assert_eq!(activated.get(), 1);
+
+    // Unsupported actions are rejected
+    assert!(!twm.raise_access_action(&pal_hwnd, checkbox_node_id, AccessAction::Increment));
+    assert!(!twm.raise_access_action(
+        &pal_hwnd,
+        pal::AccessNodeId(u64::max_value()),
+        AccessAction::Press
+    ));
+}
+
+#[use_testing_wm]
+#[test]
+fn access_tree_inactive(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let style_manager = Manager::global(wm);
+    let wnd = HWnd::new(wm);
+
+    let checkbox = Checkbox::new(style_manager);
+    checkbox.set_caption("Checkbox");
+    wnd.content_view()
+        .set_layout(FillLayout::new(checkbox.view()));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    // The tree isn't updated while no assistive technologies are listening
+    twm.set_access_active(&pal_hwnd, false);
+    checkbox.set_checked(true);
+    wnd.set_caption("Window");
+    twm.step_unsend();
+
+    let tree = twm.access_tree(&pal_hwnd).unwrap();
+    assert_eq!(tree.info.name.as_deref(), Some("TCW3 Window"));
+    let checkbox_node = tree.find_by_name(AccessRole::CheckBox, "Checkbox").unwrap();
+    assert!(!checkbox_node.info.states.contains(AccessStates::CHECKED));
+
+    // The tree is brought up-to-date upon activation
+    twm.set_access_active(&pal_hwnd, true);
+    twm.step_unsend();
+
+    let tree = twm.access_tree(&pal_hwnd).unwrap();
+    assert_eq!(tree.info.name.as_deref(), Some("Window"));
+    let checkbox_node = tree.find_by_name(AccessRole::CheckBox, "Checkbox").unwrap();
+    assert!(checkbox_node.info.states.contains(AccessStates::CHECKED));
+}
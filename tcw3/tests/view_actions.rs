@@ -6,7 +6,7 @@ use tcw3::{
     testing::{prelude::*, use_testing_wm},
     ui::{layouts::TableLayout, AlignFlags},
     uicore::{
-        ActionId, ActionStatus, HView, HViewRef, HWnd, HWndRef, ViewFlags, ViewListener,
+        ActionId, ActionStatus, HView, HViewRef, HWnd, HWndRef, MenuItem, ViewFlags, ViewListener,
         WndListener,
     },
 };
@@ -57,6 +57,20 @@ fn init_test(
     wnd_opts: ActionStatus,
     view_opts: Vec<ActionStatus>,
 ) -> (HWnd, pal::HWnd, Rc<RefCell<Vec<(usize, Event)>>>) {
+    let (wnd, pal_hwnd, events, _) = init_test_with_views(twm, wnd_opts, view_opts);
+    (wnd, pal_hwnd, events)
+}
+
+fn init_test_with_views(
+    twm: &dyn TestingWm,
+    wnd_opts: ActionStatus,
+    view_opts: Vec<ActionStatus>,
+) -> (
+    HWnd,
+    pal::HWnd,
+    Rc<RefCell<Vec<(usize, Event)>>>,
+    Vec<HView>,
+) {
     let wm = twm.wm();
     let wnd = HWnd::new(wm);
 
@@ -90,7 +104,7 @@ fn init_test(
     twm.step_unsend();
 
     views.last().unwrap().focus();
-    (wnd, pal_hwnd, events)
+    (wnd, pal_hwnd, events, views)
 }
 
 #[use_testing_wm]
@@ -164,3 +178,33 @@ fn disabled(twm: &dyn TestingWm) {
         ActionStatus::VALID
     );
 }
+
+#[use_testing_wm]
+#[test]
+fn popup_menu(twm: &dyn TestingWm) {
+    let (_wnd, pal_hwnd, events, views) = init_test_with_views(
+        twm,
+        ActionStatus::empty(),
+        vec![
+            ActionStatus::VALID | ActionStatus::ENABLED, // This will handle the action
+            ActionStatus::empty(),
+            ActionStatus::VALID | ActionStatus::ENABLED, // Focused but not an ancestor
+        ],
+    );
+
+    // Actions are routed from the anchor view, not from the focused view
+    views[1].popup_menu(&[MenuItem::action("Do it", 42)]);
+
+    let menu = twm.active_popup_menu().expect("no popup menu");
+    assert_eq!(menu.hwnd, pal_hwnd);
+    assert_eq!(menu.anchor, views[1].global_frame());
+    assert!(menu.items[0].is_enabled());
+
+    twm.pick_menu_item(&[0]);
+    twm.step_unsend();
+    assert_eq!(
+        replace(&mut *events.borrow_mut(), Vec::new()),
+        [(1, Event::Action)]
+    );
+    assert!(twm.active_popup_menu().is_none());
+}
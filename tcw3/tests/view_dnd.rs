@@ -0,0 +1,248 @@
+use cggeom::prelude::*;
+use cgmath::Point2;
+use std::{cell::RefCell, mem::replace, path::PathBuf, rc::Rc};
+use try_match::try_match;
+
+use tcw3::{
+    pal,
+    testing::{prelude::*, use_testing_wm},
+    ui::{
+        layouts::{EmptyLayout, FillLayout, TableLayout},
+        AlignFlags,
+    },
+    uicore::{
+        DragData, DragDataTypes, HView, HViewRef, HWnd, MouseDragListener, SizeTraits, ViewFlags,
+        ViewListener,
+    },
+};
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    DragEnter(DragDataTypes),
+    DragOver(DragDataTypes),
+    DragLeave,
+    DragDrop(DragData),
+}
+
+struct RecordingViewListener(u8, DragDataTypes, Rc<RefCell<Vec<(u8, Event)>>>);
+
+impl ViewListener for RecordingViewListener {
+    fn drag_enter(&self, _: pal::Wm, _: HViewRef<'_>, types: DragDataTypes) {
+        self.2.borrow_mut().push((self.0, Event::DragEnter(types)));
+    }
+    fn drag_over(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        types: DragDataTypes,
+    ) -> DragDataTypes {
+        self.2.borrow_mut().push((self.0, Event::DragOver(types)));
+        self.1
+    }
+    fn drag_leave(&self, _: pal::Wm, _: HViewRef<'_>) {
+        self.2.borrow_mut().push((self.0, Event::DragLeave));
+    }
+    fn drag_drop(&self, _: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, data: &DragData) -> bool {
+        self.2
+            .borrow_mut()
+            .push((self.0, Event::DragDrop(data.clone())));
+        true
+    }
+}
+
+macro_rules! flush_and_assert_events {
+    ($events:expr, $expected:expr) => {
+        assert_eq!(replace(&mut *$events.borrow_mut(), Vec::new()), $expected);
+    };
+}
+
+fn new_leaf_view(flags: ViewFlags) -> HView {
+    let view = HView::new(flags);
+    view.set_layout(EmptyLayout::new(
+        SizeTraits::default().with_preferred([20.0; 2].into()),
+    ));
+    view
+}
+
+#[use_testing_wm]
+#[test]
+fn drop_evts(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+
+    let view0 = HView::new(ViewFlags::default());
+    let view1 = HView::new(ViewFlags::ACCEPT_DROP);
+    let view2 = new_leaf_view(ViewFlags::default());
+    let view3 = new_leaf_view(ViewFlags::ACCEPT_DROP);
+    let view4 = new_leaf_view(ViewFlags::default());
+
+    view1.set_listener(RecordingViewListener(
+        1,
+        DragDataTypes::FILES,
+        events.clone(),
+    ));
+    view3.set_listener(RecordingViewListener(
+        3,
+        DragDataTypes::TEXT,
+        events.clone(),
+    ));
+
+    // `view2` doesn't accept drops, so its superview `view1` receives them
+    view1.set_layout(FillLayout::new(view2.clone()).with_uniform_margin(10.0));
+
+    view0.set_layout(
+        TableLayout::stack_horz(vec![
+            (view1.clone(), AlignFlags::JUSTIFY),
+            (view3.clone(), AlignFlags::JUSTIFY),
+            (view4.clone(), AlignFlags::JUSTIFY),
+        ])
+        .with_uniform_margin(10.0),
+    );
+
+    wnd.content_view().set_layout(FillLayout::new(view0));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    let all_types = DragDataTypes::TEXT | DragDataTypes::FILES;
+
+    // Only the types accepted by the view are reported
+    let accepted = twm.raise_drag_motion(&pal_hwnd, view2.global_frame().mid(), all_types);
+    assert_eq!(accepted, DragDataTypes::FILES);
+    flush_and_assert_events!(
+        events,
+        vec![
+            (1, Event::DragEnter(all_types)),
+            (1, Event::DragOver(all_types)),
+        ]
+    );
+
+    let accepted = twm.raise_drag_motion(&pal_hwnd, view3.global_frame().mid(), all_types);
+    assert_eq!(accepted, DragDataTypes::TEXT);
+    flush_and_assert_events!(
+        events,
+        vec![
+            (1, Event::DragLeave),
+            (3, Event::DragEnter(all_types)),
+            (3, Event::DragOver(all_types)),
+        ]
+    );
+
+    // `view4` doesn't accept drops
+    let accepted = twm.raise_drag_motion(&pal_hwnd, view4.global_frame().mid(), all_types);
+    assert_eq!(accepted, DragDataTypes::empty());
+    flush_and_assert_events!(events, vec![(3, Event::DragLeave)]);
+
+    twm.raise_drag_motion(&pal_hwnd, view3.global_frame().mid(), all_types);
+    events.borrow_mut().clear();
+
+    twm.raise_drag_leave(&pal_hwnd);
+    flush_and_assert_events!(events, vec![(3, Event::DragLeave)]);
+
+    // Drop files on `view1`. `drag_leave` is called after `drag_drop`.
+    let data = DragData::with_files(vec![PathBuf::from("/tmp/hoge.txt")]);
+    assert!(twm.simulate_drop(&pal_hwnd, view2.global_frame().mid(), &data));
+    flush_and_assert_events!(
+        events,
+        vec![
+            (1, Event::DragEnter(DragDataTypes::FILES)),
+            (1, Event::DragOver(DragDataTypes::FILES)),
+            (1, Event::DragDrop(data)),
+            (1, Event::DragLeave),
+        ]
+    );
+
+    // `view3` only accepts text
+    let data = DragData::with_files(vec![PathBuf::from("/tmp/hoge.txt")]);
+    assert!(!twm.simulate_drop(&pal_hwnd, view3.global_frame().mid(), &data));
+    flush_and_assert_events!(
+        events,
+        vec![
+            (3, Event::DragEnter(DragDataTypes::FILES)),
+            (3, Event::DragOver(DragDataTypes::FILES)),
+            (3, Event::DragLeave),
+        ]
+    );
+
+    // Only the accepted types are delivered
+    let data = DragData {
+        text: Some("hello".to_owned()),
+        files: vec![PathBuf::from("/tmp/hoge.txt")],
+    };
+    assert!(twm.simulate_drop(&pal_hwnd, view3.global_frame().mid(), &data));
+    flush_and_assert_events!(
+        events,
+        vec![
+            (3, Event::DragEnter(all_types)),
+            (3, Event::DragOver(all_types)),
+            (3, Event::DragDrop(DragData::with_text("hello"))),
+            (3, Event::DragLeave),
+        ]
+    );
+}
+
+struct DragSourceViewListener(Rc<RefCell<Vec<bool>>>);
+
+impl ViewListener for DragSourceViewListener {
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        Box::new(DragSourceMouseDragListener(self.0.clone()))
+    }
+}
+
+struct DragSourceMouseDragListener(Rc<RefCell<Vec<bool>>>);
+
+impl MouseDragListener for DragSourceMouseDragListener {
+    fn mouse_motion(&self, _: pal::Wm, hview: HViewRef<'_>, _loc: Point2<f32>) {
+        let results = self.0.clone();
+        hview.start_drag(DragData::with_text("hello"), move |_, accepted| {
+            results.borrow_mut().push(accepted);
+        });
+    }
+}
+
+#[use_testing_wm]
+#[test]
+fn start_drag(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let results = Rc::new(RefCell::new(Vec::new()));
+
+    let view = new_leaf_view(ViewFlags::ACCEPT_MOUSE_DRAG);
+    view.set_listener(DragSourceViewListener(results.clone()));
+    wnd.content_view().set_layout(FillLayout::new(view.clone()));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    assert_eq!(twm.active_drag(), None);
+
+    let drag = twm.raise_mouse_drag(&pal_hwnd, view.global_frame().mid(), 0);
+    drag.mouse_down(view.global_frame().mid(), 0);
+    drag.mouse_motion(view.global_frame().mid());
+
+    let active_drag = twm.active_drag().expect("no active drag");
+    assert_eq!(active_drag.hwnd, pal_hwnd);
+    assert_eq!(active_drag.data, DragData::with_text("hello"));
+
+    // The completion handler is called with the result
+    assert!(results.borrow().is_empty());
+    twm.finish_drag(true);
+    assert_eq!(*results.borrow(), vec![true]);
+    assert_eq!(twm.active_drag(), None);
+}
@@ -0,0 +1,140 @@
+use cggeom::prelude::*;
+use cgmath::Vector2;
+use std::time::{Duration, Instant};
+use try_match::try_match;
+
+use tcw3::{
+    pal,
+    testing::{prelude::*, use_testing_wm},
+    ui::{
+        layouts::{EmptyLayout, FillLayout, TableLayout},
+        AlignFlags,
+    },
+    uicore::{HView, HWnd, SizeTraits, ViewFlags},
+};
+
+/// Process events for a duration longer than the hover delay.
+fn wait_hover_delay(twm: &dyn TestingWm) {
+    let till = Instant::now() + Duration::from_millis(800);
+    while Instant::now() < till {
+        twm.step_until(till);
+    }
+}
+
+#[use_testing_wm]
+#[test]
+fn tooltip_hover(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view0 = HView::new(ViewFlags::default());
+    let view1 = HView::new(ViewFlags::ACCEPT_MOUSE_OVER);
+    let view2 = HView::new(ViewFlags::ACCEPT_MOUSE_OVER);
+    let view3 = HView::new(ViewFlags::ACCEPT_MOUSE_OVER);
+
+    view1.set_layout(FillLayout::new(view2.clone()).with_uniform_margin(10.0));
+    view2.set_layout(EmptyLayout::new(
+        SizeTraits::default().with_preferred([20.0; 2].into()),
+    ));
+    view3.set_layout(EmptyLayout::new(
+        SizeTraits::default().with_preferred([20.0; 2].into()),
+    ));
+
+    view0.set_layout(
+        TableLayout::stack_horz(vec![
+            (view1.clone(), AlignFlags::JUSTIFY),
+            (view3.clone(), AlignFlags::JUSTIFY),
+        ])
+        .with_uniform_margin(10.0),
+    );
+
+    wnd.content_view().set_layout(FillLayout::new(view0));
+
+    view1.set_tooltip(Some("Hello".to_owned()));
+    view3.set_tooltip(Some("World".to_owned()));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    // `view2` doesn't have a tooltip, so its superview's tooltip is used.
+    // The tooltip is displayed after a delay.
+    twm.raise_mouse_motion(&pal_hwnd, view2.global_frame().mid());
+    assert_eq!(twm.active_tooltip(), None);
+    wait_hover_delay(twm);
+    let tooltip = twm.active_tooltip().expect("no tooltip");
+    assert_eq!(tooltip.hwnd, pal_hwnd);
+    assert_eq!(tooltip.text, "Hello");
+    assert_eq!(tooltip.anchor, view1.global_frame());
+
+    // Moving the mouse pointer within the same tooltip view doesn't hide it
+    twm.raise_mouse_motion(&pal_hwnd, view1.global_frame().min + Vector2::new(1.0, 1.0));
+    assert!(twm.active_tooltip().is_some());
+
+    // Update the text of the displayed tooltip
+    view1.set_tooltip(Some("Hello!".to_owned()));
+    assert_eq!(twm.active_tooltip().unwrap().text, "Hello!");
+
+    // Moving to another view hides the tooltip immediately
+    twm.raise_mouse_motion(&pal_hwnd, view3.global_frame().mid());
+    assert_eq!(twm.active_tooltip(), None);
+    wait_hover_delay(twm);
+    assert_eq!(twm.active_tooltip().unwrap().text, "World");
+
+    // Leaving the window hides the tooltip
+    twm.raise_mouse_leave(&pal_hwnd);
+    assert_eq!(twm.active_tooltip(), None);
+    wait_hover_delay(twm);
+    assert_eq!(twm.active_tooltip(), None);
+}
+
+#[use_testing_wm]
+#[test]
+fn tooltip_dismiss(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view = HView::new(ViewFlags::ACCEPT_MOUSE_OVER);
+    view.set_tooltip(Some("Hello".to_owned()));
+    wnd.content_view().set_layout(FillLayout::new(view.clone()));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    twm.raise_mouse_motion(&pal_hwnd, view.global_frame().mid());
+    wait_hover_delay(twm);
+    assert!(twm.active_tooltip().is_some());
+
+    // A keypress hides the tooltip, which doesn't reappear until the mouse
+    // pointer moves to another view
+    twm.raise_key_down(&pal_hwnd, "windows", "A");
+    assert_eq!(twm.active_tooltip(), None);
+    wait_hover_delay(twm);
+    assert_eq!(twm.active_tooltip(), None);
+
+    // Removing the tooltip cancels the pending one
+    twm.raise_mouse_leave(&pal_hwnd);
+    twm.raise_mouse_motion(&pal_hwnd, view.global_frame().mid());
+    view.set_tooltip(None);
+    wait_hover_delay(twm);
+    assert_eq!(twm.active_tooltip(), None);
+
+    // Scrolling hides the tooltip
+    view.set_tooltip(Some("Hello".to_owned()));
+    wait_hover_delay(twm);
+    assert!(twm.active_tooltip().is_some());
+    twm.raise_scroll_motion(
+        &pal_hwnd,
+        view.global_frame().mid(),
+        &pal::ScrollDelta {
+            precise: false,
+            delta: [0.0, 1.0].into(),
+        },
+    );
+    assert_eq!(twm.active_tooltip(), None);
+}
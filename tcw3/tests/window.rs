@@ -6,7 +6,8 @@ use tcw3::{
     pal,
     prelude::*,
     testing::{prelude::*, use_testing_wm},
-    uicore::{HWnd, HWndRef},
+    ui::layouts::EmptyLayout,
+    uicore::{HWnd, HWndRef, SizeTraits},
 };
 
 #[use_testing_wm]
@@ -39,6 +40,38 @@ fn close_wnd(twm: &dyn TestingWm) {
     assert_eq!(twm.hwnds().len(), 0);
 }
 
+#[use_testing_wm]
+#[test]
+fn clamp_wnd_size(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+    wnd.content_view().set_layout(EmptyLayout::new(SizeTraits {
+        min: [20.0, 10.0].into(),
+        max: [100.0, std::f32::INFINITY].into(),
+        preferred: [50.0, 50.0].into(),
+    }));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    let attrs = twm.wnd_attrs(&pal_hwnd).unwrap();
+    assert_eq!(attrs.min_size, [20, 10]);
+    // An unbounded dimension is clamped to a finite value
+    assert_eq!(attrs.max_size, [100, 16_777_216]);
+
+    // The window is resized to fit in the maximum size
+    twm.set_wnd_size(&pal_hwnd, [300, 300]);
+    twm.step_unsend();
+    assert_eq!(twm.wnd_attrs(&pal_hwnd).unwrap().size, [100, 300]);
+
+    // ... and the minimum size
+    twm.set_wnd_size(&pal_hwnd, [5, 5]);
+    twm.step_unsend();
+    assert_eq!(twm.wnd_attrs(&pal_hwnd).unwrap().size, [20, 10]);
+}
+
 #[use_testing_wm]
 #[test]
 fn invoke_on_update(twm: &dyn TestingWm) {